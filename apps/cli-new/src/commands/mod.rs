@@ -9,7 +9,8 @@ pub use auth::{login, logout, status};
 pub use daemon::{daemon_logs, daemon_start, daemon_status, daemon_stop};
 pub use repos::{repos_add, repos_list, repos_remove};
 pub use sessions::{
-    sessions_create, sessions_delete, sessions_list, sessions_messages, sessions_show,
+    sessions_create, sessions_delete, sessions_export, sessions_import, sessions_list,
+    sessions_messages, sessions_show,
 };
 
 use anyhow::Result;
@@ -243,3 +243,86 @@ pub async fn sessions_messages(id: &str, format: &OutputFormat) -> Result<()> {
 
     Ok(())
 }
+
+/// Resolve a bundle path against the CLI's working directory.
+///
+/// The daemon runs elsewhere, so it only accepts absolute paths.
+fn absolute_bundle_path(file: &str) -> Result<String> {
+    Ok(std::path::absolute(file)?.to_string_lossy().to_string())
+}
+
+/// Export a session to a bundle file.
+pub async fn sessions_export(id: &str, file: &str, format: &OutputFormat) -> Result<()> {
+    let client = require_daemon().await?;
+
+    let path = absolute_bundle_path(file)?;
+    let params = serde_json::json!({ "session_id": id, "path": path });
+    let response = client
+        .call_method_with_params(Method::SessionExport, params)
+        .await?;
+
+    if let Some(result) = &response.result {
+        match format {
+            OutputFormat::Text => {
+                let count = result
+                    .get("message_count")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                output::print_success(
+                    &format!("Session {} exported to {} ({} messages)", id, path, count),
+                    format,
+                );
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(result)?);
+            }
+        }
+    } else if let Some(error) = &response.error {
+        output::print_error(&error.message, format);
+    }
+
+    Ok(())
+}
+
+/// Import a session from a bundle file.
+pub async fn sessions_import(
+    file: &str,
+    repository: Option<&str>,
+    format: &OutputFormat,
+) -> Result<()> {
+    let client = require_daemon().await?;
+
+    let mut params = serde_json::json!({ "path": absolute_bundle_path(file)? });
+    if let Some(repository) = repository {
+        params["repository_id"] = serde_json::json!(repository);
+    }
+    let response = client
+        .call_method_with_params(Method::SessionImport, params)
+        .await?;
+
+    if let Some(result) = &response.result {
+        match format {
+            OutputFormat::Text => {
+                let session_id = result.get("id").and_then(|v| v.as_str()).unwrap_or("-");
+                let repository_id = result
+                    .get("repository_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("-");
+                output::print_success(
+                    &format!(
+                        "Session imported: {} (repository {})",
+                        session_id, repository_id
+                    ),
+                    format,
+                );
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(result)?);
+            }
+        }
+    } else if let Some(error) = &response.error {
+        output::print_error(&error.message, format);
+    }
+
+    Ok(())
+}
@@ -118,6 +118,21 @@ enum SessionCommands {
         /// Session ID
         id: String,
     },
+    /// Export a session to a bundle file
+    Export {
+        /// Session ID
+        id: String,
+        /// Bundle file to write (.tar.gz)
+        file: String,
+    },
+    /// Import a session from a bundle file
+    Import {
+        /// Bundle file to read
+        file: String,
+        /// Repository ID to import into (defaults to the repository at the bundle's path)
+        #[arg(short, long)]
+        repository: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -316,6 +331,12 @@ async fn main() {
                 SessionCommands::Messages { id } => {
                     commands::sessions_messages(&id, &cli.format).await
                 }
+                SessionCommands::Export { id, file } => {
+                    commands::sessions_export(&id, &file, &cli.format).await
+                }
+                SessionCommands::Import { file, repository } => {
+                    commands::sessions_import(&file, repository.as_deref(), &cli.format).await
+                }
             },
             Commands::Repos { command } => match command {
                 RepoCommands::List => commands::repos_list(&cli.format).await,
@@ -2,8 +2,11 @@
 
 #![allow(dead_code)]
 
-use super::claude_events::{AssistantMessage, ClaudeCodeMessage, ToolUseBlock};
 use super::theme::{Theme, ThemeMode};
+use agent_stream_types::claude::{
+    AssistantMessage, ClaudeCodeMessage, ContentDelta, StreamEvent, StreamEventMessage,
+    ToolUseBlock,
+};
 use daemon_ipc::Event as DaemonEvent;
 use std::collections::{HashMap, HashSet};
 use tokio::sync::mpsc;
@@ -25,6 +28,8 @@ pub struct SessionState {
     pub terminal_exit_code: Option<i32>,
     pub terminal_input: String,
     pub needs_message_refresh: bool,
+    /// Highest message sequence seen, used to resume the subscription
+    pub last_message_sequence: Option<i64>,
     /// Last message preview for sidebar display
     pub last_message_preview: Option<String>,
 }
@@ -47,6 +52,7 @@ impl Default for SessionState {
             terminal_exit_code: None,
             terminal_input: String::new(),
             needs_message_refresh: true,
+            last_message_sequence: None,
             last_message_preview: None,
         }
     }
@@ -180,6 +186,8 @@ pub struct App {
     // Streaming subscription
     pub event_receiver: Option<mpsc::Receiver<DaemonEvent>>,
     pub subscription_task: Option<tokio::task::JoinHandle<()>>,
+    /// Sequence of the latest session message seen, used to resume the stream.
+    pub last_message_sequence: Option<i64>,
 }
 
 /// Active panel in the UI.
@@ -401,6 +409,7 @@ impl App {
             // Streaming subscription
             event_receiver: None,
             subscription_task: None,
+            last_message_sequence: None,
         }
     }
 
@@ -424,7 +433,7 @@ impl App {
             ClaudeCodeMessage::User(ref user) => {
                 // Tool results - update tool status
                 for result in user.tool_results() {
-                    let status = if result.is_error {
+                    let status = if result.is_error() {
                         ToolStatus::Failed
                     } else {
                         ToolStatus::Completed
@@ -452,8 +461,8 @@ impl App {
                     }
                 }
             }
-            ClaudeCodeMessage::System(_) => {
-                // System messages - no TUI action needed
+            ClaudeCodeMessage::System(_) | ClaudeCodeMessage::Other(_) => {
+                // System and unknown messages - no TUI action needed
             }
             ClaudeCodeMessage::StreamEvent(ref stream_event) => {
                 // Handle streaming deltas for real-time display
@@ -463,13 +472,11 @@ impl App {
     }
 
     /// Handle a streaming event for real-time content display.
-    fn handle_stream_event(&mut self, stream_event: &super::claude_events::StreamEventMessage) {
-        use super::claude_events::{ContentDelta, StreamEvent};
-
+    fn handle_stream_event(&mut self, stream_event: &StreamEventMessage) {
         match &stream_event.event {
             StreamEvent::ContentBlockDelta { delta, .. } => {
                 match delta {
-                    ContentDelta::TextDelta { text } => {
+                    ContentDelta::TextDelta { text, .. } => {
                         // Append text to streaming content
                         if let Some(ref mut content) = self.streaming_content {
                             content.push_str(text);
@@ -480,7 +487,7 @@ impl App {
                     ContentDelta::InputJsonDelta { .. } => {
                         // Tool input deltas - could update tool preview
                     }
-                    ContentDelta::Unknown => {}
+                    ContentDelta::ThinkingDelta { .. } | ContentDelta::Other(_) => {}
                 }
             }
             StreamEvent::ContentBlockStart { .. } => {
@@ -496,10 +503,10 @@ impl App {
             StreamEvent::MessageDelta { .. } => {
                 // Message metadata update
             }
-            StreamEvent::MessageStop => {
+            StreamEvent::MessageStop { .. } => {
                 // Message complete - streaming content will be replaced by final message
             }
-            StreamEvent::Unknown => {}
+            StreamEvent::Other(_) => {}
         }
     }
 
@@ -804,6 +811,7 @@ impl App {
             state.terminal_running = self.terminal_running;
             state.terminal_exit_code = self.terminal_exit_code.take();
             state.terminal_input = std::mem::take(&mut self.terminal_input);
+            state.last_message_sequence = self.last_message_sequence.take();
         }
     }
 
@@ -827,6 +835,7 @@ impl App {
             self.terminal_running = state.terminal_running;
             self.terminal_exit_code = state.terminal_exit_code.take();
             self.terminal_input = std::mem::take(&mut state.terminal_input);
+            self.last_message_sequence = state.last_message_sequence.take();
 
             // Re-insert the (now emptied) state so the key stays in the map
             state.needs_message_refresh = false;
@@ -849,6 +858,7 @@ impl App {
             self.terminal_running = false;
             self.terminal_exit_code = None;
             self.terminal_input.clear();
+            self.last_message_sequence = None;
 
             true // needs message refresh
         }
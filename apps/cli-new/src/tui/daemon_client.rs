@@ -4,14 +4,18 @@ use super::app::{
     ActiveSubAgent, ActiveTool, App, AuthStatus, ChatMessage, FileEntry, FileStatus, MessageRole,
     Repository, Session, SessionState, ToolHistoryEntry, ToolStatus,
 };
-use super::claude_events::ClaudeCodeMessage;
+use agent_stream_types::claude::ClaudeCodeMessage;
 use anyhow::Result;
 use daemon_config_and_utils::Paths;
 use daemon_ipc::{Event as DaemonEvent, IpcClient, Method, StreamingSubscription};
 use std::collections::HashMap;
 use std::process::Command;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
+/// Delay between attempts to re-establish a dropped session stream.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
 impl App {
     /// Refresh all data from the daemon.
     pub async fn refresh_data(&mut self) -> Result<()> {
@@ -148,7 +152,8 @@ impl App {
 
     /// Send a message in the current session.
     /// This invokes the Claude CLI and streams events to the database.
-    pub async fn send_message(&mut self, content: &str) -> Result<()> {
+    /// Returns the queue length if the message was queued behind a running turn.
+    pub async fn send_message(&mut self, content: &str) -> Result<Option<u64>> {
         let session_id = self
             .selected_session_id
             .clone()
@@ -170,6 +175,12 @@ impl App {
             return Err(anyhow::anyhow!("{}", error.message));
         }
 
+        // Queued messages are sent by the daemon when the current turn ends
+        let result = response.result.unwrap_or_default();
+        if result.get("status").and_then(|v| v.as_str()) == Some("queued") {
+            return Ok(result.get("queue_length").and_then(|v| v.as_u64()));
+        }
+
         // Mark that we're waiting for Claude
         self.claude_running = true;
 
@@ -177,6 +188,35 @@ impl App {
         // (daemon has already persisted it)
         self.fetch_messages().await?;
 
+        Ok(None)
+    }
+
+    /// Answer a pending AskUserQuestion prompt with one option per question.
+    /// The daemon resumes Claude with the answer as the tool result.
+    pub async fn answer_question(&mut self, tool_use_id: &str, answers: &[String]) -> Result<()> {
+        let session_id = self
+            .selected_session_id
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No session selected"))?;
+
+        let client = get_ipc_client()?;
+        let params = serde_json::json!({
+            "session_id": session_id,
+            "tool_use_id": tool_use_id,
+            "answers": answers,
+        });
+
+        let response = client
+            .call_method_with_params(Method::AgentAnswerQuestion, params)
+            .await?;
+
+        if let Some(error) = &response.error {
+            return Err(anyhow::anyhow!("{}", error.message));
+        }
+
+        self.claude_running = true;
+        self.fetch_messages().await?;
+
         Ok(())
     }
 
@@ -252,6 +292,17 @@ impl App {
         }
 
         if let Some(result) = &response.result {
+            // Remember where the transcript ends so the stream resumes from there
+            let last_sequence = result
+                .get("messages")
+                .and_then(|v| v.as_array())
+                .and_then(|arr| {
+                    arr.iter()
+                        .filter_map(|m| m.get("sequence_number").and_then(|v| v.as_i64()))
+                        .max()
+                });
+            self.last_message_sequence = Some(last_sequence.unwrap_or(0));
+
             // Parse all raw messages first
             let raw_messages: Vec<_> = result
                 .get("messages")
@@ -262,23 +313,11 @@ impl App {
                             let content = m.get("content").and_then(|v| v.as_str())?;
                             // Try to parse as Claude event, or wrap as raw user input
                             match ClaudeCodeMessage::from_json(content) {
-                                Ok(parsed) => Some((parsed, None)),
-                                Err(_) => Some((
-                                    ClaudeCodeMessage::Result(
-                                        super::claude_events::ResultMessage {
-                                            subtype: None,
-                                            is_error: None,
-                                            result: None,
-                                            duration_ms: None,
-                                            duration_api_ms: None,
-                                            num_turns: None,
-                                            session_id: None,
-                                            cost_usd: None,
-                                            usage: None,
-                                        },
-                                    ),
+                                Ok(ClaudeCodeMessage::Other(_)) | Err(_) => Some((
+                                    ClaudeCodeMessage::Other(serde_json::Value::Null),
                                     Some(content.to_string()),
                                 )),
+                                Ok(parsed) => Some((parsed, None)),
                             }
                         })
                         .collect()
@@ -294,7 +333,7 @@ impl App {
             for (msg, _) in &raw_messages {
                 if let ClaudeCodeMessage::User(user) = msg {
                     for result in user.tool_results() {
-                        tool_results.insert(result.tool_use_id.clone(), result.is_error);
+                        tool_results.insert(result.tool_use_id.clone(), result.is_error());
                     }
                 }
             }
@@ -303,10 +342,17 @@ impl App {
             for (msg, raw_content) in raw_messages {
                 // Handle raw user input that couldn't be parsed
                 if let Some(content) = raw_content {
-                    self.messages.push(ChatMessage {
-                        role: MessageRole::User,
-                        content,
-                    });
+                    let message = match permission_decision_summary(&content) {
+                        Some(summary) => ChatMessage {
+                            role: MessageRole::System,
+                            content: summary,
+                        },
+                        None => ChatMessage {
+                            role: MessageRole::User,
+                            content,
+                        },
+                    };
+                    self.messages.push(message);
                     continue;
                 }
 
@@ -380,7 +426,8 @@ impl App {
                     // System messages and User messages (tool results) are hidden
                     ClaudeCodeMessage::System(_)
                     | ClaudeCodeMessage::User(_)
-                    | ClaudeCodeMessage::StreamEvent(_) => {}
+                    | ClaudeCodeMessage::StreamEvent(_)
+                    | ClaudeCodeMessage::Other(_) => {}
                 }
             }
 
@@ -546,8 +593,10 @@ impl App {
         // Stop any existing subscription
         self.stop_subscription().await;
 
+        // Resume after the last loaded message so nothing committed since is missed
+        let after_sequence = self.last_message_sequence;
         let client = get_ipc_client()?;
-        let subscription = client.subscribe(&session_id).await?;
+        let subscription = client.subscribe_after(&session_id, after_sequence).await?;
 
         // Create channel for events
         let (tx, rx) = mpsc::channel::<DaemonEvent>(100);
@@ -555,7 +604,7 @@ impl App {
 
         // Spawn task to forward events from subscription to channel
         let task = tokio::spawn(async move {
-            forward_subscription_events(subscription, tx).await;
+            forward_subscription_events(subscription, session_id, after_sequence, tx).await;
         });
         self.subscription_task = Some(task);
 
@@ -576,6 +625,13 @@ impl App {
     pub fn handle_daemon_event(&mut self, event: DaemonEvent) {
         use daemon_ipc::EventType;
 
+        if event.event_type.carries_message_sequence() {
+            self.last_message_sequence = Some(
+                self.last_message_sequence
+                    .map_or(event.sequence, |last| last.max(event.sequence)),
+            );
+        }
+
         match event.event_type {
             EventType::ClaudeEvent => {
                 // Parse the raw JSON data from the event
@@ -620,7 +676,7 @@ impl App {
             }
             EventType::TerminalOutput => {
                 // Terminal output chunk
-                if let Some(output) = event.data.get("output").and_then(|v| v.as_str()) {
+                if let Some(output) = event.data.get("content").and_then(|v| v.as_str()) {
                     self.terminal_output.push(output.to_string());
                 }
             }
@@ -634,34 +690,103 @@ impl App {
             EventType::SessionCreated | EventType::SessionDeleted => {
                 // Session lifecycle events - could refresh session list
             }
-            EventType::AgentEvent | EventType::InitialState | EventType::Ping => {
+            EventType::PermissionRequest => {
+                // Approvals are answered by other clients; just surface the wait
+                let tool_name = event
+                    .data
+                    .get("tool_name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("a tool");
+                self.messages.push(ChatMessage {
+                    role: MessageRole::System,
+                    content: format!("Waiting for permission to use {tool_name}"),
+                });
+            }
+            EventType::AgentEvent
+            | EventType::InitialState
+            | EventType::Ping
+            | EventType::SchedulerUpdated => {
                 // Handled by subscription setup or ignored
             }
         }
     }
 }
 
+/// One-line summary of a recorded permission decision, if `content` is one.
+fn permission_decision_summary(content: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(content).ok()?;
+    if value.get("type").and_then(|v| v.as_str()) != Some("permission_decision") {
+        return None;
+    }
+    let tool_name = value
+        .get("tool_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("tool");
+    let verdict = match value.get("behavior").and_then(|v| v.as_str()) {
+        Some("allow") => "Allowed",
+        _ => "Denied",
+    };
+    Some(match value.get("message").and_then(|v| v.as_str()) {
+        Some(message) => format!("{verdict} {tool_name}: {message}"),
+        None => format!("{verdict} {tool_name}"),
+    })
+}
+
 /// Forward events from a streaming subscription to an mpsc channel.
+///
+/// If the daemon ends the stream (restart, or this client fell behind), the
+/// subscription is re-established after the last message sequence received,
+/// so the daemon replays whatever was missed in between.
 async fn forward_subscription_events(
     mut subscription: StreamingSubscription,
+    session_id: String,
+    mut last_sequence: Option<i64>,
     tx: mpsc::Sender<DaemonEvent>,
 ) {
     loop {
         match subscription.recv().await {
             Some(event) => {
+                if event.event_type.carries_message_sequence() {
+                    last_sequence =
+                        Some(last_sequence.map_or(event.sequence, |last| last.max(event.sequence)));
+                }
                 if tx.send(event).await.is_err() {
                     // Receiver dropped, stop forwarding
                     break;
                 }
             }
             None => {
-                // Subscription closed
-                break;
+                // Subscription closed - resume unless the TUI stopped listening
+                match resubscribe(&session_id, last_sequence, &tx).await {
+                    Some(resumed) => subscription = resumed,
+                    None => break,
+                }
             }
         }
     }
 }
 
+/// Reconnect a dropped session stream, retrying until it succeeds or the
+/// receiving side goes away.
+async fn resubscribe(
+    session_id: &str,
+    after_sequence: Option<i64>,
+    tx: &mpsc::Sender<DaemonEvent>,
+) -> Option<StreamingSubscription> {
+    loop {
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        if tx.is_closed() {
+            return None;
+        }
+        let Ok(client) = get_ipc_client() else {
+            return None;
+        };
+        if let Ok(subscription) = client.subscribe_after(session_id, after_sequence).await {
+            return Some(subscription);
+        }
+    }
+}
+
 /// Get the IPC client for communicating with the daemon.
 fn get_ipc_client() -> Result<IpcClient> {
     let paths = Paths::new()?;
@@ -217,15 +217,20 @@ async fn handle_prompt_mode(app: &mut App, key: KeyEvent) -> bool {
                 .get(selected_idx)
                 .map(|o| o.label.clone())
                 .unwrap_or_default();
-            let _tool_use_id = prompt.tool_use_id.clone();
+            let tool_use_id = prompt.tool_use_id.clone();
 
             // Clear prompt state
             app.input_mode = InputMode::Normal;
             app.pending_prompt = None;
 
-            // TODO: Send the selected option back to Claude via daemon
-            // For now, just show a status message
-            app.set_status_message(format!("Selected: {}", selected_label));
+            // Send the selected option back to Claude via daemon
+            match app
+                .answer_question(&tool_use_id, std::slice::from_ref(&selected_label))
+                .await
+            {
+                Ok(()) => app.set_status_message(format!("Answered: {}", selected_label)),
+                Err(e) => app.set_status_message(format!("Error: {}", e)),
+            }
         }
         _ => {}
     }
@@ -328,7 +333,13 @@ async fn handle_edit_mode(app: &mut App, key: KeyEvent) -> bool {
                 app.chat_input.clear();
 
                 match app.send_message(&content).await {
-                    Ok(()) => {
+                    Ok(Some(queue_length)) => {
+                        app.set_status_message(format!(
+                            "Message queued ({} waiting)",
+                            queue_length
+                        ));
+                    }
+                    Ok(None) => {
                         // Ensure subscription is active for receiving events
                         if app.event_receiver.is_none() {
                             if let Err(e) = app.start_subscription().await {
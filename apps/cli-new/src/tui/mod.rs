@@ -6,7 +6,6 @@
 //! - Right: version control (files, diff, terminal)
 
 mod app;
-mod claude_events;
 mod components;
 mod daemon_client;
 mod event;
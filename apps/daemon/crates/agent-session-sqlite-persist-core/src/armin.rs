@@ -15,9 +15,9 @@
 //!
 //! On startup:
 //! 1. Open SQLite
-//! 2. Load sessions
+//! 2. Load session cursors (no message bodies)
 //! 3. Rebuild deltas
-//! 4. Serve reads
+//! 4. Serve reads, loading session snapshots on demand
 //!
 //! Recovery emits NO side-effects and NO live notifications.
 
@@ -26,60 +26,120 @@ use std::path::Path;
 use std::sync::Arc;
 
 use crate::delta::{DeltaStore, DeltaView};
-use crate::live::{LiveHub, LiveSubscription};
+use crate::live::{LiveHub, LiveSubscription, DEFAULT_LIVE_BUFFER_CAPACITY};
 use crate::reader::SessionReader;
 use crate::side_effect::{SideEffect, SideEffectSink};
-use crate::snapshot::{SessionSnapshot, SnapshotView};
-use crate::sqlite::SqliteStore;
+use crate::snapshot::{
+    SnapshotCache, SnapshotCursor, SnapshotView, DEFAULT_SNAPSHOT_CACHE_SESSIONS,
+    DEFAULT_SNAPSHOT_PAGE_SIZE,
+};
+use crate::sqlite::{SeededSession, SqliteStore};
 use crate::types::{
-    CodingSessionStatus, Message, NewMessage, NewRepository, NewSession, NewSessionSecret,
-    Repository, RepositoryId, Session, SessionId, SessionSecret, SessionState, SessionStatus,
-    SessionUpdate,
+    CodingSessionStatus, ImportedMessage, Message, MessageSearchHit, MessageSearchQuery,
+    NewMessage, NewProcessRecord, NewQueuedMessage, NewRepository, NewSession, NewSessionSecret,
+    NewTerminal, ProcessRecord, QuestionRuntimeState, QueuedMessage, Repository, RepositoryId,
+    Session, SessionForkOptions, SessionHistory, SessionId, SessionSecret, SessionState,
+    SessionStatus, SessionUpdate, Terminal, TerminalOutputPage, TimestampedMessage,
+    TERMINAL_SCROLLBACK_BYTES,
 };
 use crate::writer::SessionWriter;
 use crate::ArminError;
 
+/// Tuning for Armin's derived read state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArminConfig {
+    /// Maximum number of sessions whose messages stay cached for snapshot reads.
+    ///
+    /// Sessions beyond this bound are evicted least-recently-used first and
+    /// reloaded from SQLite on their next access. Zero disables caching.
+    pub snapshot_cache_sessions: usize,
+    /// Number of messages read per keyset page when loading a session snapshot.
+    pub snapshot_page_size: usize,
+    /// Number of undelivered messages buffered per live subscription.
+    ///
+    /// A subscriber that falls this far behind is cut off and reports the
+    /// sequence it lagged at, so it can catch up from SQLite.
+    pub live_buffer_capacity: usize,
+}
+
+impl Default for ArminConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_cache_sessions: DEFAULT_SNAPSHOT_CACHE_SESSIONS,
+            snapshot_page_size: DEFAULT_SNAPSHOT_PAGE_SIZE,
+            live_buffer_capacity: DEFAULT_LIVE_BUFFER_CAPACITY,
+        }
+    }
+}
+
 /// The Armin session engine.
 ///
 /// Coordinates SQLite storage, derived state, and side-effects.
 pub struct Armin<S: SideEffectSink> {
-    sqlite: SqliteStore,
+    sqlite: Arc<SqliteStore>,
     delta: DeltaStore,
     live: LiveHub,
     sink: Arc<S>,
-    /// Cached snapshot (rebuilt on startup, refreshed on demand)
+    /// Bounded cache of session messages backing lazy snapshots.
+    snapshot_cache: Arc<SnapshotCache>,
+    /// Current snapshot cursors (rebuilt on startup, refreshed on demand)
     snapshot: std::sync::RwLock<SnapshotView>,
 }
 
 impl<S: SideEffectSink> Armin<S> {
     /// Opens an Armin engine with a SQLite database at the given path.
     ///
-    /// This performs recovery: it loads all sessions and messages from SQLite
-    /// and rebuilds derived state. No side-effects are emitted during recovery.
+    /// This performs recovery: it loads session cursors from SQLite and
+    /// rebuilds derived state. Messages are read lazily when a session's
+    /// snapshot is first accessed. No side-effects are emitted during recovery.
     pub fn open(path: impl AsRef<Path>, sink: S) -> Result<Self, ArminError> {
+        Self::open_with_config(path, sink, ArminConfig::default())
+    }
+
+    /// Opens an Armin engine at the given path with explicit tuning.
+    pub fn open_with_config(
+        path: impl AsRef<Path>,
+        sink: S,
+        config: ArminConfig,
+    ) -> Result<Self, ArminError> {
         let sqlite = SqliteStore::open(path)?;
-        Self::from_sqlite(sqlite, sink)
+        Self::from_sqlite(sqlite, sink, config)
     }
 
     /// Creates an Armin engine with an in-memory SQLite database.
     ///
     /// Useful for testing.
     pub fn in_memory(sink: S) -> Result<Self, ArminError> {
+        Self::in_memory_with_config(sink, ArminConfig::default())
+    }
+
+    /// Creates an in-memory Armin engine with explicit tuning.
+    pub fn in_memory_with_config(sink: S, config: ArminConfig) -> Result<Self, ArminError> {
         let sqlite = SqliteStore::in_memory()?;
-        Self::from_sqlite(sqlite, sink)
+        Self::from_sqlite(sqlite, sink, config)
     }
 
     /// Creates an Armin engine from an existing SQLite store.
-    fn from_sqlite(sqlite: SqliteStore, sink: S) -> Result<Self, ArminError> {
+    fn from_sqlite(sqlite: SqliteStore, sink: S, config: ArminConfig) -> Result<Self, ArminError> {
+        let sqlite = Arc::new(sqlite);
         let delta = DeltaStore::new();
-        let live = LiveHub::new();
+        let live = LiveHub::with_capacity(config.live_buffer_capacity);
+        let snapshot_cache = Arc::new(SnapshotCache::new(
+            sqlite.clone(),
+            config.snapshot_cache_sessions,
+            config.snapshot_page_size,
+        ));
 
         let engine = Self {
             sqlite,
             delta,
             live,
             sink: Arc::new(sink),
-            snapshot: std::sync::RwLock::new(SnapshotView::empty()),
+            snapshot: std::sync::RwLock::new(SnapshotView::lazy(
+                HashMap::new(),
+                snapshot_cache.clone(),
+            )),
+            snapshot_cache,
         };
 
         // Perform recovery (no side-effects)
@@ -94,33 +154,31 @@ impl<S: SideEffectSink> Armin<S> {
     fn recover(&self) -> Result<(), ArminError> {
         tracing::info!("armin: starting recovery from SQLite");
 
-        // Load all sessions from local_llm_conversations table
-        let sessions = self.sqlite.list_all_agent_sessions()?;
+        // Load session cursors only; message bodies are read on first access
+        let sessions = self.sqlite.list_agent_session_cursors()?;
         tracing::debug!("armin: found {} sessions", sessions.len());
 
-        // Build snapshot and initialize deltas
-        let mut snapshot_sessions = HashMap::new();
+        // Build snapshot cursors and initialize deltas
+        let mut cursors = HashMap::with_capacity(sessions.len());
 
         for session in sessions {
-            // Load messages for this session
-            let messages = self.sqlite.get_agent_messages(&session.id)?;
-            let last_message_id = messages.last().map(|m| m.id.clone());
-
             // Initialize delta tracking with cursor at end of current messages
-            self.delta.init_session(session.id.clone(), last_message_id);
+            self.delta
+                .init_session(session.id.clone(), session.last_message_id.clone());
 
             // Sessions are "closed" if status is not active
-            let closed = session.status != crate::types::SessionStatus::Active;
-
-            // Create snapshot
-            snapshot_sessions.insert(
-                session.id.clone(),
-                SessionSnapshot::new(session.id.clone(), messages, closed),
+            cursors.insert(
+                session.id,
+                SnapshotCursor {
+                    last_sequence: session.last_sequence,
+                    closed: session.status != crate::types::SessionStatus::Active,
+                },
             );
         }
 
         // Update snapshot
-        *self.snapshot.write().expect("lock poisoned") = SnapshotView::new(snapshot_sessions);
+        *self.snapshot.write().expect("lock poisoned") =
+            SnapshotView::lazy(cursors, self.snapshot_cache.clone());
 
         tracing::info!("armin: recovery complete");
         Ok(())
@@ -143,27 +201,106 @@ impl<S: SideEffectSink> Armin<S> {
 
     /// Refreshes the snapshot from current state.
     ///
-    /// This rebuilds the snapshot from SQLite and clears all deltas.
+    /// This re-reads session cursors from SQLite and clears all deltas.
+    /// Cached messages stay valid and are extended on the next access.
     pub fn refresh_snapshot(&self) -> Result<(), ArminError> {
-        let sessions = self.sqlite.list_all_agent_sessions()?;
-        let mut snapshot_sessions = HashMap::new();
+        let sessions = self.sqlite.list_agent_session_cursors()?;
+        let mut cursors = HashMap::with_capacity(sessions.len());
 
         for session in sessions {
-            let messages = self.sqlite.get_agent_messages(&session.id)?;
-
             // Clear delta and update cursor
             self.delta.clear(&session.id);
 
-            let closed = session.status != crate::types::SessionStatus::Active;
+            cursors.insert(
+                session.id,
+                SnapshotCursor {
+                    last_sequence: session.last_sequence,
+                    closed: session.status != crate::types::SessionStatus::Active,
+                },
+            );
+        }
+
+        *self.snapshot.write().expect("lock poisoned") =
+            SnapshotView::lazy(cursors, self.snapshot_cache.clone());
+        Ok(())
+    }
+
+    /// Reads one page of committed messages for a session, straight from SQLite.
+    ///
+    /// Returns up to `limit` messages with a sequence number greater than
+    /// `after_sequence`, in sequence order. Pass the last returned sequence
+    /// number to fetch the next page.
+    pub fn list_messages_page(
+        &self,
+        session: &SessionId,
+        after_sequence: i64,
+        limit: usize,
+    ) -> Result<Vec<Message>, ArminError> {
+        Ok(self
+            .sqlite
+            .get_agent_messages_page(session, after_sequence, i64::MAX, limit)?)
+    }
+
+    /// Reads one page of committed messages with the time each was written.
+    ///
+    /// Pages the same way as [`Self::list_messages_page`].
+    pub fn list_timestamped_messages_page(
+        &self,
+        session: &SessionId,
+        after_sequence: i64,
+        limit: usize,
+    ) -> Result<Vec<TimestampedMessage>, ArminError> {
+        Ok(self
+            .sqlite
+            .get_agent_timestamped_messages_page(session, after_sequence, limit)?)
+    }
+
+    /// Emits the session's current runtime status envelope.
+    fn emit_runtime_status(&self, session: &SessionId) -> Result<(), ArminError> {
+        let runtime_status = self
+            .sqlite
+            .get_or_create_session_state(session)?
+            .runtime_status;
+        self.sink.emit(SideEffect::RuntimeStatusUpdated {
+            session_id: session.clone(),
+            runtime_status,
+        });
+        Ok(())
+    }
+
+    /// Updates derived state for a session committed together with its messages,
+    /// then emits `SessionCreated`.
+    ///
+    /// The committed messages are served from the snapshot, so the delta starts empty.
+    fn finish_seeded_session(&self, seeded: SeededSession) -> Session {
+        let session = seeded.session;
 
-            snapshot_sessions.insert(
+        // 2. Update derived state
+        self.delta
+            .init_session(session.id.clone(), seeded.last_message_id);
+        {
+            let mut snapshot = self.snapshot.write().expect("lock poisoned");
+            *snapshot = snapshot.with_cursor(
                 session.id.clone(),
-                SessionSnapshot::new(session.id.clone(), messages, closed),
+                SnapshotCursor {
+                    last_sequence: seeded.last_sequence,
+                    closed: false,
+                },
             );
         }
 
-        *self.snapshot.write().expect("lock poisoned") = SnapshotView::new(snapshot_sessions);
-        Ok(())
+        // 3. Emit side-effect
+        self.sink.emit(SideEffect::SessionCreated {
+            session_id: session.id.clone(),
+        });
+
+        session
+    }
+
+    /// Returns the number of sessions whose messages are currently cached.
+    #[cfg(test)]
+    pub(crate) fn cached_snapshot_sessions(&self) -> usize {
+        self.snapshot_cache.len()
     }
 }
 
@@ -275,6 +412,7 @@ impl<S: SideEffectSink> SessionWriter for Armin<S> {
             // 2. Update derived state
             self.live.close_session(id);
             self.delta.clear(id);
+            self.snapshot_cache.invalidate(id);
 
             // 3. Emit side-effect
             self.sink.emit(SideEffect::SessionDeleted {
@@ -285,6 +423,65 @@ impl<S: SideEffectSink> SessionWriter for Armin<S> {
         Ok(deleted)
     }
 
+    fn fork_session_with_options(
+        &self,
+        source: &SessionId,
+        up_to_sequence: i64,
+        options: SessionForkOptions,
+    ) -> Result<Session, ArminError> {
+        let Some(parent) = self.sqlite.get_agent_session(source)? else {
+            return Err(ArminError::SessionNotFound(source.as_str().to_string()));
+        };
+        let last_sequence = self.sqlite.last_agent_message_sequence(source)?;
+        if !(0..=last_sequence).contains(&up_to_sequence) {
+            return Err(ArminError::InvalidForkPoint(format!(
+                "sequence {up_to_sequence} is outside 0..={last_sequence} for session {source}"
+            )));
+        }
+
+        let fork = NewSession {
+            id: options.id,
+            repository_id: parent.repository_id,
+            machine_id: parent.machine_id,
+            space_id: parent.space_id,
+            title: options.title.unwrap_or(parent.title),
+            agent_name: parent.agent_name,
+            issue_id: parent.issue_id,
+            issue_title: parent.issue_title,
+            issue_url: parent.issue_url,
+            provider: parent.provider,
+            provider_session_id: None,
+            claude_session_id: None,
+            // A fork only owns a worktree created for it; otherwise it works in
+            // the parent's directory without taking ownership of it.
+            is_worktree: options.worktree_path.is_some(),
+            worktree_path: options.worktree_path.or(parent.worktree_path),
+        };
+
+        // 1. Commit fact to SQLite (session row and copied messages together)
+        let forked = self
+            .sqlite
+            .fork_agent_session(source, up_to_sequence, &fork)?;
+
+        // 2-3. Update derived state and emit side-effect
+        Ok(self.finish_seeded_session(forked))
+    }
+
+    fn import_session(
+        &self,
+        session: NewSession,
+        history: SessionHistory,
+        messages: Vec<ImportedMessage>,
+    ) -> Result<Session, ArminError> {
+        // 1. Commit fact to SQLite (session row and messages together)
+        let imported = self
+            .sqlite
+            .import_agent_session(&session, history, &messages)?;
+
+        // 2-3. Update derived state and emit side-effect
+        Ok(self.finish_seeded_session(imported))
+    }
+
     // ========================================================================
     // Session state operations
     // ========================================================================
@@ -320,6 +517,139 @@ impl<S: SideEffectSink> SessionWriter for Armin<S> {
         Ok(())
     }
 
+    fn update_runtime_question(
+        &self,
+        session: &SessionId,
+        device_id: &str,
+        question: Option<QuestionRuntimeState>,
+    ) -> Result<(), ArminError> {
+        // 1. Ensure session state exists, then update
+        let _ = self.sqlite.get_or_create_session_state(session)?;
+
+        let updated = self
+            .sqlite
+            .update_runtime_question(session, device_id, question.as_ref())?;
+
+        if updated {
+            // 3. Emit side-effect
+            let runtime_status = self
+                .sqlite
+                .get_session_state(session)?
+                .map(|state| state.runtime_status)
+                .ok_or_else(|| ArminError::SessionNotFound(session.as_str().to_string()))?;
+
+            self.sink.emit(SideEffect::RuntimeStatusUpdated {
+                session_id: session.clone(),
+                runtime_status,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn update_runtime_scheduler_position(
+        &self,
+        session: &SessionId,
+        device_id: &str,
+        position: Option<i64>,
+    ) -> Result<(), ArminError> {
+        // 1. Ensure session state exists, then update
+        let _ = self.sqlite.get_or_create_session_state(session)?;
+
+        let updated = self
+            .sqlite
+            .update_runtime_scheduler_position(session, device_id, position)?;
+
+        if updated {
+            // 3. Emit side-effect
+            self.emit_runtime_status(session)?;
+        }
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Message queue operations
+    // ========================================================================
+
+    fn enqueue_message(
+        &self,
+        session: &SessionId,
+        message: NewQueuedMessage,
+    ) -> Result<QueuedMessage, ArminError> {
+        // 1. Commit fact to SQLite
+        let queued = self.sqlite.enqueue_message(session, &message)?;
+
+        // 2. No derived state; the queue length is read with the runtime status
+        // 3. Emit side-effect
+        self.emit_runtime_status(session)?;
+        Ok(queued)
+    }
+
+    fn pop_queued_message(&self, session: &SessionId) -> Result<Option<QueuedMessage>, ArminError> {
+        let popped = self.sqlite.pop_queued_message(session)?;
+        if popped.is_some() {
+            self.emit_runtime_status(session)?;
+        }
+        Ok(popped)
+    }
+
+    fn remove_queued_message(&self, session: &SessionId, id: &str) -> Result<bool, ArminError> {
+        let removed = self.sqlite.remove_queued_message(session, id)?;
+        if removed {
+            self.emit_runtime_status(session)?;
+        }
+        Ok(removed)
+    }
+
+    fn clear_queued_messages(&self, session: &SessionId) -> Result<usize, ArminError> {
+        let cleared = self.sqlite.clear_queued_messages(session)?;
+        if cleared > 0 {
+            self.emit_runtime_status(session)?;
+        }
+        Ok(cleared)
+    }
+
+    // ========================================================================
+    // Process record operations
+    // ========================================================================
+
+    fn record_process(
+        &self,
+        session: &SessionId,
+        process: NewProcessRecord,
+    ) -> Result<ProcessRecord, ArminError> {
+        // Bookkeeping only: no derived state and no side-effect
+        Ok(self.sqlite.record_process(session, &process)?)
+    }
+
+    fn remove_process_record(&self, id: &str) -> Result<bool, ArminError> {
+        Ok(self.sqlite.remove_process_record(id)?)
+    }
+
+    // ========================================================================
+    // Terminal operations
+    // ========================================================================
+
+    fn create_terminal(
+        &self,
+        session: &SessionId,
+        terminal: NewTerminal,
+    ) -> Result<Terminal, ArminError> {
+        Ok(self.sqlite.create_terminal(session, &terminal)?)
+    }
+
+    fn finish_terminal(&self, id: &str, exit_code: Option<i32>) -> Result<bool, ArminError> {
+        Ok(self.sqlite.finish_terminal(id, exit_code)?)
+    }
+
+    fn append_terminal_output(&self, id: &str, content: &str) -> Result<u64, ArminError> {
+        // Kept out of the transcript: no sequence number, no side effect
+        Ok(self
+            .sqlite
+            .append_terminal_output(id, content, TERMINAL_SCROLLBACK_BYTES)?)
+    }
+
     // ========================================================================
     // Simple session operations (for tests - creates default repository)
     // ========================================================================
@@ -428,6 +758,7 @@ impl<S: SideEffectSink> SessionWriter for Armin<S> {
             status: Some(SessionStatus::Archived),
             claude_session_id: None,
             last_accessed_at: None,
+            permission_mode: None,
         };
         self.sqlite.update_agent_session(session, &update)?;
 
@@ -516,6 +847,62 @@ impl<S: SideEffectSink> SessionReader for Armin<S> {
         self.live.subscribe(session)
     }
 
+    fn search_messages(
+        &self,
+        query: &MessageSearchQuery,
+    ) -> Result<Vec<MessageSearchHit>, ArminError> {
+        if query.text.split_whitespace().next().is_none() {
+            return Err(ArminError::InvalidSearchQuery(
+                "search text is empty".to_string(),
+            ));
+        }
+        Ok(self.sqlite.search_agent_messages(query)?)
+    }
+
+    fn list_queued_messages(&self, session: &SessionId) -> Result<Vec<QueuedMessage>, ArminError> {
+        Ok(self.sqlite.list_queued_messages(session)?)
+    }
+
+    fn sessions_with_queued_messages(&self) -> Result<Vec<SessionId>, ArminError> {
+        Ok(self.sqlite.sessions_with_queued_messages()?)
+    }
+
+    fn sessions_with_runtime_status(
+        &self,
+        statuses: &[CodingSessionStatus],
+    ) -> Result<Vec<SessionId>, ArminError> {
+        Ok(self.sqlite.sessions_with_runtime_status(statuses)?)
+    }
+
+    fn list_process_records(&self) -> Result<Vec<ProcessRecord>, ArminError> {
+        Ok(self.sqlite.list_process_records()?)
+    }
+
+    // ========================================================================
+    // Terminal operations
+    // ========================================================================
+
+    fn get_terminal(&self, id: &str) -> Result<Option<Terminal>, ArminError> {
+        Ok(self.sqlite.get_terminal(id)?)
+    }
+
+    fn list_terminals(&self, session: &SessionId) -> Result<Vec<Terminal>, ArminError> {
+        Ok(self.sqlite.list_terminals(session)?)
+    }
+
+    fn list_unfinished_terminals(&self) -> Result<Vec<Terminal>, ArminError> {
+        Ok(self.sqlite.list_unfinished_terminals()?)
+    }
+
+    fn read_terminal_output(
+        &self,
+        id: &str,
+        offset: u64,
+        limit: usize,
+    ) -> Result<TerminalOutputPage, ArminError> {
+        Ok(self.sqlite.read_terminal_output(id, offset, limit)?)
+    }
+
     // ========================================================================
     // Session secrets operations
     // ========================================================================
@@ -645,6 +1032,132 @@ mod tests {
         }
     }
 
+    #[test]
+    fn runtime_question_survives_status_updates_until_cleared() {
+        let sink = RecordingSink::new();
+        let armin = Armin::in_memory(sink).unwrap();
+        let session_id = armin.create_session().unwrap();
+        let device_id = "11111111-1111-1111-1111-111111111111";
+        let question = |resolved| QuestionRuntimeState {
+            tool_use_id: "toolu_ask".to_string(),
+            resolved,
+        };
+        let current_question = || {
+            armin
+                .get_session_state(&session_id)
+                .unwrap()
+                .unwrap()
+                .runtime_status
+                .coding_session
+                .question
+        };
+
+        armin
+            .update_runtime_question(&session_id, device_id, Some(question(false)))
+            .unwrap();
+        armin
+            .update_runtime_status(&session_id, device_id, CodingSessionStatus::Waiting, None)
+            .unwrap();
+        assert_eq!(current_question(), Some(question(false)));
+
+        armin.sink().clear();
+        armin
+            .update_runtime_question(&session_id, device_id, Some(question(true)))
+            .unwrap();
+        assert_eq!(current_question(), Some(question(true)));
+        match &armin.sink().effects()[..] {
+            [SideEffect::RuntimeStatusUpdated { runtime_status, .. }] => {
+                assert_eq!(runtime_status.coding_session.question, Some(question(true)));
+                assert_eq!(
+                    runtime_status.coding_session.status,
+                    CodingSessionStatus::Waiting
+                );
+            }
+            other => panic!("unexpected side effects: {other:?}"),
+        }
+
+        armin
+            .update_runtime_question(&session_id, device_id, None)
+            .unwrap();
+        assert_eq!(current_question(), None);
+    }
+
+    #[test]
+    fn scheduler_position_is_set_and_cleared_without_changing_status() {
+        let sink = RecordingSink::new();
+        let armin = Armin::in_memory(sink).unwrap();
+        let session_id = armin.create_session().unwrap();
+        let device_id = "11111111-1111-1111-1111-111111111111";
+        armin
+            .update_runtime_status(&session_id, device_id, CodingSessionStatus::Waiting, None)
+            .unwrap();
+
+        armin.sink().clear();
+        armin
+            .update_runtime_scheduler_position(&session_id, device_id, Some(2))
+            .unwrap();
+        match &armin.sink().effects()[..] {
+            [SideEffect::RuntimeStatusUpdated { runtime_status, .. }] => {
+                assert_eq!(runtime_status.coding_session.scheduler_position, Some(2));
+                assert_eq!(
+                    runtime_status.coding_session.status,
+                    CodingSessionStatus::Waiting
+                );
+            }
+            other => panic!("unexpected side effects: {other:?}"),
+        }
+
+        armin
+            .update_runtime_scheduler_position(&session_id, device_id, None)
+            .unwrap();
+        let coding_session = armin
+            .get_session_state(&session_id)
+            .unwrap()
+            .unwrap()
+            .runtime_status
+            .coding_session;
+        assert_eq!(coding_session.scheduler_position, None);
+        assert_eq!(coding_session.status, CodingSessionStatus::Waiting);
+    }
+
+    #[test]
+    fn queue_changes_emit_runtime_status_with_queue_length() {
+        let sink = RecordingSink::new();
+        let armin = Armin::in_memory(sink).unwrap();
+        let session_id = armin.create_session().unwrap();
+        let queue_length = |effects: &[SideEffect]| match effects {
+            [SideEffect::RuntimeStatusUpdated { runtime_status, .. }] => {
+                runtime_status.coding_session.queue_length
+            }
+            other => panic!("unexpected side effects: {other:?}"),
+        };
+
+        armin.sink().clear();
+        let queued = armin
+            .enqueue_message(
+                &session_id,
+                NewQueuedMessage {
+                    content: "next".to_string(),
+                    request_json: "{}".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(queue_length(&armin.sink().effects()), 1);
+
+        armin.sink().clear();
+        assert!(!armin.remove_queued_message(&session_id, "missing").unwrap());
+        assert!(armin.sink().effects().is_empty());
+
+        let popped = armin.pop_queued_message(&session_id).unwrap().unwrap();
+        assert_eq!(popped.id, queued.id);
+        assert_eq!(queue_length(&armin.sink().effects()), 0);
+
+        armin.sink().clear();
+        assert!(armin.pop_queued_message(&session_id).unwrap().is_none());
+        assert_eq!(armin.clear_queued_messages(&session_id).unwrap(), 0);
+        assert!(armin.sink().effects().is_empty());
+    }
+
     #[test]
     fn delta_contains_appended_messages() {
         let sink = RecordingSink::new();
@@ -684,7 +1197,7 @@ mod tests {
         let armin = Armin::in_memory(sink).unwrap();
 
         let session_id = armin.create_session().unwrap();
-        let sub = armin.subscribe(&session_id);
+        let mut sub = armin.subscribe(&session_id);
 
         armin
             .append(
@@ -73,14 +73,19 @@ pub mod writer;
 #[cfg(test)]
 mod tests;
 
-pub use crate::armin::Armin;
+pub use crate::armin::{Armin, ArminConfig};
 pub use reader::SessionReader;
 pub use side_effect::{NullSink, RecordingSink, SideEffect, SideEffectSink};
+pub use sqlite::MESSAGE_SEARCH_BODY_SQL;
 pub use types::{
-    AgentStatus, CodingSessionRuntimeState, CodingSessionStatus, Message, MessageId, NewMessage,
-    NewRepository, NewSession, NewSessionSecret, Repository, RepositoryId, RuntimeStatusEnvelope,
-    Session, SessionId, SessionSecret, SessionState, SessionStatus, SessionUpdate, UserSetting,
-    RUNTIME_STATUS_SCHEMA_VERSION,
+    AgentStatus, CodingSessionRuntimeState, CodingSessionStatus, ImportedMessage, Message,
+    MessageId, MessageSearchHit, MessageSearchQuery, NewMessage, NewProcessRecord,
+    NewQueuedMessage, NewRepository, NewSession, NewSessionSecret, NewTerminal, ProcessKind,
+    ProcessRecord, QuestionRuntimeState, QueuedMessage, Repository, RepositoryId,
+    RuntimeStatusEnvelope, Session, SessionForkOptions, SessionHistory, SessionId, SessionSecret,
+    SessionState, SessionStatus, SessionUpdate, Terminal, TerminalOutputChunk, TerminalOutputPage,
+    TimestampedMessage, UserSetting, DEFAULT_MESSAGE_SEARCH_LIMIT, RUNTIME_STATUS_SCHEMA_VERSION,
+    TERMINAL_SCROLLBACK_BYTES,
 };
 pub use writer::SessionWriter;
 
@@ -94,4 +99,12 @@ pub enum ArminError {
     /// Session not found or closed.
     #[error("session not found or closed: {0}")]
     SessionNotFound(String),
+
+    /// Search text contained no searchable words.
+    #[error("invalid search query: {0}")]
+    InvalidSearchQuery(String),
+
+    /// Fork point lies outside the source session's messages.
+    #[error("invalid fork point: {0}")]
+    InvalidForkPoint(String),
 }
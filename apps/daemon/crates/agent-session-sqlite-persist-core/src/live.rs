@@ -7,112 +7,181 @@
 //! - Subscriptions are notified after facts are committed
 //! - Subscriptions are derived from committed state
 //! - Recovery does not trigger live notifications
+//! - Subscriptions are bounded; a subscriber that falls behind is cut off and
+//!   told where its gap starts, never handed a stream with holes in it
 
 use std::collections::HashMap;
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::RwLock;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
 
 use crate::types::{Message, SessionId};
 
+/// Default number of undelivered messages buffered per subscription.
+pub const DEFAULT_LIVE_BUFFER_CAPACITY: usize = 1024;
+
+/// Why a subscription stopped early: its buffer filled up.
+///
+/// Every message before `first_missed_sequence` was delivered; that message and
+/// everything after it were not. Consumers catch up by reading committed
+/// messages after `first_missed_sequence - 1` from SQLite, then resubscribing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiveLag {
+    pub first_missed_sequence: i64,
+}
+
 /// A subscription to live updates for a session.
+///
+/// Yields messages in commit order as a [`Stream`]. The stream ends when the
+/// session is closed or when the subscriber lags; [`LiveSubscription::lagged`]
+/// tells the two apart.
 pub struct LiveSubscription {
     receiver: Receiver<Message>,
+    lag: Arc<OnceLock<LiveLag>>,
     _session_id: SessionId,
 }
 
 impl LiveSubscription {
-    /// Creates a new subscription instance with the given MPSC receiver.
+    /// Creates a new subscription instance with the given channel receiver.
     ///
     /// Internal constructor used by LiveHub to wrap the receiver end of a
     /// channel. The session_id is stored for potential future use (debugging, filtering).
-    fn new(session_id: SessionId, receiver: Receiver<Message>) -> Self {
+    fn new(
+        session_id: SessionId,
+        receiver: Receiver<Message>,
+        lag: Arc<OnceLock<LiveLag>>,
+    ) -> Self {
         Self {
             receiver,
+            lag,
             _session_id: session_id,
         }
     }
 
-    /// Blocks the current thread until a new message is available.
+    /// Waits for the next message.
     ///
-    /// Returns the next message when it arrives, or None if the subscription
-    /// has been closed (all senders dropped or session closed). Useful for
-    /// worker threads that process messages as they arrive.
-    pub fn recv(&self) -> Option<Message> {
-        self.receiver.recv().ok()
+    /// Returns None once the subscription has ended (session closed or subscriber
+    /// lagged) and all buffered messages have been received.
+    pub async fn recv(&mut self) -> Option<Message> {
+        self.receiver.recv().await
     }
 
-    /// Attempts to receive a message without blocking the current thread.
+    /// Attempts to receive a message without waiting.
     ///
     /// Returns Some(Message) if a message is immediately available, or None
-    /// if the queue is empty or the subscription has been closed. Useful for
-    /// polling in event loops or non-blocking contexts.
-    pub fn try_recv(&self) -> Option<Message> {
+    /// if the buffer is empty or the subscription has ended. Useful for
+    /// polling in event loops or non-async contexts.
+    pub fn try_recv(&mut self) -> Option<Message> {
         self.receiver.try_recv().ok()
     }
 
-    /// Creates a blocking iterator that yields messages as they arrive.
+    /// Returns where this subscription's gap starts if it was cut off for lagging.
     ///
-    /// The iterator will block waiting for each message and terminate when
-    /// the subscription is closed. Enables idiomatic for-loop consumption
-    /// of the message stream.
-    pub fn iter(&self) -> impl Iterator<Item = Message> + '_ {
-        std::iter::from_fn(|| self.recv())
+    /// Messages still buffered ahead of the gap remain receivable.
+    pub fn lagged(&self) -> Option<LiveLag> {
+        self.lag.get().copied()
+    }
+}
+
+impl Stream for LiveSubscription {
+    type Item = Message;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Message>> {
+        self.receiver.poll_recv(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.receiver.len(), None)
     }
 }
 
+/// Sending half of one subscription, held by the hub.
+#[derive(Debug)]
+struct Subscriber {
+    sender: Sender<Message>,
+    lag: Arc<OnceLock<LiveLag>>,
+}
+
 /// A hub that manages live subscriptions for all sessions.
 #[derive(Debug)]
 pub struct LiveHub {
     /// Map of session ID to list of subscribers
-    subscribers: RwLock<HashMap<SessionId, Vec<Sender<Message>>>>,
+    subscribers: RwLock<HashMap<SessionId, Vec<Subscriber>>>,
+    /// Messages buffered per subscription before it is considered lagging
+    capacity: usize,
 }
 
 impl LiveHub {
-    /// Creates a new empty hub with no active subscriptions.
+    /// Creates a new empty hub with the default per-subscription buffer.
     ///
     /// Initializes the subscriber map wrapped in a RwLock for thread-safe
     /// access from multiple concurrent sessions and notification sources.
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_LIVE_BUFFER_CAPACITY)
+    }
+
+    /// Creates a new empty hub buffering up to `capacity` messages per subscription.
+    ///
+    /// A capacity of zero is treated as one.
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
             subscribers: RwLock::new(HashMap::new()),
+            capacity: capacity.max(1),
         }
     }
 
     /// Creates a new subscription for receiving live updates on a session.
     ///
-    /// Sets up an MPSC channel and registers the sender with the hub. The
+    /// Sets up a bounded channel and registers the sender with the hub. The
     /// returned LiveSubscription receives all messages appended to the session
     /// after this call. Messages sent before subscription are not received.
     pub fn subscribe(&self, session: &SessionId) -> LiveSubscription {
-        let (sender, receiver) = mpsc::channel();
+        let (sender, receiver) = mpsc::channel(self.capacity);
+        let lag = Arc::new(OnceLock::new());
 
         let mut subscribers = self.subscribers.write().expect("lock poisoned");
         subscribers
             .entry(session.clone())
-            .or_insert_with(Vec::new)
-            .push(sender);
+            .or_default()
+            .push(Subscriber {
+                sender,
+                lag: lag.clone(),
+            });
 
-        LiveSubscription::new(session.clone(), receiver)
+        LiveSubscription::new(session.clone(), receiver, lag)
     }
 
     /// Broadcasts a message to all subscribers of a session.
     ///
-    /// Clones the message and sends it to each registered subscriber. Dead
-    /// subscribers (where the receiver has been dropped) are automatically
-    /// removed during this operation. Must be called after SQLite commit.
+    /// Never blocks. Dead subscribers (where the receiver has been dropped) are
+    /// removed, and subscribers whose buffer is full are marked lagged at this
+    /// message and removed, which ends their stream. Must be called after SQLite commit.
     pub fn notify(&self, session: &SessionId, message: Message) {
         let mut subscribers = self.subscribers.write().expect("lock poisoned");
 
-        if let Some(senders) = subscribers.get_mut(session) {
-            // Send to all subscribers, removing dead ones
-            senders.retain(|sender| sender.send(message.clone()).is_ok());
+        if let Some(entries) = subscribers.get_mut(session) {
+            entries.retain(
+                |subscriber| match subscriber.sender.try_send(message.clone()) {
+                    Ok(()) => true,
+                    Err(TrySendError::Full(_)) => {
+                        let _ = subscriber.lag.set(LiveLag {
+                            first_missed_sequence: message.sequence_number,
+                        });
+                        false
+                    }
+                    Err(TrySendError::Closed(_)) => false,
+                },
+            );
         }
     }
 
     /// Removes all subscribers for a session and closes their channels.
     ///
-    /// Called when a session is closed to clean up resources. All pending
-    /// receives on subscriptions will return None after this call.
+    /// Called when a session is closed to clean up resources. Subscriptions
+    /// end once their buffered messages have been received.
     pub fn close_session(&self, session: &SessionId) {
         let mut subscribers = self.subscribers.write().expect("lock poisoned");
         subscribers.remove(session);
@@ -141,12 +210,17 @@ impl Default for LiveHub {
 mod tests {
     use super::*;
     use crate::types::MessageId;
+    use futures_util::StreamExt;
 
     fn make_message(id: &str, content: &str) -> Message {
+        make_sequenced_message(id, content, 0)
+    }
+
+    fn make_sequenced_message(id: &str, content: &str, sequence_number: i64) -> Message {
         Message {
             id: MessageId::from_string(id),
             content: content.to_string(),
-            sequence_number: 0,
+            sequence_number,
         }
     }
 
@@ -155,7 +229,7 @@ mod tests {
         let hub = LiveHub::new();
         let session = SessionId::from_string("session-1");
 
-        let sub = hub.subscribe(&session);
+        let mut sub = hub.subscribe(&session);
         assert_eq!(hub.subscriber_count(&session), 1);
 
         hub.notify(&session, make_message("1", "Hello"));
@@ -169,8 +243,8 @@ mod tests {
         let hub = LiveHub::new();
         let session = SessionId::from_string("session-1");
 
-        let sub1 = hub.subscribe(&session);
-        let sub2 = hub.subscribe(&session);
+        let mut sub1 = hub.subscribe(&session);
+        let mut sub2 = hub.subscribe(&session);
         assert_eq!(hub.subscriber_count(&session), 2);
 
         hub.notify(&session, make_message("1", "Broadcast"));
@@ -213,8 +287,8 @@ mod tests {
         let session1 = SessionId::from_string("session-1");
         let session2 = SessionId::from_string("session-2");
 
-        let sub1 = hub.subscribe(&session1);
-        let sub2 = hub.subscribe(&session2);
+        let mut sub1 = hub.subscribe(&session1);
+        let mut sub2 = hub.subscribe(&session2);
 
         hub.notify(&session1, make_message("1", "Session 1"));
         hub.notify(&session2, make_message("2", "Session 2"));
@@ -236,9 +310,54 @@ mod tests {
         hub.notify(&session, make_message("1", "Before"));
 
         // Subscribe after
-        let sub = hub.subscribe(&session);
+        let mut sub = hub.subscribe(&session);
 
         // Should not receive the message sent before subscription
         assert!(sub.try_recv().is_none());
     }
+
+    #[tokio::test]
+    async fn full_buffer_marks_subscriber_lagged_and_ends_stream() {
+        let hub = LiveHub::with_capacity(2);
+        let session = SessionId::from_string("session-1");
+        let mut slow = hub.subscribe(&session);
+        let mut fast = hub.subscribe(&session);
+
+        for sequence in 1..=3 {
+            hub.notify(
+                &session,
+                make_sequenced_message(&sequence.to_string(), "msg", sequence),
+            );
+            if sequence < 3 {
+                fast.next().await.unwrap();
+            }
+        }
+
+        assert_eq!(
+            slow.lagged(),
+            Some(LiveLag {
+                first_missed_sequence: 3
+            })
+        );
+        assert_eq!(hub.subscriber_count(&session), 1);
+        let delivered: Vec<i64> = (&mut slow).map(|m| m.sequence_number).collect().await;
+        assert_eq!(delivered, vec![1, 2]);
+
+        assert_eq!(fast.lagged(), None);
+        assert_eq!(fast.next().await.unwrap().sequence_number, 3);
+    }
+
+    #[tokio::test]
+    async fn closed_session_ends_stream_without_lag() {
+        let hub = LiveHub::new();
+        let session = SessionId::from_string("session-1");
+        let mut sub = hub.subscribe(&session);
+
+        hub.notify(&session, make_message("1", "Last"));
+        hub.close_session(&session);
+
+        assert_eq!(sub.next().await.unwrap().content, "Last");
+        assert!(sub.next().await.is_none());
+        assert_eq!(sub.lagged(), None);
+    }
 }
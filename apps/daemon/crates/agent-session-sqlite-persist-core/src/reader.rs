@@ -4,14 +4,18 @@
 //!
 //! # Design Principles
 //!
-//! - Reads never hit SQLite directly (except on recovery)
+//! - Reads never hit SQLite directly (except on recovery and lazy snapshot loads)
 //! - Reads never emit side-effects
 //! - All read state is derived from SQLite on startup
 
 use crate::delta::DeltaView;
 use crate::live::LiveSubscription;
 use crate::snapshot::SnapshotView;
-use crate::types::{Repository, RepositoryId, Session, SessionId, SessionSecret, SessionState};
+use crate::types::{
+    CodingSessionStatus, MessageSearchHit, MessageSearchQuery, ProcessRecord, QueuedMessage,
+    Repository, RepositoryId, Session, SessionId, SessionSecret, SessionState, Terminal,
+    TerminalOutputPage,
+};
 use crate::ArminError;
 
 /// A reader for session data.
@@ -65,6 +69,53 @@ pub trait SessionReader {
     /// Returns a subscription that yields new messages as they are appended.
     fn subscribe(&self, session: &SessionId) -> LiveSubscription;
 
+    /// Searches message transcripts across sessions, best matches first.
+    ///
+    /// Unlike the views above, this queries the SQLite full-text index directly.
+    fn search_messages(
+        &self,
+        query: &MessageSearchQuery,
+    ) -> Result<Vec<MessageSearchHit>, ArminError>;
+
+    /// Lists a session's queued messages, oldest first.
+    ///
+    /// Like search, this reads SQLite directly.
+    fn list_queued_messages(&self, session: &SessionId) -> Result<Vec<QueuedMessage>, ArminError>;
+
+    /// Sessions with at least one queued message.
+    fn sessions_with_queued_messages(&self) -> Result<Vec<SessionId>, ArminError>;
+
+    /// Sessions whose runtime status is one of `statuses`, read from SQLite.
+    fn sessions_with_runtime_status(
+        &self,
+        statuses: &[CodingSessionStatus],
+    ) -> Result<Vec<SessionId>, ArminError>;
+
+    /// Every recorded child process, oldest first, read from SQLite.
+    fn list_process_records(&self) -> Result<Vec<ProcessRecord>, ArminError>;
+
+    // ========================================================================
+    // Terminal operations
+    // ========================================================================
+
+    /// Gets a terminal by ID, read from SQLite.
+    fn get_terminal(&self, id: &str) -> Result<Option<Terminal>, ArminError>;
+
+    /// A session's terminals, oldest first, read from SQLite.
+    fn list_terminals(&self, session: &SessionId) -> Result<Vec<Terminal>, ArminError>;
+
+    /// Terminals of every session whose command has not finished.
+    fn list_unfinished_terminals(&self) -> Result<Vec<Terminal>, ArminError>;
+
+    /// At most `limit` chunks of a terminal's retained output, from the
+    /// chunk containing `offset`, read from SQLite.
+    fn read_terminal_output(
+        &self,
+        id: &str,
+        offset: u64,
+        limit: usize,
+    ) -> Result<TerminalOutputPage, ArminError>;
+
     // ========================================================================
     // Session secrets operations
     // ========================================================================
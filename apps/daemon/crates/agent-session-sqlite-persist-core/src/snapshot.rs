@@ -4,19 +4,52 @@
 //!
 //! # Design Principles
 //!
-//! - Snapshots are derived from SQLite on startup
-//! - Snapshots are immutable after creation
+//! - Snapshots are derived from SQLite, one session at a time, on first access
+//! - A snapshot pins every session to a sequence cursor, so it never changes after creation
+//! - Loaded sessions are shared through a bounded LRU cache
 //! - Reads from snapshots never cause side-effects
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use crate::sqlite::SqliteStore;
 use crate::types::{Message, SessionId};
 
+/// Default number of sessions kept in the snapshot cache.
+pub const DEFAULT_SNAPSHOT_CACHE_SESSIONS: usize = 64;
+
+/// Default number of messages read per keyset page when loading a session.
+pub const DEFAULT_SNAPSHOT_PAGE_SIZE: usize = 500;
+
+/// Position of a session at the time a snapshot was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SnapshotCursor {
+    /// Sequence number of the last message included in the snapshot (0 if none).
+    pub last_sequence: i64,
+    /// Whether the session had a non-active status when the snapshot was taken.
+    pub closed: bool,
+}
+
 /// An immutable snapshot of all sessions.
+///
+/// A view built by Armin only records a cursor per session; message bodies are
+/// loaded from SQLite the first time a session is accessed. Because each
+/// session is pinned to its cursor, a view keeps returning the same messages
+/// even after newer snapshots are taken.
 #[derive(Debug, Clone)]
 pub struct SnapshotView {
-    sessions: Arc<HashMap<SessionId, SessionSnapshot>>,
+    source: SnapshotSource,
+}
+
+#[derive(Debug, Clone)]
+enum SnapshotSource {
+    /// Sessions fully materialized at construction time.
+    Materialized(Arc<HashMap<SessionId, Arc<SessionSnapshot>>>),
+    /// Session cursors only; messages are loaded through the cache on access.
+    Lazy {
+        cursors: Arc<HashMap<SessionId, SnapshotCursor>>,
+        cache: Arc<SnapshotCache>,
+    },
 }
 
 impl SnapshotView {
@@ -25,9 +58,7 @@ impl SnapshotView {
     /// Returns a SnapshotView backed by an empty Arc-wrapped HashMap,
     /// representing a state with no existing sessions.
     pub fn empty() -> Self {
-        Self {
-            sessions: Arc::new(HashMap::new()),
-        }
+        Self::new(HashMap::new())
     }
 
     /// Creates a snapshot from an existing map of session snapshots.
@@ -35,47 +66,126 @@ impl SnapshotView {
     /// Wraps the provided HashMap in an Arc for efficient cloning and sharing
     /// across threads without copying the underlying data.
     pub fn new(sessions: HashMap<SessionId, SessionSnapshot>) -> Self {
+        let sessions = sessions
+            .into_iter()
+            .map(|(id, snapshot)| (id, Arc::new(snapshot)))
+            .collect();
         Self {
-            sessions: Arc::new(sessions),
+            source: SnapshotSource::Materialized(Arc::new(sessions)),
+        }
+    }
+
+    /// Creates a lazy snapshot that loads sessions through `cache`.
+    pub(crate) fn lazy(
+        cursors: HashMap<SessionId, SnapshotCursor>,
+        cache: Arc<SnapshotCache>,
+    ) -> Self {
+        Self {
+            source: SnapshotSource::Lazy {
+                cursors: Arc::new(cursors),
+                cache,
+            },
+        }
+    }
+
+    /// Returns a copy of this view that also pins `id` at `cursor`.
+    ///
+    /// Used when a session is created with committed messages (e.g. a fork), so
+    /// readers see them without a full refresh. Materialized views are returned as-is.
+    pub(crate) fn with_cursor(&self, id: SessionId, cursor: SnapshotCursor) -> Self {
+        match &self.source {
+            SnapshotSource::Materialized(_) => self.clone(),
+            SnapshotSource::Lazy { cursors, cache } => {
+                let mut cursors = cursors.as_ref().clone();
+                cursors.insert(id, cursor);
+                Self::lazy(cursors, cache.clone())
+            }
         }
     }
 
-    /// Retrieves a reference to a session snapshot by its ID.
+    /// Retrieves a session snapshot by its ID.
     ///
-    /// Returns Some reference to the SessionSnapshot if found, or None if the
-    /// session doesn't exist in this snapshot.
-    pub fn session(&self, id: &SessionId) -> Option<&SessionSnapshot> {
-        self.sessions.get(id)
+    /// Returns None if the session doesn't exist in this snapshot. For lazy
+    /// views this may read the session's messages from SQLite (in keyset
+    /// pages) the first time it is accessed; a failed read is logged and
+    /// reported as None.
+    pub fn session(&self, id: &SessionId) -> Option<Arc<SessionSnapshot>> {
+        match &self.source {
+            SnapshotSource::Materialized(sessions) => sessions.get(id).cloned(),
+            SnapshotSource::Lazy { cursors, cache } => {
+                let cursor = cursors.get(id)?;
+                match cache.load(id, *cursor) {
+                    Ok(snapshot) => Some(snapshot),
+                    Err(error) => {
+                        tracing::warn!(
+                            session_id = %id,
+                            error = %error,
+                            "armin: failed to load session snapshot"
+                        );
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the sequence number of a session's last message at snapshot time.
+    ///
+    /// Returns 0 for a session without messages and None if the session is not
+    /// in the snapshot. Lazy views answer from their cursor without reading messages.
+    pub fn last_sequence(&self, id: &SessionId) -> Option<i64> {
+        match &self.source {
+            SnapshotSource::Materialized(sessions) => sessions.get(id).map(|session| {
+                session
+                    .messages()
+                    .last()
+                    .map(|message| message.sequence_number)
+                    .unwrap_or(0)
+            }),
+            SnapshotSource::Lazy { cursors, .. } => {
+                cursors.get(id).map(|cursor| cursor.last_sequence)
+            }
+        }
     }
 
     /// Returns an iterator over all session IDs in the snapshot.
     ///
     /// Provides a way to enumerate available sessions without loading their
     /// full snapshot data.
-    pub fn session_ids(&self) -> impl Iterator<Item = &SessionId> + '_ {
-        self.sessions.keys()
+    pub fn session_ids(&self) -> Box<dyn Iterator<Item = &SessionId> + '_> {
+        match &self.source {
+            SnapshotSource::Materialized(sessions) => Box::new(sessions.keys()),
+            SnapshotSource::Lazy { cursors, .. } => Box::new(cursors.keys()),
+        }
     }
 
     /// Returns the total number of sessions in the snapshot.
     ///
     /// Provides a quick count without iterating through all sessions.
     pub fn len(&self) -> usize {
-        self.sessions.len()
+        match &self.source {
+            SnapshotSource::Materialized(sessions) => sessions.len(),
+            SnapshotSource::Lazy { cursors, .. } => cursors.len(),
+        }
     }
 
     /// Checks if the snapshot contains no sessions.
     ///
     /// Returns true for a fresh or empty state, useful for early-exit checks.
     pub fn is_empty(&self) -> bool {
-        self.sessions.is_empty()
+        self.len() == 0
     }
 }
 
 /// An immutable snapshot of a single session.
+///
+/// Messages are shared with the snapshot cache, so snapshots of the same
+/// session at different cursors reuse one buffer.
 #[derive(Debug, Clone)]
 pub struct SessionSnapshot {
     id: SessionId,
-    messages: Vec<Message>,
+    messages: Arc<Vec<Message>>,
+    len: usize,
     closed: bool,
 }
 
@@ -85,9 +195,21 @@ impl SessionSnapshot {
     /// Captures the session's state at a point in time including all messages
     /// and the closed status. Once created, the snapshot cannot be modified.
     pub fn new(id: SessionId, messages: Vec<Message>, closed: bool) -> Self {
+        let len = messages.len();
+        Self {
+            id,
+            messages: Arc::new(messages),
+            len,
+            closed,
+        }
+    }
+
+    /// Creates a snapshot over the first `len` messages of a shared buffer.
+    fn shared(id: SessionId, messages: Arc<Vec<Message>>, len: usize, closed: bool) -> Self {
         Self {
             id,
             messages,
+            len,
             closed,
         }
     }
@@ -103,7 +225,7 @@ impl SessionSnapshot {
     ///
     /// Messages are ordered by their sequence number as they were at snapshot time.
     pub fn messages(&self) -> &[Message] {
-        &self.messages
+        &self.messages[..self.len]
     }
 
     /// Checks if the session was closed at snapshot time.
@@ -118,7 +240,174 @@ impl SessionSnapshot {
     ///
     /// Provides a quick way to check message volume without iterating.
     pub fn message_count(&self) -> usize {
-        self.messages.len()
+        self.len
+    }
+}
+
+/// Bounded LRU cache of per-session message buffers backing lazy snapshots.
+///
+/// Messages are append-only and contiguous per session, so the buffer cached
+/// at cursor `n` serves any snapshot at a cursor `<= n` as a prefix, and a
+/// later cursor only needs the missing tail from SQLite.
+pub(crate) struct SnapshotCache {
+    sqlite: Arc<SqliteStore>,
+    capacity: usize,
+    page_size: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<SessionId, CacheEntry>,
+    clock: u64,
+}
+
+struct CacheEntry {
+    messages: Arc<Vec<Message>>,
+    last_sequence: i64,
+    last_used: u64,
+}
+
+impl SnapshotCache {
+    /// Creates a cache holding at most `capacity` sessions.
+    pub fn new(sqlite: Arc<SqliteStore>, capacity: usize, page_size: usize) -> Self {
+        Self {
+            sqlite,
+            capacity,
+            page_size: page_size.max(1),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Returns the session snapshot at `cursor`, reading missing messages from SQLite.
+    fn load(
+        &self,
+        id: &SessionId,
+        cursor: SnapshotCursor,
+    ) -> Result<Arc<SessionSnapshot>, rusqlite::Error> {
+        let cached = {
+            let mut state = self.state.lock().expect("lock poisoned");
+            state.clock += 1;
+            let clock = state.clock;
+            state.entries.get_mut(id).map(|entry| {
+                entry.last_used = clock;
+                (entry.messages.clone(), entry.last_sequence)
+            })
+        };
+
+        let messages = match cached {
+            Some((messages, last_sequence)) if last_sequence >= cursor.last_sequence => messages,
+            Some((messages, last_sequence)) => {
+                let mut extended = messages.as_ref().clone();
+                self.read_pages(id, last_sequence, cursor.last_sequence, &mut extended)?;
+                let messages = Arc::new(extended);
+                self.insert(id, messages.clone(), cursor.last_sequence);
+                messages
+            }
+            None => {
+                let mut loaded = Vec::new();
+                self.read_pages(id, 0, cursor.last_sequence, &mut loaded)?;
+                let messages = Arc::new(loaded);
+                self.insert(id, messages.clone(), cursor.last_sequence);
+                messages
+            }
+        };
+
+        let len = messages.partition_point(|m| m.sequence_number <= cursor.last_sequence);
+
+        Ok(Arc::new(SessionSnapshot::shared(
+            id.clone(),
+            messages,
+            len,
+            cursor.closed,
+        )))
+    }
+
+    /// Appends messages in `(after_sequence, up_to_sequence]` to `out`, one keyset page at a time.
+    fn read_pages(
+        &self,
+        id: &SessionId,
+        after_sequence: i64,
+        up_to_sequence: i64,
+        out: &mut Vec<Message>,
+    ) -> Result<(), rusqlite::Error> {
+        let mut after = after_sequence;
+        while after < up_to_sequence {
+            let page =
+                self.sqlite
+                    .get_agent_messages_page(id, after, up_to_sequence, self.page_size)?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = last.sequence_number;
+            let full_page = page.len() == self.page_size;
+            out.extend(page);
+            if !full_page {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores a loaded buffer, evicting the least recently used session when full.
+    fn insert(&self, id: &SessionId, messages: Arc<Vec<Message>>, last_sequence: i64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("lock poisoned");
+        state.clock += 1;
+        let clock = state.clock;
+
+        // A concurrent load may already have cached a longer buffer.
+        if let Some(existing) = state.entries.get_mut(id) {
+            if existing.last_sequence >= last_sequence {
+                existing.last_used = clock;
+                return;
+            }
+        }
+
+        state.entries.insert(
+            id.clone(),
+            CacheEntry {
+                messages,
+                last_sequence,
+                last_used: clock,
+            },
+        );
+
+        while state.entries.len() > self.capacity {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+    }
+
+    /// Drops a session's cached messages.
+    pub fn invalidate(&self, id: &SessionId) {
+        let mut state = self.state.lock().expect("lock poisoned");
+        state.entries.remove(id);
+    }
+
+    /// Returns the number of sessions currently cached.
+    pub fn len(&self) -> usize {
+        self.state.lock().expect("lock poisoned").entries.len()
+    }
+}
+
+impl std::fmt::Debug for SnapshotCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SnapshotCache")
+            .field("capacity", &self.capacity)
+            .field("page_size", &self.page_size)
+            .field("cached_sessions", &self.len())
+            .finish()
     }
 }
 
@@ -15,12 +15,35 @@ use std::path::Path;
 use std::sync::Mutex;
 
 use crate::types::{
-    AgentStatus, CodingSessionRuntimeState, CodingSessionStatus, Message, MessageId, NewMessage,
-    NewRepository, NewSession, NewSessionSecret, Repository, RepositoryId, RuntimeStatusEnvelope,
-    Session, SessionId, SessionSecret, SessionState, SessionStatus, SessionUpdate, UserSetting,
-    RUNTIME_STATUS_SCHEMA_VERSION,
+    AgentStatus, CodingSessionRuntimeState, CodingSessionStatus, ImportedMessage, Message,
+    MessageId, MessageSearchHit, MessageSearchQuery, NewMessage, NewProcessRecord,
+    NewQueuedMessage, NewRepository, NewSession, NewSessionSecret, NewTerminal, ProcessKind,
+    ProcessRecord, QuestionRuntimeState, QueuedMessage, Repository, RepositoryId,
+    RuntimeStatusEnvelope, Session, SessionHistory, SessionId, SessionSecret, SessionState,
+    SessionStatus, SessionUpdate, Terminal, TerminalOutputChunk, TerminalOutputPage,
+    TimestampedMessage, UserSetting, RUNTIME_STATUS_SCHEMA_VERSION,
 };
 
+/// Selects a terminal row in the order `terminal_from_row` reads it.
+const TERMINAL_COLUMNS: &str = "SELECT id, session_id, name, command, cwd, pid, started_at, \
+     finished_at, exit_code FROM local_llm_conversation_terminals";
+
+/// Searchable text of a message row aliased as `m`.
+///
+/// JSON content (Claude/Codex events, terminal output) contributes its string leaves,
+/// minus identifier-like fields; anything else is indexed verbatim. The daemon-database
+/// `message_search_index` migration backfills the index with the same expression.
+pub const MESSAGE_SEARCH_BODY_SQL: &str = r#"
+    CASE WHEN json_valid(m.content) THEN COALESCE((
+        SELECT group_concat(t.value, ' ') FROM json_tree(m.content) t
+        WHERE t.type = 'text'
+          AND COALESCE(t.key, '') NOT IN (
+              'id', 'type', 'uuid', 'session_id', 'parent_tool_use_id', 'tool_use_id',
+              'signature', 'model', 'role', 'stop_reason', 'subtype', 'stream'
+          )
+    ), '') ELSE m.content END
+"#;
+
 /// Result of an atomic message insertion.
 #[derive(Debug, Clone)]
 pub struct InsertedMessage {
@@ -28,6 +51,27 @@ pub struct InsertedMessage {
     pub sequence_number: i64,
 }
 
+/// A session created together with its initial messages (by forking or import),
+/// with the position of its last message.
+#[derive(Debug, Clone)]
+pub struct SeededSession {
+    pub session: Session,
+    pub last_message_id: Option<MessageId>,
+    pub last_sequence: i64,
+}
+
+/// Number of messages copied per keyset page when forking a session.
+const FORK_COPY_PAGE_SIZE: i64 = 500;
+
+/// Position of a session's last message, read without loading message content.
+#[derive(Debug, Clone)]
+pub struct SessionCursorRow {
+    pub id: SessionId,
+    pub status: SessionStatus,
+    pub last_message_id: Option<MessageId>,
+    pub last_sequence: i64,
+}
+
 /// SQLite storage for sessions and messages.
 ///
 /// Thread-safe via internal Mutex. All operations acquire the lock.
@@ -104,10 +148,16 @@ impl SqliteStore {
 
         self.ensure_machine_space_hierarchy(&conn)?;
         self.ensure_session_agent_metadata_columns(&conn)?;
+        self.ensure_session_fork_columns(&conn)?;
 
         // Ensure runtime session state uses grouped JSON envelope schema
         self.ensure_session_state_runtime_envelope(&conn)?;
 
+        self.ensure_message_search_index(&conn)?;
+        self.ensure_message_queue(&conn)?;
+        self.ensure_process_records(&conn)?;
+        self.ensure_terminals(&conn)?;
+
         // Drop legacy outbox table if it exists
         conn.execute_batch("DROP TABLE IF EXISTS local_llm_conversation_event_outbox;")?;
 
@@ -364,6 +414,9 @@ impl SqliteStore {
                 status TEXT NOT NULL DEFAULT 'active',
                 is_worktree INTEGER NOT NULL DEFAULT 0,
                 worktree_path TEXT,
+                parent_session_id TEXT,
+                forked_from_sequence INTEGER,
+                permission_mode TEXT,
                 created_at TEXT NOT NULL DEFAULT (datetime('now')),
                 last_accessed_at TEXT NOT NULL DEFAULT (datetime('now')),
                 updated_at TEXT NOT NULL DEFAULT (datetime('now'))
@@ -570,6 +623,11 @@ impl SqliteStore {
                 "ALTER TABLE local_llm_conversations ADD COLUMN provider_session_id TEXT;",
             )?;
         }
+        if !columns.iter().any(|c| c == "permission_mode") {
+            conn.execute_batch(
+                "ALTER TABLE local_llm_conversations ADD COLUMN permission_mode TEXT;",
+            )?;
+        }
 
         conn.execute(
             "UPDATE local_llm_conversations
@@ -595,6 +653,120 @@ impl SqliteStore {
         Ok(())
     }
 
+    /// Ensures sessions can record the session and sequence they were forked from.
+    fn ensure_session_fork_columns(&self, conn: &Connection) -> SqliteResult<()> {
+        if !Self::table_has_column(conn, "local_llm_conversations", "parent_session_id")? {
+            conn.execute_batch(
+                "ALTER TABLE local_llm_conversations ADD COLUMN parent_session_id TEXT;",
+            )?;
+        }
+        if !Self::table_has_column(conn, "local_llm_conversations", "forked_from_sequence")? {
+            conn.execute_batch(
+                "ALTER TABLE local_llm_conversations ADD COLUMN forked_from_sequence INTEGER;",
+            )?;
+        }
+
+        conn.execute_batch(
+            "
+            CREATE INDEX IF NOT EXISTS idx_sessions_parent_session_id
+                ON local_llm_conversations(parent_session_id);
+            ",
+        )?;
+
+        Ok(())
+    }
+
+    /// Ensures the FTS5 message search index exists, backfilling it from existing messages.
+    fn ensure_message_search_index(&self, conn: &Connection) -> SqliteResult<()> {
+        if Self::table_exists(conn, "local_llm_conversation_messages_fts")? {
+            return Ok(());
+        }
+
+        conn.execute_batch(
+            r#"
+            CREATE VIRTUAL TABLE local_llm_conversation_messages_fts USING fts5(
+                body,
+                message_id UNINDEXED,
+                session_id UNINDEXED,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            "#,
+        )?;
+        conn.execute(
+            &format!(
+                "INSERT INTO local_llm_conversation_messages_fts (body, message_id, session_id)
+                 SELECT {MESSAGE_SEARCH_BODY_SQL}, m.id, m.session_id
+                 FROM local_llm_conversation_messages m"
+            ),
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    /// Creates the per-session queue of messages waiting for a running turn.
+    fn ensure_message_queue(&self, conn: &Connection) -> SqliteResult<()> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS local_llm_conversation_queue (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL REFERENCES local_llm_conversations(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                request_json TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE(session_id, position)
+            );
+            "#,
+        )
+    }
+
+    /// Creates the table of child processes spawned for sessions.
+    fn ensure_process_records(&self, conn: &Connection) -> SqliteResult<()> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS local_llm_conversation_processes (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL REFERENCES local_llm_conversations(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL CHECK (kind IN ('agent', 'terminal')),
+                pid INTEGER NOT NULL,
+                command TEXT NOT NULL,
+                started_at TEXT NOT NULL
+            );
+            "#,
+        )
+    }
+
+    /// Creates the table of commands run in session terminals and the table
+    /// of their output chunks.
+    fn ensure_terminals(&self, conn: &Connection) -> SqliteResult<()> {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS local_llm_conversation_terminals (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL REFERENCES local_llm_conversations(id) ON DELETE CASCADE,
+                name TEXT,
+                command TEXT NOT NULL,
+                cwd TEXT NOT NULL,
+                pid INTEGER,
+                started_at TEXT NOT NULL,
+                finished_at TEXT,
+                exit_code INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_local_llm_conversation_terminals_session_id
+                ON local_llm_conversation_terminals(session_id);
+            CREATE TABLE IF NOT EXISTS local_llm_conversation_terminal_output (
+                terminal_id TEXT NOT NULL
+                    REFERENCES local_llm_conversation_terminals(id) ON DELETE CASCADE,
+                start_offset INTEGER NOT NULL,
+                byte_length INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                PRIMARY KEY (terminal_id, start_offset)
+            ) WITHOUT ROWID;
+            "#,
+        )
+    }
+
     /// Returns the current time as an RFC3339 string.
     fn now_rfc3339() -> String {
         Utc::now().to_rfc3339()
@@ -749,10 +921,18 @@ impl SqliteStore {
     /// Deletes a repository by ID.
     pub fn delete_repository(&self, id: &RepositoryId) -> SqliteResult<bool> {
         let conn = self.conn.lock().expect("lock poisoned");
-        let count = conn.execute(
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM local_llm_conversation_messages_fts WHERE session_id IN (
+                SELECT id FROM local_llm_conversations WHERE repository_id = ?1
+            )",
+            params![id.as_str()],
+        )?;
+        let count = tx.execute(
             "DELETE FROM local_repositories WHERE id = ?1",
             params![id.as_str()],
         )?;
+        tx.commit()?;
         Ok(count > 0)
     }
 
@@ -823,7 +1003,7 @@ impl SqliteStore {
     pub fn get_agent_session(&self, id: &SessionId) -> SqliteResult<Option<Session>> {
         let conn = self.conn.lock().expect("lock poisoned");
         let mut stmt = conn.prepare_cached(
-            "SELECT id, repository_id, machine_id, space_id, title, agent_name, issue_id, issue_title, issue_url, provider, provider_session_id, claude_session_id, status, is_worktree, worktree_path, created_at, last_accessed_at, updated_at
+            "SELECT id, repository_id, machine_id, space_id, title, agent_name, issue_id, issue_title, issue_url, provider, provider_session_id, claude_session_id, status, is_worktree, worktree_path, parent_session_id, forked_from_sequence, created_at, last_accessed_at, updated_at, permission_mode
              FROM local_llm_conversations WHERE id = ?1",
         )?;
 
@@ -844,9 +1024,14 @@ impl SqliteStore {
                 status: SessionStatus::from_str(&row.get::<_, String>(12)?),
                 is_worktree: row.get(13)?,
                 worktree_path: row.get(14)?,
-                created_at: Self::parse_datetime(row.get::<_, String>(15)?),
-                last_accessed_at: Self::parse_datetime(row.get::<_, String>(16)?),
-                updated_at: Self::parse_datetime(row.get::<_, String>(17)?),
+                parent_session_id: row
+                    .get::<_, Option<String>>(15)?
+                    .map(SessionId::from_string),
+                forked_from_sequence: row.get(16)?,
+                permission_mode: row.get(20)?,
+                created_at: Self::parse_datetime(row.get::<_, String>(17)?),
+                last_accessed_at: Self::parse_datetime(row.get::<_, String>(18)?),
+                updated_at: Self::parse_datetime(row.get::<_, String>(19)?),
             })
         });
 
@@ -864,7 +1049,7 @@ impl SqliteStore {
     ) -> SqliteResult<Vec<Session>> {
         let conn = self.conn.lock().expect("lock poisoned");
         let mut stmt = conn.prepare_cached(
-            "SELECT id, repository_id, machine_id, space_id, title, agent_name, issue_id, issue_title, issue_url, provider, provider_session_id, claude_session_id, status, is_worktree, worktree_path, created_at, last_accessed_at, updated_at
+            "SELECT id, repository_id, machine_id, space_id, title, agent_name, issue_id, issue_title, issue_url, provider, provider_session_id, claude_session_id, status, is_worktree, worktree_path, parent_session_id, forked_from_sequence, created_at, last_accessed_at, updated_at, permission_mode
              FROM local_llm_conversations WHERE repository_id = ?1 ORDER BY last_accessed_at DESC",
         )?;
 
@@ -886,9 +1071,14 @@ impl SqliteStore {
                     status: SessionStatus::from_str(&row.get::<_, String>(12)?),
                     is_worktree: row.get(13)?,
                     worktree_path: row.get(14)?,
-                    created_at: Self::parse_datetime(row.get::<_, String>(15)?),
-                    last_accessed_at: Self::parse_datetime(row.get::<_, String>(16)?),
-                    updated_at: Self::parse_datetime(row.get::<_, String>(17)?),
+                    parent_session_id: row
+                        .get::<_, Option<String>>(15)?
+                        .map(SessionId::from_string),
+                    forked_from_sequence: row.get(16)?,
+                    permission_mode: row.get(20)?,
+                    created_at: Self::parse_datetime(row.get::<_, String>(17)?),
+                    last_accessed_at: Self::parse_datetime(row.get::<_, String>(18)?),
+                    updated_at: Self::parse_datetime(row.get::<_, String>(19)?),
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -896,40 +1086,34 @@ impl SqliteStore {
         Ok(sessions)
     }
 
-    /// Lists all agent sessions (for recovery).
-    pub fn list_all_agent_sessions(&self) -> SqliteResult<Vec<Session>> {
+    /// Lists every session with the position of its last message (for recovery).
+    ///
+    /// Reads no message content, so the cost is independent of transcript size.
+    pub fn list_agent_session_cursors(&self) -> SqliteResult<Vec<SessionCursorRow>> {
         let conn = self.conn.lock().expect("lock poisoned");
         let mut stmt = conn.prepare_cached(
-            "SELECT id, repository_id, machine_id, space_id, title, agent_name, issue_id, issue_title, issue_url, provider, provider_session_id, claude_session_id, status, is_worktree, worktree_path, created_at, last_accessed_at, updated_at
-             FROM local_llm_conversations ORDER BY last_accessed_at DESC",
+            r#"
+            SELECT s.id, s.status,
+                (SELECT m.id FROM local_llm_conversation_messages m
+                    WHERE m.session_id = s.id ORDER BY m.sequence_number DESC LIMIT 1),
+                (SELECT COALESCE(MAX(m.sequence_number), 0) FROM local_llm_conversation_messages m
+                    WHERE m.session_id = s.id)
+            FROM local_llm_conversations s
+            "#,
         )?;
 
-        let sessions = stmt
+        let cursors = stmt
             .query_map([], |row| {
-                Ok(Session {
+                Ok(SessionCursorRow {
                     id: SessionId::from_string(row.get::<_, String>(0)?),
-                    repository_id: RepositoryId::from_string(row.get::<_, String>(1)?),
-                    machine_id: row.get(2)?,
-                    space_id: row.get(3)?,
-                    title: row.get(4)?,
-                    agent_name: row.get(5)?,
-                    issue_id: row.get(6)?,
-                    issue_title: row.get(7)?,
-                    issue_url: row.get(8)?,
-                    provider: row.get(9)?,
-                    provider_session_id: row.get(10)?,
-                    claude_session_id: row.get(11)?,
-                    status: SessionStatus::from_str(&row.get::<_, String>(12)?),
-                    is_worktree: row.get(13)?,
-                    worktree_path: row.get(14)?,
-                    created_at: Self::parse_datetime(row.get::<_, String>(15)?),
-                    last_accessed_at: Self::parse_datetime(row.get::<_, String>(16)?),
-                    updated_at: Self::parse_datetime(row.get::<_, String>(17)?),
+                    status: SessionStatus::from_str(&row.get::<_, String>(1)?),
+                    last_message_id: row.get::<_, Option<String>>(2)?.map(MessageId::from_string),
+                    last_sequence: row.get(3)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(sessions)
+        Ok(cursors)
     }
 
     /// Updates a session with partial fields.
@@ -960,6 +1144,10 @@ impl SqliteStore {
             updates.push(format!("last_accessed_at = ?{}", param_index));
             param_index += 1;
         }
+        if update.permission_mode.is_some() {
+            updates.push(format!("permission_mode = ?{}", param_index));
+            param_index += 1;
+        }
 
         let sql = format!(
             "UPDATE local_llm_conversations SET {} WHERE id = ?{}",
@@ -981,6 +1169,9 @@ impl SqliteStore {
         if let Some(last_accessed) = update.last_accessed_at {
             params_vec.push(Box::new(last_accessed.to_rfc3339()));
         }
+        if let Some(ref permission_mode) = update.permission_mode {
+            params_vec.push(Box::new(permission_mode.clone()));
+        }
         params_vec.push(Box::new(id.as_str().to_string()));
 
         let params_refs: Vec<&dyn rusqlite::ToSql> =
@@ -1036,13 +1227,230 @@ impl SqliteStore {
     /// Deletes an agent session by ID.
     pub fn delete_agent_session(&self, id: &SessionId) -> SqliteResult<bool> {
         let conn = self.conn.lock().expect("lock poisoned");
-        let count = conn.execute(
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM local_llm_conversation_messages_fts WHERE session_id = ?1",
+            params![id.as_str()],
+        )?;
+        tx.execute(
+            "DELETE FROM local_llm_conversation_queue WHERE session_id = ?1",
+            params![id.as_str()],
+        )?;
+        let count = tx.execute(
             "DELETE FROM local_llm_conversations WHERE id = ?1",
             params![id.as_str()],
         )?;
+        tx.commit()?;
         Ok(count > 0)
     }
 
+    /// Inserts `fork` as a child of `source` and copies the source's messages.
+    ///
+    /// Messages with sequence numbers up to and including `up_to_sequence` are
+    /// copied in order under fresh IDs and renumbered from 1, keeping their
+    /// original timestamps. The session row, messages, and search index entries
+    /// are written in a single transaction.
+    pub fn fork_agent_session(
+        &self,
+        source: &SessionId,
+        up_to_sequence: i64,
+        fork: &NewSession,
+    ) -> SqliteResult<SeededSession> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let now = Self::now_rfc3339();
+        let tx = conn.unchecked_transaction()?;
+
+        Self::insert_seeded_session_row(
+            &tx,
+            fork,
+            Some((source, up_to_sequence)),
+            SessionStatus::Active,
+            &now,
+            &now,
+        )?;
+
+        let mut last_message_id = None;
+        let mut last_sequence = 0;
+        let mut after_sequence = 0;
+        loop {
+            let page: Vec<(i64, String, String, String)> = tx
+                .prepare_cached(
+                    "SELECT sequence_number, content, timestamp, created_at
+                     FROM local_llm_conversation_messages
+                     WHERE session_id = ?1 AND sequence_number > ?2 AND sequence_number <= ?3
+                     ORDER BY sequence_number LIMIT ?4",
+                )?
+                .query_map(
+                    params![
+                        source.as_str(),
+                        after_sequence,
+                        up_to_sequence,
+                        FORK_COPY_PAGE_SIZE
+                    ],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )?
+                .collect::<Result<Vec<_>, _>>()?;
+            let Some((page_last_sequence, ..)) = page.last() else {
+                break;
+            };
+            after_sequence = *page_last_sequence;
+            let full_page = page.len() as i64 == FORK_COPY_PAGE_SIZE;
+
+            for (_, content, timestamp, created_at) in page {
+                last_sequence += 1;
+                last_message_id = Some(Self::insert_seeded_message(
+                    &tx,
+                    &fork.id,
+                    last_sequence,
+                    &content,
+                    &timestamp,
+                    &created_at,
+                )?);
+            }
+
+            if !full_page {
+                break;
+            }
+        }
+
+        tx.commit()?;
+        drop(conn);
+
+        let session = self
+            .get_agent_session(&fork.id)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+        Ok(SeededSession {
+            session,
+            last_message_id,
+            last_sequence,
+        })
+    }
+
+    /// Inserts `session` together with `messages`, numbered from 1 in the given order.
+    ///
+    /// Used to restore exported sessions, so the session keeps its status and
+    /// creation time and each message keeps its timestamp. The session row,
+    /// messages, and search index entries are written in a single transaction.
+    pub fn import_agent_session(
+        &self,
+        session: &NewSession,
+        history: SessionHistory,
+        messages: &[ImportedMessage],
+    ) -> SqliteResult<SeededSession> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let now = Self::now_rfc3339();
+        let tx = conn.unchecked_transaction()?;
+
+        Self::insert_seeded_session_row(
+            &tx,
+            session,
+            None,
+            history.status,
+            &history.created_at.to_rfc3339(),
+            &now,
+        )?;
+
+        let mut last_message_id = None;
+        let mut last_sequence = 0;
+        for message in messages {
+            last_sequence += 1;
+            let timestamp = message.timestamp.to_rfc3339();
+            last_message_id = Some(Self::insert_seeded_message(
+                &tx,
+                &session.id,
+                last_sequence,
+                &message.content,
+                &timestamp,
+                &timestamp,
+            )?);
+        }
+
+        tx.commit()?;
+        drop(conn);
+
+        let session = self
+            .get_agent_session(&session.id)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+        Ok(SeededSession {
+            session,
+            last_message_id,
+            last_sequence,
+        })
+    }
+
+    /// Inserts the row for a session created with messages, optionally linked to a parent.
+    fn insert_seeded_session_row(
+        conn: &Connection,
+        session: &NewSession,
+        parent: Option<(&SessionId, i64)>,
+        status: SessionStatus,
+        created_at: &str,
+        now: &str,
+    ) -> SqliteResult<()> {
+        conn.execute(
+            "INSERT INTO local_llm_conversations (
+                id, repository_id, machine_id, space_id, title, agent_name, issue_id,
+                issue_title, issue_url, provider, provider_session_id, claude_session_id, status,
+                is_worktree, worktree_path, parent_session_id, forked_from_sequence,
+                created_at, last_accessed_at, updated_at
+             )
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?19)",
+            params![
+                session.id.as_str(),
+                session.repository_id.as_str(),
+                session.machine_id,
+                session.space_id,
+                session.title,
+                session.agent_name,
+                session.issue_id,
+                session.issue_title,
+                session.issue_url,
+                session.provider,
+                session.provider_session_id,
+                session.claude_session_id,
+                status.as_str(),
+                session.is_worktree,
+                session.worktree_path,
+                parent.map(|(id, _)| id.as_str()),
+                parent.map(|(_, sequence)| sequence),
+                created_at,
+                now,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Inserts one message at an explicit sequence number and indexes it for search.
+    fn insert_seeded_message(
+        conn: &Connection,
+        session: &SessionId,
+        sequence_number: i64,
+        content: &str,
+        timestamp: &str,
+        created_at: &str,
+    ) -> SqliteResult<MessageId> {
+        let id = MessageId::new();
+        conn.prepare_cached(
+            "INSERT INTO local_llm_conversation_messages (id, session_id, content, timestamp, is_streaming, sequence_number, created_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)",
+        )?
+        .execute(params![
+            id.as_str(),
+            session.as_str(),
+            content,
+            timestamp,
+            sequence_number,
+            created_at
+        ])?;
+        conn.prepare_cached(&format!(
+            "INSERT INTO local_llm_conversation_messages_fts (body, message_id, session_id)
+             SELECT {MESSAGE_SEARCH_BODY_SQL}, m.id, m.session_id
+             FROM local_llm_conversation_messages m WHERE m.id = ?1"
+        ))?
+        .execute(params![id.as_str()])?;
+        Ok(id)
+    }
+
     /// Checks if an agent session exists.
     #[allow(dead_code)]
     pub fn agent_session_exists(&self, id: &SessionId) -> SqliteResult<bool> {
@@ -1105,7 +1513,15 @@ impl SqliteStore {
                 json_extract(state_json, '$.coding_session.status') AS coding_session_status,
                 json_extract(state_json, '$.coding_session.error_message') AS error_message,
                 json_extract(state_json, '$.device_id') AS device_id,
-                updated_at_ms
+                updated_at_ms,
+                json_extract(state_json, '$.coding_session.question.tool_use_id')
+                    AS question_tool_use_id,
+                json_extract(state_json, '$.coding_session.question.resolved')
+                    AS question_resolved,
+                (SELECT COUNT(*) FROM local_llm_conversation_queue q
+                 WHERE q.session_id = local_llm_conversation_state.session_id) AS queue_length,
+                json_extract(state_json, '$.coding_session.scheduler_position')
+                    AS scheduler_position
              FROM local_llm_conversation_state WHERE session_id = ?1",
         )?;
 
@@ -1115,13 +1531,24 @@ impl SqliteStore {
             let error_message: Option<String> = row.get(2)?;
             let device_id: Option<String> = row.get(3)?;
             let updated_at_ms: i64 = row.get(4)?;
+            let question_tool_use_id: Option<String> = row.get(5)?;
+            let question_resolved: Option<bool> = row.get(6)?;
+            let queue_length: i64 = row.get(7)?;
+            let scheduler_position: Option<i64> = row.get(8)?;
             let status = CodingSessionStatus::from_str(raw_status.as_deref().unwrap_or("idle"));
+            let question = question_tool_use_id.map(|tool_use_id| QuestionRuntimeState {
+                tool_use_id,
+                resolved: question_resolved.unwrap_or(false),
+            });
 
             let runtime_status = RuntimeStatusEnvelope {
                 schema_version: RUNTIME_STATUS_SCHEMA_VERSION,
                 coding_session: CodingSessionRuntimeState {
                     status,
                     error_message,
+                    question,
+                    queue_length,
+                    scheduler_position,
                 },
                 device_id: device_id.unwrap_or_else(|| Self::DEFAULT_RUNTIME_DEVICE_ID.to_string()),
                 session_id: session_id.clone(),
@@ -1224,6 +1651,97 @@ impl SqliteStore {
         Ok(count > 0)
     }
 
+    /// Records the session's latest structured question, or clears it.
+    ///
+    /// The runtime status is left untouched.
+    pub fn update_runtime_question(
+        &self,
+        session_id: &SessionId,
+        device_id: &str,
+        question: Option<&QuestionRuntimeState>,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let now_ms = Self::now_timestamp_ms();
+        let count = if let Some(question) = question {
+            conn.execute(
+                "UPDATE local_llm_conversation_state
+                 SET
+                    state_json = json_set(
+                        state_json,
+                        '$.coding_session.question',
+                        json_object('tool_use_id', ?4, 'resolved', json(?5)),
+                        '$.device_id', ?2,
+                        '$.updated_at_ms', ?3
+                    ),
+                    updated_at_ms = ?3
+                 WHERE session_id = ?1 AND state_json IS NOT NULL",
+                params![
+                    session_id.as_str(),
+                    device_id,
+                    now_ms,
+                    question.tool_use_id,
+                    if question.resolved { "true" } else { "false" }
+                ],
+            )?
+        } else {
+            conn.execute(
+                "UPDATE local_llm_conversation_state
+                 SET
+                    state_json = json_set(
+                        json_remove(state_json, '$.coding_session.question'),
+                        '$.device_id', ?2,
+                        '$.updated_at_ms', ?3
+                    ),
+                    updated_at_ms = ?3
+                 WHERE session_id = ?1 AND state_json IS NOT NULL",
+                params![session_id.as_str(), device_id, now_ms],
+            )?
+        };
+
+        Ok(count > 0)
+    }
+
+    /// Sets (or clears) the session's agent scheduler position.
+    pub fn update_runtime_scheduler_position(
+        &self,
+        session_id: &SessionId,
+        device_id: &str,
+        position: Option<i64>,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let now_ms = Self::now_timestamp_ms();
+        let count = if let Some(position) = position {
+            conn.execute(
+                "UPDATE local_llm_conversation_state
+                 SET
+                    state_json = json_set(
+                        state_json,
+                        '$.coding_session.scheduler_position', ?4,
+                        '$.device_id', ?2,
+                        '$.updated_at_ms', ?3
+                    ),
+                    updated_at_ms = ?3
+                 WHERE session_id = ?1 AND state_json IS NOT NULL",
+                params![session_id.as_str(), device_id, now_ms, position],
+            )?
+        } else {
+            conn.execute(
+                "UPDATE local_llm_conversation_state
+                 SET
+                    state_json = json_set(
+                        json_remove(state_json, '$.coding_session.scheduler_position'),
+                        '$.device_id', ?2,
+                        '$.updated_at_ms', ?3
+                    ),
+                    updated_at_ms = ?3
+                 WHERE session_id = ?1 AND state_json IS NOT NULL",
+                params![session_id.as_str(), device_id, now_ms],
+            )?
+        };
+
+        Ok(count > 0)
+    }
+
     /// Legacy scalar status writer kept during migration.
     #[allow(dead_code)]
     pub fn update_agent_status(
@@ -1239,6 +1757,8 @@ impl SqliteStore {
     // ========================================================================
 
     /// Inserts a message into the agent messages table with atomic sequence assignment.
+    ///
+    /// The message is added to the search index in the same transaction.
     pub fn insert_agent_message(
         &self,
         session: &SessionId,
@@ -1247,9 +1767,10 @@ impl SqliteStore {
         let id = MessageId::new();
         let conn = self.conn.lock().expect("lock poisoned");
         let now = Self::now_rfc3339();
+        let tx = conn.unchecked_transaction()?;
 
         // Atomic insert with sequence number computed in subquery
-        let mut stmt = conn.prepare(
+        let mut stmt = tx.prepare_cached(
             r#"
             INSERT INTO local_llm_conversation_messages (id, session_id, content, timestamp, is_streaming, sequence_number, created_at)
             VALUES (?1, ?2, ?3, ?4, 0,
@@ -1263,6 +1784,15 @@ impl SqliteStore {
             params![id.as_str(), session.as_str(), msg.content, now],
             |row| row.get(0),
         )?;
+        drop(stmt);
+
+        tx.prepare_cached(&format!(
+            "INSERT INTO local_llm_conversation_messages_fts (body, message_id, session_id)
+             SELECT {MESSAGE_SEARCH_BODY_SQL}, m.id, m.session_id
+             FROM local_llm_conversation_messages m WHERE m.id = ?1"
+        ))?
+        .execute(params![id.as_str()])?;
+        tx.commit()?;
 
         Ok(InsertedMessage {
             id,
@@ -1270,7 +1800,19 @@ impl SqliteStore {
         })
     }
 
+    /// Returns the highest message sequence number in a session (0 if it has none).
+    pub fn last_agent_message_sequence(&self, session: &SessionId) -> SqliteResult<i64> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        conn.query_row(
+            "SELECT COALESCE(MAX(sequence_number), 0) FROM local_llm_conversation_messages
+             WHERE session_id = ?1",
+            params![session.as_str()],
+            |row| row.get(0),
+        )
+    }
+
     /// Gets all messages for a session from the agent messages table.
+    #[cfg(test)]
     pub fn get_agent_messages(&self, session: &SessionId) -> SqliteResult<Vec<Message>> {
         let conn = self.conn.lock().expect("lock poisoned");
         let mut stmt = conn.prepare(
@@ -1294,19 +1836,553 @@ impl SqliteStore {
         Ok(messages)
     }
 
-    // ========================================================================
-    // Session secrets operations
-    // ========================================================================
-
-    /// Gets a session secret by session ID.
-    pub fn get_session_secret(
+    /// Gets one page of messages for a session using keyset pagination.
+    ///
+    /// Returns at most `limit` messages with
+    /// `after_sequence < sequence_number <= up_to_sequence`, ordered by sequence number.
+    /// Callers continue from the last returned sequence number.
+    pub fn get_agent_messages_page(
         &self,
-        session_id: &SessionId,
-    ) -> SqliteResult<Option<SessionSecret>> {
+        session: &SessionId,
+        after_sequence: i64,
+        up_to_sequence: i64,
+        limit: usize,
+    ) -> SqliteResult<Vec<Message>> {
         let conn = self.conn.lock().expect("lock poisoned");
         let mut stmt = conn.prepare_cached(
-            "SELECT session_id, encrypted_secret, nonce, created_at
-             FROM local_llm_conversation_secrets WHERE session_id = ?1",
+            "SELECT id, content, sequence_number FROM local_llm_conversation_messages
+             WHERE session_id = ?1 AND sequence_number > ?2 AND sequence_number <= ?3
+             ORDER BY sequence_number LIMIT ?4",
+        )?;
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let messages = stmt
+            .query_map(
+                params![session.as_str(), after_sequence, up_to_sequence, limit],
+                |row| {
+                    Ok(Message {
+                        id: MessageId::from_string(row.get::<_, String>(0)?),
+                        content: row.get(1)?,
+                        sequence_number: row.get(2)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(messages)
+    }
+
+    /// Gets one page of messages for a session with the time each was written.
+    ///
+    /// Pages like [`Self::get_agent_messages_page`], without an upper bound.
+    pub fn get_agent_timestamped_messages_page(
+        &self,
+        session: &SessionId,
+        after_sequence: i64,
+        limit: usize,
+    ) -> SqliteResult<Vec<TimestampedMessage>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, content, sequence_number, timestamp FROM local_llm_conversation_messages
+             WHERE session_id = ?1 AND sequence_number > ?2
+             ORDER BY sequence_number LIMIT ?3",
+        )?;
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let messages = stmt
+            .query_map(params![session.as_str(), after_sequence, limit], |row| {
+                Ok(TimestampedMessage {
+                    message: Message {
+                        id: MessageId::from_string(row.get::<_, String>(0)?),
+                        content: row.get(1)?,
+                        sequence_number: row.get(2)?,
+                    },
+                    timestamp: Self::parse_datetime(row.get(3)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(messages)
+    }
+
+    /// Runs a full-text search over indexed messages, best matches first.
+    ///
+    /// Returns an empty list when the query text has no searchable words.
+    pub fn search_agent_messages(
+        &self,
+        query: &MessageSearchQuery,
+    ) -> SqliteResult<Vec<MessageSearchHit>> {
+        let Some(match_expression) = fts_match_expression(&query.text) else {
+            return Ok(Vec::new());
+        };
+
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(
+            "SELECT s.id, s.repository_id, s.title, m.id, m.sequence_number,
+                    snippet(local_llm_conversation_messages_fts, 0, '[', ']', '…', 16),
+                    bm25(local_llm_conversation_messages_fts), m.created_at
+             FROM local_llm_conversation_messages_fts
+             JOIN local_llm_conversation_messages m
+                 ON m.id = local_llm_conversation_messages_fts.message_id
+             JOIN local_llm_conversations s ON s.id = m.session_id
+             WHERE local_llm_conversation_messages_fts MATCH ?1
+               AND (?2 IS NULL OR s.repository_id = ?2)
+               AND (?3 IS NULL OR m.created_at >= ?3)
+               AND (?4 IS NULL OR m.created_at <= ?4)
+             ORDER BY bm25(local_llm_conversation_messages_fts), m.created_at DESC
+             LIMIT ?5 OFFSET ?6",
+        )?;
+        let limit = i64::try_from(query.limit).unwrap_or(i64::MAX);
+        let offset = i64::try_from(query.offset).unwrap_or(i64::MAX);
+        let hits = stmt
+            .query_map(
+                params![
+                    match_expression,
+                    query.repository_id.as_ref().map(|id| id.as_str()),
+                    query.since.map(|since| since.to_rfc3339()),
+                    query.until.map(|until| until.to_rfc3339()),
+                    limit,
+                    offset,
+                ],
+                |row| {
+                    Ok(MessageSearchHit {
+                        session_id: SessionId::from_string(row.get::<_, String>(0)?),
+                        repository_id: RepositoryId::from_string(row.get::<_, String>(1)?),
+                        session_title: row.get(2)?,
+                        message_id: MessageId::from_string(row.get::<_, String>(3)?),
+                        sequence_number: row.get(4)?,
+                        snippet: row.get(5)?,
+                        rank: row.get(6)?,
+                        created_at: Self::parse_datetime(row.get(7)?),
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(hits)
+    }
+
+    // ========================================================================
+    // Message queue operations
+    // ========================================================================
+
+    /// Adds a message to the end of a session's queue.
+    pub fn enqueue_message(
+        &self,
+        session_id: &SessionId,
+        message: &NewQueuedMessage,
+    ) -> SqliteResult<QueuedMessage> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Self::now_rfc3339();
+        conn.execute(
+            "INSERT INTO local_llm_conversation_queue
+                (id, session_id, position, content, request_json, created_at)
+             VALUES (
+                ?1, ?2,
+                (SELECT COALESCE(MAX(position), 0) + 1
+                 FROM local_llm_conversation_queue WHERE session_id = ?2),
+                ?3, ?4, ?5
+             )",
+            params![
+                id,
+                session_id.as_str(),
+                message.content,
+                message.request_json,
+                now
+            ],
+        )?;
+        Ok(QueuedMessage {
+            id,
+            session_id: session_id.clone(),
+            content: message.content.clone(),
+            request_json: message.request_json.clone(),
+            created_at: Self::parse_datetime(now),
+        })
+    }
+
+    /// Lists a session's queued messages, oldest first.
+    pub fn list_queued_messages(&self, session_id: &SessionId) -> SqliteResult<Vec<QueuedMessage>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, content, request_json, created_at
+             FROM local_llm_conversation_queue
+             WHERE session_id = ?1
+             ORDER BY position",
+        )?;
+        let rows = stmt.query_map(params![session_id.as_str()], Self::queued_message_from_row)?;
+        rows.collect()
+    }
+
+    /// Removes and returns the oldest queued message for a session.
+    pub fn pop_queued_message(
+        &self,
+        session_id: &SessionId,
+    ) -> SqliteResult<Option<QueuedMessage>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let tx = conn.unchecked_transaction()?;
+        let result = tx.query_row(
+            "SELECT id, session_id, content, request_json, created_at
+             FROM local_llm_conversation_queue
+             WHERE session_id = ?1
+             ORDER BY position
+             LIMIT 1",
+            params![session_id.as_str()],
+            Self::queued_message_from_row,
+        );
+        let message = match result {
+            Ok(message) => message,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        tx.execute(
+            "DELETE FROM local_llm_conversation_queue WHERE id = ?1",
+            params![message.id],
+        )?;
+        tx.commit()?;
+        Ok(Some(message))
+    }
+
+    /// Removes one queued message. Returns false if it was not queued.
+    pub fn remove_queued_message(&self, session_id: &SessionId, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let count = conn.execute(
+            "DELETE FROM local_llm_conversation_queue WHERE session_id = ?1 AND id = ?2",
+            params![session_id.as_str(), id],
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Removes every queued message for a session, returning how many there were.
+    pub fn clear_queued_messages(&self, session_id: &SessionId) -> SqliteResult<usize> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        conn.execute(
+            "DELETE FROM local_llm_conversation_queue WHERE session_id = ?1",
+            params![session_id.as_str()],
+        )
+    }
+
+    /// Sessions that have at least one queued message.
+    pub fn sessions_with_queued_messages(&self) -> SqliteResult<Vec<SessionId>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(
+            "SELECT DISTINCT session_id FROM local_llm_conversation_queue ORDER BY session_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(SessionId::from_string(row.get::<_, String>(0)?))
+        })?;
+        rows.collect()
+    }
+
+    fn queued_message_from_row(row: &rusqlite::Row<'_>) -> SqliteResult<QueuedMessage> {
+        Ok(QueuedMessage {
+            id: row.get(0)?,
+            session_id: SessionId::from_string(row.get::<_, String>(1)?),
+            content: row.get(2)?,
+            request_json: row.get(3)?,
+            created_at: Self::parse_datetime(row.get::<_, String>(4)?),
+        })
+    }
+
+    /// Sessions whose runtime status is one of `statuses`.
+    pub fn sessions_with_runtime_status(
+        &self,
+        statuses: &[CodingSessionStatus],
+    ) -> SqliteResult<Vec<SessionId>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(
+            "SELECT session_id FROM local_llm_conversation_state
+             WHERE json_extract(state_json, '$.coding_session.status') = ?1
+             ORDER BY session_id",
+        )?;
+        let mut sessions = Vec::new();
+        for status in statuses {
+            let rows = stmt.query_map(params![status.as_str()], |row| {
+                Ok(SessionId::from_string(row.get::<_, String>(0)?))
+            })?;
+            for session in rows {
+                sessions.push(session?);
+            }
+        }
+        Ok(sessions)
+    }
+
+    // ========================================================================
+    // Process record operations
+    // ========================================================================
+
+    /// Records a process spawned for a session.
+    pub fn record_process(
+        &self,
+        session_id: &SessionId,
+        process: &NewProcessRecord,
+    ) -> SqliteResult<ProcessRecord> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Self::now_rfc3339();
+        conn.execute(
+            "INSERT INTO local_llm_conversation_processes
+                (id, session_id, kind, pid, command, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id,
+                session_id.as_str(),
+                process.kind.as_str(),
+                process.pid,
+                process.command,
+                now
+            ],
+        )?;
+        Ok(ProcessRecord {
+            id,
+            session_id: session_id.clone(),
+            kind: process.kind,
+            pid: process.pid,
+            command: process.command.clone(),
+            started_at: Self::parse_datetime(now),
+        })
+    }
+
+    /// Removes a process record. Returns false if it did not exist.
+    pub fn remove_process_record(&self, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let count = conn.execute(
+            "DELETE FROM local_llm_conversation_processes WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Lists every recorded process, oldest first.
+    pub fn list_process_records(&self) -> SqliteResult<Vec<ProcessRecord>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, kind, pid, command, started_at
+             FROM local_llm_conversation_processes
+             ORDER BY started_at, id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let raw_kind: String = row.get(2)?;
+            let kind = ProcessKind::parse(&raw_kind).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    2,
+                    rusqlite::types::Type::Text,
+                    format!("unknown process kind: {raw_kind}").into(),
+                )
+            })?;
+            Ok(ProcessRecord {
+                id: row.get(0)?,
+                session_id: SessionId::from_string(row.get::<_, String>(1)?),
+                kind,
+                pid: row.get(3)?,
+                command: row.get(4)?,
+                started_at: Self::parse_datetime(row.get::<_, String>(5)?),
+            })
+        })?;
+        rows.collect()
+    }
+
+    // ========================================================================
+    // Terminal operations
+    // ========================================================================
+
+    /// Records a terminal command started for a session.
+    pub fn create_terminal(
+        &self,
+        session_id: &SessionId,
+        terminal: &NewTerminal,
+    ) -> SqliteResult<Terminal> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Self::now_rfc3339();
+        conn.execute(
+            "INSERT INTO local_llm_conversation_terminals
+                (id, session_id, name, command, cwd, pid, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                id,
+                session_id.as_str(),
+                terminal.name,
+                terminal.command,
+                terminal.cwd,
+                terminal.pid,
+                now
+            ],
+        )?;
+        Ok(Terminal {
+            id,
+            session_id: session_id.clone(),
+            name: terminal.name.clone(),
+            command: terminal.command.clone(),
+            cwd: terminal.cwd.clone(),
+            pid: terminal.pid,
+            started_at: Self::parse_datetime(now),
+            finished_at: None,
+            exit_code: None,
+        })
+    }
+
+    /// Marks a terminal's command as finished. Returns false if the terminal
+    /// does not exist or already finished.
+    pub fn finish_terminal(&self, id: &str, exit_code: Option<i32>) -> SqliteResult<bool> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let count = conn.execute(
+            "UPDATE local_llm_conversation_terminals
+             SET finished_at = ?2, exit_code = ?3
+             WHERE id = ?1 AND finished_at IS NULL",
+            params![id, Self::now_rfc3339(), exit_code],
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Gets a terminal by ID.
+    pub fn get_terminal(&self, id: &str) -> SqliteResult<Option<Terminal>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let result = conn.query_row(
+            &format!("{TERMINAL_COLUMNS} WHERE id = ?1"),
+            params![id],
+            Self::terminal_from_row,
+        );
+
+        match result {
+            Ok(terminal) => Ok(Some(terminal)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Lists a session's terminals, oldest first.
+    pub fn list_terminals(&self, session_id: &SessionId) -> SqliteResult<Vec<Terminal>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(&format!(
+            "{TERMINAL_COLUMNS} WHERE session_id = ?1 ORDER BY started_at, id"
+        ))?;
+        let rows = stmt.query_map(params![session_id.as_str()], Self::terminal_from_row)?;
+        rows.collect()
+    }
+
+    /// Lists the terminals of every session that have not finished, oldest
+    /// first.
+    pub fn list_unfinished_terminals(&self) -> SqliteResult<Vec<Terminal>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(&format!(
+            "{TERMINAL_COLUMNS} WHERE finished_at IS NULL ORDER BY started_at, id"
+        ))?;
+        let rows = stmt.query_map([], Self::terminal_from_row)?;
+        rows.collect()
+    }
+
+    /// Appends a chunk of a terminal's output and returns its offset.
+    ///
+    /// Chunks that end more than `scrollback_bytes` before the new end of
+    /// the output are dropped, so each terminal keeps a bounded tail.
+    pub fn append_terminal_output(
+        &self,
+        terminal_id: &str,
+        content: &str,
+        scrollback_bytes: u64,
+    ) -> SqliteResult<u64> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let tx = conn.unchecked_transaction()?;
+        let offset: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(start_offset + byte_length), 0)
+             FROM local_llm_conversation_terminal_output WHERE terminal_id = ?1",
+            params![terminal_id],
+            |row| row.get(0),
+        )?;
+        let length = content.len() as i64;
+        tx.execute(
+            "INSERT INTO local_llm_conversation_terminal_output
+                (terminal_id, start_offset, byte_length, content)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![terminal_id, offset, length, content],
+        )?;
+        let retained_from = offset + length - i64::try_from(scrollback_bytes).unwrap_or(i64::MAX);
+        if retained_from > 0 {
+            tx.execute(
+                "DELETE FROM local_llm_conversation_terminal_output
+                 WHERE terminal_id = ?1 AND start_offset + byte_length <= ?2",
+                params![terminal_id, retained_from],
+            )?;
+        }
+        tx.commit()?;
+        Ok(offset as u64)
+    }
+
+    /// Reads at most `limit` chunks of a terminal's retained output, starting
+    /// with the chunk that contains `offset`.
+    pub fn read_terminal_output(
+        &self,
+        terminal_id: &str,
+        offset: u64,
+        limit: usize,
+    ) -> SqliteResult<TerminalOutputPage> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let (start_offset, end_offset): (Option<i64>, Option<i64>) = conn.query_row(
+            "SELECT MIN(start_offset), MAX(start_offset + byte_length)
+             FROM local_llm_conversation_terminal_output WHERE terminal_id = ?1",
+            params![terminal_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT start_offset, content FROM local_llm_conversation_terminal_output
+             WHERE terminal_id = ?1 AND start_offset + byte_length > ?2
+             ORDER BY start_offset LIMIT ?3",
+        )?;
+        let chunks = stmt
+            .query_map(
+                params![
+                    terminal_id,
+                    i64::try_from(offset).unwrap_or(i64::MAX),
+                    i64::try_from(limit).unwrap_or(i64::MAX)
+                ],
+                |row| {
+                    Ok(TerminalOutputChunk {
+                        offset: row.get::<_, i64>(0)? as u64,
+                        content: row.get(1)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let start_offset = start_offset.unwrap_or(0) as u64;
+        let next_offset = chunks
+            .last()
+            .map(|chunk| chunk.offset + chunk.content.len() as u64)
+            .unwrap_or(offset.max(start_offset));
+        Ok(TerminalOutputPage {
+            chunks,
+            start_offset,
+            end_offset: end_offset.unwrap_or(0) as u64,
+            next_offset,
+        })
+    }
+
+    fn terminal_from_row(row: &rusqlite::Row<'_>) -> SqliteResult<Terminal> {
+        Ok(Terminal {
+            id: row.get(0)?,
+            session_id: SessionId::from_string(row.get::<_, String>(1)?),
+            name: row.get(2)?,
+            command: row.get(3)?,
+            cwd: row.get(4)?,
+            pid: row.get(5)?,
+            started_at: Self::parse_datetime(row.get::<_, String>(6)?),
+            finished_at: row.get::<_, Option<String>>(7)?.map(Self::parse_datetime),
+            exit_code: row.get(8)?,
+        })
+    }
+
+    // ========================================================================
+    // Session secrets operations
+    // ========================================================================
+
+    /// Gets a session secret by session ID.
+    pub fn get_session_secret(
+        &self,
+        session_id: &SessionId,
+    ) -> SqliteResult<Option<SessionSecret>> {
+        let conn = self.conn.lock().expect("lock poisoned");
+        let mut stmt = conn.prepare_cached(
+            "SELECT session_id, encrypted_secret, nonce, created_at
+             FROM local_llm_conversation_secrets WHERE session_id = ?1",
         )?;
 
         let result = stmt.query_row(params![session_id.as_str()], |row| {
@@ -1415,6 +2491,21 @@ impl SqliteStore {
     }
 }
 
+/// Builds an FTS5 match expression that requires every word of `text`.
+///
+/// Each word is quoted so user input can never be parsed as FTS5 query syntax.
+fn fts_match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" AND "))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1613,6 +2704,7 @@ mod tests {
             status: Some(SessionStatus::Archived),
             claude_session_id: None,
             last_accessed_at: None,
+            permission_mode: None,
         };
         store.update_agent_session(&session_id, &update).unwrap();
 
@@ -1620,6 +2712,29 @@ mod tests {
         assert_eq!(session.status, SessionStatus::Archived);
     }
 
+    #[test]
+    fn update_session_permission_mode_sets_and_clears() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session_id = create_test_session(&store, &repo_id);
+
+        let set = SessionUpdate {
+            permission_mode: Some(Some("plan".to_string())),
+            ..SessionUpdate::default()
+        };
+        store.update_agent_session(&session_id, &set).unwrap();
+        let session = store.get_agent_session(&session_id).unwrap().unwrap();
+        assert_eq!(session.permission_mode.as_deref(), Some("plan"));
+
+        let clear = SessionUpdate {
+            permission_mode: Some(None),
+            ..SessionUpdate::default()
+        };
+        store.update_agent_session(&session_id, &clear).unwrap();
+        let session = store.get_agent_session(&session_id).unwrap().unwrap();
+        assert_eq!(session.permission_mode, None);
+    }
+
     #[test]
     fn insert_and_get_messages() {
         let store = SqliteStore::in_memory().unwrap();
@@ -1712,6 +2827,70 @@ mod tests {
         assert_eq!(inserted3.sequence_number, 3);
     }
 
+    #[test]
+    fn messages_page_uses_keyset_bounds() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session_id = create_test_session(&store, &repo_id);
+
+        for i in 1..=5 {
+            store
+                .insert_agent_message(
+                    &session_id,
+                    &NewMessage {
+                        content: format!("M{i}"),
+                    },
+                )
+                .unwrap();
+        }
+
+        let first = store
+            .get_agent_messages_page(&session_id, 0, i64::MAX, 2)
+            .unwrap();
+        let seqs: Vec<_> = first.iter().map(|m| m.sequence_number).collect();
+        assert_eq!(seqs, vec![1, 2]);
+
+        let next = store
+            .get_agent_messages_page(&session_id, 2, 4, 10)
+            .unwrap();
+        let seqs: Vec<_> = next.iter().map(|m| m.sequence_number).collect();
+        assert_eq!(seqs, vec![3, 4]);
+    }
+
+    #[test]
+    fn session_cursors_report_last_message() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let empty = create_test_session(&store, &repo_id);
+        let busy = create_test_session(&store, &repo_id);
+
+        store
+            .insert_agent_message(
+                &busy,
+                &NewMessage {
+                    content: "one".to_string(),
+                },
+            )
+            .unwrap();
+        let last = store
+            .insert_agent_message(
+                &busy,
+                &NewMessage {
+                    content: "two".to_string(),
+                },
+            )
+            .unwrap();
+
+        let cursors = store.list_agent_session_cursors().unwrap();
+        let empty_cursor = cursors.iter().find(|c| c.id == empty).unwrap();
+        assert_eq!(empty_cursor.last_sequence, 0);
+        assert!(empty_cursor.last_message_id.is_none());
+
+        let busy_cursor = cursors.iter().find(|c| c.id == busy).unwrap();
+        assert_eq!(busy_cursor.last_sequence, 2);
+        assert_eq!(busy_cursor.last_message_id.as_ref(), Some(&last.id));
+    }
+
     #[test]
     fn sequence_is_per_session() {
         let store = SqliteStore::in_memory().unwrap();
@@ -1749,4 +2928,337 @@ mod tests {
         assert_eq!(msg2.sequence_number, 1); // session2 starts at 1
         assert_eq!(msg3.sequence_number, 2); // session1 continues to 2
     }
+
+    #[test]
+    fn search_indexes_json_text_but_not_identifiers() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session = create_test_session(&store, &repo_id);
+
+        let json_message = store
+            .insert_agent_message(
+                &session,
+                &NewMessage {
+                    content: r#"{"type":"assistant","message":{"id":"msg_needle","content":[{"type":"text","text":"Refactored the parser"}]}}"#
+                        .to_string(),
+                },
+            )
+            .unwrap();
+        store
+            .insert_agent_message(
+                &session,
+                &NewMessage {
+                    content: "plain parser notes".to_string(),
+                },
+            )
+            .unwrap();
+
+        let hits = store
+            .search_agent_messages(&MessageSearchQuery::new("refactored parser"))
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, json_message.id);
+        assert_eq!(hits[0].sequence_number, 1);
+        assert!(hits[0].snippet.contains("[Refactored]"));
+
+        assert!(store
+            .search_agent_messages(&MessageSearchQuery::new("msg_needle"))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            store
+                .search_agent_messages(&MessageSearchQuery::new("parser"))
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn search_index_is_backfilled_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("armin.db");
+        let session = {
+            let store = SqliteStore::open(&path).unwrap();
+            let repo_id = create_test_repo(&store);
+            let session = create_test_session(&store, &repo_id);
+            store
+                .insert_agent_message(
+                    &session,
+                    &NewMessage {
+                        content: "written before indexing existed".to_string(),
+                    },
+                )
+                .unwrap();
+            let conn = store.conn.lock().unwrap();
+            conn.execute_batch("DROP TABLE local_llm_conversation_messages_fts;")
+                .unwrap();
+            session
+        };
+
+        let store = SqliteStore::open(&path).unwrap();
+        let hits = store
+            .search_agent_messages(&MessageSearchQuery::new("indexing"))
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, session);
+    }
+
+    #[test]
+    fn deleting_session_removes_search_rows() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session = create_test_session(&store, &repo_id);
+        store
+            .insert_agent_message(
+                &session,
+                &NewMessage {
+                    content: "ephemeral".to_string(),
+                },
+            )
+            .unwrap();
+
+        store.delete_agent_session(&session).unwrap();
+
+        let conn = store.conn.lock().unwrap();
+        let indexed: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM local_llm_conversation_messages_fts",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(indexed, 0);
+    }
+
+    #[test]
+    fn message_queue_is_first_in_first_out() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session = create_test_session(&store, &repo_id);
+        let other = create_test_session(&store, &repo_id);
+        let enqueue = |session: &SessionId, content: &str| {
+            store
+                .enqueue_message(
+                    session,
+                    &NewQueuedMessage {
+                        content: content.to_string(),
+                        request_json: "{}".to_string(),
+                    },
+                )
+                .unwrap()
+        };
+
+        let first = enqueue(&session, "first");
+        let second = enqueue(&session, "second");
+        let third = enqueue(&session, "third");
+        enqueue(&other, "elsewhere");
+
+        let contents = |session: &SessionId| -> Vec<String> {
+            store
+                .list_queued_messages(session)
+                .unwrap()
+                .into_iter()
+                .map(|queued| queued.content)
+                .collect()
+        };
+        assert_eq!(contents(&session), vec!["first", "second", "third"]);
+        assert_eq!(
+            store
+                .get_or_create_session_state(&session)
+                .unwrap()
+                .runtime_status
+                .coding_session
+                .queue_length,
+            3
+        );
+
+        assert!(store.remove_queued_message(&session, &second.id).unwrap());
+        assert!(!store.remove_queued_message(&other, &third.id).unwrap());
+        assert_eq!(
+            store.pop_queued_message(&session).unwrap().unwrap().id,
+            first.id
+        );
+        assert_eq!(contents(&session), vec!["third"]);
+
+        // Positions keep growing after pops, so new messages go to the back
+        enqueue(&session, "fourth");
+        assert_eq!(contents(&session), vec!["third", "fourth"]);
+
+        let mut queued_sessions = store.sessions_with_queued_messages().unwrap();
+        queued_sessions.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        let mut expected = vec![session.clone(), other.clone()];
+        expected.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        assert_eq!(queued_sessions, expected);
+
+        assert_eq!(store.clear_queued_messages(&session).unwrap(), 2);
+        assert!(store.pop_queued_message(&session).unwrap().is_none());
+        store.delete_agent_session(&other).unwrap();
+        assert!(store.sessions_with_queued_messages().unwrap().is_empty());
+    }
+
+    #[test]
+    fn match_expression_quotes_every_word() {
+        assert_eq!(fts_match_expression("   "), None);
+        assert_eq!(
+            fts_match_expression(r#"foo OR "bar"#).as_deref(),
+            Some(r#""foo" AND "OR" AND """bar""#)
+        );
+    }
+
+    #[test]
+    fn process_records_round_trip_and_cascade() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session = create_test_session(&store, &repo_id);
+        let other = create_test_session(&store, &repo_id);
+
+        let agent = store
+            .record_process(
+                &session,
+                &NewProcessRecord {
+                    kind: ProcessKind::Agent,
+                    pid: 4242,
+                    command: "claude".to_string(),
+                },
+            )
+            .unwrap();
+        store
+            .record_process(
+                &other,
+                &NewProcessRecord {
+                    kind: ProcessKind::Terminal,
+                    pid: 4343,
+                    command: "npm test".to_string(),
+                },
+            )
+            .unwrap();
+
+        let records = store.list_process_records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], agent);
+
+        assert!(store.remove_process_record(&agent.id).unwrap());
+        assert!(!store.remove_process_record(&agent.id).unwrap());
+
+        store.delete_agent_session(&other).unwrap();
+        assert!(store.list_process_records().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sessions_with_runtime_status_filters_by_status() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let running = create_test_session(&store, &repo_id);
+        let idle = create_test_session(&store, &repo_id);
+        store.get_or_create_session_state(&running).unwrap();
+        store.get_or_create_session_state(&idle).unwrap();
+        store
+            .update_runtime_status(&running, "device", CodingSessionStatus::Running, None)
+            .unwrap();
+        store
+            .update_runtime_status(&idle, "device", CodingSessionStatus::Idle, None)
+            .unwrap();
+
+        let sessions = store
+            .sessions_with_runtime_status(&[
+                CodingSessionStatus::Running,
+                CodingSessionStatus::Waiting,
+            ])
+            .unwrap();
+
+        assert_eq!(sessions, vec![running]);
+    }
+
+    #[test]
+    fn terminals_keep_their_history_per_session() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session = create_test_session(&store, &repo_id);
+        let other = create_test_session(&store, &repo_id);
+        let new_terminal = |name: &str, command: &str| NewTerminal {
+            name: Some(name.to_string()),
+            command: command.to_string(),
+            cwd: "/repo".to_string(),
+            pid: Some(4242),
+        };
+
+        let server = store
+            .create_terminal(&session, &new_terminal("dev", "npm run dev"))
+            .unwrap();
+        let tests = store
+            .create_terminal(&session, &new_terminal("tests", "npm test"))
+            .unwrap();
+        store
+            .create_terminal(&other, &new_terminal("dev", "cargo run"))
+            .unwrap();
+
+        assert!(store.finish_terminal(&tests.id, Some(1)).unwrap());
+        assert!(!store.finish_terminal(&tests.id, Some(0)).unwrap());
+
+        let terminals = store.list_terminals(&session).unwrap();
+        assert_eq!(terminals.len(), 2);
+        assert_eq!(terminals[0], server);
+        assert_eq!(terminals[1].exit_code, Some(1));
+        assert!(terminals[1].finished_at.is_some());
+        assert_eq!(
+            store.get_terminal(&tests.id).unwrap().as_ref(),
+            Some(&terminals[1])
+        );
+
+        let unfinished = store.list_unfinished_terminals().unwrap();
+        assert_eq!(unfinished.len(), 2);
+        assert!(unfinished.iter().all(|terminal| terminal.id != tests.id));
+
+        store.delete_agent_session(&other).unwrap();
+        assert_eq!(store.list_unfinished_terminals().unwrap(), vec![server]);
+    }
+
+    #[test]
+    fn terminal_output_keeps_a_bounded_tail() {
+        let store = SqliteStore::in_memory().unwrap();
+        let repo_id = create_test_repo(&store);
+        let session = create_test_session(&store, &repo_id);
+        let terminal = store
+            .create_terminal(
+                &session,
+                &NewTerminal {
+                    name: None,
+                    command: "cargo build".to_string(),
+                    cwd: "/repo".to_string(),
+                    pid: Some(4242),
+                },
+            )
+            .unwrap();
+
+        for (index, chunk) in ["aaaa", "bbbb", "cccc", "dd"].iter().enumerate() {
+            let offset = store
+                .append_terminal_output(&terminal.id, chunk, 8)
+                .unwrap();
+            assert_eq!(offset, index as u64 * 4);
+        }
+
+        // "aaaa" ended 10 bytes before the end, past the 8 byte scrollback
+        let page = store.read_terminal_output(&terminal.id, 0, 2).unwrap();
+        assert_eq!(page.start_offset, 4);
+        assert_eq!(page.end_offset, 14);
+        let contents: Vec<&str> = page.chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(contents, ["bbbb", "cccc"]);
+        assert_eq!(page.next_offset, 12);
+
+        let page = store
+            .read_terminal_output(&terminal.id, page.next_offset, 2)
+            .unwrap();
+        assert_eq!(page.chunks.len(), 1);
+        assert_eq!(page.chunks[0].offset, 12);
+        assert_eq!(page.next_offset, 14);
+
+        let page = store.read_terminal_output(&terminal.id, 14, 2).unwrap();
+        assert!(page.chunks.is_empty());
+        assert_eq!(page.next_offset, 14);
+
+        store.delete_agent_session(&session).unwrap();
+        let page = store.read_terminal_output(&terminal.id, 0, 2).unwrap();
+        assert!(page.chunks.is_empty());
+    }
 }
@@ -63,7 +63,7 @@ fn rule_85_concurrent_subscribers_isolated() {
     let session_id = armin.create_session().unwrap();
 
     // Create many subscribers
-    let subscribers: Vec<_> = (0..50).map(|_| armin.subscribe(&session_id)).collect();
+    let mut subscribers: Vec<_> = (0..50).map(|_| armin.subscribe(&session_id)).collect();
 
     // Send messages
     for i in 0..10 {
@@ -78,7 +78,7 @@ fn rule_85_concurrent_subscribers_isolated() {
     }
 
     // Each subscriber should receive all messages independently
-    for (sub_idx, sub) in subscribers.iter().enumerate() {
+    for (sub_idx, sub) in subscribers.iter_mut().enumerate() {
         let mut count = 0;
         while sub.try_recv().is_some() {
             count += 1;
@@ -220,7 +220,7 @@ fn rule_89_live_hub_thread_safe() {
     let session_id = armin.create_session().unwrap();
 
     // Subscribe
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
     // Append messages
     for i in 0..10 {
@@ -379,7 +379,7 @@ fn many_subscribers_single_message() {
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let subscribers: Vec<_> = (0..100).map(|_| armin.subscribe(&session_id)).collect();
+    let mut subscribers: Vec<_> = (0..100).map(|_| armin.subscribe(&session_id)).collect();
 
     armin
         .append(
@@ -391,8 +391,10 @@ fn many_subscribers_single_message() {
         .unwrap();
 
     // All should receive
-    for (i, sub) in subscribers.iter().enumerate() {
-        let msg = sub.try_recv().expect(&format!("Subscriber {} failed", i));
+    for (i, sub) in subscribers.iter_mut().enumerate() {
+        let msg = sub
+            .try_recv()
+            .unwrap_or_else(|| panic!("Subscriber {} failed", i));
         assert_eq!(msg.content, "Broadcast");
     }
 }
@@ -101,8 +101,8 @@ fn rule_23_delta_preserves_order() {
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let contents = vec!["First", "Second", "Third", "Fourth", "Fifth"];
-    for (i, content) in contents.iter().enumerate() {
+    let contents = ["First", "Second", "Third", "Fourth", "Fifth"];
+    for content in contents.iter() {
         armin
             .append(
                 &session_id,
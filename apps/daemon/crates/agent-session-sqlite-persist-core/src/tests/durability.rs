@@ -114,7 +114,7 @@ fn rule_06_message_ids_are_monotonic() {
     let mut all_ids = Vec::new();
 
     // Interleave appends across sessions
-    for i in 0..5 {
+    for _i in 0..5 {
         all_ids.push(
             armin
                 .append(
@@ -189,8 +189,8 @@ fn rule_08_multiple_appends_preserve_order() {
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let contents = vec!["First", "Second", "Third", "Fourth", "Fifth"];
-    for (i, content) in contents.iter().enumerate() {
+    let contents = ["First", "Second", "Third", "Fourth", "Fifth"];
+    for content in contents.iter() {
         armin
             .append(
                 &session_id,
@@ -459,7 +459,7 @@ fn rule_78_recovery_does_not_notify_live_subscribers() {
     let armin = Armin::open(path, sink).unwrap();
 
     // Subscribe after recovery
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
     // Should not receive the historical message
     assert!(
@@ -0,0 +1,246 @@
+//! Tests for forking sessions at a message sequence.
+//!
+//! These tests verify that:
+//! - Forks copy exactly the requested prefix with fresh sequencing
+//! - Forks are linked to their parent session
+//! - Forked messages are readable, durable, and searchable
+//! - Invalid fork points are rejected without writing anything
+
+use crate::reader::SessionReader;
+use crate::side_effect::RecordingSink;
+use crate::types::{
+    Message, MessageSearchQuery, NewMessage, NewRepository, NewSession, SessionForkOptions,
+    SessionId,
+};
+use crate::writer::SessionWriter;
+use crate::{Armin, ArminError, SideEffect};
+
+fn session_with_messages(armin: &Armin<RecordingSink>, count: usize) -> SessionId {
+    let session = armin.create_session().unwrap();
+    for i in 1..=count {
+        armin
+            .append(
+                &session,
+                NewMessage {
+                    content: format!("message {i}"),
+                },
+            )
+            .unwrap();
+    }
+    session
+}
+
+/// Snapshot followed by delta, as `message.list` reads a session.
+fn read_all(armin: &Armin<RecordingSink>, session: &SessionId) -> Vec<Message> {
+    let mut messages: Vec<Message> = armin
+        .snapshot()
+        .session(session)
+        .map(|snapshot| snapshot.messages().to_vec())
+        .unwrap_or_default();
+    messages.extend(armin.delta(session).messages().iter().cloned());
+    messages
+}
+
+#[test]
+fn fork_copies_prefix_with_fresh_sequencing() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 5);
+
+    let fork = armin.fork_session(&source, 3).unwrap();
+
+    let messages = read_all(&armin, &fork.id);
+    let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, vec!["message 1", "message 2", "message 3"]);
+    let sequences: Vec<i64> = messages.iter().map(|m| m.sequence_number).collect();
+    assert_eq!(sequences, vec![1, 2, 3]);
+
+    let source_ids: Vec<_> = read_all(&armin, &source)
+        .into_iter()
+        .map(|m| m.id)
+        .collect();
+    assert!(messages.iter().all(|m| !source_ids.contains(&m.id)));
+}
+
+#[test]
+fn fork_links_parent_and_child() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 2);
+
+    let fork = armin.fork_session(&source, 2).unwrap();
+
+    let stored = armin.get_session(&fork.id).unwrap().unwrap();
+    assert_eq!(stored.parent_session_id, Some(source.clone()));
+    assert_eq!(stored.forked_from_sequence, Some(2));
+
+    let parent = armin.get_session(&source).unwrap().unwrap();
+    assert_eq!(parent.parent_session_id, None);
+    assert_eq!(parent.forked_from_sequence, None);
+}
+
+#[test]
+fn fork_continues_sequencing_after_copied_messages() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 4);
+    let fork = armin.fork_session(&source, 2).unwrap();
+
+    let appended = armin
+        .append(
+            &fork.id,
+            NewMessage {
+                content: "a different prompt".to_string(),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(appended.sequence_number, 3);
+    let contents: Vec<String> = read_all(&armin, &fork.id)
+        .into_iter()
+        .map(|m| m.content)
+        .collect();
+    assert_eq!(
+        contents,
+        vec!["message 1", "message 2", "a different prompt"]
+    );
+    assert_eq!(read_all(&armin, &source).len(), 4);
+}
+
+#[test]
+fn fork_at_zero_creates_empty_child() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 3);
+
+    let fork = armin.fork_session(&source, 0).unwrap();
+
+    assert!(read_all(&armin, &fork.id).is_empty());
+    assert_eq!(
+        armin
+            .get_session(&fork.id)
+            .unwrap()
+            .unwrap()
+            .forked_from_sequence,
+        Some(0)
+    );
+}
+
+#[test]
+fn fork_rejects_out_of_range_sequence() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 2);
+    armin.sink().clear();
+
+    for up_to in [-1, 3] {
+        let result = armin.fork_session(&source, up_to);
+        assert!(matches!(result, Err(ArminError::InvalidForkPoint(_))));
+    }
+
+    assert!(armin.sink().is_empty());
+}
+
+#[test]
+fn fork_of_missing_session_fails() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+
+    let result = armin.fork_session(&SessionId::from_string("missing"), 0);
+
+    assert!(matches!(result, Err(ArminError::SessionNotFound(_))));
+}
+
+#[test]
+fn fork_applies_options_and_drops_provider_resume_id() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 1);
+    armin
+        .update_session_provider_session(&source, "claude", "claude-resume-id")
+        .unwrap();
+
+    let options = SessionForkOptions {
+        id: SessionId::from_string("fork-id"),
+        title: Some("Retry".to_string()),
+        worktree_path: Some("/tmp/fork-worktree".to_string()),
+    };
+    let fork = armin
+        .fork_session_with_options(&source, 1, options)
+        .unwrap();
+
+    assert_eq!(fork.id.as_str(), "fork-id");
+    assert_eq!(fork.title, "Retry");
+    assert!(fork.is_worktree);
+    assert_eq!(fork.worktree_path.as_deref(), Some("/tmp/fork-worktree"));
+    assert_eq!(fork.provider.as_deref(), Some("claude"));
+    assert_eq!(fork.provider_session_id, None);
+    assert_eq!(fork.claude_session_id, None);
+}
+
+#[test]
+fn fork_shares_but_does_not_own_the_parent_worktree() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let repo = armin
+        .create_repository(NewRepository::new("/tmp/repo", "repo", true))
+        .unwrap();
+    let parent = armin
+        .create_session_with_metadata(NewSession {
+            is_worktree: true,
+            worktree_path: Some("/tmp/parent-worktree".to_string()),
+            ..NewSession::new(repo.id, "Parent")
+        })
+        .unwrap();
+
+    let fork = armin.fork_session(&parent.id, 0).unwrap();
+
+    assert!(!fork.is_worktree);
+    assert_eq!(fork.worktree_path.as_deref(), Some("/tmp/parent-worktree"));
+}
+
+#[test]
+fn fork_emits_session_created() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 2);
+    armin.sink().clear();
+
+    let fork = armin.fork_session(&source, 2).unwrap();
+
+    assert_eq!(
+        armin.sink().effects(),
+        vec![SideEffect::SessionCreated {
+            session_id: fork.id
+        }]
+    );
+}
+
+#[test]
+fn forked_messages_survive_recovery() {
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let (source, fork) = {
+        let armin = Armin::open(temp_file.path(), RecordingSink::new()).unwrap();
+        let source = session_with_messages(&armin, 3);
+        let fork = armin.fork_session(&source, 2).unwrap();
+        (source, fork.id)
+    };
+
+    let armin = Armin::open(temp_file.path(), RecordingSink::new()).unwrap();
+
+    let sequences: Vec<i64> = read_all(&armin, &fork)
+        .iter()
+        .map(|m| m.sequence_number)
+        .collect();
+    assert_eq!(sequences, vec![1, 2]);
+    assert_eq!(
+        armin.get_session(&fork).unwrap().unwrap().parent_session_id,
+        Some(source)
+    );
+}
+
+#[test]
+fn forked_messages_are_searchable() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let source = session_with_messages(&armin, 2);
+
+    let fork = armin.fork_session(&source, 1).unwrap();
+
+    let mut hits = armin
+        .search_messages(&MessageSearchQuery::new("message"))
+        .unwrap();
+    hits.retain(|hit| hit.session_id == fork.id);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].sequence_number, 1);
+}
@@ -0,0 +1,226 @@
+//! Tests for importing exported sessions.
+//!
+//! These tests verify that:
+//! - Imported messages keep their order and are numbered from 1
+//! - Imported sessions keep their status, creation time, and message timestamps
+//! - Imported sessions behave like any other session afterwards
+//! - Imported messages are durable and searchable
+
+use crate::reader::SessionReader;
+use crate::side_effect::RecordingSink;
+use crate::types::{
+    ImportedMessage, Message, MessageSearchQuery, NewMessage, NewRepository, NewSession,
+    RepositoryId, SessionHistory, SessionId, SessionStatus,
+};
+use crate::writer::SessionWriter;
+use crate::{Armin, SideEffect};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+fn exported_at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+}
+
+/// Messages written a minute apart, starting at `exported_at`.
+fn messages(contents: &[&str]) -> Vec<ImportedMessage> {
+    contents
+        .iter()
+        .zip(0..)
+        .map(|(content, minute)| ImportedMessage {
+            content: content.to_string(),
+            timestamp: exported_at() + Duration::minutes(minute),
+        })
+        .collect()
+}
+
+fn history() -> SessionHistory {
+    SessionHistory {
+        status: SessionStatus::Active,
+        created_at: exported_at(),
+    }
+}
+
+/// Registers the target repository and returns session metadata pointing at it.
+fn new_session(armin: &Armin<RecordingSink>, title: &str) -> NewSession {
+    let repository_id = RepositoryId::from_string("import-repo");
+    if armin.get_repository(&repository_id).unwrap().is_none() {
+        let mut repository = NewRepository::new("/tmp/import-repo", "import-repo", false);
+        repository.id = repository_id.clone();
+        armin.create_repository(repository).unwrap();
+    }
+    NewSession::new(repository_id, title)
+}
+
+/// Snapshot followed by delta, as `message.list` reads a session.
+fn read_all(armin: &Armin<RecordingSink>, session: &SessionId) -> Vec<Message> {
+    let mut messages: Vec<Message> = armin
+        .snapshot()
+        .session(session)
+        .map(|snapshot| snapshot.messages().to_vec())
+        .unwrap_or_default();
+    messages.extend(armin.delta(session).messages().iter().cloned());
+    messages
+}
+
+#[test]
+fn import_preserves_message_order() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+
+    let session = armin
+        .import_session(
+            new_session(&armin, "Imported"),
+            history(),
+            messages(&["first", r#"{"type":"assistant"}"#, "third"]),
+        )
+        .unwrap();
+
+    let imported = read_all(&armin, &session.id);
+    let contents: Vec<&str> = imported.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, vec!["first", r#"{"type":"assistant"}"#, "third"]);
+    let sequences: Vec<i64> = imported.iter().map(|m| m.sequence_number).collect();
+    assert_eq!(sequences, vec![1, 2, 3]);
+    assert_eq!(session.title, "Imported");
+    assert_eq!(session.parent_session_id, None);
+}
+
+#[test]
+fn import_continues_sequencing_on_append() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin
+        .import_session(
+            new_session(&armin, "Imported"),
+            history(),
+            messages(&["a", "b"]),
+        )
+        .unwrap();
+
+    let appended = armin
+        .append(
+            &session.id,
+            NewMessage {
+                content: "c".to_string(),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(appended.sequence_number, 3);
+    assert_eq!(read_all(&armin, &session.id).len(), 3);
+}
+
+#[test]
+fn import_without_messages_creates_empty_session() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+
+    let session = armin
+        .import_session(new_session(&armin, "Empty"), history(), Vec::new())
+        .unwrap();
+
+    assert!(read_all(&armin, &session.id).is_empty());
+    assert!(armin.get_session(&session.id).unwrap().is_some());
+}
+
+#[test]
+fn import_emits_only_session_created() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let metadata = new_session(&armin, "Imported");
+    armin.sink().clear();
+
+    let session = armin
+        .import_session(metadata, history(), messages(&["a", "b"]))
+        .unwrap();
+
+    assert_eq!(
+        armin.sink().effects(),
+        vec![SideEffect::SessionCreated {
+            session_id: session.id
+        }]
+    );
+}
+
+#[test]
+fn import_with_existing_id_fails_without_writing_messages() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let existing = armin.create_session().unwrap();
+    let mut duplicate = new_session(&armin, "Duplicate");
+    duplicate.id = existing.clone();
+    armin.sink().clear();
+
+    let result = armin.import_session(duplicate, history(), messages(&["should not land"]));
+
+    assert!(result.is_err());
+    assert!(read_all(&armin, &existing).is_empty());
+    assert!(armin.sink().is_empty());
+}
+
+#[test]
+fn imported_messages_survive_recovery() {
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let session = {
+        let armin = Armin::open(temp_file.path(), RecordingSink::new()).unwrap();
+        armin
+            .import_session(
+                new_session(&armin, "Imported"),
+                history(),
+                messages(&["x", "y"]),
+            )
+            .unwrap()
+            .id
+    };
+
+    let armin = Armin::open(temp_file.path(), RecordingSink::new()).unwrap();
+
+    let contents: Vec<String> = read_all(&armin, &session)
+        .into_iter()
+        .map(|m| m.content)
+        .collect();
+    assert_eq!(contents, vec!["x", "y"]);
+}
+
+#[test]
+fn imported_messages_are_searchable() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+
+    let session = armin
+        .import_session(
+            new_session(&armin, "Imported"),
+            history(),
+            messages(&["unrelated", "the bundle restored cleanly"]),
+        )
+        .unwrap();
+
+    let hits = armin
+        .search_messages(&MessageSearchQuery::new("bundle"))
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, session.id);
+    assert_eq!(hits[0].sequence_number, 2);
+}
+
+#[test]
+fn import_keeps_session_history_and_message_timestamps() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let created_at = exported_at() - Duration::days(3);
+
+    let session = armin
+        .import_session(
+            new_session(&armin, "Archived"),
+            SessionHistory {
+                status: SessionStatus::Archived,
+                created_at,
+            },
+            messages(&["a", "b"]),
+        )
+        .unwrap();
+
+    assert_eq!(session.status, SessionStatus::Archived);
+    assert_eq!(session.created_at, created_at);
+    let timestamps: Vec<DateTime<Utc>> = armin
+        .list_timestamped_messages_page(&session.id, 0, 10)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.timestamp)
+        .collect();
+    assert_eq!(
+        timestamps,
+        vec![exported_at(), exported_at() + Duration::minutes(1)]
+    );
+}
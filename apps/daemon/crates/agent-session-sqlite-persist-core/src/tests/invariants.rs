@@ -131,7 +131,7 @@ fn rule_105_closed_session_subscriptions() {
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
     armin
         .append(
@@ -469,7 +469,7 @@ fn unicode_content_preserved() {
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let contents = vec![
+    let contents = [
         "Hello 👋",
         "世界",
         "مرحبا",
@@ -479,7 +479,7 @@ fn unicode_content_preserved() {
         "Здравствуйте",
     ];
 
-    for (i, content) in contents.iter().enumerate() {
+    for content in contents.iter() {
         armin
             .append(
                 &session_id,
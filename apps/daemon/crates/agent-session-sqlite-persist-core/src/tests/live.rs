@@ -4,89 +4,88 @@
 //! - 31. Live subscribers receive new messages
 //! - 32. Live subscribers receive messages in order
 //! - 33. Live subscribers do not receive historical messages
-//! - 34. Live subscribers block until a message arrives
+//! - 34. Live subscribers wait until a message arrives
 //! - 35. Multiple subscribers receive the same messages
 //! - 36. Slow subscribers do not block writers
-//! - 37. Dropped live messages are detectable (N/A - channel semantics)
+//! - 37. Dropped live messages are detectable
 //! - 38. Live notifications occur after SQLite commit
 //! - 39. Live notifications occur before side-effect emission (N/A - same order)
 //! - 40. Live subscriptions are session-scoped
 
+use std::time::Duration;
+
+use futures_util::StreamExt;
+
+use crate::live::LiveLag;
 use crate::reader::SessionReader;
 use crate::side_effect::RecordingSink;
-use crate::types::NewMessage;
+use crate::types::{NewMessage, SessionId};
 use crate::writer::SessionWriter;
-use crate::Armin;
+use crate::{Armin, ArminConfig};
 use tempfile::NamedTempFile;
 
-/// Rule 31: Live subscribers receive new messages
-#[test]
-fn rule_31_subscribers_receive_new_messages() {
-    let sink = RecordingSink::new();
-    let armin = Armin::in_memory(sink).unwrap();
-    let session_id = armin.create_session().unwrap();
-
-    let sub = armin.subscribe(&session_id);
+/// Upper bound on how long a test waits for a message that should already be there.
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
 
+fn append(armin: &Armin<RecordingSink>, session_id: &SessionId, content: &str) {
     armin
         .append(
-            &session_id,
+            session_id,
             NewMessage {
-                content: "Hello".to_string(),
+                content: content.to_string(),
             },
         )
         .unwrap();
+}
+
+/// Rule 31: Live subscribers receive new messages
+#[tokio::test]
+async fn rule_31_subscribers_receive_new_messages() {
+    let sink = RecordingSink::new();
+    let armin = Armin::in_memory(sink).unwrap();
+    let session_id = armin.create_session().unwrap();
+
+    let mut sub = armin.subscribe(&session_id);
 
-    let msg = sub.try_recv().expect("Should receive message");
+    append(&armin, &session_id, "Hello");
+
+    let msg = sub.next().await.expect("Should receive message");
     assert_eq!(msg.content, "Hello");
 }
 
 /// Rule 32: Live subscribers receive messages in order
-#[test]
-fn rule_32_subscribers_receive_in_order() {
+#[tokio::test]
+async fn rule_32_subscribers_receive_in_order() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
-    let contents = vec!["First", "Second", "Third", "Fourth", "Fifth"];
-    for (i, content) in contents.iter().enumerate() {
-        armin
-            .append(
-                &session_id,
-                NewMessage {
-                    content: content.to_string(),
-                },
-            )
-            .unwrap();
+    let contents = ["First", "Second", "Third", "Fourth", "Fifth"];
+    for content in contents.iter() {
+        append(&armin, &session_id, content);
     }
 
-    for expected in &contents {
-        let msg = sub.try_recv().expect("Should receive message");
+    for (i, expected) in contents.iter().enumerate() {
+        let msg = sub.next().await.expect("Should receive message");
         assert_eq!(msg.content, *expected);
+        assert_eq!(msg.sequence_number, (i + 1) as i64);
     }
 }
 
 /// Rule 33: Live subscribers do not receive historical messages
-#[test]
-fn rule_33_no_historical_messages() {
+#[tokio::test]
+async fn rule_33_no_historical_messages() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
     // Add messages before subscription
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "Historical".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "Historical");
 
     // Subscribe after
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
     // Should not receive historical message
     assert!(
@@ -95,108 +94,119 @@ fn rule_33_no_historical_messages() {
     );
 
     // But should receive new messages
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "New".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "New");
 
-    let msg = sub.try_recv().expect("Should receive new message");
+    let msg = sub.next().await.expect("Should receive new message");
     assert_eq!(msg.content, "New");
 }
 
-/// Rule 34: Live subscribers block until a message arrives
-/// (We test non-blocking behavior to avoid test hangs)
-#[test]
-fn rule_34_subscribers_block_semantics() {
+/// Rule 34: Live subscribers wait until a message arrives
+#[tokio::test]
+async fn rule_34_subscribers_wait_for_messages() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
+    let waiter = tokio::spawn(async move { sub.next().await });
 
-    // No message yet - try_recv should return None
-    assert!(sub.try_recv().is_none());
+    // The subscriber is parked with nothing to read
+    tokio::task::yield_now().await;
+    assert!(!waiter.is_finished());
 
-    // Add message
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "Test".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "Test");
 
-    // Now it should be available
-    assert!(sub.try_recv().is_some());
+    let msg = tokio::time::timeout(RECV_TIMEOUT, waiter)
+        .await
+        .expect("Subscriber should wake on append")
+        .unwrap()
+        .expect("Should receive message");
+    assert_eq!(msg.content, "Test");
 }
 
 /// Rule 35: Multiple subscribers receive the same messages
-#[test]
-fn rule_35_multiple_subscribers_same_messages() {
+#[tokio::test]
+async fn rule_35_multiple_subscribers_same_messages() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let sub1 = armin.subscribe(&session_id);
-    let sub2 = armin.subscribe(&session_id);
-    let sub3 = armin.subscribe(&session_id);
+    let mut sub1 = armin.subscribe(&session_id);
+    let mut sub2 = armin.subscribe(&session_id);
+    let mut sub3 = armin.subscribe(&session_id);
 
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "Broadcast".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "Broadcast");
 
     // All subscribers should receive the message
-    assert_eq!(sub1.try_recv().unwrap().content, "Broadcast");
-    assert_eq!(sub2.try_recv().unwrap().content, "Broadcast");
-    assert_eq!(sub3.try_recv().unwrap().content, "Broadcast");
+    assert_eq!(sub1.next().await.unwrap().content, "Broadcast");
+    assert_eq!(sub2.next().await.unwrap().content, "Broadcast");
+    assert_eq!(sub3.next().await.unwrap().content, "Broadcast");
 }
 
 /// Rule 36: Slow subscribers do not block writers
-#[test]
-fn rule_36_slow_subscribers_no_blocking() {
-    let sink = RecordingSink::new();
-    let armin = Armin::in_memory(sink).unwrap();
+#[tokio::test]
+async fn rule_36_slow_subscribers_no_blocking() {
+    let config = ArminConfig {
+        live_buffer_capacity: 10,
+        ..ArminConfig::default()
+    };
+    let armin = Armin::in_memory_with_config(RecordingSink::new(), config).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
-    // Write many messages without reading
+    // Write far more messages than the subscriber buffers, without reading
     let count = 100;
     for i in 0..count {
-        armin
-            .append(
-                &session_id,
-                NewMessage {
-                    content: format!("Message {}", i),
-                },
-            )
-            .unwrap();
+        append(&armin, &session_id, &format!("Message {}", i));
     }
 
     // Verify all writes completed
     assert_eq!(armin.delta(&session_id).len(), count);
 
-    // Slow subscriber can still read
-    let mut received = 0;
-    while sub.try_recv().is_some() {
-        received += 1;
+    // Slow subscriber can still read what was buffered
+    let received: Vec<_> = (&mut sub).collect().await;
+    assert_eq!(received.len(), 10);
+}
+
+/// Rule 37: Dropped live messages are detectable
+#[tokio::test]
+async fn rule_37_dropped_messages_detectable() {
+    let config = ArminConfig {
+        live_buffer_capacity: 3,
+        ..ArminConfig::default()
+    };
+    let armin = Armin::in_memory_with_config(RecordingSink::new(), config).unwrap();
+    let session_id = armin.create_session().unwrap();
+
+    let mut sub = armin.subscribe(&session_id);
+    for i in 1..=5 {
+        append(&armin, &session_id, &format!("Message {}", i));
     }
-    assert_eq!(received, count);
+
+    // The stream ends after the buffered prefix, with no holes in it
+    let received: Vec<i64> = (&mut sub).map(|m| m.sequence_number).collect().await;
+    assert_eq!(received, vec![1, 2, 3]);
+    assert_eq!(
+        sub.lagged(),
+        Some(LiveLag {
+            first_missed_sequence: 4
+        })
+    );
+
+    // The gap is recoverable from committed state
+    let missed = armin
+        .list_messages_page(&session_id, 3, 100)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(missed, vec![4, 5]);
 }
 
 /// Rule 38: Live notifications occur after SQLite commit
-#[test]
-fn rule_38_notifications_after_commit() {
+#[tokio::test]
+async fn rule_38_notifications_after_commit() {
     let temp_file = NamedTempFile::new().unwrap();
     let path = temp_file.path();
 
@@ -205,19 +215,12 @@ fn rule_38_notifications_after_commit() {
         let armin = Armin::open(path, sink).unwrap();
         let session_id = armin.create_session().unwrap();
 
-        let sub = armin.subscribe(&session_id);
+        let mut sub = armin.subscribe(&session_id);
 
-        armin
-            .append(
-                &session_id,
-                NewMessage {
-                    content: "Committed".to_string(),
-                },
-            )
-            .unwrap();
+        append(&armin, &session_id, "Committed");
 
         // Received via live subscription
-        let msg = sub.try_recv().expect("Should receive message");
+        let msg = sub.next().await.expect("Should receive message");
         (session_id, msg.content)
     };
 
@@ -230,42 +233,28 @@ fn rule_38_notifications_after_commit() {
 }
 
 /// Rule 40: Live subscriptions are session-scoped
-#[test]
-fn rule_40_subscriptions_session_scoped() {
+#[tokio::test]
+async fn rule_40_subscriptions_session_scoped() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
 
     let session1 = armin.create_session().unwrap();
     let session2 = armin.create_session().unwrap();
 
-    let sub1 = armin.subscribe(&session1);
-    let sub2 = armin.subscribe(&session2);
+    let mut sub1 = armin.subscribe(&session1);
+    let mut sub2 = armin.subscribe(&session2);
 
     // Message to session1
-    armin
-        .append(
-            &session1,
-            NewMessage {
-                content: "For session 1".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session1, "For session 1");
 
     // Message to session2
-    armin
-        .append(
-            &session2,
-            NewMessage {
-                content: "For session 2".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session2, "For session 2");
 
     // Each subscriber only gets their session's messages
-    assert_eq!(sub1.try_recv().unwrap().content, "For session 1");
+    assert_eq!(sub1.next().await.unwrap().content, "For session 1");
     assert!(sub1.try_recv().is_none());
 
-    assert_eq!(sub2.try_recv().unwrap().content, "For session 2");
+    assert_eq!(sub2.next().await.unwrap().content, "For session 2");
     assert!(sub2.try_recv().is_none());
 }
 
@@ -273,8 +262,8 @@ fn rule_40_subscriptions_session_scoped() {
 // Additional live subscription tests
 // =============================================================================
 
-#[test]
-fn subscription_after_recovery_receives_new_messages() {
+#[tokio::test]
+async fn subscription_after_recovery_receives_new_messages() {
     let temp_file = NamedTempFile::new().unwrap();
     let path = temp_file.path();
 
@@ -282,36 +271,22 @@ fn subscription_after_recovery_receives_new_messages() {
         let sink = RecordingSink::new();
         let armin = Armin::open(path, sink).unwrap();
         let session_id = armin.create_session().unwrap();
-        armin
-            .append(
-                &session_id,
-                NewMessage {
-                    content: "Before restart".to_string(),
-                },
-            )
-            .unwrap();
+        append(&armin, &session_id, "Before restart");
         session_id
     };
 
     // Restart and subscribe
     let sink = RecordingSink::new();
     let armin = Armin::open(path, sink).unwrap();
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
     // Should not receive historical message
     assert!(sub.try_recv().is_none());
 
     // But should receive new ones
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "After restart".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "After restart");
 
-    assert_eq!(sub.try_recv().unwrap().content, "After restart");
+    assert_eq!(sub.next().await.unwrap().content, "After restart");
 }
 
 #[test]
@@ -326,102 +301,69 @@ fn dropped_subscription_cleaned_up() {
     }
 
     // Writing should still work (dead subscriber cleaned up)
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "Test".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "Test");
 
     assert_eq!(armin.delta(&session_id).len(), 1);
 }
 
-#[test]
-fn closed_session_stops_notifications() {
+#[tokio::test]
+async fn closed_session_ends_stream() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "Before close".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "Before close");
 
     armin.close(&session_id).unwrap();
 
     // Should still receive the message that was sent
-    assert_eq!(sub.try_recv().unwrap().content, "Before close");
-
-    // No more messages expected (session is closed)
-    assert!(sub.try_recv().is_none());
+    assert_eq!(sub.next().await.unwrap().content, "Before close");
+
+    // The stream ends (session is closed) rather than waiting forever
+    let end = tokio::time::timeout(RECV_TIMEOUT, sub.next())
+        .await
+        .expect("Stream should end when the session closes");
+    assert!(end.is_none());
+    assert_eq!(sub.lagged(), None);
 }
 
-#[test]
-fn subscription_iterator() {
+#[tokio::test]
+async fn subscription_stream() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
     let sub = armin.subscribe(&session_id);
 
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "One".to_string(),
-            },
-        )
-        .unwrap();
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "Two".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "One");
+    append(&armin, &session_id, "Two");
+    armin.close(&session_id).unwrap();
 
-    // Collect via try_recv (iter() would block)
-    let mut messages = Vec::new();
-    while let Some(msg) = sub.try_recv() {
-        messages.push(msg.content);
-    }
+    let messages: Vec<_> = sub.map(|msg| msg.content).collect().await;
 
     assert_eq!(messages, vec!["One", "Two"]);
 }
 
-#[test]
-fn many_subscribers_performance() {
+#[tokio::test]
+async fn many_subscribers_performance() {
     let sink = RecordingSink::new();
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
     // Create many subscribers
-    let subscribers: Vec<_> = (0..100).map(|_| armin.subscribe(&session_id)).collect();
+    let mut subscribers: Vec<_> = (0..100).map(|_| armin.subscribe(&session_id)).collect();
 
     // Send a message
-    armin
-        .append(
-            &session_id,
-            NewMessage {
-                content: "Broadcast to many".to_string(),
-            },
-        )
-        .unwrap();
+    append(&armin, &session_id, "Broadcast to many");
 
     // All should receive it
-    for (i, sub) in subscribers.iter().enumerate() {
+    for (i, sub) in subscribers.iter_mut().enumerate() {
         let msg = sub
-            .try_recv()
-            .expect(&format!("Subscriber {} should receive", i));
+            .next()
+            .await
+            .unwrap_or_else(|| panic!("Subscriber {} should receive", i));
         assert_eq!(msg.content, "Broadcast to many");
     }
 }
@@ -12,16 +12,22 @@
 //! - `concurrency.rs`  - Rules 81-90 (Concurrency & Isolation)
 //! - `failures.rs`     - Rules 91-100 (Failure Injection)
 //! - `invariants.rs`   - Rules 101-120 (Boundary, Invariants, & Meta)
+//! - `search.rs`       - Full-text message search
+//! - `fork.rs`         - Session forking
+//! - `import.rs`       - Session import
 
 mod concurrency;
 mod delta;
 mod durability;
 mod failures;
+mod fork;
+mod import;
 mod invariants;
 mod live;
 mod ordering;
 mod reads;
 mod recovery;
+mod search;
 mod side_effects;
 mod snapshot;
 
@@ -28,7 +28,7 @@ fn rule_61_order_consistent_across_views() {
     let session_id = armin.create_session().unwrap();
 
     // Subscribe before appending
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
     // Add messages
     let mut expected_order = Vec::new();
@@ -320,7 +320,7 @@ fn rule_68_live_matches_sqlite_order() {
         let armin = Armin::open(path, sink).unwrap();
         let session_id = armin.create_session().unwrap();
 
-        let sub = armin.subscribe(&session_id);
+        let mut sub = armin.subscribe(&session_id);
 
         for i in 0..10 {
             armin
@@ -482,7 +482,7 @@ fn message_content_preserved_in_order() {
     let session_id = armin.create_session().unwrap();
 
     let contents: Vec<_> = (0..20).map(|i| format!("Content-{}", i)).collect();
-    for (i, content) in contents.iter().enumerate() {
+    for content in contents.iter() {
         armin
             .append(
                 &session_id,
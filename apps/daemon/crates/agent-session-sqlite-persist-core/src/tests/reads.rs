@@ -141,7 +141,7 @@ fn rule_54_reads_never_notify_subscribers() {
     let armin = Armin::in_memory(sink).unwrap();
     let session_id = armin.create_session().unwrap();
 
-    let sub = armin.subscribe(&session_id);
+    let mut sub = armin.subscribe(&session_id);
 
     // Perform reads
     for _ in 0..100 {
@@ -0,0 +1,252 @@
+//! Tests for full-text message search.
+//!
+//! These tests verify that:
+//! - Appended messages are searchable immediately
+//! - Hits carry session, sequence, and snippet
+//! - Repository and date filters narrow results
+//! - Search reads never emit side-effects
+
+use crate::reader::SessionReader;
+use crate::side_effect::RecordingSink;
+use crate::types::{
+    MessageSearchQuery, NewMessage, NewRepository, NewSession, RepositoryId, SessionId,
+};
+use crate::writer::SessionWriter;
+use crate::{Armin, ArminError};
+use chrono::{Duration, Utc};
+
+fn create_repository_session(armin: &Armin<RecordingSink>, repo: &str) -> SessionId {
+    let repository_id = RepositoryId::from_string(repo);
+    if armin.get_repository(&repository_id).unwrap().is_none() {
+        armin
+            .create_repository(NewRepository {
+                id: repository_id.clone(),
+                path: format!("/tmp/{repo}"),
+                name: repo.to_string(),
+                machine_id: None,
+                space_id: None,
+                is_git_repository: false,
+                sessions_path: None,
+                default_branch: None,
+                default_remote: None,
+            })
+            .unwrap();
+    }
+
+    armin
+        .create_session_with_metadata(NewSession {
+            id: SessionId::new(),
+            repository_id,
+            machine_id: None,
+            space_id: None,
+            title: format!("{repo} session"),
+            agent_name: None,
+            issue_id: None,
+            issue_title: None,
+            issue_url: None,
+            provider: None,
+            provider_session_id: None,
+            claude_session_id: None,
+            is_worktree: false,
+            worktree_path: None,
+        })
+        .unwrap()
+        .id
+}
+
+fn append(armin: &Armin<RecordingSink>, session: &SessionId, content: &str) {
+    armin
+        .append(
+            session,
+            NewMessage {
+                content: content.to_string(),
+            },
+        )
+        .unwrap();
+}
+
+#[test]
+fn appended_messages_are_searchable() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin.create_session().unwrap();
+    append(&armin, &session, "setting up the workspace");
+    append(
+        &armin,
+        &session,
+        r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Fixed the flaky migration test"}]}}"#,
+    );
+
+    let hits = armin
+        .search_messages(&MessageSearchQuery::new("flaky migration"))
+        .unwrap();
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, session);
+    assert_eq!(hits[0].sequence_number, 2);
+    assert!(hits[0].snippet.contains("[flaky]"));
+    assert!(hits[0].snippet.contains("[migration]"));
+}
+
+#[test]
+fn closed_sessions_remain_searchable_after_recovery() {
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let session = {
+        let armin = Armin::open(temp_file.path(), RecordingSink::new()).unwrap();
+        let session = armin.create_session().unwrap();
+        append(&armin, &session, "the deploy script needs a retry");
+        armin.close(&session).unwrap();
+        session
+    };
+
+    let armin = Armin::open(temp_file.path(), RecordingSink::new()).unwrap();
+    let hits = armin
+        .search_messages(&MessageSearchQuery::new("deploy"))
+        .unwrap();
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, session);
+}
+
+#[test]
+fn search_ranks_stronger_matches_first() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin.create_session().unwrap();
+    append(
+        &armin,
+        &session,
+        "cache misses everywhere, this cache layer is just a cache of the cache",
+    );
+    append(
+        &armin,
+        &session,
+        "cache mentioned once among many other unrelated words here",
+    );
+
+    let hits = armin
+        .search_messages(&MessageSearchQuery::new("cache"))
+        .unwrap();
+
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].sequence_number, 1);
+    assert!(hits[0].rank <= hits[1].rank);
+}
+
+#[test]
+fn search_filters_by_repository() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let alpha = create_repository_session(&armin, "alpha");
+    let beta = create_repository_session(&armin, "beta");
+    append(&armin, &alpha, "tokenizer panic in alpha");
+    append(&armin, &beta, "tokenizer panic in beta");
+
+    let mut query = MessageSearchQuery::new("tokenizer");
+    query.repository_id = Some(RepositoryId::from_string("beta"));
+    let hits = armin.search_messages(&query).unwrap();
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, beta);
+    assert_eq!(hits[0].session_title, "beta session");
+}
+
+#[test]
+fn search_filters_by_date_range() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin.create_session().unwrap();
+    append(&armin, &session, "rebase conflict");
+
+    let mut query = MessageSearchQuery::new("rebase");
+    query.since = Some(Utc::now() - Duration::hours(1));
+    query.until = Some(Utc::now() + Duration::hours(1));
+    assert_eq!(armin.search_messages(&query).unwrap().len(), 1);
+
+    query.since = Some(Utc::now() + Duration::hours(1));
+    query.until = None;
+    assert!(armin.search_messages(&query).unwrap().is_empty());
+
+    query.since = None;
+    query.until = Some(Utc::now() - Duration::hours(1));
+    assert!(armin.search_messages(&query).unwrap().is_empty());
+}
+
+#[test]
+fn search_pages_with_limit_and_offset() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin.create_session().unwrap();
+    for i in 0..5 {
+        append(&armin, &session, &format!("lint warning {i}"));
+    }
+
+    let mut query = MessageSearchQuery::new("lint");
+    query.limit = 2;
+    let first = armin.search_messages(&query).unwrap();
+    query.offset = 2;
+    let second = armin.search_messages(&query).unwrap();
+    query.offset = 4;
+    let third = armin.search_messages(&query).unwrap();
+
+    assert_eq!((first.len(), second.len(), third.len()), (2, 2, 1));
+    let mut sequences: Vec<i64> = first
+        .iter()
+        .chain(&second)
+        .chain(&third)
+        .map(|hit| hit.sequence_number)
+        .collect();
+    sequences.sort_unstable();
+    assert_eq!(sequences, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn search_treats_query_syntax_as_literal_words() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin.create_session().unwrap();
+    append(&armin, &session, "apply the NEAR patch");
+
+    // Unbalanced FTS5 syntax is quoted rather than rejected.
+    let hits = armin
+        .search_messages(&MessageSearchQuery::new(r#"NEAR( "patch"#))
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+
+    // `OR` is a required word, not an operator.
+    let hits = armin
+        .search_messages(&MessageSearchQuery::new("patch OR missing"))
+        .unwrap();
+    assert!(hits.is_empty());
+}
+
+#[test]
+fn empty_search_is_rejected() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+
+    let result = armin.search_messages(&MessageSearchQuery::new("  "));
+
+    assert!(matches!(result, Err(ArminError::InvalidSearchQuery(_))));
+}
+
+#[test]
+fn deleted_sessions_are_not_searchable() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin.create_session().unwrap();
+    append(&armin, &session, "temporary scratch notes");
+
+    armin.delete_session(&session).unwrap();
+
+    assert!(armin
+        .search_messages(&MessageSearchQuery::new("scratch"))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn search_emits_no_side_effects() {
+    let armin = Armin::in_memory(RecordingSink::new()).unwrap();
+    let session = armin.create_session().unwrap();
+    append(&armin, &session, "quiet read");
+    armin.sink().clear();
+
+    armin
+        .search_messages(&MessageSearchQuery::new("quiet"))
+        .unwrap();
+
+    assert!(armin.sink().is_empty());
+}
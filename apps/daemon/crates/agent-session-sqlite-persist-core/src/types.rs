@@ -154,6 +154,7 @@ impl Message {
     ///
     /// Returns a `NewMessage` struct containing only the content. The ID and
     /// sequence number are assigned atomically by Armin during the actual insertion.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(content: impl Into<String>) -> NewMessage {
         NewMessage {
             content: content.into(),
@@ -170,6 +171,66 @@ pub struct NewMessage {
     pub content: String,
 }
 
+/// A stored message together with the time it was written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimestampedMessage {
+    pub message: Message,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A message restored by a session import, keeping its original timestamp.
+#[derive(Clone, Debug)]
+pub struct ImportedMessage {
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Default number of hits returned by a message search.
+pub const DEFAULT_MESSAGE_SEARCH_LIMIT: usize = 50;
+
+/// A full-text search over message transcripts.
+///
+/// `text` is matched word-by-word; every word must appear in a message for it to hit.
+/// Date bounds apply to the message creation time and are inclusive.
+#[derive(Clone, Debug)]
+pub struct MessageSearchQuery {
+    pub text: String,
+    pub repository_id: Option<RepositoryId>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl MessageSearchQuery {
+    /// Creates a query for `text` across all repositories and dates.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            repository_id: None,
+            since: None,
+            until: None,
+            limit: DEFAULT_MESSAGE_SEARCH_LIMIT,
+            offset: 0,
+        }
+    }
+}
+
+/// A message matched by a full-text search, best matches first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageSearchHit {
+    pub session_id: SessionId,
+    pub repository_id: RepositoryId,
+    pub session_title: String,
+    pub message_id: MessageId,
+    pub sequence_number: i64,
+    /// Excerpt around the match with matched terms wrapped in `[` and `]`.
+    pub snippet: String,
+    /// BM25 rank; lower is a better match.
+    pub rank: f64,
+    pub created_at: DateTime<Utc>,
+}
+
 // ============================================================================
 // Repository types
 // ============================================================================
@@ -325,6 +386,7 @@ impl SessionStatus {
     ///
     /// Performs case-insensitive matching. Returns `Active` as the default
     /// for any unrecognized string values (fail-safe behavior).
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "archived" => Self::Archived,
@@ -352,6 +414,13 @@ pub struct Session {
     pub status: SessionStatus,
     pub is_worktree: bool,
     pub worktree_path: Option<String>,
+    /// Session this one was forked from, if any.
+    pub parent_session_id: Option<SessionId>,
+    /// Last sequence number of the parent copied into this session at fork time.
+    pub forked_from_sequence: Option<i64>,
+    /// Permission mode chosen for the session's next agent turns, overriding
+    /// the repository default.
+    pub permission_mode: Option<String>,
     pub created_at: DateTime<Utc>,
     pub last_accessed_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -444,6 +513,25 @@ impl Session {
     }
 }
 
+/// Overrides applied when forking a session.
+///
+/// Fields left unset are copied from the source session. Provider resume IDs are
+/// never copied, since they would resume the full (unforked) conversation. A fork
+/// owns a worktree only when given its own `worktree_path`.
+#[derive(Debug, Clone, Default)]
+pub struct SessionForkOptions {
+    pub id: SessionId,
+    pub title: Option<String>,
+    pub worktree_path: Option<String>,
+}
+
+/// Session history restored by an import instead of starting afresh.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionHistory {
+    pub status: SessionStatus,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Session update fields.
 #[derive(Debug, Clone, Default)]
 pub struct SessionUpdate {
@@ -451,6 +539,8 @@ pub struct SessionUpdate {
     pub claude_session_id: Option<String>,
     pub status: Option<SessionStatus>,
     pub last_accessed_at: Option<DateTime<Utc>>,
+    /// `Some(None)` clears the session's permission mode.
+    pub permission_mode: Option<Option<String>>,
 }
 
 // ============================================================================
@@ -491,6 +581,7 @@ impl CodingSessionStatus {
     ///
     /// Performs case-insensitive matching. Returns `NotAvailable` as a fallback
     /// for any unrecognized string values (fail-safe behavior).
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "running" => Self::Running,
@@ -513,6 +604,25 @@ pub struct CodingSessionRuntimeState {
     pub status: CodingSessionStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_message: Option<String>,
+    /// The latest structured question the agent asked, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub question: Option<QuestionRuntimeState>,
+    /// Number of queued messages waiting for the current turn to finish.
+    #[serde(default)]
+    pub queue_length: i64,
+    /// 1-based position in the daemon's agent scheduler while waiting for a
+    /// free process slot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduler_position: Option<i64>,
+}
+
+/// A structured question (`AskUserQuestion` tool use) tracked in the envelope.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuestionRuntimeState {
+    /// Tool use id of the question, answered by a matching tool result.
+    pub tool_use_id: String,
+    /// Whether an answer has been delivered to the agent.
+    pub resolved: bool,
 }
 
 /// Canonical runtime status envelope shared across transport/storage layers.
@@ -543,6 +653,139 @@ impl SessionState {
     }
 }
 
+// ============================================================================
+// Message queue types
+// ============================================================================
+
+/// A message waiting for the session's running agent turn to finish.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedMessage {
+    pub id: String,
+    pub session_id: SessionId,
+    /// The prompt text, for display.
+    pub content: String,
+    /// The original send request to replay, as JSON.
+    pub request_json: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A message to add to the end of a session's queue.
+#[derive(Debug, Clone)]
+pub struct NewQueuedMessage {
+    pub content: String,
+    pub request_json: String,
+}
+
+// ============================================================================
+// Process record types
+// ============================================================================
+
+/// What a recorded process does for its session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessKind {
+    Agent,
+    Terminal,
+}
+
+impl ProcessKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Agent => "agent",
+            Self::Terminal => "terminal",
+        }
+    }
+
+    /// Parses a stored kind; unknown kinds are `None`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "agent" => Some(Self::Agent),
+            "terminal" => Some(Self::Terminal),
+            _ => None,
+        }
+    }
+}
+
+/// A child process the daemon spawned for a session.
+///
+/// Records are written at spawn and removed when the process exits, so the
+/// records left at startup belong to processes a crashed daemon lost track of.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessRecord {
+    pub id: String,
+    pub session_id: SessionId,
+    pub kind: ProcessKind,
+    pub pid: u32,
+    /// The program that was started, to tell the process from a reused pid.
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/// A process to record for a session.
+#[derive(Debug, Clone)]
+pub struct NewProcessRecord {
+    pub kind: ProcessKind,
+    pub pid: u32,
+    pub command: String,
+}
+
+// ============================================================================
+// Terminal types
+// ============================================================================
+
+/// A command run in one of a session's terminals.
+///
+/// Rows outlive their process, so a session keeps the history of what ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Terminal {
+    pub id: String,
+    pub session_id: SessionId,
+    /// Label chosen by the client, e.g. `dev-server`.
+    pub name: Option<String>,
+    pub command: String,
+    /// Working directory the command started in.
+    pub cwd: String,
+    pub pid: Option<u32>,
+    pub started_at: DateTime<Utc>,
+    /// `None` while the command runs.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// `None` while the command runs, or when the daemon lost track of it.
+    pub exit_code: Option<i32>,
+}
+
+/// A terminal to record for a session.
+#[derive(Debug, Clone)]
+pub struct NewTerminal {
+    pub name: Option<String>,
+    pub command: String,
+    pub cwd: String,
+    pub pid: Option<u32>,
+}
+
+/// Bytes of output kept per terminal. Once a terminal has written more, its
+/// oldest chunks are dropped.
+pub const TERMINAL_SCROLLBACK_BYTES: u64 = 1 << 20;
+
+/// A chunk of terminal output, as read from the PTY.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalOutputChunk {
+    /// Bytes the terminal wrote before this chunk.
+    pub offset: u64,
+    pub content: String,
+}
+
+/// A page of a terminal's retained output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalOutputPage {
+    pub chunks: Vec<TerminalOutputChunk>,
+    /// Offset of the oldest retained output; anything before it was dropped.
+    pub start_offset: u64,
+    /// Bytes the terminal has written so far.
+    pub end_offset: u64,
+    /// Offset to read the next page from.
+    pub next_offset: u64,
+}
+
 // ============================================================================
 // Session secret types
 // ============================================================================
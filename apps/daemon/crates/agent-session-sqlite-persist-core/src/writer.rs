@@ -10,8 +10,10 @@
 //! - If SQLite write fails, nothing else happens
 
 use crate::types::{
-    AgentStatus, CodingSessionStatus, Message, NewMessage, NewRepository, NewSession,
-    NewSessionSecret, Repository, RepositoryId, Session, SessionId, SessionUpdate,
+    AgentStatus, CodingSessionStatus, ImportedMessage, Message, NewMessage, NewProcessRecord,
+    NewQueuedMessage, NewRepository, NewSession, NewSessionSecret, NewTerminal, ProcessRecord,
+    QuestionRuntimeState, QueuedMessage, Repository, RepositoryId, Session, SessionForkOptions,
+    SessionHistory, SessionId, SessionUpdate, Terminal,
 };
 use crate::ArminError;
 
@@ -84,6 +86,41 @@ pub trait SessionWriter {
     /// Returns true if the session was deleted.
     fn delete_session(&self, id: &SessionId) -> Result<bool, ArminError>;
 
+    /// Forks a session at a message sequence.
+    ///
+    /// Copies the source's messages with sequence numbers up to and including
+    /// `up_to_sequence` into a new session, renumbered from 1, and records the
+    /// source as the new session's parent. Pass 0 to fork without messages.
+    fn fork_session(&self, source: &SessionId, up_to_sequence: i64) -> Result<Session, ArminError> {
+        self.fork_session_with_options(source, up_to_sequence, SessionForkOptions::default())
+    }
+
+    /// Forks a session at a message sequence, overriding the copied metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SessionNotFound` if the source does not exist and
+    /// `InvalidForkPoint` if `up_to_sequence` is negative or past its last message.
+    fn fork_session_with_options(
+        &self,
+        source: &SessionId,
+        up_to_sequence: i64,
+        options: SessionForkOptions,
+    ) -> Result<Session, ArminError>;
+
+    /// Creates a session from exported data.
+    ///
+    /// The session keeps the status and creation time in `history`, and
+    /// `messages` keep their timestamps. Messages are stored in the given order
+    /// and numbered from 1. Nothing is written unless the session and all of its
+    /// messages commit together.
+    fn import_session(
+        &self,
+        session: NewSession,
+        history: SessionHistory,
+        messages: Vec<ImportedMessage>,
+    ) -> Result<Session, ArminError>;
+
     // ========================================================================
     // Session state operations
     // ========================================================================
@@ -99,6 +136,78 @@ pub trait SessionWriter {
         error_message: Option<String>,
     ) -> Result<(), ArminError>;
 
+    /// Records (or clears) the session's structured question in the runtime
+    /// status envelope, leaving the status itself unchanged.
+    fn update_runtime_question(
+        &self,
+        session: &SessionId,
+        device_id: &str,
+        question: Option<QuestionRuntimeState>,
+    ) -> Result<(), ArminError>;
+
+    /// Records (or clears) the session's position in the daemon's agent
+    /// scheduler, leaving the status itself unchanged.
+    fn update_runtime_scheduler_position(
+        &self,
+        session: &SessionId,
+        device_id: &str,
+        position: Option<i64>,
+    ) -> Result<(), ArminError>;
+
+    // ========================================================================
+    // Message queue operations
+    // ========================================================================
+
+    /// Adds a message to the end of a session's queue.
+    fn enqueue_message(
+        &self,
+        session: &SessionId,
+        message: NewQueuedMessage,
+    ) -> Result<QueuedMessage, ArminError>;
+
+    /// Removes and returns the oldest queued message, if any.
+    fn pop_queued_message(&self, session: &SessionId) -> Result<Option<QueuedMessage>, ArminError>;
+
+    /// Removes one queued message. Returns false if it was not queued.
+    fn remove_queued_message(&self, session: &SessionId, id: &str) -> Result<bool, ArminError>;
+
+    /// Empties a session's queue, returning how many messages were removed.
+    fn clear_queued_messages(&self, session: &SessionId) -> Result<usize, ArminError>;
+
+    // ========================================================================
+    // Process record operations
+    // ========================================================================
+
+    /// Records a child process spawned for a session.
+    fn record_process(
+        &self,
+        session: &SessionId,
+        process: NewProcessRecord,
+    ) -> Result<ProcessRecord, ArminError>;
+
+    /// Removes a process record once its process has exited. Returns false
+    /// if it was not recorded.
+    fn remove_process_record(&self, id: &str) -> Result<bool, ArminError>;
+
+    // ========================================================================
+    // Terminal operations
+    // ========================================================================
+
+    /// Records a command started in one of a session's terminals.
+    fn create_terminal(
+        &self,
+        session: &SessionId,
+        terminal: NewTerminal,
+    ) -> Result<Terminal, ArminError>;
+
+    /// Records that a terminal's command finished, with its exit code if
+    /// known. Returns false if it was not running.
+    fn finish_terminal(&self, id: &str, exit_code: Option<i32>) -> Result<bool, ArminError>;
+
+    /// Stores a chunk of a terminal's output and returns its offset. Only
+    /// the last `TERMINAL_SCROLLBACK_BYTES` of output are kept.
+    fn append_terminal_output(&self, id: &str, content: &str) -> Result<u64, ArminError>;
+
     /// Legacy scalar status update helper kept during migration.
     ///
     /// Prefer `update_runtime_status`.
@@ -1,12 +1,25 @@
-//! Typed Rust structs for Claude Code JSON stream events.
+//! Claude Code `stream-json` events, as printed by
+//! `claude -p --verbose --output-format stream-json`.
 //!
-//! These types match the TypeScript Zod schema used by Claude Code.
-//! They are used by the TUI to parse raw JSON from ClaudeEvent.
+//! Fields Claude commonly sends as `null` (`stop_reason`,
+//! `parent_tool_use_id`, ...) are left in `extra` rather than modeled, so
+//! the `null` survives a round-trip.
 
+use crate::{TokenUsage, ToolCall};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Version of this model; see the crate docs.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Name of the tool Claude uses to ask the user a question.
+pub const ASK_USER_QUESTION_TOOL: &str = "AskUserQuestion";
+
+/// Name of the tool Claude uses to start a sub-agent.
+pub const TASK_TOOL: &str = "Task";
 
 /// Top-level Claude message discriminated by `type` field.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClaudeCodeMessage {
     System(SystemMessage),
@@ -14,32 +27,50 @@ pub enum ClaudeCodeMessage {
     User(UserMessage),
     Result(ResultMessage),
     StreamEvent(StreamEventMessage),
+    /// A message of a type not modeled here, or one that does not match
+    /// its model, kept as received.
+    #[serde(untagged)]
+    Other(Value),
 }
 
 /// System message containing session metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SystemMessage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub subtype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<ToolInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub mcp_servers: Option<Vec<McpServerInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cwd: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Tool information from system message.
 /// Can be either a simple string or a detailed object.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ToolInfo {
     /// Simple string format: "Task", "Bash", etc.
     Simple(String),
     /// Detailed object format: { "name": "Task", "type": "builtin" }
-    Detailed {
-        name: String,
-        #[serde(rename = "type")]
-        tool_type: Option<String>,
-    },
+    Detailed(ToolDetails),
+}
+
+/// Detailed tool information.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDetails {
+    pub name: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub tool_type: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl ToolInfo {
@@ -47,141 +78,282 @@ impl ToolInfo {
     pub fn name(&self) -> &str {
         match self {
             ToolInfo::Simple(name) => name,
-            ToolInfo::Detailed { name, .. } => name,
+            ToolInfo::Detailed(details) => &details.name,
         }
     }
 }
 
 /// MCP server information from system message.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct McpServerInfo {
     pub name: String,
     pub status: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Assistant message containing Claude's response.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AssistantMessage {
     pub message: MessageContent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// User message containing tool results.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserMessage {
     pub message: MessageContent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Result message indicating completion.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ResultMessage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub subtype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub result: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub duration_api_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub num_turns: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub session_id: Option<String>,
+    /// Cost as reported by older Claude Code versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub usage: Option<Usage>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ResultMessage {
+    /// Whether the turn failed: an error flag or an `error_*` subtype.
+    pub fn is_error(&self) -> bool {
+        self.is_error == Some(true)
+            || self
+                .subtype
+                .as_deref()
+                .is_some_and(|subtype| subtype.starts_with("error"))
+    }
+
+    /// Cost of the turn in USD, from either field name.
+    pub fn cost(&self) -> Option<f64> {
+        self.total_cost_usd.or(self.cost_usd)
+    }
 }
 
 /// Token usage information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Usage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub output_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cache_creation_input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cache_read_input_tokens: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Usage {
+    /// The normalized usage. Claude counts cache writes and reads apart
+    /// from `input_tokens`; both are input.
+    pub fn token_usage(&self) -> TokenUsage {
+        let cached = self.cache_read_input_tokens.unwrap_or(0);
+        TokenUsage {
+            input_tokens: self.input_tokens.unwrap_or(0)
+                + self.cache_creation_input_tokens.unwrap_or(0)
+                + cached,
+            cached_input_tokens: cached,
+            output_tokens: self.output_tokens.unwrap_or(0),
+        }
+    }
 }
 
 /// Message content wrapper with blocks.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MessageContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub role: Option<String>,
-    pub content: Vec<ContentBlock>,
+    pub content: Content,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
-    pub stop_reason: Option<String>,
-    pub stop_sequence: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl MessageContent {
+    /// The content blocks; a plain string content has none.
+    pub fn blocks(&self) -> &[ContentBlock] {
+        match &self.content {
+            Content::Blocks(blocks) => blocks,
+            Content::Text(_) => &[],
+        }
+    }
+}
+
+/// Message content: a list of blocks, or plain text for prompts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Blocks(Vec<ContentBlock>),
+    Text(String),
 }
 
 /// Content block discriminated by `type` field.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ContentBlock {
     Text(TextBlock),
     ToolUse(ToolUseBlock),
     ToolResult(ToolResultBlock),
+    Thinking(ThinkingBlock),
+    #[serde(untagged)]
+    Other(Value),
 }
 
 /// Text content block.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TextBlock {
     pub text: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Extended thinking content block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThinkingBlock {
+    pub thinking: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Tool use content block.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolUseBlock {
     pub id: String,
     pub name: String,
-    pub input: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub input: Value,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Tool result content block.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolResultBlock {
     pub tool_use_id: String,
-    #[serde(default)]
-    pub is_error: bool,
-    pub content: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_error: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ToolResultBlock {
+    /// Whether the tool failed; an absent flag means it did not.
+    pub fn is_error(&self) -> bool {
+        self.is_error == Some(true)
+    }
 }
 
 /// Stream event message for real-time content deltas.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StreamEventMessage {
     pub event: StreamEvent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Stream event types.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum StreamEvent {
     ContentBlockDelta {
         delta: ContentDelta,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         index: Option<u32>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     ContentBlockStart {
         index: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         content_block: Option<ContentBlock>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     ContentBlockStop {
         index: u32,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     MessageStart {
-        message: Option<serde_json::Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<Value>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     MessageDelta {
-        delta: Option<serde_json::Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delta: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         usage: Option<Usage>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    MessageStop {
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
-    MessageStop,
-    #[serde(other)]
-    Unknown,
+    #[serde(untagged)]
+    Other(Value),
 }
 
 /// Content delta types for streaming.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ContentDelta {
     TextDelta {
         text: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     InputJsonDelta {
         partial_json: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    ThinkingDelta {
+        thinking: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
-    #[serde(other)]
-    Unknown,
+    #[serde(untagged)]
+    Other(Value),
 }
 
 impl ClaudeCodeMessage {
@@ -190,14 +362,55 @@ impl ClaudeCodeMessage {
         serde_json::from_str(json)
     }
 
+    /// Parse an already decoded JSON value.
+    pub fn from_value(json: &Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(json)
+    }
+
     /// Get the event type as a string.
-    pub fn event_type(&self) -> &'static str {
+    pub fn event_type(&self) -> &str {
         match self {
             Self::System(_) => "system",
             Self::Assistant(_) => "assistant",
             Self::User(_) => "user",
             Self::Result(_) => "result",
             Self::StreamEvent(_) => "stream_event",
+            Self::Other(json) => json
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown"),
+        }
+    }
+
+    /// Claude's session id, carried by most messages.
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            Self::System(message) => message.session_id.as_deref(),
+            Self::Assistant(message) => message.session_id.as_deref(),
+            Self::User(message) => message.session_id.as_deref(),
+            Self::Result(message) => message.session_id.as_deref(),
+            Self::StreamEvent(message) => message.session_id.as_deref(),
+            Self::Other(json) => json.get("session_id").and_then(Value::as_str),
+        }
+    }
+
+    /// Usage of the whole turn, reported by its result message.
+    pub fn turn_usage(&self) -> Option<TokenUsage> {
+        match self {
+            Self::Result(result) => result.usage.as_ref().map(Usage::token_usage),
+            _ => None,
+        }
+    }
+
+    /// Tool calls requested by an assistant message.
+    pub fn tool_calls(&self) -> Vec<ToolCall> {
+        match self {
+            Self::Assistant(assistant) => assistant
+                .tool_uses()
+                .into_iter()
+                .map(ToolUseBlock::tool_call)
+                .collect(),
+            _ => Vec::new(),
         }
     }
 }
@@ -205,21 +418,24 @@ impl ClaudeCodeMessage {
 impl AssistantMessage {
     /// Extract all text content concatenated.
     pub fn full_text(&self) -> String {
+        self.text_blocks().collect::<Vec<_>>().join("")
+    }
+
+    /// The text blocks of the message, in order.
+    pub fn text_blocks(&self) -> impl Iterator<Item = &str> {
         self.message
-            .content
+            .blocks()
             .iter()
             .filter_map(|block| match block {
                 ContentBlock::Text(t) => Some(t.text.as_str()),
                 _ => None,
             })
-            .collect::<Vec<_>>()
-            .join("")
     }
 
     /// Get all tool use blocks.
     pub fn tool_uses(&self) -> Vec<&ToolUseBlock> {
         self.message
-            .content
+            .blocks()
             .iter()
             .filter_map(|block| match block {
                 ContentBlock::ToolUse(t) => Some(t),
@@ -231,7 +447,7 @@ impl AssistantMessage {
     /// Check if this message contains any tool use.
     pub fn has_tool_use(&self) -> bool {
         self.message
-            .content
+            .blocks()
             .iter()
             .any(|b| matches!(b, ContentBlock::ToolUse(_)))
     }
@@ -241,7 +457,7 @@ impl UserMessage {
     /// Get all tool result blocks.
     pub fn tool_results(&self) -> Vec<&ToolResultBlock> {
         self.message
-            .content
+            .blocks()
             .iter()
             .filter_map(|block| match block {
                 ContentBlock::ToolResult(t) => Some(t),
@@ -252,13 +468,13 @@ impl UserMessage {
 }
 
 /// AskUserQuestion input structure (for prompts).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AskUserQuestionInput {
     pub questions: Vec<Question>,
 }
 
 /// A single question in AskUserQuestion.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Question {
     pub question: String,
     pub header: Option<String>,
@@ -268,14 +484,14 @@ pub struct Question {
 }
 
 /// An option for a question.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct QuestionOption {
     pub label: String,
     pub description: Option<String>,
 }
 
 /// Task tool input for sub-agents.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaskInput {
     pub prompt: String,
     pub subagent_type: String,
@@ -286,18 +502,18 @@ pub struct TaskInput {
 impl ToolUseBlock {
     /// Check if this is a Task tool (sub-agent).
     pub fn is_task(&self) -> bool {
-        self.name == "Task"
+        self.name == TASK_TOOL
     }
 
     /// Check if this is an AskUserQuestion tool (prompt).
     pub fn is_ask_user_question(&self) -> bool {
-        self.name == "AskUserQuestion"
+        self.name == ASK_USER_QUESTION_TOOL
     }
 
     /// Try to parse input as AskUserQuestion.
     pub fn as_ask_user_question(&self) -> Option<AskUserQuestionInput> {
         if self.is_ask_user_question() {
-            serde_json::from_value(self.input.clone()).ok()
+            AskUserQuestionInput::deserialize(&self.input).ok()
         } else {
             None
         }
@@ -306,45 +522,31 @@ impl ToolUseBlock {
     /// Try to parse input as Task (sub-agent).
     pub fn as_task(&self) -> Option<TaskInput> {
         if self.is_task() {
-            serde_json::from_value(self.input.clone()).ok()
+            TaskInput::deserialize(&self.input).ok()
         } else {
             None
         }
     }
 
+    /// The provider-neutral form of this tool use.
+    pub fn tool_call(&self) -> ToolCall {
+        ToolCall {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+            input: self.input.clone(),
+        }
+    }
+
     /// Get a preview string for the tool input.
     pub fn input_preview(&self) -> Option<String> {
+        let field = |name: &str| self.input.get(name).and_then(Value::as_str);
         match self.name.as_str() {
-            "Read" | "Write" => self
-                .input
-                .get("file_path")
-                .and_then(|v| v.as_str())
-                .map(|s| truncate_path(s, 40)),
-            "Bash" => self
-                .input
-                .get("command")
-                .and_then(|v| v.as_str())
-                .map(|s| truncate_str(s, 50)),
-            "Glob" | "Grep" => self
-                .input
-                .get("pattern")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            "Edit" => self
-                .input
-                .get("file_path")
-                .and_then(|v| v.as_str())
-                .map(|s| truncate_path(s, 40)),
-            "Task" => self
-                .input
-                .get("description")
-                .and_then(|v| v.as_str())
-                .map(|s| truncate_str(s, 40)),
-            "WebFetch" | "WebSearch" => self
-                .input
-                .get("url")
-                .or_else(|| self.input.get("query"))
-                .and_then(|v| v.as_str())
+            "Read" | "Write" | "Edit" => field("file_path").map(|s| truncate_path(s, 40)),
+            "Bash" => field("command").map(|s| truncate_str(s, 50)),
+            "Glob" | "Grep" => field("pattern").map(String::from),
+            "Task" => field("description").map(|s| truncate_str(s, 40)),
+            "WebFetch" | "WebSearch" => field("url")
+                .or_else(|| field("query"))
                 .map(|s| truncate_str(s, 40)),
             _ => None,
         }
@@ -405,12 +607,9 @@ mod tests {
         let msg = ClaudeCodeMessage::from_json(json).unwrap();
         match msg {
             ClaudeCodeMessage::System(s) => {
-                assert_eq!(s.subtype, Some("init".to_string()));
                 let tools = s.tools.unwrap();
                 assert_eq!(tools.len(), 4);
                 assert_eq!(tools[0].name(), "Task");
-                assert_eq!(tools[1].name(), "Bash");
-                assert_eq!(tools[2].name(), "Read");
                 assert_eq!(tools[3].name(), "Write");
             }
             _ => panic!("Expected System message"),
@@ -433,26 +632,14 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_parse_assistant_message_with_text() {
-        let json =
-            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Hello world"}]}}"#;
-        let msg = ClaudeCodeMessage::from_json(json).unwrap();
-        match msg {
-            ClaudeCodeMessage::Assistant(a) => {
-                assert_eq!(a.full_text(), "Hello world");
-                assert!(!a.has_tool_use());
-            }
-            _ => panic!("Expected Assistant message"),
-        }
-    }
-
     #[test]
     fn test_parse_assistant_message_with_tool_use() {
-        let json = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"tu_1","name":"Read","input":{"file_path":"/test.txt"}}]}}"#;
+        let json = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Reading"},{"type":"tool_use","id":"tu_1","name":"Read","input":{"file_path":"/test.txt"}}]}}"#;
         let msg = ClaudeCodeMessage::from_json(json).unwrap();
+        let calls = msg.tool_calls();
         match msg {
             ClaudeCodeMessage::Assistant(a) => {
+                assert_eq!(a.full_text(), "Reading");
                 assert!(a.has_tool_use());
                 let tools = a.tool_uses();
                 assert_eq!(tools.len(), 1);
@@ -461,6 +648,9 @@ mod tests {
             }
             _ => panic!("Expected Assistant message"),
         }
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id.as_deref(), Some("tu_1"));
+        assert_eq!(calls[0].input["file_path"], "/test.txt");
     }
 
     #[test]
@@ -472,27 +662,80 @@ mod tests {
                 let results = u.tool_results();
                 assert_eq!(results.len(), 1);
                 assert_eq!(results[0].tool_use_id, "tu_1");
-                assert!(!results[0].is_error);
+                assert!(!results[0].is_error());
             }
             _ => panic!("Expected User message"),
         }
     }
 
     #[test]
-    fn test_parse_result_message() {
-        let json = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":1234,"cost_usd":0.01}"#;
+    fn test_parse_user_message_with_text_content() {
+        let json = r#"{"type":"user","message":{"role":"user","content":"Fix the build"}}"#;
         let msg = ClaudeCodeMessage::from_json(json).unwrap();
+        match msg {
+            ClaudeCodeMessage::User(u) => {
+                assert_eq!(
+                    u.message.content,
+                    Content::Text("Fix the build".to_string())
+                );
+                assert!(u.tool_results().is_empty());
+            }
+            _ => panic!("Expected User message"),
+        }
+    }
+
+    #[test]
+    fn test_parse_result_message_usage() {
+        let json = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":1234,"total_cost_usd":0.01,"usage":{"input_tokens":10,"cache_creation_input_tokens":20,"cache_read_input_tokens":300,"output_tokens":40}}"#;
+        let msg = ClaudeCodeMessage::from_json(json).unwrap();
+        assert_eq!(
+            msg.turn_usage(),
+            Some(TokenUsage {
+                input_tokens: 330,
+                cached_input_tokens: 300,
+                output_tokens: 40,
+            })
+        );
         match msg {
             ClaudeCodeMessage::Result(r) => {
-                assert_eq!(r.subtype, Some("success".to_string()));
-                assert_eq!(r.is_error, Some(false));
+                assert!(!r.is_error());
                 assert_eq!(r.duration_ms, Some(1234));
-                assert_eq!(r.cost_usd, Some(0.01));
+                assert_eq!(r.cost(), Some(0.01));
             }
             _ => panic!("Expected Result message"),
         }
     }
 
+    #[test]
+    fn test_error_subtype_is_an_error() {
+        let json = r#"{"type":"result","subtype":"error_max_turns"}"#;
+        match ClaudeCodeMessage::from_json(json).unwrap() {
+            ClaudeCodeMessage::Result(r) => assert!(r.is_error()),
+            _ => panic!("Expected Result message"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_types_are_kept() {
+        let json = r#"{"type":"rate_limit","retry_after_ms":500}"#;
+        let msg = ClaudeCodeMessage::from_json(json).unwrap();
+        assert_eq!(msg.event_type(), "rate_limit");
+        assert!(matches!(msg, ClaudeCodeMessage::Other(_)));
+
+        let json = r#"{"type":"assistant","message":{"content":[{"type":"redacted_thinking","data":"x"}]}}"#;
+        let msg = ClaudeCodeMessage::from_json(json).unwrap();
+        match &msg {
+            ClaudeCodeMessage::Assistant(a) => {
+                assert!(matches!(a.message.blocks()[0], ContentBlock::Other(_)))
+            }
+            _ => panic!("Expected Assistant message"),
+        }
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            serde_json::from_str::<Value>(json).unwrap()
+        );
+    }
+
     #[test]
     fn test_ask_user_question_parsing() {
         let json = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"tu_1","name":"AskUserQuestion","input":{"questions":[{"question":"Which option?","options":[{"label":"A"},{"label":"B"}]}]}}]}}"#;
@@ -532,10 +775,10 @@ mod tests {
         let msg = ClaudeCodeMessage::from_json(json).unwrap();
         match msg {
             ClaudeCodeMessage::StreamEvent(s) => match s.event {
-                StreamEvent::ContentBlockDelta { delta, index } => {
+                StreamEvent::ContentBlockDelta { delta, index, .. } => {
                     assert_eq!(index, Some(0));
                     match delta {
-                        ContentDelta::TextDelta { text } => assert_eq!(text, "Hello"),
+                        ContentDelta::TextDelta { text, .. } => assert_eq!(text, "Hello"),
                         _ => panic!("Expected TextDelta"),
                     }
                 }
@@ -0,0 +1,385 @@
+//! Codex events, as printed by `codex exec --json`.
+//!
+//! A turn is a `thread.started` (first turn only), `turn.started`, the
+//! `item.*` events of its messages and tool calls, and a `turn.completed`
+//! or `turn.failed`.
+
+use crate::{TokenUsage, ToolCall};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Version of this model; see the crate docs.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Top-level Codex event discriminated by `type` field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CodexEvent {
+    #[serde(rename = "thread.started")]
+    ThreadStarted {
+        thread_id: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    #[serde(rename = "turn.started")]
+    TurnStarted {
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    #[serde(rename = "turn.completed")]
+    TurnCompleted {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    #[serde(rename = "turn.failed")]
+    TurnFailed {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<ErrorDetail>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    #[serde(rename = "item.started")]
+    ItemStarted {
+        item: ThreadItem,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    #[serde(rename = "item.updated")]
+    ItemUpdated {
+        item: ThreadItem,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    #[serde(rename = "item.completed")]
+    ItemCompleted {
+        item: ThreadItem,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    /// A stream-level error, e.g. a dropped connection.
+    #[serde(rename = "error")]
+    Error {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<ErrorDetail>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    /// An event of a type not modeled here, or one that does not match its
+    /// model, kept as received.
+    #[serde(untagged)]
+    Other(Value),
+}
+
+/// An error reported as a string or as an object with a message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ErrorDetail {
+    Text(String),
+    Object {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+}
+
+impl ErrorDetail {
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Text(message) => Some(message),
+            Self::Object { message, .. } => message.as_deref(),
+        }
+    }
+}
+
+/// Token usage of a turn. Codex counts cached input as part of
+/// `input_tokens`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Usage {
+    pub fn token_usage(&self) -> TokenUsage {
+        TokenUsage {
+            input_tokens: self.input_tokens.unwrap_or(0),
+            cached_input_tokens: self.cached_input_tokens.unwrap_or(0),
+            output_tokens: self.output_tokens.unwrap_or(0),
+        }
+    }
+}
+
+/// One item of a turn: a message, reasoning, or a tool call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThreadItem {
+    AgentMessage {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        text: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    Reasoning {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        text: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    CommandExecution {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        command: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        aggregated_output: Option<String>,
+        /// `Some(None)` while the command is still running.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::nullable"
+        )]
+        exit_code: Option<Option<i32>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<String>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    FileChange {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(default)]
+        changes: Vec<FileUpdate>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<String>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    McpToolCall {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        server: String,
+        tool: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<String>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    WebSearch {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        query: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    Error {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        message: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    #[serde(untagged)]
+    Other(Value),
+}
+
+/// One file touched by a `file_change` item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileUpdate {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CodexEvent {
+    /// Parse a raw JSON string into a CodexEvent.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Parse an already decoded JSON value.
+    pub fn from_value(json: &Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(json)
+    }
+
+    /// The error of a failed turn or of the stream, with a generic message
+    /// when Codex sent none.
+    pub fn error_message(&self) -> Option<String> {
+        let (Self::TurnFailed { message, error, .. } | Self::Error { message, error, .. }) = self
+        else {
+            return None;
+        };
+        Some(
+            message
+                .as_deref()
+                .or_else(|| error.as_ref().and_then(ErrorDetail::message))
+                .unwrap_or("Codex reported an error result")
+                .to_string(),
+        )
+    }
+
+    /// The text of a completed agent message.
+    pub fn agent_message(&self) -> Option<&str> {
+        match self {
+            Self::ItemCompleted {
+                item: ThreadItem::AgentMessage { text, .. },
+                ..
+            } => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Usage of the whole turn, reported when it completes.
+    pub fn turn_usage(&self) -> Option<TokenUsage> {
+        match self {
+            Self::TurnCompleted { usage, .. } => usage.as_ref().map(Usage::token_usage),
+            _ => None,
+        }
+    }
+
+    /// The tool call of a completed item. Counted on completion because
+    /// Codex only reports some items, like file changes, when they are done.
+    pub fn tool_call(&self) -> Option<ToolCall> {
+        match self {
+            Self::ItemCompleted { item, .. } => item.tool_call(),
+            _ => None,
+        }
+    }
+}
+
+impl ThreadItem {
+    /// The provider-neutral form of a tool call item; `None` for messages,
+    /// reasoning and errors.
+    pub fn tool_call(&self) -> Option<ToolCall> {
+        let (id, name, input) = match self {
+            Self::CommandExecution { id, command, .. } => (
+                id,
+                "command_execution".to_string(),
+                serde_json::json!({ "command": command }),
+            ),
+            Self::FileChange { id, changes, .. } => (
+                id,
+                "file_change".to_string(),
+                serde_json::json!({ "changes": changes }),
+            ),
+            Self::McpToolCall {
+                id,
+                server,
+                tool,
+                extra,
+                ..
+            } => (
+                id,
+                format!("{server}.{tool}"),
+                extra.get("arguments").cloned().unwrap_or(Value::Null),
+            ),
+            Self::WebSearch { id, query, .. } => (
+                id,
+                "web_search".to_string(),
+                serde_json::json!({ "query": query }),
+            ),
+            _ => return None,
+        };
+        Some(ToolCall {
+            id: id.clone(),
+            name,
+            input,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_turn_lifecycle() {
+        let started =
+            CodexEvent::from_json(r#"{"type":"thread.started","thread_id":"thread-1"}"#).unwrap();
+        assert!(matches!(
+            started,
+            CodexEvent::ThreadStarted { ref thread_id, .. } if thread_id == "thread-1"
+        ));
+
+        let completed = CodexEvent::from_json(
+            r#"{"type":"turn.completed","usage":{"input_tokens":1200,"cached_input_tokens":1000,"output_tokens":30}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            completed.turn_usage(),
+            Some(TokenUsage {
+                input_tokens: 1200,
+                cached_input_tokens: 1000,
+                output_tokens: 30,
+            })
+        );
+    }
+
+    #[test]
+    fn error_messages_come_from_strings_or_objects() {
+        let failed =
+            CodexEvent::from_json(r#"{"type":"turn.failed","error":{"message":"quota exceeded"}}"#)
+                .unwrap();
+        assert_eq!(failed.error_message().as_deref(), Some("quota exceeded"));
+
+        let error = CodexEvent::from_json(r#"{"type":"error","message":"stream lost"}"#).unwrap();
+        assert_eq!(error.error_message().as_deref(), Some("stream lost"));
+
+        let bare = CodexEvent::from_json(r#"{"type":"turn.failed"}"#).unwrap();
+        assert_eq!(
+            bare.error_message().as_deref(),
+            Some("Codex reported an error result")
+        );
+    }
+
+    #[test]
+    fn completed_items_carry_messages_and_tool_calls() {
+        let message = CodexEvent::from_json(
+            r#"{"type":"item.completed","item":{"id":"item_1","type":"agent_message","text":"Done"}}"#,
+        )
+        .unwrap();
+        assert_eq!(message.agent_message(), Some("Done"));
+        assert_eq!(message.tool_call(), None);
+
+        let command = CodexEvent::from_json(
+            r#"{"type":"item.completed","item":{"id":"item_0","type":"command_execution","command":"ls","aggregated_output":"a\n","exit_code":0,"status":"completed"}}"#,
+        )
+        .unwrap();
+        let call = command.tool_call().unwrap();
+        assert_eq!(call.id.as_deref(), Some("item_0"));
+        assert_eq!(call.name, "command_execution");
+        assert_eq!(call.input["command"], "ls");
+    }
+
+    #[test]
+    fn running_command_keeps_its_null_exit_code() {
+        let raw = r#"{"type":"item.started","item":{"type":"command_execution","command":"ls","exit_code":null}}"#;
+        let event = CodexEvent::from_json(raw).unwrap();
+        match &event {
+            CodexEvent::ItemStarted {
+                item: ThreadItem::CommandExecution { exit_code, .. },
+                ..
+            } => assert_eq!(*exit_code, Some(None)),
+            _ => panic!("Expected a command execution"),
+        }
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::from_str::<Value>(raw).unwrap()
+        );
+    }
+}
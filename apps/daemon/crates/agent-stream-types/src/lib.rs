@@ -0,0 +1,71 @@
+//! Typed models of the JSON event streams printed by coding agent CLIs.
+//!
+//! - [`claude`]: Claude Code `--output-format stream-json`
+//! - [`codex`]: `codex exec --json`
+//!
+//! Every object keeps the fields it does not model in a flattened `extra`
+//! map, and every tagged enum has an `Other` variant holding unknown types
+//! as received, so parsing and re-serializing an event is lossless. Each
+//! module has a `SCHEMA_VERSION`, bumped whenever a model changes in a way
+//! callers can observe.
+//!
+//! This crate contains only data types and serialization — no I/O, no async.
+
+pub mod claude;
+pub mod codex;
+
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+
+/// Token usage normalized across providers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// All input tokens, cached ones included.
+    pub input_tokens: u64,
+    /// Input tokens served from the provider's prompt cache.
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+/// A tool call normalized across providers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Provider id of the call, used to match its result.
+    pub id: Option<String>,
+    /// Tool name, e.g. `Bash` for Claude or `command_execution` for Codex.
+    pub name: String,
+    pub input: serde_json::Value,
+}
+
+/// Serde helpers for `Option<Option<T>>` fields that tell an explicit
+/// `null` (`Some(None)`) from an absent field (`None`).
+pub(crate) mod nullable {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, T>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        match value {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+}
@@ -0,0 +1,274 @@
+//! Round-trip properties checked against captured CLI output.
+//!
+//! Fixtures are named after the schema version they were captured for.
+//! Besides parsing every line losslessly, each line is re-parsed with
+//! random unknown fields added at random depths, which must neither be
+//! lost nor change what the typed model reads.
+
+use agent_stream_types::claude::ClaudeCodeMessage;
+use agent_stream_types::codex::CodexEvent;
+use agent_stream_types::{claude, codex, TokenUsage};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt::Debug;
+
+/// Mutated copies checked per fixture line.
+const CASES_PER_LINE: usize = 64;
+
+const CLAUDE_FIXTURE: &str = include_str!("fixtures/claude_stream_v1.jsonl");
+const CODEX_FIXTURE: &str = include_str!("fixtures/codex_exec_v1.jsonl");
+
+/// Message types the Claude model covers; all others parse as `Other`.
+const CLAUDE_MODELED_TYPES: &[&str] = &["system", "assistant", "user", "result", "stream_event"];
+
+/// Event types the Codex model covers.
+const CODEX_MODELED_TYPES: &[&str] = &[
+    "thread.started",
+    "turn.started",
+    "turn.completed",
+    "turn.failed",
+    "item.started",
+    "item.updated",
+    "item.completed",
+    "error",
+];
+
+fn fixture_lines(fixture: &str) -> Vec<Value> {
+    fixture
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("fixture line is JSON"))
+        .collect()
+}
+
+fn round_trip<T: Serialize + DeserializeOwned>(json: &Value) -> (T, Value) {
+    let parsed: T = serde_json::from_value(json.clone()).expect("fixture line parses");
+    let serialized = serde_json::to_value(&parsed).unwrap();
+    (parsed, serialized)
+}
+
+/// Add a field with an unused name and a random value to a random object
+/// inside `json`.
+fn add_unknown_field(json: &mut Value, rng: &mut StdRng) {
+    let mut objects = Vec::new();
+    collect_objects(json, &mut objects);
+    let target = objects[rng.gen_range(0..objects.len())].clone();
+    let value = match rng.gen_range(0..5) {
+        0 => Value::Null,
+        1 => json!(rng.gen::<bool>()),
+        2 => json!(rng.gen_range(-1000..1000)),
+        3 => json!(format!("value-{}", rng.gen::<u16>())),
+        _ => json!({ "nested": [rng.gen::<u8>(), null] }),
+    };
+    let name = format!("unmodeled_{}", rng.gen::<u32>());
+    json.pointer_mut(&target)
+        .and_then(Value::as_object_mut)
+        .expect("collected path is an object")
+        .insert(name, value);
+}
+
+fn collect_objects(json: &Value, paths: &mut Vec<String>) {
+    fn walk(json: &Value, path: String, paths: &mut Vec<String>) {
+        match json {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    walk(value, format!("{path}/{}", escape(key)), paths);
+                }
+                paths.push(path);
+            }
+            Value::Array(items) => {
+                for (index, value) in items.iter().enumerate() {
+                    walk(value, format!("{path}/{index}"), paths);
+                }
+            }
+            _ => {}
+        }
+    }
+    walk(json, String::new(), paths);
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// What the typed model reads from a line, compared before and after
+/// unknown fields are added.
+trait TypedView {
+    type View: PartialEq + Debug;
+
+    fn view(&self) -> Self::View;
+}
+
+impl TypedView for ClaudeCodeMessage {
+    type View = (
+        String,
+        Option<String>,
+        String,
+        Vec<String>,
+        Option<TokenUsage>,
+    );
+
+    fn view(&self) -> Self::View {
+        let text = match self {
+            ClaudeCodeMessage::Assistant(assistant) => assistant.full_text(),
+            _ => String::new(),
+        };
+        (
+            self.event_type().to_string(),
+            self.session_id().map(str::to_string),
+            text,
+            self.tool_calls()
+                .into_iter()
+                .map(|call| call.name)
+                .collect(),
+            self.turn_usage(),
+        )
+    }
+}
+
+impl TypedView for CodexEvent {
+    type View = (
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<TokenUsage>,
+    );
+
+    fn view(&self) -> Self::View {
+        (
+            self.error_message(),
+            self.agent_message().map(str::to_string),
+            self.tool_call().map(|call| call.name),
+            self.turn_usage(),
+        )
+    }
+}
+
+fn check_fixture<T>(fixture: &str, modeled_types: &[&str], is_other: fn(&T) -> bool, seed: u64)
+where
+    T: Serialize + DeserializeOwned + TypedView,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    for (line_number, line) in fixture_lines(fixture).iter().enumerate() {
+        let (parsed, serialized) = round_trip::<T>(line);
+        assert_eq!(&serialized, line, "line {} changed", line_number + 1);
+
+        let event_type = line["type"].as_str().unwrap_or_default();
+        assert_eq!(
+            is_other(&parsed),
+            !modeled_types.contains(&event_type),
+            "line {} ({event_type}) fell back to Other",
+            line_number + 1
+        );
+
+        for _ in 0..CASES_PER_LINE {
+            let mut mutated = line.clone();
+            for _ in 0..rng.gen_range(1..4) {
+                add_unknown_field(&mut mutated, &mut rng);
+            }
+            let (reparsed, reserialized) = round_trip::<T>(&mutated);
+            assert_eq!(
+                reserialized,
+                mutated,
+                "line {} lost an unknown field",
+                line_number + 1
+            );
+            assert_eq!(
+                reparsed.view(),
+                parsed.view(),
+                "line {} read differently with unknown fields: {mutated}",
+                line_number + 1
+            );
+        }
+    }
+}
+
+#[test]
+fn fixtures_match_schema_versions() {
+    assert_eq!(claude::SCHEMA_VERSION, 1);
+    assert_eq!(codex::SCHEMA_VERSION, 1);
+}
+
+#[test]
+fn claude_fixture_round_trips_with_unknown_fields() {
+    check_fixture::<ClaudeCodeMessage>(
+        CLAUDE_FIXTURE,
+        CLAUDE_MODELED_TYPES,
+        |message| matches!(message, ClaudeCodeMessage::Other(_)),
+        0x0c1a_0de0,
+    );
+}
+
+#[test]
+fn codex_fixture_round_trips_with_unknown_fields() {
+    check_fixture::<CodexEvent>(
+        CODEX_FIXTURE,
+        CODEX_MODELED_TYPES,
+        |event| matches!(event, CodexEvent::Other(_)),
+        0x0c0d_e700,
+    );
+}
+
+#[test]
+fn claude_fixture_reads_usage_and_tool_calls() {
+    let messages: Vec<ClaudeCodeMessage> = fixture_lines(CLAUDE_FIXTURE)
+        .iter()
+        .map(|line| ClaudeCodeMessage::from_value(line).unwrap())
+        .collect();
+
+    let tool_names: Vec<String> = messages
+        .iter()
+        .flat_map(ClaudeCodeMessage::tool_calls)
+        .map(|call| call.name)
+        .collect();
+    assert_eq!(
+        tool_names,
+        [
+            "Read",
+            "Edit",
+            "Task",
+            "AskUserQuestion",
+            "mcp__linear__list_issues"
+        ]
+    );
+
+    let usage = messages
+        .iter()
+        .find_map(ClaudeCodeMessage::turn_usage)
+        .unwrap();
+    assert_eq!(usage.input_tokens, 45 + 4439 + 121072);
+    assert_eq!(usage.cached_input_tokens, 121072);
+    assert_eq!(usage.output_tokens, 433);
+}
+
+#[test]
+fn codex_fixture_reads_usage_and_tool_calls() {
+    let events: Vec<CodexEvent> = fixture_lines(CODEX_FIXTURE)
+        .iter()
+        .map(|line| CodexEvent::from_value(line).unwrap())
+        .collect();
+
+    let tool_names: Vec<String> = events
+        .iter()
+        .filter_map(CodexEvent::tool_call)
+        .map(|call| call.name)
+        .collect();
+    assert_eq!(
+        tool_names,
+        [
+            "command_execution",
+            "file_change",
+            "linear.list_issues",
+            "web_search",
+            "command_execution",
+        ]
+    );
+
+    let usage = events.iter().find_map(CodexEvent::turn_usage).unwrap();
+    assert_eq!(usage.input_tokens, 24763);
+    assert_eq!(usage.cached_input_tokens, 24448);
+    assert_eq!(usage.output_tokens, 122);
+}
@@ -1,8 +1,18 @@
 //! Configuration for Claude CLI processes.
 
+use crate::error::ClaudeProcessError;
+use login_shell::Shell;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
 /// Default allowed tools for Claude CLI.
 pub const DEFAULT_ALLOWED_TOOLS: &str = "AskUserQuestion,Bash,TaskOutput,Edit,ExitPlanMode,Glob,Grep,KillShell,MCPSearch,NotebookEdit,Read,Skill,Task,TaskCreate,TaskGet,TaskList,TaskUpdate,WebFetch,WebSearch,Write";
 
+/// Tools allowed without asking when permission prompts are routed to a tool.
+///
+/// Everything else (shell, edits, network) goes through the prompt tool.
+pub const PROMPTED_ALLOWED_TOOLS: &str = "AskUserQuestion,TaskOutput,ExitPlanMode,Glob,Grep,MCPSearch,Read,Skill,Task,TaskCreate,TaskGet,TaskList,TaskUpdate";
+
 /// Configuration for spawning a Claude process.
 #[derive(Debug, Clone)]
 pub struct ClaudeConfig {
@@ -20,11 +30,120 @@ pub struct ClaudeConfig {
 
     /// Optional permission mode for Claude CLI.
     pub permission_mode: Option<PermissionMode>,
+
+    /// Optional MCP tool that answers permission prompts.
+    pub permission_prompt: Option<PermissionPromptTool>,
+
+    /// Optional file declaring further MCP servers (`--mcp-config`).
+    pub mcp_config_file: Option<McpConfigFile>,
+
+    /// Optional tool result sent as the turn's input instead of `message`.
+    pub tool_result: Option<ToolResultInput>,
+
+    /// Keep the process running after its first turn, reading further turns
+    /// from stdin; it shuts down after this long without one.
+    pub keep_alive: Option<Duration>,
+
+    /// Extra environment variables for the process. They may hold secrets,
+    /// so only their count is logged.
+    pub env: Vec<(String, String)>,
+
+    /// The shell that runs the CLI, with its login environment.
+    pub shell: Shell,
+}
+
+/// A `tool_result` content block answering an earlier `tool_use`.
+///
+/// Sent over stdin as a stream-json user message, which lets a resumed session
+/// continue from a tool call that was left waiting (e.g. `AskUserQuestion`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolResultInput {
+    /// Id of the `tool_use` block being answered.
+    pub tool_use_id: String,
+    /// Result text handed back to the model.
+    pub content: String,
+}
+
+/// An MCP tool Claude calls to decide tool-use permissions
+/// (`--permission-prompt-tool`), together with the server that hosts it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionPromptTool {
+    /// Inline JSON passed to `--mcp-config`, declaring the hosting server.
+    pub mcp_config: String,
+    /// Fully qualified tool name, e.g. `mcp__unbound__approval_prompt`.
+    pub tool_name: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An `--mcp-config` file and the JSON it holds.
+///
+/// The caller writes `contents` to `path` before spawning and removes it once
+/// the process has exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpConfigFile {
+    /// Where the config is written.
+    pub path: String,
+    /// The `{"mcpServers": ...}` JSON document.
+    pub contents: String,
+}
+
+/// How Claude CLI asks for tool-use permissions (`--permission-mode`).
+///
+/// Serializes to the CLI's own spelling, e.g. `"acceptEdits"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum PermissionMode {
+    /// Ask before each tool use not already allowed.
+    Default,
+    /// Apply file edits without asking.
+    AcceptEdits,
+    /// Read-only planning; no edits or commands until the plan is accepted.
     Plan,
+    /// Skip all permission checks.
+    BypassPermissions,
+}
+
+impl PermissionMode {
+    /// Every mode, in the order Claude documents them.
+    pub const ALL: [PermissionMode; 4] = [
+        PermissionMode::Default,
+        PermissionMode::AcceptEdits,
+        PermissionMode::Plan,
+        PermissionMode::BypassPermissions,
+    ];
+
+    /// The mode's name as Claude CLI spells it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PermissionMode::Default => "default",
+            PermissionMode::AcceptEdits => "acceptEdits",
+            PermissionMode::Plan => "plan",
+            PermissionMode::BypassPermissions => "bypassPermissions",
+        }
+    }
+}
+
+impl std::fmt::Display for PermissionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PermissionMode {
+    type Err = ClaudeProcessError;
+
+    /// Parse a mode name as Claude CLI spells it.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == value)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|mode| mode.as_str()).collect();
+                ClaudeProcessError::Config(format!(
+                    "unknown permission mode {value:?}, expected one of: {}",
+                    names.join(", ")
+                ))
+            })
+    }
 }
 
 impl ClaudeConfig {
@@ -36,6 +155,12 @@ impl ClaudeConfig {
             resume_session_id: None,
             allowed_tools: None,
             permission_mode: None,
+            permission_prompt: None,
+            mcp_config_file: None,
+            tool_result: None,
+            keep_alive: None,
+            env: Vec::new(),
+            shell: Shell::login().clone(),
         }
     }
 
@@ -57,35 +182,162 @@ impl ClaudeConfig {
         self
     }
 
+    /// Route permission prompts through an MCP tool.
+    ///
+    /// Unless custom allowed tools are set, only [`PROMPTED_ALLOWED_TOOLS`] are
+    /// pre-approved so every other tool use reaches the prompt tool.
+    pub fn with_permission_prompt(mut self, permission_prompt: PermissionPromptTool) -> Self {
+        self.permission_prompt = Some(permission_prompt);
+        self
+    }
+
+    /// Load further MCP servers from a config file.
+    pub fn with_mcp_config_file(mut self, mcp_config_file: McpConfigFile) -> Self {
+        self.mcp_config_file = Some(mcp_config_file);
+        self
+    }
+
+    /// Answer a pending tool use instead of sending `message` as a prompt.
+    ///
+    /// Usually combined with [`Self::with_resume_session`].
+    pub fn with_tool_result(
+        mut self,
+        tool_use_id: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Self {
+        self.tool_result = Some(ToolResultInput {
+            tool_use_id: tool_use_id.into(),
+            content: content.into(),
+        });
+        self
+    }
+
+    /// Keep the process alive between turns, see [`crate::ClaudeInput`].
+    ///
+    /// The first turn's input is written to stdin, which stays open for the
+    /// next ones until `idle_timeout` passes without a turn.
+    pub fn with_keep_alive(mut self, idle_timeout: Duration) -> Self {
+        self.keep_alive = Some(idle_timeout);
+        self
+    }
+
+    /// Set extra environment variables for the process.
+    pub fn with_env(mut self, env: Vec<(String, String)>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Run the CLI in `shell` instead of the user's login shell.
+    pub fn with_shell(mut self, shell: Shell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Whether a keep-alive process started with this configuration can run
+    /// a turn configured as `other`: everything fixed at launch must match.
+    /// MCP config files are compared by contents, since each spawn writes
+    /// its own.
+    pub fn can_share_process(&self, other: &ClaudeConfig) -> bool {
+        self.working_dir == other.working_dir
+            && self.allowed_tools() == other.allowed_tools()
+            && self.permission_mode == other.permission_mode
+            && self.permission_prompt == other.permission_prompt
+            && self.env == other.env
+            && self.shell == other.shell
+            && self.mcp_config_file.as_ref().map(|file| &file.contents)
+                == other.mcp_config_file.as_ref().map(|file| &file.contents)
+    }
+
+    /// The stream-json user message written to stdin, if input goes there.
+    pub fn stdin_message(&self) -> Option<String> {
+        if let Some(tool_result) = &self.tool_result {
+            return Some(tool_result_event(
+                &tool_result.tool_use_id,
+                &tool_result.content,
+            ));
+        }
+        self.keep_alive.map(|_| user_message_event(&self.message))
+    }
+
     /// Get the allowed tools string.
     pub fn allowed_tools(&self) -> &str {
         self.allowed_tools
             .as_deref()
-            .unwrap_or(DEFAULT_ALLOWED_TOOLS)
+            .unwrap_or(if self.permission_prompt.is_some() {
+                PROMPTED_ALLOWED_TOOLS
+            } else {
+                DEFAULT_ALLOWED_TOOLS
+            })
     }
 
     /// Build the Claude CLI command string.
     pub(crate) fn build_command(&self) -> String {
-        let escaped_message = shell_escape(&self.message);
         let allowed_tools = self.allowed_tools();
 
+        // Tool results and keep-alive turns are read from stdin
+        let input = if self.tool_result.is_some() || self.keep_alive.is_some() {
+            "--input-format stream-json".to_string()
+        } else {
+            shell_escape(&self.message)
+        };
+
         let mut cmd = format!(
             "claude -p {} --verbose --output-format stream-json --allowedTools {}",
-            escaped_message, allowed_tools
+            input, allowed_tools
         );
 
         if let Some(ref session_id) = self.resume_session_id {
             cmd.push_str(&format!(" -r {}", session_id));
         }
 
-        if matches!(self.permission_mode, Some(PermissionMode::Plan)) {
-            cmd.push_str(" --permission-mode plan");
+        if let Some(permission_mode) = self.permission_mode {
+            cmd.push_str(&format!(" --permission-mode {}", permission_mode));
+        }
+
+        if let Some(ref prompt) = self.permission_prompt {
+            cmd.push_str(&format!(
+                " --mcp-config {} --permission-prompt-tool {}",
+                shell_escape(&prompt.mcp_config),
+                shell_escape(&prompt.tool_name)
+            ));
+        }
+
+        if let Some(ref file) = self.mcp_config_file {
+            cmd.push_str(&format!(" --mcp-config {}", shell_escape(&file.path)));
         }
 
         cmd
     }
 }
 
+/// A stream-json user message carrying prompt text.
+pub(crate) fn user_message_event(text: &str) -> String {
+    serde_json::json!({
+        "type": "user",
+        "message": {
+            "role": "user",
+            "content": [{ "type": "text", "text": text }],
+        },
+    })
+    .to_string()
+}
+
+/// A stream-json user message answering the tool use `tool_use_id`.
+pub(crate) fn tool_result_event(tool_use_id: &str, content: &str) -> String {
+    serde_json::json!({
+        "type": "user",
+        "message": {
+            "role": "user",
+            "content": [{
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": content,
+            }],
+        },
+    })
+    .to_string()
+}
+
 /// Escape a string for shell usage.
 fn shell_escape(s: &str) -> String {
     // Use single quotes and escape any single quotes within
@@ -105,6 +357,10 @@ mod tests {
         assert!(config.resume_session_id.is_none());
         assert!(config.allowed_tools.is_none());
         assert!(config.permission_mode.is_none());
+        assert!(config.permission_prompt.is_none());
+        assert!(config.tool_result.is_none());
+        assert!(config.keep_alive.is_none());
+        assert!(config.stdin_message().is_none());
     }
 
     #[test]
@@ -136,6 +392,122 @@ mod tests {
         assert!(cmd.contains("--permission-mode plan"));
     }
 
+    #[test]
+    fn test_build_command_with_every_permission_mode() {
+        for mode in PermissionMode::ALL {
+            let cmd = ClaudeConfig::new("Hello", "/tmp")
+                .with_permission_mode(mode)
+                .build_command();
+            assert!(cmd.contains(&format!("--permission-mode {}", mode.as_str())));
+        }
+        let cmd = ClaudeConfig::new("Hello", "/tmp").build_command();
+        assert!(!cmd.contains("--permission-mode"));
+    }
+
+    #[test]
+    fn test_permission_mode_parse_round_trip() {
+        for mode in PermissionMode::ALL {
+            assert_eq!(mode.as_str().parse::<PermissionMode>().unwrap(), mode);
+        }
+        for mode in PermissionMode::ALL {
+            let json = serde_json::to_value(mode).unwrap();
+            assert_eq!(json, mode.as_str());
+            assert_eq!(
+                serde_json::from_value::<PermissionMode>(json).unwrap(),
+                mode
+            );
+        }
+        let err = "accept_edits".parse::<PermissionMode>().unwrap_err();
+        assert!(err.to_string().contains("acceptEdits"));
+    }
+
+    #[test]
+    fn test_build_command_with_mcp_config_file() {
+        let config = ClaudeConfig::new("Hello", "/tmp").with_mcp_config_file(McpConfigFile {
+            path: "/tmp/mcp configs/session.json".to_string(),
+            contents: r#"{"mcpServers":{}}"#.to_string(),
+        });
+        let cmd = config.build_command();
+        assert!(cmd.ends_with(" --mcp-config '/tmp/mcp configs/session.json'"));
+    }
+
+    #[test]
+    fn test_build_command_with_permission_prompt() {
+        let config =
+            ClaudeConfig::new("Hello", "/tmp").with_permission_prompt(PermissionPromptTool {
+                mcp_config: r#"{"mcpServers":{}}"#.to_string(),
+                tool_name: "mcp__unbound__approval_prompt".to_string(),
+            });
+        let cmd = config.build_command();
+        assert!(cmd.contains(r#"--mcp-config '{"mcpServers":{}}'"#));
+        assert!(cmd.contains("--permission-prompt-tool 'mcp__unbound__approval_prompt'"));
+        assert!(cmd.contains(&format!("--allowedTools {}", PROMPTED_ALLOWED_TOOLS)));
+        assert!(!config.allowed_tools().split(',').any(|tool| tool == "Bash"));
+    }
+
+    #[test]
+    fn test_build_command_with_tool_result() {
+        let config = ClaudeConfig::new("", "/tmp")
+            .with_resume_session("sess-abc")
+            .with_tool_result("toolu_1", "User picked \"A\"");
+        let cmd = config.build_command();
+        assert!(cmd.contains("claude -p --input-format stream-json --verbose"));
+        assert!(cmd.contains("-r sess-abc"));
+
+        let message: serde_json::Value =
+            serde_json::from_str(&config.stdin_message().unwrap()).unwrap();
+        assert_eq!(message["type"], "user");
+        let block = &message["message"]["content"][0];
+        assert_eq!(block["type"], "tool_result");
+        assert_eq!(block["tool_use_id"], "toolu_1");
+        assert_eq!(block["content"], "User picked \"A\"");
+    }
+
+    #[test]
+    fn test_build_command_with_keep_alive() {
+        let config = ClaudeConfig::new("Hello world", "/tmp")
+            .with_resume_session("sess-abc")
+            .with_keep_alive(Duration::from_secs(300));
+        let cmd = config.build_command();
+        assert!(cmd.contains("claude -p --input-format stream-json --verbose"));
+        assert!(!cmd.contains("Hello world"));
+
+        let message: serde_json::Value =
+            serde_json::from_str(&config.stdin_message().unwrap()).unwrap();
+        assert_eq!(message["type"], "user");
+        assert_eq!(message["message"]["content"][0]["text"], "Hello world");
+    }
+
+    #[test]
+    fn test_can_share_process() {
+        let running = ClaudeConfig::new("first", "/tmp").with_keep_alive(Duration::from_secs(60));
+        let next = ClaudeConfig::new("second", "/tmp").with_resume_session("sess-abc");
+        assert!(running.can_share_process(&next));
+        assert!(running.can_share_process(&next.clone().with_tool_result("toolu_1", "ok")));
+
+        assert!(!running.can_share_process(&ClaudeConfig::new("second", "/other")));
+        assert!(
+            !running.can_share_process(&next.clone().with_permission_mode(PermissionMode::Plan))
+        );
+        assert!(!running.can_share_process(&next.clone().with_allowed_tools("Read")));
+        assert!(!running.can_share_process(
+            &next
+                .clone()
+                .with_env(vec![("API_TOKEN".to_string(), "t".to_string())])
+        ));
+
+        let mcp = |path: &str, contents: &str| McpConfigFile {
+            path: path.to_string(),
+            contents: contents.to_string(),
+        };
+        let running = running.with_mcp_config_file(mcp("/a.json", "{}"));
+        assert!(running.can_share_process(&next.clone().with_mcp_config_file(mcp("/b.json", "{}"))));
+        assert!(
+            !running.can_share_process(&next.clone().with_mcp_config_file(mcp("/a.json", "[]")))
+        );
+        assert!(!running.can_share_process(&next));
+    }
+
     #[test]
     fn test_shell_escape() {
         assert_eq!(shell_escape("hello"), "'hello'");
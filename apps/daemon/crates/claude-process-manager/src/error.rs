@@ -13,6 +13,10 @@ pub enum ClaudeProcessError {
     #[error("Failed to get stdout from Claude process")]
     NoStdout,
 
+    /// Stdin of a keep-alive process was closed, e.g. after its idle timeout.
+    #[error("Claude process input is closed")]
+    InputClosed,
+
     /// Process was killed.
     #[error("Claude process was killed")]
     Killed,
@@ -1,5 +1,8 @@
 //! Claude CLI events.
 
+use agent_stream_types::claude::{ClaudeCodeMessage, Usage};
+use agent_stream_types::TokenUsage;
+
 /// An event emitted by the Claude CLI process.
 #[derive(Debug, Clone)]
 pub enum ClaudeEvent {
@@ -25,6 +28,8 @@ pub enum ClaudeEvent {
     Result {
         /// Whether the result indicates an error.
         is_error: bool,
+        /// Token usage of the turn, if Claude reported it.
+        usage: Option<TokenUsage>,
         /// The raw JSON string.
         raw: String,
     },
@@ -50,15 +55,21 @@ pub enum ClaudeEvent {
 impl ClaudeEvent {
     /// Create a JSON event from parsed data.
     pub(crate) fn from_json(raw: String, json: serde_json::Value) -> Self {
-        let event_type = json
-            .get("type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
+        // Unknown or malformed messages parse as `Other`, keeping their type
+        let message = ClaudeCodeMessage::from_value(&json)
+            .unwrap_or_else(|_| ClaudeCodeMessage::Other(json.clone()));
+
+        if let ClaudeCodeMessage::Result(result) = &message {
+            return Self::Result {
+                is_error: result.is_error == Some(true),
+                usage: result.usage.as_ref().map(Usage::token_usage),
+                raw,
+            };
+        }
 
-        // Check for special event types
+        let event_type = message.event_type().to_string();
         if event_type == "system" {
-            if let Some(session_id) = json.get("session_id").and_then(|v| v.as_str()) {
+            if let Some(session_id) = message.session_id() {
                 return Self::SystemWithSessionId {
                     claude_session_id: session_id.to_string(),
                     raw,
@@ -66,14 +77,6 @@ impl ClaudeEvent {
             }
         }
 
-        if event_type == "result" {
-            let is_error = json
-                .get("is_error")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-            return Self::Result { is_error, raw };
-        }
-
         Self::Json {
             event_type,
             raw,
@@ -152,13 +155,43 @@ mod tests {
         let event = ClaudeEvent::from_json(json.to_string(), json);
 
         match event {
-            ClaudeEvent::Result { is_error, .. } => {
+            ClaudeEvent::Result {
+                is_error, usage, ..
+            } => {
                 assert!(!is_error);
+                assert_eq!(usage, None);
             }
             _ => panic!("Expected Result"),
         }
     }
 
+    #[test]
+    fn test_from_json_result_usage() {
+        let json = serde_json::json!({
+            "type": "result",
+            "subtype": "success",
+            "is_error": false,
+            "usage": {
+                "input_tokens": 12,
+                "cache_read_input_tokens": 400,
+                "output_tokens": 30
+            }
+        });
+        let event = ClaudeEvent::from_json(json.to_string(), json);
+
+        match event {
+            ClaudeEvent::Result { usage, .. } => assert_eq!(
+                usage,
+                Some(TokenUsage {
+                    input_tokens: 412,
+                    cached_input_tokens: 400,
+                    output_tokens: 30,
+                })
+            ),
+            _ => panic!("Expected Result"),
+        }
+    }
+
     #[test]
     fn test_is_terminal() {
         assert!(ClaudeEvent::Finished {
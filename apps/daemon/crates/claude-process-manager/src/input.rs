@@ -0,0 +1,116 @@
+//! Stdin of keep-alive Claude processes.
+
+use crate::config::{tool_result_event, user_message_event};
+use crate::error::{ClaudeProcessError, ClaudeProcessResult};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::ChildStdin;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Writes turns to a keep-alive Claude process as stream-json user messages.
+///
+/// Clones share the same stdin. Every message starts a turn, which the
+/// process's [`crate::ClaudeEventStream`] ends with a `Result` event. Once
+/// stdin is closed, by [`Self::close`] or the idle timeout, sends fail with
+/// [`ClaudeProcessError::InputClosed`].
+#[derive(Clone)]
+pub struct ClaudeInput {
+    shared: Arc<InputShared>,
+}
+
+struct InputShared {
+    stdin: Mutex<Option<ChildStdin>>,
+    /// Turns started through this input, counted under the stdin lock.
+    turns_started: AtomicU64,
+}
+
+impl ClaudeInput {
+    pub(crate) fn new(stdin: ChildStdin) -> Self {
+        Self {
+            shared: Arc::new(InputShared {
+                stdin: Mutex::new(Some(stdin)),
+                turns_started: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Start a turn with a prompt.
+    pub async fn send_message(&self, message: &str) -> ClaudeProcessResult<()> {
+        self.write_turn(user_message_event(message)).await
+    }
+
+    /// Start a turn answering the pending tool use `tool_use_id`.
+    pub async fn send_tool_result(
+        &self,
+        tool_use_id: &str,
+        content: &str,
+    ) -> ClaudeProcessResult<()> {
+        self.write_turn(tool_result_event(tool_use_id, content))
+            .await
+    }
+
+    /// Close stdin; Claude exits once its current turn is done.
+    pub async fn close(&self) {
+        if let Some(mut stdin) = self.shared.stdin.lock().await.take() {
+            let _ = stdin.shutdown().await;
+        }
+    }
+
+    /// Whether stdin has been closed.
+    pub async fn is_closed(&self) -> bool {
+        self.shared.stdin.lock().await.is_none()
+    }
+
+    /// Number of turns started so far.
+    pub fn turns_started(&self) -> u64 {
+        self.shared.turns_started.load(Ordering::SeqCst)
+    }
+
+    /// Close stdin unless a turn started after `turns_completed` finished
+    /// turns. Returns whether it closed.
+    pub(crate) async fn close_if_idle(&self, turns_completed: u64) -> bool {
+        let mut stdin = self.shared.stdin.lock().await;
+        if self.turns_started() > turns_completed {
+            return false;
+        }
+        if let Some(mut stdin) = stdin.take() {
+            let _ = stdin.shutdown().await;
+        }
+        true
+    }
+
+    pub(crate) async fn write_turn(&self, line: String) -> ClaudeProcessResult<()> {
+        let mut guard = self.shared.stdin.lock().await;
+        let Some(stdin) = guard.as_mut() else {
+            return Err(ClaudeProcessError::InputClosed);
+        };
+
+        // Counted before writing, so the stream never sees the turn's events
+        // while it still looks idle
+        self.shared.turns_started.fetch_add(1, Ordering::SeqCst);
+        let written = async {
+            stdin.write_all(line.as_bytes()).await?;
+            stdin.write_all(b"\n").await?;
+            stdin.flush().await
+        }
+        .await;
+
+        if let Err(e) = written {
+            debug!(error = %e, "Failed to write to Claude stdin");
+            self.shared.turns_started.fetch_sub(1, Ordering::SeqCst);
+            *guard = None;
+            return Err(ClaudeProcessError::InputClosed);
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ClaudeInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClaudeInput")
+            .field("turns_started", &self.turns_started())
+            .finish_non_exhaustive()
+    }
+}
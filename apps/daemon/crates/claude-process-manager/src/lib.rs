@@ -4,6 +4,7 @@
 //! - Process spawning with proper configuration
 //! - Stdout streaming and JSON event parsing
 //! - Process control (stop signals)
+//! - Keep-alive processes that take further turns over stdin
 //!
 //! # Architecture
 //!
@@ -60,11 +61,16 @@
 mod config;
 mod error;
 mod event;
+mod input;
 mod process;
 mod stream;
 
-pub use config::{ClaudeConfig, PermissionMode, DEFAULT_ALLOWED_TOOLS};
+pub use config::{
+    ClaudeConfig, McpConfigFile, PermissionMode, PermissionPromptTool, ToolResultInput,
+    DEFAULT_ALLOWED_TOOLS, PROMPTED_ALLOWED_TOOLS,
+};
 pub use error::{ClaudeProcessError, ClaudeProcessResult};
 pub use event::ClaudeEvent;
+pub use input::ClaudeInput;
 pub use process::ClaudeProcess;
 pub use stream::ClaudeEventStream;
@@ -2,9 +2,11 @@
 
 use crate::config::ClaudeConfig;
 use crate::error::ClaudeProcessResult;
+use crate::input::ClaudeInput;
 use crate::stream::ClaudeEventStream;
 use std::process::Stdio;
-use tokio::process::Command;
+use std::time::Duration;
+use tokio::process::Child;
 use tokio::sync::broadcast;
 use tracing::{debug, info};
 
@@ -16,6 +18,8 @@ pub struct ClaudeProcess {
     stream: Option<ClaudeEventStream>,
     /// Process ID if available.
     pid: Option<u32>,
+    /// Stdin of a keep-alive process.
+    input: Option<ClaudeInput>,
 }
 
 impl ClaudeProcess {
@@ -31,23 +35,57 @@ impl ClaudeProcess {
     /// and receive events.
     pub async fn spawn(config: ClaudeConfig) -> ClaudeProcessResult<Self> {
         let command = config.build_command();
+        let stdin_message = config.stdin_message();
 
         info!(
             working_dir = %config.working_dir,
             has_resume = config.resume_session_id.is_some(),
+            keep_alive = config.keep_alive.is_some(),
+            env_vars = config.env.len(),
             "Spawning Claude CLI process"
         );
         debug!(command = %command, "Claude command");
 
         // Spawn the process via shell
-        let child = Command::new("zsh")
-            .args(["-l", "-c", &command])
+        let child = config
+            .shell
+            .invocation(&command)
+            .await
+            .command()
             .current_dir(&config.working_dir)
-            .stdin(Stdio::null())
+            .envs(config.env.iter().map(|(key, value)| (key, value)))
+            .stdin(if stdin_message.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?;
 
+        Self::start(child, stdin_message, config.keep_alive).await
+    }
+
+    /// Write the first turn's input and attach the event stream.
+    async fn start(
+        mut child: Child,
+        stdin_message: Option<String>,
+        keep_alive: Option<Duration>,
+    ) -> ClaudeProcessResult<Self> {
+        let mut input = None;
+        if let Some(stdin) = child.stdin.take() {
+            let writer = ClaudeInput::new(stdin);
+            if let Some(message) = stdin_message {
+                writer.write_turn(message).await?;
+            }
+            // Without keep-alive, closing stdin makes Claude end after the turn
+            if keep_alive.is_some() {
+                input = Some(writer);
+            } else {
+                writer.close().await;
+            }
+        }
+
         let pid = child.id();
         info!(pid = ?pid, "Claude process spawned");
 
@@ -55,12 +93,16 @@ impl ClaudeProcess {
         let (stop_tx, stop_rx) = broadcast::channel::<()>(1);
 
         // Create the event stream
-        let stream = ClaudeEventStream::new(child, stop_rx)?;
+        let mut stream = ClaudeEventStream::new(child, stop_rx)?;
+        if let (Some(input), Some(idle_timeout)) = (&input, keep_alive) {
+            stream = stream.with_keep_alive(input.clone(), idle_timeout);
+        }
 
         Ok(Self {
             stop_tx,
             stream: Some(stream),
             pid,
+            input,
         })
     }
 
@@ -92,6 +134,11 @@ impl ClaudeProcess {
         self.pid
     }
 
+    /// Stdin for further turns, if the process was spawned with keep-alive.
+    pub fn input(&self) -> Option<ClaudeInput> {
+        self.input.clone()
+    }
+
     /// Check if the stream has been taken.
     pub fn stream_taken(&self) -> bool {
         self.stream.is_none()
@@ -110,6 +157,8 @@ impl std::fmt::Debug for ClaudeProcess {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{ClaudeEvent, ClaudeProcessError};
+    use tokio::process::Command;
 
     // Note: These tests require the Claude CLI to be installed.
     // They are marked as ignored by default.
@@ -129,4 +178,79 @@ mod tests {
         // Stop the process
         process.stop();
     }
+
+    /// A stand-in for `claude -p --input-format stream-json`: one result
+    /// event per stdin line, exiting when stdin closes.
+    fn fake_claude() -> Child {
+        Command::new("sh")
+            .args([
+                "-c",
+                r#"while read -r line; do echo '{"type":"assistant"}'; echo '{"type":"result","is_error":false}'; done"#,
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    async fn next_turn(stream: &mut ClaudeEventStream) -> Vec<String> {
+        let mut types = Vec::new();
+        while let Some(event) = stream.next().await {
+            types.push(event.event_type().to_string());
+            if matches!(event, ClaudeEvent::Result { .. }) || event.is_terminal() {
+                break;
+            }
+        }
+        types
+    }
+
+    #[tokio::test]
+    async fn keep_alive_process_runs_turns_until_idle() {
+        let config = ClaudeConfig::new("first", "/tmp").with_keep_alive(Duration::from_millis(300));
+        let mut process =
+            ClaudeProcess::start(fake_claude(), config.stdin_message(), config.keep_alive)
+                .await
+                .unwrap();
+        let input = process.input().unwrap();
+        let mut stream = process.take_stream().unwrap();
+
+        assert_eq!(next_turn(&mut stream).await, vec!["assistant", "result"]);
+        assert_eq!(stream.turns_completed(), 1);
+        assert!(!stream.turn_in_progress());
+
+        input.send_message("second").await.unwrap();
+        assert!(stream.turn_in_progress());
+        assert_eq!(next_turn(&mut stream).await, vec!["assistant", "result"]);
+        assert_eq!(stream.turns_completed(), 2);
+        assert_eq!(input.turns_started(), 2);
+
+        // Nothing is sent for longer than the idle timeout
+        let finished = stream.next().await.unwrap();
+        assert!(matches!(
+            finished,
+            ClaudeEvent::Finished { success: true, .. }
+        ));
+        assert!(input.is_closed().await);
+        assert!(matches!(
+            input.send_message("too late").await,
+            Err(ClaudeProcessError::InputClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn single_turn_process_closes_stdin() {
+        let config = ClaudeConfig::new("", "/tmp").with_tool_result("toolu_1", "answer");
+        let mut process = ClaudeProcess::start(fake_claude(), config.stdin_message(), None)
+            .await
+            .unwrap();
+        assert!(process.input().is_none());
+        let mut stream = process.take_stream().unwrap();
+
+        assert_eq!(next_turn(&mut stream).await, vec!["assistant", "result"]);
+        assert!(matches!(
+            stream.next().await,
+            Some(ClaudeEvent::Finished { success: true, .. })
+        ));
+    }
 }
@@ -1,13 +1,21 @@
 //! Claude event streaming.
 
 use crate::event::ClaudeEvent;
+use crate::input::ClaudeInput;
 use regex::Regex;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader, Lines};
 use tokio::process::{Child, ChildStderr, ChildStdout};
 use tokio::sync::broadcast;
-use tracing::{debug, warn};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
 
 /// A stream of events from a Claude CLI process.
+///
+/// Each turn ends with a `Result` event. A single-turn process finishes right
+/// after it; a keep-alive process goes on with the next turn written to its
+/// [`ClaudeInput`], and closes its stdin once it has been idle for the
+/// timeout, which ends the stream with `Finished`.
 pub struct ClaudeEventStream {
     /// Buffered stdout reader.
     stdout: Lines<BufReader<ChildStdout>>,
@@ -21,6 +29,14 @@ pub struct ClaudeEventStream {
     ansi_regex: Regex,
     /// Whether the process has finished.
     finished: bool,
+    /// Stdin and idle timeout of a keep-alive process.
+    keep_alive: Option<(ClaudeInput, Duration)>,
+    /// Number of `Result` events seen.
+    turns_completed: u64,
+    /// When the last turn completed (or the stream started).
+    idle_since: Instant,
+    /// Whether the idle timeout has closed stdin.
+    idle_closed: bool,
 }
 
 impl ClaudeEventStream {
@@ -48,9 +64,19 @@ impl ClaudeEventStream {
             stop_rx,
             ansi_regex,
             finished: false,
+            keep_alive: None,
+            turns_completed: 0,
+            idle_since: Instant::now(),
+            idle_closed: false,
         })
     }
 
+    /// Keep the stream open across turns written to `input`.
+    pub(crate) fn with_keep_alive(mut self, input: ClaudeInput, idle_timeout: Duration) -> Self {
+        self.keep_alive = Some((input, idle_timeout));
+        self
+    }
+
     /// Get the next event from the stream.
     ///
     /// Returns `None` when the stream is exhausted (process finished or stopped).
@@ -60,6 +86,7 @@ impl ClaudeEventStream {
         }
 
         loop {
+            let idle_deadline = self.idle_deadline();
             tokio::select! {
                 // Check for stop signal
                 _ = self.stop_rx.recv() => {
@@ -74,6 +101,10 @@ impl ClaudeEventStream {
                     match line_result {
                         Ok(Some(line)) => {
                             if let Some(event) = self.process_line(&line) {
+                                if matches!(event, ClaudeEvent::Result { .. }) {
+                                    self.turns_completed += 1;
+                                    self.idle_since = Instant::now();
+                                }
                                 return Some(event);
                             }
                             // Continue if line was skipped
@@ -88,7 +119,56 @@ impl ClaudeEventStream {
                         }
                     }
                 }
+
+                // Drain stderr so a long-lived process never blocks on it
+                line_result = next_stderr_line(&mut self.stderr) => {
+                    match line_result {
+                        Some(line) => return Some(ClaudeEvent::Stderr { line }),
+                        None => self.stderr = None,
+                    }
+                }
+
+                // Close stdin of an idle keep-alive process
+                _ = sleep_until(idle_deadline), if idle_deadline.is_some() => {
+                    if let Some((input, idle_timeout)) = &self.keep_alive {
+                        if input.close_if_idle(self.turns_completed).await {
+                            info!(
+                                idle_secs = idle_timeout.as_secs(),
+                                "Closed stdin of idle keep-alive Claude process"
+                            );
+                            self.idle_closed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of turns that have ended with a `Result` event.
+    pub fn turns_completed(&self) -> u64 {
+        self.turns_completed
+    }
+
+    /// Whether this is a keep-alive process that takes turns over stdin.
+    pub fn is_keep_alive(&self) -> bool {
+        self.keep_alive.is_some()
+    }
+
+    /// Whether a turn has started that has not yet ended with a `Result`.
+    pub fn turn_in_progress(&self) -> bool {
+        match &self.keep_alive {
+            Some((input, _)) => input.turns_started() > self.turns_completed,
+            None => !self.finished && self.turns_completed == 0,
+        }
+    }
+
+    /// When an idle keep-alive process should have its stdin closed.
+    fn idle_deadline(&self) -> Option<Instant> {
+        match &self.keep_alive {
+            Some((_, idle_timeout)) if !self.idle_closed && !self.turn_in_progress() => {
+                Some(self.idle_since + *idle_timeout)
             }
+            _ => None,
         }
     }
 
@@ -168,6 +248,20 @@ impl std::fmt::Debug for ClaudeEventStream {
     }
 }
 
+async fn next_stderr_line(stderr: &mut Option<Lines<BufReader<ChildStderr>>>) -> Option<String> {
+    match stderr {
+        Some(stderr) => stderr.next_line().await.ok().flatten(),
+        None => std::future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Strip ANSI escape codes from a string and attempt to parse it as a Claude event.
 ///
 /// Returns `None` for empty lines, non-JSON lines, and invalid JSON.
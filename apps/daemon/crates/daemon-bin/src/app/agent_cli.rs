@@ -1,44 +1,47 @@
-use claude_process_manager::DEFAULT_ALLOWED_TOOLS;
+use crate::app::agent_provider::AgentProvider;
+use crate::utils::repository_config::McpServerConfig;
+use crate::utils::sandbox::SandboxPolicy;
+use claude_process_manager::PermissionMode;
+use login_shell::Shell;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader, Lines};
 use tokio::process::{Child, ChildStderr, ChildStdout, Command};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
 use tokio::time::{timeout, Duration};
 use tracing::{debug, info, warn};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum AgentCliKind {
-    Claude,
-    Codex,
-}
-
 #[derive(Debug, Clone)]
 pub struct AgentCliConfig {
-    pub kind: AgentCliKind,
     pub executable: String,
     pub message: String,
     pub working_dir: String,
     pub resume_session_id: Option<String>,
     pub model: Option<String>,
     pub thinking_effort: Option<String>,
-    pub permission_mode: Option<String>,
+    pub permission_mode: Option<PermissionMode>,
     pub enable_chrome: bool,
     pub skip_permissions: bool,
     pub interrupt_grace_sec: Option<u64>,
     pub extra_args: Vec<String>,
     pub environment_variables: Vec<(String, String)>,
+    /// MCP servers from the repository config; providers skip disabled ones.
+    pub mcp_servers: BTreeMap<String, McpServerConfig>,
+    /// Run the CLI (or, for in-process agents, their commands) sandboxed.
+    pub sandbox: Option<SandboxPolicy>,
+    /// Shell for commands run by in-process agents.
+    pub shell: Shell,
 }
 
 impl AgentCliConfig {
     pub fn new(
-        kind: AgentCliKind,
         executable: impl Into<String>,
         message: impl Into<String>,
         working_dir: impl Into<String>,
     ) -> Self {
         Self {
-            kind,
             executable: executable.into(),
             message: message.into(),
             working_dir: working_dir.into(),
@@ -51,13 +54,9 @@ impl AgentCliConfig {
             interrupt_grace_sec: None,
             extra_args: Vec::new(),
             environment_variables: Vec::new(),
-        }
-    }
-
-    pub fn build_args(&self) -> Vec<String> {
-        match self.kind {
-            AgentCliKind::Claude => build_claude_args(self),
-            AgentCliKind::Codex => build_codex_args(self),
+            mcp_servers: BTreeMap::new(),
+            sandbox: None,
+            shell: Shell::login().clone(),
         }
     }
 }
@@ -85,45 +84,79 @@ impl AgentCliEvent {
 }
 
 pub struct AgentCliProcess {
+    pid: Option<u32>,
     stop_tx: broadcast::Sender<()>,
     stream: Option<AgentCliEventStream>,
 }
 
 impl AgentCliProcess {
-    pub async fn spawn(config: AgentCliConfig) -> Result<Self, std::io::Error> {
-        let args = config.build_args();
+    /// Run a provider's CLI; see [`AgentProvider::spawn`].
+    pub fn spawn_cli<P: AgentProvider + ?Sized>(
+        provider: &P,
+        config: AgentCliConfig,
+    ) -> Result<Self, std::io::Error> {
+        let args = provider.build_args(&config);
 
         info!(
             executable = %config.executable,
             working_dir = %config.working_dir,
-            kind = ?config.kind,
+            provider = provider.name(),
             has_resume = config.resume_session_id.is_some(),
+            sandboxed = config.sandbox.is_some(),
             "Spawning coding agent process"
         );
         debug!(args = ?args, "Agent CLI arguments");
 
-        let mut command = Command::new(&config.executable);
+        let mut command = match &config.sandbox {
+            Some(sandbox) => sandbox.command(&config.executable, &args),
+            None => {
+                let mut command = Command::new(&config.executable);
+                command.args(&args);
+                command
+            }
+        };
         command
-            .args(&args)
             .current_dir(&config.working_dir)
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
-        for (key, value) in &config.environment_variables {
+        for (key, value) in provider.build_env(&config) {
             command.env(key, value);
         }
 
         let child = command.spawn()?;
+        let pid = child.id();
         let (stop_tx, stop_rx) = broadcast::channel::<()>(1);
         let stream = AgentCliEventStream::new(child, stop_rx, config.interrupt_grace_sec)?;
 
         Ok(Self {
+            pid,
             stop_tx,
             stream: Some(stream),
         })
     }
 
+    /// Wrap an in-process agent: `task` sends its events to `events` and
+    /// ends with a terminal event. Stopping aborts the task.
+    pub fn from_task(events: mpsc::Receiver<AgentCliEvent>, task: JoinHandle<()>) -> Self {
+        let (stop_tx, stop_rx) = broadcast::channel::<()>(1);
+        Self {
+            pid: None,
+            stop_tx,
+            stream: Some(AgentCliEventStream {
+                source: EventSource::Task { events, task },
+                stop_rx,
+                finished: false,
+            }),
+        }
+    }
+
+    /// Pid of the CLI process; `None` for an in-process agent.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
     pub fn take_stream(&mut self) -> Option<AgentCliEventStream> {
         self.stream.take()
     }
@@ -134,12 +167,24 @@ impl AgentCliProcess {
 }
 
 pub struct AgentCliEventStream {
+    source: EventSource,
+    stop_rx: broadcast::Receiver<()>,
+    finished: bool,
+}
+
+enum EventSource {
+    Process(Box<ProcessSource>),
+    Task {
+        events: mpsc::Receiver<AgentCliEvent>,
+        task: JoinHandle<()>,
+    },
+}
+
+struct ProcessSource {
     stdout: Lines<BufReader<ChildStdout>>,
     stderr: Option<Lines<BufReader<ChildStderr>>>,
     child: Child,
-    stop_rx: broadcast::Receiver<()>,
     interrupt_grace_sec: Option<u64>,
-    finished: bool,
 }
 
 impl AgentCliEventStream {
@@ -155,11 +200,13 @@ impl AgentCliEventStream {
         let stderr = child.stderr.take();
 
         Ok(Self {
-            stdout: BufReader::new(stdout).lines(),
-            stderr: stderr.map(|reader| BufReader::new(reader).lines()),
-            child,
+            source: EventSource::Process(Box::new(ProcessSource {
+                stdout: BufReader::new(stdout).lines(),
+                stderr: stderr.map(|reader| BufReader::new(reader).lines()),
+                child,
+                interrupt_grace_sec,
+            })),
             stop_rx,
-            interrupt_grace_sec,
             finished: false,
         })
     }
@@ -169,147 +216,146 @@ impl AgentCliEventStream {
             return None;
         }
 
-        loop {
-            tokio::select! {
-                _ = self.stop_rx.recv() => {
-                    debug!("Stop signal received - stopping coding agent process");
-                    self.stop_process().await;
-                    self.finished = true;
-                    return Some(AgentCliEvent::Stopped);
+        let event = match &mut self.source {
+            EventSource::Process(process) => {
+                next_process_event(
+                    &mut process.stdout,
+                    &mut process.stderr,
+                    &mut process.child,
+                    process.interrupt_grace_sec,
+                    &mut self.stop_rx,
+                )
+                .await
+            }
+            EventSource::Task { events, task } => {
+                tokio::select! {
+                    _ = self.stop_rx.recv() => {
+                        debug!("Stop signal received - stopping in-process agent");
+                        task.abort();
+                        AgentCliEvent::Stopped
+                    }
+                    event = events.recv() => event.unwrap_or(AgentCliEvent::Finished {
+                        success: false,
+                        exit_code: None,
+                    }),
                 }
-                line_result = self.stdout.next_line() => {
-                    match line_result {
-                        Ok(Some(line)) => {
-                            if let Some(event) = parse_stdout_event(&line) {
-                                return Some(event);
-                            }
-                        }
-                        Ok(None) => return self.finish_process().await,
-                        Err(error) => {
-                            warn!(error = %error, "Error reading coding agent stdout");
-                            return self.finish_process().await;
+            }
+        };
+
+        self.finished = event.is_terminal();
+        Some(event)
+    }
+}
+
+async fn next_process_event(
+    stdout: &mut Lines<BufReader<ChildStdout>>,
+    stderr: &mut Option<Lines<BufReader<ChildStderr>>>,
+    child: &mut Child,
+    interrupt_grace_sec: Option<u64>,
+    stop_rx: &mut broadcast::Receiver<()>,
+) -> AgentCliEvent {
+    loop {
+        tokio::select! {
+            _ = stop_rx.recv() => {
+                debug!("Stop signal received - stopping coding agent process");
+                stop_process(child, interrupt_grace_sec).await;
+                return AgentCliEvent::Stopped;
+            }
+            line_result = stdout.next_line() => {
+                match line_result {
+                    Ok(Some(line)) => {
+                        if let Some(event) = parse_stdout_event(&line) {
+                            return event;
                         }
                     }
-                }
-                line_result = async {
-                    if let Some(stderr) = &mut self.stderr {
-                        stderr.next_line().await
-                    } else {
-                        Ok(None)
+                    Ok(None) => return finish_process(child).await,
+                    Err(error) => {
+                        warn!(error = %error, "Error reading coding agent stdout");
+                        return finish_process(child).await;
                     }
-                }, if self.stderr.is_some() => {
-                    match line_result {
-                        Ok(Some(line)) => {
-                            if !line.trim().is_empty() {
-                                return Some(AgentCliEvent::Stderr { line });
-                            }
-                        }
-                        Ok(None) => {
-                            self.stderr = None;
-                        }
-                        Err(error) => {
-                            warn!(error = %error, "Error reading coding agent stderr");
-                            self.stderr = None;
+                }
+            }
+            line_result = async {
+                if let Some(stderr) = stderr.as_mut() {
+                    stderr.next_line().await
+                } else {
+                    Ok(None)
+                }
+            }, if stderr.is_some() => {
+                match line_result {
+                    Ok(Some(line)) => {
+                        if !line.trim().is_empty() {
+                            return AgentCliEvent::Stderr { line };
                         }
                     }
+                    Ok(None) => {
+                        *stderr = None;
+                    }
+                    Err(error) => {
+                        warn!(error = %error, "Error reading coding agent stderr");
+                        *stderr = None;
+                    }
                 }
             }
         }
     }
+}
 
-    async fn finish_process(&mut self) -> Option<AgentCliEvent> {
-        self.finished = true;
-
-        match self.child.wait().await {
-            Ok(status) => Some(AgentCliEvent::Finished {
-                success: status.success(),
-                exit_code: status.code(),
-            }),
-            Err(error) => {
-                warn!(error = %error, "Error waiting for coding agent process");
-                Some(AgentCliEvent::Finished {
-                    success: false,
-                    exit_code: None,
-                })
+async fn finish_process(child: &mut Child) -> AgentCliEvent {
+    match child.wait().await {
+        Ok(status) => AgentCliEvent::Finished {
+            success: status.success(),
+            exit_code: status.code(),
+        },
+        Err(error) => {
+            warn!(error = %error, "Error waiting for coding agent process");
+            AgentCliEvent::Finished {
+                success: false,
+                exit_code: None,
             }
         }
     }
+}
 
-    async fn stop_process(&mut self) {
-        #[cfg(unix)]
-        if let Some(grace_sec) = self.interrupt_grace_sec {
-            if grace_sec > 0 {
-                if let Some(pid) = self.child.id() {
-                    // Let the CLI flush and persist state before we force-kill it.
-                    unsafe {
-                        libc::kill(pid as i32, libc::SIGINT);
-                    }
-                    if timeout(Duration::from_secs(grace_sec), self.child.wait())
-                        .await
-                        .is_ok()
-                    {
-                        return;
-                    }
+async fn stop_process(child: &mut Child, interrupt_grace_sec: Option<u64>) {
+    #[cfg(unix)]
+    if let Some(grace_sec) = interrupt_grace_sec {
+        if grace_sec > 0 {
+            if let Some(pid) = child.id() {
+                // Let the CLI flush and persist state before we force-kill it.
+                unsafe {
+                    libc::kill(pid as i32, libc::SIGINT);
+                }
+                if timeout(Duration::from_secs(grace_sec), child.wait())
+                    .await
+                    .is_ok()
+                {
+                    return;
                 }
             }
         }
-
-        let _ = self.child.kill().await;
-        let _ = self.child.wait().await;
     }
-}
-
-pub fn detect_agent_cli_kind(command: Option<&str>, model: Option<&str>) -> AgentCliKind {
-    let command = command.unwrap_or("claude");
-    let model = model.unwrap_or_default();
-    let command_name = std::path::Path::new(command)
-        .file_name()
-        .and_then(|value| value.to_str())
-        .unwrap_or(command)
-        .to_ascii_lowercase();
-    let model_name = model.to_ascii_lowercase();
-
-    if command_name.contains("codex") || model_name.contains("codex") {
-        AgentCliKind::Codex
-    } else {
-        AgentCliKind::Claude
-    }
-}
 
-pub fn agent_cli_label(kind: AgentCliKind) -> &'static str {
-    match kind {
-        AgentCliKind::Claude => "Claude",
-        AgentCliKind::Codex => "Codex",
-    }
+    let _ = child.kill().await;
+    let _ = child.wait().await;
 }
 
 pub fn build_agent_cli_config_from_adapter(
+    provider: &dyn AgentProvider,
     adapter_config: Option<&serde_json::Map<String, Value>>,
     message: &str,
     working_dir: String,
     resume_session_id: Option<&str>,
 ) -> AgentCliConfig {
-    let kind = detect_agent_cli_kind(
-        adapter_config
-            .and_then(|config| config.get("command"))
-            .and_then(Value::as_str),
-        adapter_config
-            .and_then(|config| config.get("model"))
-            .and_then(Value::as_str),
-    );
-    let default_command = match kind {
-        AgentCliKind::Claude => "claude",
-        AgentCliKind::Codex => "codex",
-    };
     let executable = adapter_config
         .and_then(|config| config.get("command"))
         .and_then(Value::as_str)
         .map(str::trim)
         .filter(|value| !value.is_empty())
-        .unwrap_or(default_command)
+        .unwrap_or(provider.default_executable())
         .to_string();
 
-    let mut config = AgentCliConfig::new(kind, executable, message, working_dir);
+    let mut config = AgentCliConfig::new(executable, message, working_dir);
     config.resume_session_id = resume_session_id.map(ToOwned::to_owned);
     config.model = adapter_config
         .and_then(|config| config.get("model"))
@@ -326,7 +372,13 @@ pub fn build_agent_cli_config_from_adapter(
     config.permission_mode = adapter_config
         .and_then(|config| config.get("permissionMode"))
         .and_then(Value::as_str)
-        .map(ToOwned::to_owned);
+        .and_then(|mode| match mode.trim().parse() {
+            Ok(mode) => Some(mode),
+            Err(e) => {
+                warn!(error = %e, "Ignoring adapter permission mode");
+                None
+            }
+        });
     config.enable_chrome = adapter_config
         .and_then(|config| config.get("enableChrome"))
         .and_then(Value::as_bool)
@@ -356,143 +408,6 @@ pub fn build_agent_cli_config_from_adapter(
     config
 }
 
-fn build_claude_args(config: &AgentCliConfig) -> Vec<String> {
-    let mut args = vec![
-        "-p".to_string(),
-        "--verbose".to_string(),
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--allowedTools".to_string(),
-        DEFAULT_ALLOWED_TOOLS.to_string(),
-    ];
-
-    if let Some(model) = normalize_model(config.model.as_deref()) {
-        args.push("--model".to_string());
-        args.push(model);
-    }
-
-    if let Some(effort) = normalize_thinking_effort(config.thinking_effort.as_deref()) {
-        args.push("--effort".to_string());
-        args.push(effort);
-    }
-
-    if normalize_optional_string(config.permission_mode.as_deref())
-        .is_some_and(|mode| mode == "plan")
-    {
-        args.push("--permission-mode".to_string());
-        args.push("plan".to_string());
-    }
-
-    if let Some(session_id) = normalize_optional_string(config.resume_session_id.as_deref()) {
-        args.push("-r".to_string());
-        args.push(session_id);
-    }
-
-    if config.enable_chrome {
-        args.push("--chrome".to_string());
-    }
-
-    if config.skip_permissions {
-        args.push("--dangerously-skip-permissions".to_string());
-    }
-
-    args.extend(config.extra_args.iter().cloned());
-    args.push(config.message.clone());
-    args
-}
-
-fn build_codex_args(config: &AgentCliConfig) -> Vec<String> {
-    let mut args = vec!["exec".to_string()];
-    let approval_policy = if config.skip_permissions {
-        r#"approval_policy="never""#
-    } else {
-        r#"approval_policy="on-request""#
-    };
-
-    if let Some(session_id) = normalize_optional_string(config.resume_session_id.as_deref()) {
-        args.push("resume".to_string());
-        args.push("--json".to_string());
-        args.push("--skip-git-repo-check".to_string());
-        args.push("-c".to_string());
-        args.push(approval_policy.to_string());
-        args.push("-c".to_string());
-        args.push(r#"sandbox_mode="workspace-write""#.to_string());
-
-        if let Some(model) = normalize_model(config.model.as_deref()) {
-            args.push("-m".to_string());
-            args.push(model);
-        }
-
-        if config.enable_chrome {
-            args.push("--enable".to_string());
-            args.push("web_search".to_string());
-        }
-
-        if let Some(effort) = normalize_thinking_effort(config.thinking_effort.as_deref()) {
-            args.push("-c".to_string());
-            args.push(format!(r#"model_reasoning_effort="{effort}""#));
-        }
-
-        args.extend(config.extra_args.iter().cloned());
-        args.push(session_id);
-        args.push(config.message.clone());
-        return args;
-    }
-
-    args.push("--json".to_string());
-    args.push("--skip-git-repo-check".to_string());
-    args.push("-c".to_string());
-    args.push(approval_policy.to_string());
-    args.push("-c".to_string());
-    args.push(r#"sandbox_mode="workspace-write""#.to_string());
-
-    if let Some(model) = normalize_model(config.model.as_deref()) {
-        args.push("-m".to_string());
-        args.push(model);
-    }
-
-    if config.enable_chrome {
-        args.push("--enable".to_string());
-        args.push("web_search".to_string());
-    }
-
-    if let Some(effort) = normalize_thinking_effort(config.thinking_effort.as_deref()) {
-        args.push("-c".to_string());
-        args.push(format!(r#"model_reasoning_effort="{effort}""#));
-    }
-
-    args.extend(config.extra_args.iter().cloned());
-    args.push(config.message.clone());
-    args
-}
-
-fn normalize_optional_string(value: Option<&str>) -> Option<String> {
-    let trimmed = value?.trim();
-    if trimmed.is_empty() {
-        None
-    } else {
-        Some(trimmed.to_string())
-    }
-}
-
-fn normalize_model(value: Option<&str>) -> Option<String> {
-    let model = normalize_optional_string(value)?;
-    if model.eq_ignore_ascii_case("default") {
-        None
-    } else {
-        Some(model)
-    }
-}
-
-fn normalize_thinking_effort(value: Option<&str>) -> Option<String> {
-    let effort = normalize_optional_string(value)?;
-    if effort.eq_ignore_ascii_case("auto") {
-        None
-    } else {
-        Some(effort)
-    }
-}
-
 fn parse_environment_variables(value: Option<&Value>) -> Vec<(String, String)> {
     value
         .and_then(Value::as_array)
@@ -537,65 +452,10 @@ fn parse_stdout_event(line: &str) -> Option<AgentCliEvent> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::agent_provider::{ClaudeProvider, CodexProvider};
 
     #[test]
-    fn claude_args_include_selected_model_and_effort() {
-        let mut config = AgentCliConfig::new(AgentCliKind::Claude, "claude", "hello", "/tmp");
-        config.model = Some("claude-sonnet-4-6".to_string());
-        config.thinking_effort = Some("high".to_string());
-        config.resume_session_id = Some("sess-123".to_string());
-        config.skip_permissions = true;
-        config.enable_chrome = true;
-        config.extra_args = vec!["--brief".to_string()];
-
-        let args = config.build_args();
-
-        assert!(args.iter().any(|arg| arg == "--model"));
-        assert!(args.iter().any(|arg| arg == "claude-sonnet-4-6"));
-        assert!(args.iter().any(|arg| arg == "--effort"));
-        assert!(args.iter().any(|arg| arg == "high"));
-        assert!(args
-            .iter()
-            .any(|arg| arg == "--dangerously-skip-permissions"));
-        assert!(args.iter().any(|arg| arg == "--chrome"));
-        assert!(args.iter().any(|arg| arg == "--brief"));
-        assert_eq!(args.last().map(String::as_str), Some("hello"));
-    }
-
-    #[test]
-    fn codex_args_support_resume_and_reasoning_effort() {
-        let mut config = AgentCliConfig::new(AgentCliKind::Codex, "codex", "continue", "/tmp");
-        config.model = Some("gpt-5.3-codex".to_string());
-        config.thinking_effort = Some("medium".to_string());
-        config.resume_session_id = Some("thread-123".to_string());
-        config.enable_chrome = true;
-
-        let args = config.build_args();
-
-        assert_eq!(args.first().map(String::as_str), Some("exec"));
-        assert!(args.iter().any(|arg| arg == "resume"));
-        assert!(args.iter().any(|arg| arg == "--json"));
-        assert!(args.iter().any(|arg| arg == "--skip-git-repo-check"));
-        assert!(args
-            .iter()
-            .any(|arg| arg == r#"approval_policy="on-request""#));
-        assert!(args
-            .iter()
-            .any(|arg| arg == r#"sandbox_mode="workspace-write""#));
-        assert!(args.iter().any(|arg| arg == "web_search"));
-        assert!(args.iter().any(|arg| arg == "gpt-5.3-codex"));
-        assert!(args
-            .iter()
-            .any(|arg| arg == r#"model_reasoning_effort="medium""#));
-        assert_eq!(
-            args.get(args.len() - 2).map(String::as_str),
-            Some("thread-123")
-        );
-        assert_eq!(args.last().map(String::as_str), Some("continue"));
-    }
-
-    #[test]
-    fn build_config_detects_reasoning_effort_alias() {
+    fn build_config_reads_reasoning_effort_alias() {
         let adapter = serde_json::json!({
             "command": "codex",
             "model": "gpt-5.3-codex",
@@ -604,15 +464,26 @@ mod tests {
         });
 
         let config = build_agent_cli_config_from_adapter(
+            &CodexProvider,
             adapter.as_object(),
             "hello",
             "/tmp".to_string(),
             None,
         );
 
-        assert_eq!(config.kind, AgentCliKind::Codex);
+        assert_eq!(config.executable, "codex");
         assert_eq!(config.thinking_effort.as_deref(), Some("high"));
         assert!(config.skip_permissions);
+
+        // Without a command the provider's own executable runs
+        let config = build_agent_cli_config_from_adapter(
+            &ClaudeProvider,
+            None,
+            "hello",
+            "/tmp".into(),
+            None,
+        );
+        assert_eq!(config.executable, "claude");
     }
 
     #[test]
@@ -0,0 +1,201 @@
+//! The built-in Claude Code provider.
+//!
+//! Claude turns started by `agent.send` run through claude-process-manager,
+//! which adds interactive permissions and tool results on top of these
+//! arguments; this provider describes the plain `claude -p` invocation.
+
+use super::{
+    normalize_model, normalize_optional_string, normalize_thinking_effort, AgentEvent,
+    AgentProvider, CLAUDE_PROVIDER,
+};
+use crate::app::agent_cli::AgentCliConfig;
+use crate::utils::mcp_servers::claude_mcp_config;
+use agent_session_sqlite_persist_core::Session;
+use agent_stream_types::claude::ClaudeCodeMessage;
+use claude_process_manager::DEFAULT_ALLOWED_TOOLS;
+use serde_json::Value;
+
+/// Claude Code in print mode with `stream-json` output.
+pub struct ClaudeProvider;
+
+impl AgentProvider for ClaudeProvider {
+    fn name(&self) -> &str {
+        CLAUDE_PROVIDER
+    }
+
+    fn default_executable(&self) -> &str {
+        "claude"
+    }
+
+    fn build_args(&self, config: &AgentCliConfig) -> Vec<String> {
+        let mut args = vec![
+            "-p".to_string(),
+            "--verbose".to_string(),
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+            "--allowedTools".to_string(),
+            DEFAULT_ALLOWED_TOOLS.to_string(),
+        ];
+
+        if let Some(model) = normalize_model(config.model.as_deref()) {
+            args.push("--model".to_string());
+            args.push(model);
+        }
+
+        if let Some(effort) = normalize_thinking_effort(config.thinking_effort.as_deref()) {
+            args.push("--effort".to_string());
+            args.push(effort);
+        }
+
+        if let Some(mode) = config.permission_mode {
+            args.push("--permission-mode".to_string());
+            args.push(mode.as_str().to_string());
+        }
+
+        if let Some(session_id) = normalize_optional_string(config.resume_session_id.as_deref()) {
+            args.push("-r".to_string());
+            args.push(session_id);
+        }
+
+        if let Some(mcp_config) = claude_mcp_config(&config.mcp_servers) {
+            args.push("--mcp-config".to_string());
+            args.push(mcp_config);
+        }
+
+        if config.enable_chrome {
+            args.push("--chrome".to_string());
+        }
+
+        if config.skip_permissions {
+            args.push("--dangerously-skip-permissions".to_string());
+        }
+
+        args.extend(config.extra_args.iter().cloned());
+        args.push(config.message.clone());
+        args
+    }
+
+    fn parse_event(&self, json: &Value) -> AgentEvent {
+        let message = ClaudeCodeMessage::from_value(json)
+            .unwrap_or_else(|_| ClaudeCodeMessage::Other(json.clone()));
+        let (text, awaiting_input) = match &message {
+            ClaudeCodeMessage::Assistant(assistant) => (
+                Some(assistant.text_blocks().collect::<Vec<_>>().join("\n"))
+                    .filter(|text| !text.is_empty()),
+                assistant
+                    .tool_uses()
+                    .iter()
+                    .any(|tool_use| tool_use.is_ask_user_question()),
+            ),
+            _ => (None, false),
+        };
+        let failed = matches!(&message, ClaudeCodeMessage::Result(result) if result.is_error());
+
+        AgentEvent {
+            session_id: message.session_id().map(ToOwned::to_owned),
+            text,
+            error: failed.then(|| "Claude reported an error result".to_string()),
+            awaiting_input,
+            usage: message.turn_usage(),
+            tool_calls: message.tool_calls(),
+        }
+    }
+
+    fn resume_session_id<'a>(&self, session: &'a Session) -> Option<&'a str> {
+        session.claude_session_id.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claude_process_manager::PermissionMode;
+    use serde_json::json;
+
+    #[test]
+    fn args_include_selected_model_and_effort() {
+        let mut config = AgentCliConfig::new("claude", "hello", "/tmp");
+        config.model = Some("claude-sonnet-4-6".to_string());
+        config.thinking_effort = Some("high".to_string());
+        config.resume_session_id = Some("sess-123".to_string());
+        config.skip_permissions = true;
+        config.enable_chrome = true;
+        config.extra_args = vec!["--brief".to_string()];
+
+        let args = ClaudeProvider.build_args(&config);
+
+        assert!(args.iter().any(|arg| arg == "--model"));
+        assert!(args.iter().any(|arg| arg == "claude-sonnet-4-6"));
+        assert!(args.iter().any(|arg| arg == "--effort"));
+        assert!(args.iter().any(|arg| arg == "high"));
+        assert!(args
+            .iter()
+            .any(|arg| arg == "--dangerously-skip-permissions"));
+        assert!(args.iter().any(|arg| arg == "--chrome"));
+        assert!(args.iter().any(|arg| arg == "--brief"));
+        assert_eq!(args.last().map(String::as_str), Some("hello"));
+    }
+
+    #[test]
+    fn args_include_every_permission_mode() {
+        for mode in PermissionMode::ALL {
+            let mut config = AgentCliConfig::new("claude", "hello", "/tmp");
+            config.permission_mode = Some(mode);
+            let args = ClaudeProvider.build_args(&config);
+            let flag = ["--permission-mode".to_string(), mode.as_str().to_string()];
+            assert!(args.windows(2).any(|pair| pair == flag));
+        }
+    }
+
+    #[test]
+    fn parses_text_questions_and_errors() {
+        let event = ClaudeProvider.parse_event(&json!({
+            "type": "assistant",
+            "session_id": "sess-1",
+            "message": {
+                "content": [
+                    { "type": "text", "text": "Which one?" },
+                    { "type": "tool_use", "id": "toolu_1", "name": "AskUserQuestion" }
+                ]
+            }
+        }));
+        assert_eq!(event.session_id.as_deref(), Some("sess-1"));
+        assert_eq!(event.text.as_deref(), Some("Which one?"));
+        assert!(event.awaiting_input);
+        assert!(event.error.is_none());
+
+        let failed = ClaudeProvider.parse_event(&json!({
+            "type": "result",
+            "subtype": "error_max_turns",
+            "is_error": true
+        }));
+        assert!(failed.error.is_some());
+        assert!(!failed.awaiting_input);
+    }
+
+    #[test]
+    fn parses_tool_calls_and_turn_usage() {
+        let event = ClaudeProvider.parse_event(&json!({
+            "type": "assistant",
+            "message": {
+                "content": [
+                    { "type": "tool_use", "id": "toolu_1", "name": "Bash", "input": { "command": "ls" } }
+                ]
+            }
+        }));
+        assert_eq!(event.tool_calls.len(), 1);
+        assert_eq!(event.tool_calls[0].name, "Bash");
+        assert_eq!(event.usage, None);
+
+        let result = ClaudeProvider.parse_event(&json!({
+            "type": "result",
+            "subtype": "success",
+            "usage": { "input_tokens": 5, "cache_read_input_tokens": 100, "output_tokens": 7 }
+        }));
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, 105);
+        assert_eq!(usage.cached_input_tokens, 100);
+        assert_eq!(usage.output_tokens, 7);
+        assert!(result.error.is_none());
+    }
+}
@@ -0,0 +1,237 @@
+//! The built-in Codex provider.
+
+use super::{
+    normalize_model, normalize_optional_string, normalize_thinking_effort, AgentEvent,
+    AgentProvider, CODEX_PROVIDER,
+};
+use crate::app::agent_cli::AgentCliConfig;
+use crate::utils::mcp_servers::{codex_mcp_args, codex_mcp_env};
+use agent_stream_types::codex::CodexEvent;
+use claude_process_manager::PermissionMode;
+use serde_json::Value;
+
+/// `codex exec --json`, resuming threads with `codex exec resume`.
+pub struct CodexProvider;
+
+impl AgentProvider for CodexProvider {
+    fn name(&self) -> &str {
+        CODEX_PROVIDER
+    }
+
+    fn default_executable(&self) -> &str {
+        "codex"
+    }
+
+    fn build_args(&self, config: &AgentCliConfig) -> Vec<String> {
+        let mut args = vec!["exec".to_string()];
+        // Codex has no permission modes; plan maps to a read-only sandbox and
+        // bypassPermissions to never asking for approval.
+        let approval_policy = if config.skip_permissions
+            || config.permission_mode == Some(PermissionMode::BypassPermissions)
+        {
+            r#"approval_policy="never""#
+        } else {
+            r#"approval_policy="on-request""#
+        };
+        let sandbox_mode = if config.permission_mode == Some(PermissionMode::Plan) {
+            r#"sandbox_mode="read-only""#
+        } else {
+            r#"sandbox_mode="workspace-write""#
+        };
+        let resume_session_id = normalize_optional_string(config.resume_session_id.as_deref());
+
+        if resume_session_id.is_some() {
+            args.push("resume".to_string());
+        }
+        args.push("--json".to_string());
+        args.push("--skip-git-repo-check".to_string());
+        args.push("-c".to_string());
+        args.push(approval_policy.to_string());
+        args.push("-c".to_string());
+        args.push(sandbox_mode.to_string());
+
+        if let Some(model) = normalize_model(config.model.as_deref()) {
+            args.push("-m".to_string());
+            args.push(model);
+        }
+
+        if config.enable_chrome {
+            args.push("--enable".to_string());
+            args.push("web_search".to_string());
+        }
+
+        if let Some(effort) = normalize_thinking_effort(config.thinking_effort.as_deref()) {
+            args.push("-c".to_string());
+            args.push(format!(r#"model_reasoning_effort="{effort}""#));
+        }
+
+        args.extend(codex_mcp_args(&config.mcp_servers));
+        args.extend(config.extra_args.iter().cloned());
+        args.extend(resume_session_id);
+        args.push(config.message.clone());
+        args
+    }
+
+    fn build_env(&self, config: &AgentCliConfig) -> Vec<(String, String)> {
+        // Set last so MCP servers see their own values
+        let mut env = config.environment_variables.clone();
+        env.extend(codex_mcp_env(&config.mcp_servers));
+        env
+    }
+
+    fn parse_event(&self, json: &Value) -> AgentEvent {
+        let event =
+            CodexEvent::from_value(json).unwrap_or_else(|_| CodexEvent::Other(json.clone()));
+
+        AgentEvent {
+            session_id: json
+                .get("thread_id")
+                .or_else(|| json.get("threadId"))
+                .or_else(|| json.get("id"))
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned),
+            text: event.agent_message().map(ToOwned::to_owned),
+            error: event.error_message(),
+            awaiting_input: false,
+            usage: event.turn_usage(),
+            tool_calls: event.tool_call().into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn args_support_resume_and_reasoning_effort() {
+        let mut config = AgentCliConfig::new("codex", "continue", "/tmp");
+        config.model = Some("gpt-5.3-codex".to_string());
+        config.thinking_effort = Some("medium".to_string());
+        config.resume_session_id = Some("thread-123".to_string());
+        config.enable_chrome = true;
+
+        let args = CodexProvider.build_args(&config);
+
+        assert_eq!(args.first().map(String::as_str), Some("exec"));
+        assert!(args.iter().any(|arg| arg == "resume"));
+        assert!(args.iter().any(|arg| arg == "--json"));
+        assert!(args.iter().any(|arg| arg == "--skip-git-repo-check"));
+        assert!(args
+            .iter()
+            .any(|arg| arg == r#"approval_policy="on-request""#));
+        assert!(args
+            .iter()
+            .any(|arg| arg == r#"sandbox_mode="workspace-write""#));
+        assert!(args.iter().any(|arg| arg == "web_search"));
+        assert!(args.iter().any(|arg| arg == "gpt-5.3-codex"));
+        assert!(args
+            .iter()
+            .any(|arg| arg == r#"model_reasoning_effort="medium""#));
+        assert_eq!(
+            args.get(args.len() - 2).map(String::as_str),
+            Some("thread-123")
+        );
+        assert_eq!(args.last().map(String::as_str), Some("continue"));
+    }
+
+    #[test]
+    fn args_include_every_permission_mode() {
+        for mode in PermissionMode::ALL {
+            let mut config = AgentCliConfig::new("codex", "hello", "/tmp");
+            config.permission_mode = Some(mode);
+            let args = CodexProvider.build_args(&config);
+
+            let (approval_policy, sandbox_mode) = match mode {
+                PermissionMode::Default | PermissionMode::AcceptEdits => {
+                    ("on-request", "workspace-write")
+                }
+                PermissionMode::Plan => ("on-request", "read-only"),
+                PermissionMode::BypassPermissions => ("never", "workspace-write"),
+            };
+            let approval_policy = format!(r#"approval_policy="{approval_policy}""#);
+            let sandbox_mode = format!(r#"sandbox_mode="{sandbox_mode}""#);
+            assert!(args.windows(2).any(|pair| pair == ["-c", &approval_policy]));
+            assert!(args.windows(2).any(|pair| pair == ["-c", &sandbox_mode]));
+        }
+    }
+
+    #[test]
+    fn args_declare_repository_mcp_servers_without_their_env_values() {
+        let mut config = AgentCliConfig::new("codex", "hello", "/tmp");
+        config.mcp_servers.insert(
+            "linear".to_string(),
+            crate::utils::repository_config::McpServerConfig {
+                command: "npx".to_string(),
+                args: vec!["linear-mcp".to_string()],
+                env: [("LINEAR_TOKEN".to_string(), "secret".to_string())].into(),
+                enabled: true,
+            },
+        );
+
+        let args = CodexProvider.build_args(&config);
+
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-c", r#"mcp_servers.linear.command="npx""#]));
+        assert!(!args.iter().any(|arg| arg.contains("secret")));
+        assert_eq!(args.last().map(String::as_str), Some("hello"));
+        assert!(CodexProvider
+            .build_env(&config)
+            .contains(&("LINEAR_TOKEN".to_string(), "secret".to_string())));
+    }
+
+    #[test]
+    fn parses_threads_messages_and_failures() {
+        let started = CodexProvider.parse_event(&json!({
+            "type": "thread.started",
+            "thread_id": "thread-1"
+        }));
+        assert_eq!(started.session_id.as_deref(), Some("thread-1"));
+
+        let message = CodexProvider.parse_event(&json!({
+            "type": "item.completed",
+            "item": { "type": "agent_message", "text": "Done" }
+        }));
+        assert_eq!(message.text.as_deref(), Some("Done"));
+
+        let failed = CodexProvider.parse_event(&json!({ "type": "turn.failed" }));
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("Codex reported an error result")
+        );
+
+        let failed = CodexProvider.parse_event(&json!({
+            "type": "turn.failed",
+            "error": { "message": "usage limit reached" }
+        }));
+        assert_eq!(failed.error.as_deref(), Some("usage limit reached"));
+    }
+
+    #[test]
+    fn parses_tool_calls_and_turn_usage() {
+        let command = CodexProvider.parse_event(&json!({
+            "type": "item.completed",
+            "item": {
+                "id": "item_0",
+                "type": "command_execution",
+                "command": "ls",
+                "exit_code": 0,
+                "status": "completed"
+            }
+        }));
+        assert_eq!(command.tool_calls.len(), 1);
+        assert_eq!(command.tool_calls[0].name, "command_execution");
+        assert!(command.text.is_none());
+
+        let completed = CodexProvider.parse_event(&json!({
+            "type": "turn.completed",
+            "usage": { "input_tokens": 900, "cached_input_tokens": 800, "output_tokens": 12 }
+        }));
+        let usage = completed.usage.unwrap();
+        assert_eq!(usage.input_tokens, 900);
+        assert_eq!(usage.cached_input_tokens, 800);
+        assert_eq!(usage.output_tokens, 12);
+    }
+}
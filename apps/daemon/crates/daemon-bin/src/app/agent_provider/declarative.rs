@@ -0,0 +1,242 @@
+//! Providers declared in the daemon config.
+//!
+//! The argument template and JSON field mappings come from an
+//! [`AgentProviderConfig`], so a new CLI only needs a config entry.
+
+use super::{
+    normalize_model, normalize_optional_string, normalize_thinking_effort, AgentEvent,
+    AgentProvider,
+};
+use crate::app::agent_cli::AgentCliConfig;
+use daemon_config_and_utils::{AgentProviderArg, AgentProviderConfig};
+use serde_json::Value;
+
+/// A provider driven entirely by its config definition.
+pub struct DeclarativeProvider {
+    definition: AgentProviderConfig,
+}
+
+impl DeclarativeProvider {
+    pub fn new(definition: AgentProviderConfig) -> Self {
+        Self { definition }
+    }
+
+    fn label(&self) -> &str {
+        self.definition
+            .label
+            .as_deref()
+            .unwrap_or(&self.definition.name)
+    }
+}
+
+impl AgentProvider for DeclarativeProvider {
+    fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    fn default_executable(&self) -> &str {
+        &self.definition.executable
+    }
+
+    fn build_args(&self, config: &AgentCliConfig) -> Vec<String> {
+        let mut args = Vec::new();
+        for entry in &self.definition.args {
+            let templates = match entry {
+                AgentProviderArg::Single(template) => std::slice::from_ref(template),
+                AgentProviderArg::Group(templates) => templates.as_slice(),
+            };
+            let rendered: Option<Vec<String>> = templates
+                .iter()
+                .map(|template| render(template, config))
+                .collect();
+            args.extend(rendered.unwrap_or_default());
+        }
+        args.extend(config.extra_args.iter().cloned());
+        args
+    }
+
+    fn build_env(&self, config: &AgentCliConfig) -> Vec<(String, String)> {
+        self.definition
+            .env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .chain(config.environment_variables.iter().cloned())
+            .collect()
+    }
+
+    fn parse_event(&self, json: &Value) -> AgentEvent {
+        let fields = &self.definition.fields;
+        let failed = if fields.error_types.is_empty() {
+            field(json, fields.error.as_ref()).is_some()
+        } else {
+            string_field(json, fields.event_type.as_ref())
+                .is_some_and(|event_type| fields.error_types.contains(&event_type))
+        };
+
+        AgentEvent {
+            session_id: string_field(json, fields.session_id.as_ref()),
+            text: string_field(json, fields.text.as_ref()),
+            error: failed.then(|| {
+                string_field(json, fields.error.as_ref())
+                    .unwrap_or_else(|| format!("{} reported an error result", self.label()))
+            }),
+            ..AgentEvent::default()
+        }
+    }
+}
+
+fn field<'a>(json: &'a Value, pointer: Option<&String>) -> Option<&'a Value> {
+    json.pointer(pointer?).filter(|value| !value.is_null())
+}
+
+fn string_field(json: &Value, pointer: Option<&String>) -> Option<String> {
+    match field(json, pointer)? {
+        Value::String(value) => Some(value.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Fill in a template's `{placeholder}`s. Returns None when a placeholder
+/// has no value; unknown `{...}` text is kept as written.
+fn render(template: &str, config: &AgentCliConfig) -> Option<String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        if rest[1..end].contains('{') {
+            // Not a placeholder; a later brace may open one
+            rendered.push('{');
+            rest = &rest[1..];
+            continue;
+        }
+        match placeholder(&rest[1..end], config) {
+            Some(Some(value)) => rendered.push_str(&value),
+            Some(None) => return None,
+            None => rendered.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    Some(rendered)
+}
+
+/// Value of a named placeholder: None if the name is unknown, Some(None) if
+/// the turn has no value for it.
+fn placeholder(name: &str, config: &AgentCliConfig) -> Option<Option<String>> {
+    let value = match name {
+        "message" => Some(config.message.clone()),
+        "working_dir" => Some(config.working_dir.clone()),
+        "model" => normalize_model(config.model.as_deref()),
+        "thinking_effort" => normalize_thinking_effort(config.thinking_effort.as_deref()),
+        "permission_mode" => config.permission_mode.map(|mode| mode.as_str().to_string()),
+        "resume_session_id" => normalize_optional_string(config.resume_session_id.as_deref()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use daemon_config_and_utils::AgentProviderFields;
+    use serde_json::json;
+
+    fn provider() -> DeclarativeProvider {
+        let definition: AgentProviderConfig = serde_json::from_value(json!({
+            "name": "aider",
+            "label": "Aider",
+            "executable": "aider",
+            "args": [
+                "--json",
+                ["--model", "{model}"],
+                ["--restore", "{resume_session_id}"],
+                "--config={\"cwd\": \"{working_dir}\"}",
+                "{message}"
+            ],
+            "env": { "AIDER_NO_COLOR": "1" },
+            "fields": {
+                "event_type": "/type",
+                "session_id": "/session/id",
+                "text": "/content",
+                "error": "/error/message",
+                "error_types": ["failed"]
+            }
+        }))
+        .unwrap();
+        DeclarativeProvider::new(definition)
+    }
+
+    #[test]
+    fn args_follow_the_template() {
+        let provider = provider();
+        let mut config = AgentCliConfig::new("aider", "fix it", "/repo");
+        config.model = Some("default".to_string());
+        config.resume_session_id = Some("chat-1".to_string());
+        config.extra_args = vec!["--yes".to_string()];
+        config.environment_variables = vec![("TOKEN".to_string(), "t".to_string())];
+
+        assert_eq!(
+            provider.build_args(&config),
+            vec![
+                "--json",
+                "--restore",
+                "chat-1",
+                "--config={\"cwd\": \"/repo\"}",
+                "fix it",
+                "--yes"
+            ]
+        );
+        assert_eq!(
+            provider.build_env(&config),
+            vec![
+                ("AIDER_NO_COLOR".to_string(), "1".to_string()),
+                ("TOKEN".to_string(), "t".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn events_are_read_through_field_pointers() {
+        let provider = provider();
+
+        let event = provider.parse_event(&json!({
+            "type": "message",
+            "session": { "id": "chat-1" },
+            "content": "Done"
+        }));
+        assert_eq!(event.session_id.as_deref(), Some("chat-1"));
+        assert_eq!(event.text.as_deref(), Some("Done"));
+        assert!(event.error.is_none());
+
+        let failed = provider.parse_event(&json!({ "type": "failed" }));
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("Aider reported an error result")
+        );
+        let failed = provider.parse_event(&json!({
+            "type": "failed",
+            "error": { "message": "rate limited" }
+        }));
+        assert_eq!(failed.error.as_deref(), Some("rate limited"));
+    }
+
+    #[test]
+    fn error_field_alone_marks_failures_without_error_types() {
+        let mut definition = provider().definition;
+        definition.fields = AgentProviderFields {
+            error: Some("/error".to_string()),
+            ..AgentProviderFields::default()
+        };
+        let provider = DeclarativeProvider::new(definition);
+
+        let error = |event: Value| provider.parse_event(&event).error;
+
+        assert_eq!(error(json!({ "text": "ok" })), None);
+        assert_eq!(error(json!({ "error": null })), None);
+        assert_eq!(error(json!({ "error": "boom" })).as_deref(), Some("boom"));
+    }
+}
@@ -0,0 +1,277 @@
+//! Coding agent providers.
+//!
+//! A provider drives one coding agent: usually a CLI, for which it builds the
+//! arguments and environment of a turn and reads the JSON events it prints.
+//! The Ollama provider instead runs its agent loop in the daemon and emits
+//! the same kind of events. Providers are looked up by name in a
+//! [`ProviderRegistry`], which holds the built-in Claude and Codex providers;
+//! startup adds Ollama and any providers declared in the daemon config.
+
+mod claude;
+mod codex;
+mod declarative;
+mod ollama;
+
+use crate::app::agent_cli::{AgentCliConfig, AgentCliProcess};
+use agent_session_sqlite_persist_core::Session;
+use agent_stream_types::{TokenUsage, ToolCall};
+use daemon_config_and_utils::AgentProviderConfig;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub use claude::ClaudeProvider;
+pub use codex::CodexProvider;
+pub use declarative::DeclarativeProvider;
+pub use ollama::OllamaProvider;
+
+/// Name of the built-in Claude provider, used when a session names none.
+pub const CLAUDE_PROVIDER: &str = "claude";
+/// Name of the built-in Codex provider.
+pub const CODEX_PROVIDER: &str = "codex";
+/// Name of the built-in Ollama provider.
+pub const OLLAMA_PROVIDER: &str = "ollama";
+
+/// What the daemon acts on in one provider JSON event.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentEvent {
+    /// Provider conversation id to resume the next turn from.
+    pub session_id: Option<String>,
+    /// Assistant text carried by the event.
+    pub text: Option<String>,
+    /// Error the provider reported for the turn.
+    pub error: Option<String>,
+    /// The agent stopped to wait for the user.
+    pub awaiting_input: bool,
+    /// Token usage of the whole turn, reported once when it ends.
+    pub usage: Option<TokenUsage>,
+    /// Tool calls the event reports.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// A coding agent CLI the daemon can run.
+pub trait AgentProvider: Send + Sync {
+    /// Registry name, stored as the session's provider.
+    fn name(&self) -> &str;
+
+    /// Executable run when the adapter config names none.
+    fn default_executable(&self) -> &str;
+
+    /// Command-line arguments for one turn.
+    fn build_args(&self, config: &AgentCliConfig) -> Vec<String>;
+
+    /// Environment variables for one turn.
+    fn build_env(&self, config: &AgentCliConfig) -> Vec<(String, String)> {
+        config.environment_variables.clone()
+    }
+
+    /// Start one turn. The default runs the CLI with [`Self::build_args`].
+    fn spawn(&self, config: AgentCliConfig) -> Result<AgentCliProcess, std::io::Error> {
+        AgentCliProcess::spawn_cli(self, config)
+    }
+
+    /// Map one JSON event of a turn onto [`AgentEvent`].
+    fn parse_event(&self, json: &Value) -> AgentEvent;
+
+    /// The provider conversation a session's next turn resumes, if any.
+    fn resume_session_id<'a>(&self, session: &'a Session) -> Option<&'a str> {
+        if session.effective_provider() == Some(self.name()) {
+            session.provider_session_id.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Error message for a process that exited unsuccessfully.
+    fn exit_error(&self, exit_code: Option<i32>) -> String {
+        match exit_code {
+            Some(code) => format!("{} process exited with status {code}", self.name()),
+            None => format!("{} process exited with non-zero status", self.name()),
+        }
+    }
+}
+
+/// Agent providers keyed by name.
+#[derive(Clone)]
+pub struct ProviderRegistry {
+    providers: HashMap<String, Arc<dyn AgentProvider>>,
+}
+
+impl ProviderRegistry {
+    /// A registry holding the built-in providers.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            providers: HashMap::new(),
+        };
+        for provider in [
+            Arc::new(ClaudeProvider) as Arc<dyn AgentProvider>,
+            Arc::new(CodexProvider),
+        ] {
+            registry
+                .register(provider)
+                .expect("built-in provider names are unique");
+        }
+        registry
+    }
+
+    /// Add the providers declared in config.
+    pub fn register_config(&mut self, definitions: &[AgentProviderConfig]) -> Result<(), String> {
+        for definition in definitions {
+            self.register(Arc::new(DeclarativeProvider::new(definition.clone())))?;
+        }
+        Ok(())
+    }
+
+    /// Add a provider; names must be unique.
+    pub fn register(&mut self, provider: Arc<dyn AgentProvider>) -> Result<(), String> {
+        let name = provider.name().to_string();
+        if self.providers.contains_key(&name) {
+            return Err(format!("agent provider \"{name}\" is already registered"));
+        }
+        self.providers.insert(name, provider);
+        Ok(())
+    }
+
+    /// Look up a provider, with a client-facing error for unknown names.
+    pub fn resolve(&self, name: &str) -> Result<Arc<dyn AgentProvider>, String> {
+        self.providers.get(name).cloned().ok_or_else(|| {
+            format!(
+                "unknown provider \"{name}\"; expected one of: {}",
+                self.names().join(", ")
+            )
+        })
+    }
+
+    /// The provider running a session. Sessions that name no provider, or
+    /// one that is no longer registered, run Claude.
+    pub fn for_session(&self, session: &Session) -> Arc<dyn AgentProvider> {
+        session
+            .effective_provider()
+            .and_then(|name| self.providers.get(name))
+            .or_else(|| self.providers.get(CLAUDE_PROVIDER))
+            .cloned()
+            .expect("the Claude provider is always registered")
+    }
+
+    /// Registered provider names, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.providers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn normalize_optional_string(value: Option<&str>) -> Option<String> {
+    let trimmed = value?.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+fn normalize_model(value: Option<&str>) -> Option<String> {
+    let model = normalize_optional_string(value)?;
+    if model.eq_ignore_ascii_case("default") {
+        None
+    } else {
+        Some(model)
+    }
+}
+
+fn normalize_thinking_effort(value: Option<&str>) -> Option<String> {
+    let effort = normalize_optional_string(value)?;
+    if effort.eq_ignore_ascii_case("auto") {
+        None
+    } else {
+        Some(effort)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_session_sqlite_persist_core::{RepositoryId, SessionId, SessionStatus};
+    use chrono::Utc;
+    use daemon_config_and_utils::{AgentProviderArg, AgentProviderFields};
+
+    fn session(provider: Option<&str>) -> Session {
+        Session {
+            id: SessionId::new(),
+            repository_id: RepositoryId::new(),
+            machine_id: None,
+            space_id: None,
+            title: "Session".to_string(),
+            agent_name: None,
+            issue_id: None,
+            issue_title: None,
+            issue_url: None,
+            provider: provider.map(String::from),
+            provider_session_id: Some("thread-1".to_string()),
+            claude_session_id: None,
+            status: SessionStatus::Active,
+            is_worktree: false,
+            worktree_path: None,
+            parent_session_id: None,
+            forked_from_sequence: None,
+            permission_mode: None,
+            created_at: Utc::now(),
+            last_accessed_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn aider() -> AgentProviderConfig {
+        AgentProviderConfig {
+            name: "aider".to_string(),
+            label: None,
+            executable: "aider".to_string(),
+            args: vec![AgentProviderArg::Single("{message}".to_string())],
+            env: Default::default(),
+            fields: AgentProviderFields::default(),
+        }
+    }
+
+    #[test]
+    fn registry_resolves_builtin_and_configured_providers() {
+        let mut registry = ProviderRegistry::new();
+        registry.register_config(&[aider()]).unwrap();
+
+        assert_eq!(registry.names(), vec!["aider", "claude", "codex"]);
+        assert_eq!(registry.resolve("codex").unwrap().name(), "codex");
+        assert_eq!(
+            registry.resolve("aider").unwrap().default_executable(),
+            "aider"
+        );
+        assert_eq!(
+            registry.resolve("gemini").err().as_deref(),
+            Some("unknown provider \"gemini\"; expected one of: aider, claude, codex")
+        );
+
+        let mut clash = aider();
+        clash.name = "codex".to_string();
+        assert!(registry.register_config(&[clash]).is_err());
+    }
+
+    #[test]
+    fn sessions_fall_back_to_claude() {
+        let registry = ProviderRegistry::new();
+
+        let codex = session(Some("codex"));
+        let provider = registry.for_session(&codex);
+        assert_eq!(provider.name(), "codex");
+        assert_eq!(provider.resume_session_id(&codex), Some("thread-1"));
+
+        assert_eq!(registry.for_session(&session(None)).name(), "claude");
+        assert_eq!(
+            registry.for_session(&session(Some("removed"))).name(),
+            "claude"
+        );
+    }
+}
@@ -0,0 +1,830 @@
+//! The built-in Ollama provider: coding sessions against a local model.
+//!
+//! Unlike the CLI providers, the agent runs inside the daemon. Each turn
+//! sends the conversation to Ollama's `/api/chat` with three tools
+//! (`read_file`, `write_file`, `run_command`), runs the tool calls the model
+//! makes, and repeats until the model answers without one. In `plan`
+//! permission mode only `read_file` is offered. A turn emits
+//! provider-neutral JSON events:
+//!
+//! - `{"type": "session", "session_id"}` first, naming the conversation
+//! - `{"type": "assistant", "text"}` for each model reply
+//! - `{"type": "tool_call", "name", "arguments"}`, then
+//!   `{"type": "tool_result", "name", "output", "is_error"}`
+//! - `{"type": "error", "message"}` when the turn fails
+//!
+//! Conversations are stored as JSON files, so sessions resume across daemon
+//! restarts.
+
+use super::{normalize_model, AgentEvent, AgentProvider, OLLAMA_PROVIDER};
+use crate::app::agent_cli::{AgentCliConfig, AgentCliEvent, AgentCliProcess};
+use crate::observability::spawn_in_current_span;
+use crate::utils::sandbox::SandboxPolicy;
+use claude_process_manager::PermissionMode;
+use login_shell::Shell;
+use safe_file_ops::SafeFileOps;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// Model replies with tool calls allowed in one turn.
+const MAX_TOOL_STEPS: usize = 32;
+/// Tools that only inspect the repository, offered in `plan` mode.
+const READ_ONLY_TOOLS: &[&str] = &["read_file"];
+/// Largest tool output returned to the model.
+const MAX_TOOL_OUTPUT_BYTES: usize = 64 * 1024;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs turns against an Ollama server with a read/write/run tool loop.
+#[derive(Clone)]
+pub struct OllamaProvider {
+    http_client: reqwest::Client,
+    base_url: String,
+    default_model: String,
+    file_ops: Arc<SafeFileOps>,
+    conversations_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+}
+
+impl ChatMessage {
+    fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_name: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ToolCall {
+    function: ToolFunction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ToolFunction {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    tools: Value,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    message: ChatMessage,
+}
+
+impl OllamaProvider {
+    pub fn new(
+        base_url: impl Into<String>,
+        default_model: impl Into<String>,
+        file_ops: Arc<SafeFileOps>,
+        conversations_dir: PathBuf,
+    ) -> Self {
+        let http_client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            http_client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            default_model: default_model.into(),
+            file_ops,
+            conversations_dir,
+        }
+    }
+
+    async fn run(&self, config: AgentCliConfig, events: mpsc::Sender<AgentCliEvent>) {
+        let success = match self.run_turn(&config, &events).await {
+            Ok(()) => true,
+            Err(message) => {
+                warn!(error = %message, "Ollama turn failed");
+                emit(&events, json!({ "type": "error", "message": message })).await;
+                false
+            }
+        };
+        let _ = events
+            .send(AgentCliEvent::Finished {
+                success,
+                exit_code: None,
+            })
+            .await;
+    }
+
+    async fn run_turn(
+        &self,
+        config: &AgentCliConfig,
+        events: &mpsc::Sender<AgentCliEvent>,
+    ) -> Result<(), String> {
+        let conversation_id = config
+            .resume_session_id
+            .clone()
+            .filter(|id| is_conversation_id(id))
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let model =
+            normalize_model(config.model.as_deref()).unwrap_or_else(|| self.default_model.clone());
+        let root = Path::new(&config.working_dir);
+        let read_only = config.permission_mode == Some(PermissionMode::Plan);
+
+        let mut messages = self.load_conversation(&conversation_id);
+        if messages.is_empty() {
+            messages.push(ChatMessage::new(
+                "system",
+                system_prompt(&config.working_dir, read_only),
+            ));
+        }
+        messages.push(ChatMessage::new("user", config.message.clone()));
+        info!(conversation_id = %conversation_id, model = %model, "Starting Ollama turn");
+        emit(
+            events,
+            json!({ "type": "session", "session_id": conversation_id }),
+        )
+        .await;
+
+        for _ in 0..MAX_TOOL_STEPS {
+            let reply = self.chat(&model, &messages, read_only).await?;
+            messages.push(reply.clone());
+            if !reply.content.trim().is_empty() {
+                emit(
+                    events,
+                    json!({ "type": "assistant", "text": reply.content }),
+                )
+                .await;
+            }
+            if reply.tool_calls.is_empty() {
+                self.save_conversation(&conversation_id, &messages);
+                return Ok(());
+            }
+
+            for call in &reply.tool_calls {
+                let function = &call.function;
+                emit(
+                    events,
+                    json!({
+                        "type": "tool_call",
+                        "name": function.name,
+                        "arguments": function.arguments,
+                    }),
+                )
+                .await;
+                let (output, is_error) = match self
+                    .run_tool(
+                        root,
+                        &config.shell,
+                        config.sandbox.as_ref(),
+                        read_only,
+                        function,
+                    )
+                    .await
+                {
+                    Ok(output) => (output, false),
+                    Err(error) => (error, true),
+                };
+                emit(
+                    events,
+                    json!({
+                        "type": "tool_result",
+                        "name": function.name,
+                        "output": output,
+                        "is_error": is_error,
+                    }),
+                )
+                .await;
+                messages.push(ChatMessage {
+                    tool_name: Some(function.name.clone()),
+                    ..ChatMessage::new("tool", output)
+                });
+            }
+            self.save_conversation(&conversation_id, &messages);
+        }
+
+        Err(format!(
+            "Stopped after {MAX_TOOL_STEPS} tool steps without a final answer"
+        ))
+    }
+
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        read_only: bool,
+    ) -> Result<ChatMessage, String> {
+        let request = ChatRequest {
+            model,
+            messages,
+            tools: tool_definitions(read_only),
+            stream: false,
+        };
+        let response = self
+            .http_client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|error| format!("Could not reach Ollama at {}: {error}", self.base_url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let detail = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|json| json.get("error")?.as_str().map(ToOwned::to_owned))
+                .unwrap_or(body);
+            return Err(format!("Ollama returned {status}: {detail}"));
+        }
+
+        let completion: ChatResponse = response
+            .json()
+            .await
+            .map_err(|error| format!("Invalid response from Ollama: {error}"))?;
+        Ok(completion.message)
+    }
+
+    async fn run_tool(
+        &self,
+        root: &Path,
+        shell: &Shell,
+        sandbox: Option<&SandboxPolicy>,
+        read_only: bool,
+        function: &ToolFunction,
+    ) -> Result<String, String> {
+        if read_only && !READ_ONLY_TOOLS.contains(&function.name.as_str()) {
+            return Err(format!(
+                "\"{}\" is not available in plan mode",
+                function.name
+            ));
+        }
+        // Some models send the arguments as an encoded JSON string
+        let arguments = match &function.arguments {
+            Value::String(raw) => serde_json::from_str(raw).unwrap_or(Value::Null),
+            other => other.clone(),
+        };
+        let argument = |name: &str| {
+            arguments
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("missing string argument \"{name}\""))
+        };
+
+        match function.name.as_str() {
+            "read_file" => {
+                let path = relative_path(root, argument("path")?);
+                let file = self
+                    .file_ops
+                    .read_full(root, &path, MAX_TOOL_OUTPUT_BYTES)
+                    .map_err(|error| format!("{path}: {error}"))?;
+                Ok(if file.is_truncated {
+                    format!("{}\n[truncated]", file.content)
+                } else {
+                    file.content
+                })
+            }
+            "write_file" => {
+                let path = relative_path(root, argument("path")?);
+                let written = self
+                    .file_ops
+                    .write_full(root, &path, argument("content")?, None, true)
+                    .map_err(|error| format!("{path}: {error}"))?;
+                Ok(format!("Wrote {} bytes to {path}", written.bytes_written))
+            }
+            "run_command" => run_command(root, shell, sandbox, argument("command")?).await,
+            other => Err(format!("unknown tool \"{other}\"")),
+        }
+    }
+
+    fn conversation_file(&self, conversation_id: &str) -> PathBuf {
+        self.conversations_dir
+            .join(format!("{conversation_id}.json"))
+    }
+
+    fn load_conversation(&self, conversation_id: &str) -> Vec<ChatMessage> {
+        std::fs::read_to_string(self.conversation_file(conversation_id))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_conversation(&self, conversation_id: &str, messages: &[ChatMessage]) {
+        let result = std::fs::create_dir_all(&self.conversations_dir).and_then(|()| {
+            let content = serde_json::to_string(messages).map_err(std::io::Error::other)?;
+            std::fs::write(self.conversation_file(conversation_id), content)
+        });
+        if let Err(error) = result {
+            warn!(error = %error, conversation_id, "Failed to save Ollama conversation");
+        }
+    }
+}
+
+impl AgentProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        OLLAMA_PROVIDER
+    }
+
+    fn default_executable(&self) -> &str {
+        "ollama"
+    }
+
+    /// Turns talk to the Ollama server over HTTP, so there is no CLI to
+    /// pass arguments to.
+    fn build_args(&self, _config: &AgentCliConfig) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn spawn(&self, config: AgentCliConfig) -> Result<AgentCliProcess, std::io::Error> {
+        let (events, receiver) = mpsc::channel(64);
+        let provider = self.clone();
+        let task = spawn_in_current_span(async move { provider.run(config, events).await });
+        Ok(AgentCliProcess::from_task(receiver, task))
+    }
+
+    fn parse_event(&self, json: &Value) -> AgentEvent {
+        let field = |name: &str| {
+            json.get(name)
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned)
+        };
+        match json.get("type").and_then(Value::as_str) {
+            Some("session") => AgentEvent {
+                session_id: field("session_id"),
+                ..AgentEvent::default()
+            },
+            Some("assistant") => AgentEvent {
+                text: field("text"),
+                ..AgentEvent::default()
+            },
+            Some("error") => AgentEvent {
+                error: field("message"),
+                ..AgentEvent::default()
+            },
+            _ => AgentEvent::default(),
+        }
+    }
+
+    fn exit_error(&self, _exit_code: Option<i32>) -> String {
+        "Ollama turn failed".to_string()
+    }
+}
+
+async fn emit(events: &mpsc::Sender<AgentCliEvent>, json: Value) {
+    // A closed channel means the turn was stopped and this task is ending
+    let _ = events
+        .send(AgentCliEvent::Json {
+            raw: json.to_string(),
+            json,
+        })
+        .await;
+}
+
+/// Conversation ids name files, so only generated-looking ids are accepted.
+fn is_conversation_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Tool paths are relative to the working directory; absolute paths inside
+/// it are accepted too.
+fn relative_path(root: &Path, path: &str) -> String {
+    Path::new(path)
+        .strip_prefix(root)
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+async fn run_command(
+    root: &Path,
+    shell: &Shell,
+    sandbox: Option<&SandboxPolicy>,
+    command: &str,
+) -> Result<String, String> {
+    let invocation = shell.invocation(command).await;
+    let mut process = match sandbox {
+        Some(sandbox) => sandbox.shell_command(&invocation),
+        None => invocation.command(),
+    };
+    process
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, process.output())
+        .await
+        .map_err(|_| format!("command timed out after {}s", COMMAND_TIMEOUT.as_secs()))?
+        .map_err(|error| format!("failed to run command: {error}"))?;
+
+    let exit_code = output
+        .status
+        .code()
+        .map_or_else(|| "none".to_string(), |code| code.to_string());
+    let mut text = format!("exit code: {exit_code}\n");
+    text.push_str(&String::from_utf8_lossy(&output.stdout));
+    if !output.stderr.is_empty() {
+        text.push_str("\nstderr:\n");
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+    }
+    if text.len() > MAX_TOOL_OUTPUT_BYTES {
+        let mut end = MAX_TOOL_OUTPUT_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n[truncated]");
+    }
+    Ok(text)
+}
+
+fn system_prompt(working_dir: &str, read_only: bool) -> String {
+    if read_only {
+        return format!(
+            "You are a coding agent planning work in the repository at {working_dir}. \
+             Use the read_file tool to inspect it, without changing anything; \
+             paths are relative to the repository root. \
+             When you are done, reply with your plan and no tool calls."
+        );
+    }
+    format!(
+        "You are a coding agent working in the repository at {working_dir}. \
+         Use the read_file, write_file and run_command tools to inspect and change it; \
+         paths are relative to the repository root. \
+         When the task is done, reply with a short summary and no tool calls."
+    )
+}
+
+/// The tools offered to the model; only the read-only ones in `plan` mode.
+fn tool_definitions(read_only: bool) -> Value {
+    let tool = |name: &str, description: &str, parameters: &[(&str, &str)]| {
+        let properties: serde_json::Map<String, Value> = parameters
+            .iter()
+            .map(|(name, description)| {
+                (
+                    name.to_string(),
+                    json!({ "type": "string", "description": description }),
+                )
+            })
+            .collect();
+        let required: Vec<&str> = parameters.iter().map(|(name, _)| *name).collect();
+        json!({
+            "type": "function",
+            "function": {
+                "name": name,
+                "description": description,
+                "parameters": {
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                },
+            },
+        })
+    };
+
+    let tools = [
+        tool(
+            "read_file",
+            "Read a text file.",
+            &[("path", "File path relative to the repository root")],
+        ),
+        tool(
+            "write_file",
+            "Create or overwrite a text file.",
+            &[
+                ("path", "File path relative to the repository root"),
+                ("content", "The complete new file content"),
+            ],
+        ),
+        tool(
+            "run_command",
+            "Run a shell command in the repository root and return its output.",
+            &[("command", "The shell command to run")],
+        ),
+    ];
+    tools
+        .into_iter()
+        .filter(|tool| {
+            !read_only
+                || tool["function"]["name"]
+                    .as_str()
+                    .is_some_and(|name| READ_ONLY_TOOLS.contains(&name))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A stand-in Ollama server answering each request with the next
+    /// `(status, body)` and passing the request bodies back.
+    async fn stub_ollama(responses: Vec<(u16, Value)>) -> (String, mpsc::UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (requests, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                let body_start = loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                let length: usize = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|value| value.trim().parse().unwrap())
+                    .unwrap_or(0);
+                while request.len() < body_start + length {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                requests
+                    .send(serde_json::from_slice(&request[body_start..]).unwrap())
+                    .unwrap();
+
+                let body = body.to_string();
+                let response = format!(
+                    "HTTP/1.1 {status} Stub\r\ncontent-type: application/json\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn reply(content: &str, tool_calls: Value) -> (u16, Value) {
+        (
+            200,
+            json!({
+                "model": "test",
+                "message": { "role": "assistant", "content": content, "tool_calls": tool_calls },
+                "done": true
+            }),
+        )
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ollama-{name}-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn run_turn(provider: &OllamaProvider, config: AgentCliConfig) -> Vec<AgentCliEvent> {
+        let mut process = provider.spawn(config).unwrap();
+        let mut stream = process.take_stream().unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event);
+        }
+        events
+    }
+
+    fn json_events(events: &[AgentCliEvent]) -> Vec<&Value> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                AgentCliEvent::Json { json, .. } => Some(json),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn tool_loop_reads_and_writes_files_then_resumes() {
+        let (url, mut requests) = stub_ollama(vec![
+            reply(
+                "",
+                json!([{ "function": { "name": "read_file", "arguments": { "path": "notes.txt" } } }]),
+            ),
+            reply(
+                "Writing it out.",
+                json!([{ "function": {
+                    "name": "write_file",
+                    "arguments": { "path": "out.txt", "content": "HELLO" }
+                } }]),
+            ),
+            reply("Done", json!([])),
+            reply("Still here", json!([])),
+        ])
+        .await;
+        let repo = temp_dir("repo");
+        std::fs::write(repo.join("notes.txt"), "hello").unwrap();
+        let provider = OllamaProvider::new(
+            url,
+            "qwen2.5-coder",
+            Arc::new(SafeFileOps::with_defaults()),
+            temp_dir("conversations"),
+        );
+        let working_dir = repo.to_string_lossy().into_owned();
+
+        let events = run_turn(
+            &provider,
+            AgentCliConfig::new("ollama", "shout the notes", &working_dir),
+        )
+        .await;
+
+        assert_eq!(
+            std::fs::read_to_string(repo.join("out.txt")).unwrap(),
+            "HELLO"
+        );
+        let types: Vec<&str> = json_events(&events)
+            .iter()
+            .map(|json| json["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "session",
+                "tool_call",
+                "tool_result",
+                "assistant",
+                "tool_call",
+                "tool_result",
+                "assistant"
+            ]
+        );
+        assert!(matches!(
+            events.last(),
+            Some(AgentCliEvent::Finished { success: true, .. })
+        ));
+        let parsed: Vec<AgentEvent> = json_events(&events)
+            .into_iter()
+            .map(|json| provider.parse_event(json))
+            .collect();
+        let conversation_id = parsed[0].session_id.clone().unwrap();
+        assert_eq!(parsed.last().unwrap().text.as_deref(), Some("Done"));
+
+        let first = requests.recv().await.unwrap();
+        assert_eq!(first["model"], "qwen2.5-coder");
+        assert_eq!(first["stream"], false);
+        assert_eq!(first["tools"].as_array().unwrap().len(), 3);
+        let second = requests.recv().await.unwrap();
+        let tool_message = second["messages"].as_array().unwrap().last().unwrap();
+        assert_eq!(tool_message["role"], "tool");
+        assert_eq!(tool_message["content"], "hello");
+        requests.recv().await.unwrap();
+
+        // The next turn continues the stored conversation
+        let mut config = AgentCliConfig::new("ollama", "and again", &working_dir);
+        config.resume_session_id = Some(conversation_id.clone());
+        config.model = Some("llama3.1".to_string());
+        let events = run_turn(&provider, config).await;
+        assert_eq!(
+            json_events(&events)[0]["session_id"],
+            conversation_id.as_str()
+        );
+
+        let resumed = requests.recv().await.unwrap();
+        assert_eq!(resumed["model"], "llama3.1");
+        let messages = resumed["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[1]["content"], "shout the notes");
+        assert_eq!(messages.last().unwrap()["content"], "and again");
+    }
+
+    #[tokio::test]
+    async fn tool_errors_go_back_to_the_model() {
+        let (url, mut requests) = stub_ollama(vec![
+            reply(
+                "",
+                json!([{ "function": { "name": "read_file", "arguments": "{\"path\": \"../etc/passwd\"}" } }]),
+            ),
+            reply("I cannot read that.", json!([])),
+        ])
+        .await;
+        let repo = temp_dir("repo");
+        let provider = OllamaProvider::new(
+            url,
+            "qwen2.5-coder",
+            Arc::new(SafeFileOps::with_defaults()),
+            temp_dir("conversations"),
+        );
+
+        let events = run_turn(
+            &provider,
+            AgentCliConfig::new("ollama", "read it", repo.to_string_lossy()),
+        )
+        .await;
+
+        let result = json_events(&events)
+            .into_iter()
+            .find(|json| json["type"] == "tool_result")
+            .unwrap();
+        assert_eq!(result["is_error"], true);
+        requests.recv().await.unwrap();
+        let tool_message = requests.recv().await.unwrap()["messages"]
+            .as_array()
+            .unwrap()
+            .last()
+            .cloned()
+            .unwrap();
+        assert_eq!(tool_message["role"], "tool");
+        assert!(matches!(
+            events.last(),
+            Some(AgentCliEvent::Finished { success: true, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn plan_mode_offers_and_runs_only_read_only_tools() {
+        let (url, mut requests) = stub_ollama(vec![
+            reply(
+                "",
+                json!([{ "function": {
+                    "name": "write_file",
+                    "arguments": { "path": "out.txt", "content": "HELLO" }
+                } }]),
+            ),
+            reply("Here is the plan.", json!([])),
+        ])
+        .await;
+        let repo = temp_dir("repo");
+        let provider = OllamaProvider::new(
+            url,
+            "qwen2.5-coder",
+            Arc::new(SafeFileOps::with_defaults()),
+            temp_dir("conversations"),
+        );
+        let mut config = AgentCliConfig::new("ollama", "plan it", repo.to_string_lossy());
+        config.permission_mode = Some(PermissionMode::Plan);
+
+        let events = run_turn(&provider, config).await;
+
+        assert!(!repo.join("out.txt").exists());
+        let result = json_events(&events)
+            .into_iter()
+            .find(|json| json["type"] == "tool_result")
+            .unwrap();
+        assert_eq!(result["is_error"], true);
+        let first = requests.recv().await.unwrap();
+        let tools: Vec<&str> = first["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["function"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(tools, vec!["read_file"]);
+    }
+
+    #[tokio::test]
+    async fn server_errors_fail_the_turn() {
+        let (url, _requests) = stub_ollama(vec![(
+            404,
+            json!({ "error": "model \"missing\" not found, try pulling it first" }),
+        )])
+        .await;
+        let provider = OllamaProvider::new(
+            url,
+            "missing",
+            Arc::new(SafeFileOps::with_defaults()),
+            temp_dir("conversations"),
+        );
+
+        let events = run_turn(
+            &provider,
+            AgentCliConfig::new("ollama", "hi", temp_dir("repo").to_string_lossy()),
+        )
+        .await;
+
+        let error = json_events(&events)
+            .into_iter()
+            .map(|json| provider.parse_event(json))
+            .find_map(|event| event.error)
+            .unwrap();
+        assert_eq!(
+            error,
+            "Ollama returned 404 Not Found: model \"missing\" not found, try pulling it first"
+        );
+        assert!(matches!(
+            events.last(),
+            Some(AgentCliEvent::Finished { success: false, .. })
+        ));
+    }
+}
@@ -0,0 +1,251 @@
+//! Agent scheduling: admits agent turns through the daemon's
+//! [`AgentScheduler`] and publishes its queue to clients.
+//!
+//! While a turn waits for a slot its session shows `Waiting` with a
+//! `scheduler_position`, and every scheduler change is broadcast on the
+//! global channel as a `scheduler_updated` event.
+
+use crate::app::DaemonState;
+use crate::armin_adapter::DaemonArmin;
+use crate::ipc::handlers::claude::write_runtime_status;
+use crate::observability::spawn_in_current_span;
+use crate::utils::agent_scheduler::{
+    Admission, AgentSlot, SchedulerLimits, SchedulerSnapshot, SlotRequest,
+};
+use agent_session_sqlite_persist_core::{CodingSessionStatus, SessionId, SessionWriter};
+use daemon_ipc::{Event, EventType, SubscriptionManager};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use tracing::{info, warn};
+
+/// Start an agent turn once the scheduler has a slot for it.
+///
+/// `start` spawns the agent and must keep the slot until its process exits.
+/// If a slot is free it runs now and its result is returned; otherwise the
+/// session is marked `Waiting`, `start` runs in the background once admitted,
+/// and a `waiting` response with the queue position is returned.
+pub(crate) async fn schedule_agent<F, Fut>(
+    state: &DaemonState,
+    request: SlotRequest,
+    start: F,
+) -> Result<Value, (String, String)>
+where
+    F: FnOnce(AgentSlot) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Value, (String, String)>> + Send + 'static,
+{
+    let session_id = request.session_id.clone();
+    let (position, slot) = match state.scheduler.request(request) {
+        Admission::Started(slot) => return start(slot).await,
+        Admission::Waiting { position, slot } => (position, slot),
+    };
+
+    info!(session_id = %session_id, position, "Agent waiting for a scheduler slot");
+    // Written before the wait starts, so it can never land after the
+    // admitted agent has reported Running
+    write_runtime_status(state, &session_id, CodingSessionStatus::Waiting, None);
+
+    let state_for_task = state.clone();
+    let session_id_for_task = session_id.clone();
+    spawn_in_current_span(async move {
+        // The request was cancelled, e.g. by agent.stop
+        let Ok(slot) = slot.await else {
+            return;
+        };
+        if let Err((_, message)) = start(slot).await {
+            warn!(
+                session_id = %session_id_for_task,
+                error = %message,
+                "Scheduled agent failed to start"
+            );
+            write_runtime_status(
+                &state_for_task,
+                &session_id_for_task,
+                CodingSessionStatus::Error,
+                Some(message),
+            );
+        }
+    });
+
+    Ok(serde_json::json!({
+        "status": "waiting",
+        "session_id": session_id,
+        "position": position,
+    }))
+}
+
+/// Publish scheduler changes for as long as the daemon runs.
+pub(crate) fn spawn_scheduler_publisher(state: &DaemonState) {
+    let mut changes = state.scheduler.subscribe();
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut positions = HashMap::new();
+        while changes.changed().await.is_ok() {
+            let snapshot = changes.borrow_and_update().clone();
+            let device_id = state.device_id.lock().unwrap().clone();
+            publish_snapshot(
+                &state.armin,
+                &state.subscriptions,
+                device_id.as_deref(),
+                state.scheduler.limits(),
+                &snapshot,
+                &mut positions,
+            );
+        }
+    });
+}
+
+/// Record changed queue positions and broadcast the snapshot.
+///
+/// `positions` holds the positions published last time, so only sessions
+/// that moved, joined or left the queue are written.
+fn publish_snapshot(
+    armin: &DaemonArmin,
+    subscriptions: &SubscriptionManager,
+    device_id: Option<&str>,
+    limits: SchedulerLimits,
+    snapshot: &SchedulerSnapshot,
+    positions: &mut HashMap<String, usize>,
+) {
+    let current: HashMap<String, usize> = snapshot
+        .waiting
+        .iter()
+        .enumerate()
+        .map(|(index, request)| (request.session_id.clone(), index + 1))
+        .collect();
+
+    if let Some(device_id) = device_id {
+        let left = positions
+            .keys()
+            .filter(|session_id| !current.contains_key(*session_id))
+            .map(|session_id| (session_id, None));
+        let moved = current
+            .iter()
+            .filter(|(session_id, position)| positions.get(*session_id) != Some(position))
+            .map(|(session_id, position)| (session_id, Some(*position as i64)));
+        for (session_id, position) in left.chain(moved) {
+            if let Err(e) = armin.update_runtime_scheduler_position(
+                &SessionId::from_string(session_id),
+                device_id,
+                position,
+            ) {
+                warn!(session_id = %session_id, error = %e, "Failed to write scheduler position");
+            }
+        }
+    }
+    *positions = current;
+
+    subscriptions.broadcast_global(scheduler_event(limits, snapshot));
+}
+
+fn scheduler_event(limits: SchedulerLimits, snapshot: &SchedulerSnapshot) -> Event {
+    let waiting: Vec<Value> = snapshot
+        .waiting
+        .iter()
+        .enumerate()
+        .map(|(index, request)| {
+            serde_json::json!({
+                "session_id": request.session_id,
+                "repository_id": request.repository_id,
+                "priority": request.priority,
+                "position": index + 1,
+            })
+        })
+        .collect();
+
+    Event::new(
+        EventType::SchedulerUpdated,
+        "",
+        serde_json::json!({
+            "limits": limits,
+            "running": snapshot.running,
+            "waiting": waiting,
+        }),
+        0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::armin_adapter::create_test_armin;
+    use agent_session_sqlite_persist_core::{NewRepository, NewSession, SessionReader};
+
+    fn request(session_id: &str) -> SlotRequest {
+        SlotRequest {
+            session_id: session_id.to_string(),
+            repository_id: "repo".to_string(),
+            priority: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn publishes_positions_and_global_event() {
+        let subscriptions = SubscriptionManager::new();
+        let armin = create_test_armin(subscriptions.clone()).unwrap();
+        let repository = armin
+            .create_repository(NewRepository::new(
+                format!("/tmp/schedule-test-{}", uuid::Uuid::new_v4()),
+                "schedule-repo",
+                false,
+            ))
+            .unwrap();
+        let session = || {
+            armin
+                .create_session_with_metadata(NewSession::new(repository.id.clone(), "Scheduled"))
+                .unwrap()
+                .id
+        };
+        let (first, second) = (session(), session());
+        let position = |session_id: &SessionId| {
+            armin
+                .get_session_state(session_id)
+                .unwrap()
+                .and_then(|state| state.runtime_status.coding_session.scheduler_position)
+        };
+        let limits = SchedulerLimits {
+            max_concurrent: 1,
+            max_per_repository: 1,
+        };
+        let mut positions = HashMap::new();
+        // Subscribe after creating the sessions to skip their created events
+        let mut global = subscriptions.subscribe_global();
+
+        let snapshot = SchedulerSnapshot {
+            running: vec![request("running")],
+            waiting: vec![request(first.as_str()), request(second.as_str())],
+        };
+        publish_snapshot(
+            &armin,
+            &subscriptions,
+            Some("device"),
+            limits,
+            &snapshot,
+            &mut positions,
+        );
+        assert_eq!(position(&first), Some(1));
+        assert_eq!(position(&second), Some(2));
+
+        let event = global.try_recv().unwrap();
+        assert_eq!(event.event_type, EventType::SchedulerUpdated);
+        assert_eq!(event.data["limits"]["max_concurrent"], 1);
+        assert_eq!(event.data["running"][0]["session_id"], "running");
+        assert_eq!(event.data["waiting"][1]["session_id"], second.as_str());
+        assert_eq!(event.data["waiting"][1]["position"], 2);
+
+        let snapshot = SchedulerSnapshot {
+            running: vec![request(first.as_str())],
+            waiting: vec![request(second.as_str())],
+        };
+        publish_snapshot(
+            &armin,
+            &subscriptions,
+            Some("device"),
+            limits,
+            &snapshot,
+            &mut positions,
+        );
+        assert_eq!(position(&first), None);
+        assert_eq!(position(&second), Some(1));
+    }
+}
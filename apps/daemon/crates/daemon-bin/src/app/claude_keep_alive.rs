@@ -0,0 +1,126 @@
+//! Keep-alive Claude processes: with `claude_keep_alive_secs` set, a session
+//! keeps one Claude process and sends it later turns over stdin.
+//!
+//! A turn holds its scheduler slot and its `claude_processes` entry only until
+//! its result has been handled, so an idle process neither takes a slot nor
+//! makes new messages queue behind it.
+
+use crate::app::DaemonState;
+use crate::ipc::handlers::claude::write_runtime_status;
+use crate::ipc::handlers::queue::dispatch_next_queued;
+use crate::observability::spawn_in_current_span;
+use crate::utils::agent_scheduler::AgentSlot;
+use crate::utils::agent_watchdog::TurnLimits;
+use agent_session_sqlite_persist_core::CodingSessionStatus;
+use claude_process_manager::{ClaudeConfig, ClaudeInput};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tracing::info;
+
+/// A session's keep-alive Claude process.
+pub struct KeepAliveClaude {
+    pub pid: Option<u32>,
+    /// Configuration the process was spawned with.
+    pub config: ClaudeConfig,
+    pub input: ClaudeInput,
+    pub stop_tx: broadcast::Sender<()>,
+    /// Completed turns, updated once the event handler has handled each
+    /// turn's result.
+    pub turns: watch::Receiver<u64>,
+    /// Limits of the current turn; sending a turn's limits starts the event
+    /// handler's watchdog for it.
+    pub limits: watch::Sender<TurnLimits>,
+}
+
+/// Idle timeout for new Claude processes, if keep-alive is enabled.
+pub(crate) fn keep_alive_timeout(state: &DaemonState) -> Option<Duration> {
+    let secs = state.config.claude_keep_alive_secs;
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Run a turn on the session's keep-alive process if it can take `config`,
+/// watched against `limits`.
+///
+/// Hands the slot back when a new process has to be spawned instead: there is
+/// none, it was spawned with other flags, or its stdin has already closed.
+pub(crate) async fn send_to_keep_alive(
+    state: &DaemonState,
+    session_id: &str,
+    config: &ClaudeConfig,
+    limits: TurnLimits,
+    slot: AgentSlot,
+) -> Result<(), AgentSlot> {
+    let (input, stop_tx, turns, limits_tx) = {
+        let mut processes = state.claude_keep_alive.lock().unwrap();
+        match processes.get(session_id) {
+            Some(process) if process.config.can_share_process(config) => (
+                process.input.clone(),
+                process.stop_tx.clone(),
+                process.turns.clone(),
+                process.limits.clone(),
+            ),
+            Some(_) => {
+                // Spawned with other flags; let it exit and start a new one
+                if let Some(process) = processes.remove(session_id) {
+                    info!(session_id = %session_id, "Replacing keep-alive Claude process");
+                    spawn_in_current_span(async move { process.input.close().await });
+                }
+                return Err(slot);
+            }
+            None => return Err(slot),
+        }
+    };
+
+    let turns_completed = *turns.borrow();
+    state
+        .claude_processes
+        .lock()
+        .unwrap()
+        .insert(session_id.to_string(), stop_tx);
+    write_runtime_status(state, session_id, CodingSessionStatus::Running, None);
+    limits_tx.send_replace(limits);
+
+    let sent = match &config.tool_result {
+        Some(tool_result) => {
+            input
+                .send_tool_result(&tool_result.tool_use_id, &tool_result.content)
+                .await
+        }
+        None => input.send_message(&config.message).await,
+    };
+    if let Err(e) = sent {
+        info!(session_id = %session_id, error = %e, "Keep-alive Claude process is gone");
+        state.claude_processes.lock().unwrap().remove(session_id);
+        return Err(slot);
+    }
+
+    info!(session_id = %session_id, turn = turns_completed + 1, "Sent turn to keep-alive Claude process");
+    end_turn_on_result(state, session_id, turns, turns_completed, slot);
+    Ok(())
+}
+
+/// Once the turn after `turns_completed` has its result handled, release its
+/// slot and running entry and send the session's next queued message.
+pub(crate) fn end_turn_on_result(
+    state: &DaemonState,
+    session_id: &str,
+    mut turns: watch::Receiver<u64>,
+    turns_completed: u64,
+    slot: AgentSlot,
+) {
+    let state = state.clone();
+    let session_id = session_id.to_string();
+    spawn_in_current_span(async move {
+        // The process ended mid-turn; its event handler cleans up
+        if turns
+            .wait_for(|completed| *completed > turns_completed)
+            .await
+            .is_err()
+        {
+            return;
+        }
+        state.claude_processes.lock().unwrap().remove(&session_id);
+        drop(slot);
+        dispatch_next_queued(&state, &session_id);
+    });
+}
@@ -1,8 +1,15 @@
 //! Daemon initialization.
 
+use crate::app::agent_provider::{OllamaProvider, ProviderRegistry};
+use crate::app::agent_schedule::spawn_scheduler_publisher;
+use crate::app::process_recovery::recover_orphaned_processes;
 use crate::app::{DaemonState, StartupStatusWriter};
 use crate::armin_adapter::create_daemon_armin;
+use crate::ipc::handlers::queue::resume_message_queues;
 use crate::ipc::register_handlers;
+use crate::utils::agent_scheduler::{AgentScheduler, SchedulerLimits};
+use crate::utils::mcp_servers::remove_stale_mcp_configs;
+use crate::utils::permission_broker::PermissionBroker;
 use crate::utils::SessionSecretCache;
 use daemon_config_and_utils::{force_flush, shutdown, Config, Paths};
 use daemon_database::AsyncDatabase;
@@ -13,7 +20,7 @@ use safe_file_ops::SafeFileOps;
 use signal_hook::consts::signal::{SIGINT, SIGTERM};
 #[cfg(unix)]
 use signal_hook::iterator::Signals;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tokio::task::JoinHandle;
@@ -35,6 +42,8 @@ pub async fn run_daemon(
 
     startup_status.update("critical_bootstrap", "Ensuring runtime directories exist");
     paths.ensure_dirs()?;
+    // Only this daemon's agents use these, and none survive a restart
+    remove_stale_mcp_configs(&paths.mcp_configs_dir());
 
     startup_status.update("critical_bootstrap", "Writing daemon PID file");
     let pid = std::process::id();
@@ -67,18 +76,38 @@ pub async fn run_daemon(
         .ok_or_else(|| "Database encryption key is unavailable".to_string())?;
     info!(device_id = %device_id, "Local device identity ready");
 
+    let safe_file_ops = Arc::new(SafeFileOps::with_defaults());
+    let mut providers = ProviderRegistry::new();
+    providers.register(Arc::new(OllamaProvider::new(
+        config.ollama_url.clone(),
+        config.ollama_model.clone(),
+        safe_file_ops.clone(),
+        paths.ollama_conversations_dir(),
+    )))?;
+    providers.register_config(&config.agent_providers)?;
+    info!(providers = ?providers.names(), "Agent providers registered");
+
     let config = Arc::new(config);
     let shared_paths = Arc::new(paths.clone());
     let claude_processes = Arc::new(Mutex::new(HashMap::new()));
     let device_id_state = Arc::new(Mutex::new(Some(device_id)));
     let db_encryption_key_state = Arc::new(Mutex::new(Some(db_encryption_key)));
     let session_secret_cache = SessionSecretCache::new();
+    let scheduler = AgentScheduler::new(SchedulerLimits {
+        max_concurrent: config.max_concurrent_agents,
+        max_per_repository: config.max_concurrent_agents_per_repository,
+    });
     let state = DaemonState {
         config,
         paths: shared_paths,
         db,
         secrets: Arc::new(Mutex::new(secrets)),
         claude_processes,
+        claude_keep_alive: Arc::new(Mutex::new(HashMap::new())),
+        permissions: PermissionBroker::new(),
+        scheduler,
+        queue_dispatches: Arc::new(Mutex::new(HashSet::new())),
+        providers: Arc::new(providers),
         terminal_processes: Arc::new(Mutex::new(HashMap::new())),
         db_encryption_key: db_encryption_key_state,
         subscriptions: ipc_server.subscriptions().clone(),
@@ -86,10 +115,12 @@ pub async fn run_daemon(
         device_id: device_id_state,
         device_private_key: Arc::new(Mutex::new(Some(device_private_key))),
         armin,
-        safe_file_ops: Arc::new(SafeFileOps::with_defaults()),
+        safe_file_ops,
     };
 
     register_handlers(&ipc_server, state.clone()).await;
+    spawn_scheduler_publisher(&state);
+    recover_orphaned_processes(&state);
 
     startup_status.update("critical_bootstrap", "Starting IPC server");
     let socket_path = paths.socket_file();
@@ -108,6 +139,10 @@ pub async fn run_daemon(
 
     startup_status.update("ready", "Daemon IPC socket is listening");
 
+    // Messages queued before a restart are sent once the socket is up, so
+    // agents can reach the permission prompt server
+    resume_message_queues(&state);
+
     let server_result = match ipc_task.await {
         Ok(result) => result.map_err(|err| -> Box<dyn std::error::Error> { err.into() }),
         Err(err) => Err(format!("IPC server task join failed: {err}").into()),
@@ -1,14 +1,20 @@
 //! Application wiring and lifecycle management.
 
 pub(crate) mod agent_cli;
+pub(crate) mod agent_provider;
+pub(crate) mod agent_schedule;
+pub(crate) mod claude_keep_alive;
 mod init;
 mod lifecycle;
+pub(crate) mod permission_mcp;
+pub(crate) mod process_recovery;
 mod space_scope;
 mod startup_status;
 mod state;
 
 pub use init::run_daemon;
 pub use lifecycle::{check_status, stop_daemon};
+pub use permission_mcp::{permission_prompt_tool, run_permission_mcp};
 pub(crate) use space_scope::resolve_machine_space_scope;
 pub(crate) use startup_status::StartupStatusWriter;
 pub use state::DaemonState;
@@ -0,0 +1,339 @@
+//! Permission prompt MCP server (`unbound-daemon permission-mcp`).
+//!
+//! Claude runs with `--permission-prompt-tool mcp__unbound__approval_prompt`
+//! and an `--mcp-config` that launches this binary in this mode. It speaks MCP
+//! (JSON-RPC over stdio) and forwards every approval prompt to the daemon as an
+//! `agent.permission_request`, which blocks until a client decides.
+
+use claude_process_manager::PermissionPromptTool;
+use daemon_ipc::{IpcClient, Method};
+use serde_json::{json, Value};
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// MCP server name the prompt tool is registered under.
+pub const PERMISSION_MCP_SERVER_NAME: &str = "unbound";
+/// Name of the approval tool exposed by the server.
+pub const PERMISSION_PROMPT_TOOL: &str = "approval_prompt";
+/// Protocol version offered when the client does not name one.
+pub(crate) const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Build the permission prompt tool configuration for a Claude session.
+pub fn permission_prompt_tool(
+    executable: &Path,
+    socket_path: &str,
+    session_id: &str,
+) -> PermissionPromptTool {
+    let mcp_config = json!({
+        "mcpServers": {
+            PERMISSION_MCP_SERVER_NAME: {
+                "command": executable.to_string_lossy(),
+                "args": ["permission-mcp", "--socket", socket_path, "--session-id", session_id],
+            }
+        }
+    });
+    PermissionPromptTool {
+        mcp_config: mcp_config.to_string(),
+        tool_name: format!("mcp__{PERMISSION_MCP_SERVER_NAME}__{PERMISSION_PROMPT_TOOL}"),
+    }
+}
+
+/// Serve MCP on stdin/stdout until the agent closes stdin.
+pub async fn run_permission_mcp(
+    socket_path: &str,
+    session_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+    // Prompts are answered concurrently, so replies go through one writer task
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<Value>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(reply) = reply_rx.recv().await {
+            let line = format!("{reply}\n");
+            if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(line) = lines.next_line().await? {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let message: Value = match serde_json::from_str(trimmed) {
+            Ok(message) => message,
+            Err(error) => {
+                warn!(error = %error, "Failed to parse MCP message");
+                let _ = reply_tx.send(error_reply(Value::Null, -32700, "Parse error"));
+                continue;
+            }
+        };
+
+        match route_message(&message) {
+            McpReply::None => {}
+            McpReply::Now(reply) => {
+                let _ = reply_tx.send(reply);
+            }
+            McpReply::PermissionPrompt { id, arguments } => {
+                let client = IpcClient::new(socket_path);
+                let session_id = session_id.to_string();
+                let reply_tx = reply_tx.clone();
+                tokio::spawn(async move {
+                    let decision = request_permission(&client, &session_id, &arguments).await;
+                    let _ = reply_tx.send(tool_result_reply(id, &decision));
+                });
+            }
+        }
+    }
+
+    drop(reply_tx);
+    let _ = writer.await;
+    Ok(())
+}
+
+/// How to answer one incoming MCP message.
+#[derive(Debug, PartialEq)]
+enum McpReply {
+    /// Notifications get no reply.
+    None,
+    /// Reply immediately.
+    Now(Value),
+    /// Ask the daemon, then reply with the decision.
+    PermissionPrompt { id: Value, arguments: Value },
+}
+
+fn route_message(message: &Value) -> McpReply {
+    let Some(id) = message.get("id").cloned() else {
+        return McpReply::None;
+    };
+    let method = message
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let params = message.get("params");
+
+    match method {
+        "initialize" => {
+            let protocol_version = params
+                .and_then(|p| p.get("protocolVersion"))
+                .and_then(Value::as_str)
+                .unwrap_or(MCP_PROTOCOL_VERSION);
+            McpReply::Now(result_reply(
+                id,
+                json!({
+                    "protocolVersion": protocol_version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": {
+                        "name": PERMISSION_MCP_SERVER_NAME,
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            ))
+        }
+        "ping" => McpReply::Now(result_reply(id, json!({}))),
+        "tools/list" => McpReply::Now(result_reply(
+            id,
+            json!({
+                "tools": [{
+                    "name": PERMISSION_PROMPT_TOOL,
+                    "description": "Ask the Unbound user whether a tool use may proceed.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "tool_name": { "type": "string" },
+                            "input": { "type": "object" },
+                            "tool_use_id": { "type": "string" },
+                        },
+                        "required": ["tool_name", "input"],
+                    },
+                }],
+            }),
+        )),
+        "tools/call" => {
+            let name = params
+                .and_then(|p| p.get("name"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if name != PERMISSION_PROMPT_TOOL {
+                return McpReply::Now(error_reply(id, -32602, &format!("Unknown tool: {name}")));
+            }
+            let arguments = params
+                .and_then(|p| p.get("arguments"))
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            McpReply::PermissionPrompt { id, arguments }
+        }
+        _ => McpReply::Now(error_reply(
+            id,
+            -32601,
+            &format!("Method not found: {method}"),
+        )),
+    }
+}
+
+/// Forward a prompt to the daemon and return Claude's decision payload.
+///
+/// Any failure to reach the daemon denies the tool use.
+async fn request_permission(client: &IpcClient, session_id: &str, arguments: &Value) -> Value {
+    let params = json!({
+        "session_id": session_id,
+        "tool_name": arguments.get("tool_name").cloned().unwrap_or(Value::Null),
+        "input": arguments.get("input").cloned().unwrap_or_else(|| json!({})),
+        "tool_use_id": arguments.get("tool_use_id").cloned().unwrap_or(Value::Null),
+    });
+
+    let failure = match client
+        .call_method_with_params(Method::AgentPermissionRequest, params)
+        .await
+    {
+        Ok(response) => match (response.result, response.error) {
+            (Some(decision), None) => {
+                debug!(session_id, "Permission prompt answered");
+                return decision;
+            }
+            (_, Some(error)) => error.message,
+            (None, None) => "empty response".to_string(),
+        },
+        Err(error) => error.to_string(),
+    };
+
+    warn!(session_id, error = %failure, "Permission request failed");
+    json!({
+        "behavior": "deny",
+        "message": format!("Permission request failed: {failure}"),
+    })
+}
+
+fn result_reply(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error_reply(id: Value, code: i32, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// The prompt tool contract: the decision JSON as the tool's text content.
+fn tool_result_reply(id: Value, decision: &Value) -> Value {
+    result_reply(
+        id,
+        json!({ "content": [{ "type": "text", "text": decision.to_string() }] }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initialize_echoes_protocol_version_and_advertises_tools() {
+        let reply = route_message(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2025-06-18" },
+        }));
+
+        let McpReply::Now(reply) = reply else {
+            panic!("initialize should be answered immediately");
+        };
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"]["protocolVersion"], "2025-06-18");
+        assert!(reply["result"]["capabilities"]["tools"].is_object());
+    }
+
+    #[test]
+    fn notifications_get_no_reply() {
+        let reply = route_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized",
+        }));
+        assert_eq!(reply, McpReply::None);
+    }
+
+    #[test]
+    fn tools_list_exposes_approval_prompt() {
+        let McpReply::Now(reply) = route_message(&json!({ "id": 2, "method": "tools/list" }))
+        else {
+            panic!("tools/list should be answered immediately");
+        };
+        assert_eq!(reply["result"]["tools"][0]["name"], PERMISSION_PROMPT_TOOL);
+    }
+
+    #[test]
+    fn approval_prompt_call_is_forwarded() {
+        let reply = route_message(&json!({
+            "id": "call-1",
+            "method": "tools/call",
+            "params": {
+                "name": PERMISSION_PROMPT_TOOL,
+                "arguments": { "tool_name": "Bash", "input": { "command": "ls" } },
+            },
+        }));
+
+        assert_eq!(
+            reply,
+            McpReply::PermissionPrompt {
+                id: json!("call-1"),
+                arguments: json!({ "tool_name": "Bash", "input": { "command": "ls" } }),
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_methods_and_tools_are_errors() {
+        let McpReply::Now(reply) = route_message(&json!({ "id": 3, "method": "resources/list" }))
+        else {
+            panic!("unknown methods should be answered immediately");
+        };
+        assert_eq!(reply["error"]["code"], -32601);
+
+        let McpReply::Now(reply) = route_message(&json!({
+            "id": 4,
+            "method": "tools/call",
+            "params": { "name": "other" },
+        })) else {
+            panic!("unknown tools should be answered immediately");
+        };
+        assert_eq!(reply["error"]["code"], -32602);
+    }
+
+    #[test]
+    fn tool_result_carries_decision_as_text() {
+        let reply = tool_result_reply(
+            json!(5),
+            &json!({ "behavior": "allow", "updatedInput": {} }),
+        );
+        let text = reply["result"]["content"][0]["text"].as_str().unwrap();
+        let decision: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(decision["behavior"], "allow");
+    }
+
+    #[test]
+    fn prompt_tool_config_launches_this_binary_for_the_session() {
+        let tool = permission_prompt_tool(
+            Path::new("/usr/local/bin/unbound-daemon"),
+            "/tmp/daemon.sock",
+            "session-1",
+        );
+
+        assert_eq!(tool.tool_name, "mcp__unbound__approval_prompt");
+        let config: Value = serde_json::from_str(&tool.mcp_config).unwrap();
+        let server = &config["mcpServers"]["unbound"];
+        assert_eq!(server["command"], "/usr/local/bin/unbound-daemon");
+        assert_eq!(
+            server["args"],
+            json!([
+                "permission-mcp",
+                "--socket",
+                "/tmp/daemon.sock",
+                "--session-id",
+                "session-1"
+            ])
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Pid records for agent and terminal processes, and startup recovery of the
+//! processes and sessions a crashed daemon left behind.
+
+use crate::app::DaemonState;
+use crate::ipc::handlers::claude::write_runtime_status;
+use crate::ipc::handlers::queue::dispatch_next_queued;
+use crate::machines::terminal::TerminalHandle;
+use agent_session_sqlite_persist_core::{
+    CodingSessionStatus, NewMessage, NewProcessRecord, ProcessKind, ProcessRecord, SessionId,
+    SessionReader, SessionWriter,
+};
+use daemon_config_and_utils::OrphanProcessPolicy;
+use session_lifecycle_orchestrator::{reconcile_orphans, OrphanAction, ProcessControl};
+use std::collections::HashSet;
+use std::path::Path;
+use tokio::sync::broadcast;
+use tokio::time::{interval, Duration};
+use tracing::{info, warn};
+
+/// How often an adopted process is checked for exit.
+const ADOPTED_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Record a process spawned for a session, returning the record id to pass
+/// to [`forget_process`] once it exits.
+pub(crate) fn record_process(
+    state: &DaemonState,
+    session_id: &str,
+    kind: ProcessKind,
+    pid: Option<u32>,
+    command: &str,
+) -> Option<String> {
+    let pid = pid?;
+    match state.armin.record_process(
+        &SessionId::from_string(session_id),
+        NewProcessRecord {
+            kind,
+            pid,
+            command: command.to_string(),
+        },
+    ) {
+        Ok(record) => Some(record.id),
+        Err(error) => {
+            warn!(session_id, pid, error = %error, "Failed to record process");
+            None
+        }
+    }
+}
+
+/// Drop the record of a process that has exited.
+pub(crate) fn forget_process(state: &DaemonState, record_id: Option<String>) {
+    let Some(record_id) = record_id else {
+        return;
+    };
+    if let Err(error) = state.armin.remove_process_record(&record_id) {
+        warn!(record_id, error = %error, "Failed to remove process record");
+    }
+}
+
+/// Kill or adopt processes that outlived the previous daemon and settle the
+/// sessions it left running. Runs once at startup, before any agent starts.
+pub(crate) fn recover_orphaned_processes(state: &DaemonState) {
+    let Some(device_id) = state.device_id.lock().unwrap().clone() else {
+        return;
+    };
+    let action = match state.config.orphan_processes {
+        OrphanProcessPolicy::Kill => OrphanAction::Kill,
+        OrphanProcessPolicy::Adopt => OrphanAction::Adopt,
+    };
+    let recoveries = match reconcile_orphans(&*state.armin, &SystemProcesses, action, &device_id) {
+        Ok(recoveries) => recoveries,
+        Err(error) => {
+            warn!(error = %error, "Failed to reconcile orphaned processes");
+            return;
+        }
+    };
+
+    let unfinished_terminals = match state.armin.list_unfinished_terminals() {
+        Ok(terminals) => terminals,
+        Err(error) => {
+            warn!(error = %error, "Failed to list unfinished terminals");
+            Vec::new()
+        }
+    };
+    let mut adopted_terminals = HashSet::new();
+
+    for recovery in recoveries {
+        info!(
+            session_id = %recovery.session_id,
+            stale_status = ?recovery.stale_status,
+            killed = recovery.killed.len(),
+            adopted = recovery.adopted.len(),
+            exited = recovery.exited.len(),
+            "Recovered session after daemon restart"
+        );
+        for record in recovery.adopted {
+            // An adopted terminal keeps the id of its terminal, found by pid
+            let terminal_id = unfinished_terminals
+                .iter()
+                .find(|terminal| {
+                    record.kind == ProcessKind::Terminal
+                        && terminal.session_id == record.session_id
+                        && terminal.pid == Some(record.pid)
+                })
+                .map(|terminal| terminal.id.clone());
+            adopted_terminals.extend(terminal_id.clone());
+            supervise_adopted(state, record, terminal_id);
+        }
+    }
+
+    // The other terminals were killed or exited with the previous daemon
+    for terminal in unfinished_terminals {
+        if adopted_terminals.contains(&terminal.id) {
+            continue;
+        }
+        if let Err(error) = state.armin.finish_terminal(&terminal.id, None) {
+            warn!(terminal_id = %terminal.id, error = %error, "Failed to finish lost terminal");
+        }
+    }
+}
+
+/// Watch an adopted process until it exits, stopping it on `agent.stop` or
+/// `terminal.stop`. Its output went to the previous daemon and is lost.
+///
+/// A terminal is registered under `terminal_id`, or under the record id if
+/// it has no terminal record.
+fn supervise_adopted(state: &DaemonState, record: ProcessRecord, terminal_id: Option<String>) {
+    let session_id = record.session_id.as_str().to_string();
+    let terminal_key = terminal_id.clone().unwrap_or_else(|| record.id.clone());
+    let (stop_tx, mut stop_rx) = broadcast::channel::<()>(1);
+    match record.kind {
+        ProcessKind::Agent => {
+            state
+                .claude_processes
+                .lock()
+                .unwrap()
+                .insert(session_id.clone(), stop_tx);
+        }
+        ProcessKind::Terminal => {
+            let handle = TerminalHandle {
+                session_id: session_id.clone(),
+                stop_tx,
+                pty: None,
+            };
+            state
+                .terminal_processes
+                .lock()
+                .unwrap()
+                .insert(terminal_key.clone(), handle);
+        }
+    }
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut poll = interval(ADOPTED_POLL_INTERVAL);
+        let stopped = loop {
+            tokio::select! {
+                _ = stop_rx.recv() => {
+                    SystemProcesses.kill(&record);
+                    break true;
+                }
+                _ = poll.tick() => {
+                    if !SystemProcesses.is_running(&record) {
+                        break false;
+                    }
+                }
+            }
+        };
+        info!(session_id = %session_id, pid = record.pid, stopped, "Adopted process ended");
+
+        forget_process(&state, Some(record.id.clone()));
+        match record.kind {
+            ProcessKind::Agent => {
+                state.claude_processes.lock().unwrap().remove(&session_id);
+                let status = if stopped {
+                    CodingSessionStatus::NotAvailable
+                } else {
+                    CodingSessionStatus::Idle
+                };
+                write_runtime_status(&state, &session_id, status, None);
+                dispatch_next_queued(&state, &session_id);
+            }
+            ProcessKind::Terminal => {
+                state
+                    .terminal_processes
+                    .lock()
+                    .unwrap()
+                    .remove(&terminal_key);
+                // The exit code went to the previous daemon
+                if let Some(terminal_id) = &terminal_id {
+                    if let Err(error) = state.armin.finish_terminal(terminal_id, None) {
+                        warn!(error = %error, "Failed to finish adopted terminal");
+                    }
+                }
+                let content = serde_json::json!({
+                    "type": "terminal_finished",
+                    "terminal_id": terminal_key,
+                    "exit_code": -1,
+                })
+                .to_string();
+                if let Err(error) = state
+                    .armin
+                    .append(&record.session_id, NewMessage { content })
+                {
+                    warn!(error = %error, "Failed to store terminal finished event");
+                }
+            }
+        }
+    });
+}
+
+/// Signals and inspects processes by pid.
+struct SystemProcesses;
+
+impl ProcessControl for SystemProcesses {
+    #[cfg(unix)]
+    fn is_running(&self, record: &ProcessRecord) -> bool {
+        // Signal 0 only checks the process exists and is ours to signal
+        if unsafe { libc::kill(record.pid as i32, 0) } != 0 {
+            return false;
+        }
+        // The pid may since have been reused by an unrelated process
+        let Ok(output) = std::process::Command::new("ps")
+            .args(["-o", "command=", "-p", &record.pid.to_string()])
+            .output()
+        else {
+            return false;
+        };
+        String::from_utf8_lossy(&output.stdout).contains(program_name(&record.command))
+    }
+
+    #[cfg(not(unix))]
+    fn is_running(&self, _record: &ProcessRecord) -> bool {
+        false
+    }
+
+    fn kill(&self, record: &ProcessRecord) {
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(record.pid as i32, libc::SIGKILL);
+        }
+        #[cfg(not(unix))]
+        let _ = record;
+    }
+}
+
+/// The file name of a command's program, e.g. `npm` for `/usr/bin/npm test`.
+fn program_name(command: &str) -> &str {
+    let program = command.split_whitespace().next().unwrap_or(command);
+    Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn program_name_strips_path_and_arguments() {
+        assert_eq!(program_name("claude"), "claude");
+        assert_eq!(program_name("/usr/local/bin/npm run test"), "npm");
+        assert_eq!(program_name("  cargo build"), "cargo");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn system_processes_checks_the_recorded_program() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        let record = |command: &str| ProcessRecord {
+            id: "record".to_string(),
+            session_id: SessionId::from_string("session"),
+            kind: ProcessKind::Terminal,
+            pid,
+            command: command.to_string(),
+            started_at: chrono::Utc::now(),
+        };
+
+        assert!(SystemProcesses.is_running(&record("sleep 30")));
+        // Same pid, different program: treated as a reused pid
+        assert!(!SystemProcesses.is_running(&record("claude")));
+
+        SystemProcesses.kill(&record("sleep 30"));
+        let _ = child.wait();
+        assert!(!SystemProcesses.is_running(&record("sleep 30")));
+    }
+}
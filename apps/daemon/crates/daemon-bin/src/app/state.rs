@@ -1,6 +1,12 @@
 //! Daemon state definition.
 
+use crate::app::agent_provider::ProviderRegistry;
+use crate::app::claude_keep_alive::KeepAliveClaude;
 use crate::armin_adapter::DaemonArmin;
+use crate::ipc::handlers::queue::QueueDispatches;
+use crate::machines::terminal::TerminalHandle;
+use crate::utils::agent_scheduler::AgentScheduler;
+use crate::utils::permission_broker::PermissionBroker;
 use crate::utils::SessionSecretCache;
 use daemon_config_and_utils::{Config, Paths};
 use daemon_database::AsyncDatabase;
@@ -23,8 +29,18 @@ pub struct DaemonState {
     pub secrets: Arc<Mutex<SecretsManager>>,
     /// Currently running Claude processes by session_id.
     pub claude_processes: Arc<Mutex<HashMap<String, broadcast::Sender<()>>>>,
-    /// Currently running terminal processes by session_id.
-    pub terminal_processes: Arc<Mutex<HashMap<String, broadcast::Sender<()>>>>,
+    /// Keep-alive Claude processes by session_id, running a turn or idle.
+    pub claude_keep_alive: Arc<Mutex<HashMap<String, KeepAliveClaude>>>,
+    /// Tool permission requests waiting for a client decision.
+    pub permissions: PermissionBroker,
+    /// Daemon-wide limits on concurrently running agent processes.
+    pub scheduler: AgentScheduler,
+    /// Sessions whose oldest queued message is being sent.
+    pub queue_dispatches: Arc<QueueDispatches>,
+    /// Coding agent providers by name, built-in and from config.
+    pub providers: Arc<ProviderRegistry>,
+    /// Currently running terminal processes by terminal id.
+    pub terminal_processes: Arc<Mutex<HashMap<String, TerminalHandle>>>,
     /// Cached database encryption key (derived from device private key).
     /// Updated after login when device private key is generated.
     pub db_encryption_key: Arc<Mutex<Option<[u8; 32]>>>,
@@ -3,21 +3,26 @@
 //! This module bridges Armin's side-effects to the daemon's IPC subscription system.
 
 use crate::observability::{current_trace_context, spawn_in_current_span};
-use agent_session_sqlite_persist_core::{Armin, SideEffect, SideEffectSink};
+use agent_session_sqlite_persist_core::{
+    Armin, RuntimeStatusEnvelope, SessionReader, SideEffect, SideEffectSink,
+};
 use daemon_ipc::{Event, EventType, SubscriptionManager};
-use std::sync::atomic::{AtomicI64, Ordering};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info};
 
 /// A side-effect sink that bridges Armin events to daemon subscriptions.
 ///
 /// This sink receives side-effects from Armin (SessionCreated, MessageAppended, SessionClosed)
 /// and broadcasts them to daemon IPC clients via the SubscriptionManager.
+///
+/// Event sequence numbers come from Armin's persisted message sequences, so a
+/// client can resubscribe with the last one it saw, even across daemon restarts.
 pub struct DaemonSideEffectSink {
     /// The subscription manager to broadcast events to.
     subscriptions: SubscriptionManager,
-    /// Global sequence counter for events.
-    sequence: AtomicI64,
+    /// Sequence of the latest committed message per session.
+    last_sequences: Mutex<HashMap<String, i64>>,
 }
 
 impl DaemonSideEffectSink {
@@ -25,14 +30,50 @@ impl DaemonSideEffectSink {
     pub fn new(subscriptions: SubscriptionManager) -> Self {
         Self {
             subscriptions,
-            sequence: AtomicI64::new(0),
+            last_sequences: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Gets the next sequence number for events.
-    fn next_sequence(&self) -> i64 {
-        self.sequence.fetch_add(1, Ordering::SeqCst)
+    /// Returns the sequence of the latest committed message in a session (0 if none).
+    ///
+    /// Used as the sequence of session events that do not persist a message.
+    pub fn last_sequence(&self, session_id: &str) -> i64 {
+        let last_sequences = self.last_sequences.lock().unwrap();
+        last_sequences.get(session_id).copied().unwrap_or(0)
+    }
+
+    /// Records that a session has committed messages up to `sequence`.
+    fn observe_sequence(&self, session_id: &str, sequence: i64) {
+        let mut last_sequences = self.last_sequences.lock().unwrap();
+        let last = last_sequences.entry(session_id.to_string()).or_insert(0);
+        *last = (*last).max(sequence);
     }
+
+    /// Seeds per-session sequences from recovered state.
+    fn seed_sequences(&self, sequences: impl IntoIterator<Item = (String, i64)>) {
+        for (session_id, sequence) in sequences {
+            self.observe_sequence(&session_id, sequence);
+        }
+    }
+}
+
+/// Builds the status change event pushed when a session's runtime status changes.
+pub fn status_change_event(
+    session_id: &str,
+    runtime_status: &RuntimeStatusEnvelope,
+    sequence: i64,
+) -> Event {
+    Event::new(
+        EventType::StatusChange,
+        session_id,
+        serde_json::json!({
+            "session_id": session_id,
+            "status": runtime_status.coding_session.status.as_str(),
+            "error_message": runtime_status.coding_session.error_message,
+            "runtime_status": runtime_status,
+        }),
+        sequence,
+    )
 }
 
 impl std::fmt::Debug for DaemonSideEffectSink {
@@ -58,7 +99,7 @@ impl SideEffectSink for DaemonSideEffectSink {
 
             SideEffect::SessionCreated { session_id } => {
                 debug!(session_id = %session_id, "Armin session created");
-                let seq = self.next_sequence();
+                let seq = self.last_sequence(session_id.as_str());
                 let mut event = Event::new(
                     EventType::SessionCreated,
                     session_id.as_str(),
@@ -73,7 +114,7 @@ impl SideEffectSink for DaemonSideEffectSink {
 
             SideEffect::SessionClosed { session_id } => {
                 debug!(session_id = %session_id, "Armin session closed");
-                let seq = self.next_sequence();
+                let seq = self.last_sequence(session_id.as_str());
                 let mut event = Event::new(
                     EventType::SessionDeleted,
                     session_id.as_str(),
@@ -88,7 +129,7 @@ impl SideEffectSink for DaemonSideEffectSink {
 
             SideEffect::SessionDeleted { session_id } => {
                 debug!(session_id = %session_id, "Armin session deleted");
-                let seq = self.next_sequence();
+                let seq = self.last_sequence(session_id.as_str());
                 let mut event = Event::new(
                     EventType::SessionDeleted,
                     session_id.as_str(),
@@ -109,7 +150,7 @@ impl SideEffectSink for DaemonSideEffectSink {
             SideEffect::MessageAppended {
                 session_id,
                 message_id,
-                sequence_number: _,
+                sequence_number,
                 content: _,
             } => {
                 debug!(
@@ -117,16 +158,17 @@ impl SideEffectSink for DaemonSideEffectSink {
                     message_id = %message_id,
                     "Armin message appended"
                 );
+                self.observe_sequence(session_id.as_str(), sequence_number);
                 // Broadcast to session subscribers so clients get notified
-                let seq = self.next_sequence();
                 let mut event = Event::new(
                     EventType::Message,
                     session_id.as_str(),
                     serde_json::json!({
                         "session_id": session_id.as_str(),
                         "message_id": message_id.as_str(),
+                        "sequence_number": sequence_number,
                     }),
-                    seq,
+                    sequence_number,
                 );
                 if let Some(trace_context) = trace_context.clone() {
                     event = event.with_context(trace_context);
@@ -151,18 +193,8 @@ impl SideEffectSink for DaemonSideEffectSink {
                     "Armin runtime status updated"
                 );
                 // Broadcast status change so clients know when Claude starts/stops
-                let seq = self.next_sequence();
-                let mut event = Event::new(
-                    EventType::StatusChange,
-                    session_id.as_str(),
-                    serde_json::json!({
-                        "session_id": session_id.as_str(),
-                        "status": runtime_status.coding_session.status.as_str(),
-                        "error_message": runtime_status.coding_session.error_message,
-                        "runtime_status": runtime_status,
-                    }),
-                    seq,
-                );
+                let seq = self.last_sequence(session_id.as_str());
+                let mut event = status_change_event(session_id.as_str(), &runtime_status, seq);
                 if let Some(trace_context) = trace_context.clone() {
                     event = event.with_context(trace_context);
                 }
@@ -198,6 +230,16 @@ pub fn create_daemon_armin(
     let sink = DaemonSideEffectSink::new(subscriptions);
     let armin = Armin::open(db_path, sink)?;
 
+    // Continue event sequences from what recovery found on disk
+    let snapshot = armin.snapshot();
+    armin
+        .sink()
+        .seed_sequences(snapshot.session_ids().filter_map(|session_id| {
+            snapshot
+                .last_sequence(session_id)
+                .map(|sequence| (session_id.as_str().to_string(), sequence))
+        }));
+
     info!(path = %db_path.display(), "Armin session engine initialized");
 
     Ok(Arc::new(armin))
@@ -216,6 +258,77 @@ pub fn create_test_armin(
 
 #[cfg(test)]
 mod tests {
-    // Tests removed - the SessionIdMapper is no longer needed since Armin
-    // now uses UUIDs directly via agent_session_sqlite_persist_core::SessionId
+    // SessionIdMapper tests were removed - Armin now uses UUIDs directly via
+    // agent_session_sqlite_persist_core::SessionId
+
+    use super::*;
+    use agent_session_sqlite_persist_core::{NewMessage, SessionId, SessionWriter};
+    use std::time::Duration;
+
+    fn append(armin: &DaemonArmin, session_id: &SessionId, content: &str) {
+        armin
+            .append(
+                session_id,
+                NewMessage {
+                    content: content.to_string(),
+                },
+            )
+            .unwrap();
+    }
+
+    async fn next_event(rx: &mut tokio::sync::broadcast::Receiver<Event>) -> Event {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("event should be broadcast")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn message_events_carry_persisted_sequence() {
+        let subscriptions = SubscriptionManager::new();
+        let armin = create_test_armin(subscriptions.clone()).unwrap();
+        let session_id = armin.create_session().unwrap();
+        let mut rx = subscriptions.subscribe(session_id.as_str()).await;
+
+        append(&armin, &session_id, "first");
+        append(&armin, &session_id, "second");
+
+        let mut sequences = vec![next_event(&mut rx).await, next_event(&mut rx).await]
+            .into_iter()
+            .map(|event| {
+                assert_eq!(event.event_type, EventType::Message);
+                assert_eq!(event.data["sequence_number"], event.sequence);
+                event.sequence
+            })
+            .collect::<Vec<_>>();
+        sequences.sort();
+        assert_eq!(sequences, vec![1, 2]);
+        assert_eq!(armin.sink().last_sequence(session_id.as_str()), 2);
+    }
+
+    #[tokio::test]
+    async fn sequences_continue_across_restart() {
+        let db_path =
+            std::env::temp_dir().join(format!("unbound-armin-adapter-{}.db", uuid::Uuid::new_v4()));
+
+        let session_id = {
+            let armin = create_daemon_armin(&db_path, SubscriptionManager::new()).unwrap();
+            let session_id = armin.create_session().unwrap();
+            for content in ["one", "two", "three"] {
+                append(&armin, &session_id, content);
+            }
+            session_id
+        };
+
+        let subscriptions = SubscriptionManager::new();
+        let armin = create_daemon_armin(&db_path, subscriptions.clone()).unwrap();
+        assert_eq!(armin.sink().last_sequence(session_id.as_str()), 3);
+
+        let mut rx = subscriptions.subscribe(session_id.as_str()).await;
+        append(&armin, &session_id, "four");
+        assert_eq!(next_event(&mut rx).await.sequence, 4);
+
+        drop(armin);
+        let _ = std::fs::remove_file(&db_path);
+    }
 }
@@ -1,28 +1,50 @@
 //! Claude CLI handlers.
 
-use crate::app::agent_cli::{
-    build_agent_cli_config_from_adapter, AgentCliEvent, AgentCliKind, AgentCliProcess,
+use crate::app::agent_cli::{build_agent_cli_config_from_adapter, AgentCliConfig, AgentCliEvent};
+use crate::app::agent_provider::{AgentProvider, CLAUDE_PROVIDER};
+use crate::app::agent_schedule::schedule_agent;
+use crate::app::claude_keep_alive::{
+    end_turn_on_result, keep_alive_timeout, send_to_keep_alive, KeepAliveClaude,
 };
-use crate::app::DaemonState;
-use crate::machines::claude::handle_claude_events;
+use crate::app::process_recovery::{forget_process, record_process};
+use crate::app::{permission_prompt_tool, DaemonState};
+use crate::armin_adapter::DaemonArmin;
+use crate::ipc::handlers::queue::{dispatch_next_queued, enqueue_if_running, resume_held_queue};
+use crate::machines::claude::{handle_claude_events, write_runtime_question};
+use crate::machines::terminal::resolve_shell;
 use crate::observability::{current_trace_context, spawn_in_current_span};
+use crate::utils::agent_scheduler::{AgentSlot, SlotRequest};
+use crate::utils::agent_watchdog::{TurnLimits, TurnTimeout, TurnWatchdog};
+use crate::utils::ask_user_question::{
+    find_asked_questions, format_answer, parse_answers, AskedQuestion,
+};
+use crate::utils::env_profiles::resolve_env;
+use crate::utils::mcp_servers::{session_mcp_config_file, WrittenMcpConfig};
+use crate::utils::repository_config::{
+    default_worktree_root_dir_for_repo, load_repository_config, EnvProfileConfig, McpServerConfig,
+    RepositoryConfig,
+};
+use crate::utils::sandbox::{SandboxPolicy, SandboxViolation};
 use agent_session_sqlite_persist_core::{
-    CodingSessionStatus, NewMessage, Session, SessionId, SessionReader, SessionWriter,
+    CodingSessionStatus, NewMessage, ProcessKind, QuestionRuntimeState, Session, SessionId,
+    SessionReader, SessionWriter,
 };
-use claude_process_manager::{ClaudeConfig, ClaudeProcess, PermissionMode};
+use claude_process_manager::{ClaudeConfig, ClaudeProcess, McpConfigFile, PermissionMode};
 use daemon_ipc::{error_codes, Event, EventType, IpcServer, Method, Response};
 use serde_json::Value;
-use std::sync::atomic::{AtomicI64, Ordering};
-use tracing::{info, warn, Instrument};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
+use tracing::{debug, info, warn, Instrument};
 use workspace_resolver::{resolve_working_dir_from_str, ResolveError};
 
-static AGENT_STREAM_SEQUENCE: AtomicI64 = AtomicI64::new(0);
-
 /// Register Claude handlers.
 pub async fn register(server: &IpcServer, state: DaemonState) {
     register_agent_send(server, state.clone()).await;
     register_agent_status(server, state.clone()).await;
     register_agent_stop(server, state.clone()).await;
+    register_agent_answer_question(server, state.clone()).await;
     register_claude_send(server, state.clone()).await;
     register_claude_status(server, state.clone()).await;
     register_claude_stop(server, state).await;
@@ -31,6 +53,24 @@ pub async fn register(server: &IpcServer, state: DaemonState) {
 pub async fn agent_send_core(
     state: &DaemonState,
     params: &serde_json::Value,
+) -> Result<serde_json::Value, (String, String)> {
+    agent_send(state, params, None).await
+}
+
+/// Send a queued message, `queue_id`, as if it had just arrived.
+pub(crate) async fn send_queued_message(
+    state: &DaemonState,
+    params: &serde_json::Value,
+    queue_id: &str,
+) -> Result<serde_json::Value, (String, String)> {
+    agent_send(state, params, Some(queue_id)).await
+}
+
+/// `agent.send`; `queue_id` is set when sending a queued message.
+async fn agent_send(
+    state: &DaemonState,
+    params: &serde_json::Value,
+    queue_id: Option<&str>,
 ) -> Result<serde_json::Value, (String, String)> {
     let session_id = params
         .get("session_id")
@@ -47,6 +87,11 @@ pub async fn agent_send_core(
         .filter(|value| !value.is_empty())
         .map(|value| value.to_ascii_lowercase());
     let permission_mode = parse_permission_mode(params)?;
+    let tool_approval = parse_tool_approval(params)?;
+    let priority = parse_priority(params)?;
+    let max_turn_seconds = parse_limit_seconds(params, "max_turn_seconds")?;
+    let max_idle_seconds = parse_limit_seconds(params, "max_idle_seconds")?;
+    let profile = parse_profile(params)?;
 
     let (Some(session_id), Some(content)) = (session_id, content) else {
         return Err((
@@ -67,48 +112,134 @@ pub async fn agent_send_core(
 
     let session = resolved_workspace.session;
     let working_dir = resolved_workspace.working_dir;
-    let cli_kind = match requested_provider.as_deref() {
-        Some("claude") => AgentCliKind::Claude,
-        Some("codex") => AgentCliKind::Codex,
-        Some(_) => {
-            return Err((
-                "invalid_params".to_string(),
-                "provider must be either \"claude\" or \"codex\" when provided".to_string(),
-            ))
-        }
-        None => detect_cli_kind_for_session(&session),
+    let provider = match requested_provider.as_deref() {
+        Some(name) => state
+            .providers
+            .resolve(name)
+            .map_err(|message| ("invalid_params".to_string(), message))?,
+        None => state.providers.for_session(&session),
     };
 
-    if cli_kind == AgentCliKind::Claude {
-        return claude_send_core(state, params).await;
+    // Claude runs through claude-process-manager, which adds interactive
+    // permissions and structured tool results
+    if provider.name() == CLAUDE_PROVIDER {
+        return claude_send(state, params, queue_id).await;
+    }
+
+    if let Some(queued) = enqueue_if_running(
+        &state.claude_processes,
+        &state.scheduler,
+        &state.armin,
+        &session_id,
+        &content,
+        params,
+        queue_id,
+    )? {
+        resume_held_queue(state, &session_id);
+        return Ok(queued);
     }
 
-    if permission_mode.is_some() {
+    if tool_approval == ToolApproval::Interactive {
         return Err((
             "invalid_params".to_string(),
-            "permission_mode is only supported for Claude sessions".to_string(),
+            "interactive tool_approval is only supported for Claude sessions".to_string(),
         ));
     }
 
+    let repository_config = session_repository_config(&state.armin, &session).unwrap_or_default();
+    let env_profile = requested_env_profile(profile, &repository_config)?;
+
     append_session_message(state, &session_id, &content, "user_input");
 
-    let mut config = build_agent_cli_config_from_adapter(
-        None,
+    let mut config = provider_turn_config(
+        &state.armin,
+        provider.as_ref(),
+        &session,
         &content,
         working_dir,
-        codex_resume_session_id(&session),
+        permission_mode,
     );
-    if config.kind != AgentCliKind::Codex {
-        config.kind = AgentCliKind::Codex;
-        if config.executable.trim().is_empty() || config.executable == "claude" {
-            config.executable = "codex".to_string();
-        }
+    config.shell = resolve_shell(&state.config, Some(&repository_config));
+    let limits = turn_limits(Some(&repository_config), max_turn_seconds, max_idle_seconds);
+    config.mcp_servers = repository_config.mcp_servers;
+    config.sandbox =
+        SandboxPolicy::from_config(&repository_config.sandbox, Path::new(&config.working_dir));
+    if let Some(Err(error)) = config.sandbox.as_ref().map(SandboxPolicy::check) {
+        let message = error.transcript_json();
+        let sequence = append_session_message(state, &session_id, &message, "sandbox_error");
+        broadcast_agent_event(state, &session_id, &message, None, sequence);
+        return Err(("internal_error".to_string(), error.to_string()));
     }
 
-    let mut process = AgentCliProcess::spawn(config).await.map_err(|error| {
+    let slot_request = SlotRequest {
+        session_id: session_id.clone(),
+        repository_id: session.repository_id.as_str().to_string(),
+        priority,
+    };
+    let repository_id = session.repository_id.as_str().to_string();
+    let state_for_start = state.clone();
+    schedule_agent(state, slot_request, move |slot| async move {
+        let provider_name = provider.name().to_string();
+        config.environment_variables.extend(resolve_profile_env(
+            &state_for_start,
+            &repository_id,
+            env_profile.as_ref(),
+        )?);
+        spawn_agent_cli(
+            &state_for_start,
+            &session_id,
+            provider,
+            config,
+            limits,
+            slot,
+        )
+        .await?;
+        Ok(serde_json::json!({
+            "status": "started",
+            "session_id": session_id,
+            "provider": provider_name,
+        }))
+    })
+    .await
+}
+
+/// The CLI config for a provider turn. The permission mode is the
+/// per-turn one, else the session's, else the repository default.
+fn provider_turn_config(
+    armin: &DaemonArmin,
+    provider: &dyn AgentProvider,
+    session: &Session,
+    content: &str,
+    working_dir: String,
+    permission_mode: Option<PermissionMode>,
+) -> AgentCliConfig {
+    let mut config = build_agent_cli_config_from_adapter(
+        provider,
+        None,
+        content,
+        working_dir,
+        provider.resume_session_id(session),
+    );
+    config.permission_mode = permission_mode.or_else(|| session_permission_mode(armin, session));
+    config
+}
+
+/// Spawns a provider's CLI for a session and streams its events in the
+/// background.
+async fn spawn_agent_cli(
+    state: &DaemonState,
+    session_id: &str,
+    provider: Arc<dyn AgentProvider>,
+    config: AgentCliConfig,
+    limits: TurnLimits,
+    slot: AgentSlot,
+) -> Result<(), (String, String)> {
+    let executable = config.executable.clone();
+    let sandboxed = config.sandbox.is_some();
+    let mut process = provider.spawn(config).map_err(|error| {
         (
             "internal_error".to_string(),
-            format!("Failed to spawn Codex: {error}"),
+            format!("Failed to spawn {}: {error}", provider.name()),
         )
     })?;
 
@@ -122,32 +253,49 @@ pub async fn agent_send_core(
 
     {
         let mut processes = state.claude_processes.lock().unwrap();
-        processes.insert(session_id.clone(), stop_tx);
+        processes.insert(session_id.to_string(), stop_tx.clone());
     }
+    let process_record = record_process(
+        state,
+        session_id,
+        ProcessKind::Agent,
+        process.pid(),
+        &executable,
+    );
 
     let state_for_task = state.clone();
-    let session_id_for_task = session_id.clone();
+    let session_id_for_task = session_id.to_string();
     spawn_in_current_span(async move {
         handle_agent_cli_session_events(
             stream,
-            AgentCliKind::Codex,
-            session_id_for_task,
-            state_for_task,
+            provider,
+            session_id_for_task.clone(),
+            state_for_task.clone(),
+            stop_tx,
+            limits,
+            sandboxed,
         )
         .await;
+        forget_process(&state_for_task, process_record);
+        drop(slot);
+        dispatch_next_queued(&state_for_task, &session_id_for_task);
     });
 
-    Ok(serde_json::json!({
-        "status": "started",
-        "session_id": session_id,
-        "provider": "codex",
-    }))
+    Ok(())
 }
 
 /// Core claude.send logic shared by IPC and remote command paths.
 pub async fn claude_send_core(
     state: &DaemonState,
     params: &serde_json::Value,
+) -> Result<serde_json::Value, (String, String)> {
+    claude_send(state, params, None).await
+}
+
+async fn claude_send(
+    state: &DaemonState,
+    params: &serde_json::Value,
+    queue_id: Option<&str>,
 ) -> Result<serde_json::Value, (String, String)> {
     info!("Received claude.send request");
 
@@ -160,6 +308,11 @@ pub async fn claude_send_core(
         .and_then(|v| v.as_str())
         .map(String::from);
     let permission_mode = parse_permission_mode(params)?;
+    let tool_approval = parse_tool_approval(params)?;
+    let priority = parse_priority(params)?;
+    let max_turn_seconds = parse_limit_seconds(params, "max_turn_seconds")?;
+    let max_idle_seconds = parse_limit_seconds(params, "max_idle_seconds")?;
+    let profile = parse_profile(params)?;
 
     let (Some(session_id), Some(content)) = (session_id, content) else {
         return Err((
@@ -180,8 +333,41 @@ pub async fn claude_send_core(
     ))
     .await?;
     let working_dir = resolved_workspace.working_dir;
+    let permission_mode = permission_mode
+        .or_else(|| session_permission_mode(&state.armin, &resolved_workspace.session));
+    let mcp_config_file = claude_mcp_config_file(state, &resolved_workspace.session);
+    let repository_config = session_repository_config(&state.armin, &resolved_workspace.session);
+    let shell = resolve_shell(&state.config, repository_config.as_ref());
+    let limits = turn_limits(
+        repository_config.as_ref(),
+        max_turn_seconds,
+        max_idle_seconds,
+    );
+    let env_profile = match profile {
+        Some(profile) => {
+            requested_env_profile(Some(profile), &repository_config.unwrap_or_default())?
+        }
+        None => None,
+    };
+    let repository_id = resolved_workspace.session.repository_id;
     let claude_session_id = resolved_workspace.session.claude_session_id;
 
+    // Replay through agent.send, which routes queued requests back here
+    let mut request = params.clone();
+    request["provider"] = Value::from("claude");
+    if let Some(queued) = enqueue_if_running(
+        &state.claude_processes,
+        &state.scheduler,
+        &state.armin,
+        &session_id,
+        &content,
+        &request,
+        queue_id,
+    )? {
+        resume_held_queue(state, &session_id);
+        return Ok(queued);
+    }
+
     info!(working_dir = %working_dir, "claude.send working directory");
 
     // Store the user message via Armin
@@ -217,10 +403,27 @@ pub async fn claude_send_core(
     }
 
     // Build Claude configuration using claude-process-manager
-    let mut config = ClaudeConfig::new(&content, &working_dir);
+    let mut config = ClaudeConfig::new(&content, &working_dir).with_shell(shell);
     if let Some(permission_mode) = permission_mode {
         config = config.with_permission_mode(permission_mode);
     }
+    if let Some(mcp_config_file) = mcp_config_file {
+        config = config.with_mcp_config_file(mcp_config_file);
+    }
+    if tool_approval == ToolApproval::Interactive {
+        let executable = std::env::current_exe().map_err(|e| {
+            (
+                "internal_error".to_string(),
+                format!("Failed to locate daemon executable: {}", e),
+            )
+        })?;
+        let socket_path = state.paths.socket_file();
+        config = config.with_permission_prompt(permission_prompt_tool(
+            &executable,
+            &socket_path.to_string_lossy(),
+            &session_id,
+        ));
+    }
     if let Some(ref prev_session_id) = claude_session_id {
         info!(prev_session_id = %prev_session_id, "Resuming previous Claude session");
         config = config.with_resume_session(prev_session_id);
@@ -228,13 +431,247 @@ pub async fn claude_send_core(
         info!("Starting new Claude session");
     }
 
+    let slot_request = SlotRequest {
+        session_id: session_id.clone(),
+        repository_id: repository_id.as_str().to_string(),
+        priority,
+    };
+    let state_for_start = state.clone();
+    schedule_agent(state, slot_request, move |slot| async move {
+        let env = resolve_profile_env(
+            &state_for_start,
+            repository_id.as_str(),
+            env_profile.as_ref(),
+        )?;
+        spawn_claude(
+            &state_for_start,
+            &session_id,
+            &working_dir,
+            config.with_env(env),
+            limits,
+            "claude.send",
+            slot,
+        )
+        .await?;
+
+        info!(
+            session_id = %session_id,
+            feature = "claude.send",
+            result = "started",
+            "claude.send completed - process running in background"
+        );
+
+        Ok(serde_json::json!({
+            "status": "started",
+            "session_id": session_id,
+        }))
+    })
+    .await
+}
+
+/// Core agent.answer_question logic: answer an `AskUserQuestion` tool use.
+///
+/// The chosen options are checked against the question in the transcript,
+/// then the Claude session is resumed with them as the tool result.
+pub async fn agent_answer_question_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, (String, String)> {
+    let session_id = params.get("session_id").and_then(|v| v.as_str());
+    let tool_use_id = params.get("tool_use_id").and_then(|v| v.as_str());
+    let (Some(session_id), Some(tool_use_id)) = (session_id, tool_use_id) else {
+        return Err((
+            "invalid_params".to_string(),
+            "session_id and tool_use_id are required".to_string(),
+        ));
+    };
+    let answers = parse_answers(params.get("answers"))
+        .map_err(|message| ("invalid_params".to_string(), message))?;
+    let priority = parse_priority(params)?;
+
+    let resolved_workspace = async {
+        resolve_working_dir_from_str(&*state.armin, session_id).map_err(map_resolve_error)
+    }
+    .instrument(tracing::info_span!(
+        "workspace.resolve",
+        session_id = %session_id,
+        feature = "agent.answer_question"
+    ))
+    .await?;
+    let session = resolved_workspace.session;
+    let working_dir = resolved_workspace.working_dir;
+
+    if state.providers.for_session(&session).name() != CLAUDE_PROVIDER {
+        return Err((
+            "invalid_params".to_string(),
+            "agent.answer_question is only supported for Claude sessions".to_string(),
+        ));
+    }
+    let Some(claude_session_id) = session.claude_session_id.clone() else {
+        return Err((
+            "invalid_params".to_string(),
+            "Session has no Claude conversation to resume".to_string(),
+        ));
+    };
+    if agent_busy(state, session_id) {
+        return Err((
+            "conflict".to_string(),
+            "The agent is still running; stop it before answering".to_string(),
+        ));
+    }
+
+    let armin_session_id = SessionId::from_string(session_id);
+    let question_state = state
+        .armin
+        .get_session_state(&armin_session_id)
+        .ok()
+        .flatten()
+        .and_then(|s| s.runtime_status.coding_session.question);
+    if matches!(&question_state, Some(q) if q.tool_use_id == tool_use_id && q.resolved) {
+        return Err((
+            "conflict".to_string(),
+            "This question has already been answered".to_string(),
+        ));
+    }
+
+    let questions = find_question(&state.armin, &armin_session_id, tool_use_id)?;
+    let answer = format_answer(&questions, &answers)
+        .map_err(|message| ("invalid_params".to_string(), message))?;
+
+    let repository_config = session_repository_config(&state.armin, &session);
+    let shell = resolve_shell(&state.config, repository_config.as_ref());
+    let limits = turn_limits(repository_config.as_ref(), None, None);
+    let mut config = ClaudeConfig::new("", &working_dir)
+        .with_resume_session(&claude_session_id)
+        .with_tool_result(tool_use_id, &answer)
+        .with_shell(shell);
+    if let Some(permission_mode) = session_permission_mode(&state.armin, &session) {
+        config = config.with_permission_mode(permission_mode);
+    }
+    if let Some(mcp_config_file) = claude_mcp_config_file(state, &session) {
+        config = config.with_mcp_config_file(mcp_config_file);
+    }
+    if let Some(message) = config.stdin_message() {
+        append_session_message(state, session_id, &message, "question_answer");
+    }
+
+    let slot_request = SlotRequest {
+        session_id: session_id.to_string(),
+        repository_id: session.repository_id.as_str().to_string(),
+        priority,
+    };
+    let state_for_start = state.clone();
+    let session_id = session_id.to_string();
+    let tool_use_id = tool_use_id.to_string();
+    schedule_agent(state, slot_request, move |slot| async move {
+        spawn_claude(
+            &state_for_start,
+            &session_id,
+            &working_dir,
+            config,
+            limits,
+            "agent.answer_question",
+            slot,
+        )
+        .await?;
+
+        write_runtime_question(
+            &state_for_start,
+            &armin_session_id,
+            Some(QuestionRuntimeState {
+                tool_use_id: tool_use_id.clone(),
+                resolved: true,
+            }),
+        );
+
+        Ok(serde_json::json!({
+            "status": "started",
+            "session_id": session_id,
+            "tool_use_id": tool_use_id,
+            "answer": answer,
+        }))
+    })
+    .await
+}
+
+/// Questions asked by the `AskUserQuestion` tool use `tool_use_id`, read
+/// from the session transcript.
+fn find_question(
+    armin: &DaemonArmin,
+    session_id: &SessionId,
+    tool_use_id: &str,
+) -> Result<Vec<AskedQuestion>, (String, String)> {
+    const PAGE_SIZE: usize = 500;
+    let mut after_sequence = 0;
+    loop {
+        let page = armin
+            .list_messages_page(session_id, after_sequence, PAGE_SIZE)
+            .map_err(|e| {
+                (
+                    "internal_error".to_string(),
+                    format!("Failed to read transcript: {}", e),
+                )
+            })?;
+        if let Some(questions) = page
+            .iter()
+            .find_map(|message| find_asked_questions(&message.content, tool_use_id))
+        {
+            return Ok(questions);
+        }
+        match page.last() {
+            Some(last) if page.len() == PAGE_SIZE => after_sequence = last.sequence_number,
+            _ => {
+                return Err((
+                    "not_found".to_string(),
+                    "No AskUserQuestion tool use with this tool_use_id".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+/// Spawns Claude for a session and streams its events in the background.
+///
+/// With keep-alive enabled, the turn goes to the session's running process
+/// when it has one, and a newly spawned process stays up for later turns.
+/// The turn is stopped once it exceeds `limits`.
+async fn spawn_claude(
+    state: &DaemonState,
+    session_id: &str,
+    working_dir: &str,
+    config: ClaudeConfig,
+    limits: TurnLimits,
+    feature: &'static str,
+    slot: AgentSlot,
+) -> Result<(), (String, String)> {
+    let config = match keep_alive_timeout(state) {
+        Some(idle_timeout) => config.with_keep_alive(idle_timeout),
+        None => config,
+    };
+    let slot = match send_to_keep_alive(state, session_id, &config, limits, slot).await {
+        Ok(()) => return Ok(()),
+        Err(slot) => slot,
+    };
+
+    // Kept until the process exits
+    let mcp_config = match &config.mcp_config_file {
+        Some(file) => Some(WrittenMcpConfig::write(file).map_err(|e| {
+            (
+                "internal_error".to_string(),
+                format!("Failed to write MCP config: {}", e),
+            )
+        })?),
+        None => None,
+    };
+
     // Spawn the Claude process using claude-process-manager
-    let mut process = match async { ClaudeProcess::spawn(config).await }
+    let spawn_config = config.clone();
+    let mut process = match async { ClaudeProcess::spawn(spawn_config).await }
         .instrument(tracing::info_span!(
             "claude.process.spawn",
             session_id = %session_id,
             working_dir = %working_dir,
-            feature = "claude.send"
+            feature
         ))
         .await
     {
@@ -263,27 +700,58 @@ pub async fn claude_send_core(
 
     {
         let mut processes = state.claude_processes.lock().unwrap();
-        processes.insert(session_id.clone(), stop_tx);
+        processes.insert(session_id.to_string(), stop_tx.clone());
     }
 
+    // A keep-alive process gives its slot up after each turn, not on exit
+    let (turns_tx, turns) = watch::channel(0);
+    let (limits_tx, limits) = watch::channel(limits);
+    let slot = match process.input() {
+        Some(input) => {
+            state.claude_keep_alive.lock().unwrap().insert(
+                session_id.to_string(),
+                KeepAliveClaude {
+                    pid: process.pid(),
+                    config,
+                    input,
+                    stop_tx: stop_tx.clone(),
+                    turns: turns.clone(),
+                    limits: limits_tx,
+                },
+            );
+            end_turn_on_result(state, session_id, turns, 0, slot);
+            None
+        }
+        None => Some(slot),
+    };
+    let process_record = record_process(
+        state,
+        session_id,
+        ProcessKind::Agent,
+        process.pid(),
+        "claude",
+    );
+
     let state_for_task = state.clone();
-    let session_id_for_task = session_id.clone();
+    let session_id_for_task = session_id.to_string();
 
     spawn_in_current_span(async move {
-        handle_claude_events(stream, session_id_for_task, state_for_task).await;
+        handle_claude_events(
+            stream,
+            session_id_for_task.clone(),
+            state_for_task.clone(),
+            turns_tx,
+            stop_tx,
+            limits,
+        )
+        .await;
+        forget_process(&state_for_task, process_record);
+        drop(mcp_config);
+        drop(slot);
+        dispatch_next_queued(&state_for_task, &session_id_for_task);
     });
 
-    info!(
-        session_id = %session_id,
-        feature = "claude.send",
-        result = "started",
-        "claude.send completed - process running in background"
-    );
-
-    Ok(serde_json::json!({
-        "status": "started",
-        "session_id": session_id,
-    }))
+    Ok(())
 }
 
 /// Core claude.stop logic shared by IPC and remote command paths.
@@ -314,6 +782,20 @@ pub async fn agent_stop_core(
         let mut processes = state.claude_processes.lock().unwrap();
         processes.remove(&session_id)
     };
+    // Unblock any tool use still waiting for approval
+    let cancelled = state.permissions.cancel_session(&session_id);
+    if cancelled > 0 {
+        info!(session_id = %session_id, cancelled, "Cancelled pending permission requests");
+    }
+
+    if state.scheduler.cancel_session(&session_id) {
+        info!(session_id = %session_id, "Cancelled agent waiting for a scheduler slot");
+        write_runtime_status(state, &session_id, CodingSessionStatus::NotAvailable, None);
+        return Ok(serde_json::json!({
+            "session_id": session_id,
+            "stopped": true,
+        }));
+    }
 
     if let Some(tx) = stop_tx {
         let _ = tx.send(());
@@ -360,10 +842,26 @@ pub async fn agent_status_core(
         .map(|s| s.runtime_status.coding_session.status.as_str().to_string())
         .unwrap_or_else(|| "idle".to_string());
 
+    let pending_permissions: Vec<Value> = state
+        .permissions
+        .pending_for_session(&session_id)
+        .into_iter()
+        .map(|(request_id, request)| {
+            serde_json::json!({
+                "request_id": request_id,
+                "tool_name": request.tool_name,
+                "input": request.input,
+                "tool_use_id": request.tool_use_id,
+            })
+        })
+        .collect();
+
     Ok(serde_json::json!({
         "session_id": session_id,
         "is_running": is_running,
         "agent_status": agent_status,
+        "scheduler_position": state.scheduler.snapshot().position(&session_id),
+        "pending_permissions": pending_permissions,
     }))
 }
 
@@ -421,40 +919,230 @@ fn map_resolve_error(err: ResolveError) -> (String, String) {
 fn parse_permission_mode(
     params: &serde_json::Value,
 ) -> Result<Option<PermissionMode>, (String, String)> {
-    let permission_mode = params.get("permission_mode").and_then(|v| v.as_str());
-    match permission_mode {
+    let Some(value) = params.get("permission_mode") else {
+        return Ok(None);
+    };
+    value
+        .as_str()
+        .ok_or_else(|| permission_mode_error("permission_mode"))
+        .and_then(|mode| permission_mode_from_str("permission_mode", mode))
+        .map(Some)
+        .map_err(|message| ("invalid_params".to_string(), message))
+}
+
+/// Permission mode for a session's next turn when the request names none: the
+/// mode set on the session, else the repository's `permissions.default_mode`.
+fn session_permission_mode(armin: &DaemonArmin, session: &Session) -> Option<PermissionMode> {
+    if let Some(mode) = session.permission_mode.as_deref() {
+        match mode.parse() {
+            Ok(mode) => return Some(mode),
+            Err(e) => {
+                warn!(session_id = %session.id, error = %e, "Ignoring session permission mode")
+            }
+        }
+    }
+
+    session_repository_config(armin, session)?
+        .permissions
+        .default_mode
+}
+
+/// MCP servers the session's repository declares.
+fn session_mcp_servers(
+    armin: &DaemonArmin,
+    session: &Session,
+) -> BTreeMap<String, McpServerConfig> {
+    session_repository_config(armin, session)
+        .map(|config| config.mcp_servers)
+        .unwrap_or_default()
+}
+
+/// A config file for the repository's enabled MCP servers, if it has any;
+/// written by [`spawn_claude`].
+fn claude_mcp_config_file(state: &DaemonState, session: &Session) -> Option<McpConfigFile> {
+    session_mcp_config_file(
+        &state.paths.mcp_configs_dir(),
+        session.id.as_str(),
+        &session_mcp_servers(&state.armin, session),
+    )
+}
+
+/// The `.unbound/config.json` of the session's repository.
+pub(crate) fn session_repository_config(
+    armin: &DaemonArmin,
+    session: &Session,
+) -> Option<RepositoryConfig> {
+    let repository = armin.get_repository(&session.repository_id).ok()??;
+    let default_worktree_root_dir = default_worktree_root_dir_for_repo(repository.id.as_str());
+    match load_repository_config(Path::new(&repository.path), &default_worktree_root_dir) {
+        Ok(config) => Some(config),
+        Err(error) => {
+            warn!(session_id = %session.id, error = %error, "Failed to load repository config");
+            None
+        }
+    }
+}
+
+/// Parse an optional permission mode parameter; `null` (clear the mode)
+/// becomes `Some(None)`.
+pub(crate) fn parse_optional_permission_mode(
+    params: &serde_json::Value,
+    key: &str,
+) -> Result<Option<Option<PermissionMode>>, String> {
+    match params.get(key) {
         None => Ok(None),
-        Some("plan") => Ok(Some(PermissionMode::Plan)),
+        Some(Value::Null) => Ok(Some(None)),
+        Some(value) => value
+            .as_str()
+            .ok_or_else(|| permission_mode_error(key))
+            .and_then(|mode| permission_mode_from_str(key, mode))
+            .map(|mode| Some(Some(mode))),
+    }
+}
+
+/// Parse a permission mode named by request parameter `key`.
+pub(crate) fn permission_mode_from_str(key: &str, value: &str) -> Result<PermissionMode, String> {
+    value.parse().map_err(|_| permission_mode_error(key))
+}
+
+fn permission_mode_error(key: &str) -> String {
+    let modes: Vec<&str> = PermissionMode::ALL
+        .iter()
+        .map(|mode| mode.as_str())
+        .collect();
+    format!("{key} must be one of: {}", modes.join(", "))
+}
+
+/// How tool uses outside the allow list are approved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolApproval {
+    /// Only pre-approved tools run; everything else is refused.
+    AllowedTools,
+    /// Ask subscribed clients through `agent.permission_request`.
+    Interactive,
+}
+
+fn parse_tool_approval(params: &serde_json::Value) -> Result<ToolApproval, (String, String)> {
+    match params.get("tool_approval").and_then(|v| v.as_str()) {
+        None | Some("allowed_tools") => Ok(ToolApproval::AllowedTools),
+        Some("interactive") => Ok(ToolApproval::Interactive),
         Some(_) => Err((
             "invalid_params".to_string(),
-            "permission_mode must be \"plan\" when provided".to_string(),
+            "tool_approval must be \"allowed_tools\" or \"interactive\" when provided".to_string(),
         )),
     }
 }
 
-fn detect_cli_kind_for_session(session: &Session) -> AgentCliKind {
-    match session.effective_provider() {
-        Some("codex") => AgentCliKind::Codex,
-        Some("claude") | Some(_) | None => AgentCliKind::Claude,
+/// Scheduler priority for the turn; higher priorities start first.
+fn parse_priority(params: &serde_json::Value) -> Result<i64, (String, String)> {
+    match params.get("priority") {
+        None | Some(Value::Null) => Ok(0),
+        Some(value) => value.as_i64().ok_or_else(|| {
+            (
+                "invalid_params".to_string(),
+                "priority must be an integer".to_string(),
+            )
+        }),
     }
 }
 
-fn codex_resume_session_id(session: &Session) -> Option<&str> {
-    if session.effective_provider() == Some("codex") {
-        session.provider_session_id.as_deref()
-    } else {
-        None
+/// The optional `profile` naming a repository environment profile.
+fn parse_profile(params: &serde_json::Value) -> Result<Option<String>, (String, String)> {
+    match params.get("profile") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(profile)) => Ok(Some(profile.clone())),
+        Some(_) => Err((
+            "invalid_params".to_string(),
+            "profile must be a string".to_string(),
+        )),
     }
 }
 
-fn provider_name_for_kind(kind: AgentCliKind) -> &'static str {
-    match kind {
-        AgentCliKind::Claude => "claude",
-        AgentCliKind::Codex => "codex",
+/// Look up the requested environment profile in the repository config.
+fn requested_env_profile(
+    profile: Option<String>,
+    repository_config: &RepositoryConfig,
+) -> Result<Option<(String, EnvProfileConfig)>, (String, String)> {
+    let Some(name) = profile else {
+        return Ok(None);
+    };
+    match repository_config.env_profiles.get(&name) {
+        Some(profile) => Ok(Some((name, profile.clone()))),
+        None => Err((
+            "invalid_params".to_string(),
+            format!("Unknown environment profile \"{name}\""),
+        )),
     }
 }
 
-fn append_session_message(state: &DaemonState, session_id: &str, content: &str, kind: &str) {
+/// The profile's variables, secrets included; read when the agent is
+/// spawned so secrets are not held while a turn waits for a slot.
+fn resolve_profile_env(
+    state: &DaemonState,
+    repository_id: &str,
+    profile: Option<&(String, EnvProfileConfig)>,
+) -> Result<Vec<(String, String)>, (String, String)> {
+    let Some((name, profile)) = profile else {
+        return Ok(Vec::new());
+    };
+    resolve_env(&state.secrets.lock().unwrap(), repository_id, name, profile)
+        .map_err(|message| ("internal_error".to_string(), message))
+}
+
+/// Limits for a turn: the per-request ones, or else the repository's
+/// `agent_timeouts`.
+fn turn_limits(
+    repository_config: Option<&RepositoryConfig>,
+    max_turn_seconds: Option<u64>,
+    max_idle_seconds: Option<u64>,
+) -> TurnLimits {
+    let timeouts = repository_config
+        .map(|config| config.agent_timeouts.clone())
+        .unwrap_or_default();
+    TurnLimits::from_secs(
+        max_turn_seconds.or(timeouts.max_turn_seconds),
+        max_idle_seconds.or(timeouts.max_idle_seconds),
+    )
+}
+
+/// A per-request turn limit in seconds, overriding the repository's
+/// `agent_timeouts`.
+fn parse_limit_seconds(
+    params: &serde_json::Value,
+    key: &str,
+) -> Result<Option<u64>, (String, String)> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .filter(|seconds| *seconds > 0)
+            .map(Some)
+            .ok_or_else(|| {
+                (
+                    "invalid_params".to_string(),
+                    format!("{key} must be a positive integer"),
+                )
+            }),
+    }
+}
+
+/// Whether the session has an agent running or waiting for a scheduler slot.
+fn agent_busy(state: &DaemonState, session_id: &str) -> bool {
+    state
+        .claude_processes
+        .lock()
+        .unwrap()
+        .contains_key(session_id)
+        || state.scheduler.has_session(session_id)
+}
+
+/// Stores content as a session message, returning its sequence number.
+fn append_session_message(
+    state: &DaemonState,
+    session_id: &str,
+    content: &str,
+    kind: &str,
+) -> Option<i64> {
     let armin_session_id = SessionId::from_string(session_id);
     let _guard = tracing::info_span!(
         "armin.append",
@@ -462,17 +1150,21 @@ fn append_session_message(state: &DaemonState, session_id: &str, content: &str,
         message_kind = kind
     )
     .entered();
-    if let Err(error) = state.armin.append(
+    match state.armin.append(
         &armin_session_id,
         NewMessage {
             content: content.to_string(),
         },
     ) {
-        warn!(error = %error, message_kind = kind, "Failed to append session message");
+        Ok(message) => Some(message.sequence_number),
+        Err(error) => {
+            warn!(error = %error, message_kind = kind, "Failed to append session message");
+            None
+        }
     }
 }
 
-fn write_runtime_status(
+pub(crate) fn write_runtime_status(
     state: &DaemonState,
     session_id: &str,
     status: CodingSessionStatus,
@@ -496,14 +1188,21 @@ fn write_runtime_status(
     }
 }
 
-fn broadcast_agent_event(state: &DaemonState, session_id: &str, raw_json: &str) {
-    let seq = AGENT_STREAM_SEQUENCE.fetch_add(1, Ordering::SeqCst);
-    let mut event = Event::new(
-        EventType::AgentEvent,
-        session_id,
-        serde_json::json!({ "raw_json": raw_json }),
-        seq,
-    );
+/// Broadcasts a provider event; `text` is the provider-independent
+/// assistant text, for clients that do not understand the raw format.
+fn broadcast_agent_event(
+    state: &DaemonState,
+    session_id: &str,
+    raw_json: &str,
+    text: Option<&str>,
+    sequence: Option<i64>,
+) {
+    let seq = sequence.unwrap_or_else(|| state.armin.sink().last_sequence(session_id));
+    let mut data = serde_json::json!({ "raw_json": raw_json });
+    if let Some(text) = text {
+        data["text"] = Value::from(text);
+    }
+    let mut event = Event::new(EventType::AgentEvent, session_id, data, seq);
     if let Some(trace_context) = current_trace_context() {
         event = event.with_context(trace_context);
     }
@@ -518,41 +1217,77 @@ fn broadcast_agent_event(state: &DaemonState, session_id: &str, raw_json: &str)
 
 async fn handle_agent_cli_session_events(
     mut stream: crate::app::agent_cli::AgentCliEventStream,
-    kind: AgentCliKind,
+    provider: Arc<dyn AgentProvider>,
     session_id: String,
     state: DaemonState,
+    stop_tx: broadcast::Sender<()>,
+    limits: TurnLimits,
+    sandboxed: bool,
 ) {
     write_runtime_status(&state, &session_id, CodingSessionStatus::Running, None);
+    // Keeps the reported error when the turn then finishes unsuccessfully
+    let mut reported_error = false;
+    let mut watchdog = TurnWatchdog::new(limits);
+    let mut timed_out: Option<TurnTimeout> = None;
+
+    loop {
+        let event = tokio::select! {
+            event = stream.next() => event,
+            timeout = watchdog.expired(), if timed_out.is_none() => {
+                warn!(
+                    session_id = %session_id,
+                    reason = %timeout.reason(),
+                    "Agent turn timed out; stopping agent"
+                );
+                let _ = stop_tx.send(());
+                timed_out = Some(timeout);
+                continue;
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+        watchdog.record_event();
 
-    while let Some(event) = stream.next().await {
         match &event {
             AgentCliEvent::Json { raw, json } => {
-                append_session_message(
+                let parsed = provider.parse_event(json);
+                for tool_call in &parsed.tool_calls {
+                    debug!(session_id = %session_id, tool = %tool_call.name, "Agent tool call");
+                }
+                if let Some(usage) = parsed.usage {
+                    info!(
+                        session_id = %session_id,
+                        input_tokens = usage.input_tokens,
+                        cached_input_tokens = usage.cached_input_tokens,
+                        output_tokens = usage.output_tokens,
+                        "Agent turn usage"
+                    );
+                }
+                let sequence = append_session_message(
                     &state,
                     &session_id,
                     raw,
-                    match kind {
-                        AgentCliKind::Claude => "claude_json",
-                        AgentCliKind::Codex => "codex_json",
-                    },
+                    &format!("{}_json", provider.name()),
                 );
-                broadcast_agent_event(&state, &session_id, raw);
-                if kind == AgentCliKind::Claude && is_ask_user_question(json) {
+                broadcast_agent_event(&state, &session_id, raw, parsed.text.as_deref(), sequence);
+                if parsed.awaiting_input {
                     write_runtime_status(&state, &session_id, CodingSessionStatus::Waiting, None);
                 } else {
                     write_runtime_status(&state, &session_id, CodingSessionStatus::Running, None);
                 }
-                if let Some(provider_session_id) = extract_provider_session_id(kind, json) {
+                if let Some(provider_session_id) = parsed.session_id {
                     let armin_session_id = SessionId::from_string(&session_id);
                     if let Err(error) = state.armin.update_session_provider_session(
                         &armin_session_id,
-                        provider_name_for_kind(kind),
+                        provider.name(),
                         &provider_session_id,
                     ) {
                         warn!(error = %error, "Failed to update provider session id");
                     }
                 }
-                if let Some(error_message) = extract_process_error(kind, json) {
+                if let Some(error_message) = parsed.error {
+                    reported_error = true;
                     write_runtime_status(
                         &state,
                         &session_id,
@@ -566,37 +1301,49 @@ async fn handle_agent_cli_session_events(
                     &state,
                     &session_id,
                     line,
-                    match kind {
-                        AgentCliKind::Claude => "claude_stderr",
-                        AgentCliKind::Codex => "codex_stderr",
-                    },
+                    &format!("{}_stderr", provider.name()),
                 );
+                if let Some(violation) = SandboxViolation::from_stderr(line).filter(|_| sandboxed) {
+                    let message = violation.transcript_json();
+                    let sequence =
+                        append_session_message(&state, &session_id, &message, "sandbox_violation");
+                    broadcast_agent_event(&state, &session_id, &message, None, sequence);
+                }
             }
             AgentCliEvent::Finished { success, exit_code } => {
                 if *success {
                     write_runtime_status(&state, &session_id, CodingSessionStatus::Idle, None);
-                } else {
-                    let error_message = match exit_code {
-                        Some(code) => format!(
-                            "{} process exited with status {code}",
-                            provider_name_for_kind(kind)
-                        ),
-                        None => format!(
-                            "{} process exited with non-zero status",
-                            provider_name_for_kind(kind)
-                        ),
-                    };
+                } else if !reported_error {
                     write_runtime_status(
                         &state,
                         &session_id,
                         CodingSessionStatus::Error,
-                        Some(error_message),
+                        Some(provider.exit_error(*exit_code)),
                     );
                 }
             }
-            AgentCliEvent::Stopped => {
-                write_runtime_status(&state, &session_id, CodingSessionStatus::NotAvailable, None);
-            }
+            AgentCliEvent::Stopped => match &timed_out {
+                Some(timeout) => {
+                    let message = timeout.transcript_json();
+                    let sequence =
+                        append_session_message(&state, &session_id, &message, "agent_timeout");
+                    broadcast_agent_event(&state, &session_id, &message, None, sequence);
+                    write_runtime_status(
+                        &state,
+                        &session_id,
+                        CodingSessionStatus::Error,
+                        Some(timeout.reason()),
+                    );
+                }
+                None => {
+                    write_runtime_status(
+                        &state,
+                        &session_id,
+                        CodingSessionStatus::NotAvailable,
+                        None,
+                    );
+                }
+            },
         }
 
         if event.is_terminal() {
@@ -610,66 +1357,6 @@ async fn handle_agent_cli_session_events(
     }
 }
 
-fn extract_provider_session_id(kind: AgentCliKind, json: &serde_json::Value) -> Option<String> {
-    match kind {
-        AgentCliKind::Claude => json
-            .get("session_id")
-            .and_then(|value| value.as_str())
-            .map(ToOwned::to_owned),
-        AgentCliKind::Codex => json
-            .get("thread_id")
-            .or_else(|| json.get("threadId"))
-            .or_else(|| json.get("id"))
-            .and_then(|value| value.as_str())
-            .map(ToOwned::to_owned),
-    }
-}
-
-fn extract_process_error(kind: AgentCliKind, json: &serde_json::Value) -> Option<String> {
-    match kind {
-        AgentCliKind::Claude => {
-            if json.get("type").and_then(Value::as_str) == Some("result")
-                && json.get("subtype").and_then(Value::as_str) == Some("error")
-            {
-                Some("Claude reported an error result".to_string())
-            } else {
-                None
-            }
-        }
-        AgentCliKind::Codex => {
-            let event_type = json.get("type").and_then(Value::as_str).unwrap_or_default();
-            if event_type == "turn.failed" || event_type == "error" {
-                return json
-                    .get("message")
-                    .or_else(|| json.get("error"))
-                    .and_then(Value::as_str)
-                    .map(ToOwned::to_owned)
-                    .or_else(|| Some("Codex reported an error result".to_string()));
-            }
-            None
-        }
-    }
-}
-
-fn is_ask_user_question(json: &serde_json::Value) -> bool {
-    json.get("type").and_then(Value::as_str) == Some("assistant")
-        && json
-            .get("message")
-            .and_then(Value::as_object)
-            .is_some_and(|message| {
-                message
-                    .get("content")
-                    .and_then(Value::as_array)
-                    .is_some_and(|content| {
-                        content.iter().any(|item| {
-                            item.get("type").and_then(Value::as_str) == Some("tool_use")
-                                && item.get("name").and_then(Value::as_str)
-                                    == Some("ask_user_question")
-                        })
-                    })
-            })
-}
-
 async fn register_agent_send(server: &IpcServer, state: DaemonState) {
     server
         .register_handler(Method::AgentSend, move |req| {
@@ -759,34 +1446,42 @@ async fn register_agent_stop(server: &IpcServer, state: DaemonState) {
         .await;
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
-
-    #[test]
-    fn parse_permission_mode_missing() {
-        let parsed = parse_permission_mode(&json!({}));
-        assert_eq!(parsed, Ok(None));
-    }
-
-    #[test]
-    fn parse_permission_mode_plan() {
-        let parsed = parse_permission_mode(&json!({ "permission_mode": "plan" }));
-        assert_eq!(parsed, Ok(Some(PermissionMode::Plan)));
-    }
-
-    #[test]
-    fn parse_permission_mode_invalid() {
-        let parsed = parse_permission_mode(&json!({ "permission_mode": "something" }));
-        assert_eq!(
-            parsed,
-            Err((
-                "invalid_params".to_string(),
-                "permission_mode must be \"plan\" when provided".to_string()
-            ))
-        );
-    }
+async fn register_agent_answer_question(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::AgentAnswerQuestion, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match agent_answer_question_core(&state, &params).await {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err((code, msg)) => {
+                        let error_code = match code.as_str() {
+                            "invalid_params" => error_codes::INVALID_PARAMS,
+                            "not_found" => error_codes::NOT_FOUND,
+                            "conflict" => error_codes::CONFLICT,
+                            _ => error_codes::INTERNAL_ERROR,
+                        };
+                        if code == "legacy_worktree_unsupported" {
+                            Response::error_with_data(
+                                &req.id,
+                                error_code,
+                                &msg,
+                                serde_json::json!({
+                                    "machine_code": "legacy_worktree_unsupported",
+                                }),
+                            )
+                        } else {
+                            Response::error(&req.id, error_code, &msg)
+                        }
+                    }
+                }
+            }
+        })
+        .await;
 }
 
 async fn register_claude_status(server: &IpcServer, state: DaemonState) {
@@ -840,3 +1535,224 @@ async fn register_claude_stop(server: &IpcServer, state: DaemonState) {
         })
         .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::armin_adapter::create_test_armin;
+    use daemon_ipc::SubscriptionManager;
+    use serde_json::json;
+
+    #[test]
+    fn parse_permission_mode_missing() {
+        let parsed = parse_permission_mode(&json!({}));
+        assert_eq!(parsed, Ok(None));
+    }
+
+    #[test]
+    fn parse_permission_mode_plan() {
+        let parsed = parse_permission_mode(&json!({ "permission_mode": "plan" }));
+        assert_eq!(parsed, Ok(Some(PermissionMode::Plan)));
+    }
+
+    #[test]
+    fn parse_permission_mode_accepts_every_mode() {
+        for mode in PermissionMode::ALL {
+            let parsed = parse_permission_mode(&json!({ "permission_mode": mode.as_str() }));
+            assert_eq!(parsed, Ok(Some(mode)));
+        }
+    }
+
+    #[test]
+    fn parse_permission_mode_invalid() {
+        let expected = Err((
+            "invalid_params".to_string(),
+            "permission_mode must be one of: default, acceptEdits, plan, bypassPermissions"
+                .to_string(),
+        ));
+        let parsed = parse_permission_mode(&json!({ "permission_mode": "something" }));
+        assert_eq!(parsed, expected);
+        let parsed = parse_permission_mode(&json!({ "permission_mode": 1 }));
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn parse_tool_approval_defaults_to_allowed_tools() {
+        assert_eq!(
+            parse_tool_approval(&json!({})),
+            Ok(ToolApproval::AllowedTools)
+        );
+        assert_eq!(
+            parse_tool_approval(&json!({ "tool_approval": "interactive" })),
+            Ok(ToolApproval::Interactive)
+        );
+        assert!(parse_tool_approval(&json!({ "tool_approval": "ask" })).is_err());
+    }
+
+    #[test]
+    fn parse_priority_defaults_to_zero() {
+        assert_eq!(parse_priority(&json!({})), Ok(0));
+        assert_eq!(parse_priority(&json!({ "priority": -3 })), Ok(-3));
+        assert!(parse_priority(&json!({ "priority": "high" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn find_question_reads_ask_user_question_from_transcript() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let session_id = armin.create_session().unwrap();
+        for content in [
+            "Pick a database".to_string(),
+            json!({
+                "type": "assistant",
+                "message": {
+                    "content": [{
+                        "type": "tool_use",
+                        "id": "toolu_ask",
+                        "name": "AskUserQuestion",
+                        "input": {
+                            "questions": [{
+                                "question": "Which database?",
+                                "options": [{ "label": "SQLite" }, { "label": "Postgres" }],
+                                "multiSelect": false
+                            }]
+                        }
+                    }]
+                }
+            })
+            .to_string(),
+        ] {
+            armin.append(&session_id, NewMessage { content }).unwrap();
+        }
+
+        let questions = find_question(&armin, &session_id, "toolu_ask").unwrap();
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].options, vec!["SQLite", "Postgres"]);
+
+        let err = find_question(&armin, &session_id, "toolu_missing").unwrap_err();
+        assert_eq!(err.0, "not_found");
+    }
+
+    #[test]
+    fn turn_limits_prefer_request_over_repository_timeouts() {
+        let mut config = RepositoryConfig::default();
+        config.agent_timeouts.max_turn_seconds = Some(1800);
+        config.agent_timeouts.max_idle_seconds = Some(300);
+
+        assert_eq!(
+            turn_limits(Some(&config), Some(60), None),
+            TurnLimits::from_secs(Some(60), Some(300))
+        );
+        assert_eq!(turn_limits(None, None, None), TurnLimits::default());
+    }
+
+    #[test]
+    fn session_permission_mode_prefers_session_over_repository_default() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let repo_path =
+            std::env::temp_dir().join(format!("permission-mode-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(repo_path.join(".unbound")).unwrap();
+        let repository = armin
+            .create_repository(agent_session_sqlite_persist_core::NewRepository::new(
+                repo_path.to_string_lossy(),
+                "permission-repo",
+                false,
+            ))
+            .unwrap();
+        let session = armin
+            .create_session_with_metadata(agent_session_sqlite_persist_core::NewSession::new(
+                repository.id.clone(),
+                "Modes",
+            ))
+            .unwrap();
+        assert_eq!(session_permission_mode(&armin, &session), None);
+
+        std::fs::write(
+            repo_path.join(".unbound").join("config.json"),
+            r#"{ "permissions": { "default_mode": "plan" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            session_permission_mode(&armin, &session),
+            Some(PermissionMode::Plan)
+        );
+
+        armin
+            .update_session(
+                &session.id,
+                agent_session_sqlite_persist_core::SessionUpdate {
+                    permission_mode: Some(Some("acceptEdits".to_string())),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let session = armin.get_session(&session.id).unwrap().unwrap();
+        assert_eq!(
+            session_permission_mode(&armin, &session),
+            Some(PermissionMode::AcceptEdits)
+        );
+        let _ = std::fs::remove_dir_all(repo_path);
+    }
+
+    #[test]
+    fn provider_turns_run_in_the_session_permission_mode() {
+        use crate::app::agent_provider::CodexProvider;
+
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let repo_path =
+            std::env::temp_dir().join(format!("provider-mode-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&repo_path).unwrap();
+        let repository = armin
+            .create_repository(agent_session_sqlite_persist_core::NewRepository::new(
+                repo_path.to_string_lossy(),
+                "provider-mode-repo",
+                false,
+            ))
+            .unwrap();
+        let session = armin
+            .create_session_with_metadata(agent_session_sqlite_persist_core::NewSession::new(
+                repository.id.clone(),
+                "Codex",
+            ))
+            .unwrap();
+        armin
+            .update_session(
+                &session.id,
+                agent_session_sqlite_persist_core::SessionUpdate {
+                    permission_mode: Some(Some("plan".to_string())),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let session = armin.get_session(&session.id).unwrap().unwrap();
+        let working_dir = repo_path.to_string_lossy().into_owned();
+
+        let config = provider_turn_config(
+            &armin,
+            &CodexProvider,
+            &session,
+            "hello",
+            working_dir.clone(),
+            None,
+        );
+        let args = CodexProvider.build_args(&config);
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-c", r#"sandbox_mode="read-only""#]));
+
+        // A per-turn mode wins over the session's
+        let config = provider_turn_config(
+            &armin,
+            &CodexProvider,
+            &session,
+            "hello",
+            working_dir,
+            Some(PermissionMode::BypassPermissions),
+        );
+        let args = CodexProvider.build_args(&config);
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-c", r#"approval_policy="never""#]));
+        assert!(!args.iter().any(|arg| arg.contains("read-only")));
+        let _ = std::fs::remove_dir_all(repo_path);
+    }
+}
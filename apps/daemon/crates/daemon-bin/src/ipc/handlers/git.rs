@@ -3,8 +3,9 @@
 use crate::app::DaemonState;
 use daemon_ipc::{error_codes, IpcServer, Method, Response};
 use git_ops::{
-    commit, discard_changes, get_branches, get_file_diff, get_log, get_status, list_worktrees,
-    push, stage_files, unstage_files, GitOpsError,
+    checkout_branch, commit, create_branch, delete_branch, discard_changes, get_branches,
+    get_file_diff, get_log, get_status, list_worktrees, push, rename_branch, stage_files,
+    unstage_files, GitOpsError,
 };
 use workspace_resolver::{resolve_repository_path, resolve_working_dir_from_str, ResolveError};
 
@@ -25,7 +26,11 @@ pub async fn register(server: &IpcServer, state: DaemonState) {
     register_git_unstage(server, state.clone()).await;
     register_git_discard(server, state.clone()).await;
     register_git_commit(server, state.clone()).await;
-    register_git_push(server, state).await;
+    register_git_push(server, state.clone()).await;
+    register_git_checkout(server, state.clone()).await;
+    register_git_branch_create(server, state.clone()).await;
+    register_git_branch_delete(server, state.clone()).await;
+    register_git_branch_rename(server, state).await;
 }
 
 pub async fn git_commit_core(
@@ -190,6 +195,103 @@ async fn register_git_diff_file(server: &IpcServer, state: DaemonState) {
 }
 
 /// Helper to extract repository path from request params.
+async fn git_checkout_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, GitCoreError> {
+    let repo_path = resolve_git_repo_path(state, params)?;
+    let branch = required_branch_param(params, "branch")?;
+    let force = params
+        .get("force")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let branch = checkout_branch(std::path::Path::new(&repo_path), branch, force)
+        .map_err(map_branch_error)?;
+
+    Ok(serde_json::json!({ "branch": branch }))
+}
+
+async fn git_branch_create_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, GitCoreError> {
+    let repo_path = resolve_git_repo_path(state, params)?;
+    let name = required_branch_param(params, "name")?;
+
+    let start_point = params
+        .get("start_point")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+
+    let upstream = params
+        .get("upstream")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+
+    let checkout = params
+        .get("checkout")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let repo_path = std::path::Path::new(&repo_path);
+    let mut branch =
+        create_branch(repo_path, name, start_point, upstream).map_err(map_branch_error)?;
+    if checkout {
+        branch = checkout_branch(repo_path, name, false).map_err(map_branch_error)?;
+    }
+
+    Ok(serde_json::json!({ "branch": branch }))
+}
+
+async fn git_branch_delete_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, GitCoreError> {
+    let repo_path = resolve_git_repo_path(state, params)?;
+    let name = required_branch_param(params, "name")?;
+    let force = params
+        .get("force")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let branch =
+        delete_branch(std::path::Path::new(&repo_path), name, force).map_err(map_branch_error)?;
+
+    Ok(serde_json::json!({ "deleted": true, "branch": branch }))
+}
+
+async fn git_branch_rename_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, GitCoreError> {
+    let repo_path = resolve_git_repo_path(state, params)?;
+    let name = required_branch_param(params, "name")?;
+    let new_name = required_branch_param(params, "new_name")?;
+
+    let branch = rename_branch(std::path::Path::new(&repo_path), name, new_name)
+        .map_err(map_branch_error)?;
+
+    Ok(serde_json::json!({ "branch": branch }))
+}
+
+fn required_branch_param<'a>(
+    params: &'a serde_json::Value,
+    name: &str,
+) -> Result<&'a str, GitCoreError> {
+    params
+        .get(name)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| GitCoreError {
+            code: "invalid_params".to_string(),
+            message: format!("{name} is required"),
+        })
+}
+
 async fn extract_repo_path(
     state: &DaemonState,
     params: &Option<serde_json::Value>,
@@ -515,6 +617,82 @@ async fn register_git_push(server: &IpcServer, state: DaemonState) {
         .await;
 }
 
+async fn register_git_checkout(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::GitCheckout, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match git_checkout_core(&state, &params).await {
+                    Ok(result) => Response::success(&req.id, result),
+                    Err(err) => git_core_error_response(&req.id, err),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_git_branch_create(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::GitBranchCreate, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match git_branch_create_core(&state, &params).await {
+                    Ok(result) => Response::success(&req.id, result),
+                    Err(err) => git_core_error_response(&req.id, err),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_git_branch_delete(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::GitBranchDelete, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match git_branch_delete_core(&state, &params).await {
+                    Ok(result) => Response::success(&req.id, result),
+                    Err(err) => git_core_error_response(&req.id, err),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_git_branch_rename(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::GitBranchRename, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match git_branch_rename_core(&state, &params).await {
+                    Ok(result) => Response::success(&req.id, result),
+                    Err(err) => git_core_error_response(&req.id, err),
+                }
+            }
+        })
+        .await;
+}
+
 fn resolve_git_repo_path(
     state: &DaemonState,
     params: &serde_json::Value,
@@ -584,6 +762,24 @@ fn map_git_ops_error(err: GitOpsError) -> GitCoreError {
     }
 }
 
+/// Branch operation errors, with the refusals a client can act on (force,
+/// pick another name) told apart from failures.
+fn map_branch_error(err: GitOpsError) -> GitCoreError {
+    let code = match &err {
+        GitOpsError::BranchNotFound(_) => "not_found",
+        GitOpsError::InvalidBranchName(_) => "invalid_params",
+        GitOpsError::BranchExists(_) => "branch_exists",
+        GitOpsError::BranchCheckedOut(..) => "branch_checked_out",
+        GitOpsError::BranchNotMerged(_) => "branch_not_merged",
+        GitOpsError::CheckoutConflict(_) => "checkout_conflict",
+        _ => "command_failed",
+    };
+    GitCoreError {
+        code: code.to_string(),
+        message: err.to_string(),
+    }
+}
+
 fn map_stage_error(err: String) -> GitCoreError {
     GitCoreError {
         code: "command_failed".to_string(),
@@ -596,6 +792,9 @@ fn git_core_error_response(id: &str, err: GitCoreError) -> Response {
         "invalid_params" => error_codes::INVALID_PARAMS,
         "not_found" => error_codes::NOT_FOUND,
         "legacy_worktree_unsupported" => error_codes::INVALID_PARAMS,
+        "branch_exists" | "branch_checked_out" | "branch_not_merged" | "checkout_conflict" => {
+            error_codes::CONFLICT
+        }
         _ => error_codes::INTERNAL_ERROR,
     };
     Response::error_with_data(
@@ -121,15 +121,20 @@ async fn register_message_list(server: &IpcServer, state: DaemonState) {
                     session_id = %session_id,
                     snapshot_count = tracing::field::Empty
                 );
-                let snapshot_msgs: &[Message] = {
+                let snapshot_session = {
                     let _span = snapshot_lookup_span.enter();
-                    let snapshot_msgs = snapshot
-                        .session(&armin_session_id)
-                        .map(|s| s.messages())
-                        .unwrap_or(&[]);
-                    snapshot_lookup_span.record("snapshot_count", snapshot_msgs.len());
-                    snapshot_msgs
+                    // Loads the session's messages from SQLite on first access.
+                    let snapshot_session = snapshot.session(&armin_session_id);
+                    snapshot_lookup_span.record(
+                        "snapshot_count",
+                        snapshot_session.as_ref().map_or(0, |s| s.message_count()),
+                    );
+                    snapshot_session
                 };
+                let snapshot_msgs: &[Message] = snapshot_session
+                    .as_deref()
+                    .map(|s| s.messages())
+                    .unwrap_or(&[]);
 
                 let delta_read_span = info_span!(
                     "message.list.delta.read",
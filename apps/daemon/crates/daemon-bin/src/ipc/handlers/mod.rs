@@ -8,7 +8,10 @@ pub mod gh;
 pub mod git;
 pub mod health;
 pub mod message;
+pub mod permission;
+pub mod queue;
 pub mod repository;
 pub mod session;
+pub mod subscription;
 pub mod system;
 pub mod terminal;
@@ -0,0 +1,679 @@
+//! Tool permission handlers.
+//!
+//! `agent.permission_request` is called by the permission prompt MCP server on
+//! behalf of a running agent and blocks until the tool use is decided;
+//! `agent.respond_permission` is how clients decide it.
+
+use crate::app::DaemonState;
+use crate::armin_adapter::DaemonArmin;
+use crate::ipc::handlers::claude::write_runtime_status;
+use crate::observability::current_trace_context;
+use crate::utils::permission_broker::{
+    PermissionBehavior, PermissionBroker, PermissionDecision, PermissionRequest,
+};
+use crate::utils::repository_config::{
+    add_always_allow_tool, default_worktree_root_dir_for_repo, load_repository_config,
+};
+use agent_session_sqlite_persist_core::{
+    CodingSessionStatus, NewMessage, SessionId, SessionReader, SessionWriter,
+};
+use daemon_ipc::{error_codes, Event, EventType, IpcServer, Method, Response, SubscriptionManager};
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::time::{timeout, Duration};
+use tracing::{info, warn};
+
+/// Register permission handlers.
+pub async fn register(server: &IpcServer, state: DaemonState) {
+    register_permission_request(server, state.clone()).await;
+    register_respond_permission(server, state).await;
+}
+
+/// Tools an always-allow rule cannot cover: a rule is saved for the bare tool
+/// name, and for these that would approve any future input, e.g. every shell
+/// command.
+const NO_ALWAYS_ALLOW_TOOLS: &[&str] = &["Bash"];
+
+/// How a decision was reached, as recorded in the transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DecisionSource {
+    /// A client answered through `agent.respond_permission`.
+    Client,
+    /// The repository always allows this tool.
+    RepositoryRule,
+    /// Nobody answered in time.
+    Timeout,
+    /// The agent was stopped while waiting.
+    Cancelled,
+}
+
+impl DecisionSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Client => "client",
+            Self::RepositoryRule => "repository_rule",
+            Self::Timeout => "timeout",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Core agent.permission_request logic: decide one tool use.
+///
+/// Returns the decision in the shape Claude's permission prompt tool expects.
+pub async fn permission_request_core(
+    state: &DaemonState,
+    params: &Value,
+) -> Result<Value, (String, String)> {
+    let request = parse_permission_request(params)?;
+
+    let armin_session_id = SessionId::from_string(&request.session_id);
+    match state.armin.get_session(&armin_session_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(("not_found".to_string(), "Session not found".to_string())),
+        Err(error) => {
+            return Err((
+                "internal_error".to_string(),
+                format!("Failed to get session: {error}"),
+            ))
+        }
+    }
+
+    let (request_id, decision, source) =
+        if repository_allows_tool(&state.armin, &request.session_id, &request.tool_name) {
+            let decision = PermissionDecision {
+                behavior: PermissionBehavior::Allow,
+                message: None,
+                updated_input: None,
+                always_allow: false,
+            };
+            (None, decision, DecisionSource::RepositoryRule)
+        } else {
+            write_runtime_status(
+                state,
+                &request.session_id,
+                CodingSessionStatus::Waiting,
+                None,
+            );
+            let wait = Duration::from_secs(state.config.permission_timeout_secs);
+            let (request_id, decision, source) = await_client_decision(
+                &state.armin,
+                &state.permissions,
+                &state.subscriptions,
+                &request,
+                wait,
+            )
+            .await;
+            // A stopped agent has already written its final status
+            if source != DecisionSource::Cancelled {
+                write_runtime_status(
+                    state,
+                    &request.session_id,
+                    CodingSessionStatus::Running,
+                    None,
+                );
+            }
+            (Some(request_id), decision, source)
+        };
+
+    info!(
+        session_id = %request.session_id,
+        tool_name = %request.tool_name,
+        behavior = decision.behavior.as_str(),
+        source = source.as_str(),
+        "Tool permission decided"
+    );
+    record_decision(
+        &state.armin,
+        &request,
+        request_id.as_deref(),
+        &decision,
+        source,
+    );
+
+    Ok(prompt_tool_decision(&request, &decision))
+}
+
+/// Core agent.respond_permission logic: answer a pending request.
+pub async fn respond_permission_core(
+    state: &DaemonState,
+    params: &Value,
+) -> Result<Value, (String, String)> {
+    let (request_id, decision) = parse_permission_response(params)?;
+
+    if decision.always_allow {
+        if let Some(request) = state.permissions.get(&request_id) {
+            check_always_allow(&request.tool_name)?;
+        }
+    }
+
+    let Some(request) = state.permissions.resolve(&request_id, decision.clone()) else {
+        return Err((
+            "not_found".to_string(),
+            "No pending permission request with this id".to_string(),
+        ));
+    };
+
+    let rule_saved = decision.always_allow
+        && save_always_allow_rule(&state.armin, &request.session_id, &request.tool_name);
+
+    Ok(serde_json::json!({
+        "request_id": request_id,
+        "session_id": request.session_id,
+        "resolved": true,
+        "rule_saved": rule_saved,
+    }))
+}
+
+/// Publish a request to the session's subscribers and wait for the answer.
+///
+/// Returns the request id, the decision, and how it was reached. Requests
+/// nobody answers within `wait` are denied.
+async fn await_client_decision(
+    armin: &DaemonArmin,
+    broker: &PermissionBroker,
+    subscriptions: &SubscriptionManager,
+    request: &PermissionRequest,
+    wait: Duration,
+) -> (String, PermissionDecision, DecisionSource) {
+    let (request_id, receiver) = broker.register(request.clone());
+
+    let mut event = Event::new(
+        EventType::PermissionRequest,
+        &request.session_id,
+        serde_json::json!({
+            "request_id": request_id,
+            "session_id": request.session_id,
+            "tool_name": request.tool_name,
+            "input": request.input,
+            "tool_use_id": request.tool_use_id,
+            "timeout_secs": wait.as_secs(),
+        }),
+        armin.sink().last_sequence(&request.session_id),
+    );
+    if let Some(trace_context) = current_trace_context() {
+        event = event.with_context(trace_context);
+    }
+    subscriptions
+        .broadcast_or_create(&request.session_id, event)
+        .await;
+
+    match timeout(wait, receiver).await {
+        Ok(Ok(decision)) => (request_id, decision, DecisionSource::Client),
+        Ok(Err(_)) => (
+            request_id,
+            deny("The agent was stopped before this tool use was approved"),
+            DecisionSource::Cancelled,
+        ),
+        Err(_) => {
+            broker.forget(&request_id);
+            (
+                request_id,
+                deny(&format!(
+                    "No permission decision within {} seconds",
+                    wait.as_secs()
+                )),
+                DecisionSource::Timeout,
+            )
+        }
+    }
+}
+
+fn deny(message: &str) -> PermissionDecision {
+    PermissionDecision {
+        behavior: PermissionBehavior::Deny,
+        message: Some(message.to_string()),
+        updated_input: None,
+        always_allow: false,
+    }
+}
+
+/// Append the decision to the session transcript.
+fn record_decision(
+    armin: &DaemonArmin,
+    request: &PermissionRequest,
+    request_id: Option<&str>,
+    decision: &PermissionDecision,
+    source: DecisionSource,
+) {
+    let content = serde_json::json!({
+        "type": "permission_decision",
+        "request_id": request_id,
+        "tool_name": request.tool_name,
+        "tool_use_id": request.tool_use_id,
+        "behavior": decision.behavior.as_str(),
+        "source": source.as_str(),
+        "message": decision.message,
+        "always_allow": decision.always_allow,
+    });
+    let armin_session_id = SessionId::from_string(&request.session_id);
+    if let Err(error) = armin.append(
+        &armin_session_id,
+        NewMessage {
+            content: content.to_string(),
+        },
+    ) {
+        warn!(error = %error, "Failed to record permission decision");
+    }
+}
+
+/// The permission prompt tool's answer: allow with the (possibly edited)
+/// input, or deny with a reason for the agent.
+fn prompt_tool_decision(request: &PermissionRequest, decision: &PermissionDecision) -> Value {
+    match decision.behavior {
+        PermissionBehavior::Allow => serde_json::json!({
+            "behavior": "allow",
+            "updatedInput": decision
+                .updated_input
+                .clone()
+                .unwrap_or_else(|| request.input.clone()),
+        }),
+        PermissionBehavior::Deny => serde_json::json!({
+            "behavior": "deny",
+            "message": decision
+                .message
+                .clone()
+                .unwrap_or_else(|| "Permission denied by user".to_string()),
+        }),
+    }
+}
+
+/// Repository path and default worktree root for a session's repository.
+fn session_repository(armin: &DaemonArmin, session_id: &str) -> Option<(PathBuf, String)> {
+    let session = armin
+        .get_session(&SessionId::from_string(session_id))
+        .ok()??;
+    let repository = armin.get_repository(&session.repository_id).ok()??;
+    let default_worktree_root_dir = default_worktree_root_dir_for_repo(repository.id.as_str());
+    Some((PathBuf::from(repository.path), default_worktree_root_dir))
+}
+
+fn repository_allows_tool(armin: &DaemonArmin, session_id: &str, tool_name: &str) -> bool {
+    let Some((repo_path, default_worktree_root_dir)) = session_repository(armin, session_id) else {
+        return false;
+    };
+    match load_repository_config(&repo_path, &default_worktree_root_dir) {
+        Ok(config) => config
+            .permissions
+            .always_allow_tools
+            .iter()
+            .any(|tool| tool == tool_name),
+        Err(error) => {
+            warn!(error = %error, "Failed to load repository permission rules");
+            false
+        }
+    }
+}
+
+fn save_always_allow_rule(armin: &DaemonArmin, session_id: &str, tool_name: &str) -> bool {
+    let Some((repo_path, default_worktree_root_dir)) = session_repository(armin, session_id) else {
+        return false;
+    };
+    match add_always_allow_tool(&repo_path, tool_name, &default_worktree_root_dir) {
+        Ok(_) => true,
+        Err(error) => {
+            warn!(error = %error, tool_name, "Failed to save always-allow rule");
+            false
+        }
+    }
+}
+
+fn check_always_allow(tool_name: &str) -> Result<(), (String, String)> {
+    if NO_ALWAYS_ALLOW_TOOLS.contains(&tool_name) {
+        return Err((
+            "invalid_params".to_string(),
+            format!("always_allow is not available for {tool_name}; allow each use instead"),
+        ));
+    }
+    Ok(())
+}
+
+fn parse_permission_request(params: &Value) -> Result<PermissionRequest, (String, String)> {
+    let session_id = params.get("session_id").and_then(Value::as_str);
+    let tool_name = params
+        .get("tool_name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+
+    let (Some(session_id), Some(tool_name)) = (session_id, tool_name) else {
+        return Err((
+            "invalid_params".to_string(),
+            "session_id and tool_name are required".to_string(),
+        ));
+    };
+
+    Ok(PermissionRequest {
+        session_id: session_id.to_string(),
+        tool_name: tool_name.to_string(),
+        input: params
+            .get("input")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({})),
+        tool_use_id: params
+            .get("tool_use_id")
+            .and_then(Value::as_str)
+            .map(String::from),
+    })
+}
+
+fn parse_permission_response(
+    params: &Value,
+) -> Result<(String, PermissionDecision), (String, String)> {
+    let request_id = params.get("request_id").and_then(Value::as_str);
+    let behavior = params
+        .get("behavior")
+        .and_then(Value::as_str)
+        .and_then(PermissionBehavior::parse);
+
+    let (Some(request_id), Some(behavior)) = (request_id, behavior) else {
+        return Err((
+            "invalid_params".to_string(),
+            "request_id and behavior (\"allow\" or \"deny\") are required".to_string(),
+        ));
+    };
+
+    let always_allow = params
+        .get("always_allow")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if always_allow && behavior == PermissionBehavior::Deny {
+        return Err((
+            "invalid_params".to_string(),
+            "always_allow can only be set when allowing".to_string(),
+        ));
+    }
+
+    let updated_input = params
+        .get("updated_input")
+        .cloned()
+        .filter(Value::is_object);
+
+    Ok((
+        request_id.to_string(),
+        PermissionDecision {
+            behavior,
+            message: params
+                .get("message")
+                .and_then(Value::as_str)
+                .map(String::from),
+            updated_input,
+            always_allow,
+        },
+    ))
+}
+
+async fn register_permission_request(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::AgentPermissionRequest, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match permission_request_core(&state, &params).await {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err((code, msg)) => {
+                        let error_code = match code.as_str() {
+                            "invalid_params" => error_codes::INVALID_PARAMS,
+                            "not_found" => error_codes::NOT_FOUND,
+                            _ => error_codes::INTERNAL_ERROR,
+                        };
+                        Response::error(&req.id, error_code, &msg)
+                    }
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_respond_permission(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::AgentRespondPermission, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match respond_permission_core(&state, &params).await {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err((code, msg)) => {
+                        let error_code = match code.as_str() {
+                            "invalid_params" => error_codes::INVALID_PARAMS,
+                            "not_found" => error_codes::NOT_FOUND,
+                            _ => error_codes::INTERNAL_ERROR,
+                        };
+                        Response::error(&req.id, error_code, &msg)
+                    }
+                }
+            }
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::armin_adapter::create_test_armin;
+    use crate::utils::repository_config::load_repository_config;
+    use agent_session_sqlite_persist_core::{NewRepository, NewSession};
+    use serde_json::json;
+    use std::fs;
+    use std::sync::Arc;
+
+    struct Fixture {
+        armin: Arc<DaemonArmin>,
+        subscriptions: SubscriptionManager,
+        session_id: String,
+        repo_path: PathBuf,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.repo_path);
+        }
+    }
+
+    fn fixture() -> Fixture {
+        let repo_path =
+            std::env::temp_dir().join(format!("daemon-permission-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&repo_path).unwrap();
+        let subscriptions = SubscriptionManager::new();
+        let armin = create_test_armin(subscriptions.clone()).unwrap();
+        let repository = armin
+            .create_repository(NewRepository::new(
+                repo_path.to_string_lossy().to_string(),
+                "permission-repo",
+                false,
+            ))
+            .unwrap();
+        let session = armin
+            .create_session_with_metadata(NewSession::new(repository.id, "Permissions"))
+            .unwrap();
+        Fixture {
+            armin,
+            subscriptions,
+            session_id: session.id.as_str().to_string(),
+            repo_path,
+        }
+    }
+
+    fn bash_request(session_id: &str) -> PermissionRequest {
+        PermissionRequest {
+            session_id: session_id.to_string(),
+            tool_name: "Bash".to_string(),
+            input: json!({ "command": "cargo test" }),
+            tool_use_id: Some("toolu_1".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_decision_is_broadcast_then_delivered() {
+        let fixture = fixture();
+        let broker = PermissionBroker::new();
+        let mut events = fixture.subscriptions.subscribe(&fixture.session_id).await;
+        let request = bash_request(&fixture.session_id);
+
+        let waiter = {
+            let armin = fixture.armin.clone();
+            let broker = broker.clone();
+            let subscriptions = fixture.subscriptions.clone();
+            let request = request.clone();
+            tokio::spawn(async move {
+                await_client_decision(
+                    &armin,
+                    &broker,
+                    &subscriptions,
+                    &request,
+                    Duration::from_secs(5),
+                )
+                .await
+            })
+        };
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.event_type, EventType::PermissionRequest);
+        assert_eq!(event.data["tool_name"], "Bash");
+        assert_eq!(event.data["input"]["command"], "cargo test");
+        let request_id = event.data["request_id"].as_str().unwrap().to_string();
+
+        let (_, decision) = parse_permission_response(&json!({
+            "request_id": request_id,
+            "behavior": "allow",
+            "updated_input": { "command": "cargo test --lib" },
+        }))
+        .unwrap();
+        assert!(broker.resolve(&request_id, decision).is_some());
+
+        let (answered_id, decision, source) = waiter.await.unwrap();
+        assert_eq!(answered_id, request_id);
+        assert_eq!(source, DecisionSource::Client);
+        assert_eq!(
+            prompt_tool_decision(&request, &decision),
+            json!({ "behavior": "allow", "updatedInput": { "command": "cargo test --lib" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn unanswered_request_times_out_as_deny() {
+        let fixture = fixture();
+        let broker = PermissionBroker::new();
+        let request = bash_request(&fixture.session_id);
+
+        let (_, decision, source) = await_client_decision(
+            &fixture.armin,
+            &broker,
+            &fixture.subscriptions,
+            &request,
+            Duration::from_millis(10),
+        )
+        .await;
+
+        assert_eq!(source, DecisionSource::Timeout);
+        assert_eq!(decision.behavior, PermissionBehavior::Deny);
+        assert!(broker.pending_for_session(&fixture.session_id).is_empty());
+        assert_eq!(
+            prompt_tool_decision(&request, &decision)["behavior"],
+            "deny"
+        );
+    }
+
+    #[tokio::test]
+    async fn decisions_are_recorded_in_the_transcript() {
+        let fixture = fixture();
+        let request = bash_request(&fixture.session_id);
+
+        record_decision(
+            &fixture.armin,
+            &request,
+            Some("req-1"),
+            &deny("not now"),
+            DecisionSource::Client,
+        );
+
+        let messages = fixture
+            .armin
+            .list_messages_page(&SessionId::from_string(&fixture.session_id), 0, 10)
+            .unwrap();
+        let recorded: Value = serde_json::from_str(&messages[0].content).unwrap();
+        assert_eq!(recorded["type"], "permission_decision");
+        assert_eq!(recorded["request_id"], "req-1");
+        assert_eq!(recorded["tool_use_id"], "toolu_1");
+        assert_eq!(recorded["behavior"], "deny");
+        assert_eq!(recorded["source"], "client");
+        assert_eq!(recorded["message"], "not now");
+    }
+
+    #[tokio::test]
+    async fn always_allow_rule_is_saved_for_the_repository() {
+        let fixture = fixture();
+        assert!(!repository_allows_tool(
+            &fixture.armin,
+            &fixture.session_id,
+            "Bash"
+        ));
+
+        assert!(save_always_allow_rule(
+            &fixture.armin,
+            &fixture.session_id,
+            "Bash"
+        ));
+
+        assert!(repository_allows_tool(
+            &fixture.armin,
+            &fixture.session_id,
+            "Bash"
+        ));
+        assert!(!repository_allows_tool(
+            &fixture.armin,
+            &fixture.session_id,
+            "Edit"
+        ));
+        let config = load_repository_config(&fixture.repo_path, "unused").unwrap();
+        assert_eq!(config.permissions.always_allow_tools, vec!["Bash"]);
+    }
+
+    #[test]
+    fn response_params_are_validated() {
+        assert!(parse_permission_response(&json!({ "request_id": "r" })).is_err());
+        assert!(
+            parse_permission_response(&json!({ "request_id": "r", "behavior": "maybe" })).is_err()
+        );
+        let err = parse_permission_response(&json!({
+            "request_id": "r",
+            "behavior": "deny",
+            "always_allow": true,
+        }))
+        .unwrap_err();
+        assert_eq!(err.1, "always_allow can only be set when allowing");
+
+        let (_, decision) = parse_permission_response(&json!({
+            "request_id": "r",
+            "behavior": "allow",
+            "always_allow": true,
+        }))
+        .unwrap();
+        assert!(decision.always_allow);
+    }
+
+    #[test]
+    fn always_allow_is_refused_for_bash() {
+        let err = check_always_allow("Bash").unwrap_err();
+        assert_eq!(err.0, "invalid_params");
+        assert!(check_always_allow("Edit").is_ok());
+    }
+
+    #[test]
+    fn request_params_require_session_and_tool() {
+        assert!(parse_permission_request(&json!({ "session_id": "s" })).is_err());
+        assert!(parse_permission_request(&json!({ "session_id": "s", "tool_name": " " })).is_err());
+        let request =
+            parse_permission_request(&json!({ "session_id": "s", "tool_name": "Bash" })).unwrap();
+        assert_eq!(request.input, json!({}));
+        assert_eq!(request.tool_use_id, None);
+    }
+}
@@ -0,0 +1,665 @@
+//! Message queue handlers.
+//!
+//! A send for a session whose agent is still running, or whose queue is not
+//! empty, is queued instead of rejected. When the agent finishes its turn,
+//! the oldest queued message is sent as if it had just arrived, and removed
+//! once the send is accepted; the queue is persisted, so pending messages are
+//! picked up again after a daemon restart.
+
+use crate::app::DaemonState;
+use crate::armin_adapter::DaemonArmin;
+use crate::ipc::handlers::claude::{send_queued_message, write_runtime_status};
+use crate::observability::spawn_in_current_span;
+use crate::utils::agent_scheduler::AgentScheduler;
+use agent_session_sqlite_persist_core::{
+    CodingSessionStatus, NewQueuedMessage, QueuedMessage, SessionId, SessionReader, SessionWriter,
+};
+use daemon_ipc::{error_codes, IpcServer, Method, Response};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Stop senders of running agent processes, keyed by session id.
+type RunningProcesses = Mutex<HashMap<String, broadcast::Sender<()>>>;
+
+/// Sessions whose oldest queued message is being sent.
+pub type QueueDispatches = Mutex<HashSet<String>>;
+
+/// Register message queue handlers.
+pub async fn register(server: &IpcServer, state: DaemonState) {
+    register_queue_list(server, state.clone()).await;
+    register_queue_remove(server, state.clone()).await;
+    register_queue_clear(server, state).await;
+}
+
+/// Queue `request` if an agent process is running, or waiting for a
+/// scheduler slot, for the session, or if older messages are still queued.
+///
+/// Returns the `agent.send` response for a queued message, or None if the
+/// request should be sent now. `queue_id` is set when the request is itself
+/// the queued message being dispatched; it is sent if the session is free.
+/// The running-process lock is held while enqueueing so a process finishing
+/// concurrently cannot miss the message.
+pub(crate) fn enqueue_if_running(
+    processes: &RunningProcesses,
+    scheduler: &AgentScheduler,
+    armin: &DaemonArmin,
+    session_id: &str,
+    content: &str,
+    request: &Value,
+    queue_id: Option<&str>,
+) -> Result<Option<Value>, (String, String)> {
+    let processes = processes.lock().unwrap();
+    let busy = processes.contains_key(session_id) || scheduler.has_session(session_id);
+    if queue_id.is_some() {
+        return match busy {
+            true => Err((
+                "conflict".to_string(),
+                "The agent is already running".to_string(),
+            )),
+            false => Ok(None),
+        };
+    }
+
+    let armin_session_id = SessionId::from_string(session_id);
+    let queue_empty = armin
+        .list_queued_messages(&armin_session_id)
+        .map_err(|e| {
+            (
+                "internal_error".to_string(),
+                format!("Failed to read message queue: {}", e),
+            )
+        })?
+        .is_empty();
+    if !busy && queue_empty {
+        return Ok(None);
+    }
+
+    let queued = armin
+        .enqueue_message(
+            &armin_session_id,
+            NewQueuedMessage {
+                content: content.to_string(),
+                request_json: request.to_string(),
+            },
+        )
+        .map_err(|e| {
+            (
+                "internal_error".to_string(),
+                format!("Failed to queue message: {}", e),
+            )
+        })?;
+    let queue_length = armin
+        .list_queued_messages(&armin_session_id)
+        .map(|messages| messages.len())
+        .unwrap_or_default();
+    info!(session_id = %session_id, queue_length, "Queued message behind running agent or older messages");
+
+    Ok(Some(serde_json::json!({
+        "status": "queued",
+        "session_id": session_id,
+        "queue_id": queued.id,
+        "queue_length": queue_length,
+    })))
+}
+
+/// Send the session's oldest queued message, if it is ready for one.
+///
+/// Called when an agent process exits. The send runs in the background, so
+/// this is safe to call from the event handler of the process that finished.
+pub(crate) fn dispatch_next_queued(state: &DaemonState, session_id: &str) {
+    dispatch(state, session_id, false);
+}
+
+/// Send the session's oldest queued message even if the queue is held, as a
+/// newer message was just queued behind it. Does nothing while the agent runs.
+pub(crate) fn resume_held_queue(state: &DaemonState, session_id: &str) {
+    dispatch(state, session_id, true);
+}
+
+fn dispatch(state: &DaemonState, session_id: &str, release_hold: bool) {
+    let queued = match next_dispatchable(
+        &state.claude_processes,
+        &state.scheduler,
+        &state.queue_dispatches,
+        &state.armin,
+        session_id,
+        release_hold,
+    ) {
+        Ok(Some(queued)) => queued,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(session_id = %session_id, error = %e, "Failed to read message queue");
+            return;
+        }
+    };
+    let armin_session_id = SessionId::from_string(session_id);
+    let release = |state: &DaemonState| {
+        state.queue_dispatches.lock().unwrap().remove(session_id);
+    };
+
+    let params: Value = match serde_json::from_str(&queued.request_json) {
+        Ok(params) => params,
+        Err(e) => {
+            warn!(session_id = %session_id, error = %e, "Dropping unreadable queued message");
+            if let Err(e) = state
+                .armin
+                .remove_queued_message(&armin_session_id, &queued.id)
+            {
+                warn!(session_id = %session_id, error = %e, "Failed to remove queued message");
+            }
+            release(state);
+            return;
+        }
+    };
+
+    info!(session_id = %session_id, queue_id = %queued.id, "Sending queued message");
+    let state = state.clone();
+    let session_id = session_id.to_string();
+    spawn_in_current_span(async move {
+        match send_queued_message(&state, &params, &queued.id).await {
+            Ok(_) => {
+                if let Err(e) = state
+                    .armin
+                    .remove_queued_message(&armin_session_id, &queued.id)
+                {
+                    warn!(session_id = %session_id, error = %e, "Failed to remove sent queued message");
+                }
+            }
+            // Another turn got there first; the message is sent after it
+            Err((code, _)) if code == "conflict" => {}
+            Err((_, message)) => {
+                warn!(session_id = %session_id, error = %message, "Failed to send queued message");
+                write_runtime_status(
+                    &state,
+                    &session_id,
+                    CodingSessionStatus::Error,
+                    Some(format!("Failed to send queued message: {message}")),
+                );
+            }
+        }
+        state.queue_dispatches.lock().unwrap().remove(&session_id);
+        // A turn that already finished could not dispatch while this one was
+        // claimed
+        dispatch_next_queued(&state, &session_id);
+    });
+}
+
+/// Dispatch every session's queue, e.g. after a daemon restart.
+pub fn resume_message_queues(state: &DaemonState) {
+    let sessions = match state.armin.sessions_with_queued_messages() {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            warn!(error = %e, "Failed to list sessions with queued messages");
+            return;
+        }
+    };
+    for session_id in sessions {
+        dispatch_next_queued(state, session_id.as_str());
+    }
+}
+
+/// Claim the next queued message unless the session is busy or held. The
+/// message stays queued until its send is accepted; the claim in
+/// `dispatches` keeps it from being sent twice meanwhile.
+///
+/// A queue is held while an agent is running, while a question awaits an
+/// answer, after an error, and after the agent was stopped; it resumes the
+/// next time a turn finishes normally, or with `release_hold` when a new
+/// message is queued behind it.
+fn next_dispatchable(
+    processes: &RunningProcesses,
+    scheduler: &AgentScheduler,
+    dispatches: &QueueDispatches,
+    armin: &DaemonArmin,
+    session_id: &str,
+    release_hold: bool,
+) -> Result<Option<QueuedMessage>, agent_session_sqlite_persist_core::ArminError> {
+    let processes = processes.lock().unwrap();
+    if processes.contains_key(session_id) || scheduler.has_session(session_id) {
+        return Ok(None);
+    }
+    let mut dispatches = dispatches.lock().unwrap();
+    if dispatches.contains(session_id) {
+        return Ok(None);
+    }
+
+    let armin_session_id = SessionId::from_string(session_id);
+    let status = armin
+        .get_session_state(&armin_session_id)?
+        .map(|state| state.runtime_status.coding_session.status)
+        .unwrap_or_default();
+    if !release_hold
+        && matches!(
+            status,
+            CodingSessionStatus::Waiting
+                | CodingSessionStatus::Error
+                | CodingSessionStatus::NotAvailable
+        )
+    {
+        return Ok(None);
+    }
+
+    let next = armin
+        .list_queued_messages(&armin_session_id)?
+        .into_iter()
+        .next();
+    if next.is_some() {
+        dispatches.insert(session_id.to_string());
+    }
+    Ok(next)
+}
+
+/// Core agent.queue_list logic.
+pub fn queue_list_core(armin: &DaemonArmin, params: &Value) -> Result<Value, (String, String)> {
+    let session_id = parse_session_id(armin, params)?;
+    let messages = armin.list_queued_messages(&session_id).map_err(|e| {
+        (
+            "internal_error".to_string(),
+            format!("Failed to list queued messages: {}", e),
+        )
+    })?;
+
+    let messages: Vec<Value> = messages
+        .into_iter()
+        .enumerate()
+        .map(|(index, message)| {
+            serde_json::json!({
+                "id": message.id,
+                "position": index + 1,
+                "content": message.content,
+                "created_at": message.created_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    Ok(serde_json::json!({
+        "session_id": session_id.as_str(),
+        "messages": messages,
+    }))
+}
+
+/// Core agent.queue_remove logic.
+pub fn queue_remove_core(armin: &DaemonArmin, params: &Value) -> Result<Value, (String, String)> {
+    let session_id = parse_session_id(armin, params)?;
+    let Some(queue_id) = params.get("queue_id").and_then(Value::as_str) else {
+        return Err((
+            "invalid_params".to_string(),
+            "queue_id is required".to_string(),
+        ));
+    };
+
+    let removed = armin
+        .remove_queued_message(&session_id, queue_id)
+        .map_err(|e| {
+            (
+                "internal_error".to_string(),
+                format!("Failed to remove queued message: {}", e),
+            )
+        })?;
+    if !removed {
+        return Err((
+            "not_found".to_string(),
+            "No queued message with this queue_id".to_string(),
+        ));
+    }
+
+    Ok(serde_json::json!({
+        "session_id": session_id.as_str(),
+        "queue_id": queue_id,
+        "removed": true,
+    }))
+}
+
+/// Core agent.queue_clear logic.
+pub fn queue_clear_core(armin: &DaemonArmin, params: &Value) -> Result<Value, (String, String)> {
+    let session_id = parse_session_id(armin, params)?;
+    let cleared = armin.clear_queued_messages(&session_id).map_err(|e| {
+        (
+            "internal_error".to_string(),
+            format!("Failed to clear queued messages: {}", e),
+        )
+    })?;
+
+    Ok(serde_json::json!({
+        "session_id": session_id.as_str(),
+        "cleared": cleared,
+    }))
+}
+
+fn parse_session_id(armin: &DaemonArmin, params: &Value) -> Result<SessionId, (String, String)> {
+    let Some(session_id) = params.get("session_id").and_then(Value::as_str) else {
+        return Err((
+            "invalid_params".to_string(),
+            "session_id is required".to_string(),
+        ));
+    };
+
+    let session_id = SessionId::from_string(session_id);
+    match armin.get_session(&session_id) {
+        Ok(Some(_)) => Ok(session_id),
+        Ok(None) => Err(("not_found".to_string(), "Session not found".to_string())),
+        Err(e) => Err((
+            "internal_error".to_string(),
+            format!("Failed to load session: {}", e),
+        )),
+    }
+}
+
+fn queue_error_response(id: &str, code: String, msg: &str) -> Response {
+    let error_code = match code.as_str() {
+        "invalid_params" => error_codes::INVALID_PARAMS,
+        "not_found" => error_codes::NOT_FOUND,
+        _ => error_codes::INTERNAL_ERROR,
+    };
+    Response::error(id, error_code, msg)
+}
+
+async fn register_queue_list(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::AgentQueueList, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match queue_list_core(&state.armin, &params) {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err((code, msg)) => queue_error_response(&req.id, code, &msg),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_queue_remove(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::AgentQueueRemove, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match queue_remove_core(&state.armin, &params) {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err((code, msg)) => queue_error_response(&req.id, code, &msg),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_queue_clear(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::AgentQueueClear, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match queue_clear_core(&state.armin, &params) {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err((code, msg)) => queue_error_response(&req.id, code, &msg),
+                }
+            }
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::armin_adapter::create_test_armin;
+    use crate::utils::agent_scheduler::{Admission, SchedulerLimits, SlotRequest};
+    use agent_session_sqlite_persist_core::{NewRepository, NewSession};
+    use daemon_ipc::SubscriptionManager;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn session(armin: &DaemonArmin) -> String {
+        let repository = armin
+            .create_repository(NewRepository::new(
+                format!("/tmp/queue-test-{}", uuid::Uuid::new_v4()),
+                "queue-repo",
+                false,
+            ))
+            .unwrap();
+        armin
+            .create_session_with_metadata(NewSession::new(repository.id, "Queue"))
+            .unwrap()
+            .id
+            .as_str()
+            .to_string()
+    }
+
+    fn idle_scheduler() -> AgentScheduler {
+        AgentScheduler::new(SchedulerLimits {
+            max_concurrent: 1,
+            max_per_repository: 1,
+        })
+    }
+
+    fn running(session_id: &str) -> RunningProcesses {
+        let (stop_tx, _) = broadcast::channel(1);
+        Mutex::new(HashMap::from([(session_id.to_string(), stop_tx)]))
+    }
+
+    fn enqueue(
+        armin: &Arc<DaemonArmin>,
+        processes: &RunningProcesses,
+        session_id: &str,
+        content: &str,
+    ) -> Value {
+        let request = json!({ "session_id": session_id, "content": content });
+        enqueue_if_running(
+            processes,
+            &idle_scheduler(),
+            armin,
+            session_id,
+            content,
+            &request,
+            None,
+        )
+        .unwrap()
+        .expect("message should be queued behind the running agent")
+    }
+
+    #[tokio::test]
+    async fn sends_queue_while_the_agent_runs_or_older_messages_wait() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let session_id = session(&armin);
+
+        let idle = Mutex::new(HashMap::new());
+        let request = json!({ "session_id": session_id, "content": "now" });
+        assert!(enqueue_if_running(
+            &idle,
+            &idle_scheduler(),
+            &armin,
+            &session_id,
+            "now",
+            &request,
+            None
+        )
+        .unwrap()
+        .is_none());
+
+        let processes = running(&session_id);
+        let first = enqueue(&armin, &processes, &session_id, "first");
+        let second = enqueue(&armin, &processes, &session_id, "second");
+        assert_eq!(first["status"], "queued");
+        assert_eq!(second["queue_length"], 2);
+
+        let state = armin
+            .get_session_state(&SessionId::from_string(&session_id))
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.runtime_status.coding_session.queue_length, 2);
+
+        let listed = queue_list_core(&armin, &json!({ "session_id": session_id })).unwrap();
+        assert_eq!(listed["messages"][0]["content"], "first");
+        assert_eq!(listed["messages"][1]["position"], 2);
+
+        // Once the agent is done, new sends still go behind the older ones,
+        // while the dispatched message itself is sent
+        let third = enqueue(&armin, &idle, &session_id, "third");
+        assert_eq!(third["queue_length"], 3);
+        let first_id = first["queue_id"].as_str();
+        assert!(enqueue_if_running(
+            &idle,
+            &idle_scheduler(),
+            &armin,
+            &session_id,
+            "first",
+            &request,
+            first_id
+        )
+        .unwrap()
+        .is_none());
+        let (code, _) = enqueue_if_running(
+            &processes,
+            &idle_scheduler(),
+            &armin,
+            &session_id,
+            "first",
+            &request,
+            first_id,
+        )
+        .unwrap_err();
+        assert_eq!(code, "conflict");
+    }
+
+    #[tokio::test]
+    async fn dispatch_waits_for_the_agent_and_held_statuses() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let session_id = session(&armin);
+        let armin_session_id = SessionId::from_string(&session_id);
+        let processes = running(&session_id);
+        let dispatches = QueueDispatches::default();
+        let queued = enqueue(&armin, &processes, &session_id, "next");
+        let next_dispatchable =
+            |processes: &RunningProcesses, scheduler: &AgentScheduler, release_hold: bool| {
+                next_dispatchable(
+                    processes,
+                    scheduler,
+                    &dispatches,
+                    &armin,
+                    &session_id,
+                    release_hold,
+                )
+                .unwrap()
+            };
+
+        assert!(next_dispatchable(&processes, &idle_scheduler(), false).is_none());
+
+        processes.lock().unwrap().clear();
+        armin
+            .update_runtime_status(
+                &armin_session_id,
+                "device",
+                CodingSessionStatus::Waiting,
+                None,
+            )
+            .unwrap();
+        assert!(next_dispatchable(&processes, &idle_scheduler(), false).is_none());
+
+        armin
+            .update_runtime_status(&armin_session_id, "device", CodingSessionStatus::Idle, None)
+            .unwrap();
+        let scheduler = idle_scheduler();
+        let Admission::Started(slot) = scheduler.request(SlotRequest {
+            session_id: session_id.clone(),
+            repository_id: "repo".to_string(),
+            priority: 0,
+        }) else {
+            panic!("an idle scheduler should start the request");
+        };
+        assert!(next_dispatchable(&processes, &scheduler, false).is_none());
+        drop(slot);
+
+        let next = next_dispatchable(&processes, &idle_scheduler(), false).unwrap();
+        assert_eq!(next.id, queued["queue_id"]);
+        let request: Value = serde_json::from_str(&next.request_json).unwrap();
+        assert_eq!(request["content"], "next");
+        // Claimed until its send is accepted, but still queued meanwhile
+        assert!(next_dispatchable(&processes, &idle_scheduler(), false).is_none());
+        assert_eq!(
+            armin.list_queued_messages(&armin_session_id).unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn a_new_message_releases_a_held_queue() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let session_id = session(&armin);
+        let armin_session_id = SessionId::from_string(&session_id);
+        let idle = Mutex::new(HashMap::new());
+        let dispatches = QueueDispatches::default();
+        let queued = enqueue(&armin, &running(&session_id), &session_id, "older");
+        armin
+            .update_runtime_status(
+                &armin_session_id,
+                "device",
+                CodingSessionStatus::Error,
+                None,
+            )
+            .unwrap();
+
+        assert!(next_dispatchable(
+            &idle,
+            &idle_scheduler(),
+            &dispatches,
+            &armin,
+            &session_id,
+            false
+        )
+        .unwrap()
+        .is_none());
+        enqueue(&armin, &idle, &session_id, "newer");
+        let next = next_dispatchable(
+            &idle,
+            &idle_scheduler(),
+            &dispatches,
+            &armin,
+            &session_id,
+            true,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(next.id, queued["queue_id"]);
+    }
+
+    #[tokio::test]
+    async fn remove_and_clear_edit_the_queue() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let session_id = session(&armin);
+        let processes = running(&session_id);
+        let first = enqueue(&armin, &processes, &session_id, "first");
+        enqueue(&armin, &processes, &session_id, "second");
+        enqueue(&armin, &processes, &session_id, "third");
+
+        let params = json!({ "session_id": session_id, "queue_id": first["queue_id"] });
+        assert_eq!(queue_remove_core(&armin, &params).unwrap()["removed"], true);
+        let (code, _) = queue_remove_core(&armin, &params).unwrap_err();
+        assert_eq!(code, "not_found");
+
+        let cleared = queue_clear_core(&armin, &json!({ "session_id": session_id })).unwrap();
+        assert_eq!(cleared["cleared"], 2);
+        let listed = queue_list_core(&armin, &json!({ "session_id": session_id })).unwrap();
+        assert_eq!(listed["messages"], json!([]));
+
+        let (code, _) = queue_list_core(&armin, &json!({ "session_id": "missing" })).unwrap_err();
+        assert_eq!(code, "not_found");
+        let (code, _) =
+            queue_remove_core(&armin, &json!({ "session_id": session_id })).unwrap_err();
+        assert_eq!(code, "invalid_params");
+    }
+}
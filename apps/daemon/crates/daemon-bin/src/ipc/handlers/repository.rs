@@ -1,16 +1,26 @@
 //! Repository handlers.
 
 use crate::app::{resolve_machine_space_scope, DaemonState};
+use crate::armin_adapter::DaemonArmin;
+use crate::ipc::handlers::claude::parse_optional_permission_mode;
+use crate::utils::env_profiles::{
+    delete_profile_secrets, delete_var, profiles_json, set_var, validate_profile_name,
+    validate_var_name,
+};
+use crate::utils::mcp_servers::{
+    probe_mcp_server, validate_mcp_env_conflicts, validate_mcp_server, McpProbe, MCP_PROBE_TIMEOUT,
+};
 use crate::utils::repository_config::{
     default_worktree_root_dir_for_repo, load_repository_config, update_repository_config,
-    RepositoryConfig, RepositoryConfigUpdate,
+    EnvProfileConfig, McpServerConfig, RepositoryConfig, RepositoryConfigUpdate, SandboxConfig,
 };
 use agent_session_sqlite_persist_core::{
-    NewRepository, RepositoryId, SessionReader, SessionWriter,
+    NewRepository, Repository, RepositoryId, SessionReader, SessionWriter,
 };
 use daemon_ipc::{error_codes, IpcServer, Method, Response};
 use safe_file_ops::{FileRevision, SafeFileOpsError};
 use safe_repo_dir_lister::{ListOptions, SafeRepoDirListerError};
+use std::collections::BTreeMap;
 use std::path::Path;
 use tokio::task;
 use workspace_resolver::{resolve_working_dir_from_str, ResolveError};
@@ -26,7 +36,12 @@ pub async fn register(server: &IpcServer, state: DaemonState) {
     register_repository_read_file(server, state.clone()).await;
     register_repository_read_file_slice(server, state.clone()).await;
     register_repository_write_file(server, state.clone()).await;
-    register_repository_replace_file_range(server, state).await;
+    register_repository_replace_file_range(server, state.clone()).await;
+    register_repository_mcp_list(server, state.clone()).await;
+    register_repository_mcp_update(server, state.clone()).await;
+    register_repository_env_list(server, state.clone()).await;
+    register_repository_env_set(server, state.clone()).await;
+    register_repository_env_delete(server, state).await;
 }
 
 async fn register_repository_list(server: &IpcServer, state: DaemonState) {
@@ -356,6 +371,49 @@ async fn register_repository_update_settings(server: &IpcServer, state: DaemonSt
                         }
                     };
 
+                let always_allow_tools =
+                    match parse_optional_string_list_param(&params, "always_allow_tools") {
+                        Ok(value) => value,
+                        Err(msg) => {
+                            return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                        }
+                    };
+
+                let default_permission_mode =
+                    match parse_optional_permission_mode(&params, "default_permission_mode") {
+                        Ok(value) => value,
+                        Err(msg) => {
+                            return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                        }
+                    };
+
+                let max_turn_seconds =
+                    match parse_optional_limit_seconds_param(&params, "max_turn_seconds") {
+                        Ok(value) => value,
+                        Err(msg) => {
+                            return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                        }
+                    };
+                let max_idle_seconds =
+                    match parse_optional_limit_seconds_param(&params, "max_idle_seconds") {
+                        Ok(value) => value,
+                        Err(msg) => {
+                            return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                        }
+                    };
+                let sandbox = match parse_optional_sandbox_param(&params, "sandbox") {
+                    Ok(value) => value,
+                    Err(msg) => {
+                        return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                    }
+                };
+                let shell = match parse_optional_string_param(&params, "shell") {
+                    Ok(value) => value,
+                    Err(msg) => {
+                        return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                    }
+                };
+
                 let config_update = RepositoryConfigUpdate {
                     worktree_root_dir,
                     worktree_default_base_branch,
@@ -363,6 +421,14 @@ async fn register_repository_update_settings(server: &IpcServer, state: DaemonSt
                     pre_create_timeout_seconds,
                     post_create_command,
                     post_create_timeout_seconds,
+                    always_allow_tools,
+                    default_permission_mode,
+                    mcp_servers: None,
+                    max_turn_seconds,
+                    max_idle_seconds,
+                    sandbox,
+                    env_profiles: None,
+                    shell,
                 };
                 let previous_config = match load_repository_config(
                     Path::new(&current.path),
@@ -964,6 +1030,446 @@ async fn register_repository_replace_file_range(server: &IpcServer, state: Daemo
         .await;
 }
 
+async fn register_repository_mcp_list(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::RepositoryMcpList, move |req| {
+            let armin = state.armin.clone();
+            async move {
+                let (_, repo_config) = match load_repository_and_config(&armin, req.params.as_ref())
+                {
+                    Ok(loaded) => loaded,
+                    Err((code, msg)) => return Response::error(&req.id, code, &msg),
+                };
+                Response::success(
+                    &req.id,
+                    serde_json::json!({ "servers": mcp_servers_json(&repo_config.mcp_servers) }),
+                )
+            }
+        })
+        .await;
+}
+
+async fn register_repository_mcp_update(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::RepositoryMcpUpdate, move |req| {
+            let armin = state.armin.clone();
+            async move {
+                let (repo, repo_config) =
+                    match load_repository_and_config(&armin, req.params.as_ref()) {
+                        Ok(loaded) => loaded,
+                        Err((code, msg)) => return Response::error(&req.id, code, &msg),
+                    };
+                let params = req
+                    .params
+                    .as_ref()
+                    .expect("repository id was parsed from params");
+                let update = match parse_mcp_update(params) {
+                    Ok(update) => update,
+                    Err(msg) => return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg),
+                };
+
+                let mut servers = repo_config.mcp_servers;
+                match update.server {
+                    Some(server) => {
+                        if update.dry_run {
+                            let probe =
+                                probe_mcp_server(&server, Path::new(&repo.path), MCP_PROBE_TIMEOUT)
+                                    .await;
+                            return Response::success(&req.id, mcp_probe_json(&update.name, probe));
+                        }
+                        servers.insert(update.name, server);
+                        if let Err(msg) = validate_mcp_env_conflicts(&servers) {
+                            return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                        }
+                    }
+                    None => {
+                        if servers.remove(&update.name).is_none() {
+                            return Response::error(
+                                &req.id,
+                                error_codes::NOT_FOUND,
+                                &format!("MCP server \"{}\" not found", update.name),
+                            );
+                        }
+                    }
+                }
+
+                let default_worktree_root_dir =
+                    default_worktree_root_dir_for_repo(repo.id.as_str());
+                match update_repository_config(
+                    Path::new(&repo.path),
+                    &RepositoryConfigUpdate {
+                        mcp_servers: Some(servers),
+                        ..Default::default()
+                    },
+                    &default_worktree_root_dir,
+                ) {
+                    Ok(config) => Response::success(
+                        &req.id,
+                        serde_json::json!({ "servers": mcp_servers_json(&config.mcp_servers) }),
+                    ),
+                    Err(e) => Response::error(
+                        &req.id,
+                        error_codes::INTERNAL_ERROR,
+                        &format!("Failed to update repository config: {}", e),
+                    ),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_repository_env_list(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::RepositoryEnvList, move |req| {
+            let armin = state.armin.clone();
+            async move {
+                let (_, repo_config) = match load_repository_and_config(&armin, req.params.as_ref())
+                {
+                    Ok(loaded) => loaded,
+                    Err((code, msg)) => return Response::error(&req.id, code, &msg),
+                };
+                Response::success(
+                    &req.id,
+                    serde_json::json!({ "profiles": profiles_json(&repo_config.env_profiles) }),
+                )
+            }
+        })
+        .await;
+}
+
+async fn register_repository_env_set(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::RepositoryEnvSet, move |req| {
+            let state = state.clone();
+            async move {
+                let (repo, repo_config) =
+                    match load_repository_and_config(&state.armin, req.params.as_ref()) {
+                        Ok(loaded) => loaded,
+                        Err((code, msg)) => return Response::error(&req.id, code, &msg),
+                    };
+                let params = req
+                    .params
+                    .as_ref()
+                    .expect("repository id was parsed from params");
+                let set = match parse_env_set(params) {
+                    Ok(set) => set,
+                    Err(msg) => return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg),
+                };
+
+                let mut profiles = repo_config.env_profiles;
+                let profile = profiles.entry(set.profile.clone()).or_default();
+                let stored = set_var(
+                    &state.secrets.lock().unwrap(),
+                    repo.id.as_str(),
+                    &set.profile,
+                    profile,
+                    &set.name,
+                    &set.value,
+                    set.secret,
+                );
+                if let Err(msg) = stored {
+                    return Response::error(&req.id, error_codes::INTERNAL_ERROR, &msg);
+                }
+                write_env_profiles(&req.id, &repo, profiles)
+            }
+        })
+        .await;
+}
+
+async fn register_repository_env_delete(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::RepositoryEnvDelete, move |req| {
+            let state = state.clone();
+            async move {
+                let (repo, repo_config) =
+                    match load_repository_and_config(&state.armin, req.params.as_ref()) {
+                        Ok(loaded) => loaded,
+                        Err((code, msg)) => return Response::error(&req.id, code, &msg),
+                    };
+                let params = req
+                    .params
+                    .as_ref()
+                    .expect("repository id was parsed from params");
+                let (profile_name, name) = match parse_env_delete(params) {
+                    Ok(delete) => delete,
+                    Err(msg) => return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg),
+                };
+
+                let mut profiles = repo_config.env_profiles;
+                let Some(profile) = profiles.get_mut(&profile_name) else {
+                    return Response::error(
+                        &req.id,
+                        error_codes::NOT_FOUND,
+                        &format!("Environment profile \"{profile_name}\" not found"),
+                    );
+                };
+                let secrets = state.secrets.lock().unwrap();
+                let deleted = match &name {
+                    Some(name) => {
+                        delete_var(&secrets, repo.id.as_str(), &profile_name, profile, name)
+                    }
+                    None => {
+                        delete_profile_secrets(&secrets, repo.id.as_str(), &profile_name, profile)
+                            .map(|()| profiles.remove(&profile_name).is_some())
+                    }
+                };
+                drop(secrets);
+                match deleted {
+                    Ok(true) => write_env_profiles(&req.id, &repo, profiles),
+                    Ok(false) => Response::error(
+                        &req.id,
+                        error_codes::NOT_FOUND,
+                        &format!(
+                            "Variable \"{}\" not found in environment profile \"{profile_name}\"",
+                            name.unwrap_or_default()
+                        ),
+                    ),
+                    Err(msg) => Response::error(&req.id, error_codes::INTERNAL_ERROR, &msg),
+                }
+            }
+        })
+        .await;
+}
+
+/// Save a repository's environment profiles and respond with them.
+fn write_env_profiles(
+    request_id: &str,
+    repo: &Repository,
+    profiles: BTreeMap<String, EnvProfileConfig>,
+) -> Response {
+    let default_worktree_root_dir = default_worktree_root_dir_for_repo(repo.id.as_str());
+    match update_repository_config(
+        Path::new(&repo.path),
+        &RepositoryConfigUpdate {
+            env_profiles: Some(profiles),
+            ..Default::default()
+        },
+        &default_worktree_root_dir,
+    ) {
+        Ok(config) => Response::success(
+            request_id,
+            serde_json::json!({ "profiles": profiles_json(&config.env_profiles) }),
+        ),
+        Err(e) => Response::error(
+            request_id,
+            error_codes::INTERNAL_ERROR,
+            &format!("Failed to update repository config: {}", e),
+        ),
+    }
+}
+
+/// A validated `repository.env_set` request.
+#[derive(PartialEq)]
+struct EnvSet {
+    profile: String,
+    name: String,
+    value: String,
+    secret: bool,
+}
+
+// Hand-written so a secret value never ends up in a log line
+impl std::fmt::Debug for EnvSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvSet")
+            .field("profile", &self.profile)
+            .field("name", &self.name)
+            .field("secret", &self.secret)
+            .finish_non_exhaustive()
+    }
+}
+
+fn parse_env_set(params: &serde_json::Value) -> Result<EnvSet, String> {
+    let (profile, name) = parse_env_names(params)?;
+    let name = name.ok_or_else(|| "name is required".to_string())?;
+    let value = params
+        .get("value")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "value must be a string".to_string())?
+        .to_string();
+    let secret = match params.get("secret") {
+        None | Some(serde_json::Value::Null) => false,
+        Some(secret) => secret
+            .as_bool()
+            .ok_or_else(|| "secret must be a boolean".to_string())?,
+    };
+    Ok(EnvSet {
+        profile,
+        name,
+        value,
+        secret,
+    })
+}
+
+/// The profile and, if given, the variable a `repository.env_delete`
+/// request removes.
+fn parse_env_delete(params: &serde_json::Value) -> Result<(String, Option<String>), String> {
+    parse_env_names(params)
+}
+
+fn parse_env_names(params: &serde_json::Value) -> Result<(String, Option<String>), String> {
+    let profile = params
+        .get("profile")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "profile is required".to_string())?
+        .trim()
+        .to_string();
+    validate_profile_name(&profile)?;
+    let name = match params.get("name") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(name) => {
+            let name = name
+                .as_str()
+                .ok_or_else(|| "name must be a string".to_string())?
+                .trim()
+                .to_string();
+            validate_var_name(&name)?;
+            Some(name)
+        }
+    };
+    Ok((profile, name))
+}
+
+/// Look up the repository named by the request and load its config.
+fn load_repository_and_config(
+    armin: &DaemonArmin,
+    params: Option<&serde_json::Value>,
+) -> Result<(Repository, RepositoryConfig), (i32, String)> {
+    let repository_id =
+        parse_repository_id(params).map_err(|msg| (error_codes::INVALID_PARAMS, msg))?;
+    let repo = match armin.get_repository(&RepositoryId::from_string(repository_id)) {
+        Ok(Some(repo)) => repo,
+        Ok(None) => {
+            return Err((error_codes::NOT_FOUND, "Repository not found".to_string()));
+        }
+        Err(e) => {
+            return Err((
+                error_codes::INTERNAL_ERROR,
+                format!("Failed to get repository: {}", e),
+            ));
+        }
+    };
+    let default_worktree_root_dir = default_worktree_root_dir_for_repo(repo.id.as_str());
+    let config = load_repository_config(Path::new(&repo.path), &default_worktree_root_dir)
+        .map_err(|e| {
+            (
+                error_codes::INTERNAL_ERROR,
+                format!("Failed to load repository config: {}", e),
+            )
+        })?;
+    Ok((repo, config))
+}
+
+/// A validated `repository.mcp_update` request.
+#[derive(Debug, PartialEq)]
+struct McpUpdate {
+    name: String,
+    /// The new entry, or None to remove the server.
+    server: Option<McpServerConfig>,
+    /// Probe `server` instead of saving it.
+    dry_run: bool,
+}
+
+fn parse_mcp_update(params: &serde_json::Value) -> Result<McpUpdate, String> {
+    let name = params
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "name is required".to_string())?
+        .trim()
+        .to_string();
+    let dry_run = params
+        .get("dry_run")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let server = match params.get("server") {
+        None => return Err("server is required; pass null to remove the server".to_string()),
+        Some(serde_json::Value::Null) if dry_run => {
+            return Err("dry_run needs a server to test".to_string());
+        }
+        Some(serde_json::Value::Null) => None,
+        Some(server) => Some(parse_mcp_server(server)?),
+    };
+    if let Some(server) = &server {
+        validate_mcp_server(&name, server)?;
+    }
+    Ok(McpUpdate {
+        name,
+        server,
+        dry_run,
+    })
+}
+
+fn parse_mcp_server(value: &serde_json::Value) -> Result<McpServerConfig, String> {
+    let Some(server) = value.as_object() else {
+        return Err("server must be an object".to_string());
+    };
+    let command = server
+        .get("command")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "server.command must be a string".to_string())?
+        .to_string();
+    let args = parse_optional_string_list_param(value, "args")
+        .map_err(|msg| format!("server.{msg}"))?
+        .unwrap_or_default();
+    let env = match server.get("env") {
+        None | Some(serde_json::Value::Null) => BTreeMap::new(),
+        Some(env) => env
+            .as_object()
+            .and_then(|env| {
+                env.iter()
+                    .map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                    .collect::<Option<BTreeMap<_, _>>>()
+            })
+            .ok_or_else(|| "server.env must be an object of strings".to_string())?,
+    };
+    let enabled = match server.get("enabled") {
+        None | Some(serde_json::Value::Null) => true,
+        Some(enabled) => enabled
+            .as_bool()
+            .ok_or_else(|| "server.enabled must be a boolean".to_string())?,
+    };
+    Ok(McpServerConfig {
+        command,
+        args,
+        env,
+        enabled,
+    })
+}
+
+fn mcp_servers_json(servers: &BTreeMap<String, McpServerConfig>) -> serde_json::Value {
+    servers
+        .iter()
+        .map(|(name, server)| {
+            serde_json::json!({
+                "name": name,
+                "command": server.command,
+                "args": server.args,
+                "env": server.env,
+                "enabled": server.enabled,
+            })
+        })
+        .collect()
+}
+
+fn mcp_probe_json(name: &str, probe: Result<McpProbe, String>) -> serde_json::Value {
+    match probe {
+        Ok(probe) => serde_json::json!({
+            "name": name,
+            "dry_run": true,
+            "ok": true,
+            "protocol_version": probe.protocol_version,
+            "server_name": probe.server_name,
+            "server_version": probe.server_version,
+            "tools": probe.tools,
+        }),
+        Err(error) => serde_json::json!({
+            "name": name,
+            "dry_run": true,
+            "ok": false,
+            "error": error,
+        }),
+    }
+}
+
 async fn resolve_session_root(
     state: &DaemonState,
     session_id: &str,
@@ -1056,6 +1562,82 @@ fn parse_optional_u64_param(params: &serde_json::Value, key: &str) -> Result<Opt
         .ok_or_else(|| format!("{key} must be an unsigned integer"))
 }
 
+/// Parse an optional time limit in seconds; `null` (no limit) becomes
+/// `Some(None)`.
+fn parse_optional_limit_seconds_param(
+    params: &serde_json::Value,
+    key: &str,
+) -> Result<Option<Option<u64>>, String> {
+    match params.get(key) {
+        None => Ok(None),
+        Some(serde_json::Value::Null) => Ok(Some(None)),
+        Some(value) => value
+            .as_u64()
+            .filter(|seconds| *seconds > 0)
+            .map(|seconds| Some(Some(seconds)))
+            .ok_or_else(|| format!("{key} must be a positive integer or null")),
+    }
+}
+
+/// Parse an optional sandbox policy, replacing the repository's whole
+/// `sandbox` config; `null` disables the sandbox.
+fn parse_optional_sandbox_param(
+    params: &serde_json::Value,
+    key: &str,
+) -> Result<Option<SandboxConfig>, String> {
+    let Some(value) = params.get(key) else {
+        return Ok(None);
+    };
+    if value.is_null() {
+        return Ok(Some(SandboxConfig::default()));
+    }
+    if !value.is_object() {
+        return Err(format!("{key} must be an object or null"));
+    }
+    let flag = |name: &str| match value.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(false),
+        Some(flag) => flag
+            .as_bool()
+            .ok_or_else(|| format!("{key}.{name} must be a boolean")),
+    };
+    let limit = |name: &str| {
+        parse_optional_limit_seconds_param(value, name)
+            .map(Option::flatten)
+            .map_err(|_| format!("{key}.{name} must be a positive integer or null"))
+    };
+    Ok(Some(SandboxConfig {
+        enabled: flag("enabled")?,
+        writable_paths: parse_optional_string_list_param(value, "writable_paths")
+            .map_err(|msg| format!("{key}.{msg}"))?
+            .unwrap_or_default(),
+        network_disabled: flag("network_disabled")?,
+        max_memory_mb: limit("max_memory_mb")?,
+        max_processes: limit("max_processes")?,
+    }))
+}
+
+fn parse_optional_string_list_param(
+    params: &serde_json::Value,
+    key: &str,
+) -> Result<Option<Vec<String>>, String> {
+    let Some(value) = params.get(key) else {
+        return Ok(None);
+    };
+    if value.is_null() {
+        return Ok(None);
+    }
+    value
+        .as_array()
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()
+        })
+        .map(Some)
+        .ok_or_else(|| format!("{key} must be an array of strings"))
+}
+
 fn repository_json(repo: &agent_session_sqlite_persist_core::Repository) -> serde_json::Value {
     serde_json::json!({
         "id": repo.id.as_str(),
@@ -1088,6 +1670,17 @@ fn repository_config_json(config: &RepositoryConfig) -> serde_json::Value {
                 "timeout_seconds": config.setup_hooks.post_create.timeout_seconds,
             },
         },
+        "permissions": {
+            "always_allow_tools": config.permissions.always_allow_tools,
+            "default_mode": config.permissions.default_mode,
+        },
+        "agent_timeouts": {
+            "max_turn_seconds": config.agent_timeouts.max_turn_seconds,
+            "max_idle_seconds": config.agent_timeouts.max_idle_seconds,
+        },
+        "sandbox": config.sandbox,
+        "env_profiles": profiles_json(&config.env_profiles),
+        "shell": config.shell,
     })
 }
 
@@ -1099,6 +1692,14 @@ fn rollback_update_from_config(previous: &RepositoryConfig) -> RepositoryConfigU
         pre_create_timeout_seconds: Some(previous.setup_hooks.pre_create.timeout_seconds),
         post_create_command: Some(previous.setup_hooks.post_create.command.clone()),
         post_create_timeout_seconds: Some(previous.setup_hooks.post_create.timeout_seconds),
+        always_allow_tools: Some(previous.permissions.always_allow_tools.clone()),
+        default_permission_mode: Some(previous.permissions.default_mode),
+        mcp_servers: Some(previous.mcp_servers.clone()),
+        max_turn_seconds: Some(previous.agent_timeouts.max_turn_seconds),
+        max_idle_seconds: Some(previous.agent_timeouts.max_idle_seconds),
+        sandbox: Some(previous.sandbox.clone()),
+        env_profiles: Some(previous.env_profiles.clone()),
+        shell: Some(previous.shell.clone()),
     }
 }
 
@@ -1166,6 +1767,102 @@ fn map_safe_repo_dir_lister_error(err: SafeRepoDirListerError) -> (i32, String)
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_mcp_update_reads_server_and_defaults() {
+        let update = parse_mcp_update(&serde_json::json!({
+            "name": "linear",
+            "server": { "command": "npx", "args": ["linear-mcp"], "env": { "TOKEN": "x" } }
+        }))
+        .unwrap();
+
+        assert_eq!(update.name, "linear");
+        assert!(!update.dry_run);
+        let server = update.server.unwrap();
+        assert_eq!(server.command, "npx");
+        assert_eq!(server.args, vec!["linear-mcp".to_string()]);
+        assert_eq!(server.env.get("TOKEN").map(String::as_str), Some("x"));
+        assert!(server.enabled);
+    }
+
+    #[test]
+    fn parse_mcp_update_null_server_removes() {
+        let update =
+            parse_mcp_update(&serde_json::json!({ "name": "linear", "server": null })).unwrap();
+        assert_eq!(update.server, None);
+
+        let err = parse_mcp_update(&serde_json::json!({
+            "name": "linear",
+            "server": null,
+            "dry_run": true
+        }))
+        .unwrap_err();
+        assert!(err.contains("dry_run"));
+    }
+
+    #[test]
+    fn parse_mcp_update_rejects_invalid_servers() {
+        for params in [
+            serde_json::json!({ "server": { "command": "npx" } }),
+            serde_json::json!({ "name": "linear" }),
+            serde_json::json!({ "name": "linear", "server": { "args": [] } }),
+            serde_json::json!({ "name": "linear", "server": { "command": "npx", "env": { "K": 1 } } }),
+            serde_json::json!({ "name": "linear", "server": { "command": "npx", "enabled": "yes" } }),
+            serde_json::json!({ "name": "bad name", "server": { "command": "npx" } }),
+            serde_json::json!({ "name": "linear", "server": { "command": "  " } }),
+        ] {
+            assert!(parse_mcp_update(&params).is_err(), "{params}");
+        }
+    }
+
+    #[test]
+    fn parse_env_set_reads_variable_and_redacts_value() {
+        let set = parse_env_set(&serde_json::json!({
+            "profile": "staging",
+            "name": "API_TOKEN",
+            "value": "tok-123",
+            "secret": true,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            set,
+            EnvSet {
+                profile: "staging".to_string(),
+                name: "API_TOKEN".to_string(),
+                value: "tok-123".to_string(),
+                secret: true,
+            }
+        );
+        assert!(!format!("{set:?}").contains("tok-123"));
+        assert!(
+            !parse_env_set(&serde_json::json!({ "profile": "p", "name": "A", "value": "v" }))
+                .unwrap()
+                .secret
+        );
+    }
+
+    #[test]
+    fn parse_env_requests_reject_invalid_names() {
+        assert_eq!(
+            parse_env_delete(&serde_json::json!({ "profile": "staging" })).unwrap(),
+            ("staging".to_string(), None)
+        );
+        assert!(parse_env_delete(&serde_json::json!({ "profile": "a b" })).is_err());
+        assert!(
+            parse_env_set(&serde_json::json!({ "profile": "p", "name": "1A", "value": "v" }))
+                .is_err()
+        );
+        assert_eq!(
+            parse_env_set(&serde_json::json!({ "profile": "p", "value": "v" })).unwrap_err(),
+            "name is required"
+        );
+        assert_eq!(
+            parse_env_set(&serde_json::json!({ "profile": "p", "name": "A", "value": 1 }))
+                .unwrap_err(),
+            "value must be a string"
+        );
+    }
+
     #[test]
     fn parse_expected_revision_accepts_valid_payload() {
         let params = serde_json::json!({
@@ -1240,6 +1937,16 @@ mod tests {
         assert!(err.contains("timeout must be an unsigned integer"));
     }
 
+    #[test]
+    fn parse_optional_string_list_rejects_non_string_items() {
+        let params = serde_json::json!({
+            "always_allow_tools": ["Bash", 3]
+        });
+        let err = parse_optional_string_list_param(&params, "always_allow_tools")
+            .expect_err("should fail");
+        assert!(err.contains("always_allow_tools must be an array of strings"));
+    }
+
     #[test]
     fn rollback_update_from_config_roundtrip_values() {
         let previous = RepositoryConfig {
@@ -1258,6 +1965,21 @@ mod tests {
                     timeout_seconds: 222,
                 },
             },
+            permissions: crate::utils::repository_config::PermissionsConfig {
+                always_allow_tools: vec!["Bash".to_string()],
+                default_mode: Some(claude_process_manager::PermissionMode::Plan),
+            },
+            mcp_servers: Default::default(),
+            agent_timeouts: crate::utils::repository_config::AgentTimeoutsConfig {
+                max_turn_seconds: Some(1800),
+                max_idle_seconds: None,
+            },
+            sandbox: SandboxConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            env_profiles: Default::default(),
+            shell: Some("/bin/bash".to_string()),
         };
 
         let rollback = rollback_update_from_config(&previous);
@@ -1279,5 +2001,76 @@ mod tests {
             Some(Some("echo post".to_string()))
         );
         assert_eq!(rollback.post_create_timeout_seconds, Some(222));
+        assert_eq!(rollback.always_allow_tools, Some(vec!["Bash".to_string()]));
+        assert_eq!(
+            rollback.default_permission_mode,
+            Some(Some(claude_process_manager::PermissionMode::Plan))
+        );
+        assert_eq!(rollback.max_turn_seconds, Some(Some(1800)));
+        assert_eq!(rollback.max_idle_seconds, Some(None));
+        assert_eq!(rollback.sandbox, Some(previous.sandbox.clone()));
+        assert_eq!(rollback.shell, Some(Some("/bin/bash".to_string())));
+    }
+
+    #[test]
+    fn parse_optional_sandbox_param_reads_policy_and_clears() {
+        let params = serde_json::json!({
+            "sandbox": {
+                "enabled": true,
+                "writable_paths": ["~/.cache"],
+                "network_disabled": true,
+                "max_memory_mb": 2048,
+            },
+        });
+        assert_eq!(
+            parse_optional_sandbox_param(&params, "sandbox").unwrap(),
+            Some(SandboxConfig {
+                enabled: true,
+                writable_paths: vec!["~/.cache".to_string()],
+                network_disabled: true,
+                max_memory_mb: Some(2048),
+                max_processes: None,
+            })
+        );
+        assert_eq!(
+            parse_optional_sandbox_param(&serde_json::json!({ "sandbox": null }), "sandbox")
+                .unwrap(),
+            Some(SandboxConfig::default())
+        );
+        assert_eq!(
+            parse_optional_sandbox_param(&serde_json::json!({}), "sandbox").unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_optional_sandbox_param(
+                &serde_json::json!({ "sandbox": { "max_processes": 0 } }),
+                "sandbox"
+            )
+            .unwrap_err(),
+            "sandbox.max_processes must be a positive integer or null"
+        );
+    }
+
+    #[test]
+    fn parse_optional_limit_seconds_param_sets_and_clears() {
+        let params = serde_json::json!({ "max_turn_seconds": 600, "max_idle_seconds": null });
+        assert_eq!(
+            parse_optional_limit_seconds_param(&params, "max_turn_seconds").unwrap(),
+            Some(Some(600))
+        );
+        assert_eq!(
+            parse_optional_limit_seconds_param(&params, "max_idle_seconds").unwrap(),
+            Some(None)
+        );
+        assert_eq!(
+            parse_optional_limit_seconds_param(&params, "missing").unwrap(),
+            None
+        );
+        let err = parse_optional_limit_seconds_param(
+            &serde_json::json!({ "max_turn_seconds": 0 }),
+            "max_turn_seconds",
+        )
+        .unwrap_err();
+        assert!(err.contains("positive integer"));
     }
 }
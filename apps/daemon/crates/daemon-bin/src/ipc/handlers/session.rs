@@ -1,27 +1,41 @@
 //! Session handlers.
 
+use crate::app::agent_provider::ProviderRegistry;
 use crate::app::{resolve_machine_space_scope, DaemonState};
 use crate::armin_adapter::DaemonArmin;
+use crate::ipc::handlers::claude::parse_optional_permission_mode;
+use crate::machines::terminal::resolve_shell;
 use crate::observability::spawn_in_current_span;
 use crate::utils::repository_config::{
-    default_worktree_root_dir_for_repo, load_repository_config, SetupHookStageConfig,
+    default_worktree_root_dir_for_repo, load_repository_config, RepositoryConfig,
+    SetupHookStageConfig,
+};
+use crate::utils::session_bundle::{
+    read_session_bundle, write_session_bundle, BundleMessage, SessionBundle, SessionBundleError,
+    SessionBundleManifest, SESSION_BUNDLE_SCHEMA_VERSION,
 };
 use crate::utils::SessionSecretCache;
 use agent_session_sqlite_persist_core::{
-    NewSession, RepositoryId, SessionId, SessionReader, SessionUpdate, SessionWriter,
+    ArminError, CodingSessionStatus, ImportedMessage, MessageSearchHit, MessageSearchQuery,
+    NewSession, RepositoryId, RuntimeStatusEnvelope, SessionForkOptions, SessionHistory, SessionId,
+    SessionReader, SessionUpdate, SessionWriter, DEFAULT_MESSAGE_SEARCH_LIMIT,
 };
+use chrono::{DateTime, Utc};
+use daemon_config_and_utils::Config;
 use daemon_ipc::{error_codes, IpcServer, Method, Response};
 use daemon_storage::SecretsManager;
-use git_ops::{create_worktree_with_options, list_worktrees, remove_worktree};
-use std::path::Path;
+use git_ops::{create_worktree_with_options, get_log, list_worktrees, remove_worktree};
+use login_shell::Shell;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::{Arc, Mutex};
 use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use tokio::task;
 use tokio::time::{timeout, Duration};
 use tracing::{debug, info_span, warn};
 
 const MAX_HOOK_STDERR_CHARS: usize = 1200;
+const MAX_SEARCH_LIMIT: usize = 200;
 
 #[derive(Debug, Clone)]
 pub struct SessionCreateCoreError {
@@ -126,11 +140,73 @@ fn session_json(session: &agent_session_sqlite_persist_core::Session) -> serde_j
         "status": session.status.as_str(),
         "is_worktree": session.is_worktree,
         "worktree_path": session.worktree_path,
+        "parent_session_id": session.parent_session_id.as_ref().map(|id| id.as_str()),
+        "forked_from_sequence": session.forked_from_sequence,
+        "permission_mode": session.permission_mode,
         "created_at": session.created_at.to_rfc3339(),
         "last_accessed_at": session.last_accessed_at.to_rfc3339(),
     })
 }
 
+fn search_hit_json(hit: &MessageSearchHit) -> serde_json::Value {
+    serde_json::json!({
+        "session_id": hit.session_id.as_str(),
+        "session_title": hit.session_title,
+        "repository_id": hit.repository_id.as_str(),
+        "message_id": hit.message_id.as_str(),
+        "sequence_number": hit.sequence_number,
+        "snippet": hit.snippet,
+        "rank": hit.rank,
+        "created_at": hit.created_at.to_rfc3339(),
+    })
+}
+
+fn parse_search_datetime(
+    params: &serde_json::Value,
+    key: &str,
+) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(value) = params.get(key).and_then(|value| value.as_str()) else {
+        return Ok(None);
+    };
+    DateTime::parse_from_rfc3339(value)
+        .map(|parsed| Some(parsed.with_timezone(&Utc)))
+        .map_err(|_| format!("{key} must be an RFC3339 timestamp"))
+}
+
+fn search_query_from_params(params: &serde_json::Value) -> Result<MessageSearchQuery, String> {
+    let text = params
+        .get("query")
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| "query is required".to_string())?;
+
+    let mut query = MessageSearchQuery::new(text);
+    query.repository_id =
+        normalize_optional_string(params.get("repository_id").and_then(|value| value.as_str()))
+            .map(|id| RepositoryId::from_string(id.to_lowercase()));
+    query.since = parse_search_datetime(params, "since")?;
+    query.until = parse_search_datetime(params, "until")?;
+    query.limit = params
+        .get("limit")
+        .and_then(|value| value.as_u64())
+        .map_or(DEFAULT_MESSAGE_SEARCH_LIMIT, |limit| {
+            (limit as usize).clamp(1, MAX_SEARCH_LIMIT)
+        });
+    query.offset = params
+        .get("offset")
+        .and_then(|value| value.as_u64())
+        .unwrap_or(0) as usize;
+
+    if let (Some(since), Some(until)) = (query.since, query.until) {
+        if since > until {
+            return Err("since must not be after until".to_string());
+        }
+    }
+
+    Ok(query)
+}
+
 fn is_legacy_worktree_root(root_dir: &str) -> bool {
     let trimmed = root_dir.trim();
     if trimmed.is_empty() {
@@ -222,6 +298,7 @@ fn truncate_for_error(text: &str, max_chars: usize) -> String {
 async fn run_setup_hook(
     stage: HookStage,
     hook: &SetupHookStageConfig,
+    shell: &Shell,
     cwd: &Path,
 ) -> Result<(), SessionCreateCoreError> {
     let Some(command) = normalize_optional_string(hook.command.as_deref()) else {
@@ -231,10 +308,8 @@ async fn run_setup_hook(
     let timeout_seconds = hook.timeout_seconds.max(1);
     let timeout_duration = Duration::from_secs(timeout_seconds);
 
-    let mut process = Command::new("/bin/zsh");
+    let mut process = shell.invocation(&command).await.command();
     process
-        .arg("-lc")
-        .arg(&command)
         .current_dir(cwd)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
@@ -396,7 +471,11 @@ pub async fn register(server: &IpcServer, state: DaemonState) {
     register_session_create(server, state.clone()).await;
     register_session_get(server, state.clone()).await;
     register_session_update(server, state.clone()).await;
-    register_session_delete(server, state).await;
+    register_session_delete(server, state.clone()).await;
+    register_session_search(server, state.clone()).await;
+    register_session_fork(server, state.clone()).await;
+    register_session_export(server, state.clone()).await;
+    register_session_import(server, state).await;
 }
 
 async fn register_session_list(server: &IpcServer, state: DaemonState) {
@@ -444,6 +523,87 @@ async fn register_session_list(server: &IpcServer, state: DaemonState) {
         .await;
 }
 
+/// Creates a session worktree under the repository's configured root, running
+/// the pre- and post-create setup hooks around it.
+///
+/// The worktree is removed again if the post-create hook fails.
+async fn create_session_worktree(
+    repo_path: &Path,
+    repo_config: &RepositoryConfig,
+    config: &Config,
+    default_worktree_root_dir: &str,
+    wt_name: &str,
+    base_branch: Option<&str>,
+    worktree_branch: Option<&str>,
+) -> Result<String, SessionCreateCoreError> {
+    let root_dir = if repo_config.worktree.root_dir.trim().is_empty() {
+        default_worktree_root_dir.to_string()
+    } else {
+        repo_config.worktree.root_dir.clone()
+    };
+
+    if is_legacy_worktree_root(&root_dir) {
+        return Err(SessionCreateCoreError::with_data(
+            "legacy_worktree_unsupported",
+            "legacy worktree root '.unbound-worktrees' is not supported; use '~/.unbound/<repo_id>/worktrees'",
+            serde_json::json!({
+                "configured_root_dir": root_dir,
+                "supported_root_dir": default_worktree_root_dir,
+            }),
+        ));
+    }
+
+    let shell = resolve_shell(config, Some(repo_config));
+    run_setup_hook(
+        HookStage::PreCreate,
+        &repo_config.setup_hooks.pre_create,
+        &shell,
+        repo_path,
+    )
+    .await?;
+
+    let created_worktree_path = match create_worktree_with_options(
+        repo_path,
+        wt_name,
+        Path::new(&root_dir),
+        base_branch,
+        worktree_branch,
+    ) {
+        Ok(path) => path,
+        Err(e) => {
+            return Err(SessionCreateCoreError::new(
+                "internal_error",
+                format!("Failed to create worktree: {}", e),
+            ));
+        }
+    };
+
+    if let Err(mut hook_error) = run_setup_hook(
+        HookStage::PostCreate,
+        &repo_config.setup_hooks.post_create,
+        &shell,
+        Path::new(&created_worktree_path),
+    )
+    .await
+    {
+        if let Err(cleanup_error) = remove_worktree(repo_path, Path::new(&created_worktree_path)) {
+            let cleanup_summary = truncate_for_error(&cleanup_error, MAX_HOOK_STDERR_CHARS);
+            if let Some(data) = hook_error.data.as_mut() {
+                data["cleanup_error"] = serde_json::json!(cleanup_summary);
+            } else {
+                hook_error.data = Some(serde_json::json!({
+                    "cleanup_error": cleanup_summary,
+                }));
+            }
+            hook_error.message =
+                format!("{}; cleanup failed: {}", hook_error.message, cleanup_error);
+        }
+        return Err(hook_error);
+    }
+
+    Ok(created_worktree_path)
+}
+
 /// Core session creation logic shared by IPC and remote command paths.
 /// Takes params as a serde_json::Value and returns the result or an error.
 pub async fn create_session_core(
@@ -467,6 +627,8 @@ pub async fn create_session_core(
             })?;
     create_session_core_with_services(
         state.armin.as_ref(),
+        &state.config,
+        &state.providers,
         &state.db_encryption_key,
         &state.session_secret_cache,
         machine_id,
@@ -476,8 +638,11 @@ pub async fn create_session_core(
     .await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_session_core_with_services(
     armin: &DaemonArmin,
+    config: &Config,
+    providers: &ProviderRegistry,
     db_encryption_key: &Arc<Mutex<Option<[u8; 32]>>>,
     session_secret_cache: &SessionSecretCache,
     machine_id: Option<String>,
@@ -526,12 +691,9 @@ pub async fn create_session_core_with_services(
             .map_err(|msg| SessionCreateCoreError::new("invalid_params", msg))?;
     }
     if let Some(provider) = provider.as_deref() {
-        if provider != "claude" && provider != "codex" {
-            return Err(SessionCreateCoreError::new(
-                "invalid_params",
-                "provider must be either \"claude\" or \"codex\" when provided",
-            ));
-        }
+        providers
+            .resolve(provider)
+            .map_err(|message| SessionCreateCoreError::new("invalid_params", message))?;
     }
     if existing_worktree_path.is_some() && !is_worktree {
         return Err(SessionCreateCoreError::new(
@@ -541,7 +703,6 @@ pub async fn create_session_core_with_services(
     }
 
     let session_id = SessionId::new();
-    let session_secret = SecretsManager::generate_session_secret();
     let mut worktree_cleanup_context: Option<(String, String)> = None;
 
     let worktree_path = if is_worktree {
@@ -584,69 +745,16 @@ pub async fn create_session_core_with_services(
                 repo.default_branch.clone(),
             );
             let wt_name = worktree_name.as_deref().unwrap_or(session_id.as_str());
-            let root_dir = if repo_config.worktree.root_dir.trim().is_empty() {
-                default_worktree_root_dir.clone()
-            } else {
-                repo_config.worktree.root_dir.clone()
-            };
-
-            if is_legacy_worktree_root(&root_dir) {
-                return Err(SessionCreateCoreError::with_data(
-                    "legacy_worktree_unsupported",
-                    "legacy worktree root '.unbound-worktrees' is not supported; use '~/.unbound/<repo_id>/worktrees'",
-                    serde_json::json!({
-                        "configured_root_dir": root_dir,
-                        "supported_root_dir": default_worktree_root_dir,
-                    }),
-                ));
-            }
-
-            run_setup_hook(
-                HookStage::PreCreate,
-                &repo_config.setup_hooks.pre_create,
-                repo_path,
-            )
-            .await?;
-
-            let created_worktree_path = match create_worktree_with_options(
+            let created_worktree_path = create_session_worktree(
                 repo_path,
+                &repo_config,
+                config,
+                &default_worktree_root_dir,
                 wt_name,
-                Path::new(&root_dir),
                 effective_base_branch.as_deref(),
                 requested_worktree_branch.as_deref(),
-            ) {
-                Ok(path) => path,
-                Err(e) => {
-                    return Err(SessionCreateCoreError::new(
-                        "internal_error",
-                        format!("Failed to create worktree: {}", e),
-                    ));
-                }
-            };
-
-            if let Err(mut hook_error) = run_setup_hook(
-                HookStage::PostCreate,
-                &repo_config.setup_hooks.post_create,
-                Path::new(&created_worktree_path),
             )
-            .await
-            {
-                if let Err(cleanup_error) =
-                    remove_worktree(repo_path, Path::new(&created_worktree_path))
-                {
-                    let cleanup_summary = truncate_for_error(&cleanup_error, MAX_HOOK_STDERR_CHARS);
-                    if let Some(data) = hook_error.data.as_mut() {
-                        data["cleanup_error"] = serde_json::json!(cleanup_summary);
-                    } else {
-                        hook_error.data = Some(serde_json::json!({
-                            "cleanup_error": cleanup_summary,
-                        }));
-                    }
-                    hook_error.message =
-                        format!("{}; cleanup failed: {}", hook_error.message, cleanup_error);
-                }
-                return Err(hook_error);
-            }
+            .await?;
 
             worktree_cleanup_context = Some((repo.path.clone(), created_worktree_path.clone()));
             Some(created_worktree_path)
@@ -701,6 +809,31 @@ pub async fn create_session_core_with_services(
         }
     };
 
+    persist_new_session_secret(
+        armin,
+        db_encryption_key,
+        session_secret_cache,
+        &created_session.id,
+        &mut worktree_cleanup_context,
+    )?;
+
+    let session_data = session_json(&created_session);
+
+    Ok(session_data)
+}
+
+/// Generates, encrypts, and stores the secret for a newly created session.
+///
+/// On failure the session and any worktree in `worktree_cleanup_context` are rolled back.
+fn persist_new_session_secret(
+    armin: &DaemonArmin,
+    db_encryption_key: &Arc<Mutex<Option<[u8; 32]>>>,
+    session_secret_cache: &SessionSecretCache,
+    session_id: &SessionId,
+    worktree_cleanup_context: &mut Option<(String, String)>,
+) -> Result<(), SessionCreateCoreError> {
+    let session_secret = SecretsManager::generate_session_secret();
+
     // Source of truth: persist the session secret to Armin/SQLite.
     let db_key = match *db_encryption_key.lock().unwrap() {
         Some(db_key) => db_key,
@@ -708,8 +841,8 @@ pub async fn create_session_core_with_services(
             return Err(rollback_session_creation_after_secret_failure(
                 armin,
                 session_secret_cache,
-                &created_session.id,
-                &mut worktree_cleanup_context,
+                session_id,
+                worktree_cleanup_context,
                 "Database encryption key is unavailable; cannot persist session secret".to_string(),
             ));
         }
@@ -723,15 +856,15 @@ pub async fn create_session_core_with_services(
                 return Err(rollback_session_creation_after_secret_failure(
                     armin,
                     session_secret_cache,
-                    &created_session.id,
-                    &mut worktree_cleanup_context,
+                    session_id,
+                    worktree_cleanup_context,
                     format!("Failed to encrypt session secret: {}", err),
                 ));
             }
         };
 
     debug!(
-        session_id = %created_session.id.as_str(),
+        session_id = %session_id.as_str(),
         nonce_len = nonce.len(),
         encrypted_len = encrypted_secret.len(),
         plaintext_len = session_secret.len(),
@@ -739,7 +872,7 @@ pub async fn create_session_core_with_services(
     );
 
     let new_secret = agent_session_sqlite_persist_core::NewSessionSecret {
-        session_id: created_session.id.clone(),
+        session_id: session_id.clone(),
         encrypted_secret,
         nonce: nonce.to_vec(),
     };
@@ -747,24 +880,22 @@ pub async fn create_session_core_with_services(
         return Err(rollback_session_creation_after_secret_failure(
             armin,
             session_secret_cache,
-            &created_session.id,
-            &mut worktree_cleanup_context,
+            session_id,
+            worktree_cleanup_context,
             format!("Failed to store session secret: {}", err),
         ));
     }
 
     debug!(
-        session_id = %created_session.id.as_str(),
+        session_id = %session_id.as_str(),
         "Stored session secret via Armin"
     );
 
     if let Ok(key) = SecretsManager::parse_session_secret(&session_secret) {
-        session_secret_cache.insert(created_session.id.as_str(), key);
+        session_secret_cache.insert(session_id.as_str(), key);
     }
 
-    let session_data = session_json(&created_session);
-
-    Ok(session_data)
+    Ok(())
 }
 
 fn rollback_session_creation_after_secret_failure(
@@ -803,6 +934,486 @@ fn rollback_session_creation_after_secret_failure(
     SessionCreateCoreError::new("internal_error", reason)
 }
 
+/// Forks a session at a message sequence, optionally into a new worktree.
+///
+/// With `create_worktree`, the fork gets its own worktree branched from the source
+/// worktree's current HEAD; otherwise it keeps the source's working directory.
+async fn fork_session_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, SessionCreateCoreError> {
+    let Some(source_id) = session_id_from_params(params) else {
+        return Err(SessionCreateCoreError::new(
+            "invalid_params",
+            "session_id is required",
+        ));
+    };
+    let Some(up_to_sequence) = params.get("up_to_sequence").and_then(|v| v.as_i64()) else {
+        return Err(SessionCreateCoreError::new(
+            "invalid_params",
+            "up_to_sequence is required",
+        ));
+    };
+    let title = normalize_optional_string(params.get("title").and_then(|v| v.as_str()));
+    let create_worktree = params
+        .get("create_worktree")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let worktree_name =
+        normalize_optional_string(params.get("worktree_name").and_then(|v| v.as_str()));
+    let requested_worktree_branch = resolve_worktree_branch(params);
+    if let Some(name) = worktree_name.as_deref() {
+        validate_worktree_name(name)
+            .map_err(|msg| SessionCreateCoreError::new("invalid_params", msg))?;
+    }
+
+    let armin = state.armin.as_ref();
+    let source_id = SessionId::from_string(&source_id);
+    let source = match armin.get_session(&source_id) {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return Err(SessionCreateCoreError::new(
+                "not_found",
+                "Session not found",
+            ));
+        }
+        Err(e) => {
+            return Err(SessionCreateCoreError::new(
+                "internal_error",
+                format!("Failed to get session: {}", e),
+            ));
+        }
+    };
+
+    let session_id = SessionId::new();
+    let mut worktree_cleanup_context: Option<(String, String)> = None;
+
+    let worktree_path = if create_worktree {
+        let Some(source_worktree_path) = source
+            .worktree_path
+            .as_deref()
+            .filter(|_| source.is_worktree)
+        else {
+            return Err(SessionCreateCoreError::new(
+                "invalid_params",
+                "create_worktree requires a worktree session",
+            ));
+        };
+
+        let repo = match armin.get_repository(&source.repository_id) {
+            Ok(Some(r)) => r,
+            Ok(None) => {
+                return Err(SessionCreateCoreError::new(
+                    "not_found",
+                    "Repository not found",
+                ));
+            }
+            Err(e) => {
+                return Err(SessionCreateCoreError::new(
+                    "internal_error",
+                    format!("Failed to get repository: {}", e),
+                ));
+            }
+        };
+
+        let repo_path = Path::new(&repo.path);
+        let default_worktree_root_dir = default_worktree_root_dir_for_repo(repo.id.as_str());
+        let repo_config =
+            load_repository_config(repo_path, &default_worktree_root_dir).map_err(|e| {
+                SessionCreateCoreError::new(
+                    "internal_error",
+                    format!("Failed to load repository config: {}", e),
+                )
+            })?;
+
+        // Worktrees share one object database, so the source's HEAD commit
+        // resolves from the main repository.
+        let source_head = get_log(Path::new(source_worktree_path), Some(1), None, None)
+            .and_then(|log| {
+                log.commits
+                    .into_iter()
+                    .next()
+                    .map(|commit| commit.oid)
+                    .ok_or_else(|| "source worktree has no commits".to_string())
+            })
+            .map_err(|e| {
+                SessionCreateCoreError::new(
+                    "internal_error",
+                    format!("Failed to resolve source worktree HEAD: {}", e),
+                )
+            })?;
+
+        let wt_name = worktree_name.as_deref().unwrap_or(session_id.as_str());
+        let created_worktree_path = create_session_worktree(
+            repo_path,
+            &repo_config,
+            &state.config,
+            &default_worktree_root_dir,
+            wt_name,
+            Some(&source_head),
+            requested_worktree_branch.as_deref(),
+        )
+        .await?;
+
+        worktree_cleanup_context = Some((repo.path.clone(), created_worktree_path.clone()));
+        Some(created_worktree_path)
+    } else {
+        None
+    };
+
+    let options = SessionForkOptions {
+        id: session_id,
+        title,
+        worktree_path,
+    };
+    let forked_session = match armin.fork_session_with_options(&source_id, up_to_sequence, options)
+    {
+        Ok(session) => session,
+        Err(e) => {
+            if let Some((repo_path, created_worktree_path)) = worktree_cleanup_context.take() {
+                if let Err(cleanup_error) =
+                    remove_worktree(Path::new(&repo_path), Path::new(&created_worktree_path))
+                {
+                    warn!(
+                        worktree_path = %created_worktree_path,
+                        "Failed to remove worktree after fork failure: {}",
+                        cleanup_error
+                    );
+                }
+            }
+
+            let code = match e {
+                ArminError::InvalidForkPoint(_) => "invalid_params",
+                ArminError::SessionNotFound(_) => "not_found",
+                _ => "internal_error",
+            };
+            return Err(SessionCreateCoreError::new(
+                code,
+                format!("Failed to fork session: {}", e),
+            ));
+        }
+    };
+
+    persist_new_session_secret(
+        armin,
+        &state.db_encryption_key,
+        &state.session_secret_cache,
+        &forked_session.id,
+        &mut worktree_cleanup_context,
+    )?;
+
+    Ok(session_json(&forked_session))
+}
+
+/// Number of messages read per page when exporting a session.
+const EXPORT_PAGE_SIZE: usize = 500;
+
+/// Reads a required absolute path param.
+///
+/// Relative paths are rejected because they would resolve against the daemon's
+/// working directory rather than the caller's.
+fn bundle_path_from_params(params: &serde_json::Value) -> Result<PathBuf, SessionCreateCoreError> {
+    let Some(path) = normalize_optional_string(params.get("path").and_then(|v| v.as_str())) else {
+        return Err(SessionCreateCoreError::new(
+            "invalid_params",
+            "path is required",
+        ));
+    };
+    let path = PathBuf::from(path);
+    if !path.is_absolute() {
+        return Err(SessionCreateCoreError::new(
+            "invalid_params",
+            "path must be absolute",
+        ));
+    }
+    Ok(path)
+}
+
+fn bundle_error(err: SessionBundleError) -> SessionCreateCoreError {
+    let code = match err {
+        SessionBundleError::UnsupportedSchemaVersion(_) | SessionBundleError::Malformed(_) => {
+            "invalid_params"
+        }
+        SessionBundleError::Io(_) => "internal_error",
+    };
+    SessionCreateCoreError::new(code, err.to_string())
+}
+
+/// Writes a session's metadata, runtime status, and full transcript to a bundle file.
+async fn export_session_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, SessionCreateCoreError> {
+    let Some(session_id) = session_id_from_params(params) else {
+        return Err(SessionCreateCoreError::new(
+            "invalid_params",
+            "session_id is required",
+        ));
+    };
+    let path = bundle_path_from_params(params)?;
+
+    let armin = state.armin.as_ref();
+    let session_id = SessionId::from_string(&session_id);
+    let internal = |what: &str, e: ArminError| {
+        SessionCreateCoreError::new("internal_error", format!("Failed to {what}: {e}"))
+    };
+    let session = match armin.get_session(&session_id) {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return Err(SessionCreateCoreError::new(
+                "not_found",
+                "Session not found",
+            ));
+        }
+        Err(e) => return Err(internal("get session", e)),
+    };
+    let repository = armin
+        .get_repository(&session.repository_id)
+        .map_err(|e| internal("get repository", e))?;
+    let runtime_status = armin
+        .get_session_state(&session_id)
+        .map_err(|e| internal("get session state", e))?
+        .map(|state| state.runtime_status);
+
+    let messages = bundle_messages(armin, &session_id).map_err(|e| internal("read messages", e))?;
+
+    let bundle = SessionBundle::new(&session, repository.as_ref(), runtime_status, messages);
+    let message_count = bundle.manifest.message_count;
+    let bundle_path = path.clone();
+    task::spawn_blocking(move || write_session_bundle(&bundle_path, &bundle))
+        .await
+        .map_err(|e| {
+            SessionCreateCoreError::new("internal_error", format!("export task failed: {}", e))
+        })?
+        .map_err(bundle_error)?;
+
+    Ok(serde_json::json!({
+        "session_id": session_id.as_str(),
+        "path": path.to_string_lossy(),
+        "schema_version": SESSION_BUNDLE_SCHEMA_VERSION,
+        "message_count": message_count,
+    }))
+}
+
+/// Metadata for a session restored from a bundle into `repository_id`.
+///
+/// The session gets a fresh ID and no provider resume ID, since the provider's
+/// own transcript stays on the exporting machine. The worktree path is kept
+/// only when `worktree_exists` reports it present on this machine, and the
+/// copy never owns it: it may still belong to the exported session.
+/// Reads every message of a session, with its timestamp, for a bundle.
+fn bundle_messages(
+    armin: &DaemonArmin,
+    session_id: &SessionId,
+) -> Result<Vec<BundleMessage>, ArminError> {
+    let mut messages = Vec::new();
+    let mut after_sequence = 0;
+    loop {
+        let page =
+            armin.list_timestamped_messages_page(session_id, after_sequence, EXPORT_PAGE_SIZE)?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after_sequence = last.message.sequence_number;
+        let full_page = page.len() == EXPORT_PAGE_SIZE;
+        messages.extend(page.into_iter().map(|stored| BundleMessage {
+            sequence_number: stored.message.sequence_number,
+            content: stored.message.content,
+            timestamp: stored.timestamp,
+        }));
+        if !full_page {
+            break;
+        }
+    }
+    Ok(messages)
+}
+
+/// The exported session's status and creation time, kept by the imported copy.
+fn imported_history(manifest: &SessionBundleManifest) -> SessionHistory {
+    SessionHistory {
+        status: manifest.session.status,
+        created_at: manifest.session.created_at,
+    }
+}
+
+/// Bundle messages as stored by an import, keeping their timestamps.
+fn imported_messages(messages: Vec<BundleMessage>) -> Vec<ImportedMessage> {
+    messages
+        .into_iter()
+        .map(|message| ImportedMessage {
+            content: message.content,
+            timestamp: message.timestamp,
+        })
+        .collect()
+}
+
+fn imported_new_session(
+    manifest: &SessionBundleManifest,
+    repository_id: RepositoryId,
+    machine_id: Option<String>,
+    space_id: Option<String>,
+    worktree_exists: impl FnOnce(&str) -> bool,
+) -> NewSession {
+    let bundled = &manifest.session;
+    let worktree_path = bundled
+        .worktree_path
+        .clone()
+        .filter(|path| bundled.is_worktree && worktree_exists(path));
+
+    NewSession {
+        id: SessionId::new(),
+        repository_id,
+        machine_id,
+        space_id,
+        title: bundled.title.clone(),
+        agent_name: bundled.agent_name.clone(),
+        issue_id: bundled.issue_id.clone(),
+        issue_title: bundled.issue_title.clone(),
+        issue_url: bundled.issue_url.clone(),
+        provider: bundled.provider.clone(),
+        provider_session_id: None,
+        claude_session_id: None,
+        is_worktree: false,
+        worktree_path,
+    }
+}
+
+/// Runtime status to record for an imported session.
+///
+/// No agent runs for the imported copy, so running and waiting become idle.
+fn imported_runtime_status(
+    envelope: &RuntimeStatusEnvelope,
+) -> (CodingSessionStatus, Option<String>) {
+    let status = match envelope.coding_session.status {
+        CodingSessionStatus::Running | CodingSessionStatus::Waiting => CodingSessionStatus::Idle,
+        status => status,
+    };
+    (status, envelope.coding_session.error_message.clone())
+}
+
+/// Restores a session from a bundle file as a new session.
+///
+/// The copy keeps the exported session's status, creation time, and message
+/// timestamps. The bundle's repository is remapped to the local repository registered at
+/// the same path, unless `repository_id` names one explicitly.
+async fn import_session_core(
+    state: &DaemonState,
+    params: &serde_json::Value,
+) -> Result<serde_json::Value, SessionCreateCoreError> {
+    let path = bundle_path_from_params(params)?;
+    let requested_repository_id =
+        normalize_optional_string(params.get("repository_id").and_then(|v| v.as_str()))
+            .map(|id| id.to_lowercase());
+
+    let bundle = task::spawn_blocking(move || read_session_bundle(&path))
+        .await
+        .map_err(|e| {
+            SessionCreateCoreError::new("internal_error", format!("import task failed: {}", e))
+        })?
+        .map_err(bundle_error)?;
+
+    let armin = state.armin.as_ref();
+    let repository = match (&requested_repository_id, &bundle.manifest.repository) {
+        (Some(id), _) => armin.get_repository(&RepositoryId::from_string(id)),
+        (None, Some(bundled)) => armin.get_repository_by_path(&bundled.path),
+        (None, None) => {
+            return Err(SessionCreateCoreError::new(
+                "invalid_params",
+                "bundle has no repository; repository_id is required",
+            ));
+        }
+    };
+    let repository = match repository {
+        Ok(Some(repository)) => repository,
+        Ok(None) => {
+            let message = match (&requested_repository_id, &bundle.manifest.repository) {
+                (None, Some(bundled)) => format!(
+                    "No repository registered at {}; pass repository_id to import elsewhere",
+                    bundled.path
+                ),
+                _ => "Repository not found".to_string(),
+            };
+            return Err(SessionCreateCoreError::new("not_found", message));
+        }
+        Err(e) => {
+            return Err(SessionCreateCoreError::new(
+                "internal_error",
+                format!("Failed to get repository: {}", e),
+            ));
+        }
+    };
+
+    let device_id = {
+        let guard = state.device_id.lock().unwrap();
+        guard.clone()
+    };
+    let requested_space_id =
+        normalize_optional_string(params.get("space_id").and_then(|v| v.as_str()));
+    let (machine_id, space_id) =
+        resolve_machine_space_scope(&state.db, device_id.clone(), requested_space_id)
+            .await
+            .map_err(|error| {
+                SessionCreateCoreError::new(
+                    "internal_error",
+                    format!("Failed to resolve machine/space scope: {error}"),
+                )
+            })?;
+
+    let new_session = imported_new_session(
+        &bundle.manifest,
+        repository.id,
+        machine_id,
+        space_id,
+        |path| Path::new(path).is_dir(),
+    );
+    let history = imported_history(&bundle.manifest);
+    let messages = imported_messages(bundle.messages);
+    let imported_session = armin
+        .import_session(new_session, history, messages)
+        .map_err(|e| {
+            SessionCreateCoreError::new(
+                "internal_error",
+                format!("Failed to import session: {}", e),
+            )
+        })?;
+
+    if let (Some(device_id), Some(envelope)) = (device_id, &bundle.manifest.runtime_status) {
+        let (status, error_message) = imported_runtime_status(envelope);
+        if let Err(e) =
+            armin.update_runtime_status(&imported_session.id, &device_id, status, error_message)
+        {
+            warn!(
+                session_id = %imported_session.id.as_str(),
+                "Failed to restore runtime status for imported session: {}",
+                e
+            );
+        }
+    }
+
+    persist_new_session_secret(
+        armin,
+        &state.db_encryption_key,
+        &state.session_secret_cache,
+        &imported_session.id,
+        &mut None,
+    )?;
+
+    Ok(session_json(&imported_session))
+}
+
+fn session_core_error_response(request_id: &str, err: SessionCreateCoreError) -> Response {
+    let error_code = match err.code.as_str() {
+        "invalid_params" => error_codes::INVALID_PARAMS,
+        "not_found" => error_codes::NOT_FOUND,
+        _ => error_codes::INTERNAL_ERROR,
+    };
+    if let Some(data) = err.data {
+        Response::error_with_data(request_id, error_code, &err.message, data)
+    } else {
+        Response::error(request_id, error_code, &err.message)
+    }
+}
+
 async fn register_session_create(server: &IpcServer, state: DaemonState) {
     server
         .register_handler(Method::SessionCreate, move |req| {
@@ -815,18 +1426,64 @@ async fn register_session_create(server: &IpcServer, state: DaemonState) {
                     .unwrap_or(serde_json::json!({}));
                 match create_session_core(&state, &params).await {
                     Ok(data) => Response::success(&req.id, data),
-                    Err(err) => {
-                        let error_code = match err.code.as_str() {
-                            "invalid_params" => error_codes::INVALID_PARAMS,
-                            "not_found" => error_codes::NOT_FOUND,
-                            _ => error_codes::INTERNAL_ERROR,
-                        };
-                        if let Some(data) = err.data {
-                            Response::error_with_data(&req.id, error_code, &err.message, data)
-                        } else {
-                            Response::error(&req.id, error_code, &err.message)
-                        }
-                    }
+                    Err(err) => session_core_error_response(&req.id, err),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_session_fork(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::SessionFork, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match fork_session_core(&state, &params).await {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err(err) => session_core_error_response(&req.id, err),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_session_export(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::SessionExport, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match export_session_core(&state, &params).await {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err(err) => session_core_error_response(&req.id, err),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_session_import(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::SessionImport, move |req| {
+            let state = state.clone();
+            async move {
+                let params = req
+                    .params
+                    .as_ref()
+                    .cloned()
+                    .unwrap_or(serde_json::json!({}));
+                match import_session_core(&state, &params).await {
+                    Ok(data) => Response::success(&req.id, data),
+                    Err(err) => session_core_error_response(&req.id, err),
                 }
             }
         })
@@ -880,7 +1537,7 @@ async fn register_session_update(server: &IpcServer, state: DaemonState) {
                         return Response::error(
                             &req.id,
                             error_codes::INVALID_PARAMS,
-                            "session_id and title are required",
+                            "session_id and title or permission_mode are required",
                         );
                     }
                 };
@@ -891,7 +1548,16 @@ async fn register_session_update(server: &IpcServer, state: DaemonState) {
                     .and_then(|v| v.as_str())
                     .map(String::from);
 
-                let title = normalize_optional_string(params.get("title").and_then(|v| v.as_str()));
+                let title = params
+                    .get("title")
+                    .map(|v| normalize_optional_string(v.as_str()));
+                let permission_mode =
+                    match parse_optional_permission_mode(params, "permission_mode") {
+                        Ok(value) => value,
+                        Err(msg) => {
+                            return Response::error(&req.id, error_codes::INVALID_PARAMS, &msg);
+                        }
+                    };
 
                 let Some(session_id) = session_id else {
                     return Response::error(
@@ -901,12 +1567,23 @@ async fn register_session_update(server: &IpcServer, state: DaemonState) {
                     );
                 };
 
-                let Some(title) = title else {
-                    return Response::error(
-                        &req.id,
-                        error_codes::INVALID_PARAMS,
-                        "title must not be empty",
-                    );
+                let title = match title {
+                    Some(Some(title)) => Some(title),
+                    Some(None) => {
+                        return Response::error(
+                            &req.id,
+                            error_codes::INVALID_PARAMS,
+                            "title must not be empty",
+                        );
+                    }
+                    None if permission_mode.is_none() => {
+                        return Response::error(
+                            &req.id,
+                            error_codes::INVALID_PARAMS,
+                            "title or permission_mode is required",
+                        );
+                    }
+                    None => None,
                 };
 
                 let session_id = SessionId::from_string(&session_id);
@@ -928,7 +1605,12 @@ async fn register_session_update(server: &IpcServer, state: DaemonState) {
                     }
                 };
 
-                if current.title == title {
+                // The next agent turn picks up a changed permission mode
+                let title = title.filter(|title| *title != current.title);
+                let permission_mode = permission_mode
+                    .map(|mode| mode.map(|mode| mode.as_str().to_string()))
+                    .filter(|mode| *mode != current.permission_mode);
+                if title.is_none() && permission_mode.is_none() {
                     return Response::success(
                         &req.id,
                         serde_json::json!({ "session": session_json(&current) }),
@@ -936,7 +1618,8 @@ async fn register_session_update(server: &IpcServer, state: DaemonState) {
                 }
 
                 let update = SessionUpdate {
-                    title: Some(title),
+                    title,
+                    permission_mode,
                     last_accessed_at: Some(chrono::Utc::now()),
                     ..SessionUpdate::default()
                 };
@@ -1011,30 +1694,7 @@ async fn register_session_delete(server: &IpcServer, state: DaemonState) {
                 };
 
                 // If it's a worktree session, clean up the worktree
-                if session.is_worktree {
-                    if let Some(worktree_path) = &session.worktree_path {
-                        // Get repository path for worktree cleanup
-                        if let Ok(Some(repo)) = state.armin.get_repository(&session.repository_id) {
-                            // Try to remove the worktree, but don't fail the deletion if this fails
-                            if let Err(e) =
-                                remove_worktree(Path::new(&repo.path), Path::new(worktree_path))
-                            {
-                                warn!(
-                                    session_id = %session.id.as_str(),
-                                    worktree_path = %worktree_path,
-                                    "Failed to remove worktree: {}",
-                                    e
-                                );
-                            } else {
-                                debug!(
-                                    session_id = %session.id.as_str(),
-                                    worktree_path = %worktree_path,
-                                    "Worktree removed successfully"
-                                );
-                            }
-                        }
-                    }
-                }
+                remove_session_worktree(&state.armin, &session);
 
                 // Delete the session via Armin
                 let deleted = match state.armin.delete_session(&session_id) {
@@ -1058,6 +1718,76 @@ async fn register_session_delete(server: &IpcServer, state: DaemonState) {
         .await;
 }
 
+async fn register_session_search(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::SessionSearch, move |req| {
+            let armin = state.armin.clone();
+            async move {
+                let empty = serde_json::Value::Null;
+                let query = match search_query_from_params(req.params.as_ref().unwrap_or(&empty)) {
+                    Ok(query) => query,
+                    Err(message) => {
+                        return Response::error(&req.id, error_codes::INVALID_PARAMS, &message);
+                    }
+                };
+
+                let hits = {
+                    let _span = info_span!("armin.search_messages").entered();
+                    match armin.search_messages(&query) {
+                        Ok(hits) => hits,
+                        Err(ArminError::InvalidSearchQuery(message)) => {
+                            return Response::error(&req.id, error_codes::INVALID_PARAMS, &message);
+                        }
+                        Err(e) => {
+                            return Response::error(
+                                &req.id,
+                                error_codes::INTERNAL_ERROR,
+                                &format!("Failed to search sessions: {}", e),
+                            );
+                        }
+                    }
+                };
+
+                let hit_data: Vec<serde_json::Value> = hits.iter().map(search_hit_json).collect();
+                Response::success(&req.id, serde_json::json!({ "hits": hit_data }))
+            }
+        })
+        .await;
+}
+
+/// Remove the worktree a session owns. Sessions sharing another session's
+/// worktree (forks and imports) leave it alone. Failures are logged rather
+/// than returned, so they do not block deleting the session.
+fn remove_session_worktree(
+    armin: &DaemonArmin,
+    session: &agent_session_sqlite_persist_core::Session,
+) {
+    if !session.is_worktree {
+        return;
+    }
+    let Some(worktree_path) = &session.worktree_path else {
+        return;
+    };
+    // Get repository path for worktree cleanup
+    let Ok(Some(repo)) = armin.get_repository(&session.repository_id) else {
+        return;
+    };
+    if let Err(e) = remove_worktree(Path::new(&repo.path), Path::new(worktree_path)) {
+        warn!(
+            session_id = %session.id.as_str(),
+            worktree_path = %worktree_path,
+            "Failed to remove worktree: {}",
+            e
+        );
+    } else {
+        debug!(
+            session_id = %session.id.as_str(),
+            worktree_path = %worktree_path,
+            "Worktree removed successfully"
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1065,10 +1795,6 @@ mod tests {
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    fn has_zsh() -> bool {
-        Path::new("/bin/zsh").exists()
-    }
-
     fn unique_temp_path(prefix: &str) -> PathBuf {
         let nanos = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -1180,16 +1906,12 @@ mod tests {
 
     #[tokio::test]
     async fn run_setup_hook_non_zero_includes_stage_and_stderr() {
-        if !has_zsh() {
-            return;
-        }
-
         let hook = SetupHookStageConfig {
             command: Some("echo hook failed >&2; exit 7".to_string()),
             timeout_seconds: 5,
         };
         let cwd = Path::new(env!("CARGO_MANIFEST_DIR"));
-        let err = run_setup_hook(HookStage::PreCreate, &hook, cwd)
+        let err = run_setup_hook(HookStage::PreCreate, &hook, &Shell::new("/bin/sh"), cwd)
             .await
             .expect_err("hook should fail");
 
@@ -1205,16 +1927,12 @@ mod tests {
 
     #[tokio::test]
     async fn run_setup_hook_timeout_includes_stage() {
-        if !has_zsh() {
-            return;
-        }
-
         let hook = SetupHookStageConfig {
             command: Some("sleep 2".to_string()),
             timeout_seconds: 1,
         };
         let cwd = Path::new(env!("CARGO_MANIFEST_DIR"));
-        let err = run_setup_hook(HookStage::PostCreate, &hook, cwd)
+        let err = run_setup_hook(HookStage::PostCreate, &hook, &Shell::new("/bin/sh"), cwd)
             .await
             .expect_err("hook should timeout");
 
@@ -1227,10 +1945,6 @@ mod tests {
 
     #[tokio::test]
     async fn run_setup_hook_timeout_kills_background_children() {
-        if !has_zsh() {
-            return;
-        }
-
         let marker_file = unique_temp_path("hook-leak-check");
         let marker_path = marker_file
             .to_string_lossy()
@@ -1239,14 +1953,14 @@ mod tests {
 
         let hook = SetupHookStageConfig {
             command: Some(format!(
-                "nohup /bin/zsh -lc 'sleep 2; echo leaked > \"{}\"' >/dev/null 2>&1 & sleep 30",
+                "nohup /bin/sh -c 'sleep 2; echo leaked > \"{}\"' >/dev/null 2>&1 & sleep 30",
                 marker_path
             )),
             timeout_seconds: 1,
         };
 
         let cwd = Path::new(env!("CARGO_MANIFEST_DIR"));
-        let err = run_setup_hook(HookStage::PreCreate, &hook, cwd)
+        let err = run_setup_hook(HookStage::PreCreate, &hook, &Shell::new("/bin/sh"), cwd)
             .await
             .expect_err("hook should timeout");
 
@@ -1323,6 +2037,9 @@ mod tests {
             status: agent_session_sqlite_persist_core::SessionStatus::Active,
             is_worktree: true,
             worktree_path: Some("/tmp/worktree".to_string()),
+            parent_session_id: None,
+            forked_from_sequence: None,
+            permission_mode: None,
             machine_id: None,
             space_id: None,
             created_at: chrono::Utc::now(),
@@ -1349,4 +2066,268 @@ mod tests {
 
         assert_eq!(session_id_from_params(&params), Some("new-id".to_string()));
     }
+
+    #[test]
+    fn search_query_from_params_applies_filters_and_clamps_limit() {
+        let params = serde_json::json!({
+            "query": "  flaky test  ",
+            "repository_id": "REPO-1",
+            "since": "2026-01-01T00:00:00Z",
+            "until": "2026-02-01T00:00:00+02:00",
+            "limit": 10_000,
+            "offset": 20
+        });
+
+        let query = search_query_from_params(&params).unwrap();
+        assert_eq!(query.text, "flaky test");
+        assert_eq!(
+            query.repository_id.as_ref().map(|id| id.as_str()),
+            Some("repo-1")
+        );
+        assert_eq!(
+            query.until.map(|until| until.to_rfc3339()).as_deref(),
+            Some("2026-01-31T22:00:00+00:00")
+        );
+        assert_eq!(query.limit, MAX_SEARCH_LIMIT);
+        assert_eq!(query.offset, 20);
+    }
+
+    #[test]
+    fn search_query_from_params_rejects_invalid_input() {
+        assert!(search_query_from_params(&serde_json::json!({})).is_err());
+        assert!(search_query_from_params(&serde_json::json!({ "query": "   " })).is_err());
+        assert!(search_query_from_params(&serde_json::json!({
+            "query": "x",
+            "since": "yesterday"
+        }))
+        .is_err());
+        assert!(search_query_from_params(&serde_json::json!({
+            "query": "x",
+            "since": "2026-02-01T00:00:00Z",
+            "until": "2026-01-01T00:00:00Z"
+        }))
+        .is_err());
+    }
+
+    fn bundle_manifest(is_worktree: bool) -> SessionBundleManifest {
+        let now = Utc::now();
+        SessionBundleManifest {
+            schema_version: SESSION_BUNDLE_SCHEMA_VERSION,
+            exported_at: now,
+            session: crate::utils::session_bundle::BundleSession {
+                id: "exported-session".to_string(),
+                title: "Investigate crash".to_string(),
+                agent_name: Some("debugger".to_string()),
+                issue_id: Some("ISS-7".to_string()),
+                issue_title: Some("Crash on start".to_string()),
+                issue_url: None,
+                provider: Some("codex".to_string()),
+                status: agent_session_sqlite_persist_core::SessionStatus::Active,
+                is_worktree,
+                worktree_path: is_worktree.then(|| "/worktrees/crash".to_string()),
+                parent_session_id: None,
+                forked_from_sequence: None,
+                created_at: now,
+                last_accessed_at: now,
+            },
+            repository: None,
+            runtime_status: None,
+            message_count: 0,
+        }
+    }
+
+    #[test]
+    fn imported_new_session_remaps_repository_and_drops_machine_local_ids() {
+        let session = imported_new_session(
+            &bundle_manifest(false),
+            RepositoryId::from_string("local-repo"),
+            Some("machine-2".to_string()),
+            None,
+            |_| true,
+        );
+
+        assert_ne!(session.id.as_str(), "exported-session");
+        assert_eq!(session.repository_id.as_str(), "local-repo");
+        assert_eq!(session.machine_id.as_deref(), Some("machine-2"));
+        assert_eq!(session.title, "Investigate crash");
+        assert_eq!(session.agent_name.as_deref(), Some("debugger"));
+        assert_eq!(session.issue_id.as_deref(), Some("ISS-7"));
+        assert_eq!(session.provider.as_deref(), Some("codex"));
+        assert_eq!(session.provider_session_id, None);
+        assert_eq!(session.claude_session_id, None);
+        assert!(!session.is_worktree);
+    }
+
+    #[test]
+    fn imported_new_session_shares_worktree_only_when_present() {
+        let manifest = bundle_manifest(true);
+
+        let present = imported_new_session(
+            &manifest,
+            RepositoryId::from_string("repo"),
+            None,
+            None,
+            |path| path == "/worktrees/crash",
+        );
+        assert!(!present.is_worktree);
+        assert_eq!(present.worktree_path.as_deref(), Some("/worktrees/crash"));
+
+        let missing = imported_new_session(
+            &manifest,
+            RepositoryId::from_string("repo"),
+            None,
+            None,
+            |_| false,
+        );
+        assert!(!missing.is_worktree);
+        assert_eq!(missing.worktree_path, None);
+    }
+
+    #[test]
+    fn export_and_import_keep_session_history_and_message_timestamps() {
+        let armin = crate::armin_adapter::create_test_armin(daemon_ipc::SubscriptionManager::new())
+            .unwrap();
+        let repository = armin
+            .create_repository(agent_session_sqlite_persist_core::NewRepository::new(
+                "/work/history",
+                "history",
+                false,
+            ))
+            .unwrap();
+        let created_at = Utc::now() - chrono::Duration::days(30);
+        let written_at = created_at + chrono::Duration::hours(1);
+        let original = armin
+            .import_session(
+                NewSession::new(repository.id.clone(), "Old work"),
+                SessionHistory {
+                    status: agent_session_sqlite_persist_core::SessionStatus::Archived,
+                    created_at,
+                },
+                vec![ImportedMessage {
+                    content: "hello".to_string(),
+                    timestamp: written_at,
+                }],
+            )
+            .unwrap();
+
+        let dir = unique_temp_path("history-bundle");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.tar.gz");
+        let bundle = SessionBundle::new(
+            &original,
+            Some(&repository),
+            None,
+            bundle_messages(&armin, &original.id).unwrap(),
+        );
+        write_session_bundle(&path, &bundle).unwrap();
+        let loaded = read_session_bundle(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        let restored = armin
+            .import_session(
+                imported_new_session(&loaded.manifest, repository.id, None, None, |_| false),
+                imported_history(&loaded.manifest),
+                imported_messages(loaded.messages),
+            )
+            .unwrap();
+
+        assert_eq!(
+            restored.status,
+            agent_session_sqlite_persist_core::SessionStatus::Archived
+        );
+        assert_eq!(restored.created_at, created_at);
+        let messages = bundle_messages(&armin, &restored.id).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "hello");
+        assert_eq!(messages[0].timestamp, written_at);
+    }
+
+    fn git(repo: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .status()
+            .expect("git runs");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn deleting_a_fork_keeps_the_parent_worktree() {
+        let root = unique_temp_path("fork-worktree");
+        let repo_path = root.join("repo");
+        fs::create_dir_all(&repo_path).unwrap();
+        git(&repo_path, &["init", "-q"]);
+        git(&repo_path, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        let worktree_path =
+            create_worktree_with_options(&repo_path, "parent", &root.join("worktrees"), None, None)
+                .unwrap();
+
+        let armin = crate::armin_adapter::create_test_armin(daemon_ipc::SubscriptionManager::new())
+            .unwrap();
+        let repo = armin
+            .create_repository(agent_session_sqlite_persist_core::NewRepository::new(
+                repo_path.to_string_lossy(),
+                "repo",
+                true,
+            ))
+            .unwrap();
+        let parent = armin
+            .create_session_with_metadata(NewSession {
+                is_worktree: true,
+                worktree_path: Some(worktree_path.clone()),
+                ..NewSession::new(repo.id, "Parent")
+            })
+            .unwrap();
+        let fork = armin.fork_session(&parent.id, 0).unwrap();
+
+        remove_session_worktree(&armin, &fork);
+        assert!(Path::new(&worktree_path).exists());
+
+        remove_session_worktree(&armin, &parent);
+        assert!(!Path::new(&worktree_path).exists());
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn imported_runtime_status_settles_active_states() {
+        let envelope = |status, error_message: Option<&str>| RuntimeStatusEnvelope {
+            schema_version: agent_session_sqlite_persist_core::RUNTIME_STATUS_SCHEMA_VERSION,
+            coding_session: agent_session_sqlite_persist_core::CodingSessionRuntimeState {
+                status,
+                error_message: error_message.map(String::from),
+                question: None,
+                queue_length: 0,
+                scheduler_position: None,
+            },
+            device_id: "device-1".to_string(),
+            session_id: SessionId::from_string("exported-session"),
+            updated_at_ms: 0,
+        };
+
+        assert_eq!(
+            imported_runtime_status(&envelope(CodingSessionStatus::Running, None)),
+            (CodingSessionStatus::Idle, None)
+        );
+        assert_eq!(
+            imported_runtime_status(&envelope(CodingSessionStatus::Waiting, None)),
+            (CodingSessionStatus::Idle, None)
+        );
+        assert_eq!(
+            imported_runtime_status(&envelope(CodingSessionStatus::Error, Some("exit 1"))),
+            (CodingSessionStatus::Error, Some("exit 1".to_string()))
+        );
+    }
+
+    #[test]
+    fn bundle_path_from_params_requires_absolute_path() {
+        assert!(bundle_path_from_params(&serde_json::json!({})).is_err());
+        assert!(
+            bundle_path_from_params(&serde_json::json!({ "path": "relative.tar.gz" })).is_err()
+        );
+        assert_eq!(
+            bundle_path_from_params(&serde_json::json!({ "path": "/tmp/session.tar.gz" })).unwrap(),
+            PathBuf::from("/tmp/session.tar.gz")
+        );
+    }
 }
@@ -0,0 +1,172 @@
+//! Subscription replay handler.
+//!
+//! Lets `session.subscribe` resume from a client's last seen sequence: persisted
+//! messages after `after_sequence` are replayed from Armin before the
+//! connection switches to live events.
+
+use crate::app::DaemonState;
+use crate::armin_adapter::{status_change_event, DaemonArmin};
+use agent_session_sqlite_persist_core::{ArminError, SessionId, SessionReader};
+use daemon_ipc::{Event, EventType, IpcServer};
+use tracing::{info_span, warn};
+
+/// Number of messages read per page when replaying a session.
+const REPLAY_PAGE_SIZE: usize = 500;
+
+/// Register the subscription replay handler.
+pub async fn register(server: &IpcServer, state: DaemonState) {
+    server
+        .register_initial_state_handler(move |session_id, after_sequence| {
+            let armin = state.armin.clone();
+            async move {
+                let after_sequence = after_sequence?;
+                let _span = info_span!(
+                    "session.subscribe.replay",
+                    session_id = %session_id,
+                    after_sequence
+                )
+                .entered();
+                match replay_session_events(&armin, &session_id, after_sequence) {
+                    Ok(replay) => replay,
+                    Err(error) => {
+                        warn!(
+                            session_id = %session_id,
+                            error = %error,
+                            "Failed to replay session events"
+                        );
+                        None
+                    }
+                }
+            }
+        })
+        .await;
+}
+
+/// Builds the events a resuming subscriber missed.
+///
+/// Returns one `message` event per persisted message after `after_sequence`,
+/// followed by the session's current status, together with the last message
+/// sequence covered. Returns None for unknown sessions.
+fn replay_session_events(
+    armin: &DaemonArmin,
+    session_id: &str,
+    after_sequence: i64,
+) -> Result<Option<(Vec<Event>, i64)>, ArminError> {
+    let armin_session_id = SessionId::from_string(session_id);
+    if armin.get_session(&armin_session_id)?.is_none() {
+        return Ok(None);
+    }
+
+    let mut events = Vec::new();
+    let mut last_sequence = after_sequence;
+    loop {
+        let page = armin.list_messages_page(&armin_session_id, last_sequence, REPLAY_PAGE_SIZE)?;
+        let full_page = page.len() == REPLAY_PAGE_SIZE;
+        for message in page {
+            last_sequence = message.sequence_number;
+            events.push(Event::new(
+                EventType::Message,
+                session_id,
+                serde_json::json!({
+                    "session_id": session_id,
+                    "message_id": message.id.as_str(),
+                    "sequence_number": message.sequence_number,
+                    "raw_json": message.content,
+                    "replayed": true,
+                }),
+                message.sequence_number,
+            ));
+        }
+        if !full_page {
+            break;
+        }
+    }
+
+    // Status changes are not persisted as messages; send where things stand now
+    if let Some(state) = armin.get_session_state(&armin_session_id)? {
+        events.push(status_change_event(
+            session_id,
+            &state.runtime_status,
+            last_sequence,
+        ));
+    }
+
+    Ok(Some((events, last_sequence)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::armin_adapter::create_test_armin;
+    use agent_session_sqlite_persist_core::{
+        CodingSessionStatus, NewMessage, NewRepository, NewSession, SessionWriter,
+    };
+    use daemon_ipc::SubscriptionManager;
+
+    fn session_with_messages(armin: &DaemonArmin, count: usize) -> SessionId {
+        let repository = armin
+            .create_repository(NewRepository::new("/tmp/replay-repo", "replay-repo", false))
+            .unwrap();
+        let session = armin
+            .create_session_with_metadata(NewSession::new(repository.id, "Replay"))
+            .unwrap();
+        for i in 0..count {
+            armin
+                .append(
+                    &session.id,
+                    NewMessage {
+                        content: format!("{{\"n\":{i}}}"),
+                    },
+                )
+                .unwrap();
+        }
+        session.id
+    }
+
+    #[tokio::test]
+    async fn replay_returns_messages_after_sequence_in_order() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let session_id = session_with_messages(&armin, 5);
+
+        let (events, last_sequence) = replay_session_events(&armin, session_id.as_str(), 2)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(last_sequence, 5);
+        let sequences: Vec<i64> = events.iter().map(|event| event.sequence).collect();
+        assert_eq!(sequences, vec![3, 4, 5]);
+        assert!(events
+            .iter()
+            .all(|event| event.event_type == EventType::Message));
+        assert_eq!(events[0].data["raw_json"], "{\"n\":2}");
+        assert_eq!(events[0].data["replayed"], true);
+    }
+
+    #[tokio::test]
+    async fn replay_ends_with_current_status_at_last_sequence() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+        let session_id = session_with_messages(&armin, 2);
+        armin
+            .update_runtime_status(&session_id, "device-1", CodingSessionStatus::Idle, None)
+            .unwrap();
+
+        let (events, last_sequence) = replay_session_events(&armin, session_id.as_str(), 2)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(last_sequence, 2);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::StatusChange);
+        assert_eq!(events[0].sequence, 2);
+        assert_eq!(events[0].data["status"], "idle");
+    }
+
+    #[tokio::test]
+    async fn replay_unknown_session_returns_none() {
+        let armin = create_test_armin(SubscriptionManager::new()).unwrap();
+
+        let replay = replay_session_events(&armin, SessionId::new().as_str(), 0).unwrap();
+
+        assert!(replay.is_none());
+    }
+}
@@ -1,6 +1,7 @@
 //! System handlers.
 
 use crate::app::{resolve_machine_space_scope, DaemonState};
+use crate::machines::terminal::resolve_shell;
 use daemon_ipc::{
     error_codes, DaemonVersionInfo, DesktopCompatibilityRange, IpcServer, Method, Response,
     IPC_PROTOCOL_VERSION,
@@ -10,11 +11,17 @@ use tracing::info;
 
 /// Register system handlers.
 pub async fn register(server: &IpcServer, state: crate::app::DaemonState) {
+    let dependencies_state = state.clone();
     server
-        .register_handler(Method::SystemCheckDependencies, |req| async move {
-            match runtime_capability_detector::collect_capabilities().await {
-                Ok(result) => Response::success(&req.id, serde_json::to_value(&result).unwrap()),
-                Err(e) => Response::error(&req.id, error_codes::INTERNAL_ERROR, &e.to_string()),
+        .register_handler(Method::SystemCheckDependencies, move |req| {
+            let shell = resolve_shell(&dependencies_state.config, None);
+            async move {
+                match runtime_capability_detector::collect_capabilities(&shell).await {
+                    Ok(result) => {
+                        Response::success(&req.id, serde_json::to_value(&result).unwrap())
+                    }
+                    Err(e) => Response::error(&req.id, error_codes::INTERNAL_ERROR, &e.to_string()),
+                }
             }
         })
         .await;
@@ -1,19 +1,40 @@
 //! Terminal handlers.
 
+use crate::app::process_recovery::{forget_process, record_process};
 use crate::app::DaemonState;
-use crate::machines::terminal::handle_terminal_process;
+use crate::ipc::handlers::claude::session_repository_config;
+use crate::machines::terminal::{
+    handle_terminal_process, parse_signal, resolve_shell, Pty, PtySize, TerminalHandle,
+    DEFAULT_SIZE,
+};
 use crate::observability::spawn_in_current_span;
+use crate::utils::sandbox::SandboxPolicy;
+use agent_session_sqlite_persist_core::{
+    NewMessage, NewTerminal, ProcessKind, SessionId, SessionReader, SessionWriter, Terminal,
+};
 use daemon_ipc::{error_codes, IpcServer, Method, Response};
-use std::process::Stdio;
-use tokio::process::Command;
+use login_shell::Shell;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
 use tokio::sync::broadcast;
+use tracing::warn;
 use workspace_resolver::{resolve_working_dir_from_str, ResolveError};
 
+/// Output chunks returned by `terminal.read_output` when no limit is given.
+const DEFAULT_OUTPUT_CHUNK_LIMIT: usize = 256;
+const MAX_OUTPUT_CHUNK_LIMIT: usize = 1024;
+
 /// Register terminal handlers.
 pub async fn register(server: &IpcServer, state: DaemonState) {
     register_terminal_run(server, state.clone()).await;
+    register_terminal_list(server, state.clone()).await;
     register_terminal_status(server, state.clone()).await;
-    register_terminal_stop(server, state).await;
+    register_terminal_read_output(server, state.clone()).await;
+    register_terminal_stop(server, state.clone()).await;
+    register_terminal_write(server, state.clone()).await;
+    register_terminal_resize(server, state.clone()).await;
+    register_terminal_signal(server, state).await;
 }
 
 async fn register_terminal_run(server: &IpcServer, state: DaemonState) {
@@ -42,6 +63,15 @@ async fn register_terminal_run(server: &IpcServer, state: DaemonState) {
                     .and_then(|v| v.as_str())
                     .map(String::from);
 
+                let name = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("name"))
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from);
+
                 let (Some(session_id), Some(command)) = (session_id, command) else {
                     return Response::error(
                         &req.id,
@@ -60,28 +90,34 @@ async fn register_terminal_run(server: &IpcServer, state: DaemonState) {
                     }
                 };
 
-                // Check if already running a command for this session
-                {
-                    let processes = state.terminal_processes.lock().unwrap();
-                    if processes.contains_key(&session_id) {
-                        return Response::error(
-                            &req.id,
-                            error_codes::INVALID_REQUEST,
-                            "Terminal already running for this session",
-                        );
+                let (shell, sandbox) = session_shell_and_sandbox(&state, &session_id);
+                if let Some(Err(error)) = sandbox.as_ref().map(SandboxPolicy::check) {
+                    let content = error.transcript_json();
+                    if let Err(e) = state
+                        .armin
+                        .append(&SessionId::from_string(&session_id), NewMessage { content })
+                    {
+                        warn!(error = %e, "Failed to store sandbox error");
                     }
+                    return Response::error(
+                        &req.id,
+                        error_codes::INTERNAL_ERROR,
+                        &error.to_string(),
+                    );
                 }
 
-                // Spawn the command using shell
-                let child = match Command::new("zsh")
-                    .args(["-l", "-c", &command])
+                // Spawn the command in the shell, on a PTY of the client's size
+                let invocation = shell.invocation(&command).await;
+                let mut process = match &sandbox {
+                    Some(sandbox) => sandbox.shell_command(&invocation),
+                    None => invocation.command(),
+                };
+                process
                     .current_dir(&working_dir)
-                    .stdin(Stdio::null())
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .spawn()
-                {
-                    Ok(c) => c,
+                    .env("TERM", "xterm-256color");
+                let size = pty_size_param(req.params.as_ref()).unwrap_or(DEFAULT_SIZE);
+                let (mut child, pty) = match Pty::spawn(process, size) {
+                    Ok(spawned) => spawned,
                     Err(e) => {
                         return Response::error(
                             &req.id,
@@ -92,22 +128,59 @@ async fn register_terminal_run(server: &IpcServer, state: DaemonState) {
                 };
 
                 let pid = child.id();
+                let terminal = match state.armin.create_terminal(
+                    &SessionId::from_string(&session_id),
+                    NewTerminal {
+                        name,
+                        command: command.clone(),
+                        cwd: working_dir,
+                        pid,
+                    },
+                ) {
+                    Ok(terminal) => terminal,
+                    Err(e) => {
+                        let _ = child.start_kill();
+                        return Response::error(
+                            &req.id,
+                            error_codes::INTERNAL_ERROR,
+                            &format!("Failed to record terminal: {}", e),
+                        );
+                    }
+                };
 
                 // Create stop channel
                 let (stop_tx, _) = broadcast::channel::<()>(1);
 
-                // Store the stop sender
+                // Store the stop sender and the PTY for input
+                let pty = Arc::new(pty);
                 {
                     let mut processes = state.terminal_processes.lock().unwrap();
-                    processes.insert(session_id.clone(), stop_tx.clone());
+                    processes.insert(
+                        terminal.id.clone(),
+                        TerminalHandle {
+                            session_id: session_id.clone(),
+                            stop_tx: stop_tx.clone(),
+                            pty: Some(pty.clone()),
+                        },
+                    );
                 }
+                let process_record =
+                    record_process(&state, &session_id, ProcessKind::Terminal, pid, &command);
 
                 // Spawn task to handle output
                 let state_for_task = state.clone();
-                let session_id_for_task = session_id.clone();
+                let terminal_for_task = terminal.clone();
                 spawn_in_current_span(async move {
-                    handle_terminal_process(child, session_id_for_task, state_for_task, stop_tx)
-                        .await;
+                    handle_terminal_process(
+                        child,
+                        pty,
+                        terminal_for_task,
+                        state_for_task.clone(),
+                        stop_tx,
+                        sandbox.is_some(),
+                    )
+                    .await;
+                    forget_process(&state_for_task, process_record);
                 });
 
                 Response::success(
@@ -115,6 +188,7 @@ async fn register_terminal_run(server: &IpcServer, state: DaemonState) {
                     serde_json::json!({
                         "started": true,
                         "pid": pid,
+                        "terminal_id": terminal.id,
                     }),
                 )
             }
@@ -122,6 +196,23 @@ async fn register_terminal_run(server: &IpcServer, state: DaemonState) {
         .await;
 }
 
+/// The shell for commands in the session's worktree, and the sandbox if its
+/// repository enables one.
+fn session_shell_and_sandbox(
+    state: &DaemonState,
+    session_id: &str,
+) -> (Shell, Option<SandboxPolicy>) {
+    let resolved = resolve_working_dir_from_str(&*state.armin, session_id).ok();
+    let config = resolved
+        .as_ref()
+        .and_then(|resolved| session_repository_config(&state.armin, &resolved.session));
+    let shell = resolve_shell(&state.config, config.as_ref());
+    let sandbox = resolved.zip(config).and_then(|(resolved, config)| {
+        SandboxPolicy::from_config(&config.sandbox, Path::new(&resolved.working_dir))
+    });
+    (shell, sandbox)
+}
+
 fn terminal_resolve_error_response(id: &str, err: ResolveError) -> Response {
     match err {
         ResolveError::SessionNotFound(message) | ResolveError::RepositoryNotFound(message) => {
@@ -143,9 +234,9 @@ fn terminal_resolve_error_response(id: &str, err: ResolveError) -> Response {
     }
 }
 
-async fn register_terminal_status(server: &IpcServer, state: DaemonState) {
+async fn register_terminal_list(server: &IpcServer, state: DaemonState) {
     server
-        .register_handler(Method::TerminalStatus, move |req| {
+        .register_handler(Method::TerminalList, move |req| {
             let state = state.clone();
             async move {
                 let session_id = req
@@ -163,16 +254,34 @@ async fn register_terminal_status(server: &IpcServer, state: DaemonState) {
                     );
                 };
 
-                let is_running = {
+                let terminals = match state
+                    .armin
+                    .list_terminals(&SessionId::from_string(&session_id))
+                {
+                    Ok(terminals) => terminals,
+                    Err(e) => {
+                        return Response::error(
+                            &req.id,
+                            error_codes::INTERNAL_ERROR,
+                            &format!("Failed to list terminals: {}", e),
+                        )
+                    }
+                };
+                let terminals: Vec<Value> = {
                     let processes = state.terminal_processes.lock().unwrap();
-                    processes.contains_key(&session_id)
+                    terminals
+                        .iter()
+                        .map(|terminal| {
+                            terminal_json(terminal, processes.contains_key(&terminal.id))
+                        })
+                        .collect()
                 };
 
                 Response::success(
                     &req.id,
                     serde_json::json!({
                         "session_id": session_id,
-                        "is_running": is_running,
+                        "terminals": terminals,
                     }),
                 )
             }
@@ -180,9 +289,9 @@ async fn register_terminal_status(server: &IpcServer, state: DaemonState) {
         .await;
 }
 
-async fn register_terminal_stop(server: &IpcServer, state: DaemonState) {
+async fn register_terminal_status(server: &IpcServer, state: DaemonState) {
     server
-        .register_handler(Method::TerminalStop, move |req| {
+        .register_handler(Method::TerminalStatus, move |req| {
             let state = state.clone();
             async move {
                 let session_id = req
@@ -192,26 +301,205 @@ async fn register_terminal_stop(server: &IpcServer, state: DaemonState) {
                     .and_then(|v| v.as_str())
                     .map(String::from);
 
+                let terminal_id = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("terminal_id"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                if let Some(terminal_id) = terminal_id {
+                    let terminal = match state.armin.get_terminal(&terminal_id) {
+                        Ok(Some(terminal)) => terminal,
+                        Ok(None) => {
+                            return Response::error(
+                                &req.id,
+                                error_codes::NOT_FOUND,
+                                "Terminal not found",
+                            )
+                        }
+                        Err(e) => {
+                            return Response::error(
+                                &req.id,
+                                error_codes::INTERNAL_ERROR,
+                                &format!("Failed to get terminal: {}", e),
+                            )
+                        }
+                    };
+                    let is_running = state
+                        .terminal_processes
+                        .lock()
+                        .unwrap()
+                        .contains_key(&terminal_id);
+                    return Response::success(&req.id, terminal_json(&terminal, is_running));
+                }
+
                 let Some(session_id) = session_id else {
                     return Response::error(
                         &req.id,
                         error_codes::INVALID_PARAMS,
-                        "session_id is required",
+                        "terminal_id or session_id is required",
+                    );
+                };
+
+                let running_terminal_ids = session_terminal_ids(&state, &session_id);
+
+                Response::success(
+                    &req.id,
+                    serde_json::json!({
+                        "session_id": session_id,
+                        "is_running": !running_terminal_ids.is_empty(),
+                        "running_terminal_ids": running_terminal_ids,
+                    }),
+                )
+            }
+        })
+        .await;
+}
+
+async fn register_terminal_read_output(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::TerminalReadOutput, move |req| {
+            let state = state.clone();
+            async move {
+                let terminal_id = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("terminal_id"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                let offset = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("offset"))
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+
+                let limit = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("limit"))
+                    .and_then(|v| v.as_u64())
+                    .map_or(DEFAULT_OUTPUT_CHUNK_LIMIT, |limit| {
+                        (limit as usize).clamp(1, MAX_OUTPUT_CHUNK_LIMIT)
+                    });
+
+                let Some(terminal_id) = terminal_id else {
+                    return Response::error(
+                        &req.id,
+                        error_codes::INVALID_PARAMS,
+                        "terminal_id is required",
                     );
                 };
 
-                let stop_tx = {
+                match state.armin.get_terminal(&terminal_id) {
+                    Ok(Some(_)) => {}
+                    Ok(None) => {
+                        return Response::error(
+                            &req.id,
+                            error_codes::NOT_FOUND,
+                            "Terminal not found",
+                        )
+                    }
+                    Err(e) => {
+                        return Response::error(
+                            &req.id,
+                            error_codes::INTERNAL_ERROR,
+                            &format!("Failed to get terminal: {}", e),
+                        )
+                    }
+                }
+                let page = match state
+                    .armin
+                    .read_terminal_output(&terminal_id, offset, limit)
+                {
+                    Ok(page) => page,
+                    Err(e) => {
+                        return Response::error(
+                            &req.id,
+                            error_codes::INTERNAL_ERROR,
+                            &format!("Failed to read terminal output: {}", e),
+                        )
+                    }
+                };
+                let is_running = state
+                    .terminal_processes
+                    .lock()
+                    .unwrap()
+                    .contains_key(&terminal_id);
+
+                Response::success(
+                    &req.id,
+                    serde_json::json!({
+                        "terminal_id": terminal_id,
+                        "chunks": page.chunks,
+                        "start_offset": page.start_offset,
+                        "end_offset": page.end_offset,
+                        "next_offset": page.next_offset,
+                        "has_more": page.next_offset < page.end_offset,
+                        "is_running": is_running,
+                    }),
+                )
+            }
+        })
+        .await;
+}
+
+async fn register_terminal_stop(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::TerminalStop, move |req| {
+            let state = state.clone();
+            async move {
+                let session_id = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("session_id"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                let terminal_id = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("terminal_id"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                // One terminal, or every terminal of the session
+                let terminal_ids = match (terminal_id, &session_id) {
+                    (Some(terminal_id), _) => vec![terminal_id],
+                    (None, Some(session_id)) => session_terminal_ids(&state, session_id),
+                    (None, None) => {
+                        return Response::error(
+                            &req.id,
+                            error_codes::INVALID_PARAMS,
+                            "terminal_id or session_id is required",
+                        );
+                    }
+                };
+
+                let stopped: Vec<String> = {
                     let mut processes = state.terminal_processes.lock().unwrap();
-                    processes.remove(&session_id)
+                    terminal_ids
+                        .into_iter()
+                        .filter(|id| match processes.remove(id) {
+                            Some(handle) => {
+                                let _ = handle.stop_tx.send(());
+                                true
+                            }
+                            None => false,
+                        })
+                        .collect()
                 };
 
-                if let Some(tx) = stop_tx {
-                    let _ = tx.send(());
+                if stopped.is_empty() {
                     Response::success(
                         &req.id,
                         serde_json::json!({
                             "session_id": session_id,
-                            "stopped": true,
+                            "stopped": false,
+                            "terminal_ids": stopped,
+                            "message": "No running terminal to stop",
                         }),
                     )
                 } else {
@@ -219,8 +507,8 @@ async fn register_terminal_stop(server: &IpcServer, state: DaemonState) {
                         &req.id,
                         serde_json::json!({
                             "session_id": session_id,
-                            "stopped": false,
-                            "message": "No running terminal for this session",
+                            "stopped": true,
+                            "terminal_ids": stopped,
                         }),
                     )
                 }
@@ -228,3 +516,239 @@ async fn register_terminal_stop(server: &IpcServer, state: DaemonState) {
         })
         .await;
 }
+
+async fn register_terminal_write(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::TerminalWrite, move |req| {
+            let state = state.clone();
+            async move {
+                let terminal_id = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("terminal_id"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                let data = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("data"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                let (Some(terminal_id), Some(data)) = (terminal_id, data) else {
+                    return Response::error(
+                        &req.id,
+                        error_codes::INVALID_PARAMS,
+                        "terminal_id and data are required",
+                    );
+                };
+
+                let pty = match terminal_pty(&state, &terminal_id) {
+                    Ok(pty) => pty,
+                    Err((code, message)) => return Response::error(&req.id, code, message),
+                };
+                match pty.write_all(data.as_bytes()).await {
+                    Ok(()) => Response::success(
+                        &req.id,
+                        serde_json::json!({
+                            "terminal_id": terminal_id,
+                            "written": data.len(),
+                        }),
+                    ),
+                    Err(e) => Response::error(
+                        &req.id,
+                        error_codes::INTERNAL_ERROR,
+                        &format!("Failed to write to terminal: {}", e),
+                    ),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_terminal_resize(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::TerminalResize, move |req| {
+            let state = state.clone();
+            async move {
+                let terminal_id = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("terminal_id"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                let size = pty_size_param(req.params.as_ref());
+
+                let (Some(terminal_id), Some(size)) = (terminal_id, size) else {
+                    return Response::error(
+                        &req.id,
+                        error_codes::INVALID_PARAMS,
+                        "terminal_id, rows and cols are required; rows and cols must be 1-65535",
+                    );
+                };
+
+                let pty = match terminal_pty(&state, &terminal_id) {
+                    Ok(pty) => pty,
+                    Err((code, message)) => return Response::error(&req.id, code, message),
+                };
+                match pty.resize(size) {
+                    Ok(()) => Response::success(
+                        &req.id,
+                        serde_json::json!({
+                            "terminal_id": terminal_id,
+                            "rows": size.rows,
+                            "cols": size.cols,
+                        }),
+                    ),
+                    Err(e) => Response::error(
+                        &req.id,
+                        error_codes::INTERNAL_ERROR,
+                        &format!("Failed to resize terminal: {}", e),
+                    ),
+                }
+            }
+        })
+        .await;
+}
+
+async fn register_terminal_signal(server: &IpcServer, state: DaemonState) {
+    server
+        .register_handler(Method::TerminalSignal, move |req| {
+            let state = state.clone();
+            async move {
+                let terminal_id = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("terminal_id"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                let signal_name = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("signal"))
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+
+                let (Some(terminal_id), Some(signal_name)) = (terminal_id, signal_name) else {
+                    return Response::error(
+                        &req.id,
+                        error_codes::INVALID_PARAMS,
+                        "terminal_id and signal are required",
+                    );
+                };
+                let Some(signal) = parse_signal(&signal_name) else {
+                    return Response::error(
+                        &req.id,
+                        error_codes::INVALID_PARAMS,
+                        &format!(
+                            "Unsupported signal {signal_name:?}; use INT, TERM, KILL, HUP, \
+                             QUIT, TSTP, CONT, USR1, USR2 or WINCH"
+                        ),
+                    );
+                };
+
+                let pty = match terminal_pty(&state, &terminal_id) {
+                    Ok(pty) => pty,
+                    Err((code, message)) => return Response::error(&req.id, code, message),
+                };
+                match pty.signal(signal) {
+                    Ok(()) => Response::success(
+                        &req.id,
+                        serde_json::json!({
+                            "terminal_id": terminal_id,
+                            "signal": signal_name,
+                        }),
+                    ),
+                    Err(e) => Response::error(
+                        &req.id,
+                        error_codes::INTERNAL_ERROR,
+                        &format!("Failed to signal terminal: {}", e),
+                    ),
+                }
+            }
+        })
+        .await;
+}
+
+/// The PTY of a running terminal, or the error code and message to answer
+/// with when there is none to talk to.
+fn terminal_pty(state: &DaemonState, terminal_id: &str) -> Result<Arc<Pty>, (i32, &'static str)> {
+    let processes = state.terminal_processes.lock().unwrap();
+    let Some(handle) = processes.get(terminal_id) else {
+        return Err((error_codes::NOT_FOUND, "No running terminal with this id"));
+    };
+    handle.pty.clone().ok_or((
+        error_codes::INVALID_REQUEST,
+        "This terminal was adopted after a daemon restart and has no input",
+    ))
+}
+
+/// Ids of the session's running terminals.
+fn session_terminal_ids(state: &DaemonState, session_id: &str) -> Vec<String> {
+    let processes = state.terminal_processes.lock().unwrap();
+    let mut ids: Vec<String> = processes
+        .iter()
+        .filter(|(_, handle)| handle.session_id == session_id)
+        .map(|(id, _)| id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn terminal_json(terminal: &Terminal, is_running: bool) -> Value {
+    serde_json::json!({
+        "terminal_id": terminal.id,
+        "session_id": terminal.session_id.as_str(),
+        "name": terminal.name,
+        "command": terminal.command,
+        "cwd": terminal.cwd,
+        "pid": terminal.pid,
+        "started_at": terminal.started_at.to_rfc3339(),
+        "finished_at": terminal.finished_at.map(|at| at.to_rfc3339()),
+        "exit_code": terminal.exit_code,
+        "is_running": is_running,
+    })
+}
+
+/// The `rows` and `cols` params, if both are given and fit a window size.
+fn pty_size_param(params: Option<&Value>) -> Option<PtySize> {
+    let dimension = |name| {
+        params
+            .and_then(|p| p.get(name))
+            .and_then(Value::as_u64)
+            .and_then(|v| u16::try_from(v).ok())
+            .filter(|v| *v > 0)
+    };
+    Some(PtySize {
+        rows: dimension("rows")?,
+        cols: dimension("cols")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_params_need_both_dimensions_in_range() {
+        let params = serde_json::json!({ "rows": 40, "cols": 120 });
+        assert_eq!(
+            pty_size_param(Some(&params)),
+            Some(PtySize {
+                rows: 40,
+                cols: 120
+            })
+        );
+        for params in [
+            serde_json::json!({ "rows": 40 }),
+            serde_json::json!({ "rows": 0, "cols": 120 }),
+            serde_json::json!({ "rows": 40, "cols": 70000 }),
+        ] {
+            assert_eq!(pty_size_param(Some(&params)), None);
+        }
+        assert_eq!(pty_size_param(None), None);
+    }
+}
@@ -10,9 +10,12 @@ pub async fn register_handlers(server: &IpcServer, state: DaemonState) {
     // Register all handler modules
     handlers::health::register(server).await;
     handlers::session::register(server, state.clone()).await;
+    handlers::subscription::register(server, state.clone()).await;
     handlers::repository::register(server, state.clone()).await;
     handlers::message::register(server, state.clone()).await;
     handlers::claude::register(server, state.clone()).await;
+    handlers::permission::register(server, state.clone()).await;
+    handlers::queue::register(server, state.clone()).await;
     handlers::terminal::register(server, state.clone()).await;
     handlers::git::register(server, state.clone()).await;
     handlers::gh::register(server, state.clone()).await;
@@ -5,4 +5,4 @@
 
 mod stream;
 
-pub use stream::handle_claude_events;
+pub use stream::{handle_claude_events, write_runtime_question};
@@ -2,20 +2,17 @@
 
 use crate::app::DaemonState;
 use crate::observability::{current_trace_context, spawn_in_current_span};
+use crate::utils::agent_watchdog::{TimeoutKind, TurnLimits, TurnTimeout, TurnWatchdog};
 use agent_session_sqlite_persist_core::{
-    CodingSessionStatus, NewMessage, SessionId, SessionWriter,
+    CodingSessionStatus, NewMessage, QuestionRuntimeState, SessionId, SessionWriter,
 };
 use claude_debug_logs::ClaudeDebugLogs;
 use claude_process_manager::{ClaudeEvent, ClaudeEventStream};
 use daemon_ipc::{Event, EventType};
-use std::sync::{
-    atomic::{AtomicI64, Ordering},
-    OnceLock,
-};
+use std::sync::OnceLock;
+use tokio::sync::{broadcast, watch};
 use tracing::{debug, error, info, warn};
 
-/// Global sequence counter for streaming events.
-static STREAM_SEQUENCE: AtomicI64 = AtomicI64::new(0);
 static CLAUDE_DEBUG_LOGS: OnceLock<ClaudeDebugLogs> = OnceLock::new();
 
 /// Handle Claude events from a claude-process-manager event stream.
@@ -24,11 +21,20 @@ static CLAUDE_DEBUG_LOGS: OnceLock<ClaudeDebugLogs> = OnceLock::new();
 /// 1. Stores raw JSON as messages via Armin
 /// 2. Updates Claude session ID when received
 /// 3. Broadcasts events to IPC subscribers
-/// 4. Manages agent status
+/// 4. Manages agent status, including questions awaiting an answer
+/// 5. Stops the process through `stop_tx` when a turn exceeds its limits
+///
+/// `turns` is set to the number of completed turns after each result event
+/// has been handled; keep-alive processes end their turns on it. `limits`
+/// holds the limits of the current turn, and a keep-alive process's next turn
+/// starts its watchdog by sending new ones.
 pub async fn handle_claude_events(
     mut stream: ClaudeEventStream,
     session_id: String,
     state: DaemonState,
+    turns: watch::Sender<u64>,
+    stop_tx: broadcast::Sender<()>,
+    mut limits: watch::Receiver<TurnLimits>,
 ) {
     info!(
         session_id = %session_id,
@@ -40,6 +46,11 @@ pub async fn handle_claude_events(
     let mut last_status: Option<CodingSessionStatus> = None;
     let mut last_error_message: Option<String> = None;
     let mut terminal_status_written = false;
+    let mut question_pending = false;
+    let mut turn_ended = false;
+    let pid = stream.pid();
+    let mut watchdog = TurnWatchdog::new(*limits.borrow_and_update());
+    let mut timed_out: Option<TurnTimeout> = None;
 
     // Start stream in running state.
     write_runtime_status_if_changed(
@@ -53,7 +64,48 @@ pub async fn handle_claude_events(
     );
 
     // Process events from the stream
-    while let Some(event) = stream.next().await {
+    loop {
+        let event = tokio::select! {
+            event = stream.next() => event,
+            Ok(()) = limits.changed() => {
+                watchdog = TurnWatchdog::new(*limits.borrow_and_update());
+                continue;
+            }
+            timeout = watchdog.expired(), if timed_out.is_none() => {
+                // Waiting on the user for a permission decision is not idling
+                if timeout.kind == TimeoutKind::Idle
+                    && !state.permissions.pending_for_session(&session_id).is_empty()
+                {
+                    watchdog.record_event();
+                    continue;
+                }
+                warn!(
+                    session_id = %session_id,
+                    reason = %timeout.reason(),
+                    "Claude turn timed out; stopping Claude"
+                );
+                let _ = stop_tx.send(());
+                timed_out = Some(timeout);
+                continue;
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+        watchdog.record_event();
+
+        // The first event after a result starts a keep-alive process's next turn
+        if turn_ended
+            && matches!(
+                event,
+                ClaudeEvent::Json { .. } | ClaudeEvent::SystemWithSessionId { .. }
+            )
+        {
+            turn_ended = false;
+            terminal_status_written = false;
+            question_pending = false;
+        }
+
         match &event {
             ClaudeEvent::Json {
                 event_type,
@@ -62,12 +114,23 @@ pub async fn handle_claude_events(
             } => {
                 event_count += 1;
 
-                append_claude_message(&state, &armin_session_id, raw, "claude_json");
+                let sequence = append_claude_message(&state, &armin_session_id, raw, "claude_json");
 
                 // Broadcast to streaming subscribers
-                broadcast_event(&state, &session_id, raw);
+                broadcast_event(&state, &session_id, raw, sequence);
 
                 if is_ask_user_question(json) {
+                    if let Some(tool_use_id) = ask_user_question_tool_use_id(json) {
+                        write_runtime_question(
+                            &state,
+                            &armin_session_id,
+                            Some(QuestionRuntimeState {
+                                tool_use_id: tool_use_id.to_string(),
+                                resolved: false,
+                            }),
+                        );
+                        question_pending = true;
+                    }
                     write_runtime_status_if_changed(
                         &state,
                         &armin_session_id,
@@ -98,7 +161,8 @@ pub async fn handle_claude_events(
             } => {
                 event_count += 1;
 
-                append_claude_message(&state, &armin_session_id, raw, "claude_system");
+                let sequence =
+                    append_claude_message(&state, &armin_session_id, raw, "claude_system");
 
                 // Update claude_session_id via Armin
                 if let Err(e) = state
@@ -109,7 +173,7 @@ pub async fn handle_claude_events(
                 }
 
                 // Broadcast to streaming subscribers
-                broadcast_event(&state, &session_id, raw);
+                broadcast_event(&state, &session_id, raw, sequence);
 
                 write_runtime_status_if_changed(
                     &state,
@@ -128,13 +192,18 @@ pub async fn handle_claude_events(
                 );
             }
 
-            ClaudeEvent::Result { is_error, raw } => {
+            ClaudeEvent::Result {
+                is_error,
+                usage,
+                raw,
+            } => {
                 event_count += 1;
 
-                append_claude_message(&state, &armin_session_id, raw, "claude_result");
+                let sequence =
+                    append_claude_message(&state, &armin_session_id, raw, "claude_result");
 
                 // Broadcast to streaming subscribers
-                broadcast_event(&state, &session_id, raw);
+                broadcast_event(&state, &session_id, raw, sequence);
 
                 if *is_error {
                     let error_message = extract_result_error_message(raw);
@@ -149,11 +218,23 @@ pub async fn handle_claude_events(
                         &mut last_error_message,
                     );
                 } else {
-                    info!(event_num = event_count, "result event (success)");
+                    info!(
+                        event_num = event_count,
+                        input_tokens = usage.map(|u| u.input_tokens),
+                        cached_input_tokens = usage.map(|u| u.cached_input_tokens),
+                        output_tokens = usage.map(|u| u.output_tokens),
+                        "result event (success)"
+                    );
+                    // An unanswered question keeps the session waiting on the user
+                    let status = if question_pending {
+                        CodingSessionStatus::Waiting
+                    } else {
+                        CodingSessionStatus::Idle
+                    };
                     write_runtime_status_if_changed(
                         &state,
                         &armin_session_id,
-                        CodingSessionStatus::Idle,
+                        status,
                         None,
                         "result-success",
                         &mut last_status,
@@ -161,6 +242,10 @@ pub async fn handle_claude_events(
                     );
                 }
                 terminal_status_written = true;
+                turn_ended = true;
+                // An idle keep-alive process waits for its next turn unwatched
+                watchdog = TurnWatchdog::new(TurnLimits::default());
+                turns.send_replace(stream.turns_completed());
             }
 
             ClaudeEvent::Stderr { line } => {
@@ -207,15 +292,36 @@ pub async fn handle_claude_events(
 
             ClaudeEvent::Stopped => {
                 info!("Claude process was stopped");
-                write_runtime_status_if_changed(
-                    &state,
-                    &armin_session_id,
-                    CodingSessionStatus::NotAvailable,
-                    None,
-                    "process-stopped",
-                    &mut last_status,
-                    &mut last_error_message,
-                );
+                match &timed_out {
+                    Some(timeout) => {
+                        let message = timeout.transcript_json();
+                        let sequence = append_claude_message(
+                            &state,
+                            &armin_session_id,
+                            &message,
+                            "agent_timeout",
+                        );
+                        broadcast_event(&state, &session_id, &message, sequence);
+                        write_runtime_status_if_changed(
+                            &state,
+                            &armin_session_id,
+                            CodingSessionStatus::Error,
+                            Some(timeout.reason()),
+                            "turn-timeout",
+                            &mut last_status,
+                            &mut last_error_message,
+                        );
+                    }
+                    None => write_runtime_status_if_changed(
+                        &state,
+                        &armin_session_id,
+                        CodingSessionStatus::NotAvailable,
+                        None,
+                        "process-stopped",
+                        &mut last_status,
+                        &mut last_error_message,
+                    ),
+                }
                 terminal_status_written = true;
             }
         }
@@ -240,17 +346,30 @@ pub async fn handle_claude_events(
         );
     }
 
-    // Remove from running processes
-    {
+    // Remove from running processes; a finished keep-alive turn has
+    // already been removed, and the session may have moved on to another turn
+    if !stream.is_keep_alive() || !turn_ended {
         let mut processes = state.claude_processes.lock().unwrap();
         processes.remove(&session_id);
-        info!(session_id = %session_id, "Cleaned up Claude process");
     }
+    {
+        let mut keep_alive = state.claude_keep_alive.lock().unwrap();
+        if keep_alive
+            .get(&session_id)
+            .is_some_and(|process| process.pid == pid)
+        {
+            keep_alive.remove(&session_id);
+        }
+    }
+    info!(session_id = %session_id, "Cleaned up Claude process");
 }
 
 /// Broadcast a raw JSON event to IPC subscribers.
-fn broadcast_event(state: &DaemonState, session_id: &str, raw_json: &str) {
-    let seq = STREAM_SEQUENCE.fetch_add(1, Ordering::SeqCst);
+///
+/// `sequence` is the sequence of the message the event was stored as; events
+/// that failed to store carry the session's latest committed sequence instead.
+fn broadcast_event(state: &DaemonState, session_id: &str, raw_json: &str, sequence: Option<i64>) {
+    let seq = sequence.unwrap_or_else(|| state.armin.sink().last_sequence(session_id));
     let claude_debug_logs = get_claude_debug_logs();
     if claude_debug_logs.is_enabled() {
         let claude_type = ClaudeDebugLogs::extract_claude_type(raw_json);
@@ -353,12 +472,39 @@ fn write_runtime_status_if_changed(
     }
 }
 
+/// Records the session's structured question in the runtime status envelope.
+pub fn write_runtime_question(
+    state: &DaemonState,
+    session_id: &SessionId,
+    question: Option<QuestionRuntimeState>,
+) {
+    let device_id = {
+        let guard = state.device_id.lock().unwrap();
+        guard.clone()
+    };
+    let Some(device_id) = device_id else {
+        error!(
+            session_id = %session_id,
+            "Skipping runtime question write: local device_id unavailable"
+        );
+        return;
+    };
+
+    if let Err(e) = state
+        .armin
+        .update_runtime_question(session_id, &device_id, question)
+    {
+        warn!(session_id = %session_id, error = %e, "Failed to write runtime question");
+    }
+}
+
+/// Stores a Claude event as a session message, returning its sequence number.
 fn append_claude_message(
     state: &DaemonState,
     session_id: &SessionId,
     raw: &str,
     event_kind: &'static str,
-) {
+) -> Option<i64> {
     let _guard = tracing::info_span!(
         "armin.append",
         session_id = %session_id,
@@ -366,13 +512,17 @@ fn append_claude_message(
     )
     .entered();
 
-    if let Err(e) = state.armin.append(
+    match state.armin.append(
         session_id,
         NewMessage {
             content: raw.to_string(),
         },
     ) {
-        warn!(error = %e, message_kind = event_kind, "Failed to store Claude event");
+        Ok(message) => Some(message.sequence_number),
+        Err(e) => {
+            warn!(error = %e, message_kind = event_kind, "Failed to store Claude event");
+            None
+        }
     }
 }
 
@@ -389,6 +539,20 @@ fn is_ask_user_question(json: &serde_json::Value) -> bool {
         .unwrap_or(false)
 }
 
+/// Tool use id of the first `AskUserQuestion` block in an assistant message.
+fn ask_user_question_tool_use_id(json: &serde_json::Value) -> Option<&str> {
+    json.get("message")?
+        .get("content")?
+        .as_array()?
+        .iter()
+        .find(|block| {
+            block.get("type").and_then(|v| v.as_str()) == Some("tool_use")
+                && block.get("name").and_then(|v| v.as_str()) == Some("AskUserQuestion")
+        })?
+        .get("id")?
+        .as_str()
+}
+
 fn extract_result_error_message(raw_json: &str) -> String {
     let Ok(json) = serde_json::from_str::<serde_json::Value>(raw_json) else {
         return "Claude reported an error result".to_string();
@@ -416,7 +580,9 @@ fn extract_result_error_message(raw_json: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{extract_result_error_message, is_ask_user_question};
+    use super::{
+        ask_user_question_tool_use_id, extract_result_error_message, is_ask_user_question,
+    };
 
     #[test]
     fn ask_user_question_detected_from_tool_use_block() {
@@ -454,6 +620,26 @@ mod tests {
         assert!(!is_ask_user_question(&json));
     }
 
+    #[test]
+    fn ask_user_question_tool_use_id_read_from_block() {
+        let json = serde_json::json!({
+            "type": "assistant",
+            "message": {
+                "content": [
+                    { "type": "text", "text": "One question first." },
+                    {
+                        "type": "tool_use",
+                        "id": "toolu_ask",
+                        "name": "AskUserQuestion",
+                        "input": { "questions": [] }
+                    }
+                ]
+            }
+        });
+
+        assert_eq!(ask_user_question_tool_use_id(&json), Some("toolu_ask"));
+    }
+
     #[test]
     fn extract_error_message_from_content_field() {
         let raw = r#"{"type":"result","is_error":true,"content":"Operation failed"}"#;
@@ -1,5 +1,9 @@
 //! Terminal process management.
 
+mod pty;
+mod shell;
 mod stream;
 
-pub use stream::handle_terminal_process;
+pub use pty::{parse_signal, Pty, PtySize, DEFAULT_SIZE};
+pub use shell::resolve_shell;
+pub use stream::{handle_terminal_process, TerminalHandle};
@@ -0,0 +1,307 @@
+//! Pseudo-terminals for interactive terminal runs.
+//!
+//! A command runs as the leader of a new session with the PTY's slave as its
+//! controlling terminal and stdio, so it sees a real TTY: line editing,
+//! colors, job control and the window size all work. The daemon keeps the
+//! master to read output, write input, resize and signal.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::process::Stdio;
+use std::ptr;
+use tokio::io::unix::AsyncFd;
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+/// Window size used when the client sends none.
+pub const DEFAULT_SIZE: PtySize = PtySize { rows: 24, cols: 80 };
+
+/// A terminal window size in character cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtySize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl PtySize {
+    fn winsize(self) -> libc::winsize {
+        libc::winsize {
+            ws_row: self.rows,
+            ws_col: self.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+}
+
+/// The master side of a PTY a command was spawned on.
+pub struct Pty {
+    master: AsyncFd<OwnedFd>,
+    /// Serializes writes so concurrent inputs are not interleaved.
+    write_lock: Mutex<()>,
+    /// Process group the command leads.
+    pgid: libc::pid_t,
+}
+
+impl Pty {
+    /// Spawn `command` on a new PTY of `size`.
+    ///
+    /// `command` is consumed so its copies of the slave are closed once the
+    /// child has its own: reads only report EOF after every slave is closed.
+    pub fn spawn(mut command: Command, size: PtySize) -> io::Result<(Child, Self)> {
+        let (master, slave) = open(size)?;
+        command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        // setsid and ioctl are async-signal-safe; stdio is already the slave
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        drop(command);
+
+        set_nonblocking(&master)?;
+        let pgid = child.id().map_or(0, |pid| pid as libc::pid_t);
+        let pty = Self {
+            master: AsyncFd::new(master)?,
+            write_lock: Mutex::new(()),
+            pgid,
+        };
+        Ok((child, pty))
+    }
+
+    /// Read a chunk of output. Returns `Ok(0)` once the command and
+    /// everything it started have closed the terminal.
+    pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = self.master.readable().await?;
+            let result = guard.try_io(|fd| {
+                cvt(unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) })
+            });
+            match result {
+                // Linux reports a closed slave as EIO rather than EOF
+                Ok(Err(error)) if error.raw_os_error() == Some(libc::EIO) => return Ok(0),
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Write input as if typed at the terminal.
+    pub async fn write_all(&self, mut data: &[u8]) -> io::Result<()> {
+        let _lock = self.write_lock.lock().await;
+        while !data.is_empty() {
+            let mut guard = self.master.writable().await?;
+            let result = guard.try_io(|fd| {
+                cvt(unsafe { libc::write(fd.as_raw_fd(), data.as_ptr().cast(), data.len()) })
+            });
+            if let Ok(written) = result {
+                data = &data[written?..];
+            }
+        }
+        Ok(())
+    }
+
+    /// Change the window size; the foreground job gets `SIGWINCH`.
+    pub fn resize(&self, size: PtySize) -> io::Result<()> {
+        let winsize = size.winsize();
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ as _, &winsize) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Send `signal` to the terminal's foreground process group, as typing
+    /// the matching key would, or to the command's own group if there is
+    /// none.
+    pub fn signal(&self, signal: libc::c_int) -> io::Result<()> {
+        let foreground = unsafe { libc::tcgetpgrp(self.master.as_raw_fd()) };
+        if foreground > 0 {
+            return killpg(foreground, signal);
+        }
+        self.signal_group(signal)
+    }
+
+    /// Send `signal` to the command's process group, which includes what it
+    /// started in the background and outlives the command itself.
+    pub fn signal_group(&self, signal: libc::c_int) -> io::Result<()> {
+        // Group 0 would be the daemon's own
+        if self.pgid <= 0 {
+            return Err(io::Error::from_raw_os_error(libc::ESRCH));
+        }
+        killpg(self.pgid, signal)
+    }
+}
+
+/// The signals `terminal.signal` accepts, named with or without `SIG`.
+pub fn parse_signal(name: &str) -> Option<libc::c_int> {
+    let name = name.to_ascii_uppercase();
+    let signal = match name.strip_prefix("SIG").unwrap_or(&name) {
+        "INT" => libc::SIGINT,
+        "TERM" => libc::SIGTERM,
+        "KILL" => libc::SIGKILL,
+        "HUP" => libc::SIGHUP,
+        "QUIT" => libc::SIGQUIT,
+        "TSTP" => libc::SIGTSTP,
+        "CONT" => libc::SIGCONT,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "WINCH" => libc::SIGWINCH,
+        _ => return None,
+    };
+    Some(signal)
+}
+
+fn open(size: PtySize) -> io::Result<(OwnedFd, OwnedFd)> {
+    let (mut master, mut slave) = (-1, -1);
+    let mut winsize = size.winsize();
+    let opened = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::addr_of_mut!(winsize),
+        )
+    };
+    if opened == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+    // Keep both out of other children; the command gets the slave as stdio
+    for fd in [&master, &slave] {
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok((master, slave))
+}
+
+fn killpg(group: libc::pid_t, signal: libc::c_int) -> io::Result<()> {
+    if unsafe { libc::killpg(group, signal) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn set_nonblocking(fd: &OwnedFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) };
+    if flags == -1
+        || unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn cvt(result: isize) -> io::Result<usize> {
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(result as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read_to_end(pty: &Pty) -> String {
+        let mut output = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            let read = pty.read(&mut buf).await.unwrap();
+            if read == 0 {
+                return String::from_utf8_lossy(&output).into_owned();
+            }
+            output.extend_from_slice(&buf[..read]);
+        }
+    }
+
+    #[tokio::test]
+    async fn command_sees_a_terminal_and_reads_input() {
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "test -t 0 && echo tty; stty size; read line; echo \"got $line\"",
+        ]);
+        let (mut child, pty) = Pty::spawn(
+            command,
+            PtySize {
+                rows: 30,
+                cols: 100,
+            },
+        )
+        .unwrap();
+
+        pty.write_all(b"hello\n").await.unwrap();
+        let output = read_to_end(&pty).await;
+
+        assert!(output.contains("tty"), "{output:?}");
+        assert!(output.contains("30 100"), "{output:?}");
+        assert!(output.contains("got hello"), "{output:?}");
+        assert!(child.wait().await.unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn resize_and_signal_reach_the_command() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "read line; stty size; sleep 30"]);
+        let (mut child, pty) = Pty::spawn(command, DEFAULT_SIZE).unwrap();
+
+        pty.resize(PtySize {
+            rows: 40,
+            cols: 120,
+        })
+        .unwrap();
+        pty.write_all(b"\n").await.unwrap();
+        let mut output = String::new();
+        let mut buf = [0u8; 1024];
+        while !output.contains("40 120") {
+            let read = pty.read(&mut buf).await.unwrap();
+            assert_ne!(read, 0, "{output:?}");
+            output.push_str(&String::from_utf8_lossy(&buf[..read]));
+        }
+
+        pty.signal(libc::SIGTERM).unwrap();
+        assert!(!child.wait().await.unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn group_signal_reaches_background_processes() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "trap '' HUP; sleep 30 & echo started"]);
+        let (mut child, pty) = Pty::spawn(command, DEFAULT_SIZE).unwrap();
+        assert!(child.wait().await.unwrap().success());
+
+        // The backgrounded sleep survives the hangup and holds the terminal open
+        let mut buf = [0u8; 1024];
+        let mut output = String::new();
+        while !output.contains("started") {
+            let read = pty.read(&mut buf).await.unwrap();
+            assert_ne!(read, 0, "{output:?}");
+            output.push_str(&String::from_utf8_lossy(&buf[..read]));
+        }
+        let still_open =
+            tokio::time::timeout(std::time::Duration::from_millis(200), pty.read(&mut buf)).await;
+        assert!(still_open.is_err());
+
+        pty.signal_group(libc::SIGKILL).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), read_to_end(&pty))
+            .await
+            .expect("the terminal closes once the group is gone");
+    }
+
+    #[test]
+    fn signals_parse_with_or_without_prefix() {
+        assert_eq!(parse_signal("SIGINT"), Some(libc::SIGINT));
+        assert_eq!(parse_signal("int"), Some(libc::SIGINT));
+        assert_eq!(parse_signal("TSTP"), Some(libc::SIGTSTP));
+        assert_eq!(parse_signal("SIGSEGV"), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! The shell that runs terminal commands, setup hooks and agent tools.
+
+use crate::utils::repository_config::RepositoryConfig;
+use daemon_config_and_utils::Config;
+use login_shell::Shell;
+
+/// The shell for commands in a repository: its `shell`, then the daemon's,
+/// then the user's login shell.
+pub fn resolve_shell(config: &Config, repository: Option<&RepositoryConfig>) -> Shell {
+    Shell::resolve(
+        repository
+            .and_then(|repository| repository.shell.as_deref())
+            .or(config.shell.as_deref()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn repository_shell_overrides_the_daemon_shell() {
+        let config = Config {
+            shell: Some("/bin/bash".to_string()),
+            ..Config::default()
+        };
+        let repository = RepositoryConfig {
+            shell: Some("/usr/bin/fish".to_string()),
+            ..RepositoryConfig::default()
+        };
+
+        assert_eq!(
+            resolve_shell(&config, Some(&repository)).path(),
+            Path::new("/usr/bin/fish")
+        );
+        assert_eq!(
+            resolve_shell(&config, Some(&RepositoryConfig::default())).path(),
+            Path::new("/bin/bash")
+        );
+        assert_eq!(resolve_shell(&Config::default(), None), *Shell::login());
+    }
+}
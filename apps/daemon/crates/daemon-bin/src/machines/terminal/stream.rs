@@ -1,116 +1,143 @@
 //! Terminal process output streaming and event handling.
 
+use super::pty::Pty;
 use crate::app::DaemonState;
-use crate::observability::spawn_in_current_span;
-use agent_session_sqlite_persist_core::{NewMessage, SessionId, SessionWriter};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use crate::observability::current_trace_context;
+use crate::utils::sandbox::SandboxViolation;
+use agent_session_sqlite_persist_core::{NewMessage, SessionId, SessionWriter, Terminal};
+use daemon_ipc::{Event, EventType};
+use serde_json::Value;
+use std::sync::Arc;
 use tokio::process::Child;
 use tokio::sync::broadcast;
+use tokio::time::{timeout, Duration};
 use tracing::{debug, error, info, warn};
 
-/// Handle terminal process output, storing events via Armin.
+/// Bytes read from the PTY at a time; each read is stored as one chunk.
+const READ_CHUNK_SIZE: usize = 8192;
+
+/// Longest partial line kept while looking for sandbox violations, so a
+/// progress bar that never prints a newline does not grow it unbounded.
+const MAX_PENDING_LINE: usize = 4096;
+
+/// How long a stopped command gets to exit on `SIGTERM` before its process
+/// group is killed.
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// How long output is still read once the command has exited, while
+/// processes it left in the background keep the terminal open.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Output lines kept in the transcript summary of a finished terminal.
+const SUMMARY_TAIL_LINES: usize = 20;
+
+/// Most output held back for the summary, so a long line cannot grow it
+/// unbounded.
+const MAX_SUMMARY_TAIL: usize = 8192;
+
+/// A running terminal: its session, how to stop it, and its PTY for input.
+pub struct TerminalHandle {
+    pub session_id: String,
+    pub stop_tx: broadcast::Sender<()>,
+    /// `None` for a process adopted after a daemon restart, whose terminal
+    /// went with the previous daemon.
+    pub pty: Option<Arc<Pty>>,
+}
+
+/// Handle terminal process output, storing it via Armin.
+///
+/// Each raw chunk read from the PTY, escape sequences and carriage returns
+/// included, with stdout and stderr interleaved as the command wrote them,
+/// goes to the terminal's own output store and is broadcast to the session
+/// as a `TerminalOutput` event. The transcript only gets a summary once the
+/// command finishes:
+/// - `{"type": "terminal_finished", "terminal_id": "...", "name": ..., "command": "...", "exit_code": N, "output_bytes": N, "tail": "..."}`
 ///
-/// Terminal output is stored as JSON messages with format:
-/// - `{"type": "terminal_output", "stream": "stdout"|"stderr", "content": "..."}`
-/// - `{"type": "terminal_finished", "exit_code": N}`
+/// A sandboxed process also gets a `sandbox_violation` message for each
+/// output line reporting a sandbox refusal. The terminal's record gets the
+/// exit code once the command finishes, even if processes it started in the
+/// background still hold the terminal open. Stopping the terminal stops its
+/// whole process group.
 pub async fn handle_terminal_process(
     mut child: Child,
-    session_id: String,
+    pty: Arc<Pty>,
+    terminal: Terminal,
     state: DaemonState,
     stop_tx: broadcast::Sender<()>,
+    sandboxed: bool,
 ) {
+    let terminal_id = terminal.id.as_str();
+    let session_id = terminal.session_id.as_str();
     info!(
         session_id = %session_id,
+        terminal_id = %terminal_id,
         "Starting to handle terminal process"
     );
 
     let mut stop_rx = stop_tx.subscribe();
-
-    let armin_session_id = SessionId::from_string(&session_id);
-
-    let stdout = match child.stdout.take() {
-        Some(s) => s,
-        None => {
-            error!("Failed to get stdout from terminal process");
-            return;
-        }
-    };
-
-    let stderr = child.stderr.take();
-
-    let mut stdout_reader = BufReader::new(stdout).lines();
-
-    // Spawn stderr reader task
-    let armin_for_stderr = state.armin.clone();
-    let session_id_for_stderr = armin_session_id.clone();
-    let stderr_task = if let Some(stderr) = stderr {
-        Some(spawn_in_current_span(async move {
-            let mut stderr_reader = BufReader::new(stderr).lines();
-            while let Ok(Some(line)) = stderr_reader.next_line().await {
-                // Store stderr output as message
-                let content = serde_json::json!({
-                    "type": "terminal_output",
-                    "stream": "stderr",
-                    "content": line,
-                })
-                .to_string();
-
-                if let Err(e) =
-                    armin_for_stderr.append(&session_id_for_stderr, NewMessage { content })
-                {
-                    tracing::warn!(error = %e, "Failed to store terminal stderr output");
-                }
-            }
-        }))
-    } else {
-        None
+    let mut output = TerminalOutput {
+        state: &state,
+        terminal_id,
+        session_id,
+        armin_session_id: SessionId::from_string(session_id),
+        sandboxed,
+        decoder: Utf8Chunks::default(),
+        pending_line: String::new(),
+        tail: String::new(),
+        output_bytes: 0,
     };
+    let mut buf = vec![0u8; READ_CHUNK_SIZE];
 
+    let mut exited = false;
     loop {
         tokio::select! {
-            // Check for stop signal
             _ = stop_rx.recv() => {
-                info!("Stop signal received - killing process");
-                let _ = child.kill().await;
+                info!("Stop signal received - stopping process group");
+                stop_process_group(&mut child, &pty).await;
                 break;
             }
 
-            // Read next line from stdout
-            line_result = stdout_reader.next_line() => {
-                match line_result {
-                    Ok(Some(line)) => {
-                        // Store stdout output as message
-                        let content = serde_json::json!({
-                            "type": "terminal_output",
-                            "stream": "stdout",
-                            "content": line,
-                        }).to_string();
-
-                        if let Err(e) = state.armin.append(
-                            &armin_session_id,
-                            NewMessage { content },
-                        ) {
-                            warn!(error = %e, "Failed to store terminal stdout output");
-                        }
-                    }
-                    Ok(None) => {
-                        // EOF - process finished
-                        debug!("Terminal stdout closed (EOF)");
-                        break;
-                    }
-                    Err(e) => {
-                        error!(error = %e, "Error reading terminal stdout");
-                        break;
-                    }
-                }
+            _ = child.wait() => {
+                exited = true;
+                break;
             }
+
+            read = pty.read(&mut buf) => match read {
+                Ok(0) => {
+                    debug!("Terminal closed (EOF)");
+                    break;
+                }
+                Ok(read) => output.record(&buf[..read]).await,
+                Err(e) => {
+                    error!(error = %e, "Error reading terminal output");
+                    break;
+                }
+            },
         }
     }
 
-    // Wait for stderr task to complete
-    if let Some(task) = stderr_task {
-        let _ = task.await;
+    // Whatever the command wrote before exiting is still buffered in the PTY.
+    // Processes it left in the background may hold the terminal open, so
+    // their output is only read for a moment.
+    if exited {
+        let drain = async {
+            loop {
+                match pty.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => output.record(&buf[..read]).await,
+                }
+            }
+        };
+        if timeout(OUTPUT_DRAIN_TIMEOUT, drain).await.is_err() {
+            debug!("Terminal still held open by background processes");
+        }
     }
+    let TerminalOutput {
+        armin_session_id,
+        output_bytes,
+        tail,
+        ..
+    } = output;
 
     // Wait for process to finish and get exit code
     let exit_code = match child.wait().await {
@@ -132,10 +159,15 @@ pub async fn handle_terminal_process(
         }
     };
 
-    // Store finished event
+    // Summarize the run in the transcript; the output stays in its store
     let content = serde_json::json!({
         "type": "terminal_finished",
+        "terminal_id": terminal_id,
+        "name": terminal.name,
+        "command": terminal.command,
         "exit_code": exit_code,
+        "output_bytes": output_bytes,
+        "tail": last_lines(&tail, SUMMARY_TAIL_LINES),
     })
     .to_string();
 
@@ -145,11 +177,235 @@ pub async fn handle_terminal_process(
     {
         warn!(error = %e, "Failed to store terminal finished event");
     }
+    if let Err(e) = state.armin.finish_terminal(terminal_id, Some(exit_code)) {
+        warn!(error = %e, "Failed to record terminal exit code");
+    }
+    broadcast_terminal_event(
+        &state,
+        session_id,
+        EventType::TerminalFinished,
+        serde_json::json!({
+            "terminal_id": terminal_id,
+            "exit_code": exit_code,
+        }),
+    )
+    .await;
 
     // Remove from running processes
     {
         let mut processes = state.terminal_processes.lock().unwrap();
-        processes.remove(&session_id);
-        info!(session_id = %session_id, "Cleaned up terminal process");
+        processes.remove(terminal_id);
+        info!(terminal_id = %terminal_id, "Cleaned up terminal process");
+    }
+}
+
+/// Stop everything the command started: `SIGTERM` to its process group,
+/// then `SIGKILL` to whatever is left after a grace period.
+async fn stop_process_group(child: &mut Child, pty: &Pty) {
+    if let Err(e) = pty.signal_group(libc::SIGTERM) {
+        debug!(error = %e, "Failed to send SIGTERM to terminal process group");
+    }
+    if timeout(STOP_GRACE_PERIOD, child.wait()).await.is_err() {
+        info!("Terminal process ignored SIGTERM - killing it");
+    }
+    // Background processes may outlive a leader that exited on SIGTERM
+    if let Err(e) = pty.signal_group(libc::SIGKILL) {
+        if e.raw_os_error() != Some(libc::ESRCH) {
+            warn!(error = %e, "Failed to kill terminal process group");
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// Output of a running terminal as it is recorded: stored, broadcast and
+/// checked for sandbox violations.
+struct TerminalOutput<'a> {
+    state: &'a DaemonState,
+    terminal_id: &'a str,
+    session_id: &'a str,
+    armin_session_id: SessionId,
+    sandboxed: bool,
+    decoder: Utf8Chunks,
+    pending_line: String,
+    tail: String,
+    output_bytes: u64,
+}
+
+impl TerminalOutput<'_> {
+    async fn record(&mut self, bytes: &[u8]) {
+        let chunk = self.decoder.push(bytes);
+        if chunk.is_empty() {
+            return;
+        }
+
+        let offset = match self
+            .state
+            .armin
+            .append_terminal_output(self.terminal_id, &chunk)
+        {
+            Ok(offset) => offset,
+            Err(e) => {
+                warn!(error = %e, "Failed to store terminal output");
+                self.output_bytes
+            }
+        };
+        self.output_bytes = offset + chunk.len() as u64;
+        broadcast_terminal_event(
+            self.state,
+            self.session_id,
+            EventType::TerminalOutput,
+            serde_json::json!({
+                "terminal_id": self.terminal_id,
+                "offset": offset,
+                "content": chunk,
+            }),
+        )
+        .await;
+        push_tail(&mut self.tail, &chunk);
+
+        if self.sandboxed {
+            for line in complete_lines(&mut self.pending_line, &chunk) {
+                let Some(violation) = SandboxViolation::from_stderr(&line) else {
+                    continue;
+                };
+                let content = violation.transcript_json();
+                if let Err(e) = self
+                    .state
+                    .armin
+                    .append(&self.armin_session_id, NewMessage { content })
+                {
+                    warn!(error = %e, "Failed to store sandbox violation");
+                }
+            }
+        }
+    }
+}
+
+/// Broadcast a terminal event to the session's subscribers. Awaited rather
+/// than spawned, so output chunks arrive in order.
+async fn broadcast_terminal_event(
+    state: &DaemonState,
+    session_id: &str,
+    event_type: EventType,
+    data: Value,
+) {
+    let seq = state.armin.sink().last_sequence(session_id);
+    let mut event = Event::new(event_type, session_id, data, seq);
+    if let Some(trace_context) = current_trace_context() {
+        event = event.with_context(trace_context);
+    }
+    state
+        .subscriptions
+        .broadcast_or_create(session_id, event)
+        .await;
+}
+
+/// Append `chunk` to the output held back for the summary, dropping the
+/// oldest output beyond `MAX_SUMMARY_TAIL`.
+fn push_tail(tail: &mut String, chunk: &str) {
+    tail.push_str(chunk);
+    if tail.len() > MAX_SUMMARY_TAIL {
+        let mut start = tail.len() - MAX_SUMMARY_TAIL;
+        while !tail.is_char_boundary(start) {
+            start += 1;
+        }
+        tail.drain(..start);
+    }
+}
+
+/// The last `count` lines of `output`, ignoring a trailing line ending.
+fn last_lines(output: &str, count: usize) -> &str {
+    let output = output.trim_end_matches(['\r', '\n']);
+    let start = output
+        .rmatch_indices('\n')
+        .nth(count.saturating_sub(1))
+        .map_or(0, |(index, _)| index + 1);
+    &output[start..]
+}
+
+/// Decodes output chunks as UTF-8, holding back a character split across
+/// reads until the rest of it arrives. Invalid bytes become U+FFFD.
+#[derive(Default)]
+struct Utf8Chunks {
+    pending: Vec<u8>,
+}
+
+impl Utf8Chunks {
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let mut end = self.pending.len();
+        let mut offset = 0;
+        while let Err(error) = std::str::from_utf8(&self.pending[offset..]) {
+            match error.error_len() {
+                Some(invalid) => offset += error.valid_up_to() + invalid,
+                None => {
+                    end = offset + error.valid_up_to();
+                    break;
+                }
+            }
+        }
+        let rest = self.pending.split_off(end);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+}
+
+/// Append `chunk` to `pending` and take the lines it completes, without
+/// their line endings.
+fn complete_lines(pending: &mut String, chunk: &str) -> Vec<String> {
+    pending.push_str(chunk);
+    let Some(last_newline) = pending.rfind('\n') else {
+        if pending.len() > MAX_PENDING_LINE {
+            pending.clear();
+        }
+        return Vec::new();
+    };
+    let rest = pending.split_off(last_newline + 1);
+    let lines = pending
+        .lines()
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect();
+    *pending = rest;
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_characters_wait_for_their_remaining_bytes() {
+        let mut decoder = Utf8Chunks::default();
+        let bytes = "naïve ✓".as_bytes();
+        let split = bytes.len() - 1;
+
+        assert_eq!(decoder.push(&bytes[..split]), "naïve ");
+        assert_eq!(decoder.push(&bytes[split..]), "✓");
+        assert_eq!(decoder.push(b"a\xffb\xe2"), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn lines_are_taken_once_complete() {
+        let mut pending = String::new();
+
+        assert!(complete_lines(&mut pending, "touch: /etc/x: Read-o").is_empty());
+        assert_eq!(
+            complete_lines(&mut pending, "nly file system\r\nnext"),
+            ["touch: /etc/x: Read-only file system"]
+        );
+        assert_eq!(pending, "next");
+    }
+
+    #[test]
+    fn summary_keeps_the_last_lines() {
+        let mut tail = String::new();
+        for line in 0..1000 {
+            push_tail(&mut tail, &format!("line {line}\r\n"));
+        }
+
+        assert!(tail.len() <= MAX_SUMMARY_TAIL);
+        assert_eq!(last_lines(&tail, 2), "line 998\r\nline 999");
+        assert_eq!(last_lines("only\n", SUMMARY_TAIL_LINES), "only");
     }
 }
@@ -43,6 +43,16 @@ enum Commands {
     Stop,
     /// Check daemon status
     Status,
+    /// Serve the tool permission prompt over MCP stdio (launched by agents)
+    #[command(name = "permission-mcp", hide = true)]
+    PermissionMcp {
+        /// Daemon IPC socket that permission requests are forwarded to
+        #[arg(long)]
+        socket: String,
+        /// Session the agent is running in
+        #[arg(long)]
+        session_id: String,
+    },
 }
 
 #[tokio::main]
@@ -73,6 +83,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Some(Commands::Stop) => app::stop_daemon(&paths).await,
         Some(Commands::Status) => app::check_status(&paths).await,
+        Some(Commands::PermissionMcp { socket, session_id }) => {
+            app::run_permission_mcp(&socket, &session_id).await
+        }
     };
 
     if main_owns_shutdown || result.is_err() {
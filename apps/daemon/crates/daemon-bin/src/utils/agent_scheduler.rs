@@ -0,0 +1,367 @@
+//! Daemon-wide limits on concurrently running agent processes.
+//!
+//! Every agent turn asks the scheduler for a slot before its process is
+//! spawned and holds the slot until the process exits. Requests beyond the
+//! global or per-repository limit wait in a queue ordered by priority (higher
+//! first), then arrival. A waiting request whose repository is at its limit
+//! does not hold back requests for other repositories.
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, watch};
+
+/// Concurrency limits enforced by the scheduler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SchedulerLimits {
+    pub max_concurrent: usize,
+    pub max_per_repository: usize,
+}
+
+/// An agent turn asking to run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlotRequest {
+    pub session_id: String,
+    pub repository_id: String,
+    /// Higher priorities are admitted first; equal priorities in arrival order.
+    pub priority: i64,
+}
+
+/// Point-in-time view of the scheduler, published after every change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SchedulerSnapshot {
+    pub running: Vec<SlotRequest>,
+    /// Waiting requests in admission order; position is index + 1.
+    pub waiting: Vec<SlotRequest>,
+}
+
+impl SchedulerSnapshot {
+    /// 1-based queue position of a waiting session.
+    pub fn position(&self, session_id: &str) -> Option<usize> {
+        self.waiting
+            .iter()
+            .position(|request| request.session_id == session_id)
+            .map(|index| index + 1)
+    }
+}
+
+/// Outcome of asking for a slot.
+pub enum Admission {
+    /// A slot was free; the agent may start now.
+    Started(AgentSlot),
+    /// The request is queued; the receiver yields the slot once admitted and
+    /// errors if the request is cancelled.
+    Waiting {
+        position: usize,
+        slot: oneshot::Receiver<AgentSlot>,
+    },
+}
+
+/// A held process slot, released when dropped.
+pub struct AgentSlot {
+    scheduler: AgentScheduler,
+    ticket: u64,
+}
+
+impl Drop for AgentSlot {
+    fn drop(&mut self) {
+        self.scheduler.release(self.ticket);
+    }
+}
+
+struct Waiter {
+    ticket: u64,
+    request: SlotRequest,
+    responder: oneshot::Sender<AgentSlot>,
+}
+
+struct SchedulerInner {
+    limits: SchedulerLimits,
+    next_ticket: u64,
+    running: Vec<(u64, SlotRequest)>,
+    /// Kept in admission order.
+    waiting: Vec<Waiter>,
+}
+
+impl SchedulerInner {
+    fn running_in_repository(&self, repository_id: &str) -> usize {
+        self.running
+            .iter()
+            .filter(|(_, request)| request.repository_id == repository_id)
+            .count()
+    }
+
+    /// Move every waiter that fits within the limits to running.
+    fn admit_ready(&mut self) -> Vec<(u64, oneshot::Sender<AgentSlot>)> {
+        let mut admitted = Vec::new();
+        let mut index = 0;
+        while index < self.waiting.len() && self.running.len() < self.limits.max_concurrent {
+            let repository_id = &self.waiting[index].request.repository_id;
+            if self.running_in_repository(repository_id) >= self.limits.max_per_repository {
+                index += 1;
+                continue;
+            }
+            let waiter = self.waiting.remove(index);
+            self.running.push((waiter.ticket, waiter.request));
+            admitted.push((waiter.ticket, waiter.responder));
+        }
+        admitted
+    }
+
+    fn snapshot(&self) -> SchedulerSnapshot {
+        SchedulerSnapshot {
+            running: self
+                .running
+                .iter()
+                .map(|(_, request)| request.clone())
+                .collect(),
+            waiting: self
+                .waiting
+                .iter()
+                .map(|waiter| waiter.request.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Thread-safe agent process scheduler.
+#[derive(Clone)]
+pub struct AgentScheduler {
+    inner: Arc<Mutex<SchedulerInner>>,
+    changes: Arc<watch::Sender<SchedulerSnapshot>>,
+}
+
+impl AgentScheduler {
+    /// Create a scheduler with the given limits.
+    pub fn new(limits: SchedulerLimits) -> Self {
+        let (changes, _) = watch::channel(SchedulerSnapshot::default());
+        Self {
+            inner: Arc::new(Mutex::new(SchedulerInner {
+                limits,
+                next_ticket: 0,
+                running: Vec::new(),
+                waiting: Vec::new(),
+            })),
+            changes: Arc::new(changes),
+        }
+    }
+
+    /// The limits being enforced.
+    pub fn limits(&self) -> SchedulerLimits {
+        self.inner.lock().unwrap().limits
+    }
+
+    /// Ask for a slot, starting immediately if the limits allow.
+    pub fn request(&self, request: SlotRequest) -> Admission {
+        let (responder, mut receiver) = oneshot::channel();
+        let (admitted, position) = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.next_ticket += 1;
+            let ticket = inner.next_ticket;
+            let index = inner
+                .waiting
+                .iter()
+                .position(|waiter| waiter.request.priority < request.priority)
+                .unwrap_or(inner.waiting.len());
+            inner.waiting.insert(
+                index,
+                Waiter {
+                    ticket,
+                    request,
+                    responder,
+                },
+            );
+            let admitted = inner.admit_ready();
+            let position = inner
+                .waiting
+                .iter()
+                .position(|waiter| waiter.ticket == ticket)
+                .map(|index| index + 1);
+            (admitted, position)
+        };
+        self.deliver(admitted);
+
+        match position {
+            Some(position) => Admission::Waiting {
+                position,
+                slot: receiver,
+            },
+            None => Admission::Started(
+                receiver
+                    .try_recv()
+                    .expect("admitted request has its slot delivered"),
+            ),
+        }
+    }
+
+    /// Drop a session's waiting requests, returning whether any were waiting.
+    pub fn cancel_session(&self, session_id: &str) -> bool {
+        let (cancelled, admitted) = {
+            let mut inner = self.inner.lock().unwrap();
+            let before = inner.waiting.len();
+            inner
+                .waiting
+                .retain(|waiter| waiter.request.session_id != session_id);
+            let cancelled = inner.waiting.len() != before;
+            (cancelled, inner.admit_ready())
+        };
+        if cancelled {
+            self.deliver(admitted);
+        }
+        cancelled
+    }
+
+    /// Whether the session is waiting for or holding a slot.
+    pub fn has_session(&self, session_id: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .running
+            .iter()
+            .any(|(_, request)| request.session_id == session_id)
+            || inner
+                .waiting
+                .iter()
+                .any(|waiter| waiter.request.session_id == session_id)
+    }
+
+    /// Current running and waiting requests.
+    pub fn snapshot(&self) -> SchedulerSnapshot {
+        self.inner.lock().unwrap().snapshot()
+    }
+
+    /// Watch the scheduler; the value changes after every admission,
+    /// release and cancellation.
+    pub fn subscribe(&self) -> watch::Receiver<SchedulerSnapshot> {
+        self.changes.subscribe()
+    }
+
+    fn release(&self, ticket: u64) {
+        let admitted = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.running.retain(|(running, _)| *running != ticket);
+            inner.admit_ready()
+        };
+        self.deliver(admitted);
+    }
+
+    /// Hand out slots and publish the new state, outside the lock: a slot
+    /// whose requester went away is dropped here, which releases it again.
+    fn deliver(&self, admitted: Vec<(u64, oneshot::Sender<AgentSlot>)>) {
+        self.changes.send_replace(self.snapshot());
+        for (ticket, responder) in admitted {
+            let _ = responder.send(AgentSlot {
+                scheduler: self.clone(),
+                ticket,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(max_concurrent: usize, max_per_repository: usize) -> AgentScheduler {
+        AgentScheduler::new(SchedulerLimits {
+            max_concurrent,
+            max_per_repository,
+        })
+    }
+
+    fn request(session_id: &str, repository_id: &str, priority: i64) -> SlotRequest {
+        SlotRequest {
+            session_id: session_id.to_string(),
+            repository_id: repository_id.to_string(),
+            priority,
+        }
+    }
+
+    fn started(admission: Admission) -> AgentSlot {
+        match admission {
+            Admission::Started(slot) => slot,
+            Admission::Waiting { position, .. } => panic!("queued at position {position}"),
+        }
+    }
+
+    fn waiting(admission: Admission) -> (usize, oneshot::Receiver<AgentSlot>) {
+        match admission {
+            Admission::Started(_) => panic!("expected to wait for a slot"),
+            Admission::Waiting { position, slot } => (position, slot),
+        }
+    }
+
+    #[tokio::test]
+    async fn waiters_are_admitted_by_priority_then_arrival() {
+        let scheduler = scheduler(1, 1);
+        let running = started(scheduler.request(request("a", "repo", 0)));
+
+        let (position, mut first) = waiting(scheduler.request(request("b", "repo", 0)));
+        assert_eq!(position, 1);
+        let (_, mut second) = waiting(scheduler.request(request("c", "repo", 0)));
+        let (position, urgent) = waiting(scheduler.request(request("d", "repo", 5)));
+        assert_eq!(position, 1);
+        assert_eq!(scheduler.snapshot().position("b"), Some(2));
+        assert_eq!(scheduler.snapshot().position("c"), Some(3));
+
+        drop(running);
+        let urgent = urgent.await.unwrap();
+        assert!(first.try_recv().is_err());
+
+        drop(urgent);
+        let first = first.await.unwrap();
+        assert!(second.try_recv().is_err());
+        drop(first);
+        assert!(second.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn repository_limit_does_not_block_other_repositories() {
+        let scheduler = scheduler(3, 1);
+        let _running = started(scheduler.request(request("a", "repo-1", 0)));
+
+        let (position, _blocked) = waiting(scheduler.request(request("b", "repo-1", 0)));
+        assert_eq!(position, 1);
+        let _other = started(scheduler.request(request("c", "repo-2", 0)));
+
+        let snapshot = scheduler.snapshot();
+        assert_eq!(snapshot.running.len(), 2);
+        assert_eq!(snapshot.position("b"), Some(1));
+        assert!(scheduler.has_session("b"));
+        assert!(!scheduler.has_session("d"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_and_abandoned_requests_free_their_place() {
+        let scheduler = scheduler(1, 1);
+        let running = started(scheduler.request(request("a", "repo", 0)));
+        let (_, cancelled) = waiting(scheduler.request(request("b", "repo", 0)));
+        let (_, abandoned) = waiting(scheduler.request(request("c", "repo", 0)));
+        let (_, last) = waiting(scheduler.request(request("d", "repo", 0)));
+
+        assert!(scheduler.cancel_session("b"));
+        assert!(!scheduler.cancel_session("b"));
+        assert!(cancelled.await.is_err());
+
+        // The slot handed to a dropped receiver is released straight away
+        drop(abandoned);
+        drop(running);
+        assert!(last.await.is_ok());
+        assert!(scheduler.snapshot().waiting.is_empty());
+    }
+
+    #[tokio::test]
+    async fn changes_are_published() {
+        let scheduler = scheduler(1, 1);
+        let mut changes = scheduler.subscribe();
+
+        let running = started(scheduler.request(request("a", "repo", 0)));
+        let (_, _waiting) = waiting(scheduler.request(request("b", "repo", 0)));
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(changes.borrow_and_update().position("b"), Some(1));
+
+        drop(running);
+        assert!(changes.has_changed().unwrap());
+        let snapshot = changes.borrow_and_update().clone();
+        assert_eq!(snapshot.running, vec![request("b", "repo", 0)]);
+        assert!(snapshot.waiting.is_empty());
+    }
+}
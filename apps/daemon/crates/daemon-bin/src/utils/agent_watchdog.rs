@@ -0,0 +1,188 @@
+//! Wall-clock and idle limits for agent turns.
+//!
+//! A turn may be limited in total duration and in how long it may go
+//! without emitting an event. The session event loop waits on
+//! [`TurnWatchdog::expired`] next to the agent's event stream, records each
+//! event, and stops the agent when a limit is hit.
+
+use serde::Serialize;
+use tokio::time::{sleep_until, Duration, Instant};
+
+/// Limits for one agent turn; `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TurnLimits {
+    pub max_turn: Option<Duration>,
+    pub max_idle: Option<Duration>,
+}
+
+impl TurnLimits {
+    /// Limits in whole seconds, as stored in repository config and requests.
+    pub fn from_secs(max_turn_seconds: Option<u64>, max_idle_seconds: Option<u64>) -> Self {
+        Self {
+            max_turn: max_turn_seconds.map(Duration::from_secs),
+            max_idle: max_idle_seconds.map(Duration::from_secs),
+        }
+    }
+}
+
+/// Which limit stopped a turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutKind {
+    /// The turn ran longer than `max_turn_seconds`.
+    Turn,
+    /// No event arrived for `max_idle_seconds`.
+    Idle,
+}
+
+/// A limit the watchdog found exceeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnTimeout {
+    pub kind: TimeoutKind,
+    pub limit: Duration,
+    /// Time since the turn started.
+    pub elapsed: Duration,
+}
+
+impl TurnTimeout {
+    /// Reason recorded as the session's runtime error.
+    pub fn reason(&self) -> String {
+        match self.kind {
+            TimeoutKind::Turn => format!(
+                "Agent turn exceeded its {}s time limit and was stopped",
+                self.limit.as_secs()
+            ),
+            TimeoutKind::Idle => format!(
+                "Agent produced no output for {}s and was stopped",
+                self.limit.as_secs()
+            ),
+        }
+    }
+
+    /// The `agent_timeout` message appended to the transcript.
+    pub fn transcript_json(&self) -> String {
+        serde_json::json!({
+            "type": "agent_timeout",
+            "limit": self.kind,
+            "limit_seconds": self.limit.as_secs(),
+            "elapsed_seconds": self.elapsed.as_secs(),
+            "message": self.reason(),
+        })
+        .to_string()
+    }
+}
+
+/// Tracks one turn against its [`TurnLimits`].
+#[derive(Debug)]
+pub struct TurnWatchdog {
+    limits: TurnLimits,
+    started: Instant,
+    last_event: Instant,
+}
+
+impl TurnWatchdog {
+    /// Start watching a turn that begins now.
+    pub fn new(limits: TurnLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            limits,
+            started: now,
+            last_event: now,
+        }
+    }
+
+    /// Note agent activity, restarting the idle timer.
+    pub fn record_event(&mut self) {
+        self.last_event = Instant::now();
+    }
+
+    /// The earliest limit still ahead, and when it is reached.
+    fn deadline(&self) -> Option<(Instant, TimeoutKind, Duration)> {
+        let turn = self
+            .limits
+            .max_turn
+            .map(|limit| (self.started + limit, TimeoutKind::Turn, limit));
+        let idle = self
+            .limits
+            .max_idle
+            .map(|limit| (self.last_event + limit, TimeoutKind::Idle, limit));
+        match (turn, idle) {
+            (Some(turn), Some(idle)) if idle.0 < turn.0 => Some(idle),
+            (Some(turn), _) => Some(turn),
+            (None, idle) => idle,
+        }
+    }
+
+    /// Resolves once a limit is exceeded; never resolves without limits.
+    ///
+    /// Cancel-safe: the deadline is recomputed on every call, so it follows
+    /// events recorded in between.
+    pub async fn expired(&self) -> TurnTimeout {
+        let Some((deadline, kind, limit)) = self.deadline() else {
+            return std::future::pending().await;
+        };
+        sleep_until(deadline).await;
+        TurnTimeout {
+            kind,
+            limit,
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadline_picks_the_earliest_limit() {
+        let mut watchdog = TurnWatchdog::new(TurnLimits::from_secs(Some(600), Some(60)));
+        let (deadline, kind, _) = watchdog.deadline().unwrap();
+        assert_eq!(kind, TimeoutKind::Idle);
+        assert_eq!(deadline, watchdog.started + Duration::from_secs(60));
+
+        // Activity keeps pushing the idle deadline until the turn limit is closer
+        watchdog.last_event = watchdog.started + Duration::from_secs(580);
+        let (deadline, kind, _) = watchdog.deadline().unwrap();
+        assert_eq!(kind, TimeoutKind::Turn);
+        assert_eq!(deadline, watchdog.started + Duration::from_secs(600));
+
+        assert!(TurnWatchdog::new(TurnLimits::default())
+            .deadline()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_reports_the_idle_limit() {
+        let watchdog = TurnWatchdog::new(TurnLimits {
+            max_turn: None,
+            max_idle: Some(Duration::from_millis(20)),
+        });
+
+        let timeout = watchdog.expired().await;
+
+        assert_eq!(timeout.kind, TimeoutKind::Idle);
+        assert_eq!(timeout.limit, Duration::from_millis(20));
+        assert!(timeout.elapsed >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn transcript_json_describes_the_timeout() {
+        let timeout = TurnTimeout {
+            kind: TimeoutKind::Turn,
+            limit: Duration::from_secs(900),
+            elapsed: Duration::from_secs(901),
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&timeout.transcript_json()).unwrap();
+
+        assert_eq!(json["type"], "agent_timeout");
+        assert_eq!(json["limit"], "turn");
+        assert_eq!(json["limit_seconds"], 900);
+        assert_eq!(json["elapsed_seconds"], 901);
+        assert_eq!(
+            json["message"],
+            "Agent turn exceeded its 900s time limit and was stopped"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Structured answers to `AskUserQuestion` tool uses.
+//!
+//! Claude asks with a list of questions, each offering labelled options. An
+//! answer picks option labels per question and is returned to Claude as the
+//! tool use's result text.
+
+use agent_stream_types::claude::ASK_USER_QUESTION_TOOL;
+use serde_json::Value;
+
+/// One question from an `AskUserQuestion` tool input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AskedQuestion {
+    pub question: String,
+    /// Option labels, in the order they were offered.
+    pub options: Vec<String>,
+    pub multi_select: bool,
+}
+
+/// Find the questions asked by the `AskUserQuestion` tool use `tool_use_id`.
+///
+/// `content` is one stored transcript message; returns None unless it is an
+/// assistant message containing that tool use.
+pub fn find_asked_questions(content: &str, tool_use_id: &str) -> Option<Vec<AskedQuestion>> {
+    let json: Value = serde_json::from_str(content).ok()?;
+    if json.get("type").and_then(Value::as_str) != Some("assistant") {
+        return None;
+    }
+    let block = json
+        .get("message")?
+        .get("content")?
+        .as_array()?
+        .iter()
+        .find(|block| {
+            block.get("type").and_then(Value::as_str) == Some("tool_use")
+                && block.get("id").and_then(Value::as_str) == Some(tool_use_id)
+                && block.get("name").and_then(Value::as_str) == Some(ASK_USER_QUESTION_TOOL)
+        })?;
+
+    let questions = block
+        .get("input")
+        .and_then(|input| input.get("questions"))
+        .and_then(Value::as_array)
+        .map(|questions| questions.iter().map(parse_question).collect())
+        .unwrap_or_default();
+    Some(questions)
+}
+
+fn parse_question(question: &Value) -> AskedQuestion {
+    AskedQuestion {
+        question: question
+            .get("question")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        options: question
+            .get("options")
+            .and_then(Value::as_array)
+            .map(|options| {
+                options
+                    .iter()
+                    .filter_map(|option| option.get("label").and_then(Value::as_str))
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        multi_select: question
+            .get("multiSelect")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+    }
+}
+
+/// Parse the `answers` param: one entry per question, each a label or an
+/// array of labels.
+pub fn parse_answers(value: Option<&Value>) -> Result<Vec<Vec<String>>, String> {
+    let Some(entries) = value.and_then(Value::as_array) else {
+        return Err("answers must be an array with one entry per question".to_string());
+    };
+
+    entries
+        .iter()
+        .map(|entry| match entry {
+            Value::String(label) => Ok(vec![label.clone()]),
+            Value::Array(labels) => labels
+                .iter()
+                .map(|label| {
+                    label
+                        .as_str()
+                        .map(String::from)
+                        .ok_or_else(|| "answer labels must be strings".to_string())
+                })
+                .collect(),
+            _ => Err("each answer must be a label or an array of labels".to_string()),
+        })
+        .collect()
+}
+
+/// Check `answers` against the questions and render the tool result text.
+pub fn format_answer(
+    questions: &[AskedQuestion],
+    answers: &[Vec<String>],
+) -> Result<String, String> {
+    if answers.len() != questions.len() {
+        return Err(format!(
+            "expected {} answer(s), one per question, got {}",
+            questions.len(),
+            answers.len()
+        ));
+    }
+
+    let mut parts = Vec::with_capacity(questions.len());
+    for (question, labels) in questions.iter().zip(answers) {
+        if labels.is_empty() {
+            return Err(format!("no option chosen for \"{}\"", question.question));
+        }
+        if labels.len() > 1 && !question.multi_select {
+            return Err(format!("\"{}\" accepts a single option", question.question));
+        }
+        if let Some(unknown) = labels
+            .iter()
+            .find(|label| !question.options.contains(label))
+        {
+            return Err(format!(
+                "\"{unknown}\" is not an option for \"{}\"",
+                question.question
+            ));
+        }
+        parts.push(format!(
+            "\"{}\"=\"{}\"",
+            question.question,
+            labels.join(", ")
+        ));
+    }
+
+    Ok(format!(
+        "User has answered your questions: {}. You can now continue with the user's answers in mind.",
+        parts.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assistant_with_question() -> String {
+        json!({
+            "type": "assistant",
+            "message": {
+                "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_ask",
+                    "name": "AskUserQuestion",
+                    "input": {
+                        "questions": [
+                            {
+                                "question": "Which database?",
+                                "header": "Storage",
+                                "options": [
+                                    { "label": "SQLite", "description": "Embedded" },
+                                    { "label": "Postgres", "description": "Server" }
+                                ],
+                                "multiSelect": false
+                            },
+                            {
+                                "question": "Which targets?",
+                                "options": [{ "label": "macOS" }, { "label": "Linux" }],
+                                "multiSelect": true
+                            }
+                        ]
+                    }
+                }]
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn finds_questions_by_tool_use_id() {
+        let questions = find_asked_questions(&assistant_with_question(), "toolu_ask").unwrap();
+        assert_eq!(questions.len(), 2);
+        assert_eq!(questions[0].question, "Which database?");
+        assert_eq!(questions[0].options, vec!["SQLite", "Postgres"]);
+        assert!(!questions[0].multi_select);
+        assert!(questions[1].multi_select);
+
+        assert!(find_asked_questions(&assistant_with_question(), "toolu_other").is_none());
+        assert!(find_asked_questions("plain user text", "toolu_ask").is_none());
+    }
+
+    #[test]
+    fn formats_answers_in_question_order() {
+        let questions = find_asked_questions(&assistant_with_question(), "toolu_ask").unwrap();
+        let answers = parse_answers(Some(&json!(["Postgres", ["macOS", "Linux"]]))).unwrap();
+
+        assert_eq!(
+            format_answer(&questions, &answers).unwrap(),
+            "User has answered your questions: \"Which database?\"=\"Postgres\", \
+             \"Which targets?\"=\"macOS, Linux\". You can now continue with the user's answers in mind."
+        );
+    }
+
+    #[test]
+    fn rejects_answers_that_do_not_fit_the_questions() {
+        let questions = find_asked_questions(&assistant_with_question(), "toolu_ask").unwrap();
+        let check = |answers: Value| {
+            format_answer(&questions, &parse_answers(Some(&answers)).unwrap()).unwrap_err()
+        };
+
+        assert_eq!(
+            check(json!(["SQLite"])),
+            "expected 2 answer(s), one per question, got 1"
+        );
+        assert_eq!(
+            check(json!([["SQLite", "Postgres"], "Linux"])),
+            "\"Which database?\" accepts a single option"
+        );
+        assert_eq!(
+            check(json!(["MySQL", "Linux"])),
+            "\"MySQL\" is not an option for \"Which database?\""
+        );
+        assert_eq!(
+            check(json!([[], "Linux"])),
+            "no option chosen for \"Which database?\""
+        );
+
+        assert!(parse_answers(Some(&json!("SQLite"))).is_err());
+        assert!(parse_answers(Some(&json!([1]))).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! Repository environment profiles: named sets of environment variables an
+//! agent turn opts into with `agent.send`'s `profile`.
+//!
+//! Plain values live in the repository config. Secret values live in the
+//! secrets store under keys namespaced by repository and profile, are read
+//! only when an agent is spawned, and are never sent back over IPC.
+
+use crate::utils::repository_config::EnvProfileConfig;
+use daemon_storage::SecretsManager;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Profile names follow the same rules as MCP server names.
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(
+            "profile name must be non-empty and use only letters, digits, '-' and '_'".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Variable names must be portable shell identifiers.
+pub fn validate_var_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!(
+            "invalid environment variable name {name:?}; use letters, digits and '_', \
+             not starting with a digit"
+        ));
+    }
+    Ok(())
+}
+
+/// Profiles as returned over IPC: plain values, and only the names of
+/// secrets.
+pub fn profiles_json(profiles: &BTreeMap<String, EnvProfileConfig>) -> Value {
+    profiles
+        .iter()
+        .map(|(name, profile)| {
+            (
+                name.clone(),
+                serde_json::json!({
+                    "vars": profile.vars,
+                    "secrets": profile.secrets,
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Set one variable of a profile. A secret value goes to the secrets store
+/// and only its name to the profile; a plain value replaces any secret of
+/// the same name.
+pub fn set_var(
+    secrets: &SecretsManager,
+    repository_id: &str,
+    profile_name: &str,
+    profile: &mut EnvProfileConfig,
+    name: &str,
+    value: &str,
+    secret: bool,
+) -> Result<(), String> {
+    if secret {
+        secrets
+            .set_repository_env_secret(repository_id, profile_name, name, value)
+            .map_err(|e| format!("Failed to store secret {name}: {e}"))?;
+        profile.vars.remove(name);
+        if !profile.secrets.iter().any(|existing| existing == name) {
+            profile.secrets.push(name.to_string());
+        }
+    } else {
+        delete_var(secrets, repository_id, profile_name, profile, name)?;
+        profile.vars.insert(name.to_string(), value.to_string());
+    }
+    Ok(())
+}
+
+/// Remove one variable from a profile, deleting its stored secret.
+/// Returns whether the profile had it.
+pub fn delete_var(
+    secrets: &SecretsManager,
+    repository_id: &str,
+    profile_name: &str,
+    profile: &mut EnvProfileConfig,
+    name: &str,
+) -> Result<bool, String> {
+    let had_var = profile.vars.remove(name).is_some();
+    let Some(index) = profile.secrets.iter().position(|secret| secret == name) else {
+        return Ok(had_var);
+    };
+    secrets
+        .delete_repository_env_secret(repository_id, profile_name, name)
+        .map_err(|e| format!("Failed to delete secret {name}: {e}"))?;
+    profile.secrets.remove(index);
+    Ok(true)
+}
+
+/// Delete the stored secrets of a profile being removed.
+pub fn delete_profile_secrets(
+    secrets: &SecretsManager,
+    repository_id: &str,
+    profile_name: &str,
+    profile: &EnvProfileConfig,
+) -> Result<(), String> {
+    for name in &profile.secrets {
+        secrets
+            .delete_repository_env_secret(repository_id, profile_name, name)
+            .map_err(|e| format!("Failed to delete secret {name}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// The variables to spawn an agent with, secrets included. Errors name a
+/// missing secret but never contain a value.
+pub fn resolve_env(
+    secrets: &SecretsManager,
+    repository_id: &str,
+    profile_name: &str,
+    profile: &EnvProfileConfig,
+) -> Result<Vec<(String, String)>, String> {
+    let mut env: Vec<(String, String)> = profile
+        .vars
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    for name in &profile.secrets {
+        let value = secrets
+            .get_repository_env_secret(repository_id, profile_name, name)
+            .map_err(|e| format!("Failed to read secret {name}: {e}"))?
+            .ok_or_else(|| {
+                format!("secret {name} of environment profile \"{profile_name}\" is not set")
+            })?;
+        env.push((name.clone(), value));
+    }
+    Ok(env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use daemon_storage::{SecureStorage, StorageResult};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStorage(Mutex<HashMap<String, String>>);
+
+    impl SecureStorage for MemoryStorage {
+        fn set(&self, key: &str, value: &str) -> StorageResult<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn get(&self, key: &str) -> StorageResult<Option<String>> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn delete(&self, key: &str) -> StorageResult<bool> {
+            Ok(self.0.lock().unwrap().remove(key).is_some())
+        }
+    }
+
+    fn secrets() -> SecretsManager {
+        SecretsManager::new(Box::new(MemoryStorage::default()))
+    }
+
+    #[test]
+    fn secret_values_stay_out_of_the_profile() {
+        let secrets = secrets();
+        let mut profile = EnvProfileConfig::default();
+
+        set_var(
+            &secrets,
+            "repo",
+            "dev",
+            &mut profile,
+            "API_URL",
+            "https://dev",
+            false,
+        )
+        .unwrap();
+        set_var(
+            &secrets,
+            "repo",
+            "dev",
+            &mut profile,
+            "API_TOKEN",
+            "tok-1",
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(profile.secrets, vec!["API_TOKEN".to_string()]);
+        assert!(!profile.vars.contains_key("API_TOKEN"));
+        let listed = profiles_json(&BTreeMap::from([("dev".to_string(), profile.clone())]));
+        assert!(!listed.to_string().contains("tok-1"));
+
+        let mut env = resolve_env(&secrets, "repo", "dev", &profile).unwrap();
+        env.sort();
+        assert_eq!(
+            env,
+            vec![
+                ("API_TOKEN".to_string(), "tok-1".to_string()),
+                ("API_URL".to_string(), "https://dev".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_value_replaces_a_secret_and_deletes_it() {
+        let secrets = secrets();
+        let mut profile = EnvProfileConfig::default();
+        set_var(
+            &secrets,
+            "repo",
+            "dev",
+            &mut profile,
+            "TOKEN",
+            "secret",
+            true,
+        )
+        .unwrap();
+
+        set_var(
+            &secrets,
+            "repo",
+            "dev",
+            &mut profile,
+            "TOKEN",
+            "plain",
+            false,
+        )
+        .unwrap();
+
+        assert!(profile.secrets.is_empty());
+        assert_eq!(profile.vars["TOKEN"], "plain");
+        assert_eq!(
+            secrets
+                .get_repository_env_secret("repo", "dev", "TOKEN")
+                .unwrap(),
+            None
+        );
+        assert!(delete_var(&secrets, "repo", "dev", &mut profile, "TOKEN").unwrap());
+        assert!(!delete_var(&secrets, "repo", "dev", &mut profile, "TOKEN").unwrap());
+    }
+
+    #[test]
+    fn resolve_env_reports_missing_secrets_by_name() {
+        let profile = EnvProfileConfig {
+            vars: BTreeMap::new(),
+            secrets: vec!["API_TOKEN".to_string()],
+        };
+
+        assert_eq!(
+            resolve_env(&secrets(), "repo", "dev", &profile).unwrap_err(),
+            "secret API_TOKEN of environment profile \"dev\" is not set"
+        );
+    }
+
+    #[test]
+    fn names_are_validated() {
+        assert!(validate_profile_name("staging-1").is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("a.b").is_err());
+        assert!(validate_var_name("_API_TOKEN2").is_ok());
+        assert!(validate_var_name("2FA").is_err());
+        assert!(validate_var_name("A-B").is_err());
+        assert!(validate_var_name("").is_err());
+    }
+}
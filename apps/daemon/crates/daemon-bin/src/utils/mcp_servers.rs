@@ -0,0 +1,527 @@
+//! MCP servers declared in a repository's `mcp_servers` config.
+//!
+//! Enabled servers are handed to agents for every turn: Claude gets a
+//! per-spawn `--mcp-config` file, Codex the matching `-c mcp_servers.*`
+//! overrides. Env values never go on a command line, where other users could
+//! read them: Codex is told which variables to forward and gets the values in
+//! its own environment. Servers can be probed by running the MCP handshake
+//! against them.
+
+use crate::app::permission_mcp::{MCP_PROTOCOL_VERSION, PERMISSION_MCP_SERVER_NAME};
+use crate::utils::repository_config::McpServerConfig;
+use claude_process_manager::McpConfigFile;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{ChildStdin, ChildStdout, Command};
+use tracing::{debug, warn};
+
+/// How long a probe may take, from spawn to the `tools/list` reply.
+pub const MCP_PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Check a server entry before it is saved.
+pub fn validate_mcp_server(name: &str, server: &McpServerConfig) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(
+            "MCP server name must be non-empty and use only letters, digits, '-' and '_'"
+                .to_string(),
+        );
+    }
+    if name == PERMISSION_MCP_SERVER_NAME {
+        return Err(format!(
+            "MCP server name \"{name}\" is reserved for the daemon"
+        ));
+    }
+    if server.command.trim().is_empty() {
+        return Err("MCP server command must not be empty".to_string());
+    }
+    if let Some(key) = server
+        .env
+        .keys()
+        .find(|key| key.is_empty() || key.contains('=') || key.contains('\0'))
+    {
+        return Err(format!("invalid MCP server env variable name {key:?}"));
+    }
+    Ok(())
+}
+
+/// Check that enabled servers sharing an env variable agree on its value.
+///
+/// Codex forwards each server's variables by name from its own environment,
+/// so one variable can only hold one value for all of them.
+pub fn validate_mcp_env_conflicts(
+    servers: &BTreeMap<String, McpServerConfig>,
+) -> Result<(), String> {
+    let mut seen: BTreeMap<&str, (&str, &str)> = BTreeMap::new();
+    for (name, server) in servers.iter().filter(|(_, server)| server.enabled) {
+        for (key, value) in &server.env {
+            match seen.get(key.as_str()) {
+                Some((other, existing)) if existing != value => {
+                    return Err(format!(
+                        "MCP servers \"{other}\" and \"{name}\" set env variable {key} to \
+                         different values; enabled servers must agree on shared variables"
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    seen.insert(key, (name, value));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Claude's `{"mcpServers": ...}` document for the enabled servers, or None
+/// if there are none.
+pub fn claude_mcp_config(servers: &BTreeMap<String, McpServerConfig>) -> Option<String> {
+    let servers: serde_json::Map<String, Value> = servers
+        .iter()
+        .filter(|(_, server)| server.enabled)
+        .map(|(name, server)| {
+            (
+                name.clone(),
+                json!({
+                    "command": server.command,
+                    "args": server.args,
+                    "env": server.env,
+                }),
+            )
+        })
+        .collect();
+    (!servers.is_empty()).then(|| json!({ "mcpServers": servers }).to_string())
+}
+
+/// A Claude MCP config file for one spawn of a session, in `dir`.
+pub fn session_mcp_config_file(
+    dir: &Path,
+    session_id: &str,
+    servers: &BTreeMap<String, McpServerConfig>,
+) -> Option<McpConfigFile> {
+    let contents = claude_mcp_config(servers)?;
+    let path = dir.join(format!("{session_id}-{}.json", uuid::Uuid::new_v4()));
+    Some(McpConfigFile {
+        path: path.to_string_lossy().into_owned(),
+        contents,
+    })
+}
+
+/// `codex exec` config overrides declaring the enabled servers. Env values are
+/// left out; each server forwards its variables from [`codex_mcp_env`].
+pub fn codex_mcp_args(servers: &BTreeMap<String, McpServerConfig>) -> Vec<String> {
+    let mut args = Vec::new();
+    for (name, server) in servers.iter().filter(|(_, server)| server.enabled) {
+        // JSON strings and string arrays are also valid TOML values
+        for value in [
+            format!("command={}", toml_string(&server.command)),
+            format!("args={}", json!(server.args)),
+            format!("env_vars={}", json!(server.env.keys().collect::<Vec<_>>())),
+        ] {
+            args.push("-c".to_string());
+            args.push(format!("mcp_servers.{name}.{value}"));
+        }
+    }
+    args
+}
+
+/// Env values of the enabled servers, for the Codex process itself. Codex
+/// forwards them by name, and every command the agent runs inherits them too.
+/// `repository.mcp_update` refuses servers that set a shared variable
+/// differently; for a config edited by hand the first server (by name) wins.
+pub fn codex_mcp_env(servers: &BTreeMap<String, McpServerConfig>) -> Vec<(String, String)> {
+    let mut env = BTreeMap::new();
+    for (name, server) in servers.iter().filter(|(_, server)| server.enabled) {
+        for (key, value) in &server.env {
+            match env.get(key) {
+                Some(existing) if existing != value => warn!(
+                    server = %name,
+                    variable = %key,
+                    "MCP server env variable is set differently by another server; ignoring it"
+                ),
+                Some(_) => {}
+                None => {
+                    env.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    env.into_iter().collect()
+}
+
+fn toml_string(value: &str) -> String {
+    Value::from(value).to_string()
+}
+
+/// A written MCP config file, removed when dropped.
+pub struct WrittenMcpConfig {
+    path: PathBuf,
+}
+
+impl WrittenMcpConfig {
+    /// Write `file` to disk, readable only by the daemon's user since it
+    /// holds the servers' env values.
+    pub fn write(file: &McpConfigFile) -> std::io::Result<Self> {
+        let path = PathBuf::from(&file.path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?
+            .write_all(file.contents.as_bytes())?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for WrittenMcpConfig {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!(path = %self.path.display(), error = %e, "Failed to remove MCP config file");
+        }
+    }
+}
+
+/// Remove config files left behind by a daemon that did not shut down cleanly.
+pub fn remove_stale_mcp_configs(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!(path = %path.display(), error = %e, "Failed to remove stale MCP config");
+            }
+        }
+    }
+}
+
+/// What a server reported during a probe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpProbe {
+    pub protocol_version: String,
+    pub server_name: Option<String>,
+    pub server_version: Option<String>,
+    pub tools: Vec<String>,
+}
+
+/// Spawn a server in `working_dir`, run the MCP handshake and list its
+/// tools, then stop it.
+pub async fn probe_mcp_server(
+    server: &McpServerConfig,
+    working_dir: &Path,
+    timeout: Duration,
+) -> Result<McpProbe, String> {
+    let mut child = Command::new(&server.command)
+        .args(&server.args)
+        .envs(&server.env)
+        .current_dir(working_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to start {}: {e}", server.command))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+
+    let handshake = async {
+        send(
+            &mut stdin,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "unbound-daemon",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                },
+            }),
+        )
+        .await?;
+        let initialized = read_response(&mut stdout, 1).await?;
+
+        send(
+            &mut stdin,
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        )
+        .await?;
+        send(
+            &mut stdin,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+        )
+        .await?;
+        let tools = read_response(&mut stdout, 2).await?;
+
+        let server_info = initialized.get("serverInfo");
+        let info = |key: &str| {
+            server_info
+                .and_then(|info| info.get(key))
+                .and_then(Value::as_str)
+                .map(String::from)
+        };
+        Ok(McpProbe {
+            protocol_version: initialized
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            server_name: info("name"),
+            server_version: info("version"),
+            tools: tools
+                .get("tools")
+                .and_then(Value::as_array)
+                .map(|tools| {
+                    tools
+                        .iter()
+                        .filter_map(|tool| tool.get("name").and_then(Value::as_str))
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    };
+
+    let result = match tokio::time::timeout(timeout, handshake).await {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "server did not complete the MCP handshake within {}s",
+            timeout.as_secs()
+        )),
+    };
+    if let Err(e) = child.kill().await {
+        debug!(error = %e, "MCP probe server already exited");
+    }
+    result
+}
+
+async fn send(stdin: &mut ChildStdin, message: Value) -> Result<(), String> {
+    let line = format!("{message}\n");
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("failed to write to server: {e}"))
+}
+
+/// Read stdout until the response to request `id`, skipping notifications
+/// and anything that is not JSON.
+async fn read_response(
+    stdout: &mut Lines<BufReader<ChildStdout>>,
+    id: u64,
+) -> Result<Value, String> {
+    loop {
+        let line = stdout
+            .next_line()
+            .await
+            .map_err(|e| format!("failed to read from server: {e}"))?
+            .ok_or_else(|| "server exited during the MCP handshake".to_string())?;
+        let Ok(message) = serde_json::from_str::<Value>(line.trim()) else {
+            continue;
+        };
+        if message.get("id").and_then(Value::as_u64) != Some(id) {
+            continue;
+        }
+        if let Some(error) = message.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            return Err(format!("server returned an error: {message}"));
+        }
+        return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(command: &str, args: &[&str]) -> McpServerConfig {
+        McpServerConfig {
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            env: BTreeMap::from([("TOKEN".to_string(), "a \"b\"".to_string())]),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn validation_rejects_bad_names_commands_and_env() {
+        assert!(validate_mcp_server("linear-2", &server("npx", &[])).is_ok());
+        assert!(validate_mcp_server("", &server("npx", &[])).is_err());
+        assert!(validate_mcp_server("has space", &server("npx", &[])).is_err());
+        assert!(validate_mcp_server(PERMISSION_MCP_SERVER_NAME, &server("npx", &[])).is_err());
+        assert!(validate_mcp_server("linear", &server("  ", &[])).is_err());
+
+        let mut bad_env = server("npx", &[]);
+        bad_env.env.insert("A=B".to_string(), "c".to_string());
+        assert!(validate_mcp_server("linear", &bad_env).is_err());
+    }
+
+    #[test]
+    fn only_enabled_servers_reach_agents() {
+        let mut disabled = server("disabled-cmd", &[]);
+        disabled.enabled = false;
+        let servers = BTreeMap::from([
+            ("linear".to_string(), server("npx", &["-y", "linear-mcp"])),
+            ("off".to_string(), disabled),
+        ]);
+
+        let config: Value = serde_json::from_str(&claude_mcp_config(&servers).unwrap()).unwrap();
+        assert_eq!(config["mcpServers"]["linear"]["command"], "npx");
+        assert_eq!(config["mcpServers"]["linear"]["args"][1], "linear-mcp");
+        assert_eq!(config["mcpServers"]["linear"]["env"]["TOKEN"], "a \"b\"");
+        assert!(config["mcpServers"].get("off").is_none());
+
+        assert_eq!(
+            codex_mcp_args(&servers),
+            vec![
+                "-c",
+                r#"mcp_servers.linear.command="npx""#,
+                "-c",
+                r#"mcp_servers.linear.args=["-y","linear-mcp"]"#,
+                "-c",
+                r#"mcp_servers.linear.env_vars=["TOKEN"]"#,
+            ]
+        );
+        assert_eq!(
+            codex_mcp_env(&servers),
+            vec![("TOKEN".to_string(), "a \"b\"".to_string())]
+        );
+
+        let only_disabled = BTreeMap::from([("off".to_string(), servers["off"].clone())]);
+        assert_eq!(claude_mcp_config(&only_disabled), None);
+        assert!(codex_mcp_args(&only_disabled).is_empty());
+        assert!(codex_mcp_env(&only_disabled).is_empty());
+    }
+
+    #[test]
+    fn codex_env_keeps_the_first_value_of_a_shared_variable() {
+        let mut other = server("npx", &[]);
+        other.env.insert("TOKEN".to_string(), "other".to_string());
+        other.env.insert("EXTRA".to_string(), "1".to_string());
+        let servers = BTreeMap::from([
+            ("a".to_string(), server("npx", &[])),
+            ("b".to_string(), other),
+        ]);
+        assert_eq!(
+            codex_mcp_env(&servers),
+            vec![
+                ("EXTRA".to_string(), "1".to_string()),
+                ("TOKEN".to_string(), "a \"b\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn enabled_servers_must_agree_on_shared_env() {
+        let mut other = server("npx", &[]);
+        other.env.insert("EXTRA".to_string(), "1".to_string());
+        let mut servers = BTreeMap::from([
+            ("a".to_string(), server("npx", &[])),
+            ("b".to_string(), other),
+        ]);
+        assert!(validate_mcp_env_conflicts(&servers).is_ok());
+
+        let b = servers.get_mut("b").unwrap();
+        b.env.insert("TOKEN".to_string(), "other".to_string());
+        let err = validate_mcp_env_conflicts(&servers).unwrap_err();
+        assert!(err.contains("\"a\" and \"b\""), "{err}");
+        assert!(err.contains("TOKEN"), "{err}");
+
+        servers.get_mut("b").unwrap().enabled = false;
+        assert!(validate_mcp_env_conflicts(&servers).is_ok());
+    }
+
+    #[test]
+    fn written_config_is_private_and_removed_on_drop() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("mcp-config-test-{}", uuid::Uuid::new_v4()));
+        let servers = BTreeMap::from([("linear".to_string(), server("npx", &[]))]);
+        let file = session_mcp_config_file(&dir, "session-1", &servers).unwrap();
+        assert!(file.path.starts_with(&*dir.to_string_lossy()));
+
+        let written = WrittenMcpConfig::write(&file).unwrap();
+        assert_eq!(std::fs::read_to_string(&file.path).unwrap(), file.contents);
+        let mode = std::fs::metadata(&file.path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        drop(written);
+        assert!(!Path::new(&file.path).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn probe_runs_the_handshake() {
+        let script = r#"
+while read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2024-11-05","serverInfo":{"name":"fake","version":"1.0"}}}' ;;
+    *'"tools/list"'*) echo 'starting up'; echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"search"},{"name":"create"}]}}' ;;
+  esac
+done
+"#;
+        let probe = probe_mcp_server(
+            &server("sh", &["-c", script]),
+            &std::env::temp_dir(),
+            MCP_PROBE_TIMEOUT,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            probe,
+            McpProbe {
+                protocol_version: "2024-11-05".to_string(),
+                server_name: Some("fake".to_string()),
+                server_version: Some("1.0".to_string()),
+                tools: vec!["search".to_string(), "create".to_string()],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn probe_reports_servers_that_fail() {
+        let err = probe_mcp_server(
+            &server("sh", &["-c", "exit 1"]),
+            &std::env::temp_dir(),
+            MCP_PROBE_TIMEOUT,
+        )
+        .await
+        .unwrap_err();
+        // Depending on timing the write or the read notices the exit first
+        assert!(err.contains("exited") || err.contains("write"), "{err}");
+
+        let err = probe_mcp_server(
+            &server("sh", &["-c", "sleep 5"]),
+            &std::env::temp_dir(),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("within"), "{err}");
+
+        let err = probe_mcp_server(
+            &server("/nonexistent/mcp-server", &[]),
+            &std::env::temp_dir(),
+            MCP_PROBE_TIMEOUT,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("failed to start"), "{err}");
+    }
+}
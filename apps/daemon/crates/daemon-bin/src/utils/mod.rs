@@ -1,6 +1,14 @@
 //! Utility functions for the daemon.
 
+pub mod agent_scheduler;
+pub mod agent_watchdog;
+pub mod ask_user_question;
+pub mod env_profiles;
+pub mod mcp_servers;
+pub mod permission_broker;
 pub mod repository_config;
+pub mod sandbox;
+pub mod session_bundle;
 mod session_secret_cache;
 
 pub use session_secret_cache::SessionSecretCache;
@@ -0,0 +1,200 @@
+//! Pending tool-permission requests awaiting a client decision.
+//!
+//! The permission prompt tool blocks on a request registered here until a
+//! client answers it through `agent.respond_permission`, the agent is stopped,
+//! or the request times out.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// Whether a tool use may go ahead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionBehavior {
+    Allow,
+    Deny,
+}
+
+impl PermissionBehavior {
+    /// Wire name, as used by Claude's permission prompt tool contract.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+
+    /// Parse a wire name.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "allow" => Some(Self::Allow),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// A client's answer to a permission request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionDecision {
+    pub behavior: PermissionBehavior,
+    /// Explanation passed back to the agent, mainly when denying.
+    pub message: Option<String>,
+    /// Replacement tool input when allowing; the original input is used if None.
+    pub updated_input: Option<Value>,
+    /// Allow this tool for every future request in the session's repository.
+    pub always_allow: bool,
+}
+
+/// A tool use an agent wants to make.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionRequest {
+    pub session_id: String,
+    pub tool_name: String,
+    /// Tool input as proposed by the agent.
+    pub input: Value,
+    pub tool_use_id: Option<String>,
+}
+
+struct PendingEntry {
+    request: PermissionRequest,
+    responder: oneshot::Sender<PermissionDecision>,
+}
+
+/// Thread-safe registry of permission requests waiting for an answer.
+#[derive(Clone, Default)]
+pub struct PermissionBroker {
+    pending: Arc<Mutex<HashMap<String, PendingEntry>>>,
+}
+
+impl PermissionBroker {
+    /// Create an empty broker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a request, returning its id and a receiver for the decision.
+    ///
+    /// The receiver errors if the request is cancelled without an answer.
+    pub fn register(
+        &self,
+        request: PermissionRequest,
+    ) -> (String, oneshot::Receiver<PermissionDecision>) {
+        let request_id = Uuid::new_v4().to_string();
+        let (responder, receiver) = oneshot::channel();
+        let mut pending = self.pending.lock().unwrap();
+        pending.insert(request_id.clone(), PendingEntry { request, responder });
+        (request_id, receiver)
+    }
+
+    /// Deliver a decision, returning the request it answered.
+    ///
+    /// Returns None if the request is unknown, already answered, or no longer
+    /// being waited on.
+    pub fn resolve(
+        &self,
+        request_id: &str,
+        decision: PermissionDecision,
+    ) -> Option<PermissionRequest> {
+        let entry = self.pending.lock().unwrap().remove(request_id)?;
+        entry.responder.send(decision).ok()?;
+        Some(entry.request)
+    }
+
+    /// The request waiting under `request_id`, if any.
+    pub fn get(&self, request_id: &str) -> Option<PermissionRequest> {
+        let pending = self.pending.lock().unwrap();
+        pending.get(request_id).map(|entry| entry.request.clone())
+    }
+
+    /// Drop a request without answering it, e.g. after it timed out.
+    pub fn forget(&self, request_id: &str) {
+        self.pending.lock().unwrap().remove(request_id);
+    }
+
+    /// Drop every request for a session, returning how many were pending.
+    pub fn cancel_session(&self, session_id: &str) -> usize {
+        let mut pending = self.pending.lock().unwrap();
+        let before = pending.len();
+        pending.retain(|_, entry| entry.request.session_id != session_id);
+        before - pending.len()
+    }
+
+    /// Requests still waiting for a session, keyed by request id.
+    pub fn pending_for_session(&self, session_id: &str) -> Vec<(String, PermissionRequest)> {
+        let pending = self.pending.lock().unwrap();
+        pending
+            .iter()
+            .filter(|(_, entry)| entry.request.session_id == session_id)
+            .map(|(request_id, entry)| (request_id.clone(), entry.request.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(session_id: &str, tool_name: &str) -> PermissionRequest {
+        PermissionRequest {
+            session_id: session_id.to_string(),
+            tool_name: tool_name.to_string(),
+            input: serde_json::json!({}),
+            tool_use_id: None,
+        }
+    }
+
+    fn allow() -> PermissionDecision {
+        PermissionDecision {
+            behavior: PermissionBehavior::Allow,
+            message: None,
+            updated_input: None,
+            always_allow: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_delivers_decision_once() {
+        let broker = PermissionBroker::new();
+        let (request_id, receiver) = broker.register(request("session-1", "Bash"));
+
+        let resolved = broker.resolve(&request_id, allow()).unwrap();
+        assert_eq!(resolved.session_id, "session-1");
+        assert_eq!(resolved.tool_name, "Bash");
+        assert_eq!(receiver.await.unwrap(), allow());
+
+        assert!(broker.resolve(&request_id, allow()).is_none());
+        assert!(broker.pending_for_session("session-1").is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel_session_closes_only_that_sessions_requests() {
+        let broker = PermissionBroker::new();
+        let (_, cancelled) = broker.register(request("session-1", "Bash"));
+        let (other_id, _other) = broker.register(request("session-2", "Edit"));
+
+        assert_eq!(broker.cancel_session("session-1"), 1);
+
+        assert!(cancelled.await.is_err());
+        let pending = broker.pending_for_session("session-2");
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0, other_id);
+        assert!(broker.resolve(&other_id, allow()).is_some());
+    }
+
+    #[test]
+    fn behavior_parses_wire_names() {
+        assert_eq!(
+            PermissionBehavior::parse("allow"),
+            Some(PermissionBehavior::Allow)
+        );
+        assert_eq!(
+            PermissionBehavior::parse("deny"),
+            Some(PermissionBehavior::Deny)
+        );
+        assert_eq!(PermissionBehavior::parse("maybe"), None);
+        assert_eq!(PermissionBehavior::Deny.as_str(), "deny");
+    }
+}
@@ -1,8 +1,10 @@
 //! Repository-local configuration management (`<repo>/.unbound/config.json`).
 #![allow(dead_code)]
 
+use claude_process_manager::PermissionMode;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -21,6 +23,16 @@ pub struct RepositoryConfig {
     pub schema_version: u32,
     pub worktree: WorktreeConfig,
     pub setup_hooks: SetupHooksConfig,
+    pub permissions: PermissionsConfig,
+    /// MCP servers made available to agent sessions, keyed by server name.
+    pub mcp_servers: BTreeMap<String, McpServerConfig>,
+    pub agent_timeouts: AgentTimeoutsConfig,
+    pub sandbox: SandboxConfig,
+    /// Named environment profiles for agent sessions.
+    pub env_profiles: BTreeMap<String, EnvProfileConfig>,
+    /// Shell for terminal commands, setup hooks and agent tools in this
+    /// repository, overriding the daemon's.
+    pub shell: Option<String>,
 }
 
 impl Default for RepositoryConfig {
@@ -29,6 +41,12 @@ impl Default for RepositoryConfig {
             schema_version: SCHEMA_VERSION,
             worktree: WorktreeConfig::default(),
             setup_hooks: SetupHooksConfig::default(),
+            permissions: PermissionsConfig::default(),
+            mcp_servers: BTreeMap::new(),
+            agent_timeouts: AgentTimeoutsConfig::default(),
+            sandbox: SandboxConfig::default(),
+            env_profiles: BTreeMap::new(),
+            shell: None,
         }
     }
 }
@@ -78,6 +96,60 @@ impl Default for SetupHookStageConfig {
     }
 }
 
+/// Tool permission rules applied to every agent session in the repository.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct PermissionsConfig {
+    /// Tool names approved without asking ("always allow for this repo").
+    pub always_allow_tools: Vec<String>,
+    /// Permission mode for sessions that have not chosen one.
+    pub default_mode: Option<PermissionMode>,
+}
+
+/// A stdio MCP server launched for agent sessions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct McpServerConfig {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Disabled servers stay in the config but are not passed to agents.
+    pub enabled: bool,
+}
+
+/// Limits after which a running agent turn is stopped; `None` is unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct AgentTimeoutsConfig {
+    /// Maximum wall-clock duration of a turn.
+    pub max_turn_seconds: Option<u64>,
+    /// Maximum time a turn may go without emitting an event.
+    pub max_idle_seconds: Option<u64>,
+}
+
+/// Confinement of agent CLIs and terminal commands; Linux only.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct SandboxConfig {
+    pub enabled: bool,
+    /// Paths writable besides the worktree and a private `/tmp`.
+    pub writable_paths: Vec<String>,
+    /// Cut sandboxed processes off from the network.
+    pub network_disabled: bool,
+    /// Virtual address space limit per process (`RLIMIT_AS`), which can be
+    /// far above resident memory for runtimes such as Node.
+    pub max_memory_mb: Option<u64>,
+    /// Limit on the process count of the daemon's whole user (`RLIMIT_NPROC`),
+    /// applied to sandboxed processes.
+    pub max_processes: Option<u64>,
+}
+
+/// Environment variables an agent turn can opt into by name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct EnvProfileConfig {
+    /// Plain values, stored in this file.
+    pub vars: BTreeMap<String, String>,
+    /// Names of secret variables; their values are kept in the secrets
+    /// store, never in this file.
+    pub secrets: Vec<String>,
+}
+
 /// Partial update payload for managed config keys.
 #[derive(Debug, Clone, Default)]
 pub struct RepositoryConfigUpdate {
@@ -87,6 +159,14 @@ pub struct RepositoryConfigUpdate {
     pub pre_create_timeout_seconds: Option<u64>,
     pub post_create_command: Option<Option<String>>,
     pub post_create_timeout_seconds: Option<u64>,
+    pub always_allow_tools: Option<Vec<String>>,
+    pub default_permission_mode: Option<Option<PermissionMode>>,
+    pub mcp_servers: Option<BTreeMap<String, McpServerConfig>>,
+    pub max_turn_seconds: Option<Option<u64>>,
+    pub max_idle_seconds: Option<Option<u64>>,
+    pub sandbox: Option<SandboxConfig>,
+    pub env_profiles: Option<BTreeMap<String, EnvProfileConfig>>,
+    pub shell: Option<Option<String>>,
 }
 
 /// Load repository config, applying defaults for missing managed keys.
@@ -116,6 +196,36 @@ pub fn update_repository_config(
     Ok(managed)
 }
 
+/// Add a tool to the repository's always-allow list and write the config.
+///
+/// Does nothing if the tool is already allowed.
+pub fn add_always_allow_tool(
+    repo_path: &Path,
+    tool_name: &str,
+    default_worktree_root_dir: &str,
+) -> Result<RepositoryConfig, String> {
+    let config = load_repository_config(repo_path, default_worktree_root_dir)?;
+    if config
+        .permissions
+        .always_allow_tools
+        .iter()
+        .any(|tool| tool == tool_name)
+    {
+        return Ok(config);
+    }
+
+    let mut always_allow_tools = config.permissions.always_allow_tools;
+    always_allow_tools.push(tool_name.to_string());
+    update_repository_config(
+        repo_path,
+        &RepositoryConfigUpdate {
+            always_allow_tools: Some(always_allow_tools),
+            ..Default::default()
+        },
+        default_worktree_root_dir,
+    )
+}
+
 fn config_path(repo_path: &Path) -> PathBuf {
     repo_path.join(".unbound").join("config.json")
 }
@@ -188,6 +298,60 @@ fn extract_managed_config(
             .unwrap_or(DEFAULT_HOOK_TIMEOUT_SECONDS),
     };
 
+    let permissions_obj = root.get("permissions").and_then(Value::as_object);
+    let always_allow_tools = permissions_obj
+        .and_then(|p| p.get("always_allow_tools"))
+        .and_then(Value::as_array)
+        .map(|tools| normalize_tool_names(tools.iter().filter_map(Value::as_str)))
+        .unwrap_or_default();
+    // Unknown modes are ignored rather than failing every agent turn
+    let default_mode = permissions_obj
+        .and_then(|p| p.get("default_mode"))
+        .and_then(Value::as_str)
+        .and_then(|mode| mode.parse().ok());
+
+    let mcp_servers = root
+        .get("mcp_servers")
+        .and_then(Value::as_object)
+        .map(|servers| {
+            servers
+                .iter()
+                .filter_map(|(name, server)| Some((name.clone(), extract_mcp_server(server)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let agent_timeouts_obj = root.get("agent_timeouts").and_then(Value::as_object);
+    // Zero would stop every turn immediately, so it reads as unlimited
+    let agent_timeouts = AgentTimeoutsConfig {
+        max_turn_seconds: agent_timeouts_obj
+            .and_then(|t| t.get("max_turn_seconds"))
+            .and_then(Value::as_u64)
+            .filter(|seconds| *seconds > 0),
+        max_idle_seconds: agent_timeouts_obj
+            .and_then(|t| t.get("max_idle_seconds"))
+            .and_then(Value::as_u64)
+            .filter(|seconds| *seconds > 0),
+    };
+
+    let sandbox = root.get("sandbox").map(extract_sandbox).unwrap_or_default();
+
+    let env_profiles = root
+        .get("env_profiles")
+        .and_then(Value::as_object)
+        .map(|profiles| {
+            profiles
+                .iter()
+                .map(|(name, profile)| (name.clone(), extract_env_profile(profile)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let shell = root
+        .get("shell")
+        .and_then(Value::as_str)
+        .and_then(normalize_shell);
+
     RepositoryConfig {
         schema_version,
         worktree: WorktreeConfig {
@@ -198,7 +362,123 @@ fn extract_managed_config(
             pre_create,
             post_create,
         },
+        permissions: PermissionsConfig {
+            always_allow_tools,
+            default_mode,
+        },
+        mcp_servers,
+        agent_timeouts,
+        sandbox,
+        env_profiles,
+        shell,
+    }
+}
+
+/// A configured shell path, or `None` for a blank one.
+fn normalize_shell(shell: &str) -> Option<String> {
+    let shell = shell.trim();
+    (!shell.is_empty()).then(|| shell.to_string())
+}
+
+fn extract_env_profile(value: &Value) -> EnvProfileConfig {
+    let vars = value
+        .get("vars")
+        .and_then(Value::as_object)
+        .map(|vars| {
+            vars.iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let secrets = value
+        .get("secrets")
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    EnvProfileConfig { vars, secrets }
+}
+
+fn extract_sandbox(value: &Value) -> SandboxConfig {
+    let Some(sandbox) = value.as_object() else {
+        return SandboxConfig::default();
+    };
+    let flag = |key: &str| sandbox.get(key).and_then(Value::as_bool).unwrap_or(false);
+    // Zero would stop every process from starting, so it reads as unlimited
+    let limit = |key: &str| {
+        sandbox
+            .get(key)
+            .and_then(Value::as_u64)
+            .filter(|limit| *limit > 0)
+    };
+    SandboxConfig {
+        enabled: flag("enabled"),
+        writable_paths: sandbox
+            .get("writable_paths")
+            .and_then(Value::as_array)
+            .map(|paths| {
+                paths
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        network_disabled: flag("network_disabled"),
+        max_memory_mb: limit("max_memory_mb"),
+        max_processes: limit("max_processes"),
+    }
+}
+
+/// Read one `mcp_servers` entry; entries without a command are skipped.
+fn extract_mcp_server(value: &Value) -> Option<McpServerConfig> {
+    let server = value.as_object()?;
+    let command = server.get("command").and_then(Value::as_str)?.to_string();
+    let args = server
+        .get("args")
+        .and_then(Value::as_array)
+        .map(|args| {
+            args.iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    let env = server
+        .get("env")
+        .and_then(Value::as_object)
+        .map(|env| {
+            env.iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let enabled = server
+        .get("enabled")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    Some(McpServerConfig {
+        command,
+        args,
+        env,
+        enabled,
+    })
+}
+
+/// Trim tool names, dropping empties and duplicates while keeping order.
+fn normalize_tool_names<'a>(tools: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tool in tools.map(str::trim).filter(|tool| !tool.is_empty()) {
+        if !normalized.iter().any(|existing| existing == tool) {
+            normalized.push(tool.to_string());
+        }
     }
+    normalized
 }
 
 fn apply_update(config: &mut RepositoryConfig, update: &RepositoryConfigUpdate) {
@@ -220,6 +500,31 @@ fn apply_update(config: &mut RepositoryConfig, update: &RepositoryConfigUpdate)
     if let Some(timeout_seconds) = update.post_create_timeout_seconds {
         config.setup_hooks.post_create.timeout_seconds = timeout_seconds;
     }
+    if let Some(tools) = &update.always_allow_tools {
+        config.permissions.always_allow_tools =
+            normalize_tool_names(tools.iter().map(String::as_str));
+    }
+    if let Some(default_mode) = update.default_permission_mode {
+        config.permissions.default_mode = default_mode;
+    }
+    if let Some(mcp_servers) = &update.mcp_servers {
+        config.mcp_servers = mcp_servers.clone();
+    }
+    if let Some(max_turn_seconds) = update.max_turn_seconds {
+        config.agent_timeouts.max_turn_seconds = max_turn_seconds;
+    }
+    if let Some(max_idle_seconds) = update.max_idle_seconds {
+        config.agent_timeouts.max_idle_seconds = max_idle_seconds;
+    }
+    if let Some(sandbox) = &update.sandbox {
+        config.sandbox = sandbox.clone();
+    }
+    if let Some(env_profiles) = &update.env_profiles {
+        config.env_profiles = env_profiles.clone();
+    }
+    if let Some(shell) = &update.shell {
+        config.shell = shell.as_deref().and_then(normalize_shell);
+    }
     config.schema_version = SCHEMA_VERSION;
 }
 
@@ -269,6 +574,66 @@ fn merge_managed_into_root(root: &mut Map<String, Value>, config: &RepositoryCon
         "timeout_seconds".to_string(),
         Value::Number(config.setup_hooks.post_create.timeout_seconds.into()),
     );
+
+    let permissions = ensure_object(root, "permissions");
+    permissions.insert(
+        "always_allow_tools".to_string(),
+        Value::Array(
+            config
+                .permissions
+                .always_allow_tools
+                .iter()
+                .cloned()
+                .map(Value::String)
+                .collect(),
+        ),
+    );
+    permissions.insert(
+        "default_mode".to_string(),
+        match config.permissions.default_mode {
+            Some(mode) => Value::String(mode.as_str().to_string()),
+            None => Value::Null,
+        },
+    );
+
+    root.insert(
+        "mcp_servers".to_string(),
+        serde_json::to_value(&config.mcp_servers).expect("MCP servers serialize to JSON"),
+    );
+
+    let agent_timeouts = ensure_object(root, "agent_timeouts");
+    agent_timeouts.insert(
+        "max_turn_seconds".to_string(),
+        match config.agent_timeouts.max_turn_seconds {
+            Some(v) => Value::Number(v.into()),
+            None => Value::Null,
+        },
+    );
+    agent_timeouts.insert(
+        "max_idle_seconds".to_string(),
+        match config.agent_timeouts.max_idle_seconds {
+            Some(v) => Value::Number(v.into()),
+            None => Value::Null,
+        },
+    );
+
+    root.insert(
+        "sandbox".to_string(),
+        serde_json::to_value(&config.sandbox).expect("sandbox config serializes to JSON"),
+    );
+
+    root.insert(
+        "env_profiles".to_string(),
+        serde_json::to_value(&config.env_profiles).expect("env profiles serialize to JSON"),
+    );
+
+    root.insert(
+        "shell".to_string(),
+        match &config.shell {
+            Some(v) => Value::String(v.clone()),
+            None => Value::Null,
+        },
+    );
 }
 
 fn ensure_object<'a>(parent: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
@@ -444,4 +809,202 @@ mod tests {
         );
         let _ = fs::remove_dir_all(repo_path);
     }
+
+    #[test]
+    fn add_always_allow_tool_persists_once() {
+        let repo_path = temp_repo_path();
+        let default_root = default_worktree_root_dir_for_repo("repo-123");
+
+        add_always_allow_tool(&repo_path, "Bash", &default_root).unwrap();
+        add_always_allow_tool(&repo_path, "Edit", &default_root).unwrap();
+        let updated = add_always_allow_tool(&repo_path, "Bash", &default_root).unwrap();
+
+        assert_eq!(
+            updated.permissions.always_allow_tools,
+            vec!["Bash".to_string(), "Edit".to_string()]
+        );
+        let loaded = load_repository_config(&repo_path, &default_root).unwrap();
+        assert_eq!(loaded, updated);
+        let _ = fs::remove_dir_all(repo_path);
+    }
+
+    #[test]
+    fn default_permission_mode_round_trips_and_ignores_unknown_modes() {
+        let repo_path = temp_repo_path();
+        let default_root = default_worktree_root_dir_for_repo("repo-123");
+
+        let updated = update_repository_config(
+            &repo_path,
+            &RepositoryConfigUpdate {
+                default_permission_mode: Some(Some(PermissionMode::AcceptEdits)),
+                ..Default::default()
+            },
+            &default_root,
+        )
+        .unwrap();
+        assert_eq!(
+            updated.permissions.default_mode,
+            Some(PermissionMode::AcceptEdits)
+        );
+        let content = fs::read_to_string(repo_path.join(".unbound").join("config.json")).unwrap();
+        let root: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(root["permissions"]["default_mode"], "acceptEdits");
+
+        fs::write(
+            repo_path.join(".unbound").join("config.json"),
+            r#"{ "permissions": { "default_mode": "yolo" } }"#,
+        )
+        .unwrap();
+        let loaded = load_repository_config(&repo_path, &default_root).unwrap();
+        assert_eq!(loaded.permissions.default_mode, None);
+        let _ = fs::remove_dir_all(repo_path);
+    }
+
+    #[test]
+    fn agent_timeouts_round_trip_and_clear() {
+        let repo_path = temp_repo_path();
+        let default_root = default_worktree_root_dir_for_repo("repo-123");
+
+        let updated = update_repository_config(
+            &repo_path,
+            &RepositoryConfigUpdate {
+                max_turn_seconds: Some(Some(1800)),
+                max_idle_seconds: Some(Some(300)),
+                ..Default::default()
+            },
+            &default_root,
+        )
+        .unwrap();
+        assert_eq!(
+            updated.agent_timeouts,
+            AgentTimeoutsConfig {
+                max_turn_seconds: Some(1800),
+                max_idle_seconds: Some(300),
+            }
+        );
+        let loaded = load_repository_config(&repo_path, &default_root).unwrap();
+        assert_eq!(loaded.agent_timeouts, updated.agent_timeouts);
+
+        let cleared = update_repository_config(
+            &repo_path,
+            &RepositoryConfigUpdate {
+                max_idle_seconds: Some(None),
+                ..Default::default()
+            },
+            &default_root,
+        )
+        .unwrap();
+        assert_eq!(cleared.agent_timeouts.max_turn_seconds, Some(1800));
+        assert_eq!(cleared.agent_timeouts.max_idle_seconds, None);
+        let content = fs::read_to_string(repo_path.join(".unbound").join("config.json")).unwrap();
+        let root: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(root["agent_timeouts"]["max_idle_seconds"], Value::Null);
+        let _ = fs::remove_dir_all(repo_path);
+    }
+
+    #[test]
+    fn sandbox_round_trips_and_ignores_zero_limits() {
+        let repo_path = temp_repo_path();
+        let default_root = default_worktree_root_dir_for_repo("repo-123");
+        let sandbox = SandboxConfig {
+            enabled: true,
+            writable_paths: vec!["~/.cache".to_string()],
+            network_disabled: true,
+            max_memory_mb: Some(4096),
+            max_processes: None,
+        };
+
+        let updated = update_repository_config(
+            &repo_path,
+            &RepositoryConfigUpdate {
+                sandbox: Some(sandbox.clone()),
+                ..Default::default()
+            },
+            &default_root,
+        )
+        .unwrap();
+        assert_eq!(updated.sandbox, sandbox);
+        assert_eq!(
+            load_repository_config(&repo_path, &default_root)
+                .unwrap()
+                .sandbox,
+            sandbox
+        );
+
+        fs::write(
+            repo_path.join(".unbound").join("config.json"),
+            r#"{"sandbox":{"enabled":true,"max_memory_mb":0}}"#,
+        )
+        .unwrap();
+        let loaded = load_repository_config(&repo_path, &default_root).unwrap();
+        assert!(loaded.sandbox.enabled);
+        assert!(!loaded.sandbox.network_disabled);
+        assert_eq!(loaded.sandbox.max_memory_mb, None);
+        let _ = fs::remove_dir_all(repo_path);
+    }
+
+    #[test]
+    fn env_profiles_round_trip() {
+        let repo_path = temp_repo_path();
+        let default_root = default_worktree_root_dir_for_repo("repo-123");
+        let profiles = BTreeMap::from([(
+            "staging".to_string(),
+            EnvProfileConfig {
+                vars: BTreeMap::from([("API_URL".to_string(), "https://staging".to_string())]),
+                secrets: vec!["API_TOKEN".to_string()],
+            },
+        )]);
+
+        let updated = update_repository_config(
+            &repo_path,
+            &RepositoryConfigUpdate {
+                env_profiles: Some(profiles.clone()),
+                ..Default::default()
+            },
+            &default_root,
+        )
+        .unwrap();
+        assert_eq!(updated.env_profiles, profiles);
+        let loaded = load_repository_config(&repo_path, &default_root).unwrap();
+        assert_eq!(loaded.env_profiles, profiles);
+
+        let content = fs::read_to_string(repo_path.join(".unbound").join("config.json")).unwrap();
+        let root: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(
+            root["env_profiles"]["staging"]["secrets"],
+            serde_json::json!(["API_TOKEN"])
+        );
+        let _ = fs::remove_dir_all(repo_path);
+    }
+
+    #[test]
+    fn shell_round_trips_and_blank_clears() {
+        let repo_path = temp_repo_path();
+        let default_root = default_worktree_root_dir_for_repo("repo-123");
+        let update = |shell: &str| {
+            update_repository_config(
+                &repo_path,
+                &RepositoryConfigUpdate {
+                    shell: Some(Some(shell.to_string())),
+                    ..Default::default()
+                },
+                &default_root,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(update(" /bin/bash ").shell.as_deref(), Some("/bin/bash"));
+        assert_eq!(
+            load_repository_config(&repo_path, &default_root)
+                .unwrap()
+                .shell
+                .as_deref(),
+            Some("/bin/bash")
+        );
+        assert_eq!(update("").shell, None);
+        let content = fs::read_to_string(repo_path.join(".unbound").join("config.json")).unwrap();
+        let root: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(root["shell"], Value::Null);
+        let _ = fs::remove_dir_all(repo_path);
+    }
 }
@@ -0,0 +1,426 @@
+//! Opt-in sandbox for agent CLIs and terminal commands.
+//!
+//! Sandboxed processes run under bubblewrap (`bwrap`): the filesystem is
+//! mounted read-only except for the session's worktree, its git directories,
+//! a private `/tmp` and any extra writable paths from the repository's
+//! `sandbox` config. The worktree's `.unbound` directory and the repository's
+//! git hooks and config stay read-only. The network can be unshared, and
+//! memory and process-count rlimits are set before exec. Linux only.
+//!
+//! The rlimits are coarse: `RLIMIT_AS` caps each process's virtual address
+//! space, not its resident memory, so runtimes that reserve large address
+//! ranges up front (Node, JVMs) can fail to start under low limits.
+//! `RLIMIT_NPROC` counts every process of the daemon's user, not only those
+//! in the sandbox, so it has to leave room for everything else that user runs.
+
+use crate::utils::repository_config::SandboxConfig;
+use login_shell::ShellInvocation;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+const BWRAP: &str = "bwrap";
+
+/// Directory holding the repository's `.unbound/config.json`.
+const CONFIG_DIR: &str = ".unbound";
+
+/// Confinement for processes started in one worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    root: PathBuf,
+    /// Git directories of the checkout; a linked worktree's live outside it.
+    git_dirs: Vec<PathBuf>,
+    writable_paths: Vec<PathBuf>,
+    network_disabled: bool,
+    max_memory_bytes: Option<u64>,
+    max_processes: Option<u64>,
+}
+
+impl SandboxPolicy {
+    /// The policy for processes in `root`, or `None` when the repository
+    /// has not enabled the sandbox. Relative writable paths are relative to
+    /// `root`.
+    pub fn from_config(config: &SandboxConfig, root: &Path) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        Some(Self {
+            root: root.to_path_buf(),
+            git_dirs: git_dirs(root),
+            writable_paths: config
+                .writable_paths
+                .iter()
+                .map(|path| root.join(expand_home_dir(path)))
+                .collect(),
+            network_disabled: config.network_disabled,
+            max_memory_bytes: config
+                .max_memory_mb
+                .map(|mb| mb.saturating_mul(1024 * 1024)),
+            max_processes: config.max_processes,
+        })
+    }
+
+    /// Check that sandboxed processes can be started on this machine.
+    pub fn check(&self) -> Result<(), SandboxError> {
+        if !cfg!(target_os = "linux") {
+            return Err(SandboxError::Unsupported);
+        }
+        if find_in_path(BWRAP).is_none() {
+            return Err(SandboxError::BubblewrapMissing);
+        }
+        Ok(())
+    }
+
+    /// A command running `program` with `args` inside the sandbox.
+    pub fn command(&self, program: &str, args: &[String]) -> Command {
+        let mut command = Command::new(BWRAP);
+        command.args(self.bwrap_args()).arg(program).args(args);
+        self.apply_limits(&mut command);
+        command
+    }
+
+    /// A command running a shell `invocation` inside the sandbox, with its
+    /// login environment.
+    pub fn shell_command(&self, invocation: &ShellInvocation) -> Command {
+        let mut command = self.command(&invocation.program().to_string_lossy(), invocation.args());
+        invocation.apply_env(&mut command);
+        command
+    }
+
+    fn bwrap_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "--ro-bind",
+            "/",
+            "/",
+            "--dev",
+            "/dev",
+            "--proc",
+            "/proc",
+            "--tmpfs",
+            "/tmp",
+            "--die-with-parent",
+        ]
+        .map(String::from)
+        .into();
+        let mut bind = |option: &str, path: &Path| {
+            let path = path.to_string_lossy().into_owned();
+            args.extend([option.to_string(), path.clone(), path]);
+        };
+        bind("--bind", &self.root);
+        for git_dir in &self.git_dirs {
+            bind("--bind", git_dir);
+            // Hooks and config run outside the sandbox on the next git command
+            bind("--ro-bind-try", &git_dir.join("hooks"));
+            bind("--ro-bind-try", &git_dir.join("config"));
+        }
+        for path in &self.writable_paths {
+            // Paths that do not exist yet are skipped rather than failing
+            bind("--bind-try", path);
+        }
+        // Bound last so no writable path can expose the repository config
+        let config_dir = self.root.join(CONFIG_DIR);
+        if config_dir.is_dir() {
+            bind("--ro-bind", &config_dir);
+        } else {
+            // An empty read-only mount keeps the config from being created;
+            // bubblewrap leaves the empty mount point behind
+            let config_dir = config_dir.to_string_lossy().into_owned();
+            args.extend([
+                "--tmpfs".to_string(),
+                config_dir.clone(),
+                "--remount-ro".to_string(),
+                config_dir,
+            ]);
+        }
+        if self.network_disabled {
+            args.push("--unshare-net".to_string());
+        }
+        args.push("--".to_string());
+        args
+    }
+
+    /// Set the memory limit as `RLIMIT_AS` and the process limit as the
+    /// per-user `RLIMIT_NPROC`; see the module docs for their caveats.
+    #[cfg(target_os = "linux")]
+    fn apply_limits(&self, command: &mut Command) {
+        let limits: Vec<_> = [
+            (libc::RLIMIT_AS, self.max_memory_bytes),
+            (libc::RLIMIT_NPROC, self.max_processes),
+        ]
+        .into_iter()
+        .filter_map(|(resource, limit)| Some((resource, limit?)))
+        .collect();
+        if limits.is_empty() {
+            return;
+        }
+        // setrlimit is async-signal-safe; the limits are inherited by
+        // bubblewrap and everything it starts
+        unsafe {
+            command.pre_exec(move || {
+                for (resource, limit) in &limits {
+                    let rlimit = libc::rlimit {
+                        rlim_cur: *limit as libc::rlim_t,
+                        rlim_max: *limit as libc::rlim_t,
+                    };
+                    if libc::setrlimit(*resource, &rlimit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn apply_limits(&self, _command: &mut Command) {}
+}
+
+/// Why a sandboxed process could not be started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxError {
+    Unsupported,
+    BubblewrapMissing,
+}
+
+impl std::fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "The sandbox is only supported on Linux"),
+            Self::BubblewrapMissing => write!(
+                f,
+                "The sandbox requires bubblewrap (bwrap), which was not found in PATH"
+            ),
+        }
+    }
+}
+
+impl SandboxError {
+    /// The `sandbox_error` message appended to the transcript.
+    pub fn transcript_json(&self) -> String {
+        serde_json::json!({
+            "type": "sandbox_error",
+            "message": self.to_string(),
+        })
+        .to_string()
+    }
+}
+
+/// What a sandboxed process was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    /// A write outside the writable paths.
+    Filesystem,
+    /// Network access with the network disabled.
+    Network,
+    /// An allocation over the address-space limit.
+    Memory,
+    /// A fork over the process limit, which counts all of the daemon user's
+    /// processes.
+    Processes,
+}
+
+/// A sandbox refusal recognized in a process's stderr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxViolation {
+    pub kind: ViolationKind,
+    /// The stderr line reporting it.
+    pub detail: String,
+}
+
+impl SandboxViolation {
+    /// Recognize the errors a sandbox refusal produces in a stderr line.
+    pub fn from_stderr(line: &str) -> Option<Self> {
+        let lower = line.to_ascii_lowercase();
+        let kind = if lower.contains("read-only file system") {
+            ViolationKind::Filesystem
+        } else if lower.contains("network is unreachable")
+            || lower.contains("temporary failure in name resolution")
+            || lower.contains("could not resolve host")
+        {
+            ViolationKind::Network
+        } else if lower.contains("cannot allocate memory") || lower.contains("out of memory") {
+            ViolationKind::Memory
+        } else if lower.contains("fork") && lower.contains("resource temporarily unavailable") {
+            ViolationKind::Processes
+        } else {
+            return None;
+        };
+        Some(Self {
+            kind,
+            detail: line.to_string(),
+        })
+    }
+
+    /// The `sandbox_violation` message appended to the transcript.
+    pub fn transcript_json(&self) -> String {
+        let message = match self.kind {
+            ViolationKind::Filesystem => "Write outside the sandbox's writable paths was refused",
+            ViolationKind::Network => "Network access is disabled in the sandbox",
+            ViolationKind::Memory => "The sandbox's address-space limit was reached",
+            ViolationKind::Processes => {
+                "The sandbox's process limit, which counts all of the user's processes, was reached"
+            }
+        };
+        serde_json::json!({
+            "type": "sandbox_violation",
+            "kind": self.kind,
+            "detail": self.detail,
+            "message": message,
+        })
+        .to_string()
+    }
+}
+
+/// The git directory of the checkout at `root`; for a linked worktree, its
+/// own git directory and the repository's common one.
+fn git_dirs(root: &Path) -> Vec<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return vec![dot_git];
+    }
+    let Ok(dot_git) = std::fs::read_to_string(dot_git) else {
+        return Vec::new();
+    };
+    let Some(git_dir) = dot_git.trim().strip_prefix("gitdir:") else {
+        return Vec::new();
+    };
+    let git_dir = root.join(git_dir.trim());
+    let common_dir = std::fs::read_to_string(git_dir.join("commondir"))
+        .map(|common_dir| git_dir.join(common_dir.trim()))
+        .ok();
+    [Some(git_dir), common_dir]
+        .into_iter()
+        .flatten()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .collect()
+}
+
+fn expand_home_dir(path: &str) -> PathBuf {
+    let home = || std::env::var("HOME").map(PathBuf::from);
+    if path == "~" {
+        if let Ok(home) = home() {
+            return home;
+        }
+    }
+    if let Some(suffix) = path.strip_prefix("~/") {
+        if let Ok(home) = home() {
+            return home.join(suffix);
+        }
+    }
+    PathBuf::from(path)
+}
+
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SandboxConfig {
+        SandboxConfig {
+            enabled: true,
+            writable_paths: vec!["target".to_string(), "/var/cache/build".to_string()],
+            network_disabled: true,
+            max_memory_mb: Some(512),
+            max_processes: None,
+        }
+    }
+
+    #[test]
+    fn from_config_is_none_when_disabled() {
+        let config = SandboxConfig {
+            enabled: false,
+            ..config()
+        };
+        assert_eq!(
+            SandboxPolicy::from_config(&config, Path::new("/work")),
+            None
+        );
+    }
+
+    #[test]
+    fn bwrap_args_confine_writes_to_the_worktree() {
+        let policy = SandboxPolicy::from_config(&config(), Path::new("/work/tree")).unwrap();
+        assert_eq!(policy.max_memory_bytes, Some(512 * 1024 * 1024));
+
+        let args = policy.bwrap_args().join(" ");
+
+        assert!(args.starts_with("--ro-bind / / --dev /dev --proc /proc --tmpfs /tmp"));
+        assert!(args.contains("--bind /work/tree /work/tree"));
+        assert!(args.contains("--bind-try /work/tree/target /work/tree/target"));
+        assert!(args.contains("--bind-try /var/cache/build /var/cache/build"));
+        assert!(args.contains("--tmpfs /work/tree/.unbound --remount-ro /work/tree/.unbound"));
+        assert!(args.ends_with("--unshare-net --"));
+    }
+
+    #[test]
+    fn bwrap_args_keep_config_read_only_and_git_dirs_writable() {
+        let dir = std::env::temp_dir().join(format!("sandbox-test-{}", uuid::Uuid::new_v4()));
+        let common_dir = dir.join("repo").join(".git");
+        let git_dir = common_dir.join("worktrees").join("tree");
+        let worktree = dir.join("tree");
+        std::fs::create_dir_all(&git_dir).unwrap();
+        std::fs::create_dir_all(worktree.join(CONFIG_DIR)).unwrap();
+        std::fs::write(git_dir.join("commondir"), "../..\n").unwrap();
+        std::fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", git_dir.display()),
+        )
+        .unwrap();
+        let worktree = worktree.canonicalize().unwrap();
+        let git_dir = git_dir.canonicalize().unwrap();
+        let common_dir = common_dir.canonicalize().unwrap();
+
+        let policy = SandboxPolicy::from_config(&config(), &worktree).unwrap();
+        let args = policy.bwrap_args().join(" ");
+
+        assert_eq!(policy.git_dirs, vec![git_dir.clone(), common_dir.clone()]);
+        let config_dir = worktree.join(CONFIG_DIR).display().to_string();
+        assert!(args.contains(&format!("--ro-bind {config_dir} {config_dir}")));
+        for dir in [&git_dir, &common_dir] {
+            let dir = dir.display().to_string();
+            assert!(args.contains(&format!("--bind {dir} {dir}")));
+            assert!(args.contains(&format!("--ro-bind-try {dir}/hooks {dir}/hooks")));
+        }
+        // The config mount comes after every writable one
+        assert!(args.find(&config_dir).unwrap() > args.find("--bind-try").unwrap());
+
+        assert_eq!(git_dirs(common_dir.parent().unwrap()), vec![common_dir]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn violations_are_recognized_in_stderr() {
+        let violation =
+            SandboxViolation::from_stderr("touch: cannot touch '/etc/x': Read-only file system")
+                .unwrap();
+        assert_eq!(violation.kind, ViolationKind::Filesystem);
+        assert_eq!(
+            SandboxViolation::from_stderr("curl: (6) Could not resolve host: example.com")
+                .map(|violation| violation.kind),
+            Some(ViolationKind::Network)
+        );
+        assert_eq!(
+            SandboxViolation::from_stderr("bash: fork: Resource temporarily unavailable")
+                .map(|violation| violation.kind),
+            Some(ViolationKind::Processes)
+        );
+        assert_eq!(
+            SandboxViolation::from_stderr("warning: unused import"),
+            None
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&violation.transcript_json()).unwrap();
+        assert_eq!(json["type"], "sandbox_violation");
+        assert_eq!(json["kind"], "filesystem");
+        assert_eq!(
+            json["detail"],
+            "touch: cannot touch '/etc/x': Read-only file system"
+        );
+    }
+}
@@ -0,0 +1,559 @@
+//! Portable session bundles for `session.export` / `session.import`.
+//!
+//! A bundle is a gzipped tar archive with two entries:
+//! - `manifest.json`: format version, session and repository metadata, runtime status
+//! - `messages.jsonl`: one message per line with its original timestamp, in sequence order
+//!
+//! Readers check `schema_version` before parsing anything else and refuse
+//! versions they do not know.
+
+use agent_session_sqlite_persist_core::{
+    Repository, RuntimeStatusEnvelope, Session, SessionStatus,
+};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Bundle format version written by this daemon and the only one it reads.
+pub const SESSION_BUNDLE_SCHEMA_VERSION: u64 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const MESSAGES_ENTRY: &str = "messages.jsonl";
+
+/// Session metadata carried in a bundle.
+///
+/// Machine-local fields (machine/space scope, provider resume IDs) are left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleSession {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub agent_name: Option<String>,
+    #[serde(default)]
+    pub issue_id: Option<String>,
+    #[serde(default)]
+    pub issue_title: Option<String>,
+    #[serde(default)]
+    pub issue_url: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    pub status: SessionStatus,
+    #[serde(default)]
+    pub is_worktree: bool,
+    #[serde(default)]
+    pub worktree_path: Option<String>,
+    #[serde(default)]
+    pub parent_session_id: Option<String>,
+    #[serde(default)]
+    pub forked_from_sequence: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed_at: DateTime<Utc>,
+}
+
+impl From<&Session> for BundleSession {
+    fn from(session: &Session) -> Self {
+        Self {
+            id: session.id.as_str().to_string(),
+            title: session.title.clone(),
+            agent_name: session.agent_name.clone(),
+            issue_id: session.issue_id.clone(),
+            issue_title: session.issue_title.clone(),
+            issue_url: session.issue_url.clone(),
+            provider: session.effective_provider().map(String::from),
+            status: session.status,
+            is_worktree: session.is_worktree,
+            worktree_path: session.worktree_path.clone(),
+            parent_session_id: session
+                .parent_session_id
+                .as_ref()
+                .map(|id| id.as_str().to_string()),
+            forked_from_sequence: session.forked_from_sequence,
+            created_at: session.created_at,
+            last_accessed_at: session.last_accessed_at,
+        }
+    }
+}
+
+/// Repository a bundled session belonged to. Import matches it by `path`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleRepository {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    #[serde(default)]
+    pub is_git_repository: bool,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    #[serde(default)]
+    pub default_remote: Option<String>,
+}
+
+impl From<&Repository> for BundleRepository {
+    fn from(repository: &Repository) -> Self {
+        Self {
+            id: repository.id.as_str().to_string(),
+            path: repository.path.clone(),
+            name: repository.name.clone(),
+            is_git_repository: repository.is_git_repository,
+            default_branch: repository.default_branch.clone(),
+            default_remote: repository.default_remote.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionBundleManifest {
+    pub schema_version: u64,
+    pub exported_at: DateTime<Utc>,
+    pub session: BundleSession,
+    #[serde(default)]
+    pub repository: Option<BundleRepository>,
+    #[serde(default)]
+    pub runtime_status: Option<RuntimeStatusEnvelope>,
+    pub message_count: usize,
+}
+
+/// One line of `messages.jsonl`. `content` is the raw stored message content
+/// and `timestamp` is when the message was originally written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleMessage {
+    pub sequence_number: i64,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A decoded bundle. `messages` are always in ascending sequence order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionBundle {
+    pub manifest: SessionBundleManifest,
+    pub messages: Vec<BundleMessage>,
+}
+
+impl SessionBundle {
+    /// Builds a bundle at the current format version from `messages` in sequence order.
+    pub fn new(
+        session: &Session,
+        repository: Option<&Repository>,
+        runtime_status: Option<RuntimeStatusEnvelope>,
+        messages: Vec<BundleMessage>,
+    ) -> Self {
+        Self {
+            manifest: SessionBundleManifest {
+                schema_version: SESSION_BUNDLE_SCHEMA_VERSION,
+                exported_at: Utc::now(),
+                session: BundleSession::from(session),
+                repository: repository.map(BundleRepository::from),
+                runtime_status,
+                message_count: messages.len(),
+            },
+            messages,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionBundleError {
+    /// The bundle declares a format version this daemon cannot read.
+    UnsupportedSchemaVersion(u64),
+    /// The file is not a well-formed session bundle.
+    Malformed(String),
+    /// The bundle file could not be opened or written.
+    Io(String),
+}
+
+impl fmt::Display for SessionBundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedSchemaVersion(version) => write!(
+                f,
+                "unsupported session bundle schema version {version} (supported: {SESSION_BUNDLE_SCHEMA_VERSION})"
+            ),
+            Self::Malformed(message) => write!(f, "malformed session bundle: {message}"),
+            Self::Io(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Writes `bundle` to `path`, replacing any existing file atomically.
+pub fn write_session_bundle(path: &Path, bundle: &SessionBundle) -> Result<(), SessionBundleError> {
+    let manifest = serde_json::to_vec_pretty(&bundle.manifest).map_err(|e| {
+        SessionBundleError::Io(format!("failed to serialize bundle manifest: {}", e))
+    })?;
+
+    let mut messages = Vec::new();
+    for message in &bundle.messages {
+        serde_json::to_writer(&mut messages, message).map_err(|e| {
+            SessionBundleError::Io(format!("failed to serialize bundle message: {}", e))
+        })?;
+        messages.push(b'\n');
+    }
+
+    write_archive(
+        path,
+        &[(MANIFEST_ENTRY, &manifest), (MESSAGES_ENTRY, &messages)],
+    )
+}
+
+/// Reads and validates the bundle at `path`.
+pub fn read_session_bundle(path: &Path) -> Result<SessionBundle, SessionBundleError> {
+    let file = fs::File::open(path).map_err(|e| {
+        SessionBundleError::Io(format!("failed to open bundle {}: {}", path.display(), e))
+    })?;
+
+    let malformed = |e: std::io::Error| SessionBundleError::Malformed(e.to_string());
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut manifest = None;
+    let mut messages = None;
+    for entry in archive.entries().map_err(malformed)? {
+        let mut entry = entry.map_err(malformed)?;
+        let name = entry
+            .path()
+            .map_err(malformed)?
+            .to_string_lossy()
+            .into_owned();
+        let mut data = Vec::new();
+        match name.as_str() {
+            MANIFEST_ENTRY => {
+                entry.read_to_end(&mut data).map_err(malformed)?;
+                manifest = Some(data);
+            }
+            MESSAGES_ENTRY => {
+                entry.read_to_end(&mut data).map_err(malformed)?;
+                messages = Some(data);
+            }
+            // Entries added by later writers of the same version are skipped.
+            _ => {}
+        }
+    }
+
+    let manifest = manifest
+        .ok_or_else(|| SessionBundleError::Malformed(format!("missing {MANIFEST_ENTRY}")))?;
+    let manifest: serde_json::Value = serde_json::from_slice(&manifest)
+        .map_err(|e| SessionBundleError::Malformed(format!("invalid {MANIFEST_ENTRY}: {e}")))?;
+    let Some(schema_version) = manifest.get("schema_version").and_then(|v| v.as_u64()) else {
+        return Err(SessionBundleError::Malformed(
+            "manifest has no schema_version".to_string(),
+        ));
+    };
+    if schema_version != SESSION_BUNDLE_SCHEMA_VERSION {
+        return Err(SessionBundleError::UnsupportedSchemaVersion(schema_version));
+    }
+    let manifest: SessionBundleManifest = serde_json::from_value(manifest)
+        .map_err(|e| SessionBundleError::Malformed(format!("invalid {MANIFEST_ENTRY}: {e}")))?;
+
+    let messages = messages
+        .ok_or_else(|| SessionBundleError::Malformed(format!("missing {MESSAGES_ENTRY}")))?;
+    let mut messages = parse_messages(&messages)?;
+    if messages.len() != manifest.message_count {
+        return Err(SessionBundleError::Malformed(format!(
+            "manifest lists {} messages but {MESSAGES_ENTRY} has {}",
+            manifest.message_count,
+            messages.len()
+        )));
+    }
+    messages.sort_by_key(|message| message.sequence_number);
+    if let Some(pair) = messages
+        .windows(2)
+        .find(|pair| pair[0].sequence_number == pair[1].sequence_number)
+    {
+        return Err(SessionBundleError::Malformed(format!(
+            "duplicate message sequence number {}",
+            pair[0].sequence_number
+        )));
+    }
+
+    Ok(SessionBundle { manifest, messages })
+}
+
+fn parse_messages(data: &[u8]) -> Result<Vec<BundleMessage>, SessionBundleError> {
+    data.split(|byte| *byte == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|(index, line)| {
+            serde_json::from_slice(line).map_err(|e| {
+                SessionBundleError::Malformed(format!(
+                    "invalid {MESSAGES_ENTRY} line {}: {}",
+                    index + 1,
+                    e
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Writes a gzipped tar of `entries` to a temp file next to `path`, then renames it into place.
+fn write_archive(path: &Path, entries: &[(&str, &[u8])]) -> Result<(), SessionBundleError> {
+    let file_name = path.file_name().ok_or_else(|| {
+        SessionBundleError::Io(format!("invalid bundle path: {}", path.display()))
+    })?;
+    let tmp_path = path.with_file_name(format!(
+        ".{}.tmp.{}",
+        file_name.to_string_lossy(),
+        Uuid::new_v4()
+    ));
+
+    let result = write_archive_file(&tmp_path, entries).and_then(|()| {
+        fs::rename(&tmp_path, path).map_err(|e| {
+            format!(
+                "failed to move bundle into place at {}: {}",
+                path.display(),
+                e
+            )
+        })
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result.map_err(SessionBundleError::Io)
+}
+
+fn write_archive_file(path: &Path, entries: &[(&str, &[u8])]) -> Result<(), String> {
+    let file = fs::File::create(path)
+        .map_err(|e| format!("failed to create bundle {}: {}", path.display(), e))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = Utc::now().timestamp().max(0) as u64;
+
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        builder
+            .append_data(&mut header, name, *data)
+            .map_err(|e| format!("failed to write bundle entry {}: {}", name, e))?;
+    }
+
+    let mut file = builder
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .map_err(|e| format!("failed to finish bundle {}: {}", path.display(), e))?;
+    file.flush()
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("failed to sync bundle {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_session_sqlite_persist_core::{
+        CodingSessionRuntimeState, CodingSessionStatus, RepositoryId, SessionId,
+        RUNTIME_STATUS_SCHEMA_VERSION,
+    };
+    use std::path::PathBuf;
+
+    fn temp_bundle_path() -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("daemon-session-bundle-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("session.tar.gz")
+    }
+
+    fn sample_session() -> Session {
+        let now = Utc::now();
+        Session {
+            id: SessionId::from_string("session-1"),
+            repository_id: RepositoryId::from_string("repo-1"),
+            machine_id: Some("machine-1".to_string()),
+            space_id: Some("space-1".to_string()),
+            title: "Fix the parser".to_string(),
+            agent_name: Some("builder".to_string()),
+            issue_id: None,
+            issue_title: None,
+            issue_url: None,
+            provider: Some("claude".to_string()),
+            provider_session_id: Some("resume-1".to_string()),
+            claude_session_id: None,
+            status: SessionStatus::Active,
+            is_worktree: false,
+            worktree_path: None,
+            parent_session_id: None,
+            forked_from_sequence: None,
+            permission_mode: None,
+            created_at: now,
+            last_accessed_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn sample_repository() -> Repository {
+        let now = Utc::now();
+        Repository {
+            id: RepositoryId::from_string("repo-1"),
+            path: "/work/parser".to_string(),
+            name: "parser".to_string(),
+            machine_id: None,
+            space_id: None,
+            is_git_repository: true,
+            sessions_path: None,
+            default_branch: Some("main".to_string()),
+            default_remote: Some("origin".to_string()),
+            last_accessed_at: now,
+            added_at: now,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn message(sequence_number: i64, content: &str) -> BundleMessage {
+        BundleMessage {
+            sequence_number,
+            content: content.to_string(),
+            timestamp: Utc::now() - chrono::Duration::days(2),
+        }
+    }
+
+    fn manifest_json(schema_version: u64, message_count: usize) -> Vec<u8> {
+        let mut manifest = SessionBundle::new(&sample_session(), None, None, Vec::new()).manifest;
+        manifest.schema_version = schema_version;
+        manifest.message_count = message_count;
+        serde_json::to_vec(&manifest).unwrap()
+    }
+
+    #[test]
+    fn round_trip_preserves_metadata_and_messages() {
+        let path = temp_bundle_path();
+        let runtime_status = RuntimeStatusEnvelope {
+            schema_version: RUNTIME_STATUS_SCHEMA_VERSION,
+            coding_session: CodingSessionRuntimeState {
+                status: CodingSessionStatus::Error,
+                error_message: Some("process exited".to_string()),
+                question: None,
+                queue_length: 0,
+                scheduler_position: None,
+            },
+            device_id: "device-1".to_string(),
+            session_id: SessionId::from_string("session-1"),
+            updated_at_ms: 42,
+        };
+        let mut session = sample_session();
+        session.status = SessionStatus::Archived;
+        session.created_at -= chrono::Duration::days(3);
+        let bundle = SessionBundle::new(
+            &session,
+            Some(&sample_repository()),
+            Some(runtime_status),
+            vec![
+                message(1, "plain prompt"),
+                message(2, r#"{"type":"assistant","message":{"content":[]}}"#),
+                message(3, "line one\nline two"),
+            ],
+        );
+
+        write_session_bundle(&path, &bundle).unwrap();
+        let loaded = read_session_bundle(&path).unwrap();
+
+        assert_eq!(loaded, bundle);
+        assert_eq!(loaded.manifest.session.provider.as_deref(), Some("claude"));
+        assert_eq!(loaded.manifest.session.status, SessionStatus::Archived);
+        assert_eq!(loaded.manifest.session.created_at, session.created_at);
+        assert_eq!(loaded.messages[0].timestamp, bundle.messages[0].timestamp);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn unknown_schema_version_is_refused_before_parsing_messages() {
+        let path = temp_bundle_path();
+        write_archive(
+            &path,
+            &[
+                (MANIFEST_ENTRY, &manifest_json(2, 1)),
+                (MESSAGES_ENTRY, b"not the v1 message format"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_session_bundle(&path),
+            Err(SessionBundleError::UnsupportedSchemaVersion(2))
+        );
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn messages_are_returned_in_sequence_order() {
+        let path = temp_bundle_path();
+        let lines = [
+            message(2, "second"),
+            message(1, "first"),
+            message(3, "third"),
+        ]
+        .iter()
+        .map(|m| serde_json::to_string(m).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+        write_archive(
+            &path,
+            &[
+                (MANIFEST_ENTRY, &manifest_json(1, 3)),
+                (MESSAGES_ENTRY, lines.as_bytes()),
+            ],
+        )
+        .unwrap();
+
+        let loaded = read_session_bundle(&path).unwrap();
+
+        let contents: Vec<&str> = loaded.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["first", "second", "third"]);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn inconsistent_bundles_are_malformed() {
+        let path = temp_bundle_path();
+        let duplicate = format!(
+            "{}\n{}\n",
+            serde_json::to_string(&message(1, "a")).unwrap(),
+            serde_json::to_string(&message(1, "b")).unwrap()
+        );
+        let cases: [&[(&str, &[u8])]; 4] = [
+            &[(MESSAGES_ENTRY, b"")],
+            &[(MANIFEST_ENTRY, &manifest_json(1, 0))],
+            &[
+                (MANIFEST_ENTRY, &manifest_json(1, 2)),
+                (MESSAGES_ENTRY, b""),
+            ],
+            &[
+                (MANIFEST_ENTRY, &manifest_json(1, 2)),
+                (MESSAGES_ENTRY, duplicate.as_bytes()),
+            ],
+        ];
+
+        for entries in cases {
+            write_archive(&path, entries).unwrap();
+            assert!(matches!(
+                read_session_bundle(&path),
+                Err(SessionBundleError::Malformed(_))
+            ));
+        }
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn non_archive_files_are_malformed() {
+        let path = temp_bundle_path();
+        fs::write(&path, b"{\"not\":\"a bundle\"}").unwrap();
+
+        assert!(matches!(
+            read_session_bundle(&path),
+            Err(SessionBundleError::Malformed(_))
+        ));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn missing_files_are_io_errors() {
+        let path = temp_bundle_path().with_file_name("missing.tar.gz");
+
+        assert!(matches!(
+            read_session_bundle(&path),
+            Err(SessionBundleError::Io(_))
+        ));
+    }
+}
@@ -1,7 +1,8 @@
 //! Configuration management for the daemon.
 
-use crate::{CoreResult, Paths};
+use crate::{CoreError, CoreResult, Paths};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 
 /// Default log level.
@@ -12,6 +13,19 @@ pub const DEFAULT_ENVIRONMENT: &str = "dev";
 pub const DEFAULT_OTEL_SAMPLER: &str = "always_on";
 /// Default OTEL sampler argument.
 pub const DEFAULT_OTEL_SAMPLER_ARG: f64 = 1.0;
+/// Default seconds an agent waits for a tool permission decision before denying.
+pub const DEFAULT_PERMISSION_TIMEOUT_SECS: u64 = 600;
+/// Default maximum number of agent processes running at once.
+pub const DEFAULT_MAX_CONCURRENT_AGENTS: usize = 6;
+/// Default maximum number of agent processes running at once in one repository.
+pub const DEFAULT_MAX_CONCURRENT_AGENTS_PER_REPOSITORY: usize = 3;
+/// Default seconds a keep-alive Claude process waits for the next turn; 0
+/// runs one process per turn.
+pub const DEFAULT_CLAUDE_KEEP_ALIVE_SECS: u64 = 0;
+/// Default Ollama HTTP endpoint.
+pub const DEFAULT_OLLAMA_URL: &str = "http://127.0.0.1:11434";
+/// Default model for Ollama sessions.
+pub const DEFAULT_OLLAMA_MODEL: &str = "qwen2.5-coder";
 
 /// Main daemon configuration for local-only operation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +47,114 @@ pub struct Config {
     /// OTEL trace sampler argument (ratio for ratio-based samplers).
     #[serde(default = "default_otel_sampler_arg")]
     pub otel_sampler_arg: f64,
+    /// Seconds an interactive tool permission request waits for a client
+    /// before it is denied.
+    #[serde(default = "default_permission_timeout_secs")]
+    pub permission_timeout_secs: u64,
+    /// Maximum number of agent processes running at once; further requests
+    /// wait in the scheduler queue.
+    #[serde(default = "default_max_concurrent_agents")]
+    pub max_concurrent_agents: usize,
+    /// Maximum number of agent processes running at once in one repository.
+    #[serde(default = "default_max_concurrent_agents_per_repository")]
+    pub max_concurrent_agents_per_repository: usize,
+    /// Seconds a Claude process stays running after a turn, taking the
+    /// session's next message over stdin. 0 spawns a process per turn.
+    #[serde(default = "default_claude_keep_alive_secs")]
+    pub claude_keep_alive_secs: u64,
+    /// What startup does with agent and terminal processes left running by a
+    /// daemon that crashed.
+    #[serde(default)]
+    pub orphan_processes: OrphanProcessPolicy,
+    /// Ollama HTTP endpoint used by the `ollama` provider.
+    #[serde(default = "default_ollama_url")]
+    pub ollama_url: String,
+    /// Model used by the `ollama` provider when a turn names none.
+    #[serde(default = "default_ollama_model")]
+    pub ollama_model: String,
+    /// Shell that runs terminal commands, setup hooks and dependency checks,
+    /// e.g. `/bin/bash`. The user's login shell when unset; a repository's
+    /// `shell` takes precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    /// Extra coding agent CLIs, registered next to the built-in providers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agent_providers: Vec<AgentProviderConfig>,
+}
+
+/// How the daemon treats processes that outlived the daemon that spawned them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrphanProcessPolicy {
+    /// Stop them.
+    #[default]
+    Kill,
+    /// Leave them running and watch for their exit; their output is lost.
+    Adopt,
+}
+
+/// A coding agent CLI described in config instead of compiled into the daemon.
+///
+/// The CLI is run once per turn and must print one JSON event per stdout line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentProviderConfig {
+    /// Registry name, used as a session's `provider`.
+    pub name: String,
+    /// Name shown in error messages; defaults to `name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Executable to run, resolved through `PATH`.
+    pub executable: String,
+    /// Argument template. Entries may contain `{message}`, `{working_dir}`,
+    /// `{model}`, `{thinking_effort}`, `{permission_mode}` and
+    /// `{resume_session_id}` placeholders.
+    pub args: Vec<AgentProviderArg>,
+    /// Environment variables set for the process.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Where the daemon finds things in each JSON event.
+    #[serde(default)]
+    pub fields: AgentProviderFields,
+}
+
+/// One entry of an [`AgentProviderConfig`] argument template.
+///
+/// An entry is left out when one of its placeholders has no value, so a
+/// group such as `["--model", "{model}"]` is passed only when a model is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AgentProviderArg {
+    Single(String),
+    Group(Vec<String>),
+}
+
+/// JSON pointers (RFC 6901) into a provider's stdout events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentProviderFields {
+    /// The event type, e.g. `/type`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+    /// The provider's conversation id, passed back as `{resume_session_id}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Assistant text carried by the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The error message of a failed turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Event types that report a failed turn. When empty, any event with an
+    /// `error` field counts as one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error_types: Vec<String>,
+}
+
+impl AgentProviderFields {
+    fn pointers(&self) -> impl Iterator<Item = &String> {
+        [&self.event_type, &self.session_id, &self.text, &self.error]
+            .into_iter()
+            .flatten()
+    }
 }
 
 fn default_environment() -> String {
@@ -47,6 +169,30 @@ fn default_otel_sampler_arg() -> f64 {
     DEFAULT_OTEL_SAMPLER_ARG
 }
 
+fn default_permission_timeout_secs() -> u64 {
+    DEFAULT_PERMISSION_TIMEOUT_SECS
+}
+
+fn default_max_concurrent_agents() -> usize {
+    DEFAULT_MAX_CONCURRENT_AGENTS
+}
+
+fn default_max_concurrent_agents_per_repository() -> usize {
+    DEFAULT_MAX_CONCURRENT_AGENTS_PER_REPOSITORY
+}
+
+fn default_claude_keep_alive_secs() -> u64 {
+    DEFAULT_CLAUDE_KEEP_ALIVE_SECS
+}
+
+fn default_ollama_url() -> String {
+    DEFAULT_OLLAMA_URL.to_string()
+}
+
+fn default_ollama_model() -> String {
+    DEFAULT_OLLAMA_MODEL.to_string()
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -56,6 +202,15 @@ impl Default for Config {
             otel_headers: None,
             otel_sampler: DEFAULT_OTEL_SAMPLER.to_string(),
             otel_sampler_arg: DEFAULT_OTEL_SAMPLER_ARG,
+            permission_timeout_secs: DEFAULT_PERMISSION_TIMEOUT_SECS,
+            max_concurrent_agents: DEFAULT_MAX_CONCURRENT_AGENTS,
+            max_concurrent_agents_per_repository: DEFAULT_MAX_CONCURRENT_AGENTS_PER_REPOSITORY,
+            claude_keep_alive_secs: DEFAULT_CLAUDE_KEEP_ALIVE_SECS,
+            orphan_processes: OrphanProcessPolicy::default(),
+            ollama_url: DEFAULT_OLLAMA_URL.to_string(),
+            ollama_model: DEFAULT_OLLAMA_MODEL.to_string(),
+            shell: None,
+            agent_providers: Vec::new(),
         }
     }
 }
@@ -147,9 +302,96 @@ impl Config {
                 self.otel_sampler_arg = parsed.clamp(0.0, 1.0);
             }
         }
+
+        if let Ok(timeout) = std::env::var("UNBOUND_PERMISSION_TIMEOUT_SECS") {
+            if let Ok(parsed) = timeout.trim().parse::<u64>() {
+                self.permission_timeout_secs = parsed;
+            }
+        }
+
+        if let Ok(limit) = std::env::var("UNBOUND_MAX_CONCURRENT_AGENTS") {
+            if let Ok(parsed) = limit.trim().parse::<usize>() {
+                self.max_concurrent_agents = parsed;
+            }
+        }
+
+        if let Ok(limit) = std::env::var("UNBOUND_MAX_CONCURRENT_AGENTS_PER_REPOSITORY") {
+            if let Ok(parsed) = limit.trim().parse::<usize>() {
+                self.max_concurrent_agents_per_repository = parsed;
+            }
+        }
+
+        if let Ok(keep_alive) = std::env::var("UNBOUND_CLAUDE_KEEP_ALIVE_SECS") {
+            if let Ok(parsed) = keep_alive.trim().parse::<u64>() {
+                self.claude_keep_alive_secs = parsed;
+            }
+        }
+
+        if let Ok(policy) = std::env::var("UNBOUND_ORPHAN_PROCESSES") {
+            match policy.trim().to_ascii_lowercase().as_str() {
+                "kill" => self.orphan_processes = OrphanProcessPolicy::Kill,
+                "adopt" => self.orphan_processes = OrphanProcessPolicy::Adopt,
+                _ => {}
+            }
+        }
+
+        if let Ok(url) = std::env::var("UNBOUND_OLLAMA_URL") {
+            let trimmed = url.trim();
+            if !trimmed.is_empty() {
+                self.ollama_url = trimmed.trim_end_matches('/').to_string();
+            }
+        }
+
+        if let Ok(model) = std::env::var("UNBOUND_OLLAMA_MODEL") {
+            let trimmed = model.trim();
+            if !trimmed.is_empty() {
+                self.ollama_model = trimmed.to_string();
+            }
+        }
+
+        if let Ok(shell) = std::env::var("UNBOUND_SHELL") {
+            let trimmed = shell.trim();
+            if !trimmed.is_empty() {
+                self.shell = Some(trimmed.to_string());
+            }
+        }
     }
 
     fn validate(&self) -> CoreResult<()> {
+        if self.max_concurrent_agents == 0 || self.max_concurrent_agents_per_repository == 0 {
+            return Err(CoreError::Config(
+                "agent concurrency limits must be at least 1".to_string(),
+            ));
+        }
+
+        let mut names = HashSet::new();
+        for provider in &self.agent_providers {
+            let name = provider.name.as_str();
+            if name.is_empty() || name != name.trim().to_ascii_lowercase() {
+                return Err(CoreError::Config(format!(
+                    "agent provider name \"{name}\" must be non-empty and lowercase"
+                )));
+            }
+            if !names.insert(name) {
+                return Err(CoreError::Config(format!(
+                    "agent provider \"{name}\" is defined more than once"
+                )));
+            }
+            if provider.executable.trim().is_empty() {
+                return Err(CoreError::Config(format!(
+                    "agent provider \"{name}\" has no executable"
+                )));
+            }
+            if let Some(pointer) = provider
+                .fields
+                .pointers()
+                .find(|pointer| !pointer.starts_with('/'))
+            {
+                return Err(CoreError::Config(format!(
+                    "agent provider \"{name}\" field \"{pointer}\" must be a JSON pointer"
+                )));
+            }
+        }
         Ok(())
     }
 }
@@ -168,6 +410,23 @@ mod tests {
         assert!(config.otel_headers.is_none());
         assert_eq!(config.otel_sampler, DEFAULT_OTEL_SAMPLER);
         assert_eq!(config.otel_sampler_arg, DEFAULT_OTEL_SAMPLER_ARG);
+        assert_eq!(
+            config.permission_timeout_secs,
+            DEFAULT_PERMISSION_TIMEOUT_SECS
+        );
+        assert_eq!(config.max_concurrent_agents, DEFAULT_MAX_CONCURRENT_AGENTS);
+        assert_eq!(
+            config.max_concurrent_agents_per_repository,
+            DEFAULT_MAX_CONCURRENT_AGENTS_PER_REPOSITORY
+        );
+        assert_eq!(
+            config.claude_keep_alive_secs,
+            DEFAULT_CLAUDE_KEEP_ALIVE_SECS
+        );
+        assert_eq!(config.ollama_url, DEFAULT_OLLAMA_URL);
+        assert_eq!(config.ollama_model, DEFAULT_OLLAMA_MODEL);
+        assert_eq!(config.orphan_processes, OrphanProcessPolicy::Kill);
+        assert!(config.shell.is_none());
     }
 
     #[test]
@@ -208,6 +467,15 @@ mod tests {
             otel_headers: Some("authorization=token".to_string()),
             otel_sampler: "parentbased_traceidratio".to_string(),
             otel_sampler_arg: 0.1,
+            permission_timeout_secs: 30,
+            max_concurrent_agents: 2,
+            max_concurrent_agents_per_repository: 1,
+            claude_keep_alive_secs: 300,
+            orphan_processes: OrphanProcessPolicy::Adopt,
+            ollama_url: "http://gpu-box:11434".to_string(),
+            ollama_model: "llama3.1".to_string(),
+            shell: Some("/bin/bash".to_string()),
+            agent_providers: Vec::new(),
         };
 
         config.save(&paths).unwrap();
@@ -220,6 +488,14 @@ mod tests {
             Some("https://otel.example/v1/traces")
         );
         assert_eq!(loaded.otel_headers.as_deref(), Some("authorization=token"));
+        assert_eq!(loaded.permission_timeout_secs, 30);
+        assert_eq!(loaded.max_concurrent_agents, 2);
+        assert_eq!(loaded.max_concurrent_agents_per_repository, 1);
+        assert_eq!(loaded.claude_keep_alive_secs, 300);
+        assert_eq!(loaded.orphan_processes, OrphanProcessPolicy::Adopt);
+        assert_eq!(loaded.ollama_url, "http://gpu-box:11434");
+        assert_eq!(loaded.ollama_model, "llama3.1");
+        assert_eq!(loaded.shell.as_deref(), Some("/bin/bash"));
     }
 
     #[test]
@@ -230,4 +506,75 @@ mod tests {
 
         assert_eq!(config.otel_sampler_arg, 1.0);
     }
+
+    #[test]
+    fn test_validate_rejects_zero_agent_limits() {
+        let config = Config {
+            max_concurrent_agents: 0,
+            ..Config::default()
+        };
+        assert!(matches!(config.validate(), Err(CoreError::Config(_))));
+    }
+
+    #[test]
+    fn test_agent_providers_load_from_file() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        let config_json = r#"{
+            "log_level": "info",
+            "agent_providers": [{
+                "name": "aider",
+                "executable": "aider",
+                "args": ["--json", ["--model", "{model}"], "{message}"],
+                "env": { "AIDER_NO_COLOR": "1" },
+                "fields": { "session_id": "/session", "error": "/error" }
+            }]
+        }"#;
+        std::fs::write(&config_path, config_json).unwrap();
+
+        let config = Config::load_from_file(&config_path).unwrap();
+        let provider = &config.agent_providers[0];
+        assert_eq!(provider.name, "aider");
+        assert_eq!(
+            provider.args,
+            vec![
+                AgentProviderArg::Single("--json".to_string()),
+                AgentProviderArg::Group(vec!["--model".to_string(), "{model}".to_string()]),
+                AgentProviderArg::Single("{message}".to_string()),
+            ]
+        );
+        assert_eq!(provider.env["AIDER_NO_COLOR"], "1");
+        assert_eq!(provider.fields.session_id.as_deref(), Some("/session"));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_agent_providers() {
+        let provider = AgentProviderConfig {
+            name: "aider".to_string(),
+            label: None,
+            executable: "aider".to_string(),
+            args: vec![AgentProviderArg::Single("{message}".to_string())],
+            env: BTreeMap::new(),
+            fields: AgentProviderFields::default(),
+        };
+        let with = |providers: Vec<AgentProviderConfig>| Config {
+            agent_providers: providers,
+            ..Config::default()
+        };
+
+        assert!(with(vec![provider.clone()]).validate().is_ok());
+        assert!(with(vec![provider.clone(), provider.clone()])
+            .validate()
+            .is_err());
+        let mut renamed = provider.clone();
+        renamed.name = "Aider".to_string();
+        assert!(with(vec![renamed]).validate().is_err());
+        let mut no_executable = provider.clone();
+        no_executable.executable = " ".to_string();
+        assert!(with(vec![no_executable]).validate().is_err());
+        let mut bad_pointer = provider;
+        bad_pointer.fields.session_id = Some("session".to_string());
+        assert!(with(vec![bad_pointer]).validate().is_err());
+    }
 }
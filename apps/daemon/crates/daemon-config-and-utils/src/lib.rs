@@ -8,7 +8,9 @@ mod logging;
 mod paths;
 mod telemetry;
 
-pub use config::Config;
+pub use config::{
+    AgentProviderArg, AgentProviderConfig, AgentProviderFields, Config, OrphanProcessPolicy,
+};
 pub use conversation_crypto::{
     decrypt_conversation_message, encrypt_conversation_message,
     encrypt_conversation_message_with_nonce, ConversationCryptoError, EncryptedConversationPayload,
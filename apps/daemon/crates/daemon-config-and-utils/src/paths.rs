@@ -147,6 +147,16 @@ impl Paths {
         self.agent_runs_logs_dir().join(format!("{run_id}.ndjson"))
     }
 
+    /// Get the directory that stores Ollama conversation histories.
+    pub fn ollama_conversations_dir(&self) -> PathBuf {
+        self.base_dir.join("ollama-conversations")
+    }
+
+    /// Get the directory that stores per-session MCP server configs.
+    pub fn mcp_configs_dir(&self) -> PathBuf {
+        self.base_dir.join("mcp-configs")
+    }
+
     /// Ensure all required directories exist.
     pub fn ensure_dirs(&self) -> CoreResult<()> {
         std::fs::create_dir_all(&self.base_dir)?;
@@ -154,6 +164,8 @@ impl Paths {
         std::fs::create_dir_all(self.companies_dir())?;
         std::fs::create_dir_all(self.logs_dir())?;
         std::fs::create_dir_all(self.agent_runs_logs_dir())?;
+        std::fs::create_dir_all(self.ollama_conversations_dir())?;
+        std::fs::create_dir_all(self.mcp_configs_dir())?;
         Ok(())
     }
 }
@@ -192,6 +204,11 @@ mod tests {
             paths.agent_run_log_file("run-123"),
             base.join("logs/agent-runs/run-123.ndjson")
         );
+        assert_eq!(
+            paths.ollama_conversations_dir(),
+            base.join("ollama-conversations")
+        );
+        assert_eq!(paths.mcp_configs_dir(), base.join("mcp-configs"));
     }
 
     #[test]
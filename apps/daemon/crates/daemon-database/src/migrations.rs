@@ -4,12 +4,13 @@
 //! Migrations are run in order and tracked in the `migrations` table.
 
 use crate::DatabaseResult;
+use agent_session_sqlite_persist_core::MESSAGE_SEARCH_BODY_SQL;
 use rusqlite::{Connection, OptionalExtension};
 use std::collections::HashSet;
 use tracing::{debug, info};
 
 /// Current schema version.
-pub const CURRENT_VERSION: i32 = 27;
+pub const CURRENT_VERSION: i32 = 29;
 
 /// Run all pending migrations.
 pub fn run_migrations(conn: &Connection) -> DatabaseResult<()> {
@@ -118,6 +119,12 @@ pub fn run_migrations(conn: &Connection) -> DatabaseResult<()> {
     if current_version < 27 {
         migrate_v27_drop_board_tables(conn)?;
     }
+    if current_version < 28 {
+        migrate_v28_message_search_index(conn)?;
+    }
+    if current_version < 29 {
+        migrate_v29_session_fork_lineage(conn)?;
+    }
 
     info!("Migrations complete");
     Ok(())
@@ -3215,6 +3222,71 @@ fn migrate_v27_drop_board_tables(conn: &Connection) -> DatabaseResult<()> {
     Ok(())
 }
 
+/// V28: FTS5 index over message transcripts, backfilled from existing messages.
+///
+/// JSON messages contribute their string leaves minus identifier-like fields; the
+/// extraction must match the one Armin uses when it indexes appended messages.
+fn migrate_v28_message_search_index(conn: &Connection) -> DatabaseResult<()> {
+    info!("Applying migration v28: message_search_index");
+
+    if !table_exists(conn, "local_llm_conversation_messages_fts")? {
+        conn.execute_batch(
+            "
+            CREATE VIRTUAL TABLE local_llm_conversation_messages_fts USING fts5(
+                body,
+                message_id UNINDEXED,
+                session_id UNINDEXED,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            ",
+        )?;
+
+        // Databases that never reached the plaintext message schema have nothing to backfill.
+        if table_exists(conn, "local_llm_conversation_messages")?
+            && column_names(conn, "local_llm_conversation_messages")?.contains("content")
+        {
+            conn.execute_batch(&format!(
+                "
+                INSERT INTO local_llm_conversation_messages_fts (body, message_id, session_id)
+                SELECT {MESSAGE_SEARCH_BODY_SQL}, m.id, m.session_id
+                FROM local_llm_conversation_messages m;
+                "
+            ))?;
+        }
+    }
+
+    record_migration(conn, 28, "message_search_index")?;
+    Ok(())
+}
+
+/// V29: Record the parent session and fork point of forked sessions.
+fn migrate_v29_session_fork_lineage(conn: &Connection) -> DatabaseResult<()> {
+    info!("Applying migration v29: session_fork_lineage");
+
+    let sessions_table = session_table_name(conn)?;
+    if table_exists(conn, sessions_table)? {
+        add_column_if_missing(
+            conn,
+            sessions_table,
+            "parent_session_id",
+            "parent_session_id TEXT",
+        )?;
+        add_column_if_missing(
+            conn,
+            sessions_table,
+            "forked_from_sequence",
+            "forked_from_sequence INTEGER",
+        )?;
+        conn.execute_batch(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_sessions_parent_session_id
+                ON {sessions_table}(parent_session_id);"
+        ))?;
+    }
+
+    record_migration(conn, 29, "session_fork_lineage")?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3280,7 +3352,7 @@ mod tests {
             .query_row("SELECT MAX(version) FROM migrations", [], |row| row.get(0))
             .unwrap();
 
-        assert_eq!(version, 27);
+        assert_eq!(version, 29);
     }
 
     #[test]
@@ -4067,4 +4139,54 @@ mod tests {
 
         assert_eq!(issue_id.as_deref(), Some("issue-1"));
     }
+
+    #[test]
+    fn test_v28_backfills_message_search_index() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            CREATE TABLE local_llm_conversation_messages (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                content TEXT NOT NULL
+            );
+            INSERT INTO local_llm_conversation_messages (id, session_id, content) VALUES
+                ('message-1', 'session-1', 'plain transcript text'),
+                ('message-2', 'session-1', '{"type":"assistant","id":"toolu_hidden","text":"json transcript"}');
+            "#,
+        )
+        .unwrap();
+
+        migrate_v28_message_search_index(&conn).unwrap();
+
+        let search = |term: &str| -> Vec<String> {
+            conn.prepare(
+                "SELECT message_id FROM local_llm_conversation_messages_fts
+                 WHERE local_llm_conversation_messages_fts MATCH ?1 ORDER BY message_id",
+            )
+            .unwrap()
+            .query_map([term], |row| row.get(0))
+            .unwrap()
+            .filter_map(|row| row.ok())
+            .collect()
+        };
+        assert_eq!(search("transcript"), vec!["message-1", "message-2"]);
+        assert_eq!(search("json"), vec!["message-2"]);
+        assert!(search("toolu_hidden").is_empty());
+    }
+
+    #[test]
+    fn test_v29_adds_session_fork_lineage_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+
+        let columns = column_names(&conn, "local_llm_conversations").unwrap();
+        assert!(columns.contains("parent_session_id"));
+        assert!(columns.contains("forked_from_sequence"));
+    }
 }
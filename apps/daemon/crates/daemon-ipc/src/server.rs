@@ -11,6 +11,14 @@
 //! 2. Receive success response
 //! 3. Block reading events (NDJSON lines)
 //! 4. Send `session.unsubscribe` or close connection to stop
+//!
+//! ## Resuming
+//!
+//! A client that passes `after_sequence` first receives every persisted
+//! message after that sequence (via the registered initial state handler),
+//! then live events, without gaps or duplicates. If the client falls behind
+//! the live buffer, the stream ends instead of skipping events; the client
+//! resubscribes with the last message sequence it saw.
 
 use crate::{error_codes, Event, IpcError, IpcResult, Method, Request, Response, TraceContext};
 use opentelemetry::global;
@@ -33,8 +41,14 @@ pub type HandlerFn =
     Box<dyn Fn(Request) -> Pin<Box<dyn Future<Output = Response> + Send>> + Send + Sync>;
 
 /// Callback type for getting initial subscription state.
+///
+/// Receives the session ID and the client's `after_sequence`, and returns the
+/// events to send before streaming plus the last message sequence they cover.
+/// Live message events at or below that sequence are not sent again.
 pub type InitialStateFn = Box<
-    dyn Fn(String) -> Pin<Box<dyn Future<Output = Option<(Vec<Event>, i64)>> + Send>> + Send + Sync,
+    dyn Fn(String, Option<i64>) -> Pin<Box<dyn Future<Output = Option<(Vec<Event>, i64)>> + Send>>
+        + Send
+        + Sync,
 >;
 
 /// Manages active subscriptions for streaming events.
@@ -219,10 +233,12 @@ impl IpcServer {
     /// Register the callback for getting initial subscription state.
     pub async fn register_initial_state_handler<F, Fut>(&self, handler: F)
     where
-        F: Fn(String) -> Fut + Send + Sync + 'static,
+        F: Fn(String, Option<i64>) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Option<(Vec<Event>, i64)>> + Send + 'static,
     {
-        let boxed: InitialStateFn = Box::new(move |session_id| Box::pin(handler(session_id)));
+        let boxed: InitialStateFn = Box::new(move |session_id, after_sequence| {
+            Box::pin(handler(session_id, after_sequence))
+        });
         *self.initial_state_fn.write().await = Some(boxed);
     }
 
@@ -375,6 +391,23 @@ async fn handle_connection(
                     }
                 };
 
+                let after_sequence = match parse_after_sequence(request.params.as_ref()) {
+                    Ok(after_sequence) => after_sequence,
+                    Err(message) => {
+                        let response =
+                            Response::error(&request_id, error_codes::INVALID_PARAMS, message);
+                        let response_json = response.to_json()?;
+                        writer.write_all(response_json.as_bytes()).await?;
+                        writer.write_all(b"\n").await?;
+                        writer.flush().await?;
+                        return IpcResult::Ok(());
+                    }
+                };
+
+                // Subscribe before replaying so events committed meanwhile are buffered
+                let event_rx = subscriptions.subscribe(&session_id).await;
+                let mut replayed_through = None;
+
                 async {
                     // Send success response first
                     let response = Response::success(
@@ -382,6 +415,7 @@ async fn handle_connection(
                         serde_json::json!({
                             "subscribed": true,
                             "session_id": session_id,
+                            "after_sequence": after_sequence,
                         }),
                     );
                     let response_json = response.to_json()?;
@@ -400,7 +434,9 @@ async fn handle_connection(
 
                     // Send initial state if handler is registered
                     if let Some(handler) = initial_state_fn.read().await.as_ref() {
-                        if let Some((events, _last_seq)) = handler(session_id.clone()).await {
+                        if let Some((events, last_seq)) =
+                            handler(session_id.clone(), after_sequence).await
+                        {
                             for event in events {
                                 if let Ok(event_json) = event.to_json() {
                                     let _ = writer.write_all(event_json.as_bytes()).await;
@@ -408,6 +444,7 @@ async fn handle_connection(
                                 }
                             }
                             let _ = writer.flush().await;
+                            replayed_through = Some(last_seq);
                         }
                     }
                     IpcResult::Ok(())
@@ -422,7 +459,15 @@ async fn handle_connection(
 
                 // Enter streaming mode - this consumes the connection
                 info!(session_id = %session_id, "Client subscribed, entering streaming mode");
-                handle_streaming_subscription(reader, writer, &subscriptions, &session_id).await?;
+                handle_streaming_subscription(
+                    reader,
+                    writer,
+                    event_rx,
+                    &subscriptions,
+                    &session_id,
+                    replayed_through,
+                )
+                .await?;
                 Ok(())
             }
             .instrument(request_span.clone())
@@ -508,6 +553,25 @@ async fn handle_connection(
     Ok(())
 }
 
+/// Reads the optional `after_sequence` subscribe parameter.
+fn parse_after_sequence(params: Option<&serde_json::Value>) -> Result<Option<i64>, &'static str> {
+    match params.and_then(|p| p.get("after_sequence")) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => value
+            .as_i64()
+            .filter(|sequence| *sequence >= 0)
+            .map(Some)
+            .ok_or("after_sequence must be a non-negative integer"),
+    }
+}
+
+/// Whether a live event was already delivered by the initial state replay.
+fn is_replayed(event: &Event, replayed_through: Option<i64>) -> bool {
+    replayed_through.is_some_and(|last_seq| {
+        event.event_type.carries_message_sequence() && event.sequence <= last_seq
+    })
+}
+
 /// Handle streaming subscription mode.
 ///
 /// The connection stays open and events are pushed to the client.
@@ -515,11 +579,11 @@ async fn handle_connection(
 async fn handle_streaming_subscription(
     mut reader: BufReader<tokio::net::unix::OwnedReadHalf>,
     mut writer: OwnedWriteHalf,
+    mut event_rx: broadcast::Receiver<Event>,
     subscriptions: &SubscriptionManager,
     session_id: &str,
+    replayed_through: Option<i64>,
 ) -> IpcResult<()> {
-    // Subscribe to events for this session
-    let mut event_rx = subscriptions.subscribe(session_id).await;
     let mut line = String::new();
 
     loop {
@@ -527,6 +591,7 @@ async fn handle_streaming_subscription(
             // Forward events to client
             event_result = event_rx.recv() => {
                 match event_result {
+                    Ok(event) if is_replayed(&event, replayed_through) => {}
                     Ok(event) => {
                         match event.to_json() {
                             Ok(event_json) => {
@@ -551,8 +616,10 @@ async fn handle_streaming_subscription(
                         break;
                     }
                     Err(broadcast::error::RecvError::Lagged(n)) => {
-                        warn!(skipped = n, "Client lagged, skipped events");
-                        // Continue receiving - client will catch up
+                        // End the stream rather than leave a gap; the client
+                        // resubscribes with the last sequence it received.
+                        warn!(skipped = n, "Client lagged, ending subscription");
+                        break;
                     }
                 }
             }
@@ -679,6 +746,18 @@ impl IpcClient {
     /// Returns a `StreamingSubscription` that yields events as they arrive.
     /// The connection stays open until unsubscribed or dropped.
     pub async fn subscribe(&self, session_id: &str) -> IpcResult<StreamingSubscription> {
+        self.subscribe_after(session_id, None).await
+    }
+
+    /// Subscribe to a session's events, first replaying persisted messages
+    /// with a sequence greater than `after_sequence`.
+    ///
+    /// Passing `None` behaves like [`IpcClient::subscribe`] and only streams new events.
+    pub async fn subscribe_after(
+        &self,
+        session_id: &str,
+        after_sequence: Option<i64>,
+    ) -> IpcResult<StreamingSubscription> {
         let stream = UnixStream::connect(&self.socket_path)
             .await
             .map_err(|e| IpcError::Socket(format!("Failed to connect: {}", e)))?;
@@ -687,10 +766,11 @@ impl IpcClient {
         let mut reader = BufReader::new(reader);
 
         // Send subscribe request
-        let request = Request::with_params(
-            Method::SessionSubscribe,
-            serde_json::json!({ "session_id": session_id }),
-        );
+        let mut params = serde_json::json!({ "session_id": session_id });
+        if let Some(after_sequence) = after_sequence {
+            params["after_sequence"] = serde_json::json!(after_sequence);
+        }
+        let request = Request::with_params(Method::SessionSubscribe, params);
         let request_json = request.to_json()?;
         writer.write_all(request_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
@@ -781,6 +861,7 @@ impl StreamingSubscription {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::EventType;
 
     #[tokio::test]
     async fn test_ipc_client_not_running() {
@@ -824,6 +905,88 @@ mod tests {
         assert!(true);
     }
 
+    #[test]
+    fn test_parse_after_sequence() {
+        assert_eq!(parse_after_sequence(None), Ok(None));
+        let params = serde_json::json!({ "session_id": "s1" });
+        assert_eq!(parse_after_sequence(Some(&params)), Ok(None));
+        let params = serde_json::json!({ "after_sequence": null });
+        assert_eq!(parse_after_sequence(Some(&params)), Ok(None));
+        let params = serde_json::json!({ "after_sequence": 42 });
+        assert_eq!(parse_after_sequence(Some(&params)), Ok(Some(42)));
+        let params = serde_json::json!({ "after_sequence": -1 });
+        assert!(parse_after_sequence(Some(&params)).is_err());
+        let params = serde_json::json!({ "after_sequence": "7" });
+        assert!(parse_after_sequence(Some(&params)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_after_replays_then_streams_without_duplicates() {
+        let socket_path =
+            std::env::temp_dir().join(format!("unbound-ipc-resume-{}.sock", uuid::Uuid::new_v4()));
+        let server = Arc::new(IpcServer::new(&socket_path.to_string_lossy()));
+        server
+            .register_initial_state_handler(|session_id, after_sequence| async move {
+                let after_sequence = after_sequence?;
+                let events = (after_sequence + 1..=2)
+                    .map(|seq| {
+                        Event::new(EventType::Message, &session_id, serde_json::json!({}), seq)
+                    })
+                    .collect();
+                Some((events, 2))
+            })
+            .await;
+
+        let running = server.clone();
+        let server_task = tokio::spawn(async move { running.run().await });
+        for _ in 0..100 {
+            if socket_path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let client = IpcClient::new(&socket_path.to_string_lossy());
+        let mut subscription = client.subscribe_after("s1", Some(0)).await.unwrap();
+
+        // Message 2 committed during replay reaches the live channel too
+        for (event_type, seq) in [
+            (EventType::ClaudeEvent, 2),
+            (EventType::StatusChange, 2),
+            (EventType::ClaudeEvent, 3),
+        ] {
+            server
+                .subscriptions()
+                .broadcast(
+                    "s1",
+                    Event::new(event_type, "s1", serde_json::json!({}), seq),
+                )
+                .await;
+        }
+
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            let event =
+                tokio::time::timeout(std::time::Duration::from_secs(5), subscription.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+            received.push((event.event_type, event.sequence));
+        }
+        assert_eq!(
+            received,
+            vec![
+                (EventType::Message, 1),
+                (EventType::Message, 2),
+                (EventType::StatusChange, 2),
+                (EventType::ClaudeEvent, 3),
+            ]
+        );
+
+        server.shutdown();
+        let _ = server_task.await;
+    }
+
     #[test]
     fn test_ipc_client_creation() {
         let client = IpcClient::new("/path/to/socket.sock");
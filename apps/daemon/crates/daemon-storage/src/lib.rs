@@ -184,6 +184,46 @@ mod tests {
         assert_eq!(retrieved, private_key);
     }
 
+    #[test]
+    fn test_secrets_manager_repository_env_secrets() {
+        let storage = Box::new(MemoryStorage::new());
+        let manager = SecretsManager::new(storage);
+
+        manager
+            .set_repository_env_secret("repo-1", "staging", "API_TOKEN", "tok-123")
+            .unwrap();
+        assert_eq!(
+            manager
+                .get_repository_env_secret("repo-1", "staging", "API_TOKEN")
+                .unwrap(),
+            Some("tok-123".to_string())
+        );
+
+        // Namespaced by repository and profile
+        assert_eq!(
+            manager
+                .get_repository_env_secret("repo-2", "staging", "API_TOKEN")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            manager
+                .get_repository_env_secret("repo-1", "prod", "API_TOKEN")
+                .unwrap(),
+            None
+        );
+
+        assert!(manager
+            .delete_repository_env_secret("repo-1", "staging", "API_TOKEN")
+            .unwrap());
+        assert_eq!(
+            manager
+                .get_repository_env_secret("repo-1", "staging", "API_TOKEN")
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_storage_keys_constants() {
         // Verify all storage keys are defined and non-empty
@@ -157,6 +157,42 @@ impl SecretsManager {
         self.storage.delete(&key)
     }
 
+    /// Get a secret value of a repository environment profile
+    pub fn get_repository_env_secret(
+        &self,
+        repository_id: &str,
+        profile: &str,
+        name: &str,
+    ) -> StorageResult<Option<String>> {
+        self.storage
+            .get(&repository_env_secret_key(repository_id, profile, name))
+    }
+
+    /// Store a secret value of a repository environment profile
+    pub fn set_repository_env_secret(
+        &self,
+        repository_id: &str,
+        profile: &str,
+        name: &str,
+        value: &str,
+    ) -> StorageResult<()> {
+        self.storage.set(
+            &repository_env_secret_key(repository_id, profile, name),
+            value,
+        )
+    }
+
+    /// Delete a secret value of a repository environment profile
+    pub fn delete_repository_env_secret(
+        &self,
+        repository_id: &str,
+        profile: &str,
+        name: &str,
+    ) -> StorageResult<bool> {
+        self.storage
+            .delete(&repository_env_secret_key(repository_id, profile, name))
+    }
+
     /// Generate a new session secret
     /// Format: sess_<base64url(32 bytes)>
     pub fn generate_session_secret() -> String {
@@ -199,3 +235,9 @@ impl SecretsManager {
         Ok(())
     }
 }
+
+/// Key of a repository environment secret:
+/// com.unbound.repository.<repositoryId>.env.<profile>.<name>
+fn repository_env_secret_key(repository_id: &str, profile: &str, name: &str) -> String {
+    format!("com.unbound.repository.{repository_id}.env.{profile}.{name}")
+}
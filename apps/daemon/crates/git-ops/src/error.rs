@@ -41,6 +41,42 @@ pub enum GitOpsError {
     #[error("Failed to create branch: {0}")]
     BranchCreate(String),
 
+    /// A branch with this name already exists.
+    #[error("Branch already exists: {0}")]
+    BranchExists(String),
+
+    /// Not a valid branch name.
+    #[error("Invalid branch name: {0}")]
+    InvalidBranchName(String),
+
+    /// Branch is checked out, here or in another worktree.
+    #[error("Branch '{0}' is checked out at {1}")]
+    BranchCheckedOut(String, String),
+
+    /// Branch has commits that are not merged.
+    #[error("Branch is not fully merged: {0}")]
+    BranchNotMerged(String),
+
+    /// Failed to delete branch.
+    #[error("Failed to delete branch: {0}")]
+    BranchDelete(String),
+
+    /// Failed to rename branch.
+    #[error("Failed to rename branch: {0}")]
+    BranchRename(String),
+
+    /// Failed to set or clear a branch's upstream.
+    #[error("Failed to set upstream: {0}")]
+    UpstreamSet(String),
+
+    /// Checkout would overwrite local changes to these files.
+    #[error("Checkout would overwrite local changes to: {}", .0.join(", "))]
+    CheckoutConflict(Vec<String>),
+
+    /// Failed to check out.
+    #[error("Failed to check out: {0}")]
+    Checkout(String),
+
     /// Failed to create revision walker.
     #[error("Failed to create revision walker: {0}")]
     RevwalkCreate(String),
@@ -175,6 +211,42 @@ mod tests {
                 GitOpsError::BranchCreate("exists".into()),
                 "Failed to create branch: exists",
             ),
+            (
+                GitOpsError::BranchExists("main".into()),
+                "Branch already exists: main",
+            ),
+            (
+                GitOpsError::InvalidBranchName("a..b".into()),
+                "Invalid branch name: a..b",
+            ),
+            (
+                GitOpsError::BranchCheckedOut("main".into(), "/repo".into()),
+                "Branch 'main' is checked out at /repo",
+            ),
+            (
+                GitOpsError::BranchNotMerged("feature".into()),
+                "Branch is not fully merged: feature",
+            ),
+            (
+                GitOpsError::BranchDelete("locked".into()),
+                "Failed to delete branch: locked",
+            ),
+            (
+                GitOpsError::BranchRename("locked".into()),
+                "Failed to rename branch: locked",
+            ),
+            (
+                GitOpsError::UpstreamSet("no remote".into()),
+                "Failed to set upstream: no remote",
+            ),
+            (
+                GitOpsError::CheckoutConflict(vec!["a.rs".into(), "b.rs".into()]),
+                "Checkout would overwrite local changes to: a.rs, b.rs",
+            ),
+            (
+                GitOpsError::Checkout("corrupt".into()),
+                "Failed to check out: corrupt",
+            ),
             (
                 GitOpsError::RevwalkCreate("memory".into()),
                 "Failed to create revision walker: memory",
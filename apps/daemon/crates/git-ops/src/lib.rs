@@ -18,6 +18,11 @@
 //! | [`get_file_diff`] | Generate unified diff for a file |
 //! | [`get_log`] | Retrieve commit history with pagination |
 //! | [`get_branches`] | List all local and remote branches |
+//! | [`checkout_branch`] | Switch to a branch, refusing to overwrite local changes |
+//! | [`create_branch`] | Create a branch from `HEAD` or a start point |
+//! | [`delete_branch`] | Delete a branch, refusing unmerged ones |
+//! | [`rename_branch`] | Rename a branch |
+//! | [`set_upstream`] | Set or clear a branch's upstream |
 //! | [`stage_files`] | Add files to the index |
 //! | [`unstage_files`] | Remove files from the index |
 //! | [`discard_changes`] | Reset working tree changes |
@@ -122,9 +127,9 @@ mod types;
 
 pub use error::GitOpsError;
 pub use operations::{
-    commit, create_worktree, create_worktree_with_options, discard_changes, get_branches,
-    get_file_diff, get_log, get_status, list_worktrees, push, remove_worktree, stage_files,
-    unstage_files,
+    checkout_branch, commit, create_branch, create_worktree, create_worktree_with_options,
+    delete_branch, discard_changes, get_branches, get_file_diff, get_log, get_status,
+    list_worktrees, push, remove_worktree, rename_branch, set_upstream, stage_files, unstage_files,
 };
 pub use types::{
     GitBranch, GitBranchesResult, GitCommit, GitCommitResult, GitDiffResult, GitFileStatus,
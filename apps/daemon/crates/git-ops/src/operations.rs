@@ -3,7 +3,12 @@
 //! All operations are pure functions that take a repository path and return
 //! results. They do not maintain any state between calls.
 
-use git2::{BranchType, DiffOptions, Repository, Sort, StatusOptions};
+use git2::build::CheckoutBuilder;
+use git2::{
+    Branch, BranchType, CheckoutNotificationType, DiffOptions, ErrorCode, Repository, Sort,
+    StatusOptions,
+};
+use std::cell::RefCell;
 use std::path::{Path, PathBuf};
 
 use crate::error::GitOpsError;
@@ -489,48 +494,7 @@ pub fn get_branches(repo_path: &Path) -> Result<GitBranchesResult, String> {
 
         let is_current = current.as_deref() == Some(&name);
         let is_remote = branch_type == BranchType::Remote;
-
-        // Get HEAD commit OID
-        let head_oid = branch
-            .get()
-            .target()
-            .map(|oid| oid.to_string())
-            .unwrap_or_default();
-
-        // Get upstream info for local branches
-        let (upstream, ahead, behind) = if !is_remote {
-            match branch.upstream() {
-                Ok(upstream_branch) => {
-                    let upstream_name = upstream_branch.name().ok().flatten().map(String::from);
-
-                    // Calculate ahead/behind
-                    let (ahead, behind) = if let (Some(local_oid), Some(upstream_oid)) =
-                        (branch.get().target(), upstream_branch.get().target())
-                    {
-                        repo.graph_ahead_behind(local_oid, upstream_oid)
-                            .map(|(a, b)| (a as u32, b as u32))
-                            .unwrap_or((0, 0))
-                    } else {
-                        (0, 0)
-                    };
-
-                    (upstream_name, ahead, behind)
-                }
-                Err(_) => (None, 0, 0),
-            }
-        } else {
-            (None, 0, 0)
-        };
-
-        let git_branch = GitBranch {
-            name,
-            is_current,
-            is_remote,
-            upstream,
-            ahead,
-            behind,
-            head_oid,
-        };
+        let git_branch = branch_info(&repo, &branch, name, is_current, is_remote);
 
         if is_remote {
             remote_branches.push(git_branch);
@@ -554,6 +518,460 @@ pub fn get_branches(repo_path: &Path) -> Result<GitBranchesResult, String> {
     })
 }
 
+/// Describe `branch`, with its upstream and how far it has diverged from it
+/// for a local branch.
+fn branch_info(
+    repo: &Repository,
+    branch: &Branch<'_>,
+    name: String,
+    is_current: bool,
+    is_remote: bool,
+) -> GitBranch {
+    // Get HEAD commit OID
+    let head_oid = branch
+        .get()
+        .target()
+        .map(|oid| oid.to_string())
+        .unwrap_or_default();
+
+    // Get upstream info for local branches
+    let (upstream, ahead, behind) = if !is_remote {
+        match branch.upstream() {
+            Ok(upstream_branch) => {
+                let upstream_name = upstream_branch.name().ok().flatten().map(String::from);
+
+                // Calculate ahead/behind
+                let (ahead, behind) = if let (Some(local_oid), Some(upstream_oid)) =
+                    (branch.get().target(), upstream_branch.get().target())
+                {
+                    repo.graph_ahead_behind(local_oid, upstream_oid)
+                        .map(|(a, b)| (a as u32, b as u32))
+                        .unwrap_or((0, 0))
+                } else {
+                    (0, 0)
+                };
+
+                (upstream_name, ahead, behind)
+            }
+            Err(_) => (None, 0, 0),
+        }
+    } else {
+        (None, 0, 0)
+    };
+
+    GitBranch {
+        name,
+        is_current,
+        is_remote,
+        upstream,
+        ahead,
+        behind,
+        head_oid,
+    }
+}
+
+/// Describe the local branch `name` after an operation on it.
+fn local_branch_info(repo: &Repository, name: &str) -> Result<GitBranch, GitOpsError> {
+    let branch = find_local_branch(repo, name)?;
+    let is_current = branch.is_head();
+    Ok(branch_info(
+        repo,
+        &branch,
+        name.to_string(),
+        is_current,
+        false,
+    ))
+}
+
+fn find_local_branch<'repo>(
+    repo: &'repo Repository,
+    name: &str,
+) -> Result<Branch<'repo>, GitOpsError> {
+    repo.find_branch(name, BranchType::Local).map_err(|e| {
+        if e.code() == ErrorCode::NotFound {
+            GitOpsError::BranchNotFound(name.to_string())
+        } else {
+            GitOpsError::BranchList(e.message().to_string())
+        }
+    })
+}
+
+fn validate_branch_name(name: &str) -> Result<(), GitOpsError> {
+    if Branch::name_is_valid(name).unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(GitOpsError::InvalidBranchName(name.to_string()))
+    }
+}
+
+/// Path of another checkout of this repository, the main one or a linked
+/// worktree, whose HEAD is the branch `refname`.
+fn checked_out_elsewhere(repo: &Repository, refname: &str) -> Option<PathBuf> {
+    // A linked worktree's git dir names the main one in its `commondir` file
+    let common_dir = if repo.is_worktree() {
+        let relative = std::fs::read_to_string(repo.path().join("commondir")).ok()?;
+        repo.path().join(relative.trim())
+    } else {
+        repo.path().to_path_buf()
+    };
+    let main = Repository::open(common_dir).ok()?;
+    let mut checkouts = vec![main];
+    if let Ok(names) = checkouts[0].worktrees() {
+        for name in names.iter().flatten() {
+            let linked = checkouts[0]
+                .find_worktree(name)
+                .and_then(|worktree| Repository::open_from_worktree(&worktree));
+            if let Ok(linked) = linked {
+                checkouts.push(linked);
+            }
+        }
+    }
+
+    let own_git_dir = repo.path().canonicalize().ok();
+    checkouts.into_iter().find_map(|checkout| {
+        if checkout.path().canonicalize().ok() == own_git_dir {
+            return None;
+        }
+        let head = checkout.head().ok()?;
+        if head.name() != Some(refname) {
+            return None;
+        }
+        Some(
+            checkout
+                .workdir()
+                .unwrap_or_else(|| checkout.path())
+                .to_path_buf(),
+        )
+    })
+}
+
+/// Check out the local branch `name`.
+///
+/// When no local branch has that name but exactly one remote has a branch
+/// of it, e.g. `origin/<name>`, a local branch tracking it is created first.
+/// Local changes are carried over as long as the branch does not touch the
+/// same files.
+///
+/// # Arguments
+///
+/// * `repo_path` - Path to the repository or worktree
+/// * `name` - Branch name (without `refs/heads/`)
+/// * `force` - Discard local changes that conflict with the branch
+///
+/// # Returns
+///
+/// The checked out [`GitBranch`].
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository cannot be opened
+/// - The branch does not exist locally or on a single remote
+/// - The branch is checked out in another worktree
+/// - Local changes conflict with the branch and `force` is false
+///   ([`GitOpsError::CheckoutConflict`] lists the files)
+pub fn checkout_branch(
+    repo_path: &Path,
+    name: &str,
+    force: bool,
+) -> Result<GitBranch, GitOpsError> {
+    let repo = Repository::open(repo_path)?;
+
+    let mut created_from_remote = false;
+    let branch = match repo.find_branch(name, BranchType::Local) {
+        Ok(branch) => branch,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            let remote = single_remote_branch(&repo, name)?;
+            let commit = remote
+                .get()
+                .peel_to_commit()
+                .map_err(|e| GitOpsError::BranchCreate(e.message().to_string()))?;
+            let upstream = remote
+                .name()
+                .ok()
+                .flatten()
+                .map(String::from)
+                .ok_or_else(|| GitOpsError::BranchNotFound(name.to_string()))?;
+            let mut branch = repo
+                .branch(name, &commit, false)
+                .map_err(|e| GitOpsError::BranchCreate(e.message().to_string()))?;
+            branch
+                .set_upstream(Some(&upstream))
+                .map_err(|e| GitOpsError::UpstreamSet(e.message().to_string()))?;
+            created_from_remote = true;
+            branch
+        }
+        Err(e) => return Err(GitOpsError::BranchList(e.message().to_string())),
+    };
+
+    let refname = branch
+        .get()
+        .name()
+        .map(String::from)
+        .ok_or_else(|| GitOpsError::InvalidBranchName(name.to_string()))?;
+    if let Some(path) = checked_out_elsewhere(&repo, &refname) {
+        return Err(GitOpsError::BranchCheckedOut(
+            name.to_string(),
+            path.display().to_string(),
+        ));
+    }
+
+    let result = switch_to(&repo, &branch, &refname, force);
+    if result.is_err() && created_from_remote {
+        // Do not leave behind a branch the caller never got to use
+        let _ = repo
+            .find_branch(name, BranchType::Local)
+            .and_then(|mut branch| branch.delete());
+    }
+    result?;
+
+    local_branch_info(&repo, name)
+}
+
+/// The remote-tracking branch `<remote>/<name>`, if exactly one remote has it.
+fn single_remote_branch<'repo>(
+    repo: &'repo Repository,
+    name: &str,
+) -> Result<Branch<'repo>, GitOpsError> {
+    let branches = repo
+        .branches(Some(BranchType::Remote))
+        .map_err(|e| GitOpsError::BranchList(e.message().to_string()))?;
+    let mut matches = branches.filter_map(Result::ok).filter(|(branch, _)| {
+        branch
+            .name()
+            .ok()
+            .flatten()
+            .and_then(|full| full.split_once('/'))
+            .is_some_and(|(_, branch_name)| branch_name == name)
+    });
+    match (matches.next(), matches.next()) {
+        (Some((branch, _)), None) => Ok(branch),
+        _ => Err(GitOpsError::BranchNotFound(name.to_string())),
+    }
+}
+
+/// Update the working tree and index to `branch`, then point HEAD at it.
+fn switch_to(
+    repo: &Repository,
+    branch: &Branch<'_>,
+    refname: &str,
+    force: bool,
+) -> Result<(), GitOpsError> {
+    let tree = branch
+        .get()
+        .peel_to_tree()
+        .map_err(|e| GitOpsError::Checkout(e.message().to_string()))?;
+
+    let conflicts = RefCell::new(Vec::new());
+    let mut checkout = CheckoutBuilder::new();
+    if force {
+        checkout.force();
+    } else {
+        checkout
+            .safe()
+            .notify_on(CheckoutNotificationType::CONFLICT)
+            .notify(|_, path, _, _, _| {
+                if let Some(path) = path {
+                    conflicts
+                        .borrow_mut()
+                        .push(path.to_string_lossy().into_owned());
+                }
+                true
+            });
+    }
+
+    // Conflicts are found before anything is written, so a refused
+    // checkout leaves the working tree as it was
+    if let Err(e) = repo.checkout_tree(tree.as_object(), Some(&mut checkout)) {
+        drop(checkout);
+        let conflicts = conflicts.into_inner();
+        if !conflicts.is_empty() {
+            return Err(GitOpsError::CheckoutConflict(conflicts));
+        }
+        return Err(GitOpsError::Checkout(e.message().to_string()));
+    }
+
+    repo.set_head(refname)
+        .map_err(|e| GitOpsError::Checkout(e.message().to_string()))
+}
+
+/// Create the local branch `name`.
+///
+/// # Arguments
+///
+/// * `repo_path` - Path to the repository or worktree
+/// * `name` - Name for the new branch
+/// * `start_point` - Branch, tag or commit to start from (defaults to `HEAD`)
+/// * `upstream` - Optional remote-tracking branch to track, e.g. `origin/main`
+///
+/// # Returns
+///
+/// The new [`GitBranch`]. It is not checked out.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository cannot be opened
+/// - The name is not a valid branch name, or a branch already has it
+/// - The start point or upstream cannot be found
+pub fn create_branch(
+    repo_path: &Path,
+    name: &str,
+    start_point: Option<&str>,
+    upstream: Option<&str>,
+) -> Result<GitBranch, GitOpsError> {
+    let repo = Repository::open(repo_path)?;
+    validate_branch_name(name)?;
+    if repo.find_branch(name, BranchType::Local).is_ok() {
+        return Err(GitOpsError::BranchExists(name.to_string()));
+    }
+
+    let commit = match start_point {
+        Some(start_point) => {
+            resolve_base_commit(&repo, start_point).map_err(GitOpsError::BranchCreate)?
+        }
+        None => repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map_err(|e| GitOpsError::HeadAccess(e.message().to_string()))?,
+    };
+
+    let mut branch = repo
+        .branch(name, &commit, false)
+        .map_err(|e| GitOpsError::BranchCreate(e.message().to_string()))?;
+    if let Some(upstream) = upstream {
+        if let Err(e) = branch.set_upstream(Some(upstream)) {
+            let _ = branch.delete();
+            return Err(GitOpsError::UpstreamSet(e.message().to_string()));
+        }
+    }
+
+    local_branch_info(&repo, name)
+}
+
+/// Delete the local branch `name`.
+///
+/// Without `force`, the branch must be merged: into its upstream if it has
+/// one, otherwise into `HEAD`.
+///
+/// # Returns
+///
+/// The [`GitBranch`] as it was before deletion, so its `head_oid` can be
+/// used to restore it.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository cannot be opened
+/// - The branch does not exist
+/// - The branch is checked out here or in another worktree
+/// - The branch is not merged and `force` is false
+pub fn delete_branch(repo_path: &Path, name: &str, force: bool) -> Result<GitBranch, GitOpsError> {
+    let repo = Repository::open(repo_path)?;
+    let mut branch = find_local_branch(&repo, name)?;
+
+    let refname = branch.get().name().unwrap_or_default().to_string();
+    if branch.is_head() {
+        let path = repo.workdir().unwrap_or_else(|| repo.path());
+        return Err(GitOpsError::BranchCheckedOut(
+            name.to_string(),
+            path.display().to_string(),
+        ));
+    }
+    if let Some(path) = checked_out_elsewhere(&repo, &refname) {
+        return Err(GitOpsError::BranchCheckedOut(
+            name.to_string(),
+            path.display().to_string(),
+        ));
+    }
+
+    if !force && !is_merged(&repo, &branch)? {
+        return Err(GitOpsError::BranchNotMerged(name.to_string()));
+    }
+
+    let deleted = branch_info(&repo, &branch, name.to_string(), false, false);
+    branch
+        .delete()
+        .map_err(|e| GitOpsError::BranchDelete(e.message().to_string()))?;
+    Ok(deleted)
+}
+
+/// Whether every commit on `branch` is reachable from its upstream, or from
+/// `HEAD` when it has none.
+fn is_merged(repo: &Repository, branch: &Branch<'_>) -> Result<bool, GitOpsError> {
+    let Some(branch_oid) = branch.get().target() else {
+        return Ok(true);
+    };
+    let target = match branch.upstream() {
+        Ok(upstream) => upstream.get().target(),
+        Err(_) => repo.head().ok().and_then(|head| head.target()),
+    };
+    let Some(target) = target else {
+        return Ok(false);
+    };
+    if target == branch_oid {
+        return Ok(true);
+    }
+    repo.graph_descendant_of(target, branch_oid)
+        .map_err(|e| GitOpsError::BranchDelete(e.message().to_string()))
+}
+
+/// Rename the local branch `name` to `new_name`.
+///
+/// `HEAD` follows the branch if it is checked out, and its upstream is kept.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository cannot be opened
+/// - The branch does not exist
+/// - `new_name` is not a valid branch name, or a branch already has it
+pub fn rename_branch(
+    repo_path: &Path,
+    name: &str,
+    new_name: &str,
+) -> Result<GitBranch, GitOpsError> {
+    let repo = Repository::open(repo_path)?;
+    let mut branch = find_local_branch(&repo, name)?;
+    validate_branch_name(new_name)?;
+    if repo.find_branch(new_name, BranchType::Local).is_ok() {
+        return Err(GitOpsError::BranchExists(new_name.to_string()));
+    }
+
+    branch
+        .rename(new_name, false)
+        .map_err(|e| GitOpsError::BranchRename(e.message().to_string()))?;
+
+    local_branch_info(&repo, new_name)
+}
+
+/// Set the upstream of the local branch `name`, or clear it with `None`.
+///
+/// # Arguments
+///
+/// * `repo_path` - Path to the repository or worktree
+/// * `name` - Local branch name
+/// * `upstream` - Remote-tracking branch to track, e.g. `origin/main`
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository cannot be opened
+/// - The branch or the upstream does not exist
+pub fn set_upstream(
+    repo_path: &Path,
+    name: &str,
+    upstream: Option<&str>,
+) -> Result<GitBranch, GitOpsError> {
+    let repo = Repository::open(repo_path)?;
+    let mut branch = find_local_branch(&repo, name)?;
+    branch
+        .set_upstream(upstream)
+        .map_err(|e| GitOpsError::UpstreamSet(e.message().to_string()))?;
+
+    local_branch_info(&repo, name)
+}
+
 /// List all linked worktrees for a repository.
 pub fn list_worktrees(repo_path: &Path) -> Result<Vec<GitWorktree>, String> {
     let repo =
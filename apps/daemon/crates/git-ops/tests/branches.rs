@@ -1,6 +1,10 @@
 mod common;
 
-use git_ops::get_branches;
+use git_ops::{
+    checkout_branch, create_branch, create_worktree_with_options, delete_branch, get_branches,
+    rename_branch, set_upstream, GitOpsError,
+};
+use std::fs;
 use std::path::Path;
 
 #[test]
@@ -147,3 +151,224 @@ fn remote_branches_from_clone() {
         assert!(branch.is_remote);
     }
 }
+
+fn current_branch(repo_path: &Path) -> String {
+    get_branches(repo_path)
+        .expect("get_branches failed")
+        .current
+        .expect("HEAD is on a branch")
+}
+
+/// Create `name` from HEAD and commit `content` to README.md on it, leaving
+/// HEAD on the original branch.
+fn commit_on_branch(repo_path: &Path, name: &str, content: &str) {
+    let original = current_branch(repo_path);
+    create_branch(repo_path, name, None, None).expect("create branch");
+    checkout_branch(repo_path, name, false).expect("checkout branch");
+    common::create_file(repo_path, "README.md", content);
+    common::commit_all(repo_path, &format!("Change README on {name}"));
+    checkout_branch(repo_path, &original, false).expect("checkout original");
+}
+
+#[test]
+fn create_branch_from_head_or_start_point() {
+    let (_dir, repo_path) = common::init_repo_with_commits(2);
+    let head = get_branches(&repo_path).unwrap().local[0].head_oid.clone();
+
+    let branch = create_branch(&repo_path, "feature", None, None).expect("create branch");
+    assert_eq!(branch.name, "feature");
+    assert!(!branch.is_current);
+    assert_eq!(branch.head_oid, head);
+
+    let older = create_branch(&repo_path, "older", Some("HEAD~2"), None).expect("create branch");
+    assert_ne!(older.head_oid, head);
+
+    assert!(matches!(
+        create_branch(&repo_path, "feature", None, None),
+        Err(GitOpsError::BranchExists(_))
+    ));
+    assert!(matches!(
+        create_branch(&repo_path, "bad..name", None, None),
+        Err(GitOpsError::InvalidBranchName(_))
+    ));
+    assert!(matches!(
+        create_branch(&repo_path, "missing", Some("no-such-ref"), None),
+        Err(GitOpsError::BranchCreate(_))
+    ));
+}
+
+#[test]
+fn checkout_switches_and_keeps_unrelated_changes() {
+    let (_dir, repo_path) = common::init_test_repo();
+    commit_on_branch(&repo_path, "feature", "# Feature\n");
+    common::create_file(&repo_path, "notes.txt", "draft\n");
+
+    let branch = checkout_branch(&repo_path, "feature", false).expect("checkout");
+    assert!(branch.is_current);
+    assert_eq!(current_branch(&repo_path), "feature");
+    assert_eq!(
+        fs::read_to_string(repo_path.join("README.md")).unwrap(),
+        "# Feature\n"
+    );
+    assert_eq!(
+        fs::read_to_string(repo_path.join("notes.txt")).unwrap(),
+        "draft\n"
+    );
+}
+
+#[test]
+fn checkout_refuses_conflicting_changes_unless_forced() {
+    let (_dir, repo_path) = common::init_test_repo();
+    let original = current_branch(&repo_path);
+    commit_on_branch(&repo_path, "feature", "# Feature\n");
+    common::create_file(&repo_path, "README.md", "# Local edit\n");
+
+    match checkout_branch(&repo_path, "feature", false) {
+        Err(GitOpsError::CheckoutConflict(paths)) => assert_eq!(paths, ["README.md"]),
+        other => panic!("expected a checkout conflict, got {other:?}"),
+    }
+    assert_eq!(current_branch(&repo_path), original);
+    assert_eq!(
+        fs::read_to_string(repo_path.join("README.md")).unwrap(),
+        "# Local edit\n"
+    );
+
+    checkout_branch(&repo_path, "feature", true).expect("forced checkout");
+    assert_eq!(current_branch(&repo_path), "feature");
+    assert_eq!(
+        fs::read_to_string(repo_path.join("README.md")).unwrap(),
+        "# Feature\n"
+    );
+}
+
+#[test]
+fn checkout_of_remote_branch_creates_tracking_branch() {
+    let (_dir, origin_path) = common::init_test_repo();
+    create_branch(&origin_path, "feature", None, None).expect("create branch");
+    let clone_dir = tempfile::TempDir::new().expect("create temp dir");
+    let clone_path = clone_dir.path().join("clone");
+    git2::Repository::clone(origin_path.to_str().expect("path to str"), &clone_path)
+        .expect("clone repo");
+
+    let branch = checkout_branch(&clone_path, "feature", false).expect("checkout");
+    assert!(branch.is_current);
+    assert_eq!(branch.upstream.as_deref(), Some("origin/feature"));
+
+    assert!(matches!(
+        checkout_branch(&clone_path, "missing", false),
+        Err(GitOpsError::BranchNotFound(_))
+    ));
+}
+
+#[test]
+fn delete_refuses_unmerged_branches_unless_forced() {
+    let (_dir, repo_path) = common::init_test_repo();
+    create_branch(&repo_path, "merged", None, None).expect("create branch");
+    commit_on_branch(&repo_path, "unmerged", "# Unmerged\n");
+
+    let deleted = delete_branch(&repo_path, "merged", false).expect("delete merged");
+    assert_eq!(deleted.name, "merged");
+
+    assert!(matches!(
+        delete_branch(&repo_path, "unmerged", false),
+        Err(GitOpsError::BranchNotMerged(_))
+    ));
+    let deleted = delete_branch(&repo_path, "unmerged", true).expect("forced delete");
+    assert_eq!(deleted.name, "unmerged");
+
+    let local: Vec<String> = get_branches(&repo_path)
+        .unwrap()
+        .local
+        .into_iter()
+        .map(|branch| branch.name)
+        .collect();
+    assert_eq!(local, [current_branch(&repo_path)]);
+    assert!(matches!(
+        delete_branch(&repo_path, "merged", false),
+        Err(GitOpsError::BranchNotFound(_))
+    ));
+}
+
+#[test]
+fn delete_refuses_the_current_branch() {
+    let (_dir, repo_path) = common::init_test_repo();
+    let current = current_branch(&repo_path);
+
+    assert!(matches!(
+        delete_branch(&repo_path, &current, true),
+        Err(GitOpsError::BranchCheckedOut(..))
+    ));
+}
+
+#[test]
+fn branches_checked_out_in_a_worktree_are_left_alone() {
+    let (_dir, repo_path) = common::init_test_repo();
+    let worktrees = tempfile::TempDir::new().expect("create temp dir");
+    create_worktree_with_options(
+        &repo_path,
+        "session-1",
+        worktrees.path(),
+        None,
+        Some("unbound/session-1"),
+    )
+    .expect("create worktree");
+
+    assert!(matches!(
+        checkout_branch(&repo_path, "unbound/session-1", false),
+        Err(GitOpsError::BranchCheckedOut(..))
+    ));
+    assert!(matches!(
+        delete_branch(&repo_path, "unbound/session-1", true),
+        Err(GitOpsError::BranchCheckedOut(..))
+    ));
+
+    // The worktree sees the main checkout's branch the same way
+    let main_branch = current_branch(&repo_path);
+    assert!(matches!(
+        checkout_branch(&worktrees.path().join("session-1"), &main_branch, false),
+        Err(GitOpsError::BranchCheckedOut(..))
+    ));
+}
+
+#[test]
+fn rename_moves_head_with_the_branch() {
+    let (_dir, repo_path) = common::init_test_repo();
+    let current = current_branch(&repo_path);
+
+    let renamed = rename_branch(&repo_path, &current, "trunk").expect("rename");
+    assert_eq!(renamed.name, "trunk");
+    assert!(renamed.is_current);
+    assert_eq!(current_branch(&repo_path), "trunk");
+
+    create_branch(&repo_path, "other", None, None).expect("create branch");
+    assert!(matches!(
+        rename_branch(&repo_path, "trunk", "other"),
+        Err(GitOpsError::BranchExists(_))
+    ));
+    assert!(matches!(
+        rename_branch(&repo_path, "missing", "anything"),
+        Err(GitOpsError::BranchNotFound(_))
+    ));
+}
+
+#[test]
+fn upstream_can_be_set_and_cleared() {
+    let (_dir, origin_path) = common::init_test_repo();
+    create_branch(&origin_path, "feature", None, None).expect("create branch");
+    let clone_dir = tempfile::TempDir::new().expect("create temp dir");
+    let clone_path = clone_dir.path().join("clone");
+    git2::Repository::clone(origin_path.to_str().expect("path to str"), &clone_path)
+        .expect("clone repo");
+
+    let branch = create_branch(&clone_path, "local", None, Some("origin/feature"))
+        .expect("create tracking branch");
+    assert_eq!(branch.upstream.as_deref(), Some("origin/feature"));
+
+    let branch = set_upstream(&clone_path, "local", None).expect("clear upstream");
+    assert!(branch.upstream.is_none());
+
+    assert!(matches!(
+        set_upstream(&clone_path, "local", Some("origin/missing")),
+        Err(GitOpsError::UpstreamSet(_))
+    ));
+}
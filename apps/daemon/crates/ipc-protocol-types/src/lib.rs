@@ -53,6 +53,14 @@ pub enum Method {
     SessionUpdate,
     #[serde(rename = "session.delete")]
     SessionDelete,
+    #[serde(rename = "session.search")]
+    SessionSearch,
+    #[serde(rename = "session.fork")]
+    SessionFork,
+    #[serde(rename = "session.export")]
+    SessionExport,
+    #[serde(rename = "session.import")]
+    SessionImport,
 
     // Spaces
     #[serde(rename = "space.get_current")]
@@ -91,6 +99,16 @@ pub enum Method {
     RepositoryWriteFile,
     #[serde(rename = "repository.replace_file_range")]
     RepositoryReplaceFileRange,
+    #[serde(rename = "repository.mcp_list")]
+    RepositoryMcpList,
+    #[serde(rename = "repository.mcp_update")]
+    RepositoryMcpUpdate,
+    #[serde(rename = "repository.env_list")]
+    RepositoryEnvList,
+    #[serde(rename = "repository.env_set")]
+    RepositoryEnvSet,
+    #[serde(rename = "repository.env_delete")]
+    RepositoryEnvDelete,
 
     // Agent CLI
     #[serde(rename = "agent.send")]
@@ -99,6 +117,23 @@ pub enum Method {
     AgentStatus,
     #[serde(rename = "agent.stop")]
     AgentStop,
+    /// Asks subscribed clients to approve a tool use; answered by `agent.respond_permission`.
+    #[serde(rename = "agent.permission_request")]
+    AgentPermissionRequest,
+    #[serde(rename = "agent.respond_permission")]
+    AgentRespondPermission,
+    /// Answers an `AskUserQuestion` tool use and resumes the agent.
+    #[serde(rename = "agent.answer_question")]
+    AgentAnswerQuestion,
+    /// Lists messages queued behind a running agent turn.
+    #[serde(rename = "agent.queue_list")]
+    AgentQueueList,
+    /// Removes one queued message.
+    #[serde(rename = "agent.queue_remove")]
+    AgentQueueRemove,
+    /// Removes every queued message for a session.
+    #[serde(rename = "agent.queue_clear")]
+    AgentQueueClear,
 
     // Claude CLI legacy compatibility
     #[serde(rename = "claude.send")]
@@ -135,6 +170,14 @@ pub enum Method {
     GitCommitChanges,
     #[serde(rename = "git.push")]
     GitPush,
+    #[serde(rename = "git.checkout")]
+    GitCheckout,
+    #[serde(rename = "git.branch_create")]
+    GitBranchCreate,
+    #[serde(rename = "git.branch_delete")]
+    GitBranchDelete,
+    #[serde(rename = "git.branch_rename")]
+    GitBranchRename,
 
     // GitHub CLI operations
     #[serde(rename = "gh.auth_status")]
@@ -163,6 +206,16 @@ pub enum Method {
     TerminalStatus,
     #[serde(rename = "terminal.stop")]
     TerminalStop,
+    #[serde(rename = "terminal.list")]
+    TerminalList,
+    #[serde(rename = "terminal.read_output")]
+    TerminalReadOutput,
+    #[serde(rename = "terminal.write")]
+    TerminalWrite,
+    #[serde(rename = "terminal.resize")]
+    TerminalResize,
+    #[serde(rename = "terminal.signal")]
+    TerminalSignal,
 }
 
 /// Server-push event for subscriptions.
@@ -176,6 +229,11 @@ pub struct Event {
     /// Event payload.
     pub data: serde_json::Value,
     /// Sequence number for ordering/resumption.
+    ///
+    /// For session events this is the session's persisted message sequence, so
+    /// it is stable across daemon restarts. Events that persist a message carry
+    /// that message's sequence (see [`EventType::carries_message_sequence`]);
+    /// other session events carry the sequence of the latest committed message.
     pub sequence: i64,
     /// Optional trace context for continuing the originating trace on the client.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -196,7 +254,7 @@ pub enum EventType {
     InitialState,
     /// Keepalive ping.
     Ping,
-    /// Terminal output chunk (stdout or stderr).
+    /// Terminal output chunk read from the PTY.
     TerminalOutput,
     /// Terminal command finished with exit code.
     TerminalFinished,
@@ -208,6 +266,22 @@ pub enum EventType {
     SessionCreated,
     /// A session was deleted.
     SessionDeleted,
+    /// An agent is waiting for a client to approve or deny a tool use.
+    PermissionRequest,
+    /// The agent scheduler's running or waiting requests changed.
+    SchedulerUpdated,
+}
+
+impl EventType {
+    /// Whether events of this type each persist one session message, so their
+    /// `sequence` identifies that message and can be passed back as
+    /// `after_sequence` when resubscribing.
+    pub fn carries_message_sequence(&self) -> bool {
+        matches!(
+            self,
+            EventType::Message | EventType::ClaudeEvent | EventType::AgentEvent
+        )
+    }
 }
 
 impl Event {
@@ -456,6 +530,10 @@ mod tests {
             (Method::SessionGet, "\"session.get\""),
             (Method::SessionUpdate, "\"session.update\""),
             (Method::SessionDelete, "\"session.delete\""),
+            (Method::SessionSearch, "\"session.search\""),
+            (Method::SessionFork, "\"session.fork\""),
+            (Method::SessionExport, "\"session.export\""),
+            (Method::SessionImport, "\"session.import\""),
             (Method::SpaceGetCurrent, "\"space.get_current\""),
             (
                 Method::SpaceUpdateCurrentMachineName,
@@ -483,9 +561,26 @@ mod tests {
                 Method::RepositoryReplaceFileRange,
                 "\"repository.replace_file_range\"",
             ),
+            (Method::RepositoryMcpList, "\"repository.mcp_list\""),
+            (Method::RepositoryMcpUpdate, "\"repository.mcp_update\""),
+            (Method::RepositoryEnvList, "\"repository.env_list\""),
+            (Method::RepositoryEnvSet, "\"repository.env_set\""),
+            (Method::RepositoryEnvDelete, "\"repository.env_delete\""),
             (Method::AgentSend, "\"agent.send\""),
             (Method::AgentStatus, "\"agent.status\""),
             (Method::AgentStop, "\"agent.stop\""),
+            (
+                Method::AgentPermissionRequest,
+                "\"agent.permission_request\"",
+            ),
+            (
+                Method::AgentRespondPermission,
+                "\"agent.respond_permission\"",
+            ),
+            (Method::AgentAnswerQuestion, "\"agent.answer_question\""),
+            (Method::AgentQueueList, "\"agent.queue_list\""),
+            (Method::AgentQueueRemove, "\"agent.queue_remove\""),
+            (Method::AgentQueueClear, "\"agent.queue_clear\""),
             (Method::ClaudeSend, "\"claude.send\""),
             (Method::ClaudeStatus, "\"claude.status\""),
             (Method::ClaudeStop, "\"claude.stop\""),
@@ -501,6 +596,10 @@ mod tests {
             (Method::GitDiscard, "\"git.discard\""),
             (Method::GitCommitChanges, "\"git.commit\""),
             (Method::GitPush, "\"git.push\""),
+            (Method::GitCheckout, "\"git.checkout\""),
+            (Method::GitBranchCreate, "\"git.branch_create\""),
+            (Method::GitBranchDelete, "\"git.branch_delete\""),
+            (Method::GitBranchRename, "\"git.branch_rename\""),
             (Method::GhAuthStatus, "\"gh.auth_status\""),
             (Method::GhPrCreate, "\"gh.pr_create\""),
             (Method::GhPrView, "\"gh.pr_view\""),
@@ -514,6 +613,11 @@ mod tests {
             (Method::TerminalRun, "\"terminal.run\""),
             (Method::TerminalStatus, "\"terminal.status\""),
             (Method::TerminalStop, "\"terminal.stop\""),
+            (Method::TerminalList, "\"terminal.list\""),
+            (Method::TerminalReadOutput, "\"terminal.read_output\""),
+            (Method::TerminalWrite, "\"terminal.write\""),
+            (Method::TerminalResize, "\"terminal.resize\""),
+            (Method::TerminalSignal, "\"terminal.signal\""),
         ];
 
         for (method, expected) in cases {
@@ -532,6 +636,10 @@ mod tests {
             Method::SessionGet,
             Method::SessionUpdate,
             Method::SessionDelete,
+            Method::SessionSearch,
+            Method::SessionFork,
+            Method::SessionExport,
+            Method::SessionImport,
             Method::SpaceGetCurrent,
             Method::SpaceUpdateCurrentMachineName,
             Method::MessageList,
@@ -547,9 +655,20 @@ mod tests {
             Method::RepositoryReadFileSlice,
             Method::RepositoryWriteFile,
             Method::RepositoryReplaceFileRange,
+            Method::RepositoryMcpList,
+            Method::RepositoryMcpUpdate,
+            Method::RepositoryEnvList,
+            Method::RepositoryEnvSet,
+            Method::RepositoryEnvDelete,
             Method::AgentSend,
             Method::AgentStatus,
             Method::AgentStop,
+            Method::AgentPermissionRequest,
+            Method::AgentRespondPermission,
+            Method::AgentAnswerQuestion,
+            Method::AgentQueueList,
+            Method::AgentQueueRemove,
+            Method::AgentQueueClear,
             Method::ClaudeSend,
             Method::ClaudeStatus,
             Method::ClaudeStop,
@@ -565,6 +684,10 @@ mod tests {
             Method::GitDiscard,
             Method::GitCommitChanges,
             Method::GitPush,
+            Method::GitCheckout,
+            Method::GitBranchCreate,
+            Method::GitBranchDelete,
+            Method::GitBranchRename,
             Method::GhAuthStatus,
             Method::GhPrCreate,
             Method::GhPrView,
@@ -575,6 +698,11 @@ mod tests {
             Method::TerminalRun,
             Method::TerminalStatus,
             Method::TerminalStop,
+            Method::TerminalList,
+            Method::TerminalReadOutput,
+            Method::TerminalWrite,
+            Method::TerminalResize,
+            Method::TerminalSignal,
         ];
 
         for method in methods {
@@ -874,6 +1002,8 @@ mod tests {
             (EventType::ClaudeEvent, "\"claude_event\""),
             (EventType::SessionCreated, "\"session_created\""),
             (EventType::SessionDeleted, "\"session_deleted\""),
+            (EventType::PermissionRequest, "\"permission_request\""),
+            (EventType::SchedulerUpdated, "\"scheduler_updated\""),
         ];
 
         for (event_type, expected) in types {
@@ -900,6 +1030,8 @@ mod tests {
             EventType::ClaudeEvent,
             EventType::SessionCreated,
             EventType::SessionDeleted,
+            EventType::PermissionRequest,
+            EventType::SchedulerUpdated,
         ];
         for et in types {
             let json = serde_json::to_string(&et).unwrap();
@@ -936,6 +1068,16 @@ mod tests {
         assert_eq!(e.sequence, 0);
     }
 
+    #[test]
+    fn message_bearing_event_types_carry_message_sequence() {
+        assert!(EventType::Message.carries_message_sequence());
+        assert!(EventType::ClaudeEvent.carries_message_sequence());
+        assert!(EventType::AgentEvent.carries_message_sequence());
+        assert!(!EventType::StatusChange.carries_message_sequence());
+        assert!(!EventType::StreamingChunk.carries_message_sequence());
+        assert!(!EventType::SessionCreated.carries_message_sequence());
+    }
+
     #[test]
     fn event_data_can_be_complex_json() {
         let data = serde_json::json!({
@@ -1040,6 +1182,10 @@ mod tests {
             Method::SessionGet,
             Method::SessionUpdate,
             Method::SessionDelete,
+            Method::SessionSearch,
+            Method::SessionFork,
+            Method::SessionExport,
+            Method::SessionImport,
             Method::SpaceGetCurrent,
             Method::SpaceUpdateCurrentMachineName,
             Method::MessageList,
@@ -1055,9 +1201,20 @@ mod tests {
             Method::RepositoryReadFileSlice,
             Method::RepositoryWriteFile,
             Method::RepositoryReplaceFileRange,
+            Method::RepositoryMcpList,
+            Method::RepositoryMcpUpdate,
+            Method::RepositoryEnvList,
+            Method::RepositoryEnvSet,
+            Method::RepositoryEnvDelete,
             Method::AgentSend,
             Method::AgentStatus,
             Method::AgentStop,
+            Method::AgentPermissionRequest,
+            Method::AgentRespondPermission,
+            Method::AgentAnswerQuestion,
+            Method::AgentQueueList,
+            Method::AgentQueueRemove,
+            Method::AgentQueueClear,
             Method::ClaudeSend,
             Method::ClaudeStatus,
             Method::ClaudeStop,
@@ -1073,6 +1230,10 @@ mod tests {
             Method::GitDiscard,
             Method::GitCommitChanges,
             Method::GitPush,
+            Method::GitCheckout,
+            Method::GitBranchCreate,
+            Method::GitBranchDelete,
+            Method::GitBranchRename,
             Method::GhAuthStatus,
             Method::GhPrCreate,
             Method::GhPrView,
@@ -1083,7 +1244,12 @@ mod tests {
             Method::TerminalRun,
             Method::TerminalStatus,
             Method::TerminalStop,
+            Method::TerminalList,
+            Method::TerminalReadOutput,
+            Method::TerminalWrite,
+            Method::TerminalResize,
+            Method::TerminalSignal,
         ];
-        assert_eq!(methods.len(), 50);
+        assert_eq!(methods.len(), 74);
     }
 }
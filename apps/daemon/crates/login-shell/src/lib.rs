@@ -0,0 +1,314 @@
+//! # Login Shell: the user's shell for daemon commands
+//!
+//! Terminal runs, setup hooks and dependency checks run their command lines
+//! through the user's shell, so they see the same `PATH` and environment a
+//! terminal would. [`Shell::login`] detects that shell from `$SHELL`, then
+//! the passwd entry, falling back to [`FALLBACK_SHELL`]; a configured path
+//! takes its place through [`Shell::resolve`].
+//!
+//! A login shell reads the user's profile on startup, which can take a
+//! while (nvm, pyenv, ...). The environment it ends up with is captured once
+//! per shell and reused: commands then run in a plain `<shell> -c` with that
+//! environment. If the capture fails, every command runs in a login shell
+//! instead. Profile changes are picked up when the daemon restarts.
+//!
+//! ```ignore
+//! use login_shell::Shell;
+//!
+//! let shell = Shell::resolve(config.shell.as_deref());
+//! let output = shell.invocation("which claude").await.command().output().await?;
+//! ```
+
+use std::collections::HashMap;
+use std::ffi::{CStr, OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use std::{mem, ptr};
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
+
+/// Shell used when neither `$SHELL` nor the passwd entry names one that
+/// exists.
+pub const FALLBACK_SHELL: &str = "/bin/sh";
+
+/// How long a login shell may take to report its environment.
+const ENV_CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Printed before the environment, so whatever the profile prints is
+/// skipped.
+const ENV_MARKER: &str = "__UNBOUND_LOGIN_ENV__";
+
+/// Variables describing the capturing shell rather than the user's
+/// environment.
+const SHELL_LOCAL_VARS: &[&str] = &["PWD", "OLDPWD", "SHLVL", "_"];
+
+/// Largest buffer offered to `getpwuid_r`.
+const MAX_PASSWD_BUFFER: usize = 1 << 20;
+
+/// The environment of a login shell, by variable name.
+pub type LoginEnv = HashMap<String, String>;
+
+type EnvCache = Mutex<HashMap<PathBuf, Arc<OnceCell<Option<Arc<LoginEnv>>>>>>;
+
+/// A shell that runs command lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shell {
+    path: PathBuf,
+}
+
+impl Shell {
+    /// The shell at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The user's login shell, detected on first use.
+    pub fn login() -> &'static Shell {
+        static LOGIN: OnceLock<Shell> = OnceLock::new();
+        LOGIN.get_or_init(|| {
+            let shell = Self::new(detect(std::env::var_os("SHELL"), passwd_shell()));
+            info!(shell = %shell.path.display(), "Detected login shell");
+            shell
+        })
+    }
+
+    /// The `configured` shell, or the login shell when none is configured.
+    pub fn resolve(configured: Option<&str>) -> Self {
+        match configured.map(str::trim).filter(|path| !path.is_empty()) {
+            Some(path) => Self::new(path),
+            None => Self::login().clone(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The environment of a login session of this shell, captured on first
+    /// use. `None` when the shell could not report it.
+    pub async fn login_env(&self) -> Option<Arc<LoginEnv>> {
+        static CACHE: OnceLock<EnvCache> = OnceLock::new();
+        let cell = CACHE
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .entry(self.path.clone())
+            .or_default()
+            .clone();
+        cell.get_or_init(|| capture_login_env(&self.path))
+            .await
+            .clone()
+    }
+
+    /// How to run `command` in this shell with the login environment.
+    pub async fn invocation(&self, command: &str) -> ShellInvocation {
+        let env = self.login_env().await;
+        let mut args = Vec::with_capacity(3);
+        if env.is_none() {
+            args.push("-l".to_string());
+        }
+        args.extend(["-c".to_string(), command.to_string()]);
+        ShellInvocation {
+            program: self.path.clone(),
+            args,
+            env,
+        }
+    }
+}
+
+/// A command line ready to run in a shell.
+#[derive(Debug, Clone)]
+pub struct ShellInvocation {
+    program: PathBuf,
+    args: Vec<String>,
+    env: Option<Arc<LoginEnv>>,
+}
+
+impl ShellInvocation {
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Set the login environment on `command`, which runs this invocation
+    /// or wraps it, e.g. in a sandbox. Variables set on `command` afterwards
+    /// take precedence.
+    pub fn apply_env(&self, command: &mut Command) {
+        if let Some(env) = &self.env {
+            command.envs(env.iter());
+        }
+    }
+
+    /// A command running this invocation directly.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        self.apply_env(&mut command);
+        command
+    }
+}
+
+/// The first of `$SHELL` and the passwd entry's shell that is an
+/// executable file.
+fn detect(env_shell: Option<OsString>, passwd_shell: Option<PathBuf>) -> PathBuf {
+    env_shell
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(passwd_shell)
+        .find(|path| is_executable(path))
+        .unwrap_or_else(|| PathBuf::from(FALLBACK_SHELL))
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.is_absolute()
+        && std::fs::metadata(path)
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// The current user's shell from the passwd database.
+fn passwd_shell() -> Option<PathBuf> {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        let rc = unsafe {
+            libc::getpwuid_r(
+                libc::getuid(),
+                &mut passwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        if rc == libc::ERANGE && buf.len() < MAX_PASSWD_BUFFER {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() || passwd.pw_shell.is_null() {
+            return None;
+        }
+        let shell = unsafe { CStr::from_ptr(passwd.pw_shell) }.to_bytes();
+        return (!shell.is_empty()).then(|| PathBuf::from(OsStr::from_bytes(shell)));
+    }
+}
+
+async fn capture_login_env(shell: &Path) -> Option<Arc<LoginEnv>> {
+    let script = format!("echo {ENV_MARKER}; env -0");
+    let output = Command::new(shell)
+        .args(["-l", "-c", &script])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(ENV_CAPTURE_TIMEOUT, output).await {
+        Ok(Ok(output)) if output.status.success() => output,
+        Ok(Ok(output)) => {
+            warn!(
+                shell = %shell.display(),
+                status = %output.status,
+                "Login shell environment capture failed"
+            );
+            return None;
+        }
+        Ok(Err(e)) => {
+            warn!(shell = %shell.display(), error = %e, "Failed to start login shell");
+            return None;
+        }
+        Err(_) => {
+            warn!(shell = %shell.display(), "Login shell environment capture timed out");
+            return None;
+        }
+    };
+    let Some(env) = parse_env(&output.stdout) else {
+        warn!(shell = %shell.display(), "Login shell did not report its environment");
+        return None;
+    };
+    debug!(shell = %shell.display(), vars = env.len(), "Captured login shell environment");
+    Some(Arc::new(env))
+}
+
+/// Read the NUL-separated `env -0` output following [`ENV_MARKER`].
+fn parse_env(stdout: &[u8]) -> Option<LoginEnv> {
+    let marker = format!("{ENV_MARKER}\n");
+    let start = stdout
+        .windows(marker.len())
+        .position(|window| window == marker.as_bytes())?
+        + marker.len();
+    let env = stdout[start..]
+        .split(|byte| *byte == 0)
+        .filter_map(|entry| {
+            let (name, value) = std::str::from_utf8(entry).ok()?.split_once('=')?;
+            (!name.is_empty() && !SHELL_LOCAL_VARS.contains(&name))
+                .then(|| (name.to_string(), value.to_string()))
+        })
+        .collect();
+    Some(env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_takes_the_first_existing_shell() {
+        assert_eq!(
+            detect(Some("/bin/sh".into()), Some("/nonexistent/zsh".into())),
+            Path::new("/bin/sh")
+        );
+        assert_eq!(
+            detect(Some("/nonexistent/fish".into()), Some("/bin/sh".into())),
+            Path::new("/bin/sh")
+        );
+        assert_eq!(detect(Some("sh".into()), None), Path::new(FALLBACK_SHELL));
+    }
+
+    #[test]
+    fn resolve_prefers_the_configured_shell() {
+        assert_eq!(
+            Shell::resolve(Some("/usr/bin/fish")).path(),
+            Path::new("/usr/bin/fish")
+        );
+        assert_eq!(Shell::resolve(Some("  ")), *Shell::login());
+        assert_eq!(Shell::resolve(None), *Shell::login());
+    }
+
+    #[test]
+    fn env_output_after_the_marker_is_read() {
+        let stdout = format!(
+            "Welcome back\n{ENV_MARKER}\nPATH=/opt/bin:/usr/bin\0NOTE=a=b\nc\0SHLVL=2\0_=/usr/bin/env\0"
+        );
+        let env = parse_env(stdout.as_bytes()).unwrap();
+
+        assert_eq!(env.len(), 2);
+        assert_eq!(env["PATH"], "/opt/bin:/usr/bin");
+        assert_eq!(env["NOTE"], "a=b\nc");
+        assert_eq!(parse_env(b"PATH=/usr/bin\0"), None);
+    }
+
+    #[tokio::test]
+    async fn commands_run_with_the_captured_environment() {
+        let shell = Shell::new("/bin/sh");
+        let env = shell.login_env().await.expect("sh reports its environment");
+        assert!(env.contains_key("PATH"));
+
+        let invocation = shell.invocation("echo \"$PATH\"").await;
+        assert_eq!(invocation.args(), ["-c", "echo \"$PATH\""]);
+        let output = invocation.command().output().await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), env["PATH"]);
+    }
+
+    #[tokio::test]
+    async fn missing_shell_falls_back_to_a_login_invocation() {
+        let shell = Shell::new("/nonexistent/shell");
+        assert!(shell.login_env().await.is_none());
+        assert_eq!(shell.invocation("true").await.args(), ["-l", "-c", "true"]);
+    }
+}
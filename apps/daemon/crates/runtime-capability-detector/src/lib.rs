@@ -7,8 +7,9 @@
 //! ## Overview
 //!
 //! The crate exposes pure async functions that check for dependencies
-//! by spawning a login shell and running `which`. This ensures the
-//! user's full PATH is available (including nvm, homebrew, etc.).
+//! by running `which` in the user's shell (see [`login_shell::Shell`])
+//! with its login environment. This ensures the user's full PATH is
+//! available (including nvm, homebrew, etc.).
 //!
 //! ## Key Operations
 //!
@@ -21,14 +22,15 @@
 //! ## Example Usage
 //!
 //! ```ignore
+//! use login_shell::Shell;
 //! use runtime_capability_detector::{check_all, check_dependency};
 //!
 //! // Check a single dependency
-//! let claude = check_dependency("claude").await?;
+//! let claude = check_dependency(Shell::login(), "claude").await?;
 //! println!("Claude installed: {}", claude.installed);
 //!
 //! // Check all dependencies at once
-//! let result = check_all().await?;
+//! let result = check_all(Shell::login()).await?;
 //! if !result.claude.installed {
 //!     println!("Claude Code CLI is required!");
 //! }
@@ -1,7 +1,7 @@
 //! Dependency checking operations.
 //!
 //! Pure async functions that detect whether system dependencies are installed
-//! by spawning a login shell and running `which <name>`.
+//! by running `which <name>` in the user's shell with its login environment.
 
 use crate::error::RuntimeCapabilityDetectorError;
 use crate::types::{
@@ -9,7 +9,7 @@ use crate::types::{
     ToolCapabilities,
 };
 use chrono::{SecondsFormat, Utc};
-use std::path::Path;
+use login_shell::Shell;
 use std::time::Duration;
 use tracing::debug;
 
@@ -26,15 +26,16 @@ const KNOWN_CODEX_MODELS: &[&str] = &[
 
 /// Check whether a single dependency is installed.
 ///
-/// Runs `/bin/zsh -l -c "which <name>"` to resolve the dependency path
-/// through the user's login shell (picking up PATH from .zprofile/.zshrc).
+/// Runs `which <name>` in `shell` to resolve the dependency path with the
+/// `PATH` of the user's login environment (.zprofile, .bash_profile, ...).
 pub async fn check_dependency(
+    shell: &Shell,
     name: &str,
 ) -> Result<DependencyInfo, RuntimeCapabilityDetectorError> {
     debug!("Checking dependency: {}", name);
 
     let output =
-        match run_login_shell_with_timeout(&format!("which {}", name), DEPENDENCY_CHECK_TIMEOUT)
+        match run_shell_with_timeout(shell, &format!("which {}", name), DEPENDENCY_CHECK_TIMEOUT)
             .await
         {
             Ok(output) => output,
@@ -70,8 +71,13 @@ pub async fn check_dependency(
 /// Check all required system dependencies concurrently.
 ///
 /// Returns the status of both `claude` (required) and `gh` (optional).
-pub async fn check_all() -> Result<DependencyCheckResult, RuntimeCapabilityDetectorError> {
-    let (claude, gh) = tokio::join!(check_dependency("claude"), check_dependency("gh"));
+pub async fn check_all(
+    shell: &Shell,
+) -> Result<DependencyCheckResult, RuntimeCapabilityDetectorError> {
+    let (claude, gh) = tokio::join!(
+        check_dependency(shell, "claude"),
+        check_dependency(shell, "gh")
+    );
 
     Ok(DependencyCheckResult {
         claude: claude?,
@@ -80,12 +86,14 @@ pub async fn check_all() -> Result<DependencyCheckResult, RuntimeCapabilityDetec
 }
 
 /// Collect the canonical capabilities payload exposed over local IPC.
-pub async fn collect_capabilities() -> Result<Capabilities, RuntimeCapabilityDetectorError> {
+pub async fn collect_capabilities(
+    shell: &Shell,
+) -> Result<Capabilities, RuntimeCapabilityDetectorError> {
     let (claude, gh, codex, ollama) = tokio::join!(
-        check_dependency("claude"),
-        check_dependency("gh"),
-        check_dependency("codex"),
-        check_dependency("ollama")
+        check_dependency(shell, "claude"),
+        check_dependency(shell, "gh"),
+        check_dependency(shell, "codex"),
+        check_dependency(shell, "ollama")
     );
 
     let claude = claude?;
@@ -94,7 +102,7 @@ pub async fn collect_capabilities() -> Result<Capabilities, RuntimeCapabilityDet
     let ollama = ollama?;
 
     let claude_models = if claude.installed {
-        read_claude_models().await
+        read_claude_models(shell).await
     } else {
         None
     };
@@ -108,6 +116,11 @@ pub async fn collect_capabilities() -> Result<Capabilities, RuntimeCapabilityDet
     } else {
         None
     };
+    let ollama_models = if ollama.installed {
+        read_ollama_models(shell).await
+    } else {
+        None
+    };
 
     Ok(Capabilities {
         cli: CliCapabilities {
@@ -129,7 +142,7 @@ pub async fn collect_capabilities() -> Result<Capabilities, RuntimeCapabilityDet
             ollama: ToolCapabilities {
                 installed: ollama.installed,
                 path: ollama.path,
-                models: None,
+                models: ollama_models,
             },
         },
         metadata: CapabilitiesMetadata {
@@ -139,18 +152,18 @@ pub async fn collect_capabilities() -> Result<Capabilities, RuntimeCapabilityDet
     })
 }
 
-async fn read_claude_models() -> Option<Vec<String>> {
-    if let Some(models) = try_claude_models_json().await {
+async fn read_claude_models(shell: &Shell) -> Option<Vec<String>> {
+    if let Some(models) = try_claude_models_json(shell).await {
         if !models.is_empty() {
             return Some(models);
         }
     }
 
-    try_claude_models_text().await
+    try_claude_models_text(shell).await
 }
 
-async fn try_claude_models_json() -> Option<Vec<String>> {
-    let output = run_login_shell_with_timeout("claude models --json", DEPENDENCY_CHECK_TIMEOUT)
+async fn try_claude_models_json(shell: &Shell) -> Option<Vec<String>> {
+    let output = run_shell_with_timeout(shell, "claude models --json", DEPENDENCY_CHECK_TIMEOUT)
         .await
         .ok()?;
     if !output.status.success() {
@@ -191,8 +204,8 @@ async fn try_claude_models_json() -> Option<Vec<String>> {
     normalize_models(models)
 }
 
-async fn try_claude_models_text() -> Option<Vec<String>> {
-    let output = run_login_shell_with_timeout("claude models", DEPENDENCY_CHECK_TIMEOUT)
+async fn try_claude_models_text(shell: &Shell) -> Option<Vec<String>> {
+    let output = run_shell_with_timeout(shell, "claude models", DEPENDENCY_CHECK_TIMEOUT)
         .await
         .ok()?;
     if !output.status.success() {
@@ -226,20 +239,51 @@ async fn try_claude_models_text() -> Option<Vec<String>> {
     normalize_models(models)
 }
 
-async fn run_login_shell(
+/// Models pulled into the local Ollama store, from `ollama list`.
+async fn read_ollama_models(shell: &Shell) -> Option<Vec<String>> {
+    let output = run_shell_with_timeout(shell, "ollama list", DEPENDENCY_CHECK_TIMEOUT)
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_ollama_list(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Model names from `ollama list` output: a `NAME ID SIZE MODIFIED` header,
+/// then one model per line.
+fn parse_ollama_list(stdout: &str) -> Option<Vec<String>> {
+    let models = stdout
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("NAME"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .map(ToOwned::to_owned)
+        .collect();
+
+    normalize_models(models)
+}
+
+async fn run_shell(
+    shell: &Shell,
     command: &str,
 ) -> Result<std::process::Output, RuntimeCapabilityDetectorError> {
-    Ok(tokio::process::Command::new(login_shell())
-        .args(["-l", "-c", command])
+    Ok(shell
+        .invocation(command)
+        .await
+        .command()
+        .kill_on_drop(true)
         .output()
         .await?)
 }
 
-async fn run_login_shell_with_timeout(
+async fn run_shell_with_timeout(
+    shell: &Shell,
     command: &str,
     timeout: Duration,
 ) -> Result<std::process::Output, RuntimeCapabilityDetectorError> {
-    match tokio::time::timeout(timeout, run_login_shell(command)).await {
+    match tokio::time::timeout(timeout, run_shell(shell, command)).await {
         Ok(result) => result,
         Err(_) => Err(RuntimeCapabilityDetectorError::CheckFailed(format!(
             "command timed out after {}s: {}",
@@ -249,24 +293,6 @@ async fn run_login_shell_with_timeout(
     }
 }
 
-fn login_shell() -> &'static str {
-    if let Ok(shell) = std::env::var("SHELL") {
-        if shell == "/bin/zsh" {
-            return "/bin/zsh";
-        }
-        if shell == "/bin/bash" {
-            return "/bin/bash";
-        }
-    }
-    if Path::new("/bin/zsh").exists() {
-        "/bin/zsh"
-    } else if Path::new("/bin/bash").exists() {
-        "/bin/bash"
-    } else {
-        "/bin/sh"
-    }
-}
-
 fn normalize_models(models: Vec<String>) -> Option<Vec<String>> {
     let mut seen = std::collections::HashSet::new();
     let mut unique = Vec::new();
@@ -289,7 +315,9 @@ mod tests {
 
     #[tokio::test]
     async fn check_dependency_finds_common_shell() {
-        let result = check_dependency("sh").await.expect("check should succeed");
+        let result = check_dependency(Shell::login(), "sh")
+            .await
+            .expect("check should succeed");
         assert_eq!(result.name, "sh");
         assert!(result.installed);
         assert!(result.path.is_some());
@@ -297,7 +325,7 @@ mod tests {
 
     #[tokio::test]
     async fn check_dependency_missing_binary() {
-        let result = check_dependency("this_binary_does_not_exist_9999")
+        let result = check_dependency(Shell::login(), "this_binary_does_not_exist_9999")
             .await
             .expect("check should succeed even for missing binary");
         assert_eq!(result.name, "this_binary_does_not_exist_9999");
@@ -307,7 +335,9 @@ mod tests {
 
     #[tokio::test]
     async fn check_all_returns_both() {
-        let result = check_all().await.expect("check_all should succeed");
+        let result = check_all(Shell::login())
+            .await
+            .expect("check_all should succeed");
         assert_eq!(result.claude.name, "claude");
         assert_eq!(result.gh.name, "gh");
     }
@@ -324,6 +354,23 @@ mod tests {
         assert_eq!(models.len(), 2);
     }
 
+    #[test]
+    fn parse_ollama_list_reads_model_names() {
+        let stdout = "NAME                  ID              SIZE      MODIFIED\n\
+                      qwen2.5-coder:7b      2b0496514337    4.7 GB    2 days ago\n\
+                      llama3.1:latest       46e0c10c039e    4.9 GB    3 weeks ago\n";
+
+        assert_eq!(
+            parse_ollama_list(stdout),
+            Some(vec![
+                "qwen2.5-coder:7b".to_string(),
+                "llama3.1:latest".to_string()
+            ])
+        );
+        assert_eq!(parse_ollama_list("NAME ID SIZE MODIFIED\n"), None);
+        assert_eq!(parse_ollama_list("Error: could not connect\n"), None);
+    }
+
     #[test]
     fn known_codex_models_are_unique() {
         let models = normalize_models(
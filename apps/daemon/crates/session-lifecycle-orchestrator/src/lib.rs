@@ -1,13 +1,14 @@
 //! Session lifecycle orchestration for the Unbound daemon.
 //!
 //! Manages session creation (with optional worktree), deletion (with cleanup),
-//! and the session secret cache (memory → SQLite → keychain).
+//! the session secret cache (memory → SQLite → keychain), and recovery of
+//! sessions a crashed daemon left running.
 
 use agent_session_sqlite_persist_core::{
-    ArminError, NewSession, NewSessionSecret, Repository, RepositoryId, Session, SessionId,
-    SessionReader, SessionWriter,
+    ArminError, CodingSessionStatus, NewMessage, NewSession, NewSessionSecret, ProcessKind,
+    ProcessRecord, Repository, RepositoryId, Session, SessionId, SessionReader, SessionWriter,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
@@ -186,11 +187,204 @@ pub fn store_session_secret<A: SessionWriter>(
     Ok(())
 }
 
+/// Reason recorded on sessions whose agent was lost with the daemon.
+pub const INTERRUPTED_BY_RESTART: &str = "The daemon stopped while the agent was running";
+
+/// What to do with a recorded process that outlived its daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanAction {
+    Kill,
+    Adopt,
+}
+
+/// Operating system access used to reconcile orphaned processes.
+pub trait ProcessControl {
+    /// Whether the recorded process is still running. Implementations should
+    /// check it is still the recorded program, not an unrelated process that
+    /// reused the pid.
+    fn is_running(&self, record: &ProcessRecord) -> bool;
+
+    /// Stops the recorded process.
+    fn kill(&self, record: &ProcessRecord);
+}
+
+/// What startup reconciliation did for one session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionRecovery {
+    pub session_id: SessionId,
+    /// The runtime status the session was left in, when it claimed an agent
+    /// was running or about to run.
+    pub stale_status: Option<CodingSessionStatus>,
+    /// Surviving processes that were stopped.
+    pub killed: Vec<ProcessRecord>,
+    /// Surviving processes left running for the caller to supervise.
+    pub adopted: Vec<ProcessRecord>,
+    /// Recorded processes that had already exited.
+    pub exited: Vec<ProcessRecord>,
+}
+
+impl SessionRecovery {
+    fn new(session_id: SessionId) -> Self {
+        Self {
+            session_id,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the session's agent is still running under the new daemon.
+    pub fn agent_adopted(&self) -> bool {
+        self.adopted
+            .iter()
+            .any(|record| record.kind == ProcessKind::Agent)
+    }
+
+    /// Whether the session's agent was killed or had exited.
+    fn agent_gone(&self) -> bool {
+        self.killed
+            .iter()
+            .chain(&self.exited)
+            .any(|record| record.kind == ProcessKind::Agent)
+    }
+
+    fn message(&self) -> String {
+        let mut parts = vec!["The daemon restarted after stopping unexpectedly.".to_string()];
+        for (verb, records) in [
+            ("Stopped", &self.killed),
+            ("Kept running", &self.adopted),
+            ("Found exited", &self.exited),
+        ] {
+            if !records.is_empty() {
+                let processes: Vec<String> = records
+                    .iter()
+                    .map(|record| format!("{} (pid {})", record.kind.as_str(), record.pid))
+                    .collect();
+                parts.push(format!("{verb}: {}.", processes.join(", ")));
+            }
+        }
+        if self.stale_status.is_some() && !self.agent_adopted() {
+            parts.push("The agent's last turn did not finish.".to_string());
+        }
+        parts.join(" ")
+    }
+
+    /// The `daemon_recovery` note appended to the session transcript.
+    pub fn note_json(&self) -> String {
+        let processes = |records: &[ProcessRecord]| -> Vec<serde_json::Value> {
+            records
+                .iter()
+                .map(|record| {
+                    serde_json::json!({
+                        "kind": record.kind.as_str(),
+                        "pid": record.pid,
+                        "command": record.command,
+                        "started_at": record.started_at.to_rfc3339(),
+                    })
+                })
+                .collect()
+        };
+        serde_json::json!({
+            "type": "daemon_recovery",
+            "stale_status": self.stale_status.map(|status| status.as_str()),
+            "killed": processes(&self.killed),
+            "adopted": processes(&self.adopted),
+            "exited": processes(&self.exited),
+            "message": self.message(),
+        })
+        .to_string()
+    }
+}
+
+/// Reconciles sessions and processes left behind by a daemon that did not
+/// shut down cleanly. Call once at startup, before any agent is spawned.
+///
+/// Every recorded process is checked: surviving ones are killed or adopted
+/// per `action`, and the records of killed or exited ones are removed.
+/// Sessions still marked running, waiting for a scheduler slot, or waiting
+/// on an agent that is gone (such as a permission prompt) move to `Error`
+/// unless their agent was adopted. Only a session waiting on an unanswered
+/// question keeps its status, since the answer resumes it in a new process. Each affected session gets a
+/// `daemon_recovery` note in its transcript.
+pub fn reconcile_orphans<A: SessionWriter + SessionReader>(
+    armin: &A,
+    control: &impl ProcessControl,
+    action: OrphanAction,
+    device_id: &str,
+) -> Result<Vec<SessionRecovery>, SessionError> {
+    let mut recoveries: BTreeMap<String, SessionRecovery> = BTreeMap::new();
+
+    for record in armin.list_process_records()? {
+        let recovery = recoveries
+            .entry(record.session_id.as_str().to_string())
+            .or_insert_with(|| SessionRecovery::new(record.session_id.clone()));
+        if !control.is_running(&record) {
+            armin.remove_process_record(&record.id)?;
+            recovery.exited.push(record);
+        } else if action == OrphanAction::Kill {
+            control.kill(&record);
+            armin.remove_process_record(&record.id)?;
+            recovery.killed.push(record);
+        } else {
+            recovery.adopted.push(record);
+        }
+    }
+
+    let active = armin.sessions_with_runtime_status(&[
+        CodingSessionStatus::Running,
+        CodingSessionStatus::Waiting,
+    ])?;
+    for session_id in active {
+        let Some(state) = armin.get_session_state(&session_id)? else {
+            continue;
+        };
+        let coding_session = state.runtime_status.coding_session;
+        // Waiting on a question is a settled state; waiting for a slot, or on
+        // an agent that is gone, is not
+        let question_pending = coding_session
+            .question
+            .as_ref()
+            .is_some_and(|question| !question.resolved);
+        let agent_gone = recoveries
+            .get(session_id.as_str())
+            .is_some_and(SessionRecovery::agent_gone);
+        if coding_session.status == CodingSessionStatus::Waiting
+            && coding_session.scheduler_position.is_none()
+            && (question_pending || !agent_gone)
+        {
+            continue;
+        }
+        recoveries
+            .entry(session_id.as_str().to_string())
+            .or_insert_with(|| SessionRecovery::new(session_id.clone()))
+            .stale_status = Some(coding_session.status);
+    }
+
+    let recoveries: Vec<SessionRecovery> = recoveries.into_values().collect();
+    for recovery in &recoveries {
+        armin.append(
+            &recovery.session_id,
+            NewMessage {
+                content: recovery.note_json(),
+            },
+        )?;
+        if recovery.stale_status.is_some() && !recovery.agent_adopted() {
+            armin.update_runtime_scheduler_position(&recovery.session_id, device_id, None)?;
+            armin.update_runtime_status(
+                &recovery.session_id,
+                device_id,
+                CodingSessionStatus::Error,
+                Some(INTERRUPTED_BY_RESTART.to_string()),
+            )?;
+        }
+    }
+    Ok(recoveries)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use agent_session_sqlite_persist_core::{
-        Armin, NewRepository, NullSink, SessionReader, SessionWriter,
+        Armin, NewProcessRecord, NewRepository, NullSink, QuestionRuntimeState, SessionReader,
+        SessionWriter,
     };
 
     fn make_armin() -> Armin<NullSink> {
@@ -564,6 +758,172 @@ mod tests {
         assert!(secret.is_some());
     }
 
+    // =========================================================================
+    // Orphan reconciliation tests
+    // =========================================================================
+
+    /// Pretends the listed pids are running and records kills.
+    struct FakeProcesses {
+        running: Vec<u32>,
+        killed: std::cell::RefCell<Vec<u32>>,
+    }
+
+    impl FakeProcesses {
+        fn running(pids: &[u32]) -> Self {
+            Self {
+                running: pids.to_vec(),
+                killed: Default::default(),
+            }
+        }
+    }
+
+    impl ProcessControl for FakeProcesses {
+        fn is_running(&self, record: &ProcessRecord) -> bool {
+            self.running.contains(&record.pid)
+        }
+
+        fn kill(&self, record: &ProcessRecord) {
+            self.killed.borrow_mut().push(record.pid);
+        }
+    }
+
+    fn record(armin: &Armin<NullSink>, session: &SessionId, kind: ProcessKind, pid: u32) {
+        armin
+            .record_process(
+                session,
+                NewProcessRecord {
+                    kind,
+                    pid,
+                    command: "claude".to_string(),
+                },
+            )
+            .unwrap();
+    }
+
+    fn set_status(armin: &Armin<NullSink>, session: &SessionId, status: CodingSessionStatus) {
+        armin
+            .update_runtime_status(session, "device", status, None)
+            .unwrap();
+    }
+
+    fn status(armin: &Armin<NullSink>, session: &SessionId) -> CodingSessionStatus {
+        armin
+            .get_session_state(session)
+            .unwrap()
+            .unwrap()
+            .runtime_status
+            .coding_session
+            .status
+    }
+
+    #[test]
+    fn reconcile_kills_survivors_and_fails_stale_sessions() {
+        let armin = make_armin();
+        let crashed = armin.create_session().unwrap();
+        let finished = armin.create_session().unwrap();
+        let untouched = armin.create_session().unwrap();
+        record(&armin, &crashed, ProcessKind::Agent, 100);
+        record(&armin, &crashed, ProcessKind::Terminal, 101);
+        record(&armin, &finished, ProcessKind::Agent, 200);
+        set_status(&armin, &crashed, CodingSessionStatus::Running);
+        set_status(&armin, &finished, CodingSessionStatus::Running);
+        set_status(&armin, &untouched, CodingSessionStatus::Idle);
+        let processes = FakeProcesses::running(&[100, 101]);
+
+        let recoveries =
+            reconcile_orphans(&armin, &processes, OrphanAction::Kill, "device").unwrap();
+
+        assert_eq!(*processes.killed.borrow(), vec![100, 101]);
+        assert!(armin.list_process_records().unwrap().is_empty());
+        assert_eq!(recoveries.len(), 2);
+        let crashed_recovery = recoveries
+            .iter()
+            .find(|recovery| recovery.session_id == crashed)
+            .unwrap();
+        assert_eq!(crashed_recovery.killed.len(), 2);
+        assert_eq!(
+            crashed_recovery.stale_status,
+            Some(CodingSessionStatus::Running)
+        );
+        assert_eq!(status(&armin, &crashed), CodingSessionStatus::Error);
+        assert_eq!(status(&armin, &finished), CodingSessionStatus::Error);
+        assert_eq!(status(&armin, &untouched), CodingSessionStatus::Idle);
+
+        let note = armin.delta(&crashed).iter().last().unwrap().content.clone();
+        let note: serde_json::Value = serde_json::from_str(&note).unwrap();
+        assert_eq!(note["type"], "daemon_recovery");
+        assert_eq!(note["killed"][0]["pid"], 100);
+        assert!(armin.delta(&untouched).iter().next().is_none());
+    }
+
+    #[test]
+    fn reconcile_adopts_survivors_and_keeps_them_running() {
+        let armin = make_armin();
+        let session = armin.create_session().unwrap();
+        record(&armin, &session, ProcessKind::Agent, 100);
+        set_status(&armin, &session, CodingSessionStatus::Running);
+        let processes = FakeProcesses::running(&[100]);
+
+        let recoveries =
+            reconcile_orphans(&armin, &processes, OrphanAction::Adopt, "device").unwrap();
+
+        assert!(processes.killed.borrow().is_empty());
+        assert!(recoveries[0].agent_adopted());
+        assert_eq!(armin.list_process_records().unwrap().len(), 1);
+        assert_eq!(status(&armin, &session), CodingSessionStatus::Running);
+    }
+
+    #[test]
+    fn reconcile_leaves_sessions_waiting_on_a_question() {
+        let armin = make_armin();
+        let session = armin.create_session().unwrap();
+        set_status(&armin, &session, CodingSessionStatus::Waiting);
+
+        let recoveries = reconcile_orphans(
+            &armin,
+            &FakeProcesses::running(&[]),
+            OrphanAction::Kill,
+            "device",
+        )
+        .unwrap();
+
+        assert!(recoveries.is_empty());
+        assert_eq!(status(&armin, &session), CodingSessionStatus::Waiting);
+    }
+
+    #[test]
+    fn reconcile_fails_sessions_waiting_on_an_agent_that_is_gone() {
+        let armin = make_armin();
+        let prompt = armin.create_session().unwrap();
+        let question = armin.create_session().unwrap();
+        record(&armin, &prompt, ProcessKind::Agent, 100);
+        record(&armin, &question, ProcessKind::Agent, 200);
+        set_status(&armin, &prompt, CodingSessionStatus::Waiting);
+        set_status(&armin, &question, CodingSessionStatus::Waiting);
+        armin
+            .update_runtime_question(
+                &question,
+                "device",
+                Some(QuestionRuntimeState {
+                    tool_use_id: "toolu_1".to_string(),
+                    resolved: false,
+                }),
+            )
+            .unwrap();
+
+        let recoveries = reconcile_orphans(
+            &armin,
+            &FakeProcesses::running(&[100]),
+            OrphanAction::Kill,
+            "device",
+        )
+        .unwrap();
+
+        assert_eq!(recoveries.len(), 2);
+        assert_eq!(status(&armin, &prompt), CodingSessionStatus::Error);
+        assert_eq!(status(&armin, &question), CodingSessionStatus::Waiting);
+    }
+
     // =========================================================================
     // Error display tests
     // =========================================================================
@@ -166,6 +166,9 @@ mod tests {
                 status: SessionStatus::Active,
                 is_worktree: worktree_path.is_some(),
                 worktree_path: worktree_path.map(String::from),
+                parent_session_id: None,
+                forked_from_sequence: None,
+                permission_mode: None,
                 machine_id: None,
                 space_id: None,
                 created_at: now,
@@ -221,6 +224,61 @@ mod tests {
             unimplemented!("not needed for resolution tests")
         }
 
+        fn search_messages(
+            &self,
+            _query: &MessageSearchQuery,
+        ) -> Result<Vec<MessageSearchHit>, ArminError> {
+            unimplemented!("not needed for resolution tests")
+        }
+
+        fn list_queued_messages(
+            &self,
+            _session: &SessionId,
+        ) -> Result<Vec<QueuedMessage>, ArminError> {
+            Ok(Vec::new())
+        }
+
+        fn sessions_with_queued_messages(&self) -> Result<Vec<SessionId>, ArminError> {
+            Ok(Vec::new())
+        }
+
+        fn sessions_with_runtime_status(
+            &self,
+            _statuses: &[CodingSessionStatus],
+        ) -> Result<Vec<SessionId>, ArminError> {
+            Ok(Vec::new())
+        }
+
+        fn list_process_records(&self) -> Result<Vec<ProcessRecord>, ArminError> {
+            Ok(Vec::new())
+        }
+
+        fn get_terminal(&self, _id: &str) -> Result<Option<Terminal>, ArminError> {
+            Ok(None)
+        }
+
+        fn list_terminals(&self, _session: &SessionId) -> Result<Vec<Terminal>, ArminError> {
+            Ok(Vec::new())
+        }
+
+        fn list_unfinished_terminals(&self) -> Result<Vec<Terminal>, ArminError> {
+            Ok(Vec::new())
+        }
+
+        fn read_terminal_output(
+            &self,
+            _id: &str,
+            offset: u64,
+            _limit: usize,
+        ) -> Result<TerminalOutputPage, ArminError> {
+            Ok(TerminalOutputPage {
+                chunks: Vec::new(),
+                start_offset: 0,
+                end_offset: 0,
+                next_offset: offset,
+            })
+        }
+
         fn get_session_secret(
             &self,
             _session: &SessionId,
@@ -293,6 +351,9 @@ mod tests {
             status: SessionStatus::Active,
             is_worktree: false,
             worktree_path: None,
+            parent_session_id: None,
+            forked_from_sequence: None,
+            permission_mode: None,
             machine_id: None,
             space_id: None,
             created_at: now,
@@ -8,11 +8,15 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Mutex;
+use std::time::Duration;
 use tauri::async_runtime::JoinHandle;
 use tauri::{AppHandle, Emitter, State};
 use tracing::Instrument;
 use url::Url;
 
+/// Delay between attempts to resume a dropped session stream.
+const SUBSCRIPTION_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Default)]
 pub struct DesktopState {
     subscriptions: Mutex<HashMap<String, JoinHandle<()>>>,
@@ -652,11 +656,34 @@ pub async fn git_push(params: Value) -> Result<Value, String> {
     call_daemon(Method::GitPush, Some(params)).await
 }
 
+#[tauri::command]
+pub async fn git_checkout(params: Value) -> Result<Value, String> {
+    call_daemon(Method::GitCheckout, Some(params)).await
+}
+
+#[tauri::command]
+pub async fn git_branch_create(params: Value) -> Result<Value, String> {
+    call_daemon(Method::GitBranchCreate, Some(params)).await
+}
+
+#[tauri::command]
+pub async fn git_branch_delete(params: Value) -> Result<Value, String> {
+    call_daemon(Method::GitBranchDelete, Some(params)).await
+}
+
+#[tauri::command]
+pub async fn git_branch_rename(params: Value) -> Result<Value, String> {
+    call_daemon(Method::GitBranchRename, Some(params)).await
+}
+
 #[tauri::command]
 pub async fn terminal_run(
     session_id: String,
     command: String,
     working_dir: Option<String>,
+    name: Option<String>,
+    rows: Option<u16>,
+    cols: Option<u16>,
 ) -> Result<Value, String> {
     let mut params = json!({
         "session_id": session_id,
@@ -665,27 +692,89 @@ pub async fn terminal_run(
     if let Some(working_dir) = working_dir {
         params["working_dir"] = json!(working_dir);
     }
+    if let Some(name) = name {
+        params["name"] = json!(name);
+    }
+    if let (Some(rows), Some(cols)) = (rows, cols) {
+        params["rows"] = json!(rows);
+        params["cols"] = json!(cols);
+    }
     call_daemon(Method::TerminalRun, Some(params)).await
 }
 
 #[tauri::command]
-pub async fn terminal_status(session_id: String) -> Result<Value, String> {
+pub async fn terminal_status(
+    session_id: String,
+    terminal_id: Option<String>,
+) -> Result<Value, String> {
     call_daemon(
         Method::TerminalStatus,
-        Some(json!({ "session_id": session_id })),
+        Some(json!({ "session_id": session_id, "terminal_id": terminal_id })),
     )
     .await
 }
 
 #[tauri::command]
-pub async fn terminal_stop(session_id: String) -> Result<Value, String> {
+pub async fn terminal_stop(
+    session_id: String,
+    terminal_id: Option<String>,
+) -> Result<Value, String> {
     call_daemon(
         Method::TerminalStop,
+        Some(json!({ "session_id": session_id, "terminal_id": terminal_id })),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn terminal_list(session_id: String) -> Result<Value, String> {
+    call_daemon(
+        Method::TerminalList,
         Some(json!({ "session_id": session_id })),
     )
     .await
 }
 
+#[tauri::command]
+pub async fn terminal_read_output(
+    terminal_id: String,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Result<Value, String> {
+    call_daemon(
+        Method::TerminalReadOutput,
+        Some(json!({ "terminal_id": terminal_id, "offset": offset, "limit": limit })),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn terminal_write(terminal_id: String, data: String) -> Result<Value, String> {
+    call_daemon(
+        Method::TerminalWrite,
+        Some(json!({ "terminal_id": terminal_id, "data": data })),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn terminal_resize(terminal_id: String, rows: u16, cols: u16) -> Result<Value, String> {
+    call_daemon(
+        Method::TerminalResize,
+        Some(json!({ "terminal_id": terminal_id, "rows": rows, "cols": cols })),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn terminal_signal(terminal_id: String, signal: String) -> Result<Value, String> {
+    call_daemon(
+        Method::TerminalSignal,
+        Some(json!({ "terminal_id": terminal_id, "signal": signal })),
+    )
+    .await
+}
+
 #[tauri::command]
 pub async fn session_subscribe(
     app: AppHandle,
@@ -717,25 +806,8 @@ pub async fn session_subscribe(
             let handle = spawn_in_current_span(async move {
                 async move {
                     let client = IpcClient::new(&socket_path);
-                    match client.subscribe(&session_id_for_task).await {
-                        Ok(mut subscription) => {
-                            tracing::info!(
-                                session_id = %session_id_for_task,
-                                "desktop session subscription established"
-                            );
-                            while let Some(event) = subscription.recv().await {
-                                let payload = SessionStreamPayload {
-                                    session_id: session_id_for_task.clone(),
-                                    event: serde_json::to_value(&event)
-                                        .unwrap_or_else(|_| json!({})),
-                                };
-                                let _ = app.emit("daemon-session-event", payload);
-                            }
-                            tracing::info!(
-                                session_id = %session_id_for_task,
-                                "desktop session subscription closed"
-                            );
-                        }
+                    let mut subscription = match client.subscribe(&session_id_for_task).await {
+                        Ok(subscription) => subscription,
                         Err(error) => {
                             tracing::error!(
                                 error = %error,
@@ -749,7 +821,52 @@ pub async fn session_subscribe(
                                     "message": error.to_string(),
                                 }),
                             );
+                            return;
                         }
+                    };
+                    tracing::info!(
+                        session_id = %session_id_for_task,
+                        "desktop session subscription established"
+                    );
+
+                    // Highest message sequence forwarded, so a dropped stream
+                    // resumes with a replay of whatever it missed.
+                    let mut last_sequence: Option<i64> = None;
+                    loop {
+                        while let Some(event) = subscription.recv().await {
+                            if event.event_type.carries_message_sequence() {
+                                last_sequence = Some(
+                                    last_sequence
+                                        .map_or(event.sequence, |last| last.max(event.sequence)),
+                                );
+                            }
+                            let payload = SessionStreamPayload {
+                                session_id: session_id_for_task.clone(),
+                                event: serde_json::to_value(&event).unwrap_or_else(|_| json!({})),
+                            };
+                            let _ = app.emit("daemon-session-event", payload);
+                        }
+                        tracing::info!(
+                            session_id = %session_id_for_task,
+                            after_sequence = ?last_sequence,
+                            "desktop session subscription closed, resuming"
+                        );
+
+                        // Retry until the daemon is back; session_unsubscribe aborts this task
+                        subscription = loop {
+                            tokio::time::sleep(SUBSCRIPTION_RETRY_DELAY).await;
+                            match client
+                                .subscribe_after(&session_id_for_task, last_sequence)
+                                .await
+                            {
+                                Ok(subscription) => break subscription,
+                                Err(error) => tracing::debug!(
+                                    error = %error,
+                                    session_id = %session_id_for_task,
+                                    "desktop session resubscribe failed"
+                                ),
+                            }
+                        };
                     }
                 }
                 .instrument(stream_span)
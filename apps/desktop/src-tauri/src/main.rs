@@ -77,9 +77,18 @@ fn main() {
             commands::git_discard,
             commands::git_commit,
             commands::git_push,
+            commands::git_checkout,
+            commands::git_branch_create,
+            commands::git_branch_delete,
+            commands::git_branch_rename,
             commands::terminal_run,
             commands::terminal_status,
             commands::terminal_stop,
+            commands::terminal_list,
+            commands::terminal_read_output,
+            commands::terminal_write,
+            commands::terminal_resize,
+            commands::terminal_signal,
             commands::session_subscribe,
             commands::session_unsubscribe,
             commands::settings_get,
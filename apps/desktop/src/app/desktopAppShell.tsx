@@ -23783,7 +23783,15 @@ function buildTerminalTranscript(messages: SessionMessage[]) {
     }
 
     if (content.type === "terminal_finished") {
-      return [`[exit ${String(content.exit_code ?? "unknown")}]`];
+      const summary = [];
+      if (typeof content.command === "string") {
+        summary.push(`$ ${content.command}`);
+      }
+      if (typeof content.tail === "string" && content.tail) {
+        summary.push(content.tail);
+      }
+      summary.push(`[exit ${String(content.exit_code ?? "unknown")}]`);
+      return summary;
     }
 
     return [];
@@ -23837,7 +23845,9 @@ function renderMessageContent(value: unknown, showRaw: boolean) {
   }
 
   if (content.type === "terminal_finished") {
-    return `Terminal finished with exit code ${String(content.exit_code ?? "unknown")}`;
+    const command =
+      typeof content.command === "string" ? ` \`${content.command}\`` : "";
+    return `Terminal${command} finished with exit code ${String(content.exit_code ?? "unknown")}`;
   }
 
   if (content.type === "assistant" && content.message) {
@@ -189,21 +189,67 @@ export const terminalRun = (
   sessionId: string,
   command: string,
   workingDir?: string,
+  size?: { rows: number; cols: number },
+  name?: string,
 ) =>
   invokeCommand<Record<string, unknown>>("terminal_run", {
     sessionId,
     command,
     workingDir,
+    name,
+    rows: size?.rows,
+    cols: size?.cols,
   });
 
-export const terminalStatus = (sessionId: string) =>
+export const terminalStatus = (sessionId: string, terminalId?: string) =>
   invokeCommand<Record<string, unknown>>("terminal_status", {
     sessionId,
+    terminalId,
   });
 
-export const terminalStop = (sessionId: string) =>
+export const terminalStop = (sessionId: string, terminalId?: string) =>
   invokeCommand<Record<string, unknown>>("terminal_stop", {
     sessionId,
+    terminalId,
+  });
+
+export const terminalList = (sessionId: string) =>
+  invokeCommand<Record<string, unknown>>("terminal_list", {
+    sessionId,
+  });
+
+export const terminalReadOutput = (
+  terminalId: string,
+  offset?: number,
+  limit?: number,
+) =>
+  invokeCommand<Record<string, unknown>>("terminal_read_output", {
+    terminalId,
+    offset,
+    limit,
+  });
+
+export const terminalWrite = (terminalId: string, data: string) =>
+  invokeCommand<Record<string, unknown>>("terminal_write", {
+    terminalId,
+    data,
+  });
+
+export const terminalResize = (
+  terminalId: string,
+  rows: number,
+  cols: number,
+) =>
+  invokeCommand<Record<string, unknown>>("terminal_resize", {
+    terminalId,
+    rows,
+    cols,
+  });
+
+export const terminalSignal = (terminalId: string, signal: string) =>
+  invokeCommand<Record<string, unknown>>("terminal_signal", {
+    terminalId,
+    signal,
   });
 
 export const gitStatus = (sessionId?: string, repositoryId?: string) =>
@@ -274,6 +320,18 @@ export const gitCommit = (params: Record<string, unknown>) =>
 export const gitPush = (params: Record<string, unknown>) =>
   invokeCommand<Record<string, unknown>>("git_push", { params });
 
+export const gitCheckout = (params: Record<string, unknown>) =>
+  invokeCommand<Record<string, unknown>>("git_checkout", { params });
+
+export const gitBranchCreate = (params: Record<string, unknown>) =>
+  invokeCommand<Record<string, unknown>>("git_branch_create", { params });
+
+export const gitBranchDelete = (params: Record<string, unknown>) =>
+  invokeCommand<Record<string, unknown>>("git_branch_delete", { params });
+
+export const gitBranchRename = (params: Record<string, unknown>) =>
+  invokeCommand<Record<string, unknown>>("git_branch_rename", { params });
+
 export const settingsGet = () => invokeCommand<DesktopSettings>("settings_get");
 
 export const settingsUpdate = (settings: DesktopSettings) =>